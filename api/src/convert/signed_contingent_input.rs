@@ -0,0 +1,107 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! Convert to/from mc_transaction_extra::SignedContingentInput.
+
+use crate::{external, ConversionError};
+use mc_transaction_extra::SignedContingentInput;
+
+impl From<&SignedContingentInput> for external::SignedContingentInput {
+    fn from(src: &SignedContingentInput) -> Self {
+        let mut result = external::SignedContingentInput::new();
+        result.set_block_version(src.block_version);
+        result.set_tx_in((&src.tx_in).into());
+        result.set_mlsag((&src.mlsag).into());
+        result.set_pseudo_output_amount((&src.pseudo_output_amount).into());
+        result.set_required_output_amounts(
+            src.required_output_amounts.iter().map(Into::into).collect(),
+        );
+        result.set_tx_out_global_indices(src.tx_out_global_indices.clone());
+        result
+    }
+}
+
+impl TryFrom<&external::SignedContingentInput> for SignedContingentInput {
+    type Error = ConversionError;
+
+    fn try_from(src: &external::SignedContingentInput) -> Result<Self, Self::Error> {
+        Ok(SignedContingentInput {
+            block_version: src.get_block_version(),
+            tx_in: src.get_tx_in().try_into()?,
+            mlsag: src.get_mlsag().try_into()?,
+            pseudo_output_amount: src.get_pseudo_output_amount().try_into()?,
+            required_output_amounts: src
+                .get_required_output_amounts()
+                .iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_, _>>()?,
+            tx_out_global_indices: src.get_tx_out_global_indices().to_vec(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mc_account_keys::AccountKey;
+    use mc_crypto_ring_signature_signer::NoKeysRingSigner;
+    use mc_fog_report_validation_test_utils::MockFogResolver;
+    use mc_transaction_builder::{
+        test_utils::get_input_credentials, EmptyMemoBuilder, ReservedSubaddresses,
+        SignedContingentInputBuilder,
+    };
+    use mc_transaction_core::{tokens::Mob, Amount, BlockVersion, Token, TokenId};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    // Test converting between external::SignedContingentInput and
+    // mc_transaction_extra::SignedContingentInput
+    #[test]
+    fn test_signed_contingent_input_conversion() {
+        let mut rng: StdRng = SeedableRng::from_seed([1u8; 32]);
+
+        let block_version = BlockVersion::MAX;
+        let alice = AccountKey::random(&mut rng);
+        let fpr = MockFogResolver::default();
+
+        let input_credentials = get_input_credentials(
+            block_version,
+            Amount::new(1_000_000, Mob::ID),
+            &alice,
+            &fpr,
+            &mut rng,
+        );
+
+        let mut builder = SignedContingentInputBuilder::new(
+            block_version,
+            input_credentials,
+            fpr,
+            EmptyMemoBuilder::default(),
+        )
+        .unwrap();
+        builder
+            .add_partial_fill_output(
+                Amount::new(1_000, TokenId::from(2)),
+                &alice.default_subaddress(),
+                &mut rng,
+            )
+            .unwrap();
+        builder
+            .add_partial_fill_change_output(
+                Amount::new(1_000_000, Mob::ID),
+                &ReservedSubaddresses::from(&alice),
+                &mut rng,
+            )
+            .unwrap();
+        builder.set_min_partial_fill_value(1_000);
+
+        let sci = builder.build(&NoKeysRingSigner {}, &mut rng).unwrap();
+
+        // Converting mc_transaction_extra::SignedContingentInput ->
+        // external::SignedContingentInput ->
+        // mc_transaction_extra::SignedContingentInput should be the identity
+        // function.
+        let external_sci: external::SignedContingentInput = (&sci).into();
+        let recovered_sci: SignedContingentInput = (&external_sci).try_into().unwrap();
+        assert_eq!(sci, recovered_sci);
+        recovered_sci.validate().unwrap();
+    }
+}
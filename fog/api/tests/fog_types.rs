@@ -73,6 +73,7 @@ fn fog_view_query_request_aad_round_trip() {
         let test_val = mc_fog_types::view::QueryRequestAAD {
             start_from_user_event_id: rng.next_u64() as i64,
             start_from_block_index: rng.next_u64(),
+            max_user_events: rng.next_u64(),
         };
         round_trip_message::<mc_fog_types::view::QueryRequestAAD, mc_fog_api::view::QueryRequestAAD>(
             &test_val,
@@ -88,6 +89,7 @@ fn fog_view_query_request_aad_protobuf_round_trip() {
         let mut test_val = mc_fog_api::view::QueryRequestAAD::new();
         test_val.start_from_user_event_id = rng.next_u64() as i64;
         test_val.start_from_block_index = rng.next_u64();
+        test_val.max_user_events = rng.next_u64();
 
         round_trip_protobuf_object::<
             mc_fog_api::view::QueryRequestAAD,
@@ -99,6 +101,7 @@ fn fog_view_query_request_aad_protobuf_round_trip() {
         let mut test_val = mc_fog_api::view::QueryRequestAAD::new();
         test_val.start_from_user_event_id = rng.next_u64() as i64;
         test_val.start_from_block_index = rng.next_u64();
+        test_val.max_user_events = rng.next_u64();
         round_trip_protobuf_object::<
             mc_fog_api::view::QueryRequestAAD,
             mc_fog_types::view::QueryRequestAAD,
@@ -134,6 +137,7 @@ fn fog_view_query_response_round_trip() {
                 .collect(),
             last_known_block_count: rng.next_u32() as u64,
             last_known_block_cumulative_txo_count: rng.next_u32() as u64,
+            may_have_more_user_events: rng.next_u32() % 2 == 0,
         };
         round_trip_message::<mc_fog_types::view::QueryResponse, mc_fog_api::view::QueryResponse>(
             &test_val,
@@ -157,6 +161,7 @@ fn fog_view_query_response_round_trip() {
                 .collect(),
             last_known_block_count: rng.next_u32() as u64,
             last_known_block_cumulative_txo_count: rng.next_u32() as u64,
+            may_have_more_user_events: rng.next_u32() % 2 == 0,
         };
         round_trip_message::<mc_fog_types::view::QueryResponse, mc_fog_api::view::QueryResponse>(
             &test_val,
@@ -187,6 +192,7 @@ fn fog_view_query_response_round_trip() {
                 .collect(),
             last_known_block_count: rng.next_u32() as u64,
             last_known_block_cumulative_txo_count: rng.next_u32() as u64,
+            may_have_more_user_events: rng.next_u32() % 2 == 0,
         };
         round_trip_message::<mc_fog_types::view::QueryResponse, mc_fog_api::view::QueryResponse>(
             &test_val,
@@ -432,6 +438,7 @@ impl Sample for mc_fog_types::view::TxOutSearchResult {
         Self {
             search_key: <[u8; 32]>::sample(rng).to_vec(),
             ciphertext: <[u8; 32]>::sample(rng).to_vec(),
+            payload_length: rng.next_u32(),
             result_code: 1,
         }
     }
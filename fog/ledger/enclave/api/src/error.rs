@@ -40,6 +40,12 @@ pub enum Error {
 
     /// Prost decode error
     ProstDecode,
+
+    /// Peer session is not known to the enclave
+    UnknownPeer,
+
+    /// Shard query responses did not answer the same key images
+    ShardResponseMismatch,
 }
 
 /// An error when something goes wrong with adding a record
@@ -15,7 +15,11 @@ pub use crate::{
 };
 use alloc::vec::Vec;
 use core::result::Result as StdResult;
-use mc_attest_enclave_api::{ClientAuthRequest, ClientAuthResponse, ClientSession, EnclaveMessage};
+use mc_attest_core::VerificationReport;
+use mc_attest_enclave_api::{
+    ClientAuthRequest, ClientAuthResponse, ClientSession, EnclaveMessage, PeerAuthRequest,
+    PeerAuthResponse, PeerSession,
+};
 use mc_common::ResponderId;
 use mc_crypto_keys::X25519Public;
 pub use mc_fog_types::ledger::{
@@ -63,7 +67,12 @@ pub struct UntrustedKeyImageQueryResponse {
 pub trait LedgerEnclave: ReportableEnclave {
     // UTILITY METHODS
     /// Perform one-time initialization upon enclave startup.
-    fn enclave_init(&self, self_id: &ResponderId, desired_capacity: u64) -> Result<()>;
+    fn enclave_init(
+        &self,
+        self_peer_id: &ResponderId,
+        self_client_id: &ResponderId,
+        desired_capacity: u64,
+    ) -> Result<()>;
 
     /// Retrieve the public identity of the enclave.
     fn get_identity(&self) -> Result<X25519Public>;
@@ -98,6 +107,49 @@ pub trait LedgerEnclave: ReportableEnclave {
 
     /// Add a key image data to the oram Using thrm -rf targete key image
     fn add_key_image_data(&self, records: Vec<KeyImageData>) -> Result<()>;
+
+    // KEY IMAGE STORE / ROUTER METHODS
+
+    /// Initiate peering with a key image store enclave
+    fn peer_init(&self, peer_id: &ResponderId) -> Result<PeerAuthRequest>;
+
+    /// Accept a connection proposal from a key image router enclave
+    fn peer_accept(&self, req: PeerAuthRequest) -> Result<(PeerAuthResponse, PeerSession)>;
+
+    /// Handle the remote peer_accept response to form the connection
+    fn peer_connect(
+        &self,
+        peer_id: &ResponderId,
+        msg: PeerAuthResponse,
+    ) -> Result<(PeerSession, VerificationReport)>;
+
+    /// Close a connection with a peer
+    fn peer_close(&self, session_id: &PeerSession) -> Result<()>;
+
+    /// Service a CheckKeyImagesRequest forwarded by a key image router,
+    /// looking up its key images in this enclave's ORAM
+    fn check_key_image_store(
+        &self,
+        msg: EnclaveMessage<PeerSession>,
+    ) -> Result<EnclaveMessage<PeerSession>>;
+
+    /// Decrypt a client's CheckKeyImagesRequest and re-encrypt it for each of
+    /// the key image stores this router is connected to. The results are
+    /// returned in the same order as `store_sessions`.
+    fn create_multi_key_image_store_query(
+        &self,
+        msg: EnclaveMessage<ClientSession>,
+        store_sessions: Vec<PeerSession>,
+    ) -> Result<Vec<EnclaveMessage<PeerSession>>>;
+
+    /// Merge the key image store responses to a client's request, and encrypt
+    /// the resulting CheckKeyImagesResponse for the client
+    fn collate_shard_query_responses(
+        &self,
+        client_session: ClientSession,
+        shard_query_responses: Vec<EnclaveMessage<PeerSession>>,
+        untrusted_key_image_query_response: UntrustedKeyImageQueryResponse,
+    ) -> Result<Vec<u8>>;
 }
 
 /// Helper trait which reduces boiler-plate in untrusted side
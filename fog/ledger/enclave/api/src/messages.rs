@@ -4,7 +4,10 @@
 use crate::UntrustedKeyImageQueryResponse;
 use alloc::vec::Vec;
 use mc_attest_core::{Quote, Report, TargetInfo, VerificationReport};
-use mc_attest_enclave_api::{ClientAuthRequest, ClientSession, EnclaveMessage};
+use mc_attest_enclave_api::{
+    ClientAuthRequest, ClientSession, EnclaveMessage, PeerAuthRequest, PeerAuthResponse,
+    PeerSession,
+};
 use mc_common::ResponderId;
 use mc_fog_types::ledger::GetOutputsResponse;
 use mc_transaction_core::ring_signature::KeyImage;
@@ -37,7 +40,7 @@ pub struct KeyImageData {
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub enum EnclaveCall {
     /// The [LedgerEnclave::enclave_init()] method.
-    EnclaveInit(ResponderId, u64),
+    EnclaveInit(ResponderId, ResponderId, u64),
 
     /// The [LedgerEnclave::client_accept()] method.
     ///
@@ -101,4 +104,43 @@ pub enum EnclaveCall {
     ///
     ///  Add key image data to the ORAM.
     AddKeyImageData(Vec<KeyImageData>),
+
+    /// The [LedgerEnclave::peer_init()] method.
+    ///
+    /// Initiate peering with a key image store enclave.
+    PeerInit(ResponderId),
+
+    /// The [LedgerEnclave::peer_accept()] method.
+    ///
+    /// Accept a peering request from a key image router enclave.
+    PeerAccept(PeerAuthRequest),
+
+    /// The [LedgerEnclave::peer_connect()] method.
+    ///
+    /// Complete peering with a key image store enclave.
+    PeerConnect(ResponderId, PeerAuthResponse),
+
+    /// The [LedgerEnclave::peer_close()] method.
+    ///
+    /// Tears down any in-enclave state about a peer association.
+    PeerClose(PeerSession),
+
+    /// The [LedgerEnclave::check_key_image_store()] method.
+    ///
+    /// Check the key images of a request forwarded by a key image router.
+    CheckKeyImageStore(EnclaveMessage<PeerSession>),
+
+    /// The [LedgerEnclave::create_multi_key_image_store_query()] method.
+    ///
+    /// Re-encrypt a client's key image check for each key image store.
+    CreateMultiKeyImageStoreQuery(EnclaveMessage<ClientSession>, Vec<PeerSession>),
+
+    /// The [LedgerEnclave::collate_shard_query_responses()] method.
+    ///
+    /// Merge the key image stores' responses for a client.
+    CollateShardQueryResponses(
+        ClientSession,
+        Vec<EnclaveMessage<PeerSession>>,
+        UntrustedKeyImageQueryResponse,
+    ),
 }
@@ -13,10 +13,14 @@
 extern crate alloc;
 
 mod key_image_store;
+mod shard_responses;
 use alloc::vec::Vec;
 use key_image_store::{KeyImageStore, StorageDataSize, StorageMetaSize};
 use mc_attest_core::{IasNonce, Quote, QuoteNonce, Report, TargetInfo, VerificationReport};
-use mc_attest_enclave_api::{ClientAuthRequest, ClientAuthResponse, ClientSession, EnclaveMessage};
+use mc_attest_enclave_api::{
+    ClientAuthRequest, ClientAuthResponse, ClientSession, EnclaveMessage, PeerAuthRequest,
+    PeerAuthResponse, PeerSession,
+};
 use mc_common::{
     logger::{log, Logger},
     ResponderId,
@@ -28,10 +32,12 @@ use mc_fog_ledger_enclave_api::{
 };
 use mc_fog_types::ledger::{
     CheckKeyImagesRequest, CheckKeyImagesResponse, GetOutputsRequest, GetOutputsResponse,
+    KeyImageResult,
 };
 use mc_oblivious_traits::ORAMStorageCreator;
 use mc_sgx_compat::sync::Mutex;
 use mc_sgx_report_cache_api::{ReportableEnclave, Result as ReportableEnclaveResult};
+use shard_responses::collate_shard_key_image_results;
 
 /// In-enclave state associated to the ledger enclaves
 pub struct SgxLedgerEnclave<OSC>
@@ -61,6 +67,40 @@ where
             logger,
         }
     }
+
+    /// Decode a CheckKeyImagesRequest, logging if it is malformed
+    fn decode_check_key_images_request(&self, plaintext: &[u8]) -> Result<CheckKeyImagesRequest> {
+        mc_util_serial::decode(plaintext).map_err(|e| {
+            log::error!(self.logger, "Could not decode user request: {}", e);
+            Error::ProstDecode
+        })
+    }
+
+    /// Look up the key images of a CheckKeyImagesRequest in the ORAM
+    fn find_records(&self, req: &CheckKeyImagesRequest) -> Result<Vec<KeyImageResult>> {
+        let mut lk = self.key_image_store.lock()?;
+        let store = lk.as_mut().ok_or(Error::EnclaveNotInitialized)?;
+
+        Ok(req
+            .queries
+            .iter() //  get the key images used to find the key image data using the oram
+            .map(|key| store.find_record(&key.key_image))
+            .collect())
+    }
+
+    /// Build the parts of a CheckKeyImagesResponse which come from untrusted
+    fn untrusted_key_image_query_response_to_check_key_images_response(
+        untrusted_key_image_query_response: UntrustedKeyImageQueryResponse,
+    ) -> CheckKeyImagesResponse {
+        CheckKeyImagesResponse {
+            num_blocks: untrusted_key_image_query_response.highest_processed_block_count,
+            results: Default::default(),
+            global_txo_count: untrusted_key_image_query_response
+                .last_known_block_cumulative_txo_count,
+            latest_block_version: untrusted_key_image_query_response.latest_block_version,
+            max_block_version: untrusted_key_image_query_response.max_block_version,
+        }
+    }
 }
 
 /// Implementation of the reportable enclave for sgxledger enclave
@@ -91,8 +131,14 @@ impl<OSC> LedgerEnclave for SgxLedgerEnclave<OSC>
 where
     OSC: ORAMStorageCreator<StorageDataSize, StorageMetaSize>,
 {
-    fn enclave_init(&self, self_id: &ResponderId, desired_capacity: u64) -> Result<()> {
-        self.ake.init(Default::default(), self_id.clone())?;
+    fn enclave_init(
+        &self,
+        self_peer_id: &ResponderId,
+        self_client_id: &ResponderId,
+        desired_capacity: u64,
+    ) -> Result<()> {
+        self.ake
+            .init(self_peer_id.clone(), self_client_id.clone())?;
         let mut lk = self.key_image_store.lock()?;
 
         *lk = Some(KeyImageStore::new(desired_capacity, self.logger.clone()));
@@ -145,31 +191,12 @@ where
         let channel_id = msg.channel_id.clone(); //client session does not implement copy trait so clone
         let user_plaintext = self.ake.client_decrypt(msg)?;
 
-        let req: CheckKeyImagesRequest = mc_util_serial::decode(&user_plaintext).map_err(|e| {
-            log::error!(self.logger, "Could not decode user request: {}", e);
-            Error::ProstDecode
-        })?;
-
-        let mut resp = CheckKeyImagesResponse {
-            num_blocks: untrusted_key_image_query_response.highest_processed_block_count,
-            results: Default::default(),
-            global_txo_count: untrusted_key_image_query_response
-                .last_known_block_cumulative_txo_count,
-            latest_block_version: untrusted_key_image_query_response.latest_block_version,
-            max_block_version: untrusted_key_image_query_response.max_block_version,
-        };
-
-        // Do the scope lock of keyimagetore
-        {
-            let mut lk = self.key_image_store.lock()?;
-            let store = lk.as_mut().ok_or(Error::EnclaveNotInitialized)?;
+        let req = self.decode_check_key_images_request(&user_plaintext)?;
 
-            resp.results = req
-                .queries
-                .iter() //  get the key images used to find the key image data using the oram
-                .map(|key| store.find_record(&key.key_image))
-                .collect();
-        }
+        let mut resp = Self::untrusted_key_image_query_response_to_check_key_images_response(
+            untrusted_key_image_query_response,
+        );
+        resp.results = self.find_records(&req)?;
 
         let response_plaintext_bytes = mc_util_serial::encode(&resp);
 
@@ -191,6 +218,106 @@ where
 
         Ok(())
     }
+
+    // Key image store / router specific
+
+    fn peer_init(&self, peer_id: &ResponderId) -> Result<PeerAuthRequest> {
+        Ok(self.ake.peer_init(peer_id)?)
+    }
+
+    fn peer_accept(&self, req: PeerAuthRequest) -> Result<(PeerAuthResponse, PeerSession)> {
+        Ok(self.ake.peer_accept(req)?)
+    }
+
+    fn peer_connect(
+        &self,
+        peer_id: &ResponderId,
+        msg: PeerAuthResponse,
+    ) -> Result<(PeerSession, VerificationReport)> {
+        Ok(self.ake.peer_connect(peer_id, msg)?)
+    }
+
+    fn peer_close(&self, session_id: &PeerSession) -> Result<()> {
+        Ok(self.ake.peer_close(session_id)?)
+    }
+
+    fn check_key_image_store(
+        &self,
+        msg: EnclaveMessage<PeerSession>,
+    ) -> Result<EnclaveMessage<PeerSession>> {
+        let channel_id = msg.channel_id.clone();
+        let router_plaintext = self.ake.peer_decrypt(msg)?;
+        let req = self.decode_check_key_images_request(&router_plaintext)?;
+
+        // Only the results are served by stores, the router fills in
+        // everything else.
+        let resp = CheckKeyImagesResponse {
+            results: self.find_records(&req)?,
+            ..Default::default()
+        };
+
+        let response_plaintext_bytes = mc_util_serial::encode(&resp);
+
+        Ok(self
+            .ake
+            .peer_encrypt(&channel_id, &[], &response_plaintext_bytes)?)
+    }
+
+    fn create_multi_key_image_store_query(
+        &self,
+        msg: EnclaveMessage<ClientSession>,
+        store_sessions: Vec<PeerSession>,
+    ) -> Result<Vec<EnclaveMessage<PeerSession>>> {
+        let user_plaintext = self.ake.client_decrypt(msg)?;
+        // Reject malformed requests here rather than in every store
+        self.decode_check_key_images_request(&user_plaintext)?;
+
+        // Check every session before encrypting for any of them, so that a bad
+        // session doesn't leave the others with skipped nonces
+        for session in store_sessions.iter() {
+            if !self.ake.is_peer_known(session)? {
+                return Err(Error::UnknownPeer);
+            }
+        }
+
+        store_sessions
+            .iter()
+            .map(|session| Ok(self.ake.peer_encrypt(session, &[], &user_plaintext)?))
+            .collect()
+    }
+
+    fn collate_shard_query_responses(
+        &self,
+        client_session: ClientSession,
+        shard_query_responses: Vec<EnclaveMessage<PeerSession>>,
+        untrusted_key_image_query_response: UntrustedKeyImageQueryResponse,
+    ) -> Result<Vec<u8>> {
+        let shard_results = shard_query_responses
+            .into_iter()
+            .map(|msg| {
+                let plaintext = self.ake.peer_decrypt(msg)?;
+                let shard_response: CheckKeyImagesResponse = mc_util_serial::decode(&plaintext)
+                    .map_err(|e| {
+                        log::error!(self.logger, "Could not decode store response: {}", e);
+                        Error::ProstDecode
+                    })?;
+                Ok(shard_response.results)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut resp = Self::untrusted_key_image_query_response_to_check_key_images_response(
+            untrusted_key_image_query_response,
+        );
+        resp.results = collate_shard_key_image_results(shard_results)?;
+
+        let response_plaintext_bytes = mc_util_serial::encode(&resp);
+
+        let response = self
+            .ake
+            .client_encrypt(&client_session, &[], &response_plaintext_bytes)?;
+
+        Ok(response.data)
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,122 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! Merging of the KeyImageResults returned by the key image stores a key image
+//! router fans a request out to.
+//!
+//! Each store answers every key image in the request, but a key image is only
+//! spent in one block, so at most one store finds it. The merge must not reveal
+//! (via branches or memory access patterns) which store, if any, found a key
+//! image, so every result is combined with constant-time conditional
+//! assignments.
+
+use aligned_cmov::CMov;
+use alloc::vec::Vec;
+use mc_fog_ledger_enclave_api::{Error, Result};
+use mc_fog_shard_responses::{collate_shard_results, take_other_result, ShardResponseMismatch};
+use mc_fog_types::ledger::{KeyImageResult, KeyImageResultCode};
+
+/// Merge the results of several key image store responses.
+///
+/// Every shard must answer the same key images in the same order. For each key
+/// image, a Spent result from any shard wins. Otherwise a KeyImageError from
+/// any shard wins over NotSpent.
+pub fn collate_shard_key_image_results(
+    shard_results: Vec<Vec<KeyImageResult>>,
+) -> Result<Vec<KeyImageResult>> {
+    collate_shard_results(shard_results, |result| &result.key_image, merge_result)
+        .map_err(|ShardResponseMismatch| Error::ShardResponseMismatch)
+}
+
+/// Merge `other` into `acc` in constant time.
+fn merge_result(acc: &mut KeyImageResult, other: &KeyImageResult) {
+    let take_other = take_other_result(
+        acc.key_image_result_code,
+        other.key_image_result_code,
+        KeyImageResultCode::Spent as u32,
+        KeyImageResultCode::NotSpent as u32,
+    );
+
+    acc.key_image_result_code
+        .cmov(take_other, &other.key_image_result_code);
+    acc.spent_at.cmov(take_other, &other.spent_at);
+    acc.timestamp.cmov(take_other, &other.timestamp);
+    acc.timestamp_result_code
+        .cmov(take_other, &other.timestamp_result_code);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use mc_transaction_core::ring_signature::KeyImage;
+    use mc_watcher_api::TimestampResultCode;
+
+    fn result(key_image: u64, code: KeyImageResultCode, spent_at: u64) -> KeyImageResult {
+        KeyImageResult {
+            key_image: KeyImage::from(key_image),
+            spent_at,
+            timestamp: spent_at,
+            timestamp_result_code: TimestampResultCode::TimestampFound as u32,
+            key_image_result_code: code as u32,
+        }
+    }
+
+    #[test]
+    fn spent_result_wins() {
+        let merged = collate_shard_key_image_results(vec![
+            vec![
+                result(1, KeyImageResultCode::NotSpent, u64::MAX),
+                result(2, KeyImageResultCode::Spent, 3),
+                result(3, KeyImageResultCode::NotSpent, u64::MAX),
+            ],
+            vec![
+                result(1, KeyImageResultCode::Spent, 12),
+                result(2, KeyImageResultCode::NotSpent, u64::MAX),
+                result(3, KeyImageResultCode::NotSpent, u64::MAX),
+            ],
+        ])
+        .unwrap();
+
+        assert_eq!(
+            merged,
+            vec![
+                result(1, KeyImageResultCode::Spent, 12),
+                result(2, KeyImageResultCode::Spent, 3),
+                result(3, KeyImageResultCode::NotSpent, u64::MAX),
+            ]
+        );
+    }
+
+    #[test]
+    fn errors_win_over_not_spent() {
+        let merged = collate_shard_key_image_results(vec![
+            vec![result(1, KeyImageResultCode::NotSpent, u64::MAX)],
+            vec![result(1, KeyImageResultCode::KeyImageError, u64::MAX)],
+            vec![result(1, KeyImageResultCode::NotSpent, u64::MAX)],
+        ])
+        .unwrap();
+
+        assert_eq!(
+            merged,
+            vec![result(1, KeyImageResultCode::KeyImageError, u64::MAX)]
+        );
+    }
+
+    #[test]
+    fn mismatched_shards_are_rejected() {
+        assert!(matches!(
+            collate_shard_key_image_results(vec![
+                vec![result(1, KeyImageResultCode::NotSpent, u64::MAX)],
+                vec![result(2, KeyImageResultCode::NotSpent, u64::MAX)],
+            ]),
+            Err(Error::ShardResponseMismatch)
+        ));
+        assert!(matches!(
+            collate_shard_key_image_results(vec![
+                vec![result(1, KeyImageResultCode::NotSpent, u64::MAX)],
+                vec![],
+            ]),
+            Err(Error::ShardResponseMismatch)
+        ));
+    }
+}
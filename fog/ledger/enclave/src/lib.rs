@@ -14,7 +14,10 @@ pub use mc_fog_ledger_enclave_api::{
 use mc_attest_core::{
     IasNonce, Quote, QuoteNonce, Report, SgxError, TargetInfo, VerificationReport,
 };
-use mc_attest_enclave_api::{ClientAuthRequest, ClientAuthResponse, ClientSession, EnclaveMessage};
+use mc_attest_enclave_api::{
+    ClientAuthRequest, ClientAuthResponse, ClientSession, EnclaveMessage, PeerAuthRequest,
+    PeerAuthResponse, PeerSession,
+};
 use mc_attest_verifier::DEBUG_ENCLAVE;
 use mc_common::{logger::Logger, ResponderId};
 use mc_crypto_keys::X25519Public;
@@ -69,14 +72,18 @@ impl LedgerSgxEnclave {
     ///
     /// Arguments:
     /// * enclave_path: The path to the signed enclave .so file
-    /// * self_id: The responder_id to be used when client is connecting to us
+    /// * client_responder_id: The responder_id to be used when client is
+    ///   connecting to us
+    /// * peer_responder_id: The responder_id to be used when a key image router
+    ///   connects to us as a key image store
     /// * desired_capacity: The desired capacity in the oblivious map. Must be a
     ///   power of two. Actual capacity will be ~70% of this. Memory utilization
     ///   will be about 256 bytes * this + some overhead
     /// * logger: Logger to use
     pub fn new(
         enclave_path: path::PathBuf,
-        self_id: &ResponderId,
+        client_responder_id: &ResponderId,
+        peer_responder_id: &ResponderId,
         desired_capacity: u64,
         _logger: Logger,
     ) -> LedgerSgxEnclave {
@@ -106,8 +113,8 @@ impl LedgerSgxEnclave {
         };
 
         sgx_enclave
-            .enclave_init(self_id, desired_capacity)
-            .unwrap_or_else(|e| panic!("enclave_init({}) failed: {:?}", self_id, e));
+            .enclave_init(peer_responder_id, client_responder_id, desired_capacity)
+            .unwrap_or_else(|e| panic!("enclave_init({}) failed: {:?}", client_responder_id, e));
 
         sgx_enclave
     }
@@ -125,9 +132,15 @@ impl LedgerSgxEnclave {
 /// Proxy API for talking to the corresponding implementation inside the
 /// enclave.
 impl LedgerEnclave for LedgerSgxEnclave {
-    fn enclave_init(&self, self_id: &ResponderId, desired_capacity: u64) -> Result<()> {
+    fn enclave_init(
+        &self,
+        self_peer_id: &ResponderId,
+        self_client_id: &ResponderId,
+        desired_capacity: u64,
+    ) -> Result<()> {
         let inbuf = mc_util_serial::serialize(&EnclaveCall::EnclaveInit(
-            self_id.clone(),
+            self_peer_id.clone(),
+            self_client_id.clone(),
             desired_capacity,
         ))?;
         let outbuf = self.enclave_call(&inbuf)?;
@@ -187,6 +200,71 @@ impl LedgerEnclave for LedgerSgxEnclave {
         let outbuf = self.enclave_call(&inbuf)?;
         mc_util_serial::deserialize(&outbuf[..])?
     }
+
+    fn peer_init(&self, peer_id: &ResponderId) -> Result<PeerAuthRequest> {
+        let inbuf = mc_util_serial::serialize(&EnclaveCall::PeerInit(peer_id.clone()))?;
+        let outbuf = self.enclave_call(&inbuf)?;
+        mc_util_serial::deserialize(&outbuf[..])?
+    }
+
+    fn peer_accept(&self, req: PeerAuthRequest) -> Result<(PeerAuthResponse, PeerSession)> {
+        let inbuf = mc_util_serial::serialize(&EnclaveCall::PeerAccept(req))?;
+        let outbuf = self.enclave_call(&inbuf)?;
+        mc_util_serial::deserialize(&outbuf[..])?
+    }
+
+    fn peer_connect(
+        &self,
+        peer_id: &ResponderId,
+        msg: PeerAuthResponse,
+    ) -> Result<(PeerSession, VerificationReport)> {
+        let inbuf = mc_util_serial::serialize(&EnclaveCall::PeerConnect(peer_id.clone(), msg))?;
+        let outbuf = self.enclave_call(&inbuf)?;
+        mc_util_serial::deserialize(&outbuf[..])?
+    }
+
+    fn peer_close(&self, session_id: &PeerSession) -> Result<()> {
+        let inbuf = mc_util_serial::serialize(&EnclaveCall::PeerClose(session_id.clone()))?;
+        let outbuf = self.enclave_call(&inbuf)?;
+        mc_util_serial::deserialize(&outbuf[..])?
+    }
+
+    fn check_key_image_store(
+        &self,
+        msg: EnclaveMessage<PeerSession>,
+    ) -> Result<EnclaveMessage<PeerSession>> {
+        let inbuf = mc_util_serial::serialize(&EnclaveCall::CheckKeyImageStore(msg))?;
+        let outbuf = self.enclave_call(&inbuf)?;
+        mc_util_serial::deserialize(&outbuf[..])?
+    }
+
+    fn create_multi_key_image_store_query(
+        &self,
+        msg: EnclaveMessage<ClientSession>,
+        store_sessions: Vec<PeerSession>,
+    ) -> Result<Vec<EnclaveMessage<PeerSession>>> {
+        let inbuf = mc_util_serial::serialize(&EnclaveCall::CreateMultiKeyImageStoreQuery(
+            msg,
+            store_sessions,
+        ))?;
+        let outbuf = self.enclave_call(&inbuf)?;
+        mc_util_serial::deserialize(&outbuf[..])?
+    }
+
+    fn collate_shard_query_responses(
+        &self,
+        client_session: ClientSession,
+        shard_query_responses: Vec<EnclaveMessage<PeerSession>>,
+        untrusted_key_image_query_response: UntrustedKeyImageQueryResponse,
+    ) -> Result<Vec<u8>> {
+        let inbuf = mc_util_serial::serialize(&EnclaveCall::CollateShardQueryResponses(
+            client_session,
+            shard_query_responses,
+            untrusted_key_image_query_response,
+        ))?;
+        let outbuf = self.enclave_call(&inbuf)?;
+        mc_util_serial::deserialize(&outbuf[..])?
+    }
 }
 
 extern "C" {
@@ -36,8 +36,8 @@ pub fn ecall_dispatcher(inbuf: &[u8]) -> Result<Vec<u8>, sgx_status_t> {
     // And actually do it
     match call_details {
         // Utility methods
-        EnclaveCall::EnclaveInit(self_id, desired_capacity) => {
-            serialize(&ENCLAVE.enclave_init(&self_id, desired_capacity))
+        EnclaveCall::EnclaveInit(self_peer_id, self_client_id, desired_capacity) => {
+            serialize(&ENCLAVE.enclave_init(&self_peer_id, &self_client_id, desired_capacity))
         }
         // Node-to-Client Attestation
         EnclaveCall::ClientAccept(auth_msg) => serialize(&ENCLAVE.client_accept(auth_msg)),
@@ -61,6 +61,24 @@ pub fn ecall_dispatcher(inbuf: &[u8]) -> Result<Vec<u8>, sgx_status_t> {
         }
         // Add Key Image Data
         EnclaveCall::AddKeyImageData(records) => serialize(&ENCLAVE.add_key_image_data(records)),
+        // Key image store / router
+        EnclaveCall::PeerInit(peer_id) => serialize(&ENCLAVE.peer_init(&peer_id)),
+        EnclaveCall::PeerAccept(req) => serialize(&ENCLAVE.peer_accept(req)),
+        EnclaveCall::PeerConnect(peer_id, msg) => serialize(&ENCLAVE.peer_connect(&peer_id, msg)),
+        EnclaveCall::PeerClose(session) => serialize(&ENCLAVE.peer_close(&session)),
+        EnclaveCall::CheckKeyImageStore(msg) => serialize(&ENCLAVE.check_key_image_store(msg)),
+        EnclaveCall::CreateMultiKeyImageStoreQuery(msg, store_sessions) => {
+            serialize(&ENCLAVE.create_multi_key_image_store_query(msg, store_sessions))
+        }
+        EnclaveCall::CollateShardQueryResponses(
+            client_session,
+            shard_query_responses,
+            untrusted_key_image_query_response,
+        ) => serialize(&ENCLAVE.collate_shard_query_responses(
+            client_session,
+            shard_query_responses,
+            untrusted_key_image_query_response,
+        )),
     }
     .or(Err(sgx_status_t::SGX_ERROR_UNEXPECTED))
 }
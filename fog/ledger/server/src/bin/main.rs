@@ -14,6 +14,7 @@ use mc_fog_ledger_server::{LedgerServer, LedgerServerConfig};
 use mc_ledger_db::LedgerDB;
 use mc_util_cli::ParserWithBuildInfo;
 use mc_util_grpc::AdminServer;
+use mc_util_uri::ConnectionUri;
 use mc_watcher::watcher_db::WatcherDB;
 use std::{env, sync::Arc};
 
@@ -42,9 +43,16 @@ fn main() {
         enclave_path.to_str().expect("Could not get enclave path"),
         &config.client_responder_id
     );
+    // Key image routers address this server as a store by the responder id of
+    // its client listen URI.
+    let peer_responder_id = config
+        .client_listen_uri
+        .responder_id()
+        .expect("Could not get responder id from client listen uri");
     let enclave = LedgerSgxEnclave::new(
         enclave_path,
         &config.client_responder_id,
+        &peer_responder_id,
         config.omap_capacity,
         logger.clone(),
     );
@@ -4,6 +4,7 @@
 
 #![deny(missing_docs)]
 
+use crate::sharding::EpochShardingStrategy;
 use clap::Parser;
 use mc_attest_core::ProviderId;
 use mc_common::ResponderId;
@@ -75,4 +76,12 @@ pub struct LedgerServerConfig {
     /// to disk by linux kernel.
     #[clap(long, default_value = "1048576", env = "MC_OMAP_CAPACITY")]
     pub omap_capacity: u64,
+
+    /// Which blocks to load the key images of into the ORAM, when this server
+    /// is one of several key image stores behind a key image router.
+    ///
+    /// "blocks:START-END" loads the key images of a range of blocks, END may be
+    /// omitted for an unbounded range. Defaults to loading every block.
+    #[clap(long, default_value = "blocks:0-", env = "MC_SHARDING_STRATEGY")]
+    pub sharding_strategy: EpochShardingStrategy,
 }
@@ -3,7 +3,10 @@
 //! A background thread, in the server side, that continuously checks the
 //! LedgerDB for new blocks, then gets all the key images associated to those
 //! blocks and adds them to the enclave.
-use crate::{counters, server::DbPollSharedState};
+//!
+//! Only the blocks of the server's shard are loaded, see
+//! [EpochShardingStrategy].
+use crate::{counters, server::DbPollSharedState, sharding::EpochShardingStrategy};
 use mc_common::{
     logger::{log, Logger},
     trace_time,
@@ -44,6 +47,7 @@ impl DbFetcher {
         enclave: E,
         watcher: WatcherDB,
         db_poll_shared_state: Arc<Mutex<DbPollSharedState>>,
+        sharding_strategy: EpochShardingStrategy,
         readiness_indicator: ReadinessIndicator,
         logger: Logger,
     ) -> Self {
@@ -57,7 +61,7 @@ impl DbFetcher {
                     DbFetcherThread::start(
                         db,
                         thread_stop_requested,
-                        0,
+                        sharding_strategy,
                         enclave,
                         watcher,
                         thread_shared_state,
@@ -95,6 +99,7 @@ struct DbFetcherThread<DB: Ledger, E: LedgerEnclaveProxy + Clone + Send + Sync +
     db: DB,
     stop_requested: Arc<AtomicBool>,
     next_block_index: u64,
+    sharding_strategy: EpochShardingStrategy,
     enclave: E,
     watcher: WatcherDB,
     db_poll_shared_state: Arc<Mutex<DbPollSharedState>>,
@@ -111,7 +116,7 @@ impl<DB: Ledger, E: LedgerEnclaveProxy + Clone + Send + Sync + 'static> DbFetche
     pub fn start(
         db: DB,
         stop_requested: Arc<AtomicBool>,
-        next_block_index: u64,
+        sharding_strategy: EpochShardingStrategy,
         enclave: E,
         watcher: WatcherDB,
         db_poll_shared_state: Arc<Mutex<DbPollSharedState>>,
@@ -121,7 +126,8 @@ impl<DB: Ledger, E: LedgerEnclaveProxy + Clone + Send + Sync + 'static> DbFetche
         let thread = Self {
             db,
             stop_requested,
-            next_block_index,
+            next_block_index: sharding_strategy.epoch_block_range.start_block,
+            sharding_strategy,
             enclave,
             watcher,
             db_poll_shared_state,
@@ -132,8 +138,11 @@ impl<DB: Ledger, E: LedgerEnclaveProxy + Clone + Send + Sync + 'static> DbFetche
     }
 
     fn run(mut self) {
-        log::info!(self.logger, "Db fetcher thread started.");
-        self.next_block_index = 0;
+        log::info!(
+            self.logger,
+            "Db fetcher thread started, loading {}.",
+            self.sharding_strategy
+        );
         loop {
             if self.stop_requested.load(Ordering::SeqCst) {
                 log::info!(self.logger, "Db fetcher thread stop requested.");
@@ -148,6 +157,9 @@ impl<DB: Ledger, E: LedgerEnclaveProxy + Clone + Send + Sync + 'static> DbFetche
                 // Hack: If we notice that we are way behind the ledger, set ourselves unready
                 match self.db.num_blocks() {
                     Ok(num_blocks) => {
+                        // Blocks past the end of our shard are not ours to load
+                        let num_blocks =
+                            num_blocks.min(self.sharding_strategy.epoch_block_range.end_block);
                         if num_blocks > self.next_block_index + 100 {
                             self.readiness_indicator.set_unready();
                         }
@@ -180,6 +192,14 @@ impl<DB: Ledger, E: LedgerEnclaveProxy + Clone + Send + Sync + 'static> DbFetche
         let mut may_have_more_work = true;
         let watcher_timeout: Duration = Duration::from_millis(5000);
 
+        // Once the whole shard is loaded there is nothing left to do
+        if !self
+            .sharding_strategy
+            .should_process_block(self.next_block_index)
+        {
+            return false;
+        }
+
         let start_time = SystemTime::now();
 
         match self.db.get_block_contents(self.next_block_index) {
@@ -0,0 +1,37 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+use displaydoc::Display;
+use grpcio::Error as GrpcError;
+use mc_connection::AttestationError;
+use mc_fog_ledger_enclave::Error as LedgerEnclaveError;
+
+/// An error which can occur when attesting to a key image store
+#[derive(Debug, Display)]
+pub enum KeyImageStoreAttestationError {
+    /// gRPC failure during attestation: {0}
+    Grpc(GrpcError),
+    /// Local enclave failure during attestation: {0}
+    Enclave(LedgerEnclaveError),
+}
+
+impl From<GrpcError> for KeyImageStoreAttestationError {
+    fn from(src: GrpcError) -> Self {
+        Self::Grpc(src)
+    }
+}
+
+impl From<LedgerEnclaveError> for KeyImageStoreAttestationError {
+    fn from(src: LedgerEnclaveError) -> Self {
+        Self::Enclave(src)
+    }
+}
+
+impl AttestationError for KeyImageStoreAttestationError {
+    fn should_reattest(&self) -> bool {
+        true
+    }
+
+    fn should_retry(&self) -> bool {
+        true
+    }
+}
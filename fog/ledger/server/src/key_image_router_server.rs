@@ -0,0 +1,181 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! Server object containing a key image router
+//! Constructible from config (for testability) and with a mechanism for
+//! stopping it
+
+use crate::{
+    counters, key_image_router_service::KeyImageRouterService,
+    key_image_store_connection::KeyImageStoreConnection, router_config::LedgerRouterConfig,
+};
+use futures::executor::block_on;
+use mc_attest_net::RaClient;
+use mc_common::{
+    logger::{log, Logger},
+    time::TimeProvider,
+};
+use mc_fog_api::ledger_grpc;
+use mc_fog_ledger_enclave::LedgerEnclaveProxy;
+use mc_sgx_report_cache_untrusted::ReportCacheThread;
+use mc_util_grpc::{
+    AnonymousAuthenticator, Authenticator, ConnectionUriGrpcioServer, ReadinessIndicator,
+    TokenAuthenticator,
+};
+use mc_util_uri::ConnectionUri;
+use std::sync::{Arc, Mutex};
+
+pub struct KeyImageRouterServer<E, RC>
+where
+    E: LedgerEnclaveProxy,
+    RC: RaClient + Send + Sync + 'static,
+{
+    config: LedgerRouterConfig,
+    server: grpcio::Server,
+    enclave: E,
+    ra_client: RC,
+    report_cache_thread: Option<ReportCacheThread>,
+    readiness_indicator: ReadinessIndicator,
+    logger: Logger,
+}
+
+impl<E, RC> KeyImageRouterServer<E, RC>
+where
+    E: LedgerEnclaveProxy,
+    RC: RaClient + Send + Sync + 'static,
+{
+    /// Make a new key image router instance
+    pub fn new(
+        config: LedgerRouterConfig,
+        enclave: E,
+        ra_client: RC,
+        time_provider: impl TimeProvider + 'static,
+        logger: Logger,
+    ) -> KeyImageRouterServer<E, RC> {
+        let readiness_indicator = ReadinessIndicator::default();
+
+        let env = Arc::new(
+            grpcio::EnvBuilder::new()
+                .name_prefix("Main-RPC".to_string())
+                .build(),
+        );
+
+        let client_authenticator: Arc<dyn Authenticator + Sync + Send> =
+            if let Some(shared_secret) = config.client_auth_token_secret.as_ref() {
+                Arc::new(TokenAuthenticator::new(
+                    *shared_secret,
+                    config.client_auth_token_max_lifetime,
+                    time_provider,
+                ))
+            } else {
+                Arc::new(AnonymousAuthenticator::default())
+            };
+
+        let store_env = Arc::new(
+            grpcio::EnvBuilder::new()
+                .name_prefix("KeyImageStore-RPC".to_string())
+                .build(),
+        );
+        let key_image_store_lanes = Arc::new(
+            (0..config.key_image_store_sessions.max(1))
+                .map(|_| {
+                    Mutex::new(
+                        config
+                            .key_image_store_uris
+                            .iter()
+                            .map(|uri| {
+                                KeyImageStoreConnection::new(
+                                    enclave.clone(),
+                                    uri.clone(),
+                                    store_env.clone(),
+                                    logger.clone(),
+                                )
+                            })
+                            .collect(),
+                    )
+                })
+                .collect(),
+        );
+
+        let key_image_router_service =
+            ledger_grpc::create_fog_key_image_api(KeyImageRouterService::new(
+                config.clone(),
+                enclave.clone(),
+                key_image_store_lanes,
+                client_authenticator,
+                logger.clone(),
+            ));
+        log::debug!(logger, "Constructed Key Image Router GRPC Service");
+
+        // Health check service
+        let health_service = mc_util_grpc::HealthService::new(
+            Some(readiness_indicator.clone().into()),
+            logger.clone(),
+        )
+        .into_service();
+
+        // Package service into grpc server
+        log::info!(
+            logger,
+            "Starting Key Image Router server on {}",
+            config.client_listen_uri.addr(),
+        );
+        let server_builder = grpcio::ServerBuilder::new(env)
+            .register_service(key_image_router_service)
+            .register_service(health_service)
+            .bind_using_uri(&config.client_listen_uri, logger.clone());
+
+        let server = server_builder.build().unwrap();
+
+        Self {
+            config,
+            server,
+            enclave,
+            ra_client,
+            report_cache_thread: None,
+            readiness_indicator,
+            logger,
+        }
+    }
+
+    /// Start the server, which starts all the worker threads
+    pub fn start(&mut self) {
+        self.report_cache_thread = Some(
+            ReportCacheThread::start(
+                self.enclave.clone(),
+                self.ra_client.clone(),
+                self.config.ias_spid,
+                &counters::ENCLAVE_REPORT_TIMESTAMP,
+                self.logger.clone(),
+            )
+            .expect("failed starting report cache thread"),
+        );
+
+        self.server.start();
+        for (host, port) in self.server.bind_addrs() {
+            log::info!(self.logger, "API listening on {}:{}", host, port);
+        }
+
+        // The router has no key images of its own to load, key image stores
+        // are attested lazily on the first request.
+        self.readiness_indicator.set_ready();
+    }
+
+    /// Stop the server and all worker threads
+    pub fn stop(&mut self) {
+        if let Some(ref mut thread) = self.report_cache_thread.take() {
+            thread.stop().expect("Could not stop report cache thread");
+        }
+
+        block_on(self.server.shutdown()).expect("Could not stop grpc server");
+    }
+}
+
+impl<E, RC> Drop for KeyImageRouterServer<E, RC>
+where
+    E: LedgerEnclaveProxy,
+    RC: RaClient + Send + Sync + 'static,
+{
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
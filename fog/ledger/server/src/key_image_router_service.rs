@@ -0,0 +1,362 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! Serves client key image checks on a key image router, by fanning each
+//! request out to the key image stores and merging their responses in the
+//! router's enclave.
+
+use crate::{
+    key_image_store_connection::KeyImageStoreConnection, router_config::LedgerRouterConfig,
+    sharding::contiguous_processed_block_count,
+};
+use futures::{executor::block_on, future::try_join_all};
+use grpcio::{RpcContext, RpcStatus, UnarySink};
+use mc_attest_api::{
+    attest,
+    attest::{AuthMessage, Message},
+};
+use mc_attest_enclave_api::{ClientSession, EnclaveMessage, PeerSession};
+use mc_blockchain_types::MAX_BLOCK_VERSION;
+use mc_common::logger::{log, Logger};
+use mc_connection::AttestedConnection;
+use mc_fog_api::{ledger::MultiKeyImageStoreResponse, ledger_grpc::FogKeyImageApi};
+use mc_fog_ledger_enclave::LedgerEnclaveProxy;
+use mc_fog_ledger_enclave_api::{Error as EnclaveError, UntrustedKeyImageQueryResponse};
+use mc_fog_types::common::BlockRange;
+use mc_util_grpc::{
+    check_request_chain_id, rpc_internal_error, rpc_invalid_arg_error, rpc_logger,
+    rpc_permissions_error, rpc_unavailable_error, send_result, Authenticator,
+};
+use mc_util_metrics::SVC_COUNTERS;
+use mc_util_telemetry::{tracer, Tracer};
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc, Mutex, MutexGuard,
+};
+
+/// One attested connection to each key image store.
+///
+/// The router's enclave encrypts messages for a store with sequential nonces,
+/// so a lane is only ever used by one request at a time.
+pub type KeyImageStoreLane<E> = Vec<KeyImageStoreConnection<E>>;
+
+#[derive(Clone)]
+pub struct KeyImageRouterService<E: LedgerEnclaveProxy> {
+    /// Router Config
+    config: LedgerRouterConfig,
+
+    /// The router's enclave
+    enclave: E,
+
+    /// Connections to the key image stores.
+    key_image_store_lanes: Arc<Vec<Mutex<KeyImageStoreLane<E>>>>,
+
+    /// The lane the next request should try first.
+    next_lane: Arc<AtomicUsize>,
+
+    /// GRPC request authenticator.
+    authenticator: Arc<dyn Authenticator + Send + Sync>,
+
+    /// Slog logger object
+    logger: Logger,
+}
+
+impl<E: LedgerEnclaveProxy> KeyImageRouterService<E> {
+    /// Creates a new key image router service (but does not create sockets
+    /// and start it etc.)
+    pub fn new(
+        config: LedgerRouterConfig,
+        enclave: E,
+        key_image_store_lanes: Arc<Vec<Mutex<KeyImageStoreLane<E>>>>,
+        authenticator: Arc<dyn Authenticator + Send + Sync>,
+        logger: Logger,
+    ) -> Self {
+        assert!(
+            !key_image_store_lanes.is_empty(),
+            "A key image router needs at least one lane of key image store connections"
+        );
+        Self {
+            config,
+            enclave,
+            key_image_store_lanes,
+            next_lane: Arc::new(AtomicUsize::new(0)),
+            authenticator,
+            logger,
+        }
+    }
+
+    /// Unwrap, fan out to the key image stores, and collate in the enclave
+    fn check_key_images_impl(&mut self, request: Message) -> Result<Message, RpcStatus> {
+        log::trace!(self.logger, "Getting encrypted request");
+        let tracer = tracer!();
+
+        tracer.in_span("check_key_images_impl", |_cx| {
+            let client_query: EnclaveMessage<ClientSession> = request.into();
+            let client_session = client_query.channel_id.clone();
+
+            let mut lane = self.lock_lane();
+            self.attest_lane(&mut lane)?;
+
+            let store_queries = tracer.in_span("create_multi_key_image_store_query", |_cx| {
+                let store_sessions = lane
+                    .iter()
+                    .filter_map(|conn| conn.channel_id().cloned())
+                    .collect();
+                self.enclave
+                    .create_multi_key_image_store_query(client_query, store_sessions)
+                    .map_err(|err| {
+                        if let EnclaveError::UnknownPeer = err {
+                            Self::deattest_lane(&mut lane);
+                        }
+                        self.enclave_err_to_rpc_status("create_multi_key_image_store_query", err)
+                    })
+            })?;
+
+            // From here on, the router and the stores have advanced their
+            // session nonces. If the responses don't all make it back into the
+            // enclave the sessions are out of sync and must be re-established.
+            let result = tracer.in_span("query_key_image_stores", |_cx| {
+                let store_responses = self.query_key_image_stores(&lane, store_queries)?;
+
+                let untrusted_key_image_query_response = merge_store_responses(&store_responses);
+
+                let shard_query_responses = store_responses
+                    .into_iter()
+                    .map(|mut response| response.take_query_response().into())
+                    .collect();
+
+                self.enclave
+                    .collate_shard_query_responses(
+                        client_session,
+                        shard_query_responses,
+                        untrusted_key_image_query_response,
+                    )
+                    .map_err(|e| self.enclave_err_to_rpc_status("collate_shard_responses", e))
+            });
+
+            let result_blob = result.map_err(|err| {
+                Self::deattest_lane(&mut lane);
+                err
+            })?;
+
+            let mut resp = Message::new();
+            resp.set_data(result_blob);
+            Ok(resp)
+        })
+    }
+
+    /// Take a lane of key image store connections, preferring one nobody is
+    /// using.
+    fn lock_lane(&self) -> MutexGuard<KeyImageStoreLane<E>> {
+        let num_lanes = self.key_image_store_lanes.len();
+        let first = self.next_lane.fetch_add(1, Ordering::Relaxed) % num_lanes;
+        for offset in 0..num_lanes {
+            if let Ok(lane) = self.key_image_store_lanes[(first + offset) % num_lanes].try_lock() {
+                return lane;
+            }
+        }
+        self.key_image_store_lanes[first]
+            .lock()
+            .expect("key image store lane mutex poisoned")
+    }
+
+    /// Make sure every connection in the lane is attested.
+    fn attest_lane(&self, lane: &mut KeyImageStoreLane<E>) -> Result<(), RpcStatus> {
+        for conn in lane.iter_mut() {
+            if !conn.is_attested() {
+                conn.attest().map_err(|err| {
+                    rpc_unavailable_error(
+                        "attest_key_image_store",
+                        format!("{}: {}", conn, err),
+                        &self.logger,
+                    )
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Drop the sessions of every connection in the lane.
+    fn deattest_lane(lane: &mut KeyImageStoreLane<E>) {
+        for conn in lane.iter_mut() {
+            conn.deattest();
+        }
+    }
+
+    /// Send each store its query, and wait for all of them to respond.
+    fn query_key_image_stores(
+        &self,
+        lane: &KeyImageStoreLane<E>,
+        store_queries: Vec<EnclaveMessage<PeerSession>>,
+    ) -> Result<Vec<MultiKeyImageStoreResponse>, RpcStatus> {
+        let receivers = lane
+            .iter()
+            .zip(store_queries)
+            .map(|(conn, query)| {
+                conn.multi_key_image_store_query_async(query)
+                    .map_err(|err| {
+                        rpc_unavailable_error(
+                            "multi_key_image_store_query",
+                            format!("{}: {}", conn, err),
+                            &self.logger,
+                        )
+                    })
+            })
+            .collect::<Result<Vec<_>, RpcStatus>>()?;
+
+        block_on(try_join_all(receivers))
+            .map_err(|err| rpc_unavailable_error("multi_key_image_store_query", err, &self.logger))
+    }
+
+    // Helper function that is common
+    fn enclave_err_to_rpc_status(&self, context: &str, src: EnclaveError) -> RpcStatus {
+        // Treat prost-decode error as an invalid arg,
+        // treat attest error as permission denied,
+        // everything else is an internal error
+        match src {
+            EnclaveError::ProstDecode => {
+                rpc_invalid_arg_error(context, "Prost decode failed", &self.logger)
+            }
+            EnclaveError::Attest(err) => rpc_permissions_error(context, err, &self.logger),
+            other => rpc_internal_error(context, format!("{}", &other), &self.logger),
+        }
+    }
+}
+
+/// A key image is only known to be unspent up to the blocks every shard before
+/// it has processed, so report the contiguous block count the stores cover. A
+/// store whose shard has ended stops following the chain, so the latest txo
+/// count and block version come from whichever store has seen the most.
+fn merge_store_responses(
+    store_responses: &[MultiKeyImageStoreResponse],
+) -> UntrustedKeyImageQueryResponse {
+    let shards: Vec<(BlockRange, u64)> = store_responses
+        .iter()
+        .map(|response| {
+            let block_range = response.get_block_range();
+            (
+                BlockRange::new(block_range.start_block, block_range.end_block),
+                response.processed_block_count,
+            )
+        })
+        .collect();
+
+    let last_known_block_cumulative_txo_count = store_responses
+        .iter()
+        .map(|response| response.last_known_block_cumulative_txo_count)
+        .max()
+        .unwrap_or_default();
+    let latest_block_version = store_responses
+        .iter()
+        .map(|response| response.latest_block_version)
+        .max()
+        .unwrap_or_default();
+
+    UntrustedKeyImageQueryResponse {
+        highest_processed_block_count: contiguous_processed_block_count(&shards),
+        last_known_block_cumulative_txo_count,
+        latest_block_version,
+        max_block_version: latest_block_version.max(*MAX_BLOCK_VERSION),
+    }
+}
+
+impl<E: LedgerEnclaveProxy> FogKeyImageApi for KeyImageRouterService<E> {
+    fn check_key_images(&mut self, ctx: RpcContext, request: Message, sink: UnarySink<Message>) {
+        let _timer = SVC_COUNTERS.req(&ctx);
+        mc_common::logger::scoped_global_logger(&rpc_logger(&ctx, &self.logger), |logger| {
+            if let Err(err) = check_request_chain_id(&self.config.chain_id, &ctx) {
+                return send_result(ctx, sink, Err(err), logger);
+            }
+
+            if let Err(err) = self.authenticator.authenticate_rpc(&ctx) {
+                return send_result(ctx, sink, err.into(), logger);
+            }
+
+            send_result(ctx, sink, self.check_key_images_impl(request), logger)
+        })
+    }
+
+    fn auth(&mut self, ctx: RpcContext, request: AuthMessage, sink: UnarySink<AuthMessage>) {
+        let _timer = SVC_COUNTERS.req(&ctx);
+        mc_common::logger::scoped_global_logger(&rpc_logger(&ctx, &self.logger), |logger| {
+            if let Err(err) = check_request_chain_id(&self.config.chain_id, &ctx) {
+                return send_result(ctx, sink, Err(err), logger);
+            }
+
+            if let Err(err) = self.authenticator.authenticate_rpc(&ctx) {
+                return send_result(ctx, sink, err.into(), logger);
+            }
+
+            // TODO: Use the prost message directly, once available
+            match self.enclave.client_accept(request.into()) {
+                Ok((response, _session_id)) => {
+                    send_result(ctx, sink, Ok(response.into()), logger);
+                }
+                Err(client_error) => {
+                    // This is debug because there's no requirement on the remote party to trigger
+                    // it.
+                    log::debug!(
+                        logger,
+                        "LedgerEnclave::client_accept failed: {}",
+                        client_error
+                    );
+                    send_result(
+                        ctx,
+                        sink,
+                        Err(rpc_permissions_error(
+                            "client_auth",
+                            "Permission denied",
+                            logger,
+                        )),
+                        logger,
+                    );
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mc_fog_api::fog_common;
+
+    fn store_response(
+        block_range: BlockRange,
+        processed_block_count: u64,
+        last_known_block_cumulative_txo_count: u64,
+        latest_block_version: u32,
+    ) -> MultiKeyImageStoreResponse {
+        let mut response = MultiKeyImageStoreResponse::new();
+        response.set_block_range(fog_common::BlockRange::from(&block_range));
+        response.set_processed_block_count(processed_block_count);
+        response.set_last_known_block_cumulative_txo_count(last_known_block_cumulative_txo_count);
+        response.set_latest_block_version(latest_block_version);
+        response
+    }
+
+    #[test]
+    fn merge_block_counts() {
+        // The first shard has ended and stopped following the chain, the second
+        // is still catching up.
+        let merged = merge_store_responses(&[
+            store_response(BlockRange::new(0, 10), 10, 100, 1),
+            store_response(BlockRange::new(10, u64::MAX), 14, 140, 2),
+        ]);
+
+        assert_eq!(merged.highest_processed_block_count, 14);
+        assert_eq!(merged.last_known_block_cumulative_txo_count, 140);
+        assert_eq!(merged.latest_block_version, 2);
+        assert_eq!(merged.max_block_version, 2.max(*MAX_BLOCK_VERSION));
+    }
+
+    #[test]
+    fn merge_block_counts_with_lagging_store() {
+        let merged = merge_store_responses(&[
+            store_response(BlockRange::new(0, 10), 6, 60, 1),
+            store_response(BlockRange::new(10, u64::MAX), 14, 140, 2),
+        ]);
+
+        assert_eq!(merged.highest_processed_block_count, 6);
+        assert_eq!(merged.last_known_block_cumulative_txo_count, 140);
+    }
+}
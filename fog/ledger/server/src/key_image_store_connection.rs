@@ -0,0 +1,171 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! An attested connection from a key image router to a key image store.
+
+use crate::error::KeyImageStoreAttestationError;
+use core::fmt::{Display, Formatter, Result as FmtResult};
+use grpcio::{ChannelBuilder, ClientUnaryReceiver, Environment, Error as GrpcError};
+use mc_attest_core::VerificationReport;
+use mc_attest_enclave_api::{EnclaveMessage, PeerSession};
+use mc_common::{
+    logger::{log, o, Logger},
+    ResponderId,
+};
+use mc_connection::{AttestedConnection, Connection};
+use mc_fog_api::{
+    ledger::{MultiKeyImageStoreRequest, MultiKeyImageStoreResponse},
+    ledger_grpc::FogKeyImageStoreApiClient,
+};
+use mc_fog_ledger_enclave::LedgerEnclaveProxy;
+use mc_fog_uri::KeyImageStoreUri;
+use mc_util_grpc::ConnectionUriGrpcioChannel;
+use mc_util_uri::ConnectionUri;
+use std::{
+    cmp::Ordering,
+    hash::{Hash, Hasher},
+    result::Result as StdResult,
+    sync::Arc,
+};
+
+/// This is a connection to a key image store which ensures transparent
+/// attestation between the router's enclave and the store's enclave.
+pub struct KeyImageStoreConnection<Enclave: LedgerEnclaveProxy> {
+    /// The local (router) enclave.
+    enclave: Enclave,
+
+    /// When communicating with the remote enclave, this is the handshake hash /
+    /// session ID / channel ID.
+    channel_id: Option<PeerSession>,
+
+    /// The remote node ID
+    remote_responder_id: ResponderId,
+
+    /// The remote node's URI.
+    uri: KeyImageStoreUri,
+
+    /// The logger instance we will be using.
+    logger: Logger,
+
+    /// The gRPC client used to talk to the key image store.
+    key_image_store_api_client: FogKeyImageStoreApiClient,
+}
+
+impl<Enclave: LedgerEnclaveProxy> KeyImageStoreConnection<Enclave> {
+    /// Construct a new KeyImageStoreConnection, optionally with TLS enabled.
+    pub fn new(
+        enclave: Enclave,
+        uri: KeyImageStoreUri,
+        env: Arc<Environment>,
+        logger: Logger,
+    ) -> Self {
+        let remote_responder_id = uri.responder_id().unwrap_or_else(|_| {
+            panic!("Could not get responder id from uri {:?}", uri.to_string())
+        });
+        let host_port = uri.addr();
+
+        let logger = logger.new(o!("mc.key_image_store.addr" => host_port));
+
+        let ch = ChannelBuilder::default_channel_builder(env)
+            .max_receive_message_len(std::i32::MAX)
+            .max_send_message_len(std::i32::MAX)
+            .connect_to_uri(&uri, &logger);
+
+        let key_image_store_api_client = FogKeyImageStoreApiClient::new(ch);
+
+        Self {
+            enclave,
+            channel_id: None,
+            remote_responder_id,
+            uri,
+            logger,
+            key_image_store_api_client,
+        }
+    }
+
+    /// The session with the remote enclave, if attested.
+    pub fn channel_id(&self) -> Option<&PeerSession> {
+        self.channel_id.as_ref()
+    }
+
+    /// Send a query, which must have been encrypted for this connection's
+    /// session, to the key image store without waiting for the response.
+    pub fn multi_key_image_store_query_async(
+        &self,
+        query: EnclaveMessage<PeerSession>,
+    ) -> StdResult<ClientUnaryReceiver<MultiKeyImageStoreResponse>, GrpcError> {
+        let mut request = MultiKeyImageStoreRequest::new();
+        request.set_query(query.into());
+        self.key_image_store_api_client
+            .multi_key_image_store_query_async(&request)
+    }
+}
+
+impl<Enclave: LedgerEnclaveProxy> Display for KeyImageStoreConnection<Enclave> {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "{}", self.uri)
+    }
+}
+
+impl<Enclave: LedgerEnclaveProxy> Eq for KeyImageStoreConnection<Enclave> {}
+
+impl<Enclave: LedgerEnclaveProxy> Hash for KeyImageStoreConnection<Enclave> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.uri.addr().hash(state);
+    }
+}
+
+impl<Enclave: LedgerEnclaveProxy> Ord for KeyImageStoreConnection<Enclave> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.uri.addr().cmp(&other.uri.addr())
+    }
+}
+
+impl<Enclave: LedgerEnclaveProxy> PartialEq for KeyImageStoreConnection<Enclave> {
+    fn eq(&self, other: &Self) -> bool {
+        self.uri.addr() == other.uri.addr()
+    }
+}
+
+impl<Enclave: LedgerEnclaveProxy> PartialOrd for KeyImageStoreConnection<Enclave> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.uri.addr().partial_cmp(&other.uri.addr())
+    }
+}
+
+impl<Enclave: LedgerEnclaveProxy> Connection for KeyImageStoreConnection<Enclave> {
+    type Uri = KeyImageStoreUri;
+
+    fn uri(&self) -> Self::Uri {
+        self.uri.clone()
+    }
+}
+
+impl<Enclave: LedgerEnclaveProxy> AttestedConnection for KeyImageStoreConnection<Enclave> {
+    type Error = KeyImageStoreAttestationError;
+
+    fn is_attested(&self) -> bool {
+        self.channel_id.is_some()
+    }
+
+    fn attest(&mut self) -> StdResult<VerificationReport, Self::Error> {
+        self.deattest();
+        let req = self.enclave.peer_init(&self.remote_responder_id)?;
+        let res = self.key_image_store_api_client.auth(&req.into())?;
+        let (peer_session, verification_report) = self
+            .enclave
+            .peer_connect(&self.remote_responder_id, res.into())?;
+
+        self.channel_id = Some(peer_session);
+
+        Ok(verification_report)
+    }
+
+    fn deattest(&mut self) {
+        if let Some(peer_session) = self.channel_id.take() {
+            log::trace!(self.logger, "Tearing down existing attested connection.");
+            if let Err(err) = self.enclave.peer_close(&peer_session) {
+                log::debug!(self.logger, "Failed closing peer session: {}", err);
+            }
+        }
+    }
+}
@@ -0,0 +1,149 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! Serves the attested requests a key image router makes to this server, when
+//! it acts as one of the router's key image stores.
+
+use crate::{server::DbPollSharedState, sharding::EpochShardingStrategy};
+use grpcio::{RpcContext, RpcStatus, UnarySink};
+use mc_attest_api::attest;
+use mc_common::logger::{log, Logger};
+use mc_fog_api::{
+    fog_common,
+    ledger::{MultiKeyImageStoreRequest, MultiKeyImageStoreResponse},
+    ledger_grpc::FogKeyImageStoreApi,
+};
+use mc_fog_ledger_enclave::LedgerEnclaveProxy;
+use mc_fog_ledger_enclave_api::Error as EnclaveError;
+use mc_util_grpc::{
+    rpc_internal_error, rpc_invalid_arg_error, rpc_logger, rpc_permissions_error, send_result,
+};
+use mc_util_metrics::SVC_COUNTERS;
+use std::sync::{Arc, Mutex};
+
+#[derive(Clone)]
+pub struct KeyImageStoreService<E: LedgerEnclaveProxy> {
+    /// Enclave holding this store's shard of the key images
+    enclave: E,
+
+    /// The blocks this store loads the key images of
+    sharding_strategy: EpochShardingStrategy,
+
+    /// Shared state from db polling thread.
+    db_poll_shared_state: Arc<Mutex<DbPollSharedState>>,
+
+    /// Slog logger object
+    logger: Logger,
+}
+
+impl<E: LedgerEnclaveProxy> KeyImageStoreService<E> {
+    pub fn new(
+        enclave: E,
+        sharding_strategy: EpochShardingStrategy,
+        db_poll_shared_state: Arc<Mutex<DbPollSharedState>>,
+        logger: Logger,
+    ) -> Self {
+        Self {
+            enclave,
+            sharding_strategy,
+            db_poll_shared_state,
+            logger,
+        }
+    }
+
+    /// Unwrap and forward to enclave
+    fn multi_key_image_store_query_impl(
+        &mut self,
+        request: MultiKeyImageStoreRequest,
+    ) -> Result<MultiKeyImageStoreResponse, RpcStatus> {
+        log::trace!(self.logger, "Getting encrypted request");
+
+        let mut response = MultiKeyImageStoreResponse::new();
+
+        // Read the shared state before querying the enclave, so that the
+        // reported block count never overstates what the ORAM contained.
+        {
+            let shared_state = self.db_poll_shared_state.lock().expect("mutex poisoned");
+            response.set_processed_block_count(shared_state.highest_processed_block_count);
+            response.set_last_known_block_cumulative_txo_count(
+                shared_state.last_known_block_cumulative_txo_count,
+            );
+            response.set_latest_block_version(shared_state.latest_block_version);
+        }
+
+        let query_response = self
+            .enclave
+            .check_key_image_store(request.get_query().clone().into())
+            .map_err(|e| self.enclave_err_to_rpc_status("enclave request", e))?;
+
+        response.set_query_response(query_response.into());
+        response.set_block_range(fog_common::BlockRange::from(
+            &self.sharding_strategy.epoch_block_range,
+        ));
+        Ok(response)
+    }
+
+    // Helper function that is common
+    fn enclave_err_to_rpc_status(&self, context: &str, src: EnclaveError) -> RpcStatus {
+        // Treat prost-decode error as an invalid arg,
+        // treat attest error as permission denied,
+        // everything else is an internal error
+        match src {
+            EnclaveError::ProstDecode => {
+                rpc_invalid_arg_error(context, "Prost decode failed", &self.logger)
+            }
+            EnclaveError::Attest(err) => rpc_permissions_error(context, err, &self.logger),
+            other => rpc_internal_error(context, format!("{}", &other), &self.logger),
+        }
+    }
+}
+
+impl<E: LedgerEnclaveProxy> FogKeyImageStoreApi for KeyImageStoreService<E> {
+    fn auth(
+        &mut self,
+        ctx: RpcContext,
+        request: attest::AuthMessage,
+        sink: UnarySink<attest::AuthMessage>,
+    ) {
+        let _timer = SVC_COUNTERS.req(&ctx);
+        mc_common::logger::scoped_global_logger(&rpc_logger(&ctx, &self.logger), |logger| {
+            // TODO: Use the prost message directly, once available
+            match self.enclave.peer_accept(request.into()) {
+                Ok((response, _session_id)) => {
+                    send_result(ctx, sink, Ok(response.into()), logger);
+                }
+                Err(peer_error) => {
+                    // This is debug because there's no requirement on the remote party to trigger
+                    // it.
+                    log::debug!(logger, "LedgerEnclave::peer_accept failed: {}", peer_error);
+                    send_result(
+                        ctx,
+                        sink,
+                        Err(rpc_permissions_error(
+                            "peer_auth",
+                            "Permission denied",
+                            logger,
+                        )),
+                        logger,
+                    );
+                }
+            }
+        });
+    }
+
+    fn multi_key_image_store_query(
+        &mut self,
+        ctx: RpcContext,
+        request: MultiKeyImageStoreRequest,
+        sink: UnarySink<MultiKeyImageStoreResponse>,
+    ) {
+        let _timer = SVC_COUNTERS.req(&ctx);
+        mc_common::logger::scoped_global_logger(&rpc_logger(&ctx, &self.logger), |logger| {
+            send_result(
+                ctx,
+                sink,
+                self.multi_key_image_store_query_impl(request),
+                logger,
+            )
+        })
+    }
+}
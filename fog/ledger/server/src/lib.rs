@@ -4,14 +4,30 @@ mod block_service;
 mod config;
 mod counters;
 mod db_fetcher;
+mod error;
+mod key_image_router_server;
+mod key_image_router_service;
 mod key_image_service;
+mod key_image_store_connection;
+mod key_image_store_service;
 mod merkle_proof_service;
+mod router_config;
 mod server;
+mod sharding;
 mod untrusted_tx_out_service;
 
 pub use block_service::BlockService;
 pub use config::LedgerServerConfig;
+pub use error::KeyImageStoreAttestationError;
+pub use key_image_router_server::KeyImageRouterServer;
+pub use key_image_router_service::{KeyImageRouterService, KeyImageStoreLane};
 pub use key_image_service::KeyImageService;
+pub use key_image_store_connection::KeyImageStoreConnection;
+pub use key_image_store_service::KeyImageStoreService;
 pub use merkle_proof_service::MerkleProofService;
+pub use router_config::LedgerRouterConfig;
 pub use server::LedgerServer;
+pub use sharding::{
+    contiguous_processed_block_count, EpochShardingStrategy, ShardingStrategyParseError,
+};
 pub use untrusted_tx_out_service::UntrustedTxOutService;
@@ -0,0 +1,79 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! Configuration parameters for the key image router
+
+#![deny(missing_docs)]
+
+use clap::Parser;
+use mc_attest_core::ProviderId;
+use mc_common::ResponderId;
+use mc_fog_uri::{FogLedgerUri, KeyImageStoreUri};
+use mc_util_parse::parse_duration_in_seconds;
+use mc_util_uri::AdminUri;
+use serde::Serialize;
+use std::time::Duration;
+
+/// Configuration parameters for the key image router
+#[derive(Clone, Parser, Serialize)]
+#[clap(version)]
+pub struct LedgerRouterConfig {
+    /// The chain id of the network we are a part of
+    #[clap(long, env = "MC_CHAIN_ID")]
+    pub chain_id: String,
+
+    /// Client Responder id.
+    ///
+    /// This ID needs to match the host:port clients use in their URI when
+    /// referencing this node.
+    #[clap(long, env = "MC_CLIENT_RESPONDER_ID")]
+    pub client_responder_id: ResponderId,
+
+    /// gRPC listening URI for client requests.
+    #[clap(long, env = "MC_CLIENT_LISTEN_URI")]
+    pub client_listen_uri: FogLedgerUri,
+
+    /// The key image stores to fan key image checks out to, separated by
+    /// commas.
+    ///
+    /// Each store's responder id (the host:port of the URI, or its
+    /// responder-id parameter) must match the client listen URI the store was
+    /// started with. Until the stores' sharding strategies cover every block
+    /// from the origin block, responses only cover the blocks up to the first
+    /// gap.
+    #[clap(
+        long,
+        required = true,
+        use_value_delimiter = true,
+        env = "MC_KEY_IMAGE_STORE_URIS"
+    )]
+    pub key_image_store_uris: Vec<KeyImageStoreUri>,
+
+    /// The number of independent sets of attested sessions to keep with the
+    /// key image stores. Messages on a session must be processed in order, so
+    /// this bounds the number of requests the router can serve concurrently.
+    #[clap(long, default_value = "4", env = "MC_KEY_IMAGE_STORE_SESSIONS")]
+    pub key_image_store_sessions: usize,
+
+    /// IAS Api Key.
+    #[clap(long, env = "MC_IAS_API_KEY")]
+    pub ias_api_key: String,
+
+    /// IAS Service Provider ID.
+    #[clap(long, env = "MC_IAS_SPID")]
+    pub ias_spid: ProviderId,
+
+    /// Optional admin listening URI.
+    #[clap(long, env = "MC_ADMIN_LISTEN_URI")]
+    pub admin_listen_uri: Option<AdminUri>,
+
+    /// Enables authenticating client requests using Authorization tokens using
+    /// the provided hex-encoded 32 bytes shared secret.
+    #[clap(long, value_parser = mc_util_parse::parse_hex::<[u8; 32]>, env = "MC_CLIENT_AUTH_TOKEN_SECRET")]
+    pub client_auth_token_secret: Option<[u8; 32]>,
+
+    /// Maximal client authentication token lifetime, in seconds (only relevant
+    /// when --client-auth-token-secret is used. Defaults to 86400 - 24
+    /// hours).
+    #[clap(long, default_value = "86400", value_parser = parse_duration_in_seconds, env = "MC_CLIENT_AUTH_TOKEN_MAX_LIFETIME")]
+    pub client_auth_token_max_lifetime: Duration,
+}
@@ -1,7 +1,8 @@
 // Copyright (c) 2018-2022 The MobileCoin Foundation
 
 use crate::{
-    config::LedgerServerConfig, counters, db_fetcher::DbFetcher, BlockService, KeyImageService,
+    config::LedgerServerConfig, counters, db_fetcher::DbFetcher,
+    key_image_store_service::KeyImageStoreService, BlockService, KeyImageService,
     MerkleProofService, UntrustedTxOutService,
 };
 use displaydoc::Display;
@@ -69,6 +70,7 @@ pub struct LedgerServer<E: LedgerEnclaveProxy, R: RaClient + Send + Sync + 'stat
     config: LedgerServerConfig,
     server: Option<grpcio::Server>,
     key_image_service: KeyImageService<LedgerDB, E>,
+    key_image_store_service: KeyImageStoreService<E>,
     merkle_proof_service: MerkleProofService<LedgerDB, E>,
     block_service: BlockService<LedgerDB>,
     untrusted_tx_out_service: UntrustedTxOutService<LedgerDB>,
@@ -107,10 +109,16 @@ impl<E: LedgerEnclaveProxy, R: RaClient + Send + Sync + 'static> LedgerServer<E,
             ledger.clone(),
             watcher.clone(),
             enclave.clone(),
-            shared_state,
+            shared_state.clone(),
             client_authenticator.clone(),
             logger.clone(),
         );
+        let key_image_store_service = KeyImageStoreService::new(
+            enclave.clone(),
+            config.sharding_strategy.clone(),
+            shared_state,
+            logger.clone(),
+        );
         let merkle_proof_service = MerkleProofService::new(
             config.chain_id.clone(),
             ledger.clone(),
@@ -137,6 +145,7 @@ impl<E: LedgerEnclaveProxy, R: RaClient + Send + Sync + 'static> LedgerServer<E,
             config,
             server: None,
             key_image_service,
+            key_image_store_service,
             merkle_proof_service,
             block_service,
             untrusted_tx_out_service,
@@ -165,6 +174,7 @@ impl<E: LedgerEnclaveProxy, R: RaClient + Send + Sync + 'static> LedgerServer<E,
                 self.enclave.clone(),
                 self.key_image_service.get_watcher(),
                 self.key_image_service.get_db_poll_shared_state(),
+                self.config.sharding_strategy.clone(),
                 readiness_indicator.clone(),
                 self.logger.clone(),
             ));
@@ -178,6 +188,9 @@ impl<E: LedgerEnclaveProxy, R: RaClient + Send + Sync + 'static> LedgerServer<E,
             // Package endpoints into grpc service
             let key_image_service =
                 ledger_grpc::create_fog_key_image_api(self.key_image_service.clone());
+            // Serves key image routers, when this server is one of their stores
+            let key_image_store_service =
+                ledger_grpc::create_fog_key_image_store_api(self.key_image_store_service.clone());
             let merkle_proof_service =
                 ledger_grpc::create_fog_merkle_proof_api(self.merkle_proof_service.clone());
             let block_service = ledger_grpc::create_fog_block_api(self.block_service.clone());
@@ -199,6 +212,7 @@ impl<E: LedgerEnclaveProxy, R: RaClient + Send + Sync + 'static> LedgerServer<E,
             );
             let server_builder = grpcio::ServerBuilder::new(env)
                 .register_service(key_image_service)
+                .register_service(key_image_store_service)
                 .register_service(merkle_proof_service)
                 .register_service(block_service)
                 .register_service(untrusted_tx_out_service)
@@ -0,0 +1,191 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! Sharding of the key images in the ledger across key image stores.
+//!
+//! A fog ledger server only loads the key images of the blocks in its shard
+//! into its ORAM, so that the key images of the whole ledger can be spread over
+//! several machines. A key image router asks every store about every key
+//! image, and uses the shards and progress reported by the stores to work out
+//! how many blocks the merged response covers.
+
+use displaydoc::Display;
+use mc_fog_types::common::BlockRange;
+use serde::Serialize;
+use std::{fmt, str::FromStr};
+
+/// Describes which blocks a key image store loads the key images of.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct EpochShardingStrategy {
+    /// The blocks whose key images are loaded. An end_block of u64::MAX means
+    /// the range is unbounded.
+    pub epoch_block_range: BlockRange,
+}
+
+impl Default for EpochShardingStrategy {
+    /// By default a store loads every block.
+    fn default() -> Self {
+        Self {
+            epoch_block_range: BlockRange::new(0, u64::MAX),
+        }
+    }
+}
+
+impl EpochShardingStrategy {
+    /// Test if the key images of a block belong to this shard.
+    pub fn should_process_block(&self, block_index: u64) -> bool {
+        self.epoch_block_range.contains(block_index)
+    }
+}
+
+/// An error parsing a sharding strategy
+#[derive(Debug, Display, Eq, PartialEq)]
+pub enum ShardingStrategyParseError {
+    /// Expected "blocks:START-[END]", got "{0}"
+    UnknownFormat(String),
+    /// Invalid number: {0}
+    InvalidNumber(String),
+    /// Range is empty: {0}
+    InvalidRange(String),
+}
+
+impl std::error::Error for ShardingStrategyParseError {}
+
+impl FromStr for EpochShardingStrategy {
+    type Err = ShardingStrategyParseError;
+
+    /// Parse "blocks:START-END", where END may be omitted for an unbounded
+    /// range.
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        let unknown = || ShardingStrategyParseError::UnknownFormat(src.to_string());
+        let range = src.strip_prefix("blocks:").ok_or_else(unknown)?;
+        let (start, end) = range.split_once('-').ok_or_else(unknown)?;
+        let invalid_number = |_| ShardingStrategyParseError::InvalidNumber(src.to_string());
+
+        let start_block = start.parse::<u64>().map_err(invalid_number)?;
+        let end_block = if end.is_empty() {
+            u64::MAX
+        } else {
+            end.parse::<u64>().map_err(invalid_number)?
+        };
+        let epoch_block_range = BlockRange::new(start_block, end_block);
+        if !epoch_block_range.is_valid() {
+            return Err(ShardingStrategyParseError::InvalidRange(src.to_string()));
+        }
+        Ok(Self { epoch_block_range })
+    }
+}
+
+impl fmt::Display for EpochShardingStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let range = &self.epoch_block_range;
+        if range.end_block == u64::MAX {
+            write!(f, "blocks:{}-", range.start_block)
+        } else {
+            write!(f, "blocks:{}-{}", range.start_block, range.end_block)
+        }
+    }
+}
+
+/// The number of blocks, counted from the origin block, whose key images have
+/// all been loaded by some store.
+///
+/// Each store reports its shard, and the block count up to which it has
+/// processed that shard. A store which is still catching up only covers the
+/// start of its shard, and a missing or lagging store leaves a gap after which
+/// nothing counts.
+pub fn contiguous_processed_block_count(shards: &[(BlockRange, u64)]) -> u64 {
+    let mut processed_ranges: Vec<(u64, u64)> = shards
+        .iter()
+        .map(|(block_range, processed_block_count)| {
+            (
+                block_range.start_block,
+                block_range.end_block.min(*processed_block_count),
+            )
+        })
+        .filter(|(start, end)| start < end)
+        .collect();
+    processed_ranges.sort_unstable();
+
+    let mut covered_until = 0;
+    for (start, end) in processed_ranges {
+        if start > covered_until {
+            break;
+        }
+        covered_until = covered_until.max(end);
+    }
+    covered_until
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_and_display() {
+        let strategy = EpochShardingStrategy::from_str("blocks:0-").unwrap();
+        assert_eq!(strategy, EpochShardingStrategy::default());
+        assert_eq!(strategy.to_string(), "blocks:0-");
+
+        let strategy = EpochShardingStrategy::from_str("blocks:100-200").unwrap();
+        assert_eq!(strategy.epoch_block_range, BlockRange::new(100, 200));
+        assert_eq!(strategy.to_string(), "blocks:100-200");
+        assert!(!strategy.should_process_block(99));
+        assert!(strategy.should_process_block(100));
+        assert!(strategy.should_process_block(199));
+        assert!(!strategy.should_process_block(200));
+
+        assert!(matches!(
+            EpochShardingStrategy::from_str("100-200"),
+            Err(ShardingStrategyParseError::UnknownFormat(_))
+        ));
+        assert!(matches!(
+            EpochShardingStrategy::from_str("blocks:a-200"),
+            Err(ShardingStrategyParseError::InvalidNumber(_))
+        ));
+        assert!(matches!(
+            EpochShardingStrategy::from_str("blocks:200-100"),
+            Err(ShardingStrategyParseError::InvalidRange(_))
+        ));
+    }
+
+    #[test]
+    fn processed_block_count_of_caught_up_stores() {
+        let shards = [
+            (BlockRange::new(0, 10), 10),
+            (BlockRange::new(10, u64::MAX), 25),
+        ];
+        assert_eq!(contiguous_processed_block_count(&shards), 25);
+    }
+
+    #[test]
+    fn processed_block_count_of_lagging_stores() {
+        // The first store is still loading its shard, so the second store's
+        // progress doesn't count yet.
+        let shards = [
+            (BlockRange::new(0, 10), 7),
+            (BlockRange::new(10, u64::MAX), 25),
+        ];
+        assert_eq!(contiguous_processed_block_count(&shards), 7);
+
+        // A store which hasn't started yet covers nothing.
+        let shards = [
+            (BlockRange::new(0, 10), 10),
+            (BlockRange::new(10, u64::MAX), 0),
+        ];
+        assert_eq!(contiguous_processed_block_count(&shards), 10);
+    }
+
+    #[test]
+    fn processed_block_count_with_gaps_and_overlaps() {
+        let shards = [
+            (BlockRange::new(0, 10), 10),
+            (BlockRange::new(5, 15), 15),
+            (BlockRange::new(20, u64::MAX), 30),
+        ];
+        assert_eq!(contiguous_processed_block_count(&shards), 15);
+
+        let shards = [(BlockRange::new(5, u64::MAX), 30)];
+        assert_eq!(contiguous_processed_block_count(&shards), 0);
+        assert_eq!(contiguous_processed_block_count(&[]), 0);
+    }
+}
@@ -126,11 +126,13 @@ fn fog_ledger_merkle_proofs_test(logger: Logger) {
                 client_auth_token_secret: None,
                 client_auth_token_max_lifetime: Default::default(),
                 omap_capacity: OMAP_CAPACITY,
+                sharding_strategy: Default::default(),
             };
 
             let enclave = LedgerSgxEnclave::new(
                 get_enclave_path(mc_fog_ledger_enclave::ENCLAVE_FILE),
                 &config.client_responder_id,
+                &config.client_responder_id,
                 OMAP_CAPACITY,
                 logger.clone(),
             );
@@ -357,11 +359,13 @@ fn fog_ledger_key_images_test(logger: Logger) {
                 client_auth_token_secret: None,
                 client_auth_token_max_lifetime: Default::default(),
                 omap_capacity: OMAP_CAPACITY,
+                sharding_strategy: Default::default(),
             };
 
             let enclave = LedgerSgxEnclave::new(
                 get_enclave_path(mc_fog_ledger_enclave::ENCLAVE_FILE),
                 &config.client_responder_id,
+                &config.client_responder_id,
                 OMAP_CAPACITY,
                 logger.clone(),
             );
@@ -559,11 +563,13 @@ fn fog_ledger_blocks_api_test(logger: Logger) {
             client_auth_token_secret: None,
             client_auth_token_max_lifetime: Default::default(),
             omap_capacity: OMAP_CAPACITY,
+            sharding_strategy: Default::default(),
         };
 
         let enclave = LedgerSgxEnclave::new(
             get_enclave_path(mc_fog_ledger_enclave::ENCLAVE_FILE),
             &config.client_responder_id,
+            &config.client_responder_id,
             OMAP_CAPACITY,
             logger.clone(),
         );
@@ -721,11 +727,13 @@ fn fog_ledger_untrusted_tx_out_api_test(logger: Logger) {
             client_auth_token_secret: None,
             client_auth_token_max_lifetime: Default::default(),
             omap_capacity: OMAP_CAPACITY,
+            sharding_strategy: Default::default(),
         };
 
         let enclave = LedgerSgxEnclave::new(
             get_enclave_path(mc_fog_ledger_enclave::ENCLAVE_FILE),
             &config.client_responder_id,
+            &config.client_responder_id,
             OMAP_CAPACITY,
             logger.clone(),
         );
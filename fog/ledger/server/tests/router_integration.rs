@@ -0,0 +1,322 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! Integration tests for a key image router in front of several sharded key
+//! image stores
+
+use mc_account_keys::{AccountKey, PublicAddress};
+use mc_api::watcher::TimestampResultCode;
+use mc_attest_net::{Client as AttestClient, RaClient};
+use mc_attest_verifier::{MrSignerVerifier, Verifier, DEBUG_ENCLAVE};
+use mc_blockchain_types::BlockVersion;
+use mc_common::{
+    logger::{test_with_logger, Logger},
+    time::SystemTimeProvider,
+    ResponderId,
+};
+use mc_fog_ledger_connection::{FogKeyImageGrpcClient, KeyImageResultExtension};
+use mc_fog_ledger_enclave::LedgerSgxEnclave;
+use mc_fog_ledger_server::{
+    EpochShardingStrategy, KeyImageRouterServer, LedgerRouterConfig, LedgerServer,
+    LedgerServerConfig,
+};
+use mc_fog_test_infra::get_enclave_path;
+use mc_fog_uri::{ConnectionUri, FogLedgerUri, KeyImageStoreUri};
+use mc_ledger_db::{test_utils::recreate_ledger_db, LedgerDB};
+use mc_transaction_core::{ring_signature::KeyImage, tokens::Mob, Amount, Token};
+use mc_util_grpc::GrpcRetryConfig;
+use mc_util_test_helper::{CryptoRng, RngCore, RngType, SeedableRng};
+use mc_watcher::watcher_db::WatcherDB;
+use std::{path::Path, str::FromStr, sync::Arc, thread::sleep, time::Duration};
+use tempdir::TempDir;
+use url::Url;
+
+const TEST_URL: &str = "http://www.my_url1.com";
+
+const OMAP_CAPACITY: u64 = 128 * 128;
+
+const GRPC_RETRY_CONFIG: GrpcRetryConfig = GrpcRetryConfig {
+    grpc_retry_count: 3,
+    grpc_retry_millis: 20,
+};
+
+// Test that a fog ledger connection gets the same key image results from a key
+// image router in front of two sharded stores as from a single ledger server
+#[test_with_logger]
+fn key_image_router_test(logger: Logger) {
+    let base_port = 3270;
+
+    let mut rng = RngType::from_seed([0u8; 32]);
+
+    let alice = AccountKey::random_with_fog(&mut rng);
+    let recipients = vec![alice.default_subaddress()];
+
+    let keys: Vec<KeyImage> = (0..20).map(|x| KeyImage::from(x as u64)).collect();
+
+    // Make LedgerDB
+    let ledger_dir = TempDir::new("fog-ledger").expect("Could not get test_ledger tempdir");
+    let db_full_path = ledger_dir.path();
+    let mut ledger = recreate_ledger_db(db_full_path);
+
+    // Make WatcherDB
+    let watcher_dir = TempDir::new("wallet_db").expect("Could not make tempdir for wallet db");
+    WatcherDB::create(watcher_dir.path()).unwrap();
+    let mut watcher = WatcherDB::open_rw(
+        watcher_dir.path(),
+        &[Url::parse(TEST_URL).unwrap()],
+        logger.clone(),
+    )
+    .unwrap();
+
+    // Populate ledger with some data
+    // Origin block cannot have key images
+    let block_version = BlockVersion::MAX;
+    add_block_to_ledger(
+        block_version,
+        &mut ledger,
+        &recipients,
+        &[],
+        &mut rng,
+        &mut watcher,
+    );
+    add_block_to_ledger(
+        block_version,
+        &mut ledger,
+        &recipients,
+        &keys[0..2],
+        &mut rng,
+        &mut watcher,
+    );
+    add_block_to_ledger(
+        block_version,
+        &mut ledger,
+        &recipients,
+        &keys[3..6],
+        &mut rng,
+        &mut watcher,
+    );
+    let num_blocks = add_block_to_ledger(
+        block_version,
+        &mut ledger,
+        &recipients,
+        &keys[6..9],
+        &mut rng,
+        &mut watcher,
+    );
+
+    {
+        // Make two key image stores, the first holding blocks 0 and 1, the
+        // second holding every block from 2 onwards
+        let (_first_store, first_store_uri) = start_key_image_store(
+            base_port + 1,
+            "blocks:0-2",
+            db_full_path,
+            watcher_dir.path(),
+            ledger.clone(),
+            watcher.clone(),
+            logger.clone(),
+        );
+        let (_second_store, second_store_uri) = start_key_image_store(
+            base_port + 2,
+            "blocks:2-",
+            db_full_path,
+            watcher_dir.path(),
+            ledger.clone(),
+            watcher.clone(),
+            logger.clone(),
+        );
+
+        // Make the router
+        let client_uri =
+            FogLedgerUri::from_str(&format!("insecure-fog-ledger://127.0.0.1:{}", base_port))
+                .unwrap();
+        let config = LedgerRouterConfig {
+            chain_id: "local".to_string(),
+            client_responder_id: ResponderId::from_str(&client_uri.addr()).unwrap(),
+            client_listen_uri: client_uri.clone(),
+            key_image_store_uris: vec![first_store_uri, second_store_uri],
+            key_image_store_sessions: 2,
+            ias_spid: Default::default(),
+            ias_api_key: Default::default(),
+            admin_listen_uri: Default::default(),
+            client_auth_token_secret: None,
+            client_auth_token_max_lifetime: Default::default(),
+        };
+
+        let enclave = LedgerSgxEnclave::new(
+            get_enclave_path(mc_fog_ledger_enclave::ENCLAVE_FILE),
+            &config.client_responder_id,
+            &config.client_responder_id,
+            OMAP_CAPACITY,
+            logger.clone(),
+        );
+
+        let ra_client =
+            AttestClient::new(&config.ias_api_key).expect("Could not create IAS client");
+
+        let mut router = KeyImageRouterServer::new(
+            config,
+            enclave,
+            ra_client,
+            SystemTimeProvider::default(),
+            logger.clone(),
+        );
+        router.start();
+
+        // Make ledger enclave client
+        let mut mr_signer_verifier =
+            MrSignerVerifier::from(mc_fog_ledger_enclave_measurement::sigstruct());
+        mr_signer_verifier
+            .allow_hardening_advisories(mc_fog_ledger_enclave_measurement::HARDENING_ADVISORIES);
+
+        let mut verifier = Verifier::default();
+        verifier.mr_signer(mr_signer_verifier).debug(DEBUG_ENCLAVE);
+
+        let grpc_env = Arc::new(grpcio::EnvBuilder::new().build());
+        let mut client = FogKeyImageGrpcClient::new(
+            "local".to_string(),
+            client_uri,
+            GRPC_RETRY_CONFIG,
+            verifier,
+            grpc_env,
+            logger.clone(),
+        );
+
+        // Check on key images
+        let query = [keys[0], keys[1], keys[3], keys[7], keys[19]];
+        let mut response = client
+            .check_key_images(&query)
+            .expect("check_key_images failed");
+
+        let mut n = 1;
+        // adding a delay to give the stores time to load their shards
+        while response.num_blocks != num_blocks {
+            sleep(Duration::from_secs(10));
+            response = client
+                .check_key_images(&query)
+                .expect("check_key_images failed");
+
+            // panic on the 20th time
+            n += 1;
+            if n > 20 {
+                panic!("Key image stores not fully initialized");
+            }
+        }
+
+        // Spent in block 1, found by the first store
+        assert_eq!(response.results[0].key_image, keys[0]);
+        assert_eq!(response.results[0].status(), Ok(Some(1)));
+        assert_eq!(response.results[1].key_image, keys[1]);
+        assert_eq!(response.results[1].status(), Ok(Some(1)));
+
+        // Spent in blocks 2 and 3, found by the second store
+        assert_eq!(response.results[2].key_image, keys[3]);
+        assert_eq!(response.results[2].status(), Ok(Some(2)));
+        assert_eq!(response.results[3].key_image, keys[7]);
+        assert_eq!(response.results[3].status(), Ok(Some(3)));
+
+        // Not spent, found by neither store
+        assert_eq!(response.results[4].key_image, keys[19]);
+        assert_eq!(response.results[4].status(), Ok(None));
+        assert_eq!(
+            response.results[4].timestamp_result_code,
+            TimestampResultCode::TimestampFound as u32
+        );
+        assert_eq!(response.results[4].timestamp, u64::MAX);
+    }
+
+    // grpcio detaches all its threads and does not join them, so give them a
+    // moment to see the shutdown requests.
+    sleep(Duration::from_millis(1000));
+}
+
+fn start_key_image_store(
+    port: u16,
+    sharding_strategy: &str,
+    ledger_db: &Path,
+    watcher_db: &Path,
+    ledger: LedgerDB,
+    watcher: WatcherDB,
+    logger: Logger,
+) -> (
+    LedgerServer<LedgerSgxEnclave, AttestClient>,
+    KeyImageStoreUri,
+) {
+    let client_uri =
+        FogLedgerUri::from_str(&format!("insecure-fog-ledger://127.0.0.1:{}", port)).unwrap();
+    let store_uri =
+        KeyImageStoreUri::from_str(&format!("insecure-key-image-store://127.0.0.1:{}", port))
+            .unwrap();
+
+    let config = LedgerServerConfig {
+        chain_id: "local".to_string(),
+        ledger_db: ledger_db.to_path_buf(),
+        watcher_db: watcher_db.to_path_buf(),
+        admin_listen_uri: Default::default(),
+        client_listen_uri: client_uri.clone(),
+        client_responder_id: ResponderId::from_str(&client_uri.addr()).unwrap(),
+        ias_spid: Default::default(),
+        ias_api_key: Default::default(),
+        client_auth_token_secret: None,
+        client_auth_token_max_lifetime: Default::default(),
+        omap_capacity: OMAP_CAPACITY,
+        sharding_strategy: EpochShardingStrategy::from_str(sharding_strategy).unwrap(),
+    };
+
+    let enclave = LedgerSgxEnclave::new(
+        get_enclave_path(mc_fog_ledger_enclave::ENCLAVE_FILE),
+        &config.client_responder_id,
+        &client_uri.responder_id().unwrap(),
+        OMAP_CAPACITY,
+        logger.clone(),
+    );
+
+    let ra_client = AttestClient::new(&config.ias_api_key).expect("Could not create IAS client");
+
+    let mut store = LedgerServer::new(
+        config,
+        enclave,
+        ledger,
+        watcher,
+        ra_client,
+        SystemTimeProvider::default(),
+        logger,
+    );
+    store.start().expect("Failed starting key image store");
+
+    (store, store_uri)
+}
+
+fn add_block_to_ledger(
+    block_version: BlockVersion,
+    ledger_db: &mut LedgerDB,
+    recipients: &[PublicAddress],
+    key_images: &[KeyImage],
+    rng: &mut (impl CryptoRng + RngCore),
+    watcher: &mut WatcherDB,
+) -> u64 {
+    let amount = Amount::new(10, Mob::ID);
+    let block_data = mc_ledger_db::test_utils::add_block_to_ledger(
+        ledger_db,
+        block_version,
+        recipients,
+        amount,
+        key_images,
+        rng,
+    )
+    .expect("failed to add block");
+    let block_index = block_data.block().index;
+
+    let signature = block_data.signature().expect("missing signature");
+    for src_url in watcher.get_config_urls().unwrap().iter() {
+        watcher
+            .add_block_signature(
+                src_url,
+                block_index,
+                signature.clone(),
+                format!("00/{}", block_index),
+            )
+            .expect("Could not add block signature");
+    }
+
+    block_index + 1
+}
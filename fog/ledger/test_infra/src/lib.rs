@@ -3,7 +3,10 @@
 //! Functionality for mocking and testing components in the ledger server
 
 use mc_attest_core::{IasNonce, Quote, QuoteNonce, Report, TargetInfo, VerificationReport};
-use mc_attest_enclave_api::{ClientAuthRequest, ClientAuthResponse, ClientSession, EnclaveMessage};
+use mc_attest_enclave_api::{
+    ClientAuthRequest, ClientAuthResponse, ClientSession, EnclaveMessage, PeerAuthRequest,
+    PeerAuthResponse, PeerSession,
+};
 use mc_blockchain_types::{
     Block, BlockContents, BlockData, BlockIndex, BlockMetadata, BlockSignature,
 };
@@ -44,7 +47,12 @@ impl ReportableEnclave for MockEnclave {
 }
 
 impl LedgerEnclave for MockEnclave {
-    fn enclave_init(&self, _self_id: &ResponderId, _desired_capacity: u64) -> EnclaveResult<()> {
+    fn enclave_init(
+        &self,
+        _self_peer_id: &ResponderId,
+        _self_client_id: &ResponderId,
+        _desired_capacity: u64,
+    ) -> EnclaveResult<()> {
         unimplemented!()
     }
 
@@ -84,6 +92,50 @@ impl LedgerEnclave for MockEnclave {
     ) -> Result<(), mc_fog_ledger_enclave::Error> {
         unimplemented!()
     }
+
+    fn peer_init(&self, _peer_id: &ResponderId) -> EnclaveResult<PeerAuthRequest> {
+        unimplemented!()
+    }
+
+    fn peer_accept(&self, _req: PeerAuthRequest) -> EnclaveResult<(PeerAuthResponse, PeerSession)> {
+        unimplemented!()
+    }
+
+    fn peer_connect(
+        &self,
+        _peer_id: &ResponderId,
+        _msg: PeerAuthResponse,
+    ) -> EnclaveResult<(PeerSession, VerificationReport)> {
+        unimplemented!()
+    }
+
+    fn peer_close(&self, _session_id: &PeerSession) -> EnclaveResult<()> {
+        unimplemented!()
+    }
+
+    fn check_key_image_store(
+        &self,
+        _msg: EnclaveMessage<PeerSession>,
+    ) -> EnclaveResult<EnclaveMessage<PeerSession>> {
+        unimplemented!()
+    }
+
+    fn create_multi_key_image_store_query(
+        &self,
+        _msg: EnclaveMessage<ClientSession>,
+        _store_sessions: Vec<PeerSession>,
+    ) -> EnclaveResult<Vec<EnclaveMessage<PeerSession>>> {
+        unimplemented!()
+    }
+
+    fn collate_shard_query_responses(
+        &self,
+        _client_session: ClientSession,
+        _shard_query_responses: Vec<EnclaveMessage<PeerSession>>,
+        _untrusted_keyimagequery_response: UntrustedKeyImageQueryResponse,
+    ) -> EnclaveResult<Vec<u8>> {
+        unimplemented!()
+    }
 }
 
 #[derive(Clone, Default)]
@@ -14,7 +14,7 @@
 //! should be updated to measure this effect.
 
 use clap::Parser;
-use grpcio::{ChannelBuilder, Error as GrpcioError};
+use grpcio::ChannelBuilder;
 use mc_account_keys::AccountKey;
 use mc_blockchain_test_utils::get_blocks;
 use mc_blockchain_types::{BlockSignature, BlockVersion};
@@ -22,16 +22,17 @@ use mc_common::logger::{log, Logger};
 use mc_crypto_keys::Ed25519Pair;
 use mc_crypto_rand::McRng;
 use mc_fog_ingest_client::FogIngestGrpcClient;
-use mc_fog_load_testing::get_bin_path;
+use mc_fog_load_testing::{
+    compute_basic_stats, get_bin_path, wait_for_admin_api, AutoKillChild, BasicTimingStats,
+};
 use mc_fog_recovery_db_iface::RecoveryDb;
 use mc_fog_sql_recovery_db::test_utils::SqlRecoveryDbTestContext;
 use mc_fog_uri::{ConnectionUri, FogIngestUri, IngestPeerUri};
 use mc_ledger_db::{test_utils::initialize_ledger, Ledger, LedgerDB};
 use mc_util_from_random::FromRandom;
-use mc_util_grpc::{admin_grpc::AdminApiClient, ConnectionUriGrpcioChannel, Empty};
+use mc_util_grpc::{admin_grpc::AdminApiClient, ConnectionUriGrpcioChannel};
 use mc_util_uri::AdminUri;
 use mc_watcher::watcher_db::WatcherDB;
-use retry::{delay, retry, OperationResult};
 use std::{
     path::Path,
     str::FromStr,
@@ -41,52 +42,6 @@ use std::{
 };
 use tempdir::TempDir;
 
-// Compute mean and std_dev of timings
-#[derive(Default, Clone)]
-struct BasicTimingStats {
-    pub num_samples: usize,
-    pub mean: Duration,
-    pub std_dev: Duration,
-}
-
-impl core::fmt::Display for BasicTimingStats {
-    fn fmt(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
-        write!(
-            formatter,
-            "num samples: {}, avg: {} ms +/- {} ms",
-            self.num_samples,
-            self.mean.as_secs_f64() * 1000f64,
-            self.std_dev.as_secs_f64() * 1000f64
-        )
-    }
-}
-
-// Take a series of durations, and compute their mean and standard deviation
-fn compute_basic_stats(data: &[Duration], logger: &Logger) -> BasicTimingStats {
-    let num_samples = data.len();
-
-    let mean = data.iter().fold(0f64, |l, r| l + r.as_secs_f64()) / num_samples as f64;
-    let variance = data
-        .iter()
-        .fold(0f64, |l, r| l + (r.as_secs_f64() - mean).powi(2))
-        / num_samples as f64;
-    let std_dev = variance.sqrt();
-
-    log::debug!(
-        logger,
-        "mean sec = {}, variance sec = {}, std_dev sec = {}",
-        mean,
-        variance,
-        std_dev
-    );
-
-    BasicTimingStats {
-        num_samples,
-        mean: Duration::from_secs_f64(mean),
-        std_dev: Duration::from_secs_f64(std_dev),
-    }
-}
-
 // Parameters of interest to twiddle for purposes of load testing
 #[derive(Default, Clone)]
 struct TestParams {
@@ -117,42 +72,6 @@ impl core::fmt::Display for TestResult {
     }
 }
 
-/// RAII gaurd for a child process, which kills the child on drop.
-pub struct AutoKillChild(pub std::process::Child);
-
-impl AutoKillChild {
-    /// Assert that the child process is still alive.
-    ///
-    /// Panics:
-    ///  * If the process stopped unexpectedly.
-    ///  * If there is an error while getting the process' status.
-    pub fn assert_not_stopped(&mut self) {
-        match self.0.try_wait() {
-            Ok(Some(stat)) => {
-                panic!("child stopped unexpectedly: status: {}", stat)
-            }
-            Ok(None) => {}
-            Err(err) => {
-                panic!("error getting child status: {}", err)
-            }
-        }
-    }
-}
-
-impl Drop for AutoKillChild {
-    fn drop(&mut self) {
-        if let Err(err) = self.0.kill() {
-            // Invalid input means the process is already dead, and we don't have to kill
-            // it. Anything else means that killing it failed somehow
-            if err.kind() != std::io::ErrorKind::InvalidInput {
-                panic!("Could not send SIGKILL to child: {}", err)
-            }
-        }
-
-        self.0.wait().expect("Could not reap child");
-    }
-}
-
 fn load_test(ingest_server_binary: &Path, test_params: TestParams, logger: Logger) -> TestResult {
     let mut test_results = TestResult {
         params: test_params.clone(),
@@ -255,26 +174,7 @@ fn load_test(ingest_server_binary: &Path, test_params: TestParams, logger: Logge
                 AdminApiClient::new(ch)
             };
 
-            let info = retry(delay::Fixed::from_millis(5000).map(delay::jitter), || {
-                ingest_server.assert_not_stopped();
-
-                match admin_client.get_info(&Empty::default()) {
-                    Ok(info) => OperationResult::Ok(info),
-                    Err(GrpcioError::RpcFailure(err)) => {
-                        log::info!(&logger, "Waiting for ingest server to become available");
-                        OperationResult::Retry(GrpcioError::RpcFailure(err))
-                    }
-                    Err(err) => OperationResult::Err(err),
-                }
-            })
-            .expect("Could not connect to ingest server");
-
-            log::info!(
-                logger,
-                "Connected to server:\nbuild_info: {}\nconfig_json: {}",
-                info.build_info_json,
-                info.config_json
-            );
+            wait_for_admin_api("ingest server", &admin_client, &mut ingest_server, &logger);
         }
 
         // Tell server to activate
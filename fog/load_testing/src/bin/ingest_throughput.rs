@@ -0,0 +1,612 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+#![deny(missing_docs)]
+
+//! This benchmark measures the end-to-end throughput of the fog ingest path:
+//! a block is appended to the LedgerDB, the ingest enclave scans its TxOuts
+//! (`ingest_txs`), the resulting ETxOutRecords are written to the recovery db
+//! (`add_block_data`), and the fog view server loads them into its enclave.
+//!
+//! It generates a synthetic ledger with `generate-sample-ledger`, spawns an
+//! ingest server and a view server (which should be built against the SGX
+//! simulation enclaves), activates the ingest server, and then appends the
+//! generated blocks to the ledger that the ingest server watches.
+//!
+//! It reports blocks/sec, the end-to-end latency of each block, and the
+//! average time spent in each stage, which the servers export as metrics.
+
+use clap::{error::ErrorKind, CommandFactory, Parser};
+use grpcio::ChannelBuilder;
+use mc_account_keys::AccountKey;
+use mc_blockchain_types::BlockSignature;
+use mc_common::logger::{log, Logger};
+use mc_crypto_keys::Ed25519Pair;
+use mc_crypto_rand::McRng;
+use mc_fog_ingest_client::FogIngestGrpcClient;
+use mc_fog_load_testing::{
+    compute_basic_stats, get_bin_path, get_op_metric, wait_for_admin_api, AutoKillChild,
+    BasicTimingStats,
+};
+use mc_fog_recovery_db_iface::RecoveryDb;
+use mc_fog_sql_recovery_db::test_utils::SqlRecoveryDbTestContext;
+use mc_fog_uri::{ConnectionUri, FogIngestUri, FogViewUri, IngestPeerUri};
+use mc_ledger_db::{Ledger, LedgerDB};
+use mc_util_from_random::FromRandom;
+use mc_util_grpc::{admin_grpc::AdminApiClient, ConnectionUriGrpcioChannel, Empty};
+use mc_util_uri::AdminUri;
+use mc_watcher::watcher_db::WatcherDB;
+use serde_json::json;
+use std::{
+    fs,
+    path::PathBuf,
+    str::FromStr,
+    sync::{mpsc, Arc},
+    thread::{self, sleep},
+    time::{Duration, Instant},
+};
+use tempdir::TempDir;
+
+/// How long to wait for the next block to make progress before giving up.
+const PROGRESS_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How often to poll the recovery db and the view server for new blocks.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+#[derive(Debug, Parser)]
+#[clap(
+    name = "fog-ingest-throughput-bench",
+    about = "Measures the throughput of the fog ingest path, from the ledger to the view server"
+)]
+struct BenchOptions {
+    /// The number of blocks to ingest, not counting the origin block.
+    #[clap(long, default_value = "100", env = "MC_NUM_BLOCKS")]
+    num_blocks: usize,
+
+    /// The number of TxOuts in each generated block.
+    #[clap(long, default_value = "250", env = "MC_TXOS_PER_BLOCK")]
+    txos_per_block: usize,
+
+    /// The number of blocks at the start which are left out of the
+    /// measurements, while the servers warm up.
+    #[clap(long, default_value = "5", env = "MC_WARMUP_BLOCKS")]
+    warmup_blocks: usize,
+
+    /// How long to wait between appending blocks to the ledger, in
+    /// milliseconds. The default of 0 appends them as fast as possible, which
+    /// measures the maximum throughput. A larger interval measures the
+    /// latency of each block without a backlog.
+    #[clap(long, default_value = "0", env = "MC_BLOCK_INTERVAL_MS")]
+    block_interval_ms: u64,
+
+    /// The seed for generating the ledger, so that runs can be compared.
+    #[clap(long, value_parser = mc_util_parse::parse_hex::<[u8; 32]>, env = "MC_SEED")]
+    seed: Option<[u8; 32]>,
+
+    /// The user capacity of the ingest server.
+    #[clap(long, default_value = "1048576", env = "MC_USER_CAPACITY")]
+    user_capacity: u64,
+
+    /// The ORAM capacity of the view server. This must fit every generated
+    /// TxOut.
+    #[clap(long, default_value = "1048576", env = "MC_OMAP_CAPACITY")]
+    omap_capacity: u64,
+
+    /// Also write the results as JSON to this file.
+    #[clap(long, env = "MC_OUTPUT_JSON")]
+    output_json: Option<PathBuf>,
+}
+
+/// A block that was appended to the ledger.
+struct AppendedBlock {
+    /// The index of the block.
+    index: u64,
+    /// How long appending the block to the LedgerDB took.
+    append_time: Duration,
+    /// When the block was appended.
+    appended_at: Instant,
+}
+
+/// When each block became available at each stage.
+#[derive(Default, Clone, Copy)]
+struct BlockTimes {
+    append_time: Option<Duration>,
+    appended_at: Option<Instant>,
+    recovery_db_at: Option<Instant>,
+    view_at: Option<Instant>,
+}
+
+/// Record the blocks that the appender thread has appended so far.
+fn record_appended_blocks(
+    appended_rx: &mpsc::Receiver<AppendedBlock>,
+    block_times: &mut [BlockTimes],
+) {
+    for appended in appended_rx.try_iter() {
+        let times = &mut block_times[appended.index as usize];
+        times.append_time = Some(appended.append_time);
+        times.appended_at = Some(appended.appended_at);
+    }
+}
+
+/// The metrics of the ingest and view servers at some point in time.
+struct MetricsSnapshot {
+    ingest: String,
+    view: String,
+}
+
+impl MetricsSnapshot {
+    fn get(ingest_admin: &AdminApiClient, view_admin: &AdminApiClient) -> Self {
+        let get_metrics = |admin_client: &AdminApiClient| {
+            admin_client
+                .get_prometheus_metrics(&Empty::default())
+                .expect("Could not get metrics")
+                .metrics
+        };
+        Self {
+            ingest: get_metrics(ingest_admin),
+            view: get_metrics(view_admin),
+        }
+    }
+}
+
+/// The average time per block spent in a stage, computed from the difference
+/// between two snapshots of a histogram and of a counter of blocks.
+fn average_stage_time(
+    start: &str,
+    end: &str,
+    metrics_name: &str,
+    histogram_op: &str,
+    blocks_op: &str,
+) -> Duration {
+    let delta = |metric: &str, op: &str| {
+        let get = |metrics| {
+            get_op_metric(metrics, metric, op)
+                .unwrap_or_else(|| panic!("Missing metric {}{{op=\"{}\"}}", metric, op))
+        };
+        get(end) - get(start)
+    };
+    let seconds = delta(&format!("{}_duration_sum", metrics_name), histogram_op);
+    let blocks = delta(metrics_name, blocks_op);
+    if blocks > 0f64 {
+        Duration::from_secs_f64(seconds / blocks)
+    } else {
+        Duration::default()
+    }
+}
+
+// The results of a benchmark run
+struct BenchResult {
+    num_blocks: usize,
+    txos_per_block: usize,
+    blocks_per_sec: f64,
+    ledger_append: BasicTimingStats,
+    ledger_to_recovery_db: BasicTimingStats,
+    ledger_to_view: BasicTimingStats,
+    ingest_txs: Duration,
+    add_block_data: Duration,
+    process_next_block: Duration,
+    view_add_records: Duration,
+}
+
+impl BenchResult {
+    fn to_json(&self) -> serde_json::Value {
+        let millis = |duration: &Duration| duration.as_secs_f64() * 1000f64;
+        let stats = |stats: &BasicTimingStats| {
+            json!({
+                "num_samples": stats.num_samples,
+                "mean_ms": millis(&stats.mean),
+                "std_dev_ms": millis(&stats.std_dev),
+            })
+        };
+        json!({
+            "num_blocks": self.num_blocks,
+            "txos_per_block": self.txos_per_block,
+            "blocks_per_sec": self.blocks_per_sec,
+            "txos_per_sec": self.blocks_per_sec * self.txos_per_block as f64,
+            "ledger_append": stats(&self.ledger_append),
+            "ledger_to_recovery_db": stats(&self.ledger_to_recovery_db),
+            "ledger_to_view": stats(&self.ledger_to_view),
+            "ingest_txs_mean_ms": millis(&self.ingest_txs),
+            "add_block_data_mean_ms": millis(&self.add_block_data),
+            "process_next_block_mean_ms": millis(&self.process_next_block),
+            "view_add_records_mean_ms": millis(&self.view_add_records),
+        })
+    }
+}
+
+impl core::fmt::Display for BenchResult {
+    fn fmt(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
+        let millis = |duration: &Duration| duration.as_secs_f64() * 1000f64;
+        writeln!(
+            formatter,
+            "{} blocks of {} txos: {} blocks/sec, {} txos/sec",
+            self.num_blocks,
+            self.txos_per_block,
+            self.blocks_per_sec,
+            self.blocks_per_sec * self.txos_per_block as f64
+        )?;
+        writeln!(formatter, "Ledger append: {}", self.ledger_append)?;
+        writeln!(
+            formatter,
+            "Ledger to recovery db: {}",
+            self.ledger_to_recovery_db
+        )?;
+        writeln!(formatter, "Ledger to view store: {}", self.ledger_to_view)?;
+        writeln!(
+            formatter,
+            "Ingest enclave ingest_txs: avg: {} ms per block",
+            millis(&self.ingest_txs)
+        )?;
+        writeln!(
+            formatter,
+            "Recovery db add_block_data: avg: {} ms per block",
+            millis(&self.add_block_data)
+        )?;
+        writeln!(
+            formatter,
+            "Ingest process_next_block: avg: {} ms per block",
+            millis(&self.process_next_block)
+        )?;
+        write!(
+            formatter,
+            "View enclave add_records: avg: {} ms per block",
+            millis(&self.view_add_records)
+        )
+    }
+}
+
+fn bench(opt: &BenchOptions, logger: Logger) -> BenchResult {
+    let num_blocks = opt.num_blocks as u64;
+    let warmup_blocks = opt.warmup_blocks as u64;
+
+    let mut csprng = McRng {};
+    let rng = &mut csprng;
+
+    let signer = Ed25519Pair::from_random(rng);
+
+    let ingest_server_binary = get_bin_path("fog_ingest_server");
+    let view_server_binary = get_bin_path("fog_view_server");
+
+    let result = {
+        // First make grpcio env
+        // Note: This needs to be destroyed when the servers are destroyed,
+        // then we have to sleep, see end of this scope
+        let grpcio_env = Arc::new(grpcio::EnvBuilder::new().build());
+
+        let base_port = 3060;
+        let client_listen_uri =
+            FogIngestUri::from_str(&format!("insecure-fog-ingest://127.0.0.1:{}", base_port))
+                .unwrap();
+        let peer_listen_uri =
+            IngestPeerUri::from_str(&format!("insecure-igp://127.0.0.1:{}", base_port + 1))
+                .unwrap();
+        let local_node_id = peer_listen_uri.responder_id().unwrap();
+        let view_listen_uri =
+            FogViewUri::from_str(&format!("insecure-fog-view://127.0.0.1:{}", base_port + 2))
+                .unwrap();
+        let view_responder_id = view_listen_uri.responder_id().unwrap();
+
+        let ingest_admin_uri = AdminUri::from_str("insecure-mca://127.0.0.1:8004/").unwrap();
+        let view_admin_uri = AdminUri::from_str("insecure-mca://127.0.0.1:8005/").unwrap();
+
+        // Generate the blocks to ingest. The origin block is block 0, so we
+        // generate one more block than we ingest.
+        let generated_ledger_path =
+            TempDir::new("generated_ledger").expect("Could not make tempdir for generated ledger");
+        log::info!(
+            logger,
+            "Generating {} blocks of {} txos",
+            num_blocks + 1,
+            opt.txos_per_block
+        );
+        mc_util_generate_sample_ledger::bootstrap_ledger(
+            generated_ledger_path.path(),
+            &[AccountKey::random(rng).default_subaddress()],
+            opt.txos_per_block,
+            opt.num_blocks + 1,
+            // Blocks other than the origin block must have key images.
+            1,
+            opt.seed,
+            0,
+            logger.clone(),
+        );
+        let generated_ledger =
+            LedgerDB::open(generated_ledger_path.path()).expect("Could not open generated ledger");
+        assert_eq!(generated_ledger.num_blocks().unwrap(), num_blocks + 1);
+
+        // Set up the Recovery DB
+        let db_test_context = SqlRecoveryDbTestContext::new(logger.clone());
+        let recovery_db = db_test_context.get_db_instance();
+
+        // Make recovery db available to the servers via env var
+        std::env::set_var("DATABASE_URL", db_test_context.db_url());
+
+        // Set up the Watcher DB
+        let watcher_db_path =
+            TempDir::new("wallet_db").expect("Could not make tempdir for wallet db");
+        WatcherDB::create(watcher_db_path.path()).unwrap();
+        let watcher = WatcherDB::open_rw(
+            watcher_db_path.path(),
+            &["http://bash.org".parse().unwrap()],
+            logger.clone(),
+        )
+        .unwrap();
+
+        // Set up the ledger db that ingest watches, with only the origin block.
+        let ledger_db_path =
+            TempDir::new("ledger_db").expect("Could not make tempdir for ledger db");
+        LedgerDB::create(ledger_db_path.path()).unwrap();
+        let mut ledger_db = LedgerDB::open(ledger_db_path.path()).unwrap();
+        ledger_db
+            .append_block_data(&generated_ledger.get_block_data(0).unwrap())
+            .expect("Could not add origin block");
+
+        // Dir for state file
+        let state_file_dir =
+            TempDir::new("state_file").expect("Could not make tempdir for state file");
+        let mut state_file_path = state_file_dir.path().to_path_buf();
+        state_file_path.push(".mc-fog-ingest-state");
+
+        // Start ingest server.
+        let mut command = std::process::Command::new(ingest_server_binary.to_str().unwrap());
+        command
+            .args(&["--ledger-db", ledger_db_path.path().to_str().unwrap()])
+            .args(&["--watcher-db", watcher_db_path.path().to_str().unwrap()])
+            .args(&["--client-listen-uri", &client_listen_uri.to_string()])
+            .args(&["--peer-listen-uri", &peer_listen_uri.to_string()])
+            .args(&["--ias-spid", &"0".repeat(32)])
+            .args(&["--ias-api-key", &"0".repeat(32)])
+            .args(&["--local-node-id", &local_node_id.to_string()])
+            .args(&["--peers", &peer_listen_uri.to_string()])
+            .args(&["--state-file", state_file_path.to_str().unwrap()])
+            .args(&["--admin-listen-uri", &ingest_admin_uri.to_string()])
+            .args(&["--user-capacity", &opt.user_capacity.to_string()]);
+
+        log::info!(logger, "Spawning ingest server: {:?}", command);
+
+        let mut ingest_server =
+            AutoKillChild(command.spawn().expect("Could not spawn ingest server"));
+
+        // Start view server.
+        let mut command = std::process::Command::new(view_server_binary.to_str().unwrap());
+        command
+            .args(&["--chain-id", "local"])
+            .args(&["--client-responder-id", &view_responder_id.to_string()])
+            .args(&["--client-listen-uri", &view_listen_uri.to_string()])
+            .args(&["--ias-spid", &"0".repeat(32)])
+            .args(&["--ias-api-key", &"0".repeat(32)])
+            .args(&["--admin-listen-uri", &view_admin_uri.to_string()])
+            .args(&["--omap-capacity", &opt.omap_capacity.to_string()]);
+
+        log::info!(logger, "Spawning view server: {:?}", command);
+
+        let mut view_server = AutoKillChild(command.spawn().expect("Could not spawn view server"));
+
+        let admin_client = |admin_uri: &AdminUri| {
+            let ch = ChannelBuilder::new(grpcio_env.clone()).connect_to_uri(admin_uri, &logger);
+            AdminApiClient::new(ch)
+        };
+        let ingest_admin = admin_client(&ingest_admin_uri);
+        let view_admin = admin_client(&view_admin_uri);
+        wait_for_admin_api("ingest server", &ingest_admin, &mut ingest_server, &logger);
+        wait_for_admin_api("view server", &view_admin, &mut view_server, &logger);
+
+        // Tell ingest server to activate
+        {
+            let ingest_client = FogIngestGrpcClient::new(
+                client_listen_uri,
+                Duration::from_secs(30),
+                grpcio_env.clone(),
+                logger.clone(),
+            );
+            ingest_client
+                .activate()
+                .expect("Could not activate ingest server");
+            sleep(Duration::from_millis(10));
+            ingest_server.assert_not_stopped();
+        }
+
+        let mut start_metrics =
+            (warmup_blocks == 0).then(|| MetricsSnapshot::get(&ingest_admin, &view_admin));
+
+        // Append the generated blocks to the ledger on another thread, so
+        // that we can watch them flow through ingest as they are appended.
+        log::info!(logger, "Appending {} blocks", num_blocks);
+        let block_interval = Duration::from_millis(opt.block_interval_ms);
+        let (appended_tx, appended_rx) = mpsc::channel();
+        let appender = thread::spawn(move || {
+            for index in 1..=num_blocks {
+                sleep(block_interval);
+                let block_data = generated_ledger
+                    .get_block_data(index)
+                    .expect("Could not get generated block");
+
+                let start = Instant::now();
+                ledger_db
+                    .append_block_data(&block_data)
+                    .expect("Adding block failed");
+                let appended_at = Instant::now();
+                appended_tx
+                    .send(AppendedBlock {
+                        index,
+                        append_time: appended_at - start,
+                        appended_at,
+                    })
+                    .expect("Could not send appended block");
+
+                // Add the timestamp information to watcher for this block index
+                let block = block_data.block();
+                let mut block_signature = BlockSignature::from_block_and_keypair(block, &signer)
+                    .expect("Could not create block signature from keypair");
+                block_signature.set_signed_at(block.index);
+                for src_url in watcher.get_config_urls().unwrap().iter() {
+                    watcher
+                        .add_block_signature(
+                            src_url,
+                            block.index,
+                            block_signature.clone(),
+                            format!("00/{}", block.index),
+                        )
+                        .expect("Could not add block signature");
+                }
+            }
+        });
+
+        // Poll for each block to show up in the recovery db and the view
+        // server. Block 0 is the origin block, which is not ingested.
+        let mut block_times = vec![BlockTimes::default(); opt.num_blocks + 1];
+        let mut next_recovery_db_block = 1u64;
+        let mut next_view_block = 1u64;
+        let mut last_progress_at = Instant::now();
+        while next_view_block <= num_blocks {
+            sleep(POLL_INTERVAL);
+            ingest_server.assert_not_stopped();
+            view_server.assert_not_stopped();
+
+            record_appended_blocks(&appended_rx, &mut block_times);
+
+            let now = Instant::now();
+            let highest_known_block_index = recovery_db
+                .get_highest_known_block_index()
+                .expect("Getting highest known block index failed");
+            if let Some(highest_known_block_index) = highest_known_block_index {
+                while next_recovery_db_block <= highest_known_block_index {
+                    block_times[next_recovery_db_block as usize].recovery_db_at = Some(now);
+                    next_recovery_db_block += 1;
+                    last_progress_at = now;
+                }
+            }
+
+            let view_metrics = view_admin
+                .get_prometheus_metrics(&Empty::default())
+                .expect("Could not get view server metrics")
+                .metrics;
+            let highest_processed_block_count = get_op_metric(
+                &view_metrics,
+                "fog_view_gauge",
+                "highest_processed_block_count",
+            )
+            .unwrap_or_default() as u64;
+            while next_view_block < highest_processed_block_count.min(num_blocks + 1) {
+                block_times[next_view_block as usize].view_at = Some(now);
+                next_view_block += 1;
+                last_progress_at = now;
+
+                if next_view_block == warmup_blocks + 1 {
+                    start_metrics = Some(MetricsSnapshot::get(&ingest_admin, &view_admin));
+                }
+            }
+
+            if now - last_progress_at > PROGRESS_TIMEOUT {
+                panic!(
+                    "No progress in {:?}: {} blocks in recovery db, {} blocks in view server",
+                    PROGRESS_TIMEOUT,
+                    next_recovery_db_block - 1,
+                    next_view_block - 1
+                );
+            }
+        }
+        let end_metrics = MetricsSnapshot::get(&ingest_admin, &view_admin);
+        let start_metrics = start_metrics.expect("Missing metrics from the end of warmup");
+
+        appender.join().expect("Appender thread panicked");
+        record_appended_blocks(&appended_rx, &mut block_times);
+
+        // Compute results over the blocks after the warmup.
+        let measured_blocks = &block_times[opt.warmup_blocks + 1..];
+        let latencies = |stage_at: fn(&BlockTimes) -> Option<Instant>| {
+            measured_blocks
+                .iter()
+                .map(|times| {
+                    stage_at(times)
+                        .expect("Missing stage time")
+                        .saturating_duration_since(times.appended_at.expect("Missing append time"))
+                })
+                .collect::<Vec<_>>()
+        };
+        let append_times = measured_blocks
+            .iter()
+            .map(|times| times.append_time.expect("Missing append time"))
+            .collect::<Vec<_>>();
+
+        // Throughput is measured from when the last warmup block was available
+        // in the view server, or when the first block was appended if there
+        // is no warmup.
+        let measure_start = if warmup_blocks == 0 {
+            block_times[1].appended_at
+        } else {
+            block_times[opt.warmup_blocks].view_at
+        }
+        .expect("Missing start time");
+        let measure_end = block_times[opt.num_blocks]
+            .view_at
+            .expect("Missing end time");
+        let blocks_per_sec =
+            measured_blocks.len() as f64 / (measure_end - measure_start).as_secs_f64();
+
+        let ingest_stage_time = |op| {
+            average_stage_time(
+                &start_metrics.ingest,
+                &end_metrics.ingest,
+                "fog_ingest",
+                op,
+                "blocks_processed_count",
+            )
+        };
+
+        BenchResult {
+            num_blocks: measured_blocks.len(),
+            txos_per_block: opt.txos_per_block,
+            blocks_per_sec,
+            ledger_append: compute_basic_stats(&append_times, &logger),
+            ledger_to_recovery_db: compute_basic_stats(
+                &latencies(|times| times.recovery_db_at),
+                &logger,
+            ),
+            ledger_to_view: compute_basic_stats(&latencies(|times| times.view_at), &logger),
+            ingest_txs: ingest_stage_time("ingest_txs_time"),
+            add_block_data: ingest_stage_time("db_add_block_data_time"),
+            process_next_block: ingest_stage_time("process_next_block_time"),
+            view_add_records: average_stage_time(
+                &start_metrics.view,
+                &end_metrics.view,
+                "fog_view",
+                "enclave_add_records_time",
+                "blocks_added_count",
+            ),
+        }
+    };
+    // grpcio detaches all its threads and does not join them, so we sleep
+    // after the grpcio env and all related objects have been destroyed, see
+    // the ingest load test.
+    sleep(Duration::from_millis(1000));
+
+    result
+}
+
+fn main() {
+    mc_common::setup_panic_handler();
+
+    let opt = BenchOptions::parse();
+    if opt.warmup_blocks >= opt.num_blocks {
+        BenchOptions::command()
+            .error(
+                ErrorKind::ValueValidation,
+                "--warmup-blocks must be less than --num-blocks",
+            )
+            .exit();
+    }
+
+    let logger = mc_common::logger::create_root_logger();
+
+    let result = bench(&opt, logger.clone());
+
+    println!("Ingest throughput results\n================");
+    println!("{}", result);
+
+    if let Some(path) = opt.output_json.as_ref() {
+        let json =
+            serde_json::to_string_pretty(&result.to_json()).expect("Could not serialize results");
+        fs::write(path, json).expect("Could not write results");
+        log::info!(logger, "Wrote results to {:?}", path);
+    }
+}
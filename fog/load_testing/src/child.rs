@@ -0,0 +1,79 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! Helpers for managing the server processes under test.
+
+use grpcio::Error as GrpcioError;
+use mc_common::logger::{log, Logger};
+use mc_util_grpc::{admin::GetInfoResponse, admin_grpc::AdminApiClient, Empty};
+use retry::{delay, retry, OperationResult};
+
+/// RAII gaurd for a child process, which kills the child on drop.
+pub struct AutoKillChild(pub std::process::Child);
+
+impl AutoKillChild {
+    /// Assert that the child process is still alive.
+    ///
+    /// Panics:
+    ///  * If the process stopped unexpectedly.
+    ///  * If there is an error while getting the process' status.
+    pub fn assert_not_stopped(&mut self) {
+        match self.0.try_wait() {
+            Ok(Some(stat)) => {
+                panic!("child stopped unexpectedly: status: {}", stat)
+            }
+            Ok(None) => {}
+            Err(err) => {
+                panic!("error getting child status: {}", err)
+            }
+        }
+    }
+}
+
+impl Drop for AutoKillChild {
+    fn drop(&mut self) {
+        if let Err(err) = self.0.kill() {
+            // Invalid input means the process is already dead, and we don't have to kill
+            // it. Anything else means that killing it failed somehow
+            if err.kind() != std::io::ErrorKind::InvalidInput {
+                panic!("Could not send SIGKILL to child: {}", err)
+            }
+        }
+
+        self.0.wait().expect("Could not reap child");
+    }
+}
+
+/// Wait for a server's admin API to become reachable, and log its build info
+/// and config.
+///
+/// Panics if the server stops, or if the admin API returns an error other
+/// than an RPC failure.
+pub fn wait_for_admin_api(
+    server_name: &str,
+    admin_client: &AdminApiClient,
+    server: &mut AutoKillChild,
+    logger: &Logger,
+) -> GetInfoResponse {
+    let info = retry(delay::Fixed::from_millis(5000).map(delay::jitter), || {
+        server.assert_not_stopped();
+
+        match admin_client.get_info(&Empty::default()) {
+            Ok(info) => OperationResult::Ok(info),
+            Err(GrpcioError::RpcFailure(err)) => {
+                log::info!(logger, "Waiting for {} to become available", server_name);
+                OperationResult::Retry(GrpcioError::RpcFailure(err))
+            }
+            Err(err) => OperationResult::Err(err),
+        }
+    })
+    .unwrap_or_else(|err| panic!("Could not connect to {}: {}", server_name, err));
+
+    log::info!(
+        logger,
+        "Connected to {}:\nbuild_info: {}\nconfig_json: {}",
+        server_name,
+        info.build_info_json,
+        info.config_json
+    );
+    info
+}
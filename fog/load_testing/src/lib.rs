@@ -4,6 +4,16 @@
 
 #![deny(missing_docs)]
 
+mod child;
+mod metrics;
+mod stats;
+
+pub use crate::{
+    child::{wait_for_admin_api, AutoKillChild},
+    metrics::get_op_metric,
+    stats::{compute_basic_stats, BasicTimingStats},
+};
+
 use std::{
     env,
     path::{Path, PathBuf},
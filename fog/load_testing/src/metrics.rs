@@ -0,0 +1,52 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! Reading the metrics that servers export over their admin API.
+
+/// Find the value of a sample with the given `op` label, in metrics in the
+/// Prometheus text format, as returned by the admin API's
+/// `GetPrometheusMetrics`.
+///
+/// Servers register their metrics with `OpMetrics`, so e.g. the sum of the
+/// `ingest_txs_time` histogram of `fog_ingest` is the `fog_ingest_duration_sum`
+/// metric with `op="ingest_txs_time"`.
+pub fn get_op_metric(metrics: &str, metric: &str, op: &str) -> Option<f64> {
+    let sample = format!("{}{{op=\"{}\"}}", metric, op);
+    metrics
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| line.rsplit_once(' '))
+        .find(|(name, _)| *name == sample)
+        .and_then(|(_, value)| value.parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_op_metric_finds_samples() {
+        let metrics = r#"# HELP fog_ingest Counters for fog_ingest
+# TYPE fog_ingest counter
+fog_ingest{op="blocks_processed_count"} 12
+# HELP fog_ingest_duration Histogram values for fog_ingest
+# TYPE fog_ingest_duration histogram
+fog_ingest_duration_bucket{op="ingest_txs_time",le="0.005"} 3
+fog_ingest_duration_sum{op="ingest_txs_time"} 0.125
+fog_ingest_duration_count{op="ingest_txs_time"} 12
+"#;
+
+        assert_eq!(
+            get_op_metric(metrics, "fog_ingest", "blocks_processed_count"),
+            Some(12.0)
+        );
+        assert_eq!(
+            get_op_metric(metrics, "fog_ingest_duration_sum", "ingest_txs_time"),
+            Some(0.125)
+        );
+        assert_eq!(
+            get_op_metric(metrics, "fog_ingest_duration_sum", "db_add_block_data_time"),
+            None
+        );
+        assert_eq!(get_op_metric(metrics, "fog_view", "ingest_txs_time"), None);
+    }
+}
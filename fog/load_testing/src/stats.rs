@@ -0,0 +1,55 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! Basic statistics over timing measurements.
+
+use mc_common::logger::{log, Logger};
+use std::time::Duration;
+
+/// The mean and standard deviation of some timings.
+#[derive(Default, Clone)]
+pub struct BasicTimingStats {
+    /// The number of timings.
+    pub num_samples: usize,
+    /// The mean of the timings.
+    pub mean: Duration,
+    /// The standard deviation of the timings.
+    pub std_dev: Duration,
+}
+
+impl core::fmt::Display for BasicTimingStats {
+    fn fmt(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(
+            formatter,
+            "num samples: {}, avg: {} ms +/- {} ms",
+            self.num_samples,
+            self.mean.as_secs_f64() * 1000f64,
+            self.std_dev.as_secs_f64() * 1000f64
+        )
+    }
+}
+
+/// Take a series of durations, and compute their mean and standard deviation
+pub fn compute_basic_stats(data: &[Duration], logger: &Logger) -> BasicTimingStats {
+    let num_samples = data.len();
+
+    let mean = data.iter().fold(0f64, |l, r| l + r.as_secs_f64()) / num_samples as f64;
+    let variance = data
+        .iter()
+        .fold(0f64, |l, r| l + (r.as_secs_f64() - mean).powi(2))
+        / num_samples as f64;
+    let std_dev = variance.sqrt();
+
+    log::debug!(
+        logger,
+        "mean sec = {}, variance sec = {}, std_dev sec = {}",
+        mean,
+        variance,
+        std_dev
+    );
+
+    BasicTimingStats {
+        num_samples,
+        mean: Duration::from_secs_f64(mean),
+        std_dev: Duration::from_secs_f64(std_dev),
+    }
+}
@@ -0,0 +1,396 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! Declarative alert rules that Fog Overseer evaluates on every polling round,
+//! and the sinks that alerts are sent to.
+//!
+//! Rules are loaded from a JSON file, e.g.:
+//!
+//! ```json
+//! {
+//!   "rules": [
+//!     { "name": "no-active-ingest", "condition": { "no_active_node": { "for_seconds": 60 } } },
+//!     { "name": "missed-blocks", "condition": "missed_block_ranges_non_empty" }
+//!   ],
+//!   "sinks": ["log", { "webhook": { "url": "https://hooks.example.com/overseer" } }]
+//! }
+//! ```
+//!
+//! A notification is sent when a rule starts firing, and again when it
+//! resolves.
+
+use mc_common::logger::{log, Logger};
+use mc_fog_types::common::BlockRange;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashSet,
+    fs, io,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+/// The alert rules and the sinks their notifications are sent to.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct AlertConfig {
+    /// The rules to evaluate.
+    #[serde(default)]
+    pub rules: Vec<AlertRule>,
+
+    /// Where to send notifications. Every notification goes to every sink.
+    #[serde(default)]
+    pub sinks: Vec<AlertSink>,
+}
+
+impl AlertConfig {
+    /// Load an alert config from a JSON file.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        serde_json::from_str(&contents).map_err(io::Error::from)
+    }
+
+    /// Whether any rule needs the missed block ranges from the RecoveryDb.
+    pub fn needs_missed_block_ranges(&self) -> bool {
+        self.rules
+            .iter()
+            .any(|rule| rule.condition == AlertCondition::MissedBlockRangesNonEmpty)
+    }
+}
+
+/// A named condition to alert on.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct AlertRule {
+    /// The name of the rule, included in its notifications.
+    pub name: String,
+
+    /// The condition under which the rule fires.
+    pub condition: AlertCondition,
+}
+
+/// The conditions that an alert rule can fire on.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertCondition {
+    /// None of the responding Fog Ingest nodes has been active for at least
+    /// this long.
+    NoActiveNode {
+        /// How long there must be no active node before the rule fires.
+        for_seconds: u64,
+    },
+
+    /// More than one Fog Ingest node is active.
+    MultipleActiveNodes,
+
+    /// The RecoveryDb contains missed block ranges.
+    MissedBlockRangesNonEmpty,
+}
+
+/// A destination for alert notifications.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertSink {
+    /// Log firing alerts as errors and resolved alerts as info.
+    Log,
+
+    /// POST each notification as JSON to a URL.
+    Webhook {
+        /// The URL to POST to.
+        url: String,
+    },
+}
+
+/// Whether a notification is about a rule starting or stopping to fire.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertStatus {
+    /// The rule's condition started to hold.
+    Firing,
+
+    /// The rule's condition no longer holds.
+    Resolved,
+}
+
+/// A notification about an alert rule, as sent to the sinks.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct AlertNotification {
+    /// The name of the rule.
+    pub rule: String,
+
+    /// Whether the rule started firing or resolved.
+    pub status: AlertStatus,
+
+    /// A description of the condition.
+    pub message: String,
+}
+
+/// What Overseer saw in a polling round, as input to the alert rules.
+pub struct ClusterObservation {
+    /// The number of responding nodes that are active.
+    pub active_node_count: usize,
+
+    /// The number of nodes that did not respond.
+    pub unresponsive_node_count: usize,
+
+    /// The missed block ranges in the RecoveryDb, if they were retrieved.
+    pub missed_block_ranges: Option<Vec<BlockRange>>,
+}
+
+/// Evaluates alert rules against observations, and tracks which are firing.
+pub struct AlertManager {
+    config: AlertConfig,
+
+    /// Since when no responding node has been active.
+    no_active_node_since: Option<Instant>,
+
+    /// The names of the rules that are currently firing.
+    firing_rules: HashSet<String>,
+}
+
+impl AlertManager {
+    /// Create an alert manager for the given config.
+    pub fn new(config: AlertConfig) -> Self {
+        Self {
+            config,
+            no_active_node_since: None,
+            firing_rules: HashSet::new(),
+        }
+    }
+
+    /// Whether any rule needs the missed block ranges from the RecoveryDb.
+    pub fn needs_missed_block_ranges(&self) -> bool {
+        self.config.needs_missed_block_ranges()
+    }
+
+    /// Evaluate every rule against an observation made at `now`, and return
+    /// the notifications for rules that started firing or resolved.
+    pub fn evaluate(
+        &mut self,
+        observation: &ClusterObservation,
+        now: Instant,
+    ) -> Vec<AlertNotification> {
+        if observation.active_node_count == 0 {
+            self.no_active_node_since.get_or_insert(now);
+        } else {
+            self.no_active_node_since = None;
+        }
+
+        let mut notifications = Vec::new();
+        for rule in &self.config.rules {
+            let firing_message = match &rule.condition {
+                AlertCondition::NoActiveNode { for_seconds } => {
+                    self.no_active_node_since.and_then(|since| {
+                        let duration = now.saturating_duration_since(since);
+                        (duration >= Duration::from_secs(*for_seconds)).then(|| {
+                            format!(
+                                "No Fog Ingest node has been active for {} seconds ({} nodes unresponsive)",
+                                duration.as_secs(),
+                                observation.unresponsive_node_count
+                            )
+                        })
+                    })
+                }
+                AlertCondition::MultipleActiveNodes => (observation.active_node_count > 1)
+                    .then(|| {
+                        format!(
+                            "{} Fog Ingest nodes are active",
+                            observation.active_node_count
+                        )
+                    }),
+                AlertCondition::MissedBlockRangesNonEmpty => {
+                    match observation.missed_block_ranges.as_ref() {
+                        Some(ranges) if !ranges.is_empty() => Some(format!(
+                            "The RecoveryDb has {} missed block ranges: {:?}",
+                            ranges.len(),
+                            ranges
+                        )),
+                        Some(_) => None,
+                        // If the ranges couldn't be retrieved, keep the
+                        // previous state.
+                        None => {
+                            if self.firing_rules.contains(&rule.name) {
+                                continue;
+                            }
+                            None
+                        }
+                    }
+                }
+            };
+
+            match firing_message {
+                Some(message) => {
+                    if self.firing_rules.insert(rule.name.clone()) {
+                        notifications.push(AlertNotification {
+                            rule: rule.name.clone(),
+                            status: AlertStatus::Firing,
+                            message,
+                        });
+                    }
+                }
+                None => {
+                    if self.firing_rules.remove(&rule.name) {
+                        notifications.push(AlertNotification {
+                            rule: rule.name.clone(),
+                            status: AlertStatus::Resolved,
+                            message: format!("Alert '{}' resolved", rule.name),
+                        });
+                    }
+                }
+            }
+        }
+
+        notifications
+    }
+
+    /// Send notifications to every configured sink.
+    pub fn notify(&self, notifications: &[AlertNotification], logger: &Logger) {
+        for notification in notifications {
+            for sink in &self.config.sinks {
+                match sink {
+                    AlertSink::Log => match notification.status {
+                        AlertStatus::Firing => log::error!(
+                            logger,
+                            "Alert '{}' firing: {}",
+                            notification.rule,
+                            notification.message
+                        ),
+                        AlertStatus::Resolved => log::info!(logger, "{}", notification.message),
+                    },
+                    AlertSink::Webhook { url } => {
+                        if let Err(err) = post_to_webhook(url, notification) {
+                            log::warn!(
+                                logger,
+                                "Could not send alert '{}' to webhook {}: {}",
+                                notification.rule,
+                                url,
+                                err
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// How long to wait for a webhook to accept a notification.
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+fn post_to_webhook(url: &str, notification: &AlertNotification) -> reqwest::Result<()> {
+    reqwest::blocking::Client::builder()
+        .timeout(WEBHOOK_TIMEOUT)
+        .build()?
+        .post(url)
+        .json(notification)
+        .send()?
+        .error_for_status()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn observation(
+        active_node_count: usize,
+        missed_block_ranges: Option<Vec<BlockRange>>,
+    ) -> ClusterObservation {
+        ClusterObservation {
+            active_node_count,
+            unresponsive_node_count: 0,
+            missed_block_ranges,
+        }
+    }
+
+    #[test]
+    fn parse_alert_config() {
+        let config: AlertConfig = serde_json::from_str(
+            r#"{
+                "rules": [
+                    { "name": "no-active-ingest", "condition": { "no_active_node": { "for_seconds": 60 } } },
+                    { "name": "multiple-active", "condition": "multiple_active_nodes" },
+                    { "name": "missed-blocks", "condition": "missed_block_ranges_non_empty" }
+                ],
+                "sinks": ["log", { "webhook": { "url": "https://hooks.example.com/overseer" } }]
+            }"#,
+        )
+        .expect("Could not parse alert config");
+
+        assert_eq!(config.rules.len(), 3);
+        assert_eq!(
+            config.rules[0].condition,
+            AlertCondition::NoActiveNode { for_seconds: 60 }
+        );
+        assert!(config.needs_missed_block_ranges());
+        assert_eq!(
+            config.sinks,
+            vec![
+                AlertSink::Log,
+                AlertSink::Webhook {
+                    url: "https://hooks.example.com/overseer".to_string()
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn no_active_node_fires_after_duration_and_resolves() {
+        let mut manager = AlertManager::new(AlertConfig {
+            rules: vec![AlertRule {
+                name: "no-active-ingest".to_string(),
+                condition: AlertCondition::NoActiveNode { for_seconds: 60 },
+            }],
+            sinks: vec![],
+        });
+        let start = Instant::now();
+
+        assert!(manager.evaluate(&observation(1, None), start).is_empty());
+        assert!(manager
+            .evaluate(&observation(0, None), start + Duration::from_secs(5))
+            .is_empty());
+        assert!(manager
+            .evaluate(&observation(0, None), start + Duration::from_secs(30))
+            .is_empty());
+
+        let notifications =
+            manager.evaluate(&observation(0, None), start + Duration::from_secs(65));
+        assert_eq!(notifications.len(), 1);
+        assert_eq!(notifications[0].status, AlertStatus::Firing);
+
+        // A firing rule only notifies once.
+        assert!(manager
+            .evaluate(&observation(0, None), start + Duration::from_secs(70))
+            .is_empty());
+
+        let notifications =
+            manager.evaluate(&observation(1, None), start + Duration::from_secs(75));
+        assert_eq!(notifications.len(), 1);
+        assert_eq!(notifications[0].status, AlertStatus::Resolved);
+    }
+
+    #[test]
+    fn missed_block_ranges_keeps_state_when_unknown() {
+        let mut manager = AlertManager::new(AlertConfig {
+            rules: vec![AlertRule {
+                name: "missed-blocks".to_string(),
+                condition: AlertCondition::MissedBlockRangesNonEmpty,
+            }],
+            sinks: vec![],
+        });
+        let now = Instant::now();
+
+        assert!(manager
+            .evaluate(&observation(1, Some(vec![])), now)
+            .is_empty());
+
+        let ranges = vec![BlockRange::new(10, 20)];
+        let notifications = manager.evaluate(&observation(1, Some(ranges)), now);
+        assert_eq!(notifications.len(), 1);
+        assert_eq!(notifications[0].status, AlertStatus::Firing);
+
+        // A failed lookup neither resolves nor re-fires the alert.
+        assert!(manager.evaluate(&observation(1, None), now).is_empty());
+        assert!(manager
+            .evaluate(&observation(1, Some(vec![BlockRange::new(10, 20)])), now)
+            .is_empty());
+    }
+}
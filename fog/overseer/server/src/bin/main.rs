@@ -8,7 +8,9 @@ use mc_common::{
     logger::{log, o},
     sentry,
 };
-use mc_fog_overseer_server::{config::OverseerConfig, server, service::OverseerService};
+use mc_fog_overseer_server::{
+    alerts::AlertConfig, config::OverseerConfig, server, service::OverseerService,
+};
 use mc_fog_sql_recovery_db::SqlRecoveryDb;
 use mc_util_cli::ParserWithBuildInfo;
 
@@ -35,8 +37,24 @@ async fn main() -> Result<(), rocket::Error> {
         )
     });
 
-    let mut overseer_service =
-        OverseerService::new(config.ingest_cluster_uris, recovery_db, logger.clone());
+    let alert_config = config
+        .alert_config
+        .as_ref()
+        .map(|path| {
+            AlertConfig::load(path).unwrap_or_else(|err| {
+                panic!("Could not load alert config '{}': {}", path.display(), err)
+            })
+        })
+        .unwrap_or_default();
+
+    let mut overseer_service = OverseerService::new(
+        config.ingest_cluster_uris,
+        recovery_db,
+        config.key_rotation_interval,
+        alert_config,
+        config.dry_run,
+        logger.clone(),
+    );
     overseer_service
         .start()
         .expect("OverseerService failed to start");
@@ -6,7 +6,9 @@
 use clap::Parser;
 use mc_fog_sql_recovery_db::SqlRecoveryDbConnectionConfig;
 use mc_fog_uri::FogIngestUri;
+use mc_util_parse::parse_duration_in_seconds;
 use serde::Serialize;
+use std::{path::PathBuf, time::Duration};
 
 /// Parser configuration options for an Overseer Server
 #[derive(Clone, Serialize, Parser)]
@@ -29,6 +31,25 @@ pub struct OverseerConfig {
     #[clap(long, use_value_delimiter = true, env = "MC_INGEST_CLUSTER_URIS")]
     pub ingest_cluster_uris: Vec<FogIngestUri>,
 
+    /// If set, the number of seconds an ingress key stays active before
+    /// Overseer rotates it. The old key is retired, and once it reaches its
+    /// pubkey expiry, an idle node is activated with a new key.
+    ///
+    /// If unset, keys are only rotated manually.
+    #[clap(long, value_parser = parse_duration_in_seconds, env = "MC_KEY_ROTATION_INTERVAL")]
+    pub key_rotation_interval: Option<Duration>,
+
+    /// Path to a JSON file of alert rules and the sinks to notify when they
+    /// fire. See the README for the format.
+    #[clap(long, env = "MC_OVERSEER_ALERT_CONFIG")]
+    pub alert_config: Option<PathBuf>,
+
+    /// If set, Overseer only logs the changes it would make to the Fog Ingest
+    /// cluster and the RecoveryDb, e.g. during failover, key rotation or lost
+    /// key remediation, without making them. Alerts are still sent.
+    #[clap(long, env = "MC_OVERSEER_DRY_RUN")]
+    pub dry_run: bool,
+
     /// Postgres config
     #[clap(flatten)]
     pub postgres_config: SqlRecoveryDbConnectionConfig,
@@ -55,5 +76,25 @@ mod tests {
 
         assert_eq!(config.ingest_cluster_uris[0].port(), 3226);
         assert_eq!(config.ingest_cluster_uris[1].port(), 3227);
+        assert_eq!(config.key_rotation_interval, None);
+        assert_eq!(config.alert_config, None);
+        assert!(!config.dry_run);
+    }
+
+    #[test]
+    fn key_rotation_interval_example() {
+        let config = OverseerConfig::try_parse_from(&[
+            "/usr/bin/fog_overseer_server",
+            "--ingest-cluster-uris",
+            "insecure-fog-ingest://0.0.0.0:3226/",
+            "--key-rotation-interval",
+            "86400",
+        ])
+        .expect("Could not parse command line arguments.");
+
+        assert_eq!(
+            config.key_rotation_interval,
+            Some(Duration::from_secs(86400))
+        );
     }
 }
@@ -25,6 +25,18 @@ pub enum OverseerError {
     /// Activating an idle node failed: {0}
     ActivateNode(String),
 
+    /// Retiring the active node's ingress key failed: {0}
+    RetireKey(String),
+
+    /// Unretiring a node's ingress key failed: {0}
+    UnretireKey(String),
+
+    /// Ingress key rotation handoff could not be verified: {0}
+    KeyRotationHandoff(String),
+
+    /// Lost key remediation failed: {0}
+    LostKeyRemediation(String),
+
     /// Multiple inactive outstanding keys found: {0}
     MultipleInactiveOutstandingKeys(String),
 
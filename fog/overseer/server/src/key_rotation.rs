@@ -0,0 +1,382 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! Decides when the Fog Ingest cluster's ingress key is due for rotation.
+//!
+//! A new key is brought up before the old key is retired, so that a fog report
+//! is published throughout the rotation:
+//!   1. Once the active key has been in use for the configured interval, the
+//!      worker sets new keys on an idle node and activates it. Activation
+//!      backs the new key up to the node's peers via `SetIngressPrivateKey`.
+//!      Ingest nodes refuse to activate while one of their peers is active, so
+//!      the idle node must not be a peer of the active node, e.g. it belongs to
+//!      the other half of a blue/green deployment.
+//!   2. Once the new key is active, the worker retires the old key. Its node
+//!      keeps scanning until it passes the key's pubkey expiry, and then goes
+//!      idle.
+//!   3. When the new key is the only active key left, the worker checks the
+//!      ingress key records to confirm the retired key scanned every block it
+//!      promised to.
+//!
+//! If the new key stops being active before the old key finished scanning,
+//! the old key is unretired so that it keeps publishing reports, as in a
+//! failed blue/green deployment.
+
+use mc_crypto_keys::CompressedRistrettoPublic;
+use std::time::{Duration, Instant};
+
+/// What the worker should do about key rotation after a polling round.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum KeyRotationAction {
+    /// Nothing to do.
+    None,
+
+    /// The active key is due for rotation. A new key should be set on an
+    /// idle node and activated.
+    ActivateNewKey(CompressedRistrettoPublic),
+
+    /// A new key is active alongside the key it replaces, which should now be
+    /// retired.
+    Retire(CompressedRistrettoPublic),
+
+    /// The new key stopped being active while the key it replaces was
+    /// retiring. The old key should be unretired.
+    Unretire(CompressedRistrettoPublic),
+
+    /// A key that we retired has been replaced by a new active key. The
+    /// handoff between the two should be verified.
+    VerifyHandoff {
+        /// The key that was retired.
+        retired_key: CompressedRistrettoPublic,
+        /// The key that replaced it.
+        new_key: CompressedRistrettoPublic,
+    },
+}
+
+/// An active ingress key that the schedule is tracking.
+#[derive(Clone, Copy)]
+struct TrackedKey {
+    key: CompressedRistrettoPublic,
+
+    /// When this key was first seen on an active node.
+    active_since: Instant,
+}
+
+/// A key that is being rotated out.
+struct ReplacedKey {
+    tracked: TrackedKey,
+
+    /// Whether we have retired this key.
+    retired: bool,
+}
+
+/// Tracks how long the current ingress key has been active, and the progress
+/// of its rotation.
+///
+/// Key ages are measured from when this Overseer first saw the key on an
+/// active node, so restarting Overseer restarts the clock.
+pub struct KeyRotationSchedule {
+    /// How long a key stays active before it is rotated.
+    interval: Duration,
+
+    /// The newest active key, if any has been seen.
+    current: Option<TrackedKey>,
+
+    /// The key being rotated out, once a new key was activated to replace it.
+    replaced: Option<ReplacedKey>,
+}
+
+impl KeyRotationSchedule {
+    /// Create a schedule that rotates keys after `interval`.
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            current: None,
+            replaced: None,
+        }
+    }
+
+    /// Whether `active_keys` are the two keys we expect to be active while a
+    /// key is being rotated out.
+    pub fn is_rotating(&self, active_keys: &[CompressedRistrettoPublic]) -> bool {
+        match (self.replaced.as_ref(), active_keys) {
+            (Some(replaced), [a, b]) => {
+                a != b && (a == &replaced.tracked.key || b == &replaced.tracked.key)
+            }
+            _ => false,
+        }
+    }
+
+    /// Record that `active_keys` are the ingress keys of the active nodes at
+    /// `now`, and decide what to do next.
+    pub fn observe_active_keys(
+        &mut self,
+        active_keys: &[CompressedRistrettoPublic],
+        now: Instant,
+    ) -> KeyRotationAction {
+        if self.is_rotating(active_keys) {
+            let replaced = self.replaced.as_ref().expect("is_rotating checks this");
+            let new_key = if active_keys[0] == replaced.tracked.key {
+                active_keys[1]
+            } else {
+                active_keys[0]
+            };
+            if self.current.map(|current| current.key) != Some(new_key) {
+                self.current = Some(TrackedKey {
+                    key: new_key,
+                    active_since: now,
+                });
+            }
+            return if replaced.retired {
+                KeyRotationAction::None
+            } else {
+                KeyRotationAction::Retire(replaced.tracked.key)
+            };
+        }
+
+        let key = match active_keys {
+            [key] => *key,
+            _ => return KeyRotationAction::None,
+        };
+
+        if let Some(replaced) = self.replaced.take() {
+            if replaced.tracked.key == key {
+                // The new key is gone, and the old key is the only active key left.
+                if replaced.retired {
+                    self.replaced = Some(replaced);
+                    return KeyRotationAction::Unretire(key);
+                }
+                self.current = Some(replaced.tracked);
+                return KeyRotationAction::None;
+            }
+
+            // The old key is done.
+            if self.current.map(|current| current.key) != Some(key) {
+                self.current = Some(TrackedKey {
+                    key,
+                    active_since: now,
+                });
+            }
+            return if replaced.retired {
+                KeyRotationAction::VerifyHandoff {
+                    retired_key: replaced.tracked.key,
+                    new_key: key,
+                }
+            } else {
+                KeyRotationAction::None
+            };
+        }
+
+        match self.current {
+            Some(tracked) if tracked.key == key => {
+                if now.saturating_duration_since(tracked.active_since) >= self.interval {
+                    KeyRotationAction::ActivateNewKey(key)
+                } else {
+                    KeyRotationAction::None
+                }
+            }
+            _ => {
+                self.current = Some(TrackedKey {
+                    key,
+                    active_since: now,
+                });
+                KeyRotationAction::None
+            }
+        }
+    }
+
+    /// Record that a new key was activated to replace `key`.
+    pub fn mark_new_key_activated(&mut self, key: &CompressedRistrettoPublic) {
+        if let Some(tracked) = self.current {
+            if &tracked.key == key && self.replaced.is_none() {
+                self.replaced = Some(ReplacedKey {
+                    tracked,
+                    retired: false,
+                });
+            }
+        }
+    }
+
+    /// Record that `key` was successfully retired.
+    pub fn mark_retired(&mut self, key: &CompressedRistrettoPublic) {
+        if let Some(replaced) = self.replaced.as_mut() {
+            if &replaced.tracked.key == key {
+                replaced.retired = true;
+            }
+        }
+    }
+
+    /// Record that `key` was successfully unretired, and is the active key
+    /// again.
+    pub fn mark_unretired(&mut self, key: &CompressedRistrettoPublic) {
+        if let Some(replaced) = self.replaced.take() {
+            if &replaced.tracked.key == key {
+                self.current = Some(replaced.tracked);
+            } else {
+                self.replaced = Some(replaced);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotates_after_interval_and_verifies_handoff() {
+        let interval = Duration::from_secs(60);
+        let mut schedule = KeyRotationSchedule::new(interval);
+        let old_key = CompressedRistrettoPublic::from(&[1u8; 32]);
+        let new_key = CompressedRistrettoPublic::from(&[2u8; 32]);
+        let start = Instant::now();
+
+        assert_eq!(
+            schedule.observe_active_keys(&[old_key], start),
+            KeyRotationAction::None
+        );
+        assert_eq!(
+            schedule.observe_active_keys(&[old_key], start + interval / 2),
+            KeyRotationAction::None
+        );
+        assert_eq!(
+            schedule.observe_active_keys(&[old_key], start + interval),
+            KeyRotationAction::ActivateNewKey(old_key)
+        );
+
+        // Until the new key is activated, we keep asking for it.
+        assert_eq!(
+            schedule.observe_active_keys(&[old_key], start + interval * 2),
+            KeyRotationAction::ActivateNewKey(old_key)
+        );
+        schedule.mark_new_key_activated(&old_key);
+
+        // Both keys are active, so the old key can be retired.
+        assert!(schedule.is_rotating(&[old_key, new_key]));
+        assert!(!schedule.is_rotating(&[old_key]));
+        assert_eq!(
+            schedule.observe_active_keys(&[old_key, new_key], start + interval * 2),
+            KeyRotationAction::Retire(old_key)
+        );
+        assert_eq!(
+            schedule.observe_active_keys(&[new_key, old_key], start + interval * 2),
+            KeyRotationAction::Retire(old_key)
+        );
+        schedule.mark_retired(&old_key);
+        assert_eq!(
+            schedule.observe_active_keys(&[old_key, new_key], start + interval * 2),
+            KeyRotationAction::None
+        );
+
+        // The old key finished scanning.
+        let drained = start + interval * 2 + interval / 2;
+        assert_eq!(
+            schedule.observe_active_keys(&[new_key], drained),
+            KeyRotationAction::VerifyHandoff {
+                retired_key: old_key,
+                new_key,
+            }
+        );
+        assert!(!schedule.is_rotating(&[old_key, new_key]));
+        assert_eq!(
+            schedule.observe_active_keys(&[new_key], drained),
+            KeyRotationAction::None
+        );
+
+        // The new key's age is counted from when it was first seen active.
+        assert_eq!(
+            schedule.observe_active_keys(&[new_key], start + interval * 3),
+            KeyRotationAction::ActivateNewKey(new_key)
+        );
+    }
+
+    #[test]
+    fn unretires_old_key_when_new_key_goes_away() {
+        let interval = Duration::from_secs(60);
+        let mut schedule = KeyRotationSchedule::new(interval);
+        let old_key = CompressedRistrettoPublic::from(&[1u8; 32]);
+        let new_key = CompressedRistrettoPublic::from(&[2u8; 32]);
+        let start = Instant::now();
+
+        schedule.observe_active_keys(&[old_key], start);
+        schedule.observe_active_keys(&[old_key], start + interval);
+        schedule.mark_new_key_activated(&old_key);
+        schedule.observe_active_keys(&[old_key, new_key], start + interval);
+        schedule.mark_retired(&old_key);
+
+        // The new key's node went idle before the old key finished scanning.
+        assert_eq!(
+            schedule.observe_active_keys(&[old_key], start + interval * 2),
+            KeyRotationAction::Unretire(old_key)
+        );
+        assert_eq!(
+            schedule.observe_active_keys(&[old_key], start + interval * 2),
+            KeyRotationAction::Unretire(old_key)
+        );
+        schedule.mark_unretired(&old_key);
+
+        // The old key is still due, so it is rotated again.
+        assert_eq!(
+            schedule.observe_active_keys(&[old_key], start + interval * 2),
+            KeyRotationAction::ActivateNewKey(old_key)
+        );
+    }
+
+    #[test]
+    fn retries_when_new_key_goes_away_before_retirement() {
+        let interval = Duration::from_secs(60);
+        let mut schedule = KeyRotationSchedule::new(interval);
+        let old_key = CompressedRistrettoPublic::from(&[1u8; 32]);
+        let new_key = CompressedRistrettoPublic::from(&[2u8; 32]);
+        let start = Instant::now();
+
+        schedule.observe_active_keys(&[old_key], start);
+        schedule.observe_active_keys(&[old_key], start + interval);
+        schedule.mark_new_key_activated(&old_key);
+        assert_eq!(
+            schedule.observe_active_keys(&[old_key, new_key], start + interval),
+            KeyRotationAction::Retire(old_key)
+        );
+
+        // The old key was never retired, so there is nothing to undo.
+        assert_eq!(
+            schedule.observe_active_keys(&[old_key], start + interval * 2),
+            KeyRotationAction::None
+        );
+        assert!(!schedule.is_rotating(&[old_key, new_key]));
+        assert_eq!(
+            schedule.observe_active_keys(&[old_key], start + interval * 2),
+            KeyRotationAction::ActivateNewKey(old_key)
+        );
+    }
+
+    #[test]
+    fn key_change_without_rotation_restarts_clock() {
+        let interval = Duration::from_secs(60);
+        let mut schedule = KeyRotationSchedule::new(interval);
+        let old_key = CompressedRistrettoPublic::from(&[1u8; 32]);
+        let new_key = CompressedRistrettoPublic::from(&[2u8; 32]);
+        let start = Instant::now();
+
+        schedule.observe_active_keys(&[old_key], start);
+        // E.g. an operator rotated the key by hand.
+        assert_eq!(
+            schedule.observe_active_keys(&[new_key], start + interval / 2),
+            KeyRotationAction::None
+        );
+        assert_eq!(
+            schedule.observe_active_keys(&[new_key], start + interval),
+            KeyRotationAction::None
+        );
+        assert_eq!(
+            schedule.observe_active_keys(&[new_key], start + interval + interval / 2),
+            KeyRotationAction::ActivateNewKey(new_key)
+        );
+
+        // Two active keys that are not part of a rotation are left alone.
+        assert!(!schedule.is_rotating(&[old_key, new_key]));
+        assert_eq!(
+            schedule.observe_active_keys(&[old_key, new_key], start + interval * 2),
+            KeyRotationAction::None
+        );
+    }
+}
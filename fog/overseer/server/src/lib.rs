@@ -5,11 +5,14 @@
 #![feature(proc_macro_hygiene, decl_macro)]
 #![deny(missing_docs)]
 
+pub mod alerts;
 pub mod config;
 pub mod metrics;
+pub mod remediation;
 pub mod responses;
 pub mod server;
 pub mod service;
 
 mod error;
+mod key_rotation;
 mod worker;
@@ -0,0 +1,208 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! A guided workflow for reporting a lost ingress key.
+//!
+//! When no Fog Ingest node has an outstanding ingress key anymore, the blocks
+//! that were promised to be scanned with it have to be reported as missed, so
+//! that the view server and clients can make progress. This workflow:
+//!   1. Looks up the key's record in the RecoveryDb.
+//!   2. Checks that no Fog Ingest node still has the key, since activating
+//!      such a node is always better than losing the key. Every node must
+//!      respond, unless the request forces the workflow past the ones that
+//!      don't.
+//!   3. Computes the missed block range that reporting the key lost should
+//!      produce.
+//!   4. Reports the key lost, unless this is a dry run.
+//!   5. Verifies that the expected range shows up in the missed block ranges.
+
+use crate::{error::OverseerError, responses::LostKeyRemediationResponse};
+use mc_common::logger::{log, Logger};
+use mc_crypto_keys::CompressedRistrettoPublic;
+use mc_fog_ingest_client::FogIngestGrpcClient;
+use mc_fog_recovery_db_iface::{IngressPublicKeyRecord, IngressPublicKeyRecordFilters, RecoveryDb};
+use mc_fog_types::common::BlockRange;
+use serde::Deserialize;
+
+/// A request to report an ingress key lost.
+#[derive(Deserialize)]
+pub struct LostKeyRemediationRequest {
+    /// The hex-encoded ingress public key to report lost.
+    pub ingress_public_key: String,
+
+    /// If true, only check and describe what would be done.
+    #[serde(default)]
+    pub dry_run: bool,
+
+    /// If true, report the key lost even if some Fog Ingest nodes did not
+    /// respond, and so might still have it.
+    #[serde(default)]
+    pub force: bool,
+}
+
+/// Runs the lost key workflow for `lost_key`.
+pub fn remediate_lost_key<DB: RecoveryDb>(
+    ingest_clients: &[FogIngestGrpcClient],
+    recovery_db: &DB,
+    lost_key: CompressedRistrettoPublic,
+    dry_run: bool,
+    force: bool,
+    logger: &Logger,
+) -> Result<LostKeyRemediationResponse, OverseerError>
+where
+    OverseerError: From<DB::Error>,
+{
+    let mut response = LostKeyRemediationResponse {
+        ingress_public_key: hex::encode(lost_key.as_ref() as &[u8]),
+        dry_run,
+        reported_lost: false,
+        expected_missed_block_range: None,
+        new_missed_block_ranges: Vec::new(),
+        steps: Vec::new(),
+    };
+    let add_step = |response: &mut LostKeyRemediationResponse, step: String| {
+        log::info!(logger, "Lost key remediation: {}", step);
+        response.steps.push(step);
+    };
+
+    let all_keys_filters = IngressPublicKeyRecordFilters {
+        should_include_lost_keys: true,
+        should_include_retired_keys: true,
+        should_only_include_unexpired_keys: false,
+    };
+    let record = recovery_db
+        .get_ingress_key_records(/* start_block_at_least= */ 0, &all_keys_filters)?
+        .into_iter()
+        .find(|record| record.key == lost_key)
+        .ok_or_else(|| {
+            OverseerError::LostKeyRemediation(format!(
+                "No ingress key record found for key {}",
+                response.ingress_public_key
+            ))
+        })?;
+    add_step(
+        &mut response,
+        format!(
+            "Found ingress key record: start block {}, pubkey expiry {}, last scanned block {:?}, retired {}, lost {}",
+            record.status.start_block,
+            record.status.pubkey_expiry,
+            record.last_scanned_block,
+            record.status.retired,
+            record.status.lost
+        ),
+    );
+
+    if record.status.lost {
+        add_step(
+            &mut response,
+            "The key is already reported lost, nothing to do".to_string(),
+        );
+        return Ok(response);
+    }
+
+    let mut unresponsive_nodes = Vec::new();
+    for ingest_client in ingest_clients {
+        let uri = ingest_client.get_uri();
+        match ingest_client.get_status() {
+            Ok(ingest_summary) => {
+                if CompressedRistrettoPublic::try_from(ingest_summary.get_ingress_pubkey()).ok()
+                    == Some(lost_key)
+                {
+                    return Err(OverseerError::LostKeyRemediation(format!(
+                        "Node {} still has key {}. Activate that node instead of reporting the key lost.",
+                        uri, response.ingress_public_key
+                    )));
+                }
+            }
+            Err(err) => {
+                add_step(
+                    &mut response,
+                    format!(
+                        "Node {} did not respond, so it may still have the key: {}",
+                        uri, err
+                    ),
+                );
+                unresponsive_nodes.push(uri.to_string());
+            }
+        }
+    }
+    if unresponsive_nodes.is_empty() {
+        add_step(&mut response, "No Fog Ingest node has the key".to_string());
+    } else if force {
+        add_step(
+            &mut response,
+            format!(
+                "Warning: no responding Fog Ingest node has the key, continuing without a response from {} because force is set",
+                unresponsive_nodes.join(", ")
+            ),
+        );
+    } else {
+        return Err(OverseerError::LostKeyRemediation(format!(
+            "Nodes {} did not respond, so they may still have key {}. Retry once they respond, or set force to report the key lost anyway.",
+            unresponsive_nodes.join(", "),
+            response.ingress_public_key
+        )));
+    }
+
+    response.expected_missed_block_range = expected_missed_block_range(&record);
+    let expected_step = match response.expected_missed_block_range.as_ref() {
+        Some(range) => format!(
+            "Reporting the key lost should add the missed block range {}",
+            range
+        ),
+        None => "Every block promised for this key was scanned, so reporting it lost should not add a missed block range".to_string(),
+    };
+    add_step(&mut response, expected_step);
+
+    if dry_run {
+        add_step(
+            &mut response,
+            "Dry run: not reporting the key lost".to_string(),
+        );
+        return Ok(response);
+    }
+
+    let missed_block_ranges_before = recovery_db.get_missed_block_ranges()?;
+    recovery_db.report_lost_ingress_key(lost_key)?;
+    response.reported_lost = true;
+    add_step(&mut response, "Reported the key lost".to_string());
+
+    response.new_missed_block_ranges = recovery_db
+        .get_missed_block_ranges()?
+        .into_iter()
+        .filter(|range| !missed_block_ranges_before.contains(range))
+        .collect();
+    if let Some(expected) = response.expected_missed_block_range.as_ref() {
+        // Blocks may have been scanned with the key after we read its record,
+        // which can only shrink the range from the front.
+        let found = response.new_missed_block_ranges.iter().any(|range| {
+            range.end_block == expected.end_block && range.start_block >= expected.start_block
+        });
+        if !found {
+            return Err(OverseerError::LostKeyRemediation(format!(
+                "The key was reported lost, but the expected missed block range {} was not found among the new missed block ranges {:?}",
+                expected, response.new_missed_block_ranges
+            )));
+        }
+    }
+    add_step(
+        &mut response,
+        format!(
+            "Verified the new missed block ranges: {:?}",
+            response.new_missed_block_ranges
+        ),
+    );
+
+    Ok(response)
+}
+
+/// The range of blocks that reporting `record`'s key lost marks as missed:
+/// the blocks after the last one scanned with the key, up to its pubkey expiry.
+fn expected_missed_block_range(record: &IngressPublicKeyRecord) -> Option<BlockRange> {
+    let start_block = record
+        .last_scanned_block
+        .map_or(record.status.start_block, |block_index| {
+            record.status.start_block.max(block_index + 1)
+        });
+    let range = BlockRange::new(start_block, record.status.pubkey_expiry);
+    range.is_valid().then(|| range)
+}
@@ -2,7 +2,7 @@
 
 //! Contains responses that are returned by Fog Overseer.
 
-use mc_fog_types::ingest_common::IngestSummary;
+use mc_fog_types::{common::BlockRange, ingest_common::IngestSummary};
 use mc_fog_uri::FogIngestUri;
 use serde::Serialize;
 use std::collections::HashMap;
@@ -15,3 +15,26 @@ pub struct GetIngestSummariesResponse {
     /// The ingest summaries.
     pub ingest_summaries: HashMap<FogIngestUri, Result<IngestSummary, String>>,
 }
+
+/// The outcome of the lost key remediation workflow.
+#[derive(Serialize)]
+pub struct LostKeyRemediationResponse {
+    /// The hex-encoded ingress public key.
+    pub ingress_public_key: String,
+
+    /// Whether this was a dry run, in which case nothing was changed.
+    pub dry_run: bool,
+
+    /// Whether the key was reported lost by this request.
+    pub reported_lost: bool,
+
+    /// The missed block range that reporting the key lost should produce, if
+    /// any blocks promised for the key haven't been scanned.
+    pub expected_missed_block_range: Option<BlockRange>,
+
+    /// The missed block ranges that appeared after reporting the key lost.
+    pub new_missed_block_ranges: Vec<BlockRange>,
+
+    /// A description of each step taken.
+    pub steps: Vec<String>,
+}
@@ -6,7 +6,10 @@
 //! HTTP Client -> *Overseer Rocket Server* -> OverseerService -> OverseerWorker
 
 use crate::{
-    error::OverseerError, responses::GetIngestSummariesResponse, service::OverseerService,
+    error::OverseerError,
+    remediation::LostKeyRemediationRequest,
+    responses::{GetIngestSummariesResponse, LostKeyRemediationResponse},
+    service::OverseerService,
 };
 use mc_fog_recovery_db_iface::RecoveryDb;
 use mc_fog_sql_recovery_db::SqlRecoveryDb;
@@ -34,6 +37,19 @@ fn get_ingest_summaries(
     state.overseer_service.get_ingest_summaries().map(Json)
 }
 
+/// Reports an ingress key lost, after checking that no node still has it, and
+/// verifies the resulting missed block ranges.
+#[post("/remediate_lost_key", format = "json", data = "<request>")]
+fn remediate_lost_key(
+    state: &rocket::State<OverseerState<SqlRecoveryDb>>,
+    request: Json<LostKeyRemediationRequest>,
+) -> Result<Json<LostKeyRemediationResponse>, String> {
+    state
+        .overseer_service
+        .remediate_lost_key(&request)
+        .map(Json)
+}
+
 /// Produces metrics for Prometheus.
 ///
 /// Meant to be called only by the Prometheus pull mechanism.
@@ -66,7 +82,8 @@ pub fn initialize_rocket_server<T: rocket::figment::Provider>(
             disable,
             get_status,
             get_metrics,
-            get_ingest_summaries
+            get_ingest_summaries,
+            remediate_lost_key
         ],
     )
 }
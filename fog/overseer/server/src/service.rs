@@ -7,8 +7,15 @@
 //!
 //! HTTP Client -> Overseer Rocket Server -> *OverseerService* -> OverseerWorker
 
-use crate::{error::OverseerError, responses::GetIngestSummariesResponse, worker::OverseerWorker};
+use crate::{
+    alerts::AlertConfig,
+    error::OverseerError,
+    remediation::{self, LostKeyRemediationRequest},
+    responses::{GetIngestSummariesResponse, LostKeyRemediationResponse},
+    worker::OverseerWorker,
+};
 use mc_common::logger::{log, Logger};
+use mc_crypto_keys::CompressedRistrettoPublic;
 use mc_fog_ingest_client::FogIngestGrpcClient;
 use mc_fog_recovery_db_iface::RecoveryDb;
 use mc_fog_types::ingest_common::IngestSummary;
@@ -33,6 +40,9 @@ where
     overseer_worker: Option<OverseerWorker>,
     recovery_db: DB,
     is_enabled: Arc<AtomicBool>,
+    key_rotation_interval: Option<Duration>,
+    alert_config: AlertConfig,
+    dry_run: bool,
 }
 
 impl<DB: RecoveryDb + Clone + Send + Sync + 'static> OverseerService<DB>
@@ -43,7 +53,19 @@ where
     const GRPC_RETRY_SECONDS: Duration = Duration::from_millis(10000);
 
     /// Insantiate the service with the given URIs and DB.
-    pub fn new(ingest_cluster_uris: Vec<FogIngestUri>, recovery_db: DB, logger: Logger) -> Self {
+    ///
+    /// If `key_rotation_interval` is set, the active ingress key is rotated
+    /// after it has been active for that long. If `dry_run` is set, Overseer
+    /// only logs the changes it would make to the Fog Ingest cluster and the
+    /// RecoveryDb.
+    pub fn new(
+        ingest_cluster_uris: Vec<FogIngestUri>,
+        recovery_db: DB,
+        key_rotation_interval: Option<Duration>,
+        alert_config: AlertConfig,
+        dry_run: bool,
+        logger: Logger,
+    ) -> Self {
         let grpcio_env = Arc::new(grpcio::EnvBuilder::new().build());
         let ingest_clients: Vec<FogIngestGrpcClient> = ingest_cluster_uris
             .iter()
@@ -62,6 +84,9 @@ where
             overseer_worker: None,
             recovery_db,
             is_enabled: Arc::new(AtomicBool::new(false)),
+            key_rotation_interval,
+            alert_config,
+            dry_run,
         }
     }
 
@@ -84,6 +109,9 @@ where
             self.recovery_db.clone(),
             self.logger.clone(),
             self.is_enabled.clone(),
+            self.key_rotation_interval,
+            self.alert_config.clone(),
+            self.dry_run,
         ));
 
         Ok(())
@@ -183,6 +211,29 @@ where
 
         Ok(GetIngestSummariesResponse { ingest_summaries })
     }
+
+    /// Walk through reporting an ingress key lost, and verify the resulting
+    /// missed block ranges. If Overseer is in dry-run mode, the request is
+    /// treated as a dry run.
+    pub fn remediate_lost_key(
+        &self,
+        request: &LostKeyRemediationRequest,
+    ) -> Result<LostKeyRemediationResponse, String> {
+        let mut key_bytes = [0u8; 32];
+        hex::decode_to_slice(&request.ingress_public_key, &mut key_bytes)
+            .map_err(|err| format!("Invalid ingress public key: {}", err))?;
+        let lost_key = CompressedRistrettoPublic::from(&key_bytes);
+
+        remediation::remediate_lost_key(
+            &self.ingest_clients,
+            &self.recovery_db,
+            lost_key,
+            request.dry_run || self.dry_run,
+            request.force,
+            &self.logger,
+        )
+        .map_err(|err| err.to_string())
+    }
 }
 
 impl<DB: RecoveryDb + Clone + Send + Sync + 'static> Drop for OverseerService<DB>
@@ -7,8 +7,13 @@
 //!
 //! HTTP Client -> Overseer Rocket Server -> OverseerService -> *OverseerWorker*
 
-use crate::{error::OverseerError, metrics};
-use mc_api::external;
+use crate::{
+    alerts::{AlertConfig, AlertManager, ClusterObservation},
+    error::OverseerError,
+    key_rotation::{KeyRotationAction, KeyRotationSchedule},
+    metrics,
+};
+use mc_api::{external, ConversionError};
 use mc_common::logger::{log, Logger};
 use mc_crypto_keys::CompressedRistrettoPublic;
 use mc_fog_api::ingest_common::{IngestControllerMode, IngestSummary};
@@ -23,7 +28,7 @@ use std::{
         Arc,
     },
     thread::{sleep, Builder as ThreadBuilder, JoinHandle},
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 /// Wraps a thread that is responsible for overseeing the active Fog Ingest
@@ -33,6 +38,13 @@ use std::{
 /// there is no active key, then it promotes an idle node to active, and in the
 /// case where none of the idle nodes contain the previously active ingress key,
 /// it reports that key as lost.
+///
+/// If a key rotation interval is configured, the worker also rotates the
+/// active ingress key once it has been active for that long: it activates a
+/// new key on an idle node, and then retires the old key.
+///
+/// On every round, the worker also evaluates the configured alert rules. In
+/// dry-run mode, it only logs the changes it would make.
 pub struct OverseerWorker {
     /// Join handle used to wait for the thread to terminate.
     join_handle: Option<JoinHandle<()>>,
@@ -47,6 +59,9 @@ impl OverseerWorker {
         recovery_db: DB,
         logger: Logger,
         is_enabled: Arc<AtomicBool>,
+        key_rotation_interval: Option<Duration>,
+        alert_config: AlertConfig,
+        dry_run: bool,
     ) -> Self
     where
         OverseerError: From<DB::Error>,
@@ -64,6 +79,9 @@ impl OverseerWorker {
                         thread_is_enabled,
                         thread_stop_requested,
                         HashSet::new(),
+                        key_rotation_interval,
+                        alert_config,
+                        dry_run,
                         logger,
                     )
                 })
@@ -114,6 +132,16 @@ struct OverseerWorkerThread<DB: RecoveryDb> {
     /// This helps us debug when a node starts responding again.
     unresponsive_node_urls: HashSet<FogIngestUri>,
 
+    /// Decides when to rotate the ingress key, if rotation is enabled.
+    key_rotation_schedule: Option<KeyRotationSchedule>,
+
+    /// Evaluates the alert rules and notifies their sinks.
+    alert_manager: AlertManager,
+
+    /// If this is true, the worker only logs the changes it would make to the
+    /// Fog Ingest cluster and the RecoveryDb.
+    dry_run: bool,
+
     logger: Logger,
 }
 
@@ -143,6 +171,9 @@ where
         is_enabled: Arc<AtomicBool>,
         stop_requested: Arc<AtomicBool>,
         unresponsive_node_urls: HashSet<FogIngestUri>,
+        key_rotation_interval: Option<Duration>,
+        alert_config: AlertConfig,
+        dry_run: bool,
         logger: Logger,
     ) {
         let thread = Self {
@@ -151,6 +182,9 @@ where
             is_enabled,
             stop_requested,
             unresponsive_node_urls,
+            key_rotation_schedule: key_rotation_interval.map(KeyRotationSchedule::new),
+            alert_manager: AlertManager::new(alert_config),
+            dry_run,
             logger,
         };
         thread.run();
@@ -171,16 +205,21 @@ where
                 continue;
             }
 
-            let ingest_summary_node_mappings: Vec<IngestSummaryNodeMapping> = match self
-                .retrieve_ingest_summary_node_mappings()
-            {
-                Ok(ingest_summary_node_mappings) => ingest_summary_node_mappings,
-                Err(err) => {
-                    log::error!(self.logger, "Encountered an error while retrieving ingest summaries: {}. Returning to beginning of overseer logic.", err);
-                    metrics::increment_unresponsive_node_count(&self.logger);
-                    continue;
-                }
-            };
+            let ingest_summary_node_mapping_results = self.retrieve_ingest_summary_node_mappings();
+            self.evaluate_alerts(&ingest_summary_node_mapping_results);
+
+            let ingest_summary_node_mappings: Vec<IngestSummaryNodeMapping> =
+                match ingest_summary_node_mapping_results
+                    .into_iter()
+                    .collect::<Result<_, _>>()
+                {
+                    Ok(ingest_summary_node_mappings) => ingest_summary_node_mappings,
+                    Err(err) => {
+                        log::error!(self.logger, "Encountered an error while retrieving ingest summaries: {}. Returning to beginning of overseer logic.", err);
+                        metrics::increment_unresponsive_node_count(&self.logger);
+                        continue;
+                    }
+                };
 
             let ingest_summaries: Vec<IngestSummary> = ingest_summary_node_mappings
                 .iter()
@@ -221,7 +260,20 @@ where
                         .ingest_summary
                         .get_ingress_pubkey()
                     );
-                    continue;
+                    self.perform_key_rotation(
+                        &ingest_summary_node_mappings,
+                        &active_ingest_summary_node_mappings,
+                    );
+                }
+                _ if self.is_rotating_key(&active_ingest_summary_node_mappings) => {
+                    log::trace!(
+                        self.logger,
+                        "There are two active nodes in the Fog Ingest cluster while the ingress key is being rotated.",
+                    );
+                    self.perform_key_rotation(
+                        &ingest_summary_node_mappings,
+                        &active_ingest_summary_node_mappings,
+                    );
                 }
                 _ => {
                     let active_node_ingress_pubkeys: Vec<&external::CompressedRistretto> =
@@ -243,10 +295,10 @@ where
     }
 
     /// Returns the latest round of ingest summaries for each
-    /// FogIngestGrpcClient that communicates with a node that is online.
+    /// FogIngestGrpcClient, or an error for each node that is not online.
     fn retrieve_ingest_summary_node_mappings(
         &mut self,
-    ) -> Result<Vec<IngestSummaryNodeMapping>, OverseerError> {
+    ) -> Vec<Result<IngestSummaryNodeMapping, OverseerError>> {
         let logger = &self.logger;
         let unresponsive_node_urls = &mut self.unresponsive_node_urls;
         self.ingest_clients
@@ -289,6 +341,47 @@ where
             .collect()
     }
 
+    /// Evaluates the alert rules against the latest round of ingest summaries
+    /// and notifies the sinks of any rules that started firing or resolved.
+    fn evaluate_alerts(
+        &mut self,
+        ingest_summary_node_mapping_results: &[Result<IngestSummaryNodeMapping, OverseerError>],
+    ) {
+        let active_node_count = ingest_summary_node_mapping_results
+            .iter()
+            .filter(|result| {
+                matches!(result, Ok(mapping) if mapping.ingest_summary.mode == IngestControllerMode::Active)
+            })
+            .count();
+        let unresponsive_node_count = ingest_summary_node_mapping_results
+            .iter()
+            .filter(|result| result.is_err())
+            .count();
+        let missed_block_ranges = if self.alert_manager.needs_missed_block_ranges() {
+            match self.recovery_db.get_missed_block_ranges() {
+                Ok(missed_block_ranges) => Some(missed_block_ranges),
+                Err(err) => {
+                    log::warn!(
+                        self.logger,
+                        "Could not get missed block ranges to evaluate alerts: {}",
+                        err
+                    );
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        let observation = ClusterObservation {
+            active_node_count,
+            unresponsive_node_count,
+            missed_block_ranges,
+        };
+        let notifications = self.alert_manager.evaluate(&observation, Instant::now());
+        self.alert_manager.notify(&notifications, &self.logger);
+    }
+
     /// Performs automatic failover, which means that we try to activate nodes
     /// for an outstanding ingress key, if it exists.
     ///
@@ -410,6 +503,14 @@ where
             };
             if inactive_outstanding_key.eq(&node_ingress_key) {
                 let node = &self.ingest_clients[ingest_summary_node_mapping.node_index];
+                if self.dry_run {
+                    log::info!(
+                        self.logger,
+                        "Dry run: would activate node {}.",
+                        node.get_uri()
+                    );
+                    return Ok(());
+                }
                 match node.activate() {
                     Ok(_) => {
                         log::info!(
@@ -452,6 +553,14 @@ where
         &self,
         inactive_outstanding_key: CompressedRistrettoPublic,
     ) -> Result<(), OverseerError> {
+        if self.dry_run {
+            log::info!(
+                self.logger,
+                "Dry run: would report the following key as lost: {}",
+                inactive_outstanding_key
+            );
+            return Ok(());
+        }
         let result = retry_with_index(
             Fixed::from_millis(200).take(Self::NUMBER_OF_TRIES),
             |current_try| match self
@@ -480,7 +589,30 @@ where
     /// Tries to set a new ingress key on a node. The node is assumed to be
     /// idle.
     fn set_new_key_on_a_node(&self) -> Result<usize, OverseerError> {
-        for (i, ingest_client) in self.ingest_clients.iter().enumerate() {
+        let node_indexes: Vec<usize> = (0..self.ingest_clients.len()).collect();
+        self.set_new_key_on_one_of(&node_indexes)
+    }
+
+    /// Tries to set a new ingress key on one of the given nodes, in order, and
+    /// returns the index of the node that got the new key.
+    fn set_new_key_on_one_of(&self, node_indexes: &[usize]) -> Result<usize, OverseerError> {
+        if self.dry_run {
+            return match node_indexes.first() {
+                Some(i) => {
+                    log::info!(
+                        self.logger,
+                        "Dry run: would set new keys on the ingest node {}.",
+                        self.ingest_clients[*i].get_uri()
+                    );
+                    Ok(*i)
+                }
+                None => Err(OverseerError::SetNewKey(
+                    "There are no ingest nodes to set new keys on.".to_string(),
+                )),
+            };
+        }
+        for &i in node_indexes {
+            let ingest_client = &self.ingest_clients[i];
             let result = retry_with_index(
                 Fixed::from_millis(200).take(Self::NUMBER_OF_TRIES),
                 |current_try| {
@@ -514,8 +646,340 @@ where
         ))
     }
 
+    /// Returns the ingress keys of the given nodes.
+    fn ingress_keys(
+        node_mappings: &[&IngestSummaryNodeMapping],
+    ) -> Result<Vec<CompressedRistrettoPublic>, ConversionError> {
+        node_mappings
+            .iter()
+            .map(|mapping| {
+                CompressedRistrettoPublic::try_from(mapping.ingest_summary.get_ingress_pubkey())
+            })
+            .collect()
+    }
+
+    /// Whether the active nodes are the two nodes we expect to be active while
+    /// the ingress key is being rotated.
+    fn is_rotating_key(&self, active_node_mappings: &[&IngestSummaryNodeMapping]) -> bool {
+        match (
+            self.key_rotation_schedule.as_ref(),
+            Self::ingress_keys(active_node_mappings),
+        ) {
+            (Some(schedule), Ok(active_keys)) => schedule.is_rotating(&active_keys),
+            _ => false,
+        }
+    }
+
+    /// Returns the index of the active node whose ingress key is `key`.
+    fn find_active_node(
+        active_node_mappings: &[&IngestSummaryNodeMapping],
+        key: &CompressedRistrettoPublic,
+    ) -> Option<usize> {
+        active_node_mappings
+            .iter()
+            .find(|mapping| {
+                CompressedRistrettoPublic::try_from(mapping.ingest_summary.get_ingress_pubkey())
+                    .map_or(false, |node_key| &node_key == key)
+            })
+            .map(|mapping| mapping.node_index)
+    }
+
+    /// Advances the ingress key rotation schedule, given the active nodes.
+    ///
+    /// Once the active key is due for rotation, activates a new key on an idle
+    /// node, and then retires the old key. Verifies the handoff once the old
+    /// key is done scanning.
+    fn perform_key_rotation(
+        &mut self,
+        ingest_summary_node_mappings: &[IngestSummaryNodeMapping],
+        active_node_mappings: &[&IngestSummaryNodeMapping],
+    ) {
+        let schedule = match self.key_rotation_schedule.as_mut() {
+            Some(schedule) => schedule,
+            None => return,
+        };
+        let active_keys = match Self::ingress_keys(active_node_mappings) {
+            Ok(active_keys) => active_keys,
+            Err(err) => {
+                log::error!(
+                    self.logger,
+                    "An active node has an invalid ingress key, skipping key rotation: {}",
+                    err
+                );
+                return;
+            }
+        };
+
+        match schedule.observe_active_keys(&active_keys, Instant::now()) {
+            KeyRotationAction::None => {}
+            KeyRotationAction::ActivateNewKey(key) => {
+                log::info!(
+                    self.logger,
+                    "Ingress key {} is due for rotation. Activating a new key before retiring it.",
+                    key
+                );
+                match self.activate_new_key(ingest_summary_node_mappings, &key) {
+                    Ok(()) => {
+                        if let Some(schedule) = self.key_rotation_schedule.as_mut() {
+                            schedule.mark_new_key_activated(&key);
+                        }
+                    }
+                    Err(err) => log::error!(
+                        self.logger,
+                        "Ingress key rotation failed, {} stays active: {}",
+                        key,
+                        err
+                    ),
+                }
+            }
+            KeyRotationAction::Retire(key) => {
+                log::info!(
+                    self.logger,
+                    "A new ingress key is active. Retiring ingress key {}.",
+                    key
+                );
+                let result = match Self::find_active_node(active_node_mappings, &key) {
+                    Some(node_index) => self.retire_a_node(node_index),
+                    None => Err(OverseerError::RetireKey(format!(
+                        "No active node has ingress key {}",
+                        key
+                    ))),
+                };
+                match result {
+                    Ok(()) => {
+                        if let Some(schedule) = self.key_rotation_schedule.as_mut() {
+                            schedule.mark_retired(&key);
+                        }
+                    }
+                    Err(err) => {
+                        log::error!(self.logger, "Ingress key rotation failed: {}", err)
+                    }
+                }
+            }
+            KeyRotationAction::Unretire(key) => {
+                log::warn!(
+                    self.logger,
+                    "The new ingress key stopped being active while {} was retiring. Unretiring it.",
+                    key
+                );
+                let result = match Self::find_active_node(active_node_mappings, &key) {
+                    Some(node_index) => self.unretire_a_node(node_index),
+                    None => Err(OverseerError::UnretireKey(format!(
+                        "No active node has ingress key {}",
+                        key
+                    ))),
+                };
+                match result {
+                    Ok(()) => {
+                        if let Some(schedule) = self.key_rotation_schedule.as_mut() {
+                            schedule.mark_unretired(&key);
+                        }
+                    }
+                    Err(err) => {
+                        log::error!(self.logger, "Ingress key rotation rollback failed: {}", err)
+                    }
+                }
+            }
+            KeyRotationAction::VerifyHandoff {
+                retired_key,
+                new_key,
+            } => match self.verify_key_handoff(&retired_key, &new_key) {
+                Ok(()) => log::info!(
+                    self.logger,
+                    "Ingress key rotation completed successfully: {} replaced {}.",
+                    new_key,
+                    retired_key
+                ),
+                Err(err) => log::error!(self.logger, "{}", err),
+            },
+        }
+    }
+
+    /// Sets new keys on an idle node and activates it, while the node with
+    /// `old_key` is still active.
+    ///
+    /// Nodes that hold `old_key` are backups of the active node, and so are its
+    /// peers. Ingest nodes refuse to activate while a peer is active, so only
+    /// idle nodes with other keys are considered.
+    fn activate_new_key(
+        &self,
+        ingest_summary_node_mappings: &[IngestSummaryNodeMapping],
+        old_key: &CompressedRistrettoPublic,
+    ) -> Result<(), OverseerError> {
+        let candidate_node_indexes: Vec<usize> = ingest_summary_node_mappings
+            .iter()
+            .filter(|mapping| {
+                mapping.ingest_summary.mode == IngestControllerMode::Idle
+                    && CompressedRistrettoPublic::try_from(
+                        mapping.ingest_summary.get_ingress_pubkey(),
+                    )
+                    .map_or(true, |node_key| &node_key != old_key)
+            })
+            .map(|mapping| mapping.node_index)
+            .collect();
+        if candidate_node_indexes.is_empty() {
+            return Err(OverseerError::SetNewKey(format!(
+                "There is no idle node without ingress key {} to set a new key on.",
+                old_key
+            )));
+        }
+
+        let activated_node_index = self.set_new_key_on_one_of(&candidate_node_indexes)?;
+        self.activate_a_node(activated_node_index)
+    }
+
+    /// Checks the ingress key records to confirm that a key we retired
+    /// scanned every block it promised to scan before `new_key` took over.
+    fn verify_key_handoff(
+        &self,
+        retired_key: &CompressedRistrettoPublic,
+        new_key: &CompressedRistrettoPublic,
+    ) -> Result<(), OverseerError> {
+        let all_keys_filters = IngressPublicKeyRecordFilters {
+            should_include_lost_keys: true,
+            should_include_retired_keys: true,
+            should_only_include_unexpired_keys: false,
+        };
+        let ingress_public_key_records: Vec<IngressPublicKeyRecord> = self
+            .recovery_db
+            .get_ingress_key_records(/* start_block_at_least= */ 0, &all_keys_filters)?;
+
+        let find_record = |key: &CompressedRistrettoPublic| {
+            ingress_public_key_records
+                .iter()
+                .find(|record| &record.key == key)
+                .ok_or_else(|| {
+                    OverseerError::KeyRotationHandoff(format!(
+                        "No ingress key record found for key {}",
+                        key
+                    ))
+                })
+        };
+
+        let retired_record = find_record(retired_key)?;
+        if retired_record.status.lost {
+            return Err(OverseerError::KeyRotationHandoff(format!(
+                "Retired key {} was reported lost, so the blocks it didn't scan are missed",
+                retired_key
+            )));
+        }
+        if let Some(block_index) = retired_record.next_needed_block_index() {
+            return Err(OverseerError::KeyRotationHandoff(format!(
+                "Retired key {} has not scanned block {} (pubkey expiry {})",
+                retired_key, block_index, retired_record.status.pubkey_expiry
+            )));
+        }
+
+        let new_record = find_record(new_key)?;
+        if new_record.status.lost || new_record.status.retired {
+            return Err(OverseerError::KeyRotationHandoff(format!(
+                "New key {} is unexpectedly {}",
+                new_key,
+                if new_record.status.lost {
+                    "lost"
+                } else {
+                    "retired"
+                }
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Tries to retire the ingress key of a node. The node is assumed to be
+    /// active.
+    fn retire_a_node(&self, retired_node_index: usize) -> Result<(), OverseerError> {
+        if self.dry_run {
+            log::info!(
+                self.logger,
+                "Dry run: would retire the ingress key of node {}.",
+                self.ingest_clients[retired_node_index].get_uri()
+            );
+            return Ok(());
+        }
+        let result = retry_with_index(
+            Fixed::from_millis(200).take(Self::NUMBER_OF_TRIES),
+            |current_try| {
+                let ingest_client = &self.ingest_clients[retired_node_index];
+                match ingest_client.retire() {
+                    Ok(_) => {
+                        log::info!(
+                            self.logger,
+                            "Node {} successfully retired its ingress key.",
+                            ingest_client.get_uri(),
+                        );
+                        OperationResult::Ok(())
+                    }
+                    Err(err) => {
+                        let number_of_remaining_tries =
+                            Self::NUMBER_OF_TRIES - current_try as usize;
+                        let error_message = format!(
+                            "Node {} did not retire its ingress key. Will try {} more times. Underlying error: {}",
+                            ingest_client.get_uri(),
+                            number_of_remaining_tries,
+                            err
+                        );
+                        OperationResult::Retry(OverseerError::RetireKey(error_message))
+                    }
+                }
+            },
+        );
+
+        Ok(result?)
+    }
+
+    /// Tries to unretire the ingress key of a node. The node is assumed to be
+    /// active.
+    fn unretire_a_node(&self, unretired_node_index: usize) -> Result<(), OverseerError> {
+        if self.dry_run {
+            log::info!(
+                self.logger,
+                "Dry run: would unretire the ingress key of node {}.",
+                self.ingest_clients[unretired_node_index].get_uri()
+            );
+            return Ok(());
+        }
+        let result = retry_with_index(
+            Fixed::from_millis(200).take(Self::NUMBER_OF_TRIES),
+            |current_try| {
+                let ingest_client = &self.ingest_clients[unretired_node_index];
+                match ingest_client.unretire() {
+                    Ok(_) => {
+                        log::info!(
+                            self.logger,
+                            "Node {} successfully unretired its ingress key.",
+                            ingest_client.get_uri(),
+                        );
+                        OperationResult::Ok(())
+                    }
+                    Err(err) => {
+                        let number_of_remaining_tries =
+                            Self::NUMBER_OF_TRIES - current_try as usize;
+                        let error_message = format!(
+                            "Node {} did not unretire its ingress key. Will try {} more times. Underlying error: {}",
+                            ingest_client.get_uri(),
+                            number_of_remaining_tries,
+                            err
+                        );
+                        OperationResult::Retry(OverseerError::UnretireKey(error_message))
+                    }
+                }
+            },
+        );
+
+        Ok(result?)
+    }
+
     /// Tries to activate a node. The node is assumed to be idle.
     fn activate_a_node(&self, activated_node_index: usize) -> Result<(), OverseerError> {
+        if self.dry_run {
+            log::info!(
+                self.logger,
+                "Dry run: would activate node {}.",
+                self.ingest_clients[activated_node_index].get_uri()
+            );
+            return Ok(());
+        }
         let result = retry_with_index(
             Fixed::from_millis(200).take(Self::NUMBER_OF_TRIES),
             |current_try| {
@@ -0,0 +1,93 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+use mc_common::logger::{test_with_logger, Logger};
+use mc_fog_ingest_server_test_utils::IngestServerTestHelper;
+use mc_fog_overseer_server::{remediation::LostKeyRemediationRequest, service::OverseerService};
+use mc_fog_uri::FogIngestUri;
+use std::{thread::sleep, time::Duration};
+
+const BASE_PORT: u16 = 8900;
+
+// Tests the lost key remediation workflow. It should refuse to report a key
+// lost while a node still has it or might have it, only describe what it would
+// do in a dry run, and verify the missed block range once it reports the key
+// lost.
+#[test_with_logger]
+fn remediate_lost_key_reports_key_lost_and_verifies_missed_blocks(logger: Logger) {
+    let mut helper = IngestServerTestHelper::new(BASE_PORT, logger.clone());
+    helper.add_origin_block();
+    let nodes = helper.make_nodes(2);
+
+    nodes[0].activate().expect("first node failed to activate");
+    let lost_key = nodes[0].get_ingress_key();
+    helper.add_test_blocks(3);
+    nodes[0].wait_for_ingest(4);
+    helper.wait_till_recovery_db_in_sync();
+
+    let ingest_uris: Vec<FogIngestUri> = nodes
+        .iter()
+        .map(|node| node.client_listen_uri.clone())
+        .collect();
+    let overseer_service = OverseerService::new(
+        ingest_uris,
+        helper.recovery_db.clone(),
+        None,
+        Default::default(),
+        false,
+        logger.clone(),
+    );
+    let request = |dry_run, force| LostKeyRemediationRequest {
+        ingress_public_key: hex::encode(lost_key),
+        dry_run,
+        force,
+    };
+
+    // Both nodes have the key, so it must not be reported lost, even when
+    // forced.
+    let err = overseer_service
+        .remediate_lost_key(&request(false, true))
+        .expect_err("remediation should refuse while a node has the key");
+    assert!(err.contains("still has key"), "{}", err);
+    helper.check_ingress_key(&lost_key, false, false);
+
+    // Stop both nodes, so that the key is gone.
+    drop(nodes);
+    sleep(Duration::from_secs(2));
+
+    // The nodes don't respond, so they might still have the key.
+    let err = overseer_service
+        .remediate_lost_key(&request(false, false))
+        .expect_err("remediation should refuse while nodes don't respond");
+    assert!(err.contains("did not respond"), "{}", err);
+    helper.check_ingress_key(&lost_key, false, false);
+
+    let response = overseer_service
+        .remediate_lost_key(&request(true, true))
+        .expect("dry run failed");
+    assert!(response.dry_run);
+    assert!(!response.reported_lost);
+    let expected_missed_block_range = response
+        .expected_missed_block_range
+        .expect("expected a missed block range");
+    assert_eq!(expected_missed_block_range.start_block, 4);
+    assert!(response.new_missed_block_ranges.is_empty());
+    helper.check_ingress_key(&lost_key, false, false);
+
+    let response = overseer_service
+        .remediate_lost_key(&request(false, true))
+        .expect("remediation failed");
+    assert!(!response.dry_run);
+    assert!(response.reported_lost);
+    assert_eq!(
+        response.new_missed_block_ranges,
+        vec![expected_missed_block_range]
+    );
+    helper.check_ingress_key(&lost_key, false, true);
+
+    // Running it again is a no-op.
+    let response = overseer_service
+        .remediate_lost_key(&request(false, false))
+        .expect("remediation of a lost key failed");
+    assert!(!response.reported_lost);
+    assert!(response.new_missed_block_ranges.is_empty());
+}
@@ -25,8 +25,14 @@ pub trait TestHelperExt {
 
 impl TestHelperExt for IngestServerTestHelper {
     fn enable_overseer(&self, ingest_uris: Vec<FogIngestUri>) -> Client {
-        let mut overseer_service =
-            OverseerService::new(ingest_uris, self.recovery_db.clone(), self.logger.clone());
+        let mut overseer_service = OverseerService::new(
+            ingest_uris,
+            self.recovery_db.clone(),
+            None,
+            Default::default(),
+            false,
+            self.logger.clone(),
+        );
         overseer_service
             .start()
             .expect("OverseerService failed to start");
@@ -214,6 +214,25 @@ pub trait RecoveryDb {
     fn search_user_events(
         &self,
         start_from_user_event_id: i64,
+    ) -> Result<(Vec<FogUserEvent>, i64), Self::Error> {
+        self.search_user_events_with_limit(start_from_user_event_id, usize::MAX)
+    }
+
+    /// Get at most `max_num_events` of the events which are new after
+    /// `start_after_event_id`, in order of their ids.
+    ///
+    /// Arguments:
+    /// * start_after_event_id: The last event id the user has received.
+    /// * max_num_events: The maximum number of events to return.
+    ///
+    /// Returns:
+    /// * List of found events, and the id to be used as start_after_event_id in
+    ///   the next query. This is the id of the last returned event if the limit
+    ///   was reached, and the highest event id in the database otherwise.
+    fn search_user_events_with_limit(
+        &self,
+        start_from_user_event_id: i64,
+        max_num_events: usize,
     ) -> Result<(Vec<FogUserEvent>, i64), Self::Error>;
 
     /// Get any TxOutSearchResults corresponding to given search keys.
@@ -0,0 +1,115 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! Fetching fog reports as report bundles over HTTP, for clients that can't use
+//! gRPC.
+
+use crate::{fetch_fog_reports_if_not_cached, Error};
+use mc_common::logger::{log, o, Logger};
+use mc_fog_report_resolver::verify_report_bundle;
+use mc_fog_report_types::{FogReportResponses, ReportResponse};
+use mc_util_uri::FogUri;
+use reqwest::blocking::Client;
+use std::collections::BTreeMap;
+
+/// Fog report server connection that fetches report bundles over HTTP.
+///
+/// The fog report server redirects `/reports` to the current bundle, which it
+/// serves under the hex-encoded SHA-256 digest of its contents. Bundles are
+/// verified against that digest, so they can safely be served by a CDN.
+///
+/// The HTTP server of a report server does not listen on the port of its gRPC
+/// uri, and may be behind a CDN, so the base url to fetch the reports of each
+/// fog report uri from has to be configured with [Self::with_base_url].
+#[derive(Clone)]
+pub struct HttpFogReportConnection {
+    /// The HTTP client, which follows redirects
+    client: Client,
+    /// The HTTP base url of each fog report uri, keyed by the uri
+    base_urls: BTreeMap<String, String>,
+    /// The logging instance
+    logger: Logger,
+}
+
+impl HttpFogReportConnection {
+    /// Create a new HttpFogReportConnection object
+    pub fn new(logger: Logger) -> Self {
+        Self {
+            client: Client::new(),
+            base_urls: Default::default(),
+            logger,
+        }
+    }
+
+    /// Fetch the reports of a fog report uri from the report bundle server at
+    /// the given base url, e.g. `https://fog-reports.example.com`, which
+    /// serves them under `/reports`.
+    pub fn with_base_url(mut self, uri: &FogUri, base_url: &str) -> Self {
+        self.base_urls
+            .insert(uri.to_string(), base_url.trim_end_matches('/').to_string());
+        self
+    }
+
+    /// Fetch fog reports corresponding to a series of FogUris, returning
+    /// FogReportResponses table. This attempts to be efficient, not
+    /// contacting a server twice if a FogUri appears twice.
+    pub fn fetch_fog_reports(
+        &self,
+        uris: impl Iterator<Item = FogUri>,
+    ) -> Result<FogReportResponses, Error> {
+        let mut responses = FogReportResponses::default();
+        self.fetch_fog_reports_if_not_cached(&mut responses, uris)?;
+        Ok(responses)
+    }
+
+    /// Fetch fog reports, adding them to an existing cache, if they are not
+    /// already cached.
+    pub fn fetch_fog_reports_if_not_cached(
+        &self,
+        responses: &mut FogReportResponses,
+        uris: impl Iterator<Item = FogUri>,
+    ) -> Result<(), Error> {
+        fetch_fog_reports_if_not_cached(responses, uris, |uri| self.fetch_fog_report(uri))
+    }
+
+    /// Given a fog report uri, fetch its report bundle over HTTP from the base
+    /// url configured for it, or return an error.
+    pub fn fetch_fog_report(&self, uri: &FogUri) -> Result<ReportResponse, Error> {
+        let base_url = self
+            .base_urls
+            .get(&uri.to_string())
+            .ok_or_else(|| Error::NoBaseUrl(uri.clone()))?;
+        let resp = self.fetch_report_bundle(&format!("{}/reports", base_url))?;
+
+        if resp.reports.is_empty() {
+            log::warn!(
+                self.logger,
+                "Report server at {} has no available reports",
+                uri
+            );
+            return Err(Error::NoReports(uri.clone()));
+        }
+
+        Ok(resp)
+    }
+
+    /// Fetch the current report bundle from a report server's `/reports` url,
+    /// and verify it against the digest in the url it was redirected to.
+    pub fn fetch_report_bundle(&self, url: &str) -> Result<ReportResponse, Error> {
+        let logger = self.logger.new(o!("mc.fog.cxn" => url.to_string()));
+
+        let response = self.client.get(url).send()?.error_for_status()?;
+        let bundle_url = response.url().clone();
+        log::trace!(logger, "Fetching report bundle from {}", bundle_url);
+
+        let mut digest = [0u8; 32];
+        bundle_url
+            .path_segments()
+            .and_then(|mut segments| segments.next_back())
+            .ok_or(())
+            .and_then(|segment| hex::decode_to_slice(segment, &mut digest).map_err(|_| ()))
+            .map_err(|_| Error::BundleDigest(bundle_url.to_string()))?;
+
+        let bundle = response.bytes()?;
+        Ok(verify_report_bundle(&bundle, &digest)?)
+    }
+}
@@ -6,10 +6,15 @@
 //! building up a FogReportResponses object needed to create a transaction
 //! with fog recipients.
 
+mod http;
+
+pub use http::HttpFogReportConnection;
+
 use displaydoc::Display;
 use grpcio::{CallOption, ChannelBuilder, Environment, MetadataBuilder};
 use mc_common::logger::{log, o, Logger};
 use mc_fog_report_api::{report::ReportRequest, report_grpc};
+use mc_fog_report_resolver::ReportBundleError;
 use mc_fog_report_types::ReportResponse;
 use mc_util_grpc::{ConnectionUriGrpcioChannel, CHAIN_ID_GRPC_HEADER};
 use mc_util_uri::FogUri;
@@ -63,15 +68,7 @@ impl GrpcFogReportConnection {
         responses: &mut FogReportResponses,
         uris: impl Iterator<Item = FogUri>,
     ) -> Result<(), Error> {
-        for uri in uris {
-            match responses.entry(uri.to_string()) {
-                std::collections::btree_map::Entry::Occupied(_) => {}
-                std::collections::btree_map::Entry::Vacant(ent) => {
-                    ent.insert(self.fetch_fog_report(&uri)?);
-                }
-            }
-        }
-        Ok(())
+        fetch_fog_reports_if_not_cached(responses, uris, |uri| self.fetch_fog_report(uri))
     }
 
     /// Given a fog report uri, fetch its response over grpc, or return an
@@ -112,13 +109,40 @@ impl GrpcFogReportConnection {
     }
 }
 
-/// Errors that can occur during GrpcFogReportConnection operation
+/// Fetch the fog reports for each uri that isn't already in `responses`,
+/// using `fetch_fog_report`.
+fn fetch_fog_reports_if_not_cached(
+    responses: &mut FogReportResponses,
+    uris: impl Iterator<Item = FogUri>,
+    fetch_fog_report: impl Fn(&FogUri) -> Result<ReportResponse, Error>,
+) -> Result<(), Error> {
+    for uri in uris {
+        match responses.entry(uri.to_string()) {
+            std::collections::btree_map::Entry::Occupied(_) => {}
+            std::collections::btree_map::Entry::Vacant(ent) => {
+                ent.insert(fetch_fog_report(&uri)?);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Errors that can occur during GrpcFogReportConnection or
+/// HttpFogReportConnection operation
 #[derive(Debug, Display)]
 pub enum Error {
     /// grpc failure: {0}
     Rpc(grpcio::Error),
     /// Fog Report Server has no available reports: {0}
     NoReports(FogUri),
+    /// http failure: {0}
+    Http(reqwest::Error),
+    /// No HTTP base url is configured for fog report uri: {0}
+    NoBaseUrl(FogUri),
+    /// Report bundle url does not end in a digest: {0}
+    BundleDigest(String),
+    /// Report bundle failed verification: {0}
+    Bundle(ReportBundleError),
 }
 
 impl From<grpcio::Error> for Error {
@@ -126,3 +150,15 @@ impl From<grpcio::Error> for Error {
         Self::Rpc(src)
     }
 }
+
+impl From<reqwest::Error> for Error {
+    fn from(src: reqwest::Error) -> Self {
+        Self::Http(src)
+    }
+}
+
+impl From<ReportBundleError> for Error {
+    fn from(src: ReportBundleError) -> Self {
+        Self::Bundle(src)
+    }
+}
@@ -0,0 +1,65 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! Verification of report bundles, which the fog report server serves over
+//! HTTP as an alternative to its gRPC API.
+
+use displaydoc::Display;
+use mc_fog_report_types::{report_bundle_digest, ReportResponse};
+use prost::{DecodeError, Message};
+
+/// An error that can occur when verifying a report bundle
+#[derive(Debug, Display)]
+pub enum ReportBundleError {
+    /// The report bundle does not match its content address
+    DigestMismatch,
+    /// The report bundle could not be decoded: {0}
+    Decode(DecodeError),
+}
+
+impl From<DecodeError> for ReportBundleError {
+    fn from(src: DecodeError) -> Self {
+        Self::Decode(src)
+    }
+}
+
+/// Check that a report bundle matches the content address it was fetched from,
+/// and decode it.
+///
+/// This only establishes that the bundle is the one the server published under
+/// that address. As with responses fetched over gRPC, the signature over the
+/// reports is checked against each recipient's fog authority when resolving
+/// their fog pubkey.
+pub fn verify_report_bundle(
+    bundle: &[u8],
+    digest: &[u8; 32],
+) -> Result<ReportResponse, ReportBundleError> {
+    if &report_bundle_digest(bundle) != digest {
+        return Err(ReportBundleError::DigestMismatch);
+    }
+    Ok(ReportResponse::decode(bundle)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_report_bundle_checks_digest() {
+        let response = ReportResponse {
+            reports: Default::default(),
+            chain: vec![b"cert".to_vec()],
+            signature: b"signature".to_vec(),
+        };
+        let bundle = response.encode_to_vec();
+        let digest = report_bundle_digest(&bundle);
+
+        assert_eq!(verify_report_bundle(&bundle, &digest).unwrap(), response);
+
+        let mut tampered = bundle.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(matches!(
+            verify_report_bundle(&tampered, &digest),
+            Err(ReportBundleError::DigestMismatch)
+        ));
+    }
+}
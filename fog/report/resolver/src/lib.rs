@@ -7,6 +7,10 @@
 
 extern crate alloc;
 
+mod bundle;
+
+pub use bundle::{verify_report_bundle, ReportBundleError};
+
 use mc_fog_report_validation::{FogPubkeyError, FogPubkeyResolver, FullyValidatedFogPubkey};
 
 use mc_fog_ingest_report::IngestReportVerifier;
@@ -3,7 +3,7 @@
 //! Main Method for the Fog Report Server
 
 use mc_common::{logger, sentry};
-use mc_fog_report_server::{Config, Materials, Server};
+use mc_fog_report_server::{Config, HttpServer, Materials, Server};
 use mc_fog_sql_recovery_db::SqlRecoveryDb;
 use mc_util_cli::ParserWithBuildInfo;
 use mc_util_grpc::AdminServer;
@@ -32,6 +32,17 @@ fn main() {
         )
     });
 
+    let _http_server = config.http_listen_addr.map(|http_listen_addr| {
+        HttpServer::start(
+            db.clone(),
+            config.chain_id.clone(),
+            http_listen_addr,
+            config.http_index_max_age,
+            materials.clone(),
+            logger.clone(),
+        )
+    });
+
     let mut server = Server::new(
         db,
         config.chain_id.clone(),
@@ -8,10 +8,14 @@ use displaydoc::Display;
 use mc_crypto_keys::{DistinguishedEncoding, Ed25519Pair, Ed25519Private, Ed25519Public, KeyError};
 use mc_crypto_x509_utils::{ChainError, X509CertificateChain, X509CertificateIter};
 use mc_fog_sql_recovery_db::SqlRecoveryDbConnectionConfig;
+use mc_util_parse::parse_duration_in_seconds;
 use mc_util_uri::{AdminUri, FogUri};
 use pem::PemError;
 use serde::Serialize;
-use std::{fs, io::Error as IoError, path::PathBuf, result::Result as StdResult};
+use std::{
+    fs, io::Error as IoError, net::SocketAddr, path::PathBuf, result::Result as StdResult,
+    time::Duration,
+};
 use x509_signature::X509Certificate;
 
 /// Configuration options for the report server
@@ -30,6 +34,16 @@ pub struct Config {
     #[clap(long, env = "MC_CLIENT_LISTEN_URI")]
     pub client_listen_uri: FogUri,
 
+    /// Address to serve report bundles over HTTP on, for clients that can't
+    /// use gRPC. If unset, reports are only served over gRPC.
+    #[clap(long, env = "MC_HTTP_LISTEN_ADDR")]
+    pub http_listen_addr: Option<SocketAddr>,
+
+    /// How long, in seconds, caches may keep the HTTP redirect to the current
+    /// report bundle.
+    #[clap(long, default_value = "10", value_parser = parse_duration_in_seconds, env = "MC_HTTP_INDEX_MAX_AGE")]
+    pub http_index_max_age: Duration,
+
     /// Internal admin server used for metrics/debugging.
     #[clap(long, env = "MC_ADMIN_LISTEN_URI")]
     pub admin_listen_uri: Option<AdminUri>,
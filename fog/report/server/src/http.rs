@@ -0,0 +1,278 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! HTTP server for report bundles.
+//!
+//! This serves the same signed [ReportResponse] as the gRPC API, encoded with
+//! prost, for clients that can't use gRPC. Bundles are content-addressed so
+//! that they can be cached by a CDN:
+//! * `GET /reports` redirects to the current bundle. This response may only be
+//!   cached for a short time.
+//! * `GET /reports/<digest>` serves the bundle whose SHA-256 digest has the
+//!   given hex encoding. This response never changes, so it may be cached
+//!   forever.
+//!
+//! Report signatures are deterministic, so every report server with the same
+//! materials and database state serves the same bundle under the same digest.
+
+use crate::{config::Materials, service::Service};
+use mc_common::logger::{log, Logger};
+use mc_fog_recovery_db_iface::ReportDb;
+use mc_fog_report_types::{report_bundle_digest, ReportResponse};
+use prost::Message;
+use rocket::{
+    get,
+    http::{Header, Status},
+    response::Responder,
+    routes, Shutdown, State,
+};
+use std::{
+    collections::VecDeque,
+    net::SocketAddr,
+    sync::{mpsc, Arc, Mutex},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+/// How many recently served bundles to keep, so that clients that were
+/// redirected to a bundle can still fetch it after the reports change.
+const RECENT_BUNDLES: usize = 16;
+
+/// How long a freshly built bundle is assumed to still be current, when
+/// looking up a bundle that is not recent. This bounds how often requests for
+/// unknown digests make the server build a bundle.
+const CURRENT_BUNDLE_MAX_AGE: Duration = Duration::from_secs(1);
+
+/// The length of the hex encoding of a bundle digest.
+const DIGEST_HEX_LEN: usize = 64;
+
+/// A prost-encoded [ReportResponse], with its hex-encoded content address.
+#[derive(Clone)]
+struct ReportBundle {
+    digest: String,
+    bytes: Arc<Vec<u8>>,
+}
+
+/// Recently served bundles.
+#[derive(Default)]
+struct RecentBundles {
+    /// The bundles, newest first.
+    bundles: VecDeque<ReportBundle>,
+
+    /// When the current bundle was last built. It is one of the bundles.
+    last_built: Option<Instant>,
+}
+
+/// State shared by the HTTP routes.
+struct ReportBundleState {
+    /// Builds and encodes the current report response.
+    build_bundle: Box<dyn Fn() -> Result<Vec<u8>, String> + Send + Sync>,
+
+    /// Recently served bundles.
+    recent_bundles: Mutex<RecentBundles>,
+
+    /// How long the redirect to the current bundle may be cached.
+    index_max_age: Duration,
+
+    logger: Logger,
+}
+
+impl ReportBundleState {
+    /// Build the current bundle, and remember it.
+    fn current_bundle(&self) -> Result<ReportBundle, String> {
+        let bytes = (self.build_bundle)().map_err(|err| {
+            log::error!(self.logger, "Could not build report bundle: {}", err);
+            err
+        })?;
+        let digest = hex::encode(report_bundle_digest(&bytes));
+
+        let mut recent_bundles = self.recent_bundles.lock().expect("mutex poisoned");
+        recent_bundles.last_built = Some(Instant::now());
+        if let Some(bundle) = recent_bundles
+            .bundles
+            .iter()
+            .find(|bundle| bundle.digest == digest)
+        {
+            return Ok(bundle.clone());
+        }
+        let bundle = ReportBundle {
+            digest,
+            bytes: Arc::new(bytes),
+        };
+        recent_bundles.bundles.push_front(bundle.clone());
+        recent_bundles.bundles.truncate(RECENT_BUNDLES);
+        Ok(bundle)
+    }
+
+    /// Find the bundle with the given digest, if it is recent or current.
+    fn find_bundle(&self, digest: &str) -> Result<Option<ReportBundle>, String> {
+        if !is_digest_hex(digest) {
+            return Ok(None);
+        }
+        {
+            let recent_bundles = self.recent_bundles.lock().expect("mutex poisoned");
+            if let Some(bundle) = recent_bundles
+                .bundles
+                .iter()
+                .find(|bundle| bundle.digest == digest)
+            {
+                return Ok(Some(bundle.clone()));
+            }
+            // Another report server may have redirected the client here. If the
+            // current bundle was built very recently, it is one of the recent
+            // bundles, so there is no need to build it again.
+            if recent_bundles
+                .last_built
+                .map_or(false, |built| built.elapsed() < CURRENT_BUNDLE_MAX_AGE)
+            {
+                return Ok(None);
+            }
+        }
+        let bundle = self.current_bundle()?;
+        Ok((bundle.digest == digest).then(|| bundle))
+    }
+}
+
+/// Whether a string is a lowercase hex-encoded digest, as bundles are served
+/// under.
+fn is_digest_hex(digest: &str) -> bool {
+    digest.len() == DIGEST_HEX_LEN
+        && digest
+            .bytes()
+            .all(|byte| matches!(byte, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+/// A redirect to the current bundle.
+#[derive(Responder)]
+#[response(status = 302)]
+struct BundleRedirect {
+    inner: (),
+    location: Header<'static>,
+    cache_control: Header<'static>,
+}
+
+/// A bundle, which never changes.
+#[derive(Responder)]
+#[response(content_type = "application/x-protobuf")]
+struct BundleContents {
+    inner: Vec<u8>,
+    cache_control: Header<'static>,
+    etag: Header<'static>,
+}
+
+#[get("/reports")]
+fn get_reports(state: &State<ReportBundleState>) -> Result<BundleRedirect, Status> {
+    let bundle = state
+        .current_bundle()
+        .map_err(|_| Status::InternalServerError)?;
+    Ok(BundleRedirect {
+        inner: (),
+        location: Header::new("Location", format!("/reports/{}", bundle.digest)),
+        cache_control: Header::new(
+            "Cache-Control",
+            format!("public, max-age={}", state.index_max_age.as_secs()),
+        ),
+    })
+}
+
+#[get("/reports/<digest>")]
+fn get_report_bundle(
+    state: &State<ReportBundleState>,
+    digest: &str,
+) -> Result<Option<BundleContents>, Status> {
+    let bundle = state
+        .find_bundle(digest)
+        .map_err(|_| Status::InternalServerError)?;
+    Ok(bundle.map(|bundle| BundleContents {
+        inner: bundle.bytes.as_ref().clone(),
+        cache_control: Header::new("Cache-Control", "public, max-age=31536000, immutable"),
+        etag: Header::new("ETag", format!("\"{}\"", bundle.digest)),
+    }))
+}
+
+/// The HTTP server for report bundles, which runs on its own thread.
+pub struct HttpServer {
+    shutdown: Shutdown,
+    join_handle: Option<JoinHandle<()>>,
+    logger: Logger,
+}
+
+impl HttpServer {
+    /// Start serving report bundles on the given address.
+    ///
+    /// `index_max_age` is how long caches may keep the redirect to the
+    /// current bundle, i.e. how stale the reports that clients see may be.
+    pub fn start(
+        db: impl ReportDb + Clone + Send + Sync + 'static,
+        chain_id: String,
+        listen_addr: SocketAddr,
+        index_max_age: Duration,
+        materials: Materials,
+        logger: Logger,
+    ) -> Self {
+        let service = Service::new(chain_id, db, materials, logger.clone());
+        let state = ReportBundleState {
+            build_bundle: Box::new(move || {
+                service
+                    .build_response()
+                    .map(|response: ReportResponse| response.encode_to_vec())
+                    .map_err(|err| err.to_string())
+            }),
+            recent_bundles: Default::default(),
+            index_max_age,
+            logger: logger.clone(),
+        };
+
+        let rocket_config = rocket::Config::figment()
+            .merge(("address", listen_addr.ip()))
+            .merge(("port", listen_addr.port()));
+        let rocket = rocket::custom(rocket_config)
+            .manage(state)
+            .mount("/", routes![get_reports, get_report_bundle]);
+
+        log::info!(
+            logger,
+            "Starting report bundle HTTP server on {}",
+            listen_addr
+        );
+        let (shutdown_tx, shutdown_rx) = mpsc::channel();
+        let thread_logger = logger.clone();
+        let join_handle = thread::Builder::new()
+            .name("ReportServer-HTTP".to_string())
+            .spawn(move || {
+                let result = rocket::execute(async move {
+                    let rocket = rocket.ignite().await?;
+                    let _ = shutdown_tx.send(rocket.shutdown());
+                    rocket.launch().await
+                });
+                if let Err(err) = result {
+                    log::error!(thread_logger, "Report bundle HTTP server failed: {}", err);
+                }
+            })
+            .expect("Could not spawn report bundle HTTP server thread");
+        let shutdown = shutdown_rx
+            .recv()
+            .expect("Report bundle HTTP server failed to start");
+
+        Self {
+            shutdown,
+            join_handle: Some(join_handle),
+            logger,
+        }
+    }
+
+    /// Stop the server, and wait for its thread to finish.
+    pub fn stop(&mut self) {
+        if let Some(join_handle) = self.join_handle.take() {
+            self.shutdown.clone().notify();
+            if join_handle.join().is_err() {
+                log::error!(self.logger, "Report bundle HTTP server thread panicked");
+            }
+        }
+    }
+}
+
+impl Drop for HttpServer {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
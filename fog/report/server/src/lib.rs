@@ -5,10 +5,12 @@
 #![deny(missing_docs)]
 
 mod config;
+mod http;
 mod server;
 mod service;
 
 pub use crate::{
     config::{Config, Error, Materials},
+    http::HttpServer,
     server::Server,
 };
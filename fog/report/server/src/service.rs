@@ -40,7 +40,7 @@ pub struct Service<R: ReportDb + Clone + Send + Sync> {
 /// An internal error type used to marshal DB and signature errors
 /// to RPC errors suitable for this service.
 #[derive(Clone, Debug, Display, Eq, PartialEq)]
-enum Error<E: RecoveryDbError> {
+pub(crate) enum Error<E: RecoveryDbError> {
     /// There was an error contacting the database: {0}
     Db(E),
     /// The data in the database could not be decoded: {0}
@@ -86,7 +86,7 @@ impl<R: ReportDb + Clone + Send + Sync> Service<R> {
 
     /// Loads report data from the database, signs it, and puts the results into
     /// constructs a new response structure.
-    fn build_response(&self) -> Result<ReportResponse, Error<R::Error>> {
+    pub(crate) fn build_response(&self) -> Result<ReportResponse, Error<R::Error>> {
         mc_common::trace_time!(self.logger, "Building prost response from report DB");
         let reports = self
             .report_db
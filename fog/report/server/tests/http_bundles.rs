@@ -0,0 +1,198 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+// Exercise the report server's HTTP report bundles and check that they match
+// the gRPC API
+
+use grpcio::ChannelBuilder;
+use mc_attest_core::VerificationReport;
+use mc_common::logger::{test_with_logger, Logger};
+use mc_crypto_keys::{CompressedRistrettoPublic, RistrettoPublic};
+use mc_fog_api::{report::ReportRequest as ProtobufReportRequest, report_grpc};
+use mc_fog_recovery_db_iface::{RecoveryDb, ReportData, ReportDb};
+use mc_fog_report_connection::{Error as ConnectionError, HttpFogReportConnection};
+use mc_fog_report_resolver::ReportBundleError;
+use mc_fog_report_server::{HttpServer, Materials, Server};
+use mc_fog_report_types::ReportResponse;
+use mc_fog_sql_recovery_db::test_utils::SqlRecoveryDbTestContext;
+use mc_fog_test_infra::db_tests::random_kex_rng_pubkey;
+use mc_util_from_random::FromRandom;
+use mc_util_grpc::ConnectionUriGrpcioChannel;
+use mc_util_uri::FogUri;
+use rand::{rngs::StdRng, SeedableRng};
+use reqwest::{blocking::Client, redirect::Policy, StatusCode};
+use std::{str::FromStr, sync::Arc, time::Duration};
+
+#[test_with_logger]
+fn report_server_http_bundle_tests(logger: Logger) {
+    let mut rng: StdRng = SeedableRng::from_seed([123u8; 32]);
+    let db_test_context = SqlRecoveryDbTestContext::new(logger.clone());
+
+    let db = db_test_context.get_db_instance();
+    let ingress_key = CompressedRistrettoPublic::from(&RistrettoPublic::from_random(&mut rng));
+    db.new_ingress_key(&ingress_key, 1).unwrap();
+    let invoc_id = db
+        .new_ingest_invocation(None, &ingress_key, &random_kex_rng_pubkey(&mut rng), 123)
+        .unwrap();
+
+    // Publish reports for two report ids.
+    let report_data = |http_body: &str, pubkey_expiry| ReportData {
+        ingest_invocation_id: Some(invoc_id),
+        report: VerificationReport {
+            sig: Default::default(),
+            chain: vec![b"asdf".to_vec(), b"jkl;".to_vec()],
+            http_body: http_body.to_string(),
+        },
+        pubkey_expiry,
+    };
+    db.set_report(&ingress_key, "", &report_data("body", 102030))
+        .unwrap();
+    db.set_report(&ingress_key, "report2", &report_data("other body", 405060))
+        .unwrap();
+
+    let (pem_chain, signing_keypair) = mc_crypto_x509_test_vectors::ok_rsa_chain_25519_leaf();
+    let materials = Materials::from_pem_keypair(pem_chain, signing_keypair)
+        .expect("Could not parse x509 test vectors key");
+
+    let client_uri = FogUri::from_str("insecure-fog://0.0.0.0:3410").unwrap();
+    let mut server = Server::new(
+        db.clone(),
+        "local".to_string(),
+        &client_uri,
+        materials.clone(),
+        logger.clone(),
+    );
+    server.start();
+    let _http_server = HttpServer::start(
+        db.clone(),
+        "local".to_string(),
+        "127.0.0.1:3411".parse().unwrap(),
+        Duration::from_secs(10),
+        materials,
+        logger.clone(),
+    );
+    let index_url = "http://127.0.0.1:3411/reports";
+
+    let env = Arc::new(grpcio::EnvBuilder::new().build());
+    let report_client = {
+        let ch = ChannelBuilder::default_channel_builder(env).connect_to_uri(&client_uri, &logger);
+        report_grpc::ReportApiClient::new(ch)
+    };
+    let grpc_response = ReportResponse::from(
+        report_client
+            .get_reports(&ProtobufReportRequest::new())
+            .unwrap(),
+    );
+    assert_eq!(grpc_response.reports.len(), 2);
+
+    // The bundle is the same signed response as over gRPC.
+    let connection = HttpFogReportConnection::new(logger.clone());
+    let bundle_response = connection
+        .fetch_report_bundle(index_url)
+        .expect("Could not fetch report bundle");
+    assert_eq!(bundle_response, grpc_response);
+
+    // Fetching by fog report uri requires its HTTP base url, since the HTTP
+    // server does not listen on the gRPC port.
+    assert!(matches!(
+        connection.fetch_fog_report(&client_uri),
+        Err(ConnectionError::NoBaseUrl(_))
+    ));
+    let uri_connection = HttpFogReportConnection::new(logger.clone())
+        .with_base_url(&client_uri, "http://127.0.0.1:3411/");
+    assert_eq!(
+        uri_connection
+            .fetch_fog_report(&client_uri)
+            .expect("Could not fetch fog report"),
+        grpc_response
+    );
+    let responses = uri_connection
+        .fetch_fog_reports([client_uri.clone(), client_uri.clone()].into_iter())
+        .expect("Could not fetch fog reports");
+    assert_eq!(responses.len(), 1);
+    assert_eq!(responses[&client_uri.to_string()], grpc_response);
+
+    // Check the redirect and caching headers.
+    let client = Client::builder().redirect(Policy::none()).build().unwrap();
+    let redirect = client.get(index_url).send().unwrap();
+    assert_eq!(redirect.status(), StatusCode::FOUND);
+    assert_eq!(
+        redirect.headers()["Cache-Control"].to_str().unwrap(),
+        "public, max-age=10"
+    );
+    let bundle_path = redirect.headers()["Location"].to_str().unwrap().to_string();
+    let bundle_url = format!("http://127.0.0.1:3411{}", bundle_path);
+
+    let bundle = client.get(&bundle_url).send().unwrap();
+    assert_eq!(bundle.status(), StatusCode::OK);
+    assert_eq!(
+        bundle.headers()["Cache-Control"].to_str().unwrap(),
+        "public, max-age=31536000, immutable"
+    );
+    assert_eq!(
+        bundle.headers()["Content-Type"].to_str().unwrap(),
+        "application/x-protobuf"
+    );
+
+    // Updating a report changes the bundle, but the old one is still served.
+    db.set_report(&ingress_key, "", &report_data("new body", 708090))
+        .unwrap();
+    let new_redirect = client.get(index_url).send().unwrap();
+    assert_ne!(
+        new_redirect.headers()["Location"].to_str().unwrap(),
+        bundle_path
+    );
+    let new_bundle_response = connection
+        .fetch_report_bundle(index_url)
+        .expect("Could not fetch report bundle");
+    assert_eq!(new_bundle_response.reports[0].pubkey_expiry, 708090);
+    assert_eq!(
+        client.get(&bundle_url).send().unwrap().status(),
+        StatusCode::OK
+    );
+
+    // Unknown bundles are not found.
+    let unknown_url = format!("http://127.0.0.1:3411/reports/{}", hex::encode([0u8; 32]));
+    assert_eq!(
+        client.get(&unknown_url).send().unwrap().status(),
+        StatusCode::NOT_FOUND
+    );
+    assert!(matches!(
+        connection.fetch_report_bundle(&unknown_url),
+        Err(ConnectionError::Http(_))
+    ));
+
+    // So are paths that are not a lowercase hex digest, including the digest of
+    // a known bundle in uppercase.
+    let known_digest = bundle_path.rsplit('/').next().unwrap();
+    for malformed in [
+        "not-a-digest".to_string(),
+        known_digest[1..].to_string(),
+        format!("{}0", known_digest),
+        known_digest.to_uppercase(),
+    ] {
+        let malformed_url = format!("http://127.0.0.1:3411/reports/{}", malformed);
+        assert_eq!(
+            client.get(&malformed_url).send().unwrap().status(),
+            StatusCode::NOT_FOUND,
+            "{}",
+            malformed
+        );
+    }
+
+    // A bundle served under the wrong digest is rejected.
+    let mismatched_url = format!(
+        "http://127.0.0.1:3411{}",
+        new_redirect.headers()["Location"].to_str().unwrap()
+    );
+    let old_bundle = client.get(&bundle_url).send().unwrap().bytes().unwrap();
+    let mut mismatched_digest = [0u8; 32];
+    hex::decode_to_slice(
+        mismatched_url.rsplit('/').next().unwrap(),
+        &mut mismatched_digest,
+    )
+    .unwrap();
+    assert!(matches!(
+        mc_fog_report_resolver::verify_report_bundle(&old_bundle, &mismatched_digest),
+        Err(ReportBundleError::DigestMismatch)
+    ));
+}
@@ -14,6 +14,7 @@ use mc_attest_core::VerificationReport;
 use mc_crypto_digestible::Digestible;
 use prost::Message;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
 /// A fog report from the report server
 #[derive(Clone, Digestible, Eq, PartialEq, Serialize, Deserialize, Message)]
@@ -47,6 +48,16 @@ pub struct ReportResponse {
     pub signature: Vec<u8>,
 }
 
+/// The content address of a report bundle, i.e. a prost-encoded
+/// [ReportResponse] as served by the fog report server over HTTP.
+///
+/// This is the SHA-256 digest of the encoded bytes. The server serves each
+/// bundle under the hex encoding of its digest, so that clients can check that
+/// a bundle wasn't altered in transit, e.g. by a CDN.
+pub fn report_bundle_digest(bundle: &[u8]) -> [u8; 32] {
+    Sha256::digest(bundle).into()
+}
+
 /// Represents a set of unvalidated responses from Fog report servers
 /// Key = Fog-url that was contacted, must match the string in user's public
 /// address Value = The complete response from the fog report server
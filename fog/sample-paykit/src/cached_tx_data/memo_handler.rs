@@ -3,12 +3,15 @@
 //! A memo handler object which processes memos, for use in integration tests
 
 use displaydoc::Display;
-use mc_account_keys::{AccountKey, PublicAddress, ShortAddressHash, CHANGE_SUBADDRESS_INDEX};
+use mc_account_keys::{AccountKey, PublicAddress, ShortAddressHash, DEFAULT_SUBADDRESS_INDEX};
 use mc_common::logger::{log, Logger};
-use mc_crypto_keys::{KeyError, RistrettoPublic};
-use mc_transaction_core::{get_tx_out_shared_secret, subaddress_matches_tx_out, tx::TxOut};
-use mc_transaction_extra::{MemoDecodingError, MemoType};
-use std::collections::HashMap;
+use mc_crypto_keys::KeyError;
+use mc_transaction_core::tx::TxOut;
+use mc_transaction_extra::{
+    MemoClassification, MemoDecodingError, MemoHandler as ContactsMemoHandler,
+    MemoHandlerError as ContactsMemoHandlerError, MemoType,
+};
+use std::collections::BTreeMap;
 
 /// A handler object that holds a contacts list and tries to recieve and
 /// authenticate memos. It provides the "get_last_memo" function which can be
@@ -18,7 +21,7 @@ use std::collections::HashMap;
 /// This is useful for test code.
 #[derive(Debug, Clone)]
 pub struct MemoHandler {
-    contacts: HashMap<ShortAddressHash, PublicAddress>,
+    inner: ContactsMemoHandler<BTreeMap<ShortAddressHash, PublicAddress>>,
     last_memo: Result<Option<MemoType>, MemoHandlerError>,
     logger: Logger,
 }
@@ -27,10 +30,12 @@ impl MemoHandler {
     /// Make a new memo handler with a given set of contacts
     pub fn new(address_book: Vec<PublicAddress>, logger: Logger) -> Self {
         Self {
-            contacts: address_book
-                .into_iter()
-                .map(|addr| (ShortAddressHash::from(&addr), addr))
-                .collect(),
+            inner: ContactsMemoHandler::new(
+                address_book
+                    .into_iter()
+                    .map(|addr| (ShortAddressHash::from(&addr), addr))
+                    .collect(),
+            ),
             last_memo: Ok(None),
             logger,
         }
@@ -53,102 +58,36 @@ impl MemoHandler {
         tx_out: &TxOut,
         account_key: &AccountKey,
     ) -> Result<Option<MemoType>, MemoHandlerError> {
-        let decompressed_tx_pub = RistrettoPublic::try_from(&tx_out.public_key)?;
-        let shared_secret =
-            get_tx_out_shared_secret(account_key.view_private_key(), &decompressed_tx_pub);
-
-        let memo_payload = tx_out.decrypt_memo(&shared_secret);
-
-        let memo_type = MemoType::try_from(&memo_payload)?;
-        log::trace!(self.logger, "Obtained a memo: {:?}", memo_type);
-        match memo_type.clone() {
-            MemoType::Unused(_) => Ok(None),
-            MemoType::BurnRedemption(_) => {
-                // TODO: For now we are not validating anything with burn redemption memos.
-                // Right now the memo data is unstructured, so there's nothing
-                // to verify there. In theory we should only find this type of
-                // memo on a the burn account, which cannot be used with the sample paykit since
-                // the spend key is unknown.
-                Ok(Some(memo_type))
-            }
-            MemoType::AuthenticatedSender(memo) => {
-                if let Some(addr) = self.contacts.get(&memo.sender_address_hash()) {
-                    if bool::from(memo.validate(
-                        addr,
-                        &account_key.default_subaddress_view_private(),
-                        &tx_out.public_key,
-                    )) {
-                        Ok(Some(memo_type))
-                    } else {
-                        Err(MemoHandlerError::FailedHmacValidation)
-                    }
-                } else {
-                    Err(MemoHandlerError::UnknownSender)
-                }
-            }
-            MemoType::AuthenticatedSenderWithPaymentRequestId(memo) => {
-                if let Some(addr) = self.contacts.get(&memo.sender_address_hash()) {
-                    if bool::from(memo.validate(
-                        addr,
-                        &account_key.default_subaddress_view_private(),
-                        &tx_out.public_key,
-                    )) {
-                        Ok(Some(memo_type))
-                    } else {
-                        Err(MemoHandlerError::FailedHmacValidation)
-                    }
-                } else {
-                    Err(MemoHandlerError::UnknownSender)
+        let handled = self
+            .inner
+            .handle_memo(tx_out, account_key, DEFAULT_SUBADDRESS_INDEX)?;
+        log::trace!(self.logger, "Obtained a memo: {:?}", handled.memo_type);
+        match handled.classification {
+            MemoClassification::Authenticated => Ok(Some(handled.memo_type)),
+            MemoClassification::UnknownSender => Err(MemoHandlerError::UnknownSender),
+            MemoClassification::Unauthenticated => match handled.memo_type {
+                MemoType::Unused(_) => Ok(None),
+                MemoType::AuthenticatedSender(_)
+                | MemoType::AuthenticatedSenderWithPaymentRequestId(_)
+                | MemoType::AuthenticatedSenderWithPaymentIntentId(_)
+                | MemoType::AuthenticatedSenderWithInvoice(_) => {
+                    Err(MemoHandlerError::FailedHmacValidation)
                 }
-            }
-            MemoType::Destination(_) => {
-                if subaddress_matches_tx_out(account_key, CHANGE_SUBADDRESS_INDEX, tx_out)? {
-                    Ok(Some(memo_type))
-                } else {
+                MemoType::Destination(_)
+                | MemoType::DestinationWithPaymentRequestId(_)
+                | MemoType::DestinationWithPaymentIntentId(_)
+                | MemoType::DestinationWithInvoice(_) => {
                     Err(MemoHandlerError::FailedSubaddressValidation)
                 }
-            }
-            MemoType::GiftCodeCancellation(_) => {
                 // TODO: Add Gift Code Memo Validation
-                Ok(Some(memo_type))
-            }
-            MemoType::GiftCodeFunding(_) => {
-                // TODO: Add Gift Code Memo Validation
-                Ok(Some(memo_type))
-            }
-            MemoType::GiftCodeSender(_) => {
-                // TODO: Add Gift Code Validation
-                Ok(Some(memo_type))
-            }
-            MemoType::AuthenticatedSenderWithPaymentIntentId(memo) => {
-                if let Some(addr) = self.contacts.get(&memo.sender_address_hash()) {
-                    if bool::from(memo.validate(
-                        addr,
-                        &account_key.default_subaddress_view_private(),
-                        &tx_out.public_key,
-                    )) {
-                        Ok(Some(memo_type))
-                    } else {
-                        Err(MemoHandlerError::FailedHmacValidation)
-                    }
-                } else {
-                    Err(MemoHandlerError::UnknownSender)
-                }
-            }
-            MemoType::DestinationWithPaymentRequestId(_) => {
-                if subaddress_matches_tx_out(account_key, CHANGE_SUBADDRESS_INDEX, tx_out)? {
-                    Ok(Some(memo_type))
-                } else {
-                    Err(MemoHandlerError::FailedSubaddressValidation)
-                }
-            }
-            MemoType::DestinationWithPaymentIntentId(_) => {
-                if subaddress_matches_tx_out(account_key, CHANGE_SUBADDRESS_INDEX, tx_out)? {
-                    Ok(Some(memo_type))
-                } else {
-                    Err(MemoHandlerError::FailedSubaddressValidation)
-                }
-            }
+                // For now we are not validating anything with burn redemption memos.
+                // Right now the memo data is unstructured, so there's nothing
+                // to verify there.
+                MemoType::BurnRedemption(_)
+                | MemoType::GiftCodeCancellation(_)
+                | MemoType::GiftCodeFunding(_)
+                | MemoType::GiftCodeSender(_) => Ok(Some(handled.memo_type)),
+            },
         }
     }
 }
@@ -172,14 +111,11 @@ pub enum MemoHandlerError {
     MemoDecode(MemoDecodingError),
 }
 
-impl From<KeyError> for MemoHandlerError {
-    fn from(src: KeyError) -> Self {
-        Self::Key(src)
-    }
-}
-
-impl From<MemoDecodingError> for MemoHandlerError {
-    fn from(src: MemoDecodingError) -> Self {
-        Self::MemoDecode(src)
+impl From<ContactsMemoHandlerError> for MemoHandlerError {
+    fn from(src: ContactsMemoHandlerError) -> Self {
+        match src {
+            ContactsMemoHandlerError::Key(err) => Self::Key(err),
+            ContactsMemoHandlerError::MemoDecode(err) => Self::MemoDecode(err),
+        }
     }
 }
@@ -0,0 +1,150 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+#![no_std]
+
+//! Constant-time merging of the responses of the stores a fog router fans a
+//! request out to.
+//!
+//! Each store answers every query in the request, but at most one store holds
+//! the answer to a given query. The merge must not reveal (via branches or
+//! memory access patterns) which store, if any, found an answer, so the
+//! results for each query are combined with constant-time conditional
+//! assignments. Only the layout of the results is specific to each router.
+
+#![deny(missing_docs)]
+
+extern crate alloc;
+
+use aligned_cmov::subtle::{Choice, ConstantTimeEq};
+use alloc::vec::Vec;
+use displaydoc::Display;
+
+/// Shard responses did not answer the same queries
+#[derive(Clone, Copy, Debug, Display, Eq, PartialEq)]
+pub struct ShardResponseMismatch;
+
+/// Merge the results of several store responses.
+///
+/// Every shard must answer the same queries in the same order, as returned by
+/// `query`. The results for each query are folded into the first shard's
+/// result with `merge`, which must merge its second argument into its first
+/// in constant time.
+pub fn collate_shard_results<T, Q, F, M>(
+    shard_results: Vec<Vec<T>>,
+    query: F,
+    merge: M,
+) -> Result<Vec<T>, ShardResponseMismatch>
+where
+    Q: Eq + ?Sized,
+    F: Fn(&T) -> &Q,
+    M: Fn(&mut T, &T),
+{
+    let mut shards = shard_results.into_iter();
+    let first = match shards.next() {
+        Some(first) => first,
+        None => return Ok(Vec::new()),
+    };
+    let rest: Vec<Vec<T>> = shards.collect();
+
+    // The number of results and the queries are not secret: they are chosen
+    // by the client and visible to every store.
+    for shard in rest.iter() {
+        if shard.len() != first.len()
+            || shard
+                .iter()
+                .zip(first.iter())
+                .any(|(a, b)| query(a) != query(b))
+        {
+            return Err(ShardResponseMismatch);
+        }
+    }
+
+    Ok(first
+        .into_iter()
+        .enumerate()
+        .map(|(idx, mut result)| {
+            for shard in rest.iter() {
+                merge(&mut result, &shard[idx]);
+            }
+            result
+        })
+        .collect())
+}
+
+/// Whether a result with `other_code` should replace a result with
+/// `acc_code`, in constant time.
+///
+/// A `found` result from any shard wins. Otherwise any other code (an error)
+/// wins over `not_found`.
+pub fn take_other_result(acc_code: u32, other_code: u32, found: u32, not_found: u32) -> Choice {
+    other_code.ct_eq(&found) | (acc_code.ct_eq(&not_found) & !other_code.ct_eq(&not_found))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aligned_cmov::CMov;
+    use alloc::vec;
+
+    const FOUND: u32 = 0;
+    const NOT_FOUND: u32 = 1;
+    const ERROR: u32 = 2;
+
+    /// A (query, result code, value) result.
+    type TestResult = (u8, u32, u64);
+
+    fn collate(
+        shard_results: Vec<Vec<TestResult>>,
+    ) -> Result<Vec<TestResult>, ShardResponseMismatch> {
+        collate_shard_results(
+            shard_results,
+            |result| &result.0,
+            |acc, other| {
+                let take_other = take_other_result(acc.1, other.1, FOUND, NOT_FOUND);
+                acc.1.cmov(take_other, &other.1);
+                acc.2.cmov(take_other, &other.2);
+            },
+        )
+    }
+
+    #[test]
+    fn found_result_wins() {
+        assert_eq!(
+            collate(vec![
+                vec![(1, NOT_FOUND, 0), (2, FOUND, 7), (3, ERROR, 0)],
+                vec![(1, FOUND, 9), (2, NOT_FOUND, 0), (3, FOUND, 5)],
+                vec![(1, ERROR, 0), (2, ERROR, 0), (3, NOT_FOUND, 0)],
+            ]),
+            Ok(vec![(1, FOUND, 9), (2, FOUND, 7), (3, FOUND, 5)])
+        );
+    }
+
+    #[test]
+    fn errors_win_over_not_found() {
+        assert_eq!(
+            collate(vec![
+                vec![(1, NOT_FOUND, 0)],
+                vec![(1, ERROR, 3)],
+                vec![(1, NOT_FOUND, 4)],
+            ]),
+            Ok(vec![(1, ERROR, 3)])
+        );
+    }
+
+    #[test]
+    fn no_shards() {
+        assert_eq!(collate(vec![]), Ok(vec![]));
+    }
+
+    #[test]
+    fn mismatched_shards_are_rejected() {
+        assert_eq!(
+            collate(vec![vec![(1, NOT_FOUND, 0)], vec![(2, NOT_FOUND, 0)]]),
+            Err(ShardResponseMismatch)
+        );
+        assert_eq!(
+            collate(vec![vec![(1, NOT_FOUND, 0)], vec![]]),
+            Err(ShardResponseMismatch)
+        );
+    }
+}
@@ -3,6 +3,8 @@
 //! Specifies configuration for the Fog SQL Recovery DB cleanup binary.
 
 use clap::Parser;
+use mc_fog_sql_recovery_db::SqlRecoveryDbConnectionConfig;
+use mc_fog_types::common::BlockRange;
 use serde::Serialize;
 
 /// Configuration parameters for the Fog SQL recovery DB cleanup task.
@@ -13,8 +15,47 @@ pub struct SqlRecoveryDbCleanupConfig {
     #[clap(long)]
     pub egress_keys: bool,
 
+    /// If set, moves the ETxOutRecords of all but this many most recent
+    /// blocks, and the user events only concerning the older blocks, to the
+    /// archive in --recovery-db-archive-dir.
+    #[clap(long, env = "MC_RETAIN_BLOCKS")]
+    pub retain_blocks: Option<u64>,
+
+    /// The number of blocks whose ETxOutRecords are stored in one archive
+    /// file.
+    #[clap(long, default_value = "10000", env = "MC_BLOCKS_PER_ARCHIVE_FILE")]
+    pub blocks_per_archive_file: u64,
+
+    /// If set, moves the archived ETxOutRecords of the blocks in this range,
+    /// e.g. 1000-2000, back into the database.
+    #[clap(long, value_parser = parse_block_range)]
+    pub restore_blocks: Option<BlockRange>,
+
+    /// If set to true, moves all archived user events back into the database.
+    #[clap(long)]
+    pub restore_user_events: bool,
+
     /// If set to true, prints out any DB entries that would be cleared by the
     /// command and doesn't execute the deletion.
     #[clap(long)]
     pub dry_run: bool,
+
+    /// Postgres config
+    #[clap(flatten)]
+    pub postgres_config: SqlRecoveryDbConnectionConfig,
+}
+
+/// Parse a half-open block range of the form START-END.
+fn parse_block_range(src: &str) -> Result<BlockRange, String> {
+    let (start, end) = src
+        .split_once('-')
+        .ok_or_else(|| format!("Expected a block range like 1000-2000, got {}", src))?;
+    let block_range = BlockRange::new(
+        start.parse().map_err(|err| format!("{}: {}", start, err))?,
+        end.parse().map_err(|err| format!("{}: {}", end, err))?,
+    );
+    if !block_range.is_valid() {
+        return Err(format!("Block range {} is empty", block_range));
+    }
+    Ok(block_range)
 }
@@ -6,6 +6,7 @@ use chrono::{prelude::*, Duration};
 use mc_common::logger::{log, Logger};
 use mc_fog_recovery_db_iface::RecoveryDb;
 use mc_fog_sql_recovery_db::SqlRecoveryDb;
+use mc_fog_types::common::BlockRange;
 
 /// Contains helper methods that cleanup the Fog SQL DB.
 pub struct DbCleaner {
@@ -71,4 +72,126 @@ impl DbCleaner {
             }
         }
     }
+
+    /// Moves the ETxOutRecords of all but the `retain_blocks` most recent
+    /// blocks, and the user events only concerning the older blocks, to the
+    /// archive, or prints out what would be archived.
+    pub fn archive(&self, is_dry_run: bool, retain_blocks: u64, blocks_per_archive_file: u64) {
+        let highest_known_block_count = self
+            .db
+            .get_highest_known_block_index()
+            .expect("Could not retrieve highest known block index.")
+            .map(|index| index + 1)
+            .unwrap_or(0);
+        let end_block = highest_known_block_count.saturating_sub(retain_blocks);
+
+        if is_dry_run {
+            match self
+                .db
+                .get_lowest_unarchived_block_index()
+                .expect("Could not retrieve lowest unarchived block index.")
+            {
+                Some(start_block) if start_block < end_block => log::info!(
+                    self.logger,
+                    "ETxOutRecords of blocks {} would be archived",
+                    BlockRange::new(start_block, end_block)
+                ),
+                _ => log::info!(self.logger, "There are no ETxOutRecords to archive"),
+            }
+
+            let user_events = self
+                .db
+                .get_archivable_user_events(end_block)
+                .expect("Could not retrieve archivable user events.");
+            log::info!(
+                self.logger,
+                "{} user events would be archived",
+                user_events.len()
+            );
+            for (id, user_event) in user_events.iter() {
+                log::info!(self.logger, "User event {}: {:?}", id, user_event);
+            }
+            return;
+        }
+
+        let archived_ranges = self
+            .db
+            .archive_blocks(end_block, blocks_per_archive_file)
+            .expect("Could not archive ETxOutRecords.");
+        log::info!(
+            self.logger,
+            "Archived ETxOutRecords of blocks before {} into {} files",
+            end_block,
+            archived_ranges.len()
+        );
+
+        let user_event_count = self
+            .db
+            .archive_user_events(end_block)
+            .expect("Could not archive user events.");
+        log::info!(self.logger, "Archived {} user events", user_event_count);
+    }
+
+    /// Moves the archived ETxOutRecords of the blocks in a range back into the
+    /// database, or prints out the archive files that would be restored.
+    pub fn restore_blocks(&self, is_dry_run: bool, block_range: &BlockRange) {
+        if is_dry_run {
+            let archive = self
+                .db
+                .archive()
+                .expect("Restoring blocks requires --recovery-db-archive-dir");
+            for file_range in archive
+                .block_files()
+                .expect("Could not list archive files.")
+                .into_iter()
+                .filter(|file_range| file_range.overlaps(block_range))
+            {
+                log::info!(
+                    self.logger,
+                    "ETxOutRecords of blocks {} would be restored",
+                    file_range
+                );
+            }
+            return;
+        }
+
+        let restored_ranges = self
+            .db
+            .restore_blocks(block_range)
+            .expect("Could not restore ETxOutRecords.");
+        log::info!(
+            self.logger,
+            "Restored ETxOutRecords of blocks {:?}",
+            restored_ranges
+        );
+    }
+
+    /// Moves all archived user events back into the database, or prints out
+    /// the archive files that would be restored.
+    pub fn restore_user_events(&self, is_dry_run: bool) {
+        if is_dry_run {
+            let archive = self
+                .db
+                .archive()
+                .expect("Restoring user events requires --recovery-db-archive-dir");
+            for id_range in archive
+                .user_event_files()
+                .expect("Could not list archive files.")
+            {
+                log::info!(
+                    self.logger,
+                    "User events with ids from {} to {} would be restored",
+                    id_range.start(),
+                    id_range.end()
+                );
+            }
+            return;
+        }
+
+        let user_event_count = self
+            .db
+            .restore_user_events()
+            .expect("Could not restore user events.");
+        log::info!(self.logger, "Restored {} user events", user_event_count);
+    }
 }
@@ -20,12 +20,32 @@ fn main() {
     let config = SqlRecoveryDbCleanupConfig::parse();
 
     let database_url = env::var("DATABASE_URL").expect("Missing DATABASE_URL environment variable");
-    let db = SqlRecoveryDb::new_from_url(&database_url, Default::default(), logger.clone())
-        .expect("failed connecting to database");
+    let db = SqlRecoveryDb::new_from_url(
+        &database_url,
+        config.postgres_config.clone(),
+        logger.clone(),
+    )
+    .expect("failed connecting to database");
 
     let db_cleaner = DbCleaner::new(db, logger);
 
     if config.egress_keys {
         db_cleaner.cleanup_egress_keys(config.dry_run, Duration::days(EXPIRATION_DAYS));
     }
+
+    if let Some(retain_blocks) = config.retain_blocks {
+        db_cleaner.archive(
+            config.dry_run,
+            retain_blocks,
+            config.blocks_per_archive_file,
+        );
+    }
+
+    if let Some(block_range) = config.restore_blocks.as_ref() {
+        db_cleaner.restore_blocks(config.dry_run, block_range);
+    }
+
+    if config.restore_user_events {
+        db_cleaner.restore_user_events(config.dry_run);
+    }
 }
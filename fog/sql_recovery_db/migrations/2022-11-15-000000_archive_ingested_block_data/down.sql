@@ -0,0 +1,18 @@
+-- Copyright (c) 2018-2022 The MobileCoin Foundation
+
+-- Reverting would lose track of archived data, so refuse while anything is
+-- archived. Restore it first with the mc-fog-sql-recovery-db-cleanup
+-- --restore-blocks and --restore-user-events options.
+DO $$
+BEGIN
+    IF EXISTS (SELECT 1 FROM ingested_blocks WHERE proto_ingested_block_data IS NULL) THEN
+        RAISE EXCEPTION 'Some ingested blocks are archived, restore them before reverting this migration';
+    END IF;
+    IF EXISTS (SELECT 1 FROM archived_user_event_files) THEN
+        RAISE EXCEPTION 'Some user events are archived, restore them before reverting this migration';
+    END IF;
+END $$;
+
+DROP INDEX idx_archived_user_event_files__last_user_event_id;
+DROP TABLE archived_user_event_files;
+ALTER TABLE ingested_blocks ALTER COLUMN proto_ingested_block_data SET NOT NULL;
@@ -0,0 +1,16 @@
+-- Copyright (c) 2018-2022 The MobileCoin Foundation
+
+-- The ETxOutRecords of old blocks can be moved out of the database, into the
+-- files of a recovery db archive. The rest of the ingested block row stays,
+-- with a NULL proto_ingested_block_data marking that the records are in the
+-- archive.
+ALTER TABLE ingested_blocks ALTER COLUMN proto_ingested_block_data DROP NOT NULL;
+
+-- Old user events can be moved to archive files as well. Each file is indexed
+-- here, in the transaction that deletes its events, so that searching user
+-- events only reads the files holding events the searcher hasn't seen.
+CREATE TABLE archived_user_event_files (
+    first_user_event_id BIGINT PRIMARY KEY,
+    last_user_event_id BIGINT NOT NULL
+);
+CREATE INDEX idx_archived_user_event_files__last_user_event_id ON archived_user_event_files (last_user_event_id);
@@ -0,0 +1,808 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! Moving the data of old blocks out of the recovery database and into
+//! compressed archive files, and back.
+//!
+//! Archiving a block only moves its ETxOutRecords, the rest of the
+//! ingested_blocks row stays in postgres with a NULL
+//! proto_ingested_block_data. This keeps block counts, last scanned blocks and
+//! ingestable ranges working as before. The ETxOutRecords are still served
+//! from the archive by the `get_tx_outs*` calls of a recovery db that has the
+//! archive configured.
+//!
+//! User events are archived once they no longer concern any block after the
+//! retention horizon, and are still returned by `search_user_events` of a
+//! recovery db that has the archive configured. Servers reading user events
+//! from the start (e.g. a freshly started fog view) need the archive to see
+//! them. The user event files are indexed in the archived_user_event_files
+//! table, so that searches past the archived events don't touch the archive.
+//!
+//! The archive_ingested_block_data migration can't be reverted while anything
+//! is archived: restore the blocks and user events first.
+
+use crate::{models, proto_types::*, schema, Error, SqlRecoveryDb, SQL_MAX_PARAMS, SQL_MAX_ROWS};
+use diesel::{expression::SqlLiteral, prelude::*, sql_types::Bool};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use mc_common::{logger::log, HashMap};
+use mc_fog_recovery_db_iface::FogUserEvent;
+use mc_fog_types::common::BlockRange;
+use prost::Message;
+use std::{
+    cmp::min,
+    fs::{self, File},
+    io::{Read, Write},
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+};
+
+const BLOCKS_FILE_PREFIX: &str = "blocks-";
+const USER_EVENTS_FILE_PREFIX: &str = "user-events-";
+const ARCHIVE_FILE_SUFFIX: &str = ".pb.gz";
+
+/// A directory of gzip compressed protobuf files, holding recovery db data
+/// that was moved out of postgres.
+///
+/// Block files are named after the half-open range of blocks they hold data
+/// for, and user event files after the inclusive range of user event ids they
+/// hold.
+#[derive(Clone, Debug)]
+pub struct RecoveryDbArchive {
+    dir: PathBuf,
+}
+
+impl RecoveryDbArchive {
+    /// Open the archive in a given directory, creating the directory if
+    /// needed.
+    pub fn new(dir: impl Into<PathBuf>) -> Result<Self, Error> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    /// The directory holding the archive files.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The block ranges of the block files in the archive, sorted.
+    pub fn block_files(&self) -> Result<Vec<BlockRange>, Error> {
+        let mut ranges: Vec<BlockRange> = self
+            .list_files(BLOCKS_FILE_PREFIX)?
+            .into_iter()
+            .map(|(start_block, end_block)| BlockRange::new(start_block, end_block))
+            .collect();
+        ranges.sort();
+        Ok(ranges)
+    }
+
+    /// The user event id ranges of the user event files in the archive,
+    /// sorted.
+    pub fn user_event_files(&self) -> Result<Vec<RangeInclusive<i64>>, Error> {
+        let mut ranges: Vec<RangeInclusive<i64>> = self
+            .list_files(USER_EVENTS_FILE_PREFIX)?
+            .into_iter()
+            .map(|(first_id, last_id)| first_id as i64..=last_id as i64)
+            .collect();
+        ranges.sort_by_key(|range| *range.start());
+        Ok(ranges)
+    }
+
+    fn read_blocks(&self, block_range: &BlockRange) -> Result<ProtoArchivedBlocks, Error> {
+        self.read_file(&Self::file_name(
+            BLOCKS_FILE_PREFIX,
+            block_range.start_block,
+            block_range.end_block,
+        ))
+    }
+
+    fn write_blocks(
+        &self,
+        block_range: &BlockRange,
+        blocks: &ProtoArchivedBlocks,
+    ) -> Result<(), Error> {
+        self.write_file(
+            &Self::file_name(
+                BLOCKS_FILE_PREFIX,
+                block_range.start_block,
+                block_range.end_block,
+            ),
+            blocks,
+        )
+    }
+
+    fn remove_blocks(&self, block_range: &BlockRange) -> Result<(), Error> {
+        Ok(fs::remove_file(self.dir.join(Self::file_name(
+            BLOCKS_FILE_PREFIX,
+            block_range.start_block,
+            block_range.end_block,
+        )))?)
+    }
+
+    fn read_user_events(
+        &self,
+        id_range: &RangeInclusive<i64>,
+    ) -> Result<ProtoArchivedUserEvents, Error> {
+        self.read_file(&Self::file_name(
+            USER_EVENTS_FILE_PREFIX,
+            *id_range.start() as u64,
+            *id_range.end() as u64,
+        ))
+    }
+
+    fn write_user_events(
+        &self,
+        id_range: &RangeInclusive<i64>,
+        events: &ProtoArchivedUserEvents,
+    ) -> Result<(), Error> {
+        self.write_file(
+            &Self::file_name(
+                USER_EVENTS_FILE_PREFIX,
+                *id_range.start() as u64,
+                *id_range.end() as u64,
+            ),
+            events,
+        )
+    }
+
+    fn remove_user_events(&self, id_range: &RangeInclusive<i64>) -> Result<(), Error> {
+        Ok(fs::remove_file(self.dir.join(Self::file_name(
+            USER_EVENTS_FILE_PREFIX,
+            *id_range.start() as u64,
+            *id_range.end() as u64,
+        )))?)
+    }
+
+    // Zero padding keeps the files sorted in directory listings.
+    fn file_name(prefix: &str, start: u64, end: u64) -> String {
+        format!("{}{:020}-{:020}{}", prefix, start, end, ARCHIVE_FILE_SUFFIX)
+    }
+
+    fn list_files(&self, prefix: &str) -> Result<Vec<(u64, u64)>, Error> {
+        let mut result = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let file_name = entry?.file_name().to_string_lossy().into_owned();
+            let range = match file_name
+                .strip_prefix(prefix)
+                .and_then(|rest| rest.strip_suffix(ARCHIVE_FILE_SUFFIX))
+            {
+                Some(range) => range,
+                // Not an archive file of this kind, e.g. a partially written one.
+                None => continue,
+            };
+
+            let (start, end) = range
+                .split_once('-')
+                .and_then(|(start, end)| Some((start.parse().ok()?, end.parse().ok()?)))
+                .ok_or_else(|| Error::InvalidArchiveFileName(file_name.clone()))?;
+            result.push((start, end));
+        }
+        Ok(result)
+    }
+
+    fn read_file<M: Message + Default>(&self, file_name: &str) -> Result<M, Error> {
+        let mut bytes = Vec::new();
+        GzDecoder::new(File::open(self.dir.join(file_name))?).read_to_end(&mut bytes)?;
+        Ok(M::decode(bytes.as_slice())?)
+    }
+
+    // Files are written under a temporary name and renamed once complete, so
+    // that a crash never leaves a truncated archive file behind.
+    fn write_file(&self, file_name: &str, message: &impl Message) -> Result<(), Error> {
+        let tmp_path = self.dir.join(format!("{}.tmp", file_name));
+
+        let mut encoder = GzEncoder::new(File::create(&tmp_path)?, Compression::default());
+        encoder.write_all(&message.encode_to_vec())?;
+        encoder.finish()?.sync_all()?;
+
+        fs::rename(&tmp_path, self.dir.join(file_name))?;
+        Ok(())
+    }
+}
+
+impl SqlRecoveryDb {
+    /// Use an archive to serve archived data from, and to archive data to.
+    pub fn with_archive(mut self, archive: RecoveryDbArchive) -> Self {
+        self.archive = Some(archive);
+        self
+    }
+
+    /// The archive this database serves archived data from, if any.
+    pub fn archive(&self) -> Option<&RecoveryDbArchive> {
+        self.archive.as_ref()
+    }
+
+    /// Get the lowest block index which still has its ETxOutRecords in the
+    /// database.
+    pub fn get_lowest_unarchived_block_index(&self) -> Result<Option<u64>, Error> {
+        let conn = self.pool.get()?;
+        Self::get_lowest_unarchived_block_index_impl(&conn)
+    }
+
+    /// Move the ETxOutRecords of all blocks before end_block to the archive,
+    /// in files of up to blocks_per_file blocks.
+    ///
+    /// Returns the block ranges of the archive files written.
+    pub fn archive_blocks(
+        &self,
+        end_block: u64,
+        blocks_per_file: u64,
+    ) -> Result<Vec<BlockRange>, Error> {
+        let archive = self
+            .archive
+            .as_ref()
+            .ok_or_else(|| Error::MissingArchive(BlockRange::new(0, end_block)))?;
+        let conn = self.pool.get()?;
+
+        let mut archived_ranges = Vec::new();
+        while let Some(start_block) = Self::get_lowest_unarchived_block_index_impl(&conn)? {
+            if start_block >= end_block {
+                break;
+            }
+            let block_range = BlockRange::new(
+                start_block,
+                min(start_block + blocks_per_file.max(1), end_block),
+            );
+
+            conn.build_transaction()
+                .read_write()
+                .run(|| -> Result<(), Error> {
+                    use schema::ingested_blocks::dsl;
+                    let rows: Vec<(i64, Vec<u8>, i64, Option<Vec<u8>>)> = dsl::ingested_blocks
+                        .filter(dsl::block_number.ge(block_range.start_block as i64))
+                        .filter(dsl::block_number.lt(block_range.end_block as i64))
+                        .filter(dsl::proto_ingested_block_data.is_not_null())
+                        .select((
+                            dsl::id,
+                            dsl::ingress_public_key,
+                            dsl::block_number,
+                            dsl::proto_ingested_block_data,
+                        ))
+                        .order((dsl::block_number.asc(), dsl::id.asc()))
+                        .load(&conn)?;
+
+                    // Blocks scanned late (e.g. by a lagging ingest invocation) may land in
+                    // the range of an existing file, so add to it rather than replace it.
+                    let mut archived_blocks = if archive.block_files()?.contains(&block_range) {
+                        archive.read_blocks(&block_range)?
+                    } else {
+                        Default::default()
+                    };
+                    let mut ids = Vec::with_capacity(rows.len());
+                    for (id, ingress_public_key, block_number, proto_ingested_block_data) in rows {
+                        ids.push(id);
+                        archived_blocks.blocks.push(ProtoArchivedBlock {
+                            ingress_public_key,
+                            block_number: block_number as u64,
+                            proto_ingested_block_data: proto_ingested_block_data
+                                .unwrap_or_default(),
+                        });
+                    }
+
+                    // The file must be complete before the records leave the database.
+                    archive.write_blocks(&block_range, &archived_blocks)?;
+
+                    for chunk in ids.chunks(SQL_MAX_PARAMS) {
+                        diesel::update(dsl::ingested_blocks.filter(dsl::id.eq_any(chunk)))
+                            .set(dsl::proto_ingested_block_data.eq(None::<Vec<u8>>))
+                            .execute(&conn)?;
+                    }
+                    Ok(())
+                })?;
+
+            log::info!(
+                self.logger,
+                "Archived ETxOutRecords of blocks {}",
+                block_range
+            );
+            archived_ranges.push(block_range);
+        }
+
+        if !archived_ranges.is_empty() {
+            // Autovacuum is disabled on ingested_blocks, so the space of the
+            // archived records is only reusable after an explicit vacuum.
+            diesel::sql_query("VACUUM ingested_blocks").execute(&conn)?;
+        }
+
+        Ok(archived_ranges)
+    }
+
+    /// Move the ETxOutRecords of all archived blocks in a given range back
+    /// into the database. Whole archive files are restored, so blocks outside
+    /// of the range but in the same file are restored as well.
+    ///
+    /// Returns the block ranges of the archive files restored.
+    pub fn restore_blocks(&self, block_range: &BlockRange) -> Result<Vec<BlockRange>, Error> {
+        let archive = self
+            .archive
+            .as_ref()
+            .ok_or_else(|| Error::MissingArchive(block_range.clone()))?;
+        let conn = self.pool.get()?;
+
+        let mut restored_ranges = Vec::new();
+        for file_range in archive
+            .block_files()?
+            .into_iter()
+            .filter(|file_range| file_range.overlaps(block_range))
+        {
+            let archived_blocks = archive.read_blocks(&file_range)?;
+
+            conn.build_transaction()
+                .read_write()
+                .run(|| -> Result<(), Error> {
+                    use schema::ingested_blocks::dsl;
+                    for block in archived_blocks.blocks.iter() {
+                        diesel::update(
+                            dsl::ingested_blocks
+                                .filter(
+                                    dsl::ingress_public_key.eq(block.ingress_public_key.as_slice()),
+                                )
+                                .filter(dsl::block_number.eq(block.block_number as i64))
+                                .filter(dsl::proto_ingested_block_data.is_null()),
+                        )
+                        .set(
+                            dsl::proto_ingested_block_data
+                                .eq(Some(block.proto_ingested_block_data.as_slice())),
+                        )
+                        .execute(&conn)?;
+                    }
+                    Ok(())
+                })?;
+            archive.remove_blocks(&file_range)?;
+
+            log::info!(
+                self.logger,
+                "Restored ETxOutRecords of blocks {}",
+                file_range
+            );
+            restored_ranges.push(file_range);
+        }
+
+        Ok(restored_ranges)
+    }
+
+    /// Get the user events which only concern blocks before end_block:
+    /// * missed block ranges ending at or before end_block,
+    /// * new and decommissioned ingest invocations, for decommissioned
+    ///   invocations which did not scan any block from end_block onwards.
+    pub fn get_archivable_user_events(
+        &self,
+        end_block: u64,
+    ) -> Result<Vec<(i64, FogUserEvent)>, Error> {
+        let conn = self.pool.get()?;
+        self.load_user_events_impl(
+            &conn,
+            0,
+            Some(Self::archivable_user_events(end_block)),
+            None,
+        )
+    }
+
+    /// Move the user events which only concern blocks before end_block (see
+    /// get_archivable_user_events) to the archive.
+    ///
+    /// Returns the number of user events archived.
+    pub fn archive_user_events(&self, end_block: u64) -> Result<usize, Error> {
+        let archive = self
+            .archive
+            .as_ref()
+            .ok_or_else(|| Error::MissingArchive(BlockRange::new(0, end_block)))?;
+        let conn = self.pool.get()?;
+
+        conn.build_transaction()
+            .read_write()
+            .run(|| -> Result<usize, Error> {
+                let events = self.load_user_events_impl(
+                    &conn,
+                    0,
+                    Some(Self::archivable_user_events(end_block)),
+                    None,
+                )?;
+                let (first_id, last_id) = match (events.first(), events.last()) {
+                    (Some((first_id, _)), Some((last_id, _))) => (*first_id, *last_id),
+                    _ => return Ok(0),
+                };
+
+                let archived_events = ProtoArchivedUserEvents {
+                    events: events
+                        .iter()
+                        .map(|(id, event)| ProtoArchivedUserEvent {
+                            id: *id,
+                            event: Some(event.into()),
+                        })
+                        .collect(),
+                };
+
+                // The file must be complete before the events leave the database.
+                archive.write_user_events(&(first_id..=last_id), &archived_events)?;
+
+                {
+                    use schema::archived_user_event_files::dsl;
+                    diesel::insert_into(dsl::archived_user_event_files)
+                        .values((
+                            dsl::first_user_event_id.eq(first_id),
+                            dsl::last_user_event_id.eq(last_id),
+                        ))
+                        .execute(&conn)?;
+                }
+
+                use schema::user_events::dsl;
+                let ids: Vec<i64> = events.iter().map(|(id, _)| *id).collect();
+                for chunk in ids.chunks(SQL_MAX_PARAMS) {
+                    diesel::delete(dsl::user_events.filter(dsl::id.eq_any(chunk)))
+                        .execute(&conn)?;
+                }
+
+                log::info!(
+                    self.logger,
+                    "Archived {} user events with ids from {} to {}",
+                    events.len(),
+                    first_id,
+                    last_id
+                );
+                Ok(events.len())
+            })
+    }
+
+    /// Move all archived user events back into the database, with the ids
+    /// they had before.
+    ///
+    /// Returns the number of user events restored.
+    pub fn restore_user_events(&self) -> Result<usize, Error> {
+        let archive = self
+            .archive
+            .as_ref()
+            .ok_or_else(|| Error::MissingArchive(BlockRange::new(0, u64::MAX)))?;
+        let conn = self.pool.get()?;
+
+        let mut restored_count = 0;
+        for id_range in archive.user_event_files()? {
+            let archived_events = archive.read_user_events(&id_range)?;
+            let rows = archived_events
+                .events
+                .into_iter()
+                .map(|archived_event| {
+                    let event = archived_event.event.ok_or(Error::UserEventSchemaViolation(
+                        archived_event.id,
+                        "archived user event is missing its event",
+                    ))?;
+                    Ok(models::RestoredUserEvent::new(
+                        archived_event.id,
+                        &event.into(),
+                    ))
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+
+            conn.build_transaction()
+                .read_write()
+                .run(|| -> Result<(), Error> {
+                    for chunk in rows.chunks(SQL_MAX_ROWS) {
+                        diesel::insert_into(schema::user_events::table)
+                            .values(chunk)
+                            .on_conflict_do_nothing()
+                            .execute(&conn)?;
+                    }
+
+                    use schema::archived_user_event_files::dsl;
+                    diesel::delete(
+                        dsl::archived_user_event_files
+                            .filter(dsl::first_user_event_id.eq(*id_range.start())),
+                    )
+                    .execute(&conn)?;
+                    Ok(())
+                })?;
+            archive.remove_user_events(&id_range)?;
+
+            log::info!(
+                self.logger,
+                "Restored {} user events with ids from {} to {}",
+                rows.len(),
+                id_range.start(),
+                id_range.end()
+            );
+            restored_count += rows.len();
+        }
+
+        Ok(restored_count)
+    }
+
+    /// Replace the data of archived ingested blocks, which is NULL in the
+    /// database, with the data held by the archive.
+    ///
+    /// Arguments:
+    /// * rows: (ingress public key, block number, proto_ingested_block_data) of
+    ///   ingested blocks
+    ///
+    /// Returns:
+    /// * The proto_ingested_block_data of each of the rows, in order.
+    pub(crate) fn fill_in_archived_block_data(
+        &self,
+        rows: Vec<(Vec<u8>, u64, Option<Vec<u8>>)>,
+    ) -> Result<Vec<Vec<u8>>, Error> {
+        let archived_block_numbers: Vec<u64> = rows
+            .iter()
+            .filter(|(_, _, data)| data.is_none())
+            .map(|(_, block_number, _)| *block_number)
+            .collect();
+        if archived_block_numbers.is_empty() {
+            return Ok(rows.into_iter().filter_map(|(_, _, data)| data).collect());
+        }
+
+        let archive = self.archive.as_ref().ok_or_else(|| {
+            Error::MissingArchive(BlockRange::new(
+                archived_block_numbers[0],
+                archived_block_numbers[archived_block_numbers.len() - 1] + 1,
+            ))
+        })?;
+
+        // Read each archive file holding any of the blocks once.
+        let mut archived_data = HashMap::<(Vec<u8>, u64), Vec<u8>>::default();
+        for file_range in archive.block_files()?.into_iter().filter(|file_range| {
+            archived_block_numbers
+                .iter()
+                .any(|block_number| file_range.contains(*block_number))
+        }) {
+            for block in archive.read_blocks(&file_range)?.blocks {
+                archived_data.insert(
+                    (block.ingress_public_key, block.block_number),
+                    block.proto_ingested_block_data,
+                );
+            }
+        }
+
+        rows.into_iter()
+            .map(|(ingress_public_key, block_number, data)| match data {
+                Some(data) => Ok(data),
+                None => archived_data
+                    .remove(&(ingress_public_key, block_number))
+                    .ok_or(Error::MissingArchivedBlock(block_number)),
+            })
+            .collect()
+    }
+
+    /// Get the archived user events with ids greater than
+    /// start_from_user_event_id, or nothing if there is no archive.
+    ///
+    /// Only the files that archived_user_event_files lists as holding such
+    /// events are read, so searches past the archived events don't touch the
+    /// archive. This should run in the same transaction as the search of the
+    /// user events in the database, so that events archived concurrently are
+    /// seen in exactly one of the two.
+    pub(crate) fn get_archived_user_events(
+        &self,
+        conn: &PgConnection,
+        start_from_user_event_id: i64,
+    ) -> Result<Vec<(i64, FogUserEvent)>, Error> {
+        let archive = match self.archive.as_ref() {
+            Some(archive) => archive,
+            None => return Ok(Vec::new()),
+        };
+
+        let id_ranges: Vec<(i64, i64)> = {
+            use schema::archived_user_event_files::dsl;
+            dsl::archived_user_event_files
+                .filter(dsl::last_user_event_id.gt(start_from_user_event_id))
+                .select((dsl::first_user_event_id, dsl::last_user_event_id))
+                .order_by(dsl::first_user_event_id)
+                .load(conn)?
+        };
+
+        let mut events = Vec::new();
+        for (first_id, last_id) in id_ranges {
+            for archived_event in archive.read_user_events(&(first_id..=last_id))?.events {
+                if archived_event.id <= start_from_user_event_id {
+                    continue;
+                }
+                let event = archived_event.event.ok_or(Error::UserEventSchemaViolation(
+                    archived_event.id,
+                    "archived user event is missing its event",
+                ))?;
+                events.push((archived_event.id, event.into()));
+            }
+        }
+        Ok(events)
+    }
+
+    fn get_lowest_unarchived_block_index_impl(conn: &PgConnection) -> Result<Option<u64>, Error> {
+        use schema::ingested_blocks::dsl;
+        Ok(dsl::ingested_blocks
+            .filter(dsl::proto_ingested_block_data.is_not_null())
+            .select(diesel::dsl::min(dsl::block_number))
+            .first::<Option<i64>>(conn)?
+            .map(|val| val as u64))
+    }
+
+    // Filter on user_events selecting the events get_archivable_user_events
+    // returns.
+    fn archivable_user_events(end_block: u64) -> SqlLiteral<Bool> {
+        diesel::dsl::sql::<Bool>(&format!(
+            "((user_events.event_type = 'missing_blocks' AND user_events.missing_blocks_end <= {end_block}) \
+            OR (user_events.event_type <> 'missing_blocks' AND EXISTS (\
+                SELECT 1 FROM ingest_invocations \
+                WHERE ingest_invocations.id = COALESCE(user_events.new_ingest_invocation_id, user_events.decommission_ingest_invocation_id) \
+                AND ingest_invocations.decommissioned \
+                AND COALESCE((SELECT MAX(block_number) + 1 FROM ingested_blocks WHERE ingested_blocks.ingest_invocation_id = ingest_invocations.id), ingest_invocations.start_block) <= {end_block})))",
+            end_block = end_block as i64
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::SqlRecoveryDbTestContext;
+    use mc_common::logger::{test_with_logger, Logger};
+    use mc_crypto_keys::{CompressedRistrettoPublic, RistrettoPublic};
+    use mc_fog_recovery_db_iface::RecoveryDb;
+    use mc_fog_test_infra::db_tests::{random_block, random_kex_rng_pubkey};
+    use mc_util_from_random::FromRandom;
+    use rand::{rngs::StdRng, SeedableRng};
+    use tempdir::TempDir;
+
+    #[test]
+    fn test_archive_files_round_trip() {
+        let dir = TempDir::new("recovery_db_archive").unwrap();
+        let archive = RecoveryDbArchive::new(dir.path()).unwrap();
+        assert!(archive.block_files().unwrap().is_empty());
+
+        let blocks = ProtoArchivedBlocks {
+            blocks: vec![ProtoArchivedBlock {
+                ingress_public_key: vec![1; 32],
+                block_number: 12,
+                proto_ingested_block_data: vec![2; 100],
+            }],
+        };
+        archive
+            .write_blocks(&BlockRange::new(10, 20), &blocks)
+            .unwrap();
+        archive
+            .write_blocks(&BlockRange::new(0, 10), &Default::default())
+            .unwrap();
+
+        // Unrelated files are ignored.
+        fs::write(dir.path().join("README"), "hello").unwrap();
+
+        assert_eq!(
+            archive.block_files().unwrap(),
+            vec![BlockRange::new(0, 10), BlockRange::new(10, 20)]
+        );
+        assert!(archive.user_event_files().unwrap().is_empty());
+
+        let read_blocks = archive.read_blocks(&BlockRange::new(10, 20)).unwrap();
+        assert_eq!(read_blocks.blocks.len(), 1);
+        assert_eq!(read_blocks.blocks[0].block_number, 12);
+        assert_eq!(
+            read_blocks.blocks[0].proto_ingested_block_data,
+            vec![2; 100]
+        );
+
+        archive.remove_blocks(&BlockRange::new(0, 10)).unwrap();
+        assert_eq!(
+            archive.block_files().unwrap(),
+            vec![BlockRange::new(10, 20)]
+        );
+    }
+
+    #[test_with_logger]
+    fn test_archive_and_restore(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([123u8; 32]);
+        let db_test_context = SqlRecoveryDbTestContext::new(logger);
+        let archive_dir = TempDir::new("recovery_db_archive").unwrap();
+        let db = db_test_context
+            .get_db_instance()
+            .with_archive(RecoveryDbArchive::new(archive_dir.path()).unwrap());
+
+        let ingress_key = CompressedRistrettoPublic::from(RistrettoPublic::from_random(&mut rng));
+        db.new_ingress_key(&ingress_key, 0).unwrap();
+
+        // The first invocation scans blocks 0 to 4 and is decommissioned, the
+        // second one scans blocks 5 to 9.
+        let invoc_id1 = db
+            .new_ingest_invocation(None, &ingress_key, &random_kex_rng_pubkey(&mut rng), 0)
+            .unwrap();
+        let mut records = Vec::new();
+        for block_index in 0..5 {
+            let (block, block_records) = random_block(&mut rng, block_index, 10);
+            db.add_block_data(&invoc_id1, &block, 0, &block_records)
+                .unwrap();
+            records.push(block_records);
+        }
+        let invoc_id2 = db
+            .new_ingest_invocation(
+                Some(invoc_id1),
+                &ingress_key,
+                &random_kex_rng_pubkey(&mut rng),
+                5,
+            )
+            .unwrap();
+        for block_index in 5..10 {
+            let (block, block_records) = random_block(&mut rng, block_index, 10);
+            db.add_block_data(&invoc_id2, &block, 0, &block_records)
+                .unwrap();
+            records.push(block_records);
+        }
+
+        let (user_events, _) = db.search_user_events(0).unwrap();
+        assert_eq!(user_events.len(), 3);
+
+        // Archive everything before block 7, in files of 3 blocks.
+        assert_eq!(
+            db.archive_blocks(7, 3).unwrap(),
+            vec![
+                BlockRange::new(0, 3),
+                BlockRange::new(3, 6),
+                BlockRange::new(6, 7)
+            ]
+        );
+        assert_eq!(db.get_lowest_unarchived_block_index().unwrap(), Some(7));
+        assert_eq!(db.get_highest_known_block_index().unwrap(), Some(9));
+        assert_eq!(
+            db.get_last_scanned_block_index(&ingress_key).unwrap(),
+            Some(9)
+        );
+
+        // Only the events of the first, decommissioned invocation are archived.
+        assert_eq!(db.archive_user_events(7).unwrap(), 2);
+        assert_eq!(archive_dir.path().read_dir().unwrap().count(), 4);
+
+        // Archived data is still served.
+        assert_eq!(
+            db.get_tx_outs_by_block_range_and_key(ingress_key, 0, 10)
+                .unwrap(),
+            records
+        );
+        assert_eq!(
+            db.get_tx_outs_by_block_and_key(ingress_key, 4).unwrap(),
+            Some(records[4].clone())
+        );
+        assert_eq!(db.search_user_events(0).unwrap(), (user_events.clone(), 3));
+
+        // But not without the archive.
+        let db_without_archive = db_test_context.get_db_instance();
+        assert!(db_without_archive
+            .get_tx_outs_by_block_and_key(ingress_key, 4)
+            .is_err());
+        assert_eq!(db_without_archive.search_user_events(0).unwrap().0.len(), 1);
+
+        // Searches past the archived user events don't read the archive, so
+        // they succeed even if the archive files are gone.
+        let user_event_files = db.archive().unwrap().user_event_files().unwrap();
+        assert_eq!(user_event_files.len(), 1);
+        let last_archived_id = *user_event_files[0].end();
+        let empty_archive_dir = TempDir::new("empty_recovery_db_archive").unwrap();
+        let db_with_empty_archive = db_test_context
+            .get_db_instance()
+            .with_archive(RecoveryDbArchive::new(empty_archive_dir.path()).unwrap());
+        assert!(db_with_empty_archive.search_user_events(0).is_err());
+        assert_eq!(
+            db_with_empty_archive
+                .search_user_events(last_archived_id)
+                .unwrap(),
+            db.search_user_events(last_archived_id).unwrap()
+        );
+
+        // Restoring brings the data back into the database.
+        assert_eq!(
+            db.restore_blocks(&BlockRange::new(2, 4)).unwrap(),
+            vec![BlockRange::new(0, 3), BlockRange::new(3, 6)]
+        );
+        assert_eq!(db.get_lowest_unarchived_block_index().unwrap(), Some(0));
+        assert_eq!(
+            db_without_archive
+                .get_tx_outs_by_block_range_and_key(ingress_key, 0, 6)
+                .unwrap(),
+            records[0..6]
+        );
+
+        assert_eq!(db.restore_user_events().unwrap(), 2);
+        assert_eq!(
+            db_without_archive.search_user_events(0).unwrap(),
+            (user_events, 3)
+        );
+
+        assert_eq!(
+            db.archive().unwrap().block_files().unwrap(),
+            vec![BlockRange::new(6, 7)]
+        );
+        assert!(db.archive().unwrap().user_event_files().unwrap().is_empty());
+    }
+}
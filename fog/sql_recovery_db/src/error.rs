@@ -7,6 +7,7 @@ use mc_fog_recovery_db_iface::RecoveryDbError;
 use mc_fog_types::common::BlockRange;
 use prost::{DecodeError, EncodeError};
 use r2d2::Error as R2d2Error;
+use std::io::Error as IoError;
 
 /// Enum for error types.
 #[derive(Display, Debug)]
@@ -49,6 +50,19 @@ pub enum Error {
 
     /// The data could not be encoded for storage in the database: {0:?}
     Encode(EncodeError),
+
+    /// IO error: {0}
+    Io(IoError),
+
+    /// Data for blocks {0} was moved to an archive, but no archive is
+    /// configured
+    MissingArchive(BlockRange),
+
+    /// Archived data for block {1} and ingress key {0:?} is not in the archive
+    MissingArchivedBlock(CompressedRistrettoPublic, u64),
+
+    /// Invalid archive file name: {0}
+    InvalidArchiveFileName(String),
 }
 
 impl RecoveryDbError for Error {
@@ -83,6 +97,12 @@ impl From<ConnectionError> for Error {
     }
 }
 
+impl From<IoError> for Error {
+    fn from(src: IoError) -> Self {
+        Self::Io(src)
+    }
+}
+
 impl From<DecodeError> for Error {
     fn from(src: DecodeError) -> Self {
         Self::Decode(src)
@@ -8,10 +8,12 @@ extern crate diesel;
 #[macro_use]
 extern crate diesel_migrations;
 
+pub use archive::RecoveryDbArchive;
 pub use error::Error;
 
 pub mod test_utils;
 
+mod archive;
 mod error;
 mod models;
 mod proto_types;
@@ -22,9 +24,11 @@ use crate::sql_types::{SqlCompressedRistrettoPublic, UserEventType};
 use chrono::NaiveDateTime;
 use clap::Parser;
 use diesel::{
+    expression::SqlLiteral,
     pg::PgConnection,
     prelude::*,
     r2d2::{ConnectionManager, Pool},
+    sql_types::Bool,
 };
 use mc_attest_core::VerificationReport;
 use mc_blockchain_types::Block;
@@ -49,7 +53,7 @@ use prost::Message;
 use proto_types::ProtoIngestedBlockData;
 use retry::{delay, Error as RetryError, OperationResult};
 use serde::Serialize;
-use std::{cmp::max, time::Duration};
+use std::{cmp::max, path::PathBuf, time::Duration};
 
 /// Maximum number of parameters PostgreSQL allows in a single query.
 /// The actual limit is 65535. This value is more conservative, resulting on
@@ -95,6 +99,12 @@ pub struct SqlRecoveryDbConnectionConfig {
     /// (connection / diesel errors)
     #[clap(long, default_value = "20", env = "MC_POSTGRES_RETRY_MILLIS")]
     pub postgres_retry_millis: u64,
+
+    /// The directory of the recovery db archive, which the ETxOutRecords and
+    /// user events of old blocks may have been moved to by
+    /// mc-fog-sql-recovery-db-cleanup. Needed to serve that data.
+    #[clap(long, env = "MC_RECOVERY_DB_ARCHIVE_DIR")]
+    pub recovery_db_archive_dir: Option<PathBuf>,
 }
 
 impl Default for SqlRecoveryDbConnectionConfig {
@@ -106,6 +116,7 @@ impl Default for SqlRecoveryDbConnectionConfig {
             postgres_max_connections: 1,
             postgres_retry_count: 3,
             postgres_retry_millis: 20,
+            recovery_db_archive_dir: None,
         }
     }
 }
@@ -115,6 +126,7 @@ impl Default for SqlRecoveryDbConnectionConfig {
 pub struct SqlRecoveryDb {
     pool: Pool<ConnectionManager<PgConnection>>,
     config: SqlRecoveryDbConnectionConfig,
+    archive: Option<RecoveryDbArchive>,
     logger: Logger,
 }
 
@@ -128,6 +140,7 @@ impl SqlRecoveryDb {
         Self {
             pool,
             config,
+            archive: None,
             logger,
         }
     }
@@ -147,7 +160,17 @@ impl SqlRecoveryDb {
             .connection_timeout(config.postgres_connection_timeout)
             .test_on_check_out(true)
             .build(manager)?;
-        Ok(Self::new(pool, config, logger))
+        let archive = config
+            .recovery_db_archive_dir
+            .as_ref()
+            .map(RecoveryDbArchive::new)
+            .transpose()?;
+
+        let db = Self::new(pool, config, logger);
+        Ok(match archive {
+            Some(archive) => db.with_archive(archive),
+            None => db,
+        })
     }
 
     // Helper function for retries config
@@ -716,17 +739,58 @@ impl SqlRecoveryDb {
     fn search_user_events_retriable(
         &self,
         start_from_user_event_id: i64,
+        max_num_events: usize,
     ) -> Result<(Vec<FogUserEvent>, i64), Error> {
         // Early return if start_from_user_event_id is max
         if start_from_user_event_id == i64::MAX {
             return Ok((Default::default(), i64::MAX));
         }
 
+        // Archived events have no particular relation to the ids of the events
+        // still in the database, so they are all merged in before the limit
+        // is applied.
+        let limit = i64::try_from(max_num_events).unwrap_or(i64::MAX);
         let conn = self.pool.get()?;
+        let mut events = conn.build_transaction().read_only().repeatable_read().run(
+            || -> Result<_, Error> {
+                let mut events =
+                    self.load_user_events_impl(&conn, start_from_user_event_id, None, Some(limit))?;
+                events.extend(self.get_archived_user_events(&conn, start_from_user_event_id)?);
+                Ok(events)
+            },
+        )?;
+
+        // Ensure events are properly sorted.
+        events.sort_by_key(|(id, _event)| *id);
+        events.truncate(max_num_events);
+
+        // If no events are found, return start_from_user_event_id and not 0
+        let max_user_event_id = events
+            .last()
+            .map(|(id, _event)| max(*id, start_from_user_event_id))
+            .unwrap_or(start_from_user_event_id);
+
+        // Return.
+        Ok((
+            events.into_iter().map(|(_event_id, event)| event).collect(),
+            max_user_event_id,
+        ))
+    }
+
+    /// Load the user events with ids greater than start_from_user_event_id,
+    /// and matching an optional additional filter, sorted by id.
+    /// If a limit is given, only that many of the lowest ids are loaded.
+    fn load_user_events_impl(
+        &self,
+        conn: &PgConnection,
+        start_from_user_event_id: i64,
+        filter: Option<SqlLiteral<Bool>>,
+        limit: Option<i64>,
+    ) -> Result<Vec<(i64, FogUserEvent)>, Error> {
         let mut events: Vec<(i64, FogUserEvent)> = Vec::new();
 
         // Collect all events of interest
-        let query = schema::user_events::dsl::user_events
+        let mut query = schema::user_events::dsl::user_events
             // Left-join ingest invocation information, needed for NewRngRecord events
             .left_join(
                 schema::ingest_invocations::dsl::ingest_invocations.on(
@@ -756,7 +820,14 @@ impl SqlRecoveryDb {
                 // Fields for MissingBlocks events
                 schema::user_events::dsl::missing_blocks_start,
                 schema::user_events::dsl::missing_blocks_end,
-            ));
+            ))
+            .into_boxed();
+        if let Some(filter) = filter {
+            query = query.filter(filter);
+        }
+        if let Some(limit) = limit {
+            query = query.order_by(schema::user_events::dsl::id).limit(limit);
+        }
 
         // The list of fields here must match the .select() clause above.
         let data = query.load::<(
@@ -774,10 +845,8 @@ impl SqlRecoveryDb {
             // For MissingBlocks events
             Option<i64>, // user_events.missing_blocks_start
             Option<i64>, // user_events.missing_blocks_end
-        )>(&conn)?;
+        )>(conn)?;
 
-        // If no events are found, return start_from_user_event_id and not 0
-        let mut max_user_event_id = start_from_user_event_id;
         for row in data.into_iter() {
             // The list of fields here must match the .select() clause above.
             let (
@@ -793,9 +862,6 @@ impl SqlRecoveryDb {
                 missing_blocks_end,
             ) = row;
 
-            // Update running max
-            max_user_event_id = core::cmp::max(max_user_event_id, user_event_id);
-
             events.push((
                 user_event_id,
                 match user_event_type {
@@ -864,11 +930,7 @@ impl SqlRecoveryDb {
         // Ensure events are properly sorted.
         events.sort_by_key(|(id, _event)| *id);
 
-        // Return.
-        Ok((
-            events.into_iter().map(|(_event_id, event)| event).collect(),
-            max_user_event_id,
-        ))
+        Ok(events)
     }
 
     /// Get any TxOutSearchResults corresponding to given search keys.
@@ -896,10 +958,21 @@ impl SqlRecoveryDb {
 
         let query = schema::ingested_blocks::dsl::ingested_blocks
             .filter(schema::ingested_blocks::dsl::block_number.ge(start_block as i64))
-            .select(schema::ingested_blocks::dsl::proto_ingested_block_data);
+            .select((
+                schema::ingested_blocks::dsl::ingress_public_key,
+                schema::ingested_blocks::dsl::block_number,
+                schema::ingested_blocks::dsl::proto_ingested_block_data,
+            ));
+        let rows = query
+            .load::<(Vec<u8>, i64, Option<Vec<u8>>)>(&conn)?
+            .into_iter()
+            .map(|(ingress_public_key, block_number, data)| {
+                (ingress_public_key, block_number as u64, data)
+            })
+            .collect();
 
         let mut search_key_to_payload = HashMap::<Vec<u8>, Vec<u8>>::default();
-        for proto_bytes in query.load::<Vec<u8>>(&conn)? {
+        for proto_bytes in self.fill_in_archived_block_data(rows)? {
             let proto = ProtoIngestedBlockData::decode(&*proto_bytes)?;
             for e_tx_out_record in proto.e_tx_out_records {
                 search_key_to_payload.insert(e_tx_out_record.search_key, e_tx_out_record.payload);
@@ -913,12 +986,14 @@ impl SqlRecoveryDb {
                     search_key: search_key.clone(),
                     result_code: TxOutSearchResultCode::Found as u32,
                     ciphertext: payload.clone(),
+                    payload_length: 0,
                 },
 
                 None => TxOutSearchResult {
                     search_key: search_key.clone(),
                     result_code: TxOutSearchResultCode::NotFound as u32,
                     ciphertext: Default::default(),
+                    payload_length: 0,
                 },
             });
         }
@@ -960,11 +1035,16 @@ impl SqlRecoveryDb {
 
         // The result of load should be 0 or 1, since there is a database constraint
         // around ingress keys and block indices
-        let protos: Vec<Vec<u8>> = query.load::<Vec<u8>>(&conn)?;
+        let protos: Vec<Option<Vec<u8>>> = query.load::<Option<Vec<u8>>>(&conn)?;
 
         if protos.is_empty() {
             Ok(None)
         } else if protos.len() == 1 {
+            let protos = self.fill_in_archived_block_data(vec![(
+                key_bytes.to_vec(),
+                block_index,
+                protos.into_iter().next().flatten(),
+            )])?;
             let proto = ProtoIngestedBlockData::decode(&*protos[0])?;
             Ok(Some(proto.e_tx_out_records))
         } else {
@@ -1012,7 +1092,7 @@ impl SqlRecoveryDb {
         };
 
         // We will get one row for each hit in the table we found
-        let rows: Vec<(i64, Vec<u8>)> = query.load(&conn)?;
+        let rows: Vec<(i64, Option<Vec<u8>>)> = query.load(&conn)?;
 
         if rows.len() > block_count {
             log::warn!(
@@ -1031,17 +1111,21 @@ impl SqlRecoveryDb {
         // there to be gaps, that's not how the system works, so it isn't
         // important to optimize for that case.
 
-        let mut result = Vec::new();
+        let mut contiguous_rows = Vec::new();
         for (idx, (block_number, proto)) in rows.into_iter().enumerate() {
             if block_index + idx as u64 == block_number as u64 {
-                let proto = ProtoIngestedBlockData::decode(&*proto)?;
-                result.push(proto.e_tx_out_records);
+                contiguous_rows.push((key_bytes.to_vec(), block_number as u64, proto));
             } else {
                 log::warn!(self.logger, "When querying for block index {} and up to {} blocks on, the {}'th response has block_number {} which is not expected. Gaps in the data?", block_index, block_count, idx, block_number);
                 break;
             }
         }
-        Ok(result)
+
+        // Blocks whose ETxOutRecords were archived are served from the archive.
+        self.fill_in_archived_block_data(contiguous_rows)?
+            .into_iter()
+            .map(|proto| Ok(ProtoIngestedBlockData::decode(&*proto)?.e_tx_out_records))
+            .collect()
     }
 
     /// Get iid that produced data for given ingress key and a given block
@@ -1401,12 +1485,13 @@ impl RecoveryDb for SqlRecoveryDb {
         })
     }
 
-    fn search_user_events(
+    fn search_user_events_with_limit(
         &self,
         start_from_user_event_id: i64,
+        max_num_events: usize,
     ) -> Result<(Vec<FogUserEvent>, i64), Self::Error> {
         our_retry(self.get_retries(), || {
-            self.search_user_events_retriable(start_from_user_event_id)
+            self.search_user_events_retriable(start_from_user_event_id, max_num_events)
         })
     }
 
@@ -2244,7 +2329,8 @@ mod tests {
                     .map(|search_key| TxOutSearchResult {
                         search_key: search_key.clone(),
                         result_code: TxOutSearchResultCode::NotFound as u32,
-                        ciphertext: vec![]
+                        ciphertext: vec![],
+                        payload_length: 0
                     })
                     .collect::<Vec<_>>()
             );
@@ -2265,27 +2351,32 @@ mod tests {
                 TxOutSearchResult {
                     search_key: test_case[0].clone(),
                     result_code: TxOutSearchResultCode::NotFound as u32,
-                    ciphertext: vec![]
+                    ciphertext: vec![],
+                    payload_length: 0
                 },
                 TxOutSearchResult {
                     search_key: test_case[1].clone(),
                     result_code: TxOutSearchResultCode::Found as u32,
                     ciphertext: records1[0].payload.clone(),
+                    payload_length: 0,
                 },
                 TxOutSearchResult {
                     search_key: test_case[2].clone(),
                     result_code: TxOutSearchResultCode::Found as u32,
                     ciphertext: records1[5].payload.clone(),
+                    payload_length: 0,
                 },
                 TxOutSearchResult {
                     search_key: test_case[3].clone(),
                     result_code: TxOutSearchResultCode::Found as u32,
                     ciphertext: records2[3].payload.clone(),
+                    payload_length: 0,
                 },
                 TxOutSearchResult {
                     search_key: test_case[4].clone(),
                     result_code: TxOutSearchResultCode::NotFound as u32,
-                    ciphertext: vec![]
+                    ciphertext: vec![],
+                    payload_length: 0
                 },
             ]
         );
@@ -2297,27 +2388,32 @@ mod tests {
                 TxOutSearchResult {
                     search_key: test_case[0].clone(),
                     result_code: TxOutSearchResultCode::NotFound as u32,
-                    ciphertext: vec![]
+                    ciphertext: vec![],
+                    payload_length: 0
                 },
                 TxOutSearchResult {
                     search_key: test_case[1].clone(),
                     result_code: TxOutSearchResultCode::Found as u32,
                     ciphertext: records1[0].payload.clone(),
+                    payload_length: 0,
                 },
                 TxOutSearchResult {
                     search_key: test_case[2].clone(),
                     result_code: TxOutSearchResultCode::Found as u32,
                     ciphertext: records1[5].payload.clone(),
+                    payload_length: 0,
                 },
                 TxOutSearchResult {
                     search_key: test_case[3].clone(),
                     result_code: TxOutSearchResultCode::Found as u32,
                     ciphertext: records2[3].payload.clone(),
+                    payload_length: 0,
                 },
                 TxOutSearchResult {
                     search_key: test_case[4].clone(),
                     result_code: TxOutSearchResultCode::NotFound as u32,
-                    ciphertext: vec![]
+                    ciphertext: vec![],
+                    payload_length: 0
                 },
             ]
         );
@@ -2331,27 +2427,32 @@ mod tests {
                 TxOutSearchResult {
                     search_key: test_case[0].clone(),
                     result_code: TxOutSearchResultCode::NotFound as u32,
-                    ciphertext: vec![]
+                    ciphertext: vec![],
+                    payload_length: 0
                 },
                 TxOutSearchResult {
                     search_key: test_case[1].clone(),
                     result_code: TxOutSearchResultCode::NotFound as u32,
-                    ciphertext: vec![]
+                    ciphertext: vec![],
+                    payload_length: 0
                 },
                 TxOutSearchResult {
                     search_key: test_case[2].clone(),
                     result_code: TxOutSearchResultCode::NotFound as u32,
-                    ciphertext: vec![]
+                    ciphertext: vec![],
+                    payload_length: 0
                 },
                 TxOutSearchResult {
                     search_key: test_case[3].clone(),
                     result_code: TxOutSearchResultCode::NotFound as u32,
-                    ciphertext: vec![]
+                    ciphertext: vec![],
+                    payload_length: 0
                 },
                 TxOutSearchResult {
                     search_key: test_case[4].clone(),
                     result_code: TxOutSearchResultCode::NotFound as u32,
-                    ciphertext: vec![]
+                    ciphertext: vec![],
+                    payload_length: 0
                 },
             ]
         );
@@ -2363,27 +2464,32 @@ mod tests {
                 TxOutSearchResult {
                     search_key: test_case[0].clone(),
                     result_code: TxOutSearchResultCode::NotFound as u32,
-                    ciphertext: vec![]
+                    ciphertext: vec![],
+                    payload_length: 0
                 },
                 TxOutSearchResult {
                     search_key: test_case[1].clone(),
                     result_code: TxOutSearchResultCode::NotFound as u32,
-                    ciphertext: vec![]
+                    ciphertext: vec![],
+                    payload_length: 0
                 },
                 TxOutSearchResult {
                     search_key: test_case[2].clone(),
                     result_code: TxOutSearchResultCode::NotFound as u32,
-                    ciphertext: vec![]
+                    ciphertext: vec![],
+                    payload_length: 0
                 },
                 TxOutSearchResult {
                     search_key: test_case[3].clone(),
                     result_code: TxOutSearchResultCode::Found as u32,
                     ciphertext: records2[3].payload.clone(),
+                    payload_length: 0,
                 },
                 TxOutSearchResult {
                     search_key: test_case[4].clone(),
                     result_code: TxOutSearchResultCode::NotFound as u32,
-                    ciphertext: vec![]
+                    ciphertext: vec![],
+                    payload_length: 0
                 },
             ]
         );
@@ -2,6 +2,7 @@
 
 use super::schema::*;
 use crate::sql_types::{SqlCompressedRistrettoPublic, UserEventType};
+use mc_fog_recovery_db_iface::FogUserEvent;
 use mc_fog_types::common::BlockRange;
 
 #[derive(Debug, Queryable)]
@@ -53,7 +54,7 @@ pub struct IngestedBlock {
     pub block_number: i64,
     pub cumulative_txo_count: i64,
     pub block_signature_timestamp: i64,
-    pub proto_ingested_block_data: Vec<u8>,
+    pub proto_ingested_block_data: Option<Vec<u8>>,
 }
 
 #[derive(Debug, Insertable)]
@@ -111,6 +112,42 @@ impl NewUserEvent {
     }
 }
 
+/// A user event brought back from a recovery db archive, keeping the id it had
+/// before it was archived.
+#[derive(Debug, Insertable)]
+#[table_name = "user_events"]
+pub struct RestoredUserEvent {
+    pub id: i64,
+    pub event_type: UserEventType,
+    pub new_ingest_invocation_id: Option<i64>,
+    pub decommission_ingest_invocation_id: Option<i64>,
+    pub missing_blocks_start: Option<i64>,
+    pub missing_blocks_end: Option<i64>,
+}
+
+impl RestoredUserEvent {
+    pub fn new(id: i64, event: &FogUserEvent) -> Self {
+        let new_event = match event {
+            FogUserEvent::NewRngRecord(rng_record) => {
+                NewUserEvent::new_ingest_invocation(rng_record.ingest_invocation_id)
+            }
+            FogUserEvent::DecommissionIngestInvocation(decommissioned) => {
+                NewUserEvent::decommission_ingest_invocation(decommissioned.ingest_invocation_id)
+            }
+            FogUserEvent::MissingBlocks(block_range) => NewUserEvent::missing_blocks(block_range),
+        };
+
+        Self {
+            id,
+            event_type: new_event.event_type,
+            new_ingest_invocation_id: new_event.new_ingest_invocation_id,
+            decommission_ingest_invocation_id: new_event.decommission_ingest_invocation_id,
+            missing_blocks_start: new_event.missing_blocks_start,
+            missing_blocks_end: new_event.missing_blocks_end,
+        }
+    }
+}
+
 #[derive(Debug, Insertable)]
 #[table_name = "reports"]
 pub struct NewReport<'a> {
@@ -1,7 +1,12 @@
 // Copyright (c) 2018-2022 The MobileCoin Foundation
 
-use mc_fog_types::ETxOutRecord;
-use prost::Message;
+use mc_fog_recovery_db_iface::FogUserEvent;
+use mc_fog_types::{
+    common::BlockRange,
+    view::{DecommissionedIngestInvocation, RngRecord},
+    ETxOutRecord,
+};
+use prost::{Message, Oneof};
 
 /// Protobuf data stored with an ingested block record in sql
 /// Having smaller tables (fewer entries which are bigger) is better for perf in
@@ -16,3 +21,90 @@ pub struct ProtoIngestedBlockData {
     #[prost(repeated, message, tag = 1)]
     pub e_tx_out_records: Vec<ETxOutRecord>,
 }
+
+/// The ETxOutRecords of one ingested block, as stored in a recovery db
+/// archive file. The rest of the ingested block record stays in postgres.
+#[derive(Message)]
+pub struct ProtoArchivedBlock {
+    /// The ingress public key the block was scanned with
+    #[prost(bytes, tag = 1)]
+    pub ingress_public_key: Vec<u8>,
+
+    /// The block index in the blockchain
+    #[prost(uint64, tag = 2)]
+    pub block_number: u64,
+
+    /// The encoded ProtoIngestedBlockData which was stored in postgres
+    #[prost(bytes, tag = 3)]
+    pub proto_ingested_block_data: Vec<u8>,
+}
+
+/// The contents of a recovery db archive file holding ingested blocks
+#[derive(Message)]
+pub struct ProtoArchivedBlocks {
+    /// The archived blocks, ordered by block number
+    #[prost(repeated, message, tag = 1)]
+    pub blocks: Vec<ProtoArchivedBlock>,
+}
+
+/// A user event, as stored in a recovery db archive file
+#[derive(Message)]
+pub struct ProtoArchivedUserEvent {
+    /// The id the user event had in postgres
+    #[prost(int64, tag = 1)]
+    pub id: i64,
+
+    /// The event, as returned by search_user_events
+    #[prost(oneof = "ProtoArchivedUserEventData", tags = "2, 3, 4")]
+    pub event: Option<ProtoArchivedUserEventData>,
+}
+
+/// The possible user events in a recovery db archive file
+#[derive(Oneof)]
+pub enum ProtoArchivedUserEventData {
+    /// A new RNG record
+    #[prost(message, tag = 2)]
+    NewRngRecord(RngRecord),
+
+    /// A decommissioned ingest invocation
+    #[prost(message, tag = 3)]
+    DecommissionIngestInvocation(DecommissionedIngestInvocation),
+
+    /// A missed block range
+    #[prost(message, tag = 4)]
+    MissingBlocks(BlockRange),
+}
+
+impl From<&FogUserEvent> for ProtoArchivedUserEventData {
+    fn from(src: &FogUserEvent) -> Self {
+        match src {
+            FogUserEvent::NewRngRecord(rng_record) => Self::NewRngRecord(rng_record.clone()),
+            FogUserEvent::DecommissionIngestInvocation(decommissioned) => {
+                Self::DecommissionIngestInvocation(decommissioned.clone())
+            }
+            FogUserEvent::MissingBlocks(block_range) => Self::MissingBlocks(block_range.clone()),
+        }
+    }
+}
+
+impl From<ProtoArchivedUserEventData> for FogUserEvent {
+    fn from(src: ProtoArchivedUserEventData) -> Self {
+        match src {
+            ProtoArchivedUserEventData::NewRngRecord(rng_record) => Self::NewRngRecord(rng_record),
+            ProtoArchivedUserEventData::DecommissionIngestInvocation(decommissioned) => {
+                Self::DecommissionIngestInvocation(decommissioned)
+            }
+            ProtoArchivedUserEventData::MissingBlocks(block_range) => {
+                Self::MissingBlocks(block_range)
+            }
+        }
+    }
+}
+
+/// The contents of a recovery db archive file holding user events
+#[derive(Message)]
+pub struct ProtoArchivedUserEvents {
+    /// The archived user events, ordered by id
+    #[prost(repeated, message, tag = 1)]
+    pub events: Vec<ProtoArchivedUserEvent>,
+}
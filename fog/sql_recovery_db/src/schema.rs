@@ -2,6 +2,16 @@
 // Run `diesel migration run` to update (in src/fog/sql_recovery_db)
 #![allow(unused_imports)]
 
+table! {
+    use diesel::sql_types::*;
+    use crate::sql_types::*;
+
+    archived_user_event_files (first_user_event_id) {
+        first_user_event_id -> Int8,
+        last_user_event_id -> Int8,
+    }
+}
+
 table! {
     use diesel::sql_types::*;
     use crate::sql_types::*;
@@ -28,7 +38,7 @@ table! {
         block_number -> Int8,
         cumulative_txo_count -> Int8,
         block_signature_timestamp -> Int8,
-        proto_ingested_block_data -> Bytea,
+        proto_ingested_block_data -> Nullable<Bytea>,
     }
 }
 
@@ -78,6 +88,7 @@ joinable!(reports -> ingest_invocations (ingest_invocation_id));
 joinable!(reports -> ingress_keys (ingress_public_key));
 
 allow_tables_to_appear_in_same_query!(
+    archived_user_event_files,
     ingest_invocations,
     ingested_blocks,
     ingress_keys,
@@ -34,6 +34,16 @@ fn sql_recovery_db_rng_records_decommissioning(logger: Logger) {
     })
 }
 
+#[test_with_logger]
+fn sql_recovery_db_user_events_pagination(logger: Logger) {
+    mc_util_test_helper::run_with_several_seeds(|mut rng| {
+        let db_test_context = SqlRecoveryDbTestContext::new(logger.clone());
+        let db = db_test_context.get_db_instance();
+
+        recovery_db_user_events_pagination(&mut rng, &db);
+    })
+}
+
 #[test_with_logger]
 fn sql_recovery_db_ingress_keys(logger: Logger) {
     mc_util_test_helper::run_with_several_seeds(|mut rng| {
@@ -0,0 +1,9 @@
+-- Copyright (c) 2018-2022 The MobileCoin Foundation
+
+DROP TABLE reports;
+DROP INDEX idx_user_events__event_type__id;
+DROP TABLE user_events;
+DROP INDEX idx_ingested_blocks__block_number;
+DROP TABLE ingested_blocks;
+DROP TABLE ingest_invocations;
+DROP TABLE ingress_keys;
@@ -0,0 +1,66 @@
+-- Copyright (c) 2018-2022 The MobileCoin Foundation
+
+-- This is the schema of fog/sql_recovery_db, translated to SQLite. See the
+-- migrations of that crate for documentation of the columns.
+--
+-- SQLite has no enum or BIGSERIAL types, so user event types are stored as
+-- text, and ids are INTEGER PRIMARY KEY AUTOINCREMENT columns, which like
+-- BIGSERIAL start from 1 and never reuse an id.
+
+-- Ingress keys
+CREATE TABLE ingress_keys (
+    ingress_public_key BLOB PRIMARY KEY NOT NULL,
+    start_block BIGINT NOT NULL,
+    pubkey_expiry BIGINT NOT NULL DEFAULT 0,
+    retired BOOLEAN NOT NULL DEFAULT 0,
+    lost BOOLEAN NOT NULL DEFAULT 0
+);
+
+-- Ingest invocations
+CREATE TABLE ingest_invocations (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    ingress_public_key BLOB NOT NULL REFERENCES ingress_keys(ingress_public_key),
+    egress_public_key BLOB NOT NULL UNIQUE,
+    last_active_at TIMESTAMP NOT NULL,
+    start_block BIGINT NOT NULL,
+    decommissioned BOOLEAN NOT NULL DEFAULT 0,
+    rng_version INTEGER NOT NULL
+);
+
+-- Ingested blocks
+CREATE TABLE ingested_blocks (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    ingest_invocation_id BIGINT NOT NULL REFERENCES ingest_invocations(id),
+    ingress_public_key BLOB NOT NULL REFERENCES ingress_keys(ingress_public_key),
+    block_number BIGINT NOT NULL,
+    cumulative_txo_count BIGINT NOT NULL,
+    block_signature_timestamp BIGINT NOT NULL,
+    proto_ingested_block_data BLOB NOT NULL,
+    UNIQUE (ingest_invocation_id, block_number),
+    UNIQUE (ingress_public_key, block_number)
+);
+
+CREATE INDEX idx_ingested_blocks__block_number ON ingested_blocks (block_number);
+
+-- User events
+CREATE TABLE user_events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    event_type TEXT NOT NULL CHECK (event_type IN ('new_ingest_invocation', 'decommission_ingest_invocation', 'missing_blocks')),
+    new_ingest_invocation_id BIGINT NULL UNIQUE REFERENCES ingest_invocations(id),
+    decommission_ingest_invocation_id BIGINT NULL UNIQUE REFERENCES ingest_invocations(id),
+    missing_blocks_start BIGINT NULL,
+    missing_blocks_end BIGINT NULL,
+    UNIQUE (missing_blocks_start, missing_blocks_end)
+);
+
+CREATE INDEX idx_user_events__event_type__id ON user_events (event_type, id);
+
+-- Reports
+CREATE TABLE reports (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    ingress_public_key BLOB NOT NULL REFERENCES ingress_keys(ingress_public_key),
+    ingest_invocation_id BIGINT NULL REFERENCES ingest_invocations(id),
+    fog_report_id VARCHAR(64) NOT NULL UNIQUE,
+    report BLOB NOT NULL,
+    pubkey_expiry BIGINT NOT NULL
+);
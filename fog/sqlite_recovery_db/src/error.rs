@@ -0,0 +1,90 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+use diesel::{result::Error as DieselError, ConnectionError};
+use diesel_migrations::RunMigrationsError;
+use displaydoc::Display;
+use mc_crypto_keys::CompressedRistrettoPublic;
+use mc_fog_recovery_db_iface::RecoveryDbError;
+use prost::{DecodeError, EncodeError};
+
+/// Enum for error types.
+#[derive(Display, Debug)]
+pub enum Error {
+    /// Orm: {0}
+    Orm(DieselError),
+
+    /// Connection error: {0}
+    Connection(ConnectionError),
+
+    /// Migrations failed: {0}
+    Migrations(RunMigrationsError),
+
+    /// The following ingress key was not found: {0:?}
+    MissingIngressKey(CompressedRistrettoPublic),
+
+    /// UserEvent schema violation on row #{0}: {1}
+    UserEventSchemaViolation(i64, &'static str),
+
+    /// IngressKeys schema violation: {0}
+    IngressKeysSchemaViolation(String),
+
+    /// New ingress key wasn't inserted successfully: {0}
+    IngressKeyUnsuccessfulInsert(String),
+
+    /// IngestedBlock schema violation: {0}
+    IngestedBlockSchemaViolation(String),
+
+    /**
+     * The data in the database could not be decoded as a
+     * VerificationReport: {0:?}
+     */
+    Decode(DecodeError),
+
+    /// The data could not be encoded for storage in the database: {0:?}
+    Encode(EncodeError),
+}
+
+impl RecoveryDbError for Error {
+    /// Policy decision, whether the call should be retried.
+    ///
+    /// Another process writing to the same database file makes SQLite report
+    /// the database as busy once the busy timeout runs out.
+    fn should_retry(&self) -> bool {
+        match self {
+            Self::Orm(DieselError::DatabaseError(_, info)) => {
+                info.message() == "database is locked" || info.message() == "database is busy"
+            }
+            _ => false,
+        }
+    }
+}
+
+impl From<DieselError> for Error {
+    fn from(src: DieselError) -> Self {
+        Self::Orm(src)
+    }
+}
+
+impl From<ConnectionError> for Error {
+    fn from(src: ConnectionError) -> Self {
+        Self::Connection(src)
+    }
+}
+
+impl From<RunMigrationsError> for Error {
+    fn from(src: RunMigrationsError) -> Self {
+        Self::Migrations(src)
+    }
+}
+
+impl From<DecodeError> for Error {
+    fn from(src: DecodeError) -> Self {
+        Self::Decode(src)
+    }
+}
+
+impl From<EncodeError> for Error {
+    fn from(src: EncodeError) -> Self {
+        Self::Encode(src)
+    }
+}
@@ -0,0 +1,1308 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+#![deny(missing_docs)]
+
+//! Recovery db implementation using an embedded SQLite database backend.
+//!
+//! This is meant for tests and small deployments, where running a PostgreSQL
+//! server is not worth it. The schema and the semantics of every call mirror
+//! `mc-fog-sql-recovery-db`.
+
+#[macro_use]
+extern crate diesel;
+#[macro_use]
+extern crate diesel_migrations;
+
+pub use error::Error;
+
+pub mod test_utils;
+
+mod error;
+mod models;
+mod proto_types;
+mod schema;
+mod sql_types;
+
+use crate::sql_types::{SqlCompressedRistrettoPublic, UserEventType};
+use chrono::{NaiveDateTime, Utc};
+use diesel::{prelude::*, sqlite::SqliteConnection};
+use mc_attest_core::VerificationReport;
+use mc_blockchain_types::Block;
+use mc_common::{
+    logger::{log, Logger},
+    HashMap,
+};
+use mc_crypto_keys::CompressedRistrettoPublic;
+use mc_fog_kex_rng::KexRngPubkey;
+use mc_fog_recovery_db_iface::{
+    AddBlockDataStatus, ExpiredInvocationRecord, FogUserEvent, IngestInvocationId,
+    IngressPublicKeyRecord, IngressPublicKeyRecordFilters, IngressPublicKeyStatus, RecoveryDb,
+    ReportData, ReportDb,
+};
+use mc_fog_types::{
+    common::BlockRange,
+    view::{TxOutSearchResult, TxOutSearchResultCode},
+    ETxOutRecord,
+};
+use prost::Message;
+use proto_types::ProtoIngestedBlockData;
+use std::{
+    cmp::max,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+embed_migrations!("migrations/");
+
+// SQLite has no RETURNING clause in the version diesel supports, so the id of
+// a freshly inserted row is read back with this function.
+no_arg_sql_function!(
+    last_insert_rowid,
+    diesel::sql_types::BigInt,
+    "Represents the SQLite last_insert_rowid() function"
+);
+
+/// The path SQLite understands as a private, in-memory database.
+pub const IN_MEMORY_PATH: &str = ":memory:";
+
+/// How long (milliseconds) SQLite waits for another connection to release its
+/// lock on the database file before failing with "database is locked".
+pub const BUSY_TIMEOUT_MILLIS: u64 = 5000;
+
+/// SQLite-backed recovery database.
+///
+/// SQLite only allows one writer at a time, and an in-memory database only
+/// exists for the connection that created it, so all clones of this object
+/// share a single connection rather than a pool.
+#[derive(Clone)]
+pub struct SqliteRecoveryDb {
+    conn: Arc<Mutex<SqliteConnection>>,
+    logger: Logger,
+}
+
+impl SqliteRecoveryDb {
+    /// Open (or create) the database at the given path, and bring its schema
+    /// up to date.
+    pub fn new_from_path(path: impl AsRef<Path>, logger: Logger) -> Result<Self, Error> {
+        let path = path.as_ref().to_string_lossy();
+        let conn = SqliteConnection::establish(&path)?;
+
+        conn.batch_execute(&format!(
+            "PRAGMA foreign_keys = ON; PRAGMA busy_timeout = {};",
+            BUSY_TIMEOUT_MILLIS
+        ))?;
+        if path != IN_MEMORY_PATH {
+            // Let readers in other processes (e.g. fog view next to fog ingest)
+            // proceed while a block is being written.
+            conn.batch_execute("PRAGMA journal_mode = WAL;")?;
+        }
+
+        embedded_migrations::run(&conn)?;
+        log::debug!(logger, "Opened sqlite recovery db at {}", path);
+
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+            logger,
+        })
+    }
+
+    /// Create a new, empty database that only lives in memory, for as long as
+    /// this object or one of its clones does.
+    pub fn new_in_memory(logger: Logger) -> Result<Self, Error> {
+        Self::new_from_path(IN_MEMORY_PATH, logger)
+    }
+
+    // Run a function against the shared connection.
+    fn with_conn<R>(
+        &self,
+        func: impl FnOnce(&SqliteConnection) -> Result<R, Error>,
+    ) -> Result<R, Error> {
+        let conn = self
+            .conn
+            .lock()
+            .expect("sqlite recovery db connection mutex poisoned");
+        func(&conn)
+    }
+
+    // Run a function against the shared connection, inside a transaction that
+    // takes the write lock up front.
+    fn with_write_transaction<R>(
+        &self,
+        func: impl FnOnce(&SqliteConnection) -> Result<R, Error>,
+    ) -> Result<R, Error> {
+        self.with_conn(|conn| conn.immediate_transaction(|| func(conn)))
+    }
+
+    /// Mark a given ingest invocation as decommissioned.
+    fn decommission_ingest_invocation_impl(
+        conn: &SqliteConnection,
+        ingest_invocation_id: &IngestInvocationId,
+    ) -> Result<(), Error> {
+        // Mark the ingest invocation as decommissioned.
+        diesel::update(
+            schema::ingest_invocations::dsl::ingest_invocations
+                .filter(schema::ingest_invocations::dsl::id.eq(**ingest_invocation_id)),
+        )
+        .set((
+            schema::ingest_invocations::dsl::decommissioned.eq(true),
+            schema::ingest_invocations::dsl::last_active_at.eq(Utc::now().naive_utc()),
+        ))
+        .execute(conn)?;
+
+        // Write a user event.
+        let new_event =
+            models::NewUserEvent::decommission_ingest_invocation(**ingest_invocation_id);
+
+        diesel::insert_into(schema::user_events::table)
+            .values(&new_event)
+            .execute(conn)?;
+
+        Ok(())
+    }
+
+    /// Mark a given ingest invocation as still being alive.
+    fn update_last_active_at_impl(
+        conn: &SqliteConnection,
+        ingest_invocation_id: &IngestInvocationId,
+    ) -> Result<(), Error> {
+        diesel::update(
+            schema::ingest_invocations::dsl::ingest_invocations
+                .filter(schema::ingest_invocations::dsl::id.eq(**ingest_invocation_id)),
+        )
+        .set(schema::ingest_invocations::dsl::last_active_at.eq(Utc::now().naive_utc()))
+        .execute(conn)?;
+
+        Ok(())
+    }
+
+    fn get_ingress_key_status_impl(
+        conn: &SqliteConnection,
+        key: &CompressedRistrettoPublic,
+    ) -> Result<Option<IngressPublicKeyStatus>, Error> {
+        let key_bytes: &[u8] = key.as_ref();
+        use schema::ingress_keys::dsl;
+        let key_records: Vec<models::IngressKey> = dsl::ingress_keys
+            .filter(dsl::ingress_public_key.eq(key_bytes))
+            .load(conn)?;
+
+        if key_records.is_empty() {
+            Ok(None)
+        } else if key_records.len() == 1 {
+            Ok(Some(IngressPublicKeyStatus {
+                start_block: key_records[0].start_block as u64,
+                pubkey_expiry: key_records[0].pubkey_expiry as u64,
+                retired: key_records[0].retired,
+                lost: key_records[0].lost,
+            }))
+        } else {
+            Err(Error::IngressKeysSchemaViolation(format!(
+                "Found multiple entries for key: {:?}",
+                key
+            )))
+        }
+    }
+
+    fn get_highest_known_block_index_impl(conn: &SqliteConnection) -> Result<Option<u64>, Error> {
+        Ok(schema::ingested_blocks::dsl::ingested_blocks
+            .select(diesel::dsl::max(schema::ingested_blocks::dsl::block_number))
+            .first::<Option<i64>>(conn)?
+            .map(|val| val as u64))
+    }
+}
+
+/// See trait `fog_recovery_db_iface::RecoveryDb` for documentation.
+impl RecoveryDb for SqliteRecoveryDb {
+    type Error = Error;
+
+    fn get_ingress_key_status(
+        &self,
+        key: &CompressedRistrettoPublic,
+    ) -> Result<Option<IngressPublicKeyStatus>, Self::Error> {
+        self.with_conn(|conn| Self::get_ingress_key_status_impl(conn, key))
+    }
+
+    fn new_ingress_key(
+        &self,
+        key: &CompressedRistrettoPublic,
+        start_block_count: u64,
+    ) -> Result<u64, Self::Error> {
+        self.with_write_transaction(|conn| {
+            let highest_known_block_count: u64 = Self::get_highest_known_block_index_impl(conn)?
+                .map(|index| index + 1)
+                .unwrap_or(0);
+
+            let accepted_start_block_count = max(start_block_count, highest_known_block_count);
+            let obj = models::NewIngressKey {
+                ingress_public_key: (*key).into(),
+                start_block: accepted_start_block_count as i64,
+                pubkey_expiry: 0,
+                retired: false,
+                lost: false,
+            };
+
+            let inserted_row_count = diesel::insert_or_ignore_into(schema::ingress_keys::table)
+                .values(&obj)
+                .execute(conn)?;
+
+            if inserted_row_count > 0 {
+                Ok(accepted_start_block_count)
+            } else {
+                Err(Error::IngressKeyUnsuccessfulInsert(format!(
+                    "Unable to insert ingress key: {:?}",
+                    key
+                )))
+            }
+        })
+    }
+
+    fn retire_ingress_key(
+        &self,
+        key: &CompressedRistrettoPublic,
+        set_retired: bool,
+    ) -> Result<(), Self::Error> {
+        let key_bytes: &[u8] = key.as_ref();
+
+        self.with_conn(|conn| {
+            use schema::ingress_keys::dsl;
+            diesel::update(dsl::ingress_keys.filter(dsl::ingress_public_key.eq(key_bytes)))
+                .set(dsl::retired.eq(set_retired))
+                .execute(conn)?;
+            Ok(())
+        })
+    }
+
+    fn get_last_scanned_block_index(
+        &self,
+        key: &CompressedRistrettoPublic,
+    ) -> Result<Option<u64>, Self::Error> {
+        let key_bytes: &[u8] = key.as_ref();
+
+        self.with_conn(|conn| {
+            use schema::ingested_blocks::dsl;
+            let maybe_index: Option<i64> = dsl::ingested_blocks
+                .filter(dsl::ingress_public_key.eq(key_bytes))
+                .select(diesel::dsl::max(dsl::block_number))
+                .first(conn)?;
+
+            Ok(maybe_index.map(|val| val as u64))
+        })
+    }
+
+    fn get_ingress_key_records(
+        &self,
+        start_block_at_least: u64,
+        ingress_public_key_record_filters: &IngressPublicKeyRecordFilters,
+    ) -> Result<Vec<IngressPublicKeyRecord>, Self::Error> {
+        self.with_conn(|conn| {
+            use schema::ingress_keys::dsl;
+            let last_scanned_block = diesel::dsl::sql::<diesel::sql_types::BigInt>(
+                "(SELECT MAX(block_number) FROM ingested_blocks WHERE ingress_keys.ingress_public_key = ingested_blocks.ingress_public_key)"
+            );
+            let mut query = dsl::ingress_keys
+                .select((
+                    dsl::ingress_public_key,
+                    dsl::start_block,
+                    dsl::pubkey_expiry,
+                    dsl::retired,
+                    dsl::lost,
+                    last_scanned_block.clone().nullable(),
+                ))
+                .filter(dsl::start_block.ge(start_block_at_least as i64))
+                .into_boxed();
+
+            if ingress_public_key_record_filters.should_only_include_unexpired_keys {
+                query = query
+                    .filter(last_scanned_block.clone().is_not_null())
+                    .filter(dsl::pubkey_expiry.gt(last_scanned_block));
+            }
+            if !ingress_public_key_record_filters.should_include_lost_keys {
+                query = query.filter(dsl::lost.eq(false));
+            }
+            if !ingress_public_key_record_filters.should_include_retired_keys {
+                query = query.filter(dsl::retired.eq(false));
+            }
+
+            // The list of fields here must match the .select() clause above.
+            Ok(query
+                .load::<(
+                    SqlCompressedRistrettoPublic,
+                    i64,
+                    i64,
+                    bool,
+                    bool,
+                    Option<i64>,
+                )>(conn)?
+                .into_iter()
+                .map(
+                    |(
+                        ingress_public_key,
+                        start_block,
+                        pubkey_expiry,
+                        retired,
+                        lost,
+                        last_scanned_block,
+                    )| {
+                        let status = IngressPublicKeyStatus {
+                            start_block: start_block as u64,
+                            pubkey_expiry: pubkey_expiry as u64,
+                            retired,
+                            lost,
+                        };
+
+                        IngressPublicKeyRecord {
+                            key: *ingress_public_key,
+                            status,
+                            last_scanned_block: last_scanned_block.map(|v| v as u64),
+                        }
+                    },
+                )
+                .collect())
+        })
+    }
+
+    fn new_ingest_invocation(
+        &self,
+        prev_ingest_invocation_id: Option<IngestInvocationId>,
+        ingress_public_key: &CompressedRistrettoPublic,
+        egress_public_key: &KexRngPubkey,
+        start_block: u64,
+    ) -> Result<IngestInvocationId, Self::Error> {
+        self.with_write_transaction(|conn| {
+            // Optionally decommission old invocation.
+            if let Some(prev_ingest_invocation_id) = prev_ingest_invocation_id {
+                Self::decommission_ingest_invocation_impl(conn, &prev_ingest_invocation_id)?;
+            }
+
+            // Write new invocation.
+            let obj = models::NewIngestInvocation {
+                ingress_public_key: (*ingress_public_key).into(),
+                egress_public_key: egress_public_key.public_key.clone(),
+                last_active_at: Utc::now().naive_utc(),
+                start_block: start_block as i64,
+                decommissioned: false,
+                rng_version: egress_public_key.version as i32,
+            };
+
+            diesel::insert_into(schema::ingest_invocations::table)
+                .values(&obj)
+                .execute(conn)?;
+            let ingest_invocation_id = diesel::select(last_insert_rowid).get_result::<i64>(conn)?;
+
+            // Write a user event.
+            let new_event = models::NewUserEvent::new_ingest_invocation(ingest_invocation_id);
+
+            diesel::insert_into(schema::user_events::table)
+                .values(&new_event)
+                .execute(conn)?;
+
+            // Success.
+            Ok(IngestInvocationId::from(ingest_invocation_id))
+        })
+    }
+
+    fn get_ingestable_ranges(
+        &self,
+    ) -> Result<Vec<mc_fog_recovery_db_iface::IngestableRange>, Self::Error> {
+        self.with_conn(|conn| {
+            // For each ingest invocation we are aware of get its id, start block, is
+            // decommissioned and the max block number it has ingested (if
+            // available).
+            let query = schema::ingest_invocations::dsl::ingest_invocations
+                .select((
+                    schema::ingest_invocations::dsl::id,
+                    schema::ingest_invocations::dsl::start_block,
+                    schema::ingest_invocations::dsl::decommissioned,
+                    diesel::dsl::sql::<diesel::sql_types::BigInt>(
+                        "(SELECT MAX(block_number) FROM ingested_blocks WHERE ingest_invocations.id = ingested_blocks.ingest_invocation_id)"
+                    ).nullable(),
+                ))
+                .order_by(schema::ingest_invocations::dsl::id);
+
+            // The list of fields here must match the .select() clause above.
+            let data = query.load::<(i64, i64, bool, Option<i64>)>(conn)?;
+            Ok(data
+                .into_iter()
+                .map(|row| {
+                    let (ingest_invocation_id, start_block, decommissioned, last_ingested_block) =
+                        row;
+
+                    mc_fog_recovery_db_iface::IngestableRange {
+                        id: IngestInvocationId::from(ingest_invocation_id),
+                        start_block: start_block as u64,
+                        decommissioned,
+                        last_ingested_block: last_ingested_block.map(|v| v as u64),
+                    }
+                })
+                .collect())
+        })
+    }
+
+    fn decommission_ingest_invocation(
+        &self,
+        ingest_invocation_id: &IngestInvocationId,
+    ) -> Result<(), Self::Error> {
+        self.with_write_transaction(|conn| {
+            Self::decommission_ingest_invocation_impl(conn, ingest_invocation_id)
+        })
+    }
+
+    fn add_block_data(
+        &self,
+        ingest_invocation_id: &IngestInvocationId,
+        block: &Block,
+        block_signature_timestamp: u64,
+        txs: &[mc_fog_types::ETxOutRecord],
+    ) -> Result<AddBlockDataStatus, Self::Error> {
+        match self.with_write_transaction(|conn| {
+            // Get ingress pubkey of this ingest invocation id, which is also stored in the
+            // ingested_block record
+            let ingress_key_bytes: Vec<u8> = schema::ingest_invocations::table
+                .filter(schema::ingest_invocations::dsl::id.eq(**ingest_invocation_id))
+                .select(schema::ingest_invocations::ingress_public_key)
+                .first(conn)?;
+
+            // Get bytes of encoded proto ingested block data
+            let proto_bytes = {
+                let proto_ingested_block_data = ProtoIngestedBlockData {
+                    e_tx_out_records: txs.to_vec(),
+                };
+                let mut bytes = Vec::<u8>::with_capacity(proto_ingested_block_data.encoded_len());
+                proto_ingested_block_data.encode(&mut bytes)?;
+                bytes
+            };
+
+            // Add an IngestedBlock record.
+            let new_ingested_block = models::NewIngestedBlock {
+                ingress_public_key: ingress_key_bytes,
+                ingest_invocation_id: **ingest_invocation_id,
+                block_number: block.index as i64,
+                cumulative_txo_count: block.cumulative_txo_count as i64,
+                block_signature_timestamp: block_signature_timestamp as i64,
+                proto_ingested_block_data: proto_bytes,
+            };
+
+            diesel::insert_into(schema::ingested_blocks::table)
+                .values(&new_ingested_block)
+                .execute(conn)?;
+
+            // Update last active at.
+            Self::update_last_active_at_impl(conn, ingest_invocation_id)
+        }) {
+            Ok(()) => Ok(AddBlockDataStatus {
+                block_already_scanned_with_this_key: false,
+            }),
+            // If a unique constraint is violated, we return Ok(block_already_scanned: true) instead
+            // of an error, as the PostgreSQL implementation does.
+            Err(Error::Orm(diesel::result::Error::DatabaseError(
+                diesel::result::DatabaseErrorKind::UniqueViolation,
+                details,
+            ))) => {
+                log::info!(self.logger, "Unique constraint violated when adding block {} for ingest invocation id {}: {:?}", block.index, ingest_invocation_id, details);
+                Ok(AddBlockDataStatus {
+                    block_already_scanned_with_this_key: true,
+                })
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    fn report_lost_ingress_key(
+        &self,
+        lost_ingress_key: CompressedRistrettoPublic,
+    ) -> Result<(), Self::Error> {
+        self.with_write_transaction(|conn| {
+            // Find the ingress key and update it to be marked lost
+            let key_bytes: &[u8] = lost_ingress_key.as_ref();
+            {
+                use schema::ingress_keys::dsl;
+                diesel::update(dsl::ingress_keys.filter(dsl::ingress_public_key.eq(key_bytes)))
+                    .set(dsl::lost.eq(true))
+                    .execute(conn)?;
+            }
+
+            // Compute a missed block range based on looking at the key status,
+            // which is correct if no blocks have actually been scanned using the key.
+            let key_status = Self::get_ingress_key_status_impl(conn, &lost_ingress_key)?
+                .ok_or(Error::MissingIngressKey(lost_ingress_key))?;
+            let mut missed_block_range = BlockRange {
+                start_block: key_status.start_block,
+                end_block: key_status.pubkey_expiry,
+            };
+
+            // Find the last scanned block index (if any block has been scanned with this
+            // key)
+            let maybe_block_index: Option<i64> = {
+                use schema::ingested_blocks::dsl;
+                dsl::ingested_blocks
+                    .filter(dsl::ingress_public_key.eq(key_bytes))
+                    .select(diesel::dsl::max(dsl::block_number))
+                    .first(conn)?
+            };
+
+            if let Some(block_index) = maybe_block_index {
+                let block_index = block_index as u64;
+                if block_index + 1 >= missed_block_range.end_block {
+                    // There aren't actually any blocks that need to be scanned, so we are done
+                    // without creating a user event.
+                    return Ok(());
+                }
+                // If we did actually scan some blocks, then report a smaller range
+                if block_index + 1 > missed_block_range.start_block {
+                    missed_block_range.start_block = block_index + 1;
+                }
+            }
+
+            // If the missed block range is invalid (empty), we don't have to add it.
+            // This can happen if the ingress key was never actually published to the report
+            // server, and then pubkey_expiry is zero.
+            if !missed_block_range.is_valid() {
+                return Ok(());
+            }
+
+            // Add new range.
+            let new_event = models::NewUserEvent::missing_blocks(&missed_block_range);
+
+            diesel::insert_into(schema::user_events::table)
+                .values(&new_event)
+                .execute(conn)?;
+
+            Ok(())
+        })
+    }
+
+    fn get_missed_block_ranges(&self) -> Result<Vec<BlockRange>, Self::Error> {
+        self.with_conn(|conn| {
+            let query = schema::user_events::dsl::user_events
+                .filter(schema::user_events::dsl::event_type.eq(UserEventType::MissingBlocks))
+                .select((
+                    schema::user_events::dsl::id,
+                    schema::user_events::dsl::missing_blocks_start,
+                    schema::user_events::dsl::missing_blocks_end,
+                ))
+                .order_by(schema::user_events::dsl::id);
+
+            let rows = query.load::<(i64, Option<i64>, Option<i64>)>(conn)?;
+
+            rows.iter()
+                .map(|row| match row {
+                    (_, Some(start_index), Some(end_index)) => {
+                        Ok(BlockRange::new(*start_index as u64, *end_index as u64))
+                    }
+                    (id, _, _) => Err(Error::UserEventSchemaViolation(
+                        *id,
+                        "missing start or end block indices",
+                    )),
+                })
+                .collect::<Result<Vec<BlockRange>, Error>>()
+        })
+    }
+
+    fn search_user_events_with_limit(
+        &self,
+        start_from_user_event_id: i64,
+        max_num_events: usize,
+    ) -> Result<(Vec<FogUserEvent>, i64), Self::Error> {
+        // Early return if start_from_user_event_id is max
+        if start_from_user_event_id == i64::MAX {
+            return Ok((Default::default(), i64::MAX));
+        }
+        let limit = i64::try_from(max_num_events).unwrap_or(i64::MAX);
+
+        // Collect all events of interest
+        let data = self.with_conn(|conn| {
+            let query = schema::user_events::dsl::user_events
+                // Left-join ingest invocation information, needed for NewRngRecord events
+                .left_join(
+                    schema::ingest_invocations::dsl::ingest_invocations.on(
+                        schema::user_events::dsl::new_ingest_invocation_id.eq(
+                            schema::ingest_invocations::dsl::id.nullable()
+                        )
+                    )
+                )
+                // Filtered by the subset of ids we are exploring
+                // NOTE: AUTOINCREMENT columns start from 1, so "start_from_user_event_id = 0"
+                // will capture everything
+                .filter(schema::user_events::dsl::id.gt(start_from_user_event_id))
+                // Get only the fields that we need
+                .select((
+                    // Fields for every event type
+                    schema::user_events::dsl::id,
+                    schema::user_events::dsl::event_type,
+                    // Fields for NewIngestInvocation events
+                    schema::ingest_invocations::dsl::id.nullable(),
+                    schema::ingest_invocations::dsl::egress_public_key.nullable(),
+                    schema::ingest_invocations::dsl::rng_version.nullable(),
+                    schema::ingest_invocations::dsl::start_block.nullable(),
+                    // Fields for DecommissionIngestInvocation
+                    schema::user_events::dsl::decommission_ingest_invocation_id,
+                    diesel::dsl::sql::<diesel::sql_types::BigInt>("(SELECT COALESCE(MAX(block_number), 0) FROM ingested_blocks WHERE user_events.event_type = 'decommission_ingest_invocation' AND ingested_blocks.ingest_invocation_id = user_events.decommission_ingest_invocation_id)"),
+                    // Fields for MissingBlocks events
+                    schema::user_events::dsl::missing_blocks_start,
+                    schema::user_events::dsl::missing_blocks_end,
+                ))
+                .order_by(schema::user_events::dsl::id)
+                .limit(limit);
+
+            // The list of fields here must match the .select() clause above.
+            Ok(query.load::<(
+                // For all event types
+                i64,           // user_events.id
+                UserEventType, // user_events.event_type
+                // For NewRngRecord events
+                Option<i64>,     // rng_record.ingest_invocation_id
+                Option<Vec<u8>>, // rng_record.egress_public_key
+                Option<i32>,     // rng_record.rng_version
+                Option<i64>,     // rng_record.start_block
+                // For DecommissionIngestInvocation events
+                Option<i64>, // ingest_invocations.id
+                i64,         // MAX(ingested_blocks.block_number)
+                // For MissingBlocks events
+                Option<i64>, // user_events.missing_blocks_start
+                Option<i64>, // user_events.missing_blocks_end
+            )>(conn)?)
+        })?;
+
+        let mut events: Vec<(i64, FogUserEvent)> = Vec::new();
+
+        // If no events are found, return start_from_user_event_id and not 0
+        let mut max_user_event_id = start_from_user_event_id;
+        for row in data.into_iter() {
+            // The list of fields here must match the .select() clause above.
+            let (
+                user_event_id,
+                user_event_type,
+                rng_record_ingest_invocation_id,
+                rng_record_egress_public_key,
+                rng_record_rng_version,
+                rng_record_start_block,
+                decommission_ingest_invocation_id,
+                decommission_ingest_invocation_max_block,
+                missing_blocks_start,
+                missing_blocks_end,
+            ) = row;
+
+            // Update running max
+            max_user_event_id = core::cmp::max(max_user_event_id, user_event_id);
+
+            events.push((
+                user_event_id,
+                match user_event_type {
+                    UserEventType::NewIngestInvocation => {
+                        FogUserEvent::NewRngRecord(mc_fog_types::view::RngRecord {
+                            ingest_invocation_id: rng_record_ingest_invocation_id.ok_or(
+                                Error::UserEventSchemaViolation(
+                                    user_event_id,
+                                    "missing rng_record_ingest_invocation_id",
+                                ),
+                            )?,
+                            pubkey: mc_fog_types::view::KexRngPubkey {
+                                public_key: rng_record_egress_public_key.ok_or(
+                                    Error::UserEventSchemaViolation(
+                                        user_event_id,
+                                        "missing rng_record_egress_public_key",
+                                    ),
+                                )?,
+                                version: rng_record_rng_version.ok_or(
+                                    Error::UserEventSchemaViolation(
+                                        user_event_id,
+                                        "missing rng_record_rng_version",
+                                    ),
+                                )? as u32,
+                            },
+                            start_block: rng_record_start_block.ok_or(
+                                Error::UserEventSchemaViolation(
+                                    user_event_id,
+                                    "missing rng_record_start_block",
+                                ),
+                            )? as u64,
+                        })
+                    }
+                    UserEventType::DecommissionIngestInvocation => {
+                        FogUserEvent::DecommissionIngestInvocation(
+                            mc_fog_types::view::DecommissionedIngestInvocation {
+                                ingest_invocation_id: decommission_ingest_invocation_id.ok_or(
+                                    Error::UserEventSchemaViolation(
+                                        user_event_id,
+                                        "missing decommission_ingest_invocation_id",
+                                    ),
+                                )?,
+                                last_ingested_block: decommission_ingest_invocation_max_block
+                                    as u64,
+                            },
+                        )
+                    }
+                    UserEventType::MissingBlocks => {
+                        FogUserEvent::MissingBlocks(mc_fog_types::common::BlockRange {
+                            start_block: missing_blocks_start.ok_or(
+                                Error::UserEventSchemaViolation(
+                                    user_event_id,
+                                    "missing missing_blocks_start",
+                                ),
+                            )? as u64,
+                            end_block: missing_blocks_end.ok_or(Error::UserEventSchemaViolation(
+                                user_event_id,
+                                "missing missing_blocks_end",
+                            ))? as u64,
+                        })
+                    }
+                },
+            ));
+        }
+
+        // Ensure events are properly sorted.
+        events.sort_by_key(|(id, _event)| *id);
+
+        // Return.
+        Ok((
+            events.into_iter().map(|(_event_id, event)| event).collect(),
+            max_user_event_id,
+        ))
+    }
+
+    /// Get any TxOutSearchResults corresponding to given search keys.
+    /// Nonzero start_block can be provided as an optimization opportunity.
+    ///
+    /// Note: Like the PostgreSQL implementation, this decodes every ingested
+    /// block from start_block onwards, so it should not be used except in
+    /// tests.
+    fn get_tx_outs(
+        &self,
+        start_block: u64,
+        search_keys: &[Vec<u8>],
+    ) -> Result<Vec<TxOutSearchResult>, Self::Error> {
+        let protos = self.with_conn(|conn| {
+            Ok(schema::ingested_blocks::dsl::ingested_blocks
+                .filter(schema::ingested_blocks::dsl::block_number.ge(start_block as i64))
+                .select(schema::ingested_blocks::dsl::proto_ingested_block_data)
+                .load::<Vec<u8>>(conn)?)
+        })?;
+
+        let mut search_key_to_payload = HashMap::<Vec<u8>, Vec<u8>>::default();
+        for proto_bytes in protos {
+            let proto = ProtoIngestedBlockData::decode(&*proto_bytes)?;
+            for e_tx_out_record in proto.e_tx_out_records {
+                search_key_to_payload.insert(e_tx_out_record.search_key, e_tx_out_record.payload);
+            }
+        }
+
+        Ok(search_keys
+            .iter()
+            .map(|search_key| match search_key_to_payload.get(search_key) {
+                Some(payload) => TxOutSearchResult {
+                    search_key: search_key.clone(),
+                    result_code: TxOutSearchResultCode::Found as u32,
+                    ciphertext: payload.clone(),
+                    payload_length: 0,
+                },
+                None => TxOutSearchResult {
+                    search_key: search_key.clone(),
+                    result_code: TxOutSearchResultCode::NotFound as u32,
+                    ciphertext: Default::default(),
+                    payload_length: 0,
+                },
+            })
+            .collect())
+    }
+
+    fn update_last_active_at(
+        &self,
+        ingest_invocation_id: &IngestInvocationId,
+    ) -> Result<(), Self::Error> {
+        self.with_conn(|conn| Self::update_last_active_at_impl(conn, ingest_invocation_id))
+    }
+
+    fn get_tx_outs_by_block_and_key(
+        &self,
+        ingress_key: CompressedRistrettoPublic,
+        block_index: u64,
+    ) -> Result<Option<Vec<ETxOutRecord>>, Self::Error> {
+        let key_bytes: &[u8] = ingress_key.as_ref();
+
+        // The result of load should be 0 or 1, since there is a database constraint
+        // around ingress keys and block indices
+        let protos: Vec<Vec<u8>> = self.with_conn(|conn| {
+            Ok(schema::ingested_blocks::dsl::ingested_blocks
+                .filter(schema::ingested_blocks::dsl::ingress_public_key.eq(key_bytes))
+                .filter(schema::ingested_blocks::dsl::block_number.eq(block_index as i64))
+                .select(schema::ingested_blocks::dsl::proto_ingested_block_data)
+                .load::<Vec<u8>>(conn)?)
+        })?;
+
+        if protos.is_empty() {
+            Ok(None)
+        } else if protos.len() == 1 {
+            let proto = ProtoIngestedBlockData::decode(&*protos[0])?;
+            Ok(Some(proto.e_tx_out_records))
+        } else {
+            Err(Error::IngestedBlockSchemaViolation(format!("Found {} different entries for ingress_key {:?} and block_index {}, which goes against the constraint", protos.len(), ingress_key, block_index)))
+        }
+    }
+
+    fn get_tx_outs_by_block_range_and_key(
+        &self,
+        ingress_key: CompressedRistrettoPublic,
+        block_index: u64,
+        block_count: usize,
+    ) -> Result<Vec<Vec<ETxOutRecord>>, Self::Error> {
+        let key_bytes: &[u8] = ingress_key.as_ref();
+
+        // We will get one row for each hit in the table we found, ordered by block
+        // number so that we can detect any gaps in the data
+        let rows: Vec<(i64, Vec<u8>)> = self.with_conn(|conn| {
+            use schema::ingested_blocks::dsl;
+            Ok(dsl::ingested_blocks
+                .filter(dsl::ingress_public_key.eq(key_bytes))
+                .filter(dsl::block_number.ge(block_index as i64))
+                .limit(block_count as i64)
+                .select((dsl::block_number, dsl::proto_ingested_block_data))
+                .order(dsl::block_number.asc())
+                .load(conn)?)
+        })?;
+
+        let mut result = Vec::new();
+        for (idx, (block_number, proto)) in rows.into_iter().enumerate() {
+            if block_index + idx as u64 == block_number as u64 {
+                let proto = ProtoIngestedBlockData::decode(&*proto)?;
+                result.push(proto.e_tx_out_records);
+            } else {
+                log::warn!(self.logger, "When querying for block index {} and up to {} blocks on, the {}'th response has block_number {} which is not expected. Gaps in the data?", block_index, block_count, idx, block_number);
+                break;
+            }
+        }
+        Ok(result)
+    }
+
+    fn get_invocation_id_by_block_and_key(
+        &self,
+        ingress_key: CompressedRistrettoPublic,
+        block_index: u64,
+    ) -> Result<Option<IngestInvocationId>, Self::Error> {
+        let key_bytes: &[u8] = ingress_key.as_ref();
+
+        // The result of load should be 0 or 1, since there is a database constraint
+        // around ingress keys and block indices
+        let iids: Vec<i64> = self.with_conn(|conn| {
+            Ok(schema::ingested_blocks::dsl::ingested_blocks
+                .filter(schema::ingested_blocks::dsl::ingress_public_key.eq(key_bytes))
+                .filter(schema::ingested_blocks::dsl::block_number.eq(block_index as i64))
+                .select(schema::ingested_blocks::dsl::ingest_invocation_id)
+                .load::<i64>(conn)?)
+        })?;
+
+        if iids.is_empty() {
+            Ok(None)
+        } else if iids.len() == 1 {
+            Ok(Some(iids[0].into()))
+        } else {
+            Err(Error::IngestedBlockSchemaViolation(format!("Found {} different entries for ingress_key {:?} and block_index {}, which goes against the constraint", iids.len(), ingress_key, block_index)))
+        }
+    }
+
+    fn get_cumulative_txo_count_for_block(
+        &self,
+        block_index: u64,
+    ) -> Result<Option<u64>, Self::Error> {
+        let data: Vec<i64> = self.with_conn(|conn| {
+            Ok(schema::ingested_blocks::dsl::ingested_blocks
+                .filter(schema::ingested_blocks::dsl::block_number.eq(block_index as i64))
+                .select(schema::ingested_blocks::dsl::cumulative_txo_count)
+                .load::<i64>(conn)?)
+        })?;
+
+        if data.is_empty() {
+            Ok(None)
+        } else {
+            let cumulative_txo_count = data[0];
+            if data.iter().all(|val| *val == cumulative_txo_count) {
+                Ok(Some(cumulative_txo_count as u64))
+            } else {
+                Err(Error::IngestedBlockSchemaViolation(format!(
+                    "Found multiple cumulative_txo_count values for block {}: {:?}",
+                    block_index, data
+                )))
+            }
+        }
+    }
+
+    fn get_block_signature_timestamp_for_block(
+        &self,
+        block_index: u64,
+    ) -> Result<Option<u64>, Self::Error> {
+        self.with_conn(|conn| {
+            let data = schema::ingested_blocks::dsl::ingested_blocks
+                .filter(schema::ingested_blocks::dsl::block_number.eq(block_index as i64))
+                .select(schema::ingested_blocks::dsl::block_signature_timestamp)
+                .load::<i64>(conn)?;
+            Ok(data.first().map(|val| *val as u64))
+        })
+    }
+
+    fn get_highest_known_block_index(&self) -> Result<Option<u64>, Self::Error> {
+        self.with_conn(Self::get_highest_known_block_index_impl)
+    }
+
+    fn get_expired_invocations(
+        &self,
+        expiration: NaiveDateTime,
+    ) -> Result<Vec<ExpiredInvocationRecord>, Self::Error> {
+        let data = self.with_conn(|conn| {
+            use schema::ingest_invocations::dsl;
+            Ok(dsl::ingest_invocations
+                .select((
+                    dsl::id,
+                    dsl::rng_version,
+                    dsl::egress_public_key,
+                    dsl::last_active_at,
+                ))
+                .filter(dsl::last_active_at.lt(expiration))
+                .load::<(i64, i32, Vec<u8>, NaiveDateTime)>(conn)?)
+        })?;
+
+        Ok(data
+            .into_iter()
+            .map(
+                |(ingest_invocation_id, rng_version, egress_public_key_bytes, last_active_at)| {
+                    ExpiredInvocationRecord {
+                        ingest_invocation_id,
+                        egress_public_key: KexRngPubkey {
+                            public_key: egress_public_key_bytes,
+                            version: rng_version as u32,
+                        },
+                        last_active_at,
+                    }
+                },
+            )
+            .collect())
+    }
+}
+
+/// See trait `fog_recovery_db_iface::ReportDb` for documentation.
+impl ReportDb for SqliteRecoveryDb {
+    type Error = Error;
+
+    fn get_all_reports(&self) -> Result<Vec<(String, ReportData)>, Self::Error> {
+        let rows = self.with_conn(|conn| {
+            Ok(schema::reports::dsl::reports
+                .select((
+                    schema::reports::dsl::ingest_invocation_id,
+                    schema::reports::dsl::fog_report_id,
+                    schema::reports::dsl::report,
+                    schema::reports::dsl::pubkey_expiry,
+                ))
+                .order_by(schema::reports::dsl::id)
+                .load::<(Option<i64>, String, Vec<u8>, i64)>(conn)?)
+        })?;
+
+        rows.into_iter()
+            .map(|(ingest_invocation_id, report_id, report, pubkey_expiry)| {
+                let report = VerificationReport::decode(&*report)?;
+                Ok((
+                    report_id,
+                    ReportData {
+                        ingest_invocation_id: ingest_invocation_id.map(IngestInvocationId::from),
+                        report,
+                        pubkey_expiry: pubkey_expiry as u64,
+                    },
+                ))
+            })
+            .collect()
+    }
+
+    fn set_report(
+        &self,
+        ingress_key: &CompressedRistrettoPublic,
+        report_id: &str,
+        data: &ReportData,
+    ) -> Result<IngressPublicKeyStatus, Self::Error> {
+        let mut report_bytes = Vec::with_capacity(data.report.encoded_len());
+        data.report.encode(&mut report_bytes)?;
+
+        self.with_write_transaction(|conn| {
+            let key_bytes: &[u8] = ingress_key.as_ref();
+
+            // First, try to update the pubkey_expiry value on this ingress key, only
+            // allowing it to increase, and only if it is not retired
+            {
+                use schema::ingress_keys::dsl;
+                diesel::update(
+                    dsl::ingress_keys
+                        .filter(dsl::ingress_public_key.eq(key_bytes))
+                        .filter(dsl::retired.eq(false))
+                        .filter(dsl::pubkey_expiry.lt(data.pubkey_expiry as i64)),
+                )
+                .set(dsl::pubkey_expiry.eq(data.pubkey_expiry as i64))
+                .execute(conn)?;
+            }
+
+            // The key might not exist, or it might have had a larger pubkey expiry
+            // (because this server is behind), so read back whichever is the case
+            let result = Self::get_ingress_key_status_impl(conn, ingress_key)?
+                .ok_or(Error::MissingIngressKey(*ingress_key))?;
+
+            log::info!(self.logger, "Got status for key: {:?}", result);
+            if result.retired {
+                log::info!(self.logger, "Cannot publish key because it is retired");
+                return Ok(result);
+            }
+
+            // Replace the report with this id in place if there is one, so that it
+            // keeps its position in get_all_reports
+            let updated_row_count = {
+                use schema::reports::dsl;
+                diesel::update(dsl::reports.filter(dsl::fog_report_id.eq(report_id)))
+                    .set((
+                        dsl::ingress_public_key.eq(key_bytes),
+                        dsl::ingest_invocation_id.eq(data.ingest_invocation_id.map(i64::from)),
+                        dsl::report.eq(report_bytes.as_slice()),
+                        dsl::pubkey_expiry.eq(data.pubkey_expiry as i64),
+                    ))
+                    .execute(conn)?
+            };
+
+            if updated_row_count == 0 {
+                let report = models::NewReport {
+                    ingress_public_key: key_bytes,
+                    ingest_invocation_id: data.ingest_invocation_id.map(i64::from),
+                    fog_report_id: report_id,
+                    report: report_bytes.as_slice(),
+                    pubkey_expiry: data.pubkey_expiry as i64,
+                };
+
+                diesel::insert_into(schema::reports::dsl::reports)
+                    .values(&report)
+                    .execute(conn)?;
+            }
+
+            Ok(result)
+        })
+    }
+
+    fn remove_report(&self, report_id: &str) -> Result<(), Self::Error> {
+        self.with_conn(|conn| {
+            diesel::delete(
+                schema::reports::dsl::reports
+                    .filter(schema::reports::dsl::fog_report_id.eq(report_id)),
+            )
+            .execute(conn)?;
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use mc_common::logger::{test_with_logger, Logger};
+    use mc_crypto_keys::RistrettoPublic;
+    use mc_fog_test_infra::db_tests::{random_block, random_kex_rng_pubkey};
+    use mc_util_from_random::FromRandom;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test_with_logger]
+    fn test_in_memory_clones_share_data(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([123u8; 32]);
+        let db = SqliteRecoveryDb::new_in_memory(logger).unwrap();
+        let db2 = db.clone();
+
+        let ingress_key = CompressedRistrettoPublic::from(RistrettoPublic::from_random(&mut rng));
+        db.new_ingress_key(&ingress_key, 10).unwrap();
+
+        let status = db2.get_ingress_key_status(&ingress_key).unwrap().unwrap();
+        assert_eq!(status.start_block, 10);
+    }
+
+    #[test_with_logger]
+    fn test_add_block_data_twice(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([123u8; 32]);
+        let db = SqliteRecoveryDb::new_in_memory(logger).unwrap();
+
+        let ingress_key = CompressedRistrettoPublic::from(RistrettoPublic::from_random(&mut rng));
+        db.new_ingress_key(&ingress_key, 0).unwrap();
+        let invoc_id1 = db
+            .new_ingest_invocation(None, &ingress_key, &random_kex_rng_pubkey(&mut rng), 0)
+            .unwrap();
+        let invoc_id2 = db
+            .new_ingest_invocation(None, &ingress_key, &random_kex_rng_pubkey(&mut rng), 0)
+            .unwrap();
+
+        let (block, records) = random_block(&mut rng, 0, 10);
+        let status = db.add_block_data(&invoc_id1, &block, 0, &records).unwrap();
+        assert!(!status.block_already_scanned_with_this_key);
+
+        // Same ingress key, so another invocation can't add the same block.
+        let status = db.add_block_data(&invoc_id2, &block, 0, &records).unwrap();
+        assert!(status.block_already_scanned_with_this_key);
+
+        assert_eq!(
+            db.get_tx_outs_by_block_and_key(ingress_key, 0).unwrap(),
+            Some(records)
+        );
+        assert_eq!(
+            db.get_invocation_id_by_block_and_key(ingress_key, 0)
+                .unwrap(),
+            Some(invoc_id1)
+        );
+    }
+
+    #[test_with_logger]
+    fn test_get_expired_invocations(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([123u8; 32]);
+        let db = SqliteRecoveryDb::new_in_memory(logger).unwrap();
+
+        let ingress_key = CompressedRistrettoPublic::from(RistrettoPublic::from_random(&mut rng));
+        db.new_ingress_key(&ingress_key, 0).unwrap();
+        let egress_key = random_kex_rng_pubkey(&mut rng);
+        let invoc_id = db
+            .new_ingest_invocation(None, &ingress_key, &egress_key, 0)
+            .unwrap();
+
+        let an_hour_ago = Utc::now().naive_utc() - Duration::hours(1);
+        assert!(db.get_expired_invocations(an_hour_ago).unwrap().is_empty());
+
+        let in_an_hour = Utc::now().naive_utc() + Duration::hours(1);
+        let expired = db.get_expired_invocations(in_an_hour).unwrap();
+        assert_eq!(expired.len(), 1);
+        assert_eq!(
+            IngestInvocationId::from(expired[0].ingest_invocation_id),
+            invoc_id
+        );
+        assert_eq!(expired[0].egress_public_key, egress_key);
+    }
+
+    fn create_report(name: &str) -> VerificationReport {
+        VerificationReport {
+            sig: format!("{} sig", name).into_bytes().into(),
+            chain: vec![format!("{} chain", name).into_bytes()],
+            http_body: format!("{} body", name),
+        }
+    }
+
+    #[test_with_logger]
+    fn test_reports_db(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([123u8; 32]);
+        let db = SqliteRecoveryDb::new_in_memory(logger).unwrap();
+
+        let ingress_key = CompressedRistrettoPublic::from(RistrettoPublic::from_random(&mut rng));
+        db.new_ingress_key(&ingress_key, 123).unwrap();
+
+        let invoc_id1 = db
+            .new_ingest_invocation(None, &ingress_key, &random_kex_rng_pubkey(&mut rng), 123)
+            .unwrap();
+
+        let invoc_id2 = db
+            .new_ingest_invocation(None, &ingress_key, &random_kex_rng_pubkey(&mut rng), 123)
+            .unwrap();
+
+        // We start with no reports.
+        assert_eq!(db.get_all_reports().unwrap(), vec![]);
+
+        // Insert a report and see that we can get it back.
+        let report_id1 = "";
+        let report1 = ReportData {
+            ingest_invocation_id: Some(invoc_id1),
+            report: create_report(report_id1),
+            pubkey_expiry: 102030,
+        };
+        let key_status = db.set_report(&ingress_key, report_id1, &report1).unwrap();
+        assert_eq!(key_status.pubkey_expiry, 102030);
+
+        assert_eq!(
+            db.get_all_reports().unwrap(),
+            vec![(report_id1.into(), report1.clone())]
+        );
+
+        // Insert another report and see that we can get it back.
+        let report_id2 = "report 2";
+        let report2 = ReportData {
+            ingest_invocation_id: Some(invoc_id2),
+            report: create_report(report_id2),
+            pubkey_expiry: 10203040,
+        };
+        let key_status = db.set_report(&ingress_key, report_id2, &report2).unwrap();
+        assert_eq!(key_status.pubkey_expiry, 10203040);
+
+        assert_eq!(
+            db.get_all_reports().unwrap(),
+            vec![
+                (report_id1.into(), report1),
+                (report_id2.into(), report2.clone()),
+            ]
+        );
+
+        // Update an existing report. It keeps its position.
+        let updated_report1 = ReportData {
+            ingest_invocation_id: Some(invoc_id2),
+            report: create_report("updated_report1"),
+            pubkey_expiry: 424242,
+        };
+
+        let key_status = db
+            .set_report(&ingress_key, report_id1, &updated_report1)
+            .unwrap();
+        assert_eq!(
+            key_status.pubkey_expiry, 10203040,
+            "pubkey expiry should not have decreased"
+        );
+
+        assert_eq!(
+            db.get_all_reports().unwrap(),
+            vec![
+                (report_id1.into(), updated_report1),
+                (report_id2.into(), report2.clone()),
+            ]
+        );
+
+        // Delete the first report and ensure it got removed.
+        db.remove_report(report_id1).unwrap();
+
+        assert_eq!(
+            db.get_all_reports().unwrap(),
+            vec![(report_id2.into(), report2.clone())]
+        );
+
+        // Retire the ingress public key
+        db.retire_ingress_key(&ingress_key, true).unwrap();
+
+        let report1 = ReportData {
+            ingest_invocation_id: Some(invoc_id1),
+            report: create_report(report_id1),
+            pubkey_expiry: 10203050,
+        };
+        let key_status = db.set_report(&ingress_key, report_id1, &report1).unwrap();
+        assert!(key_status.retired);
+        assert_eq!(
+            key_status.pubkey_expiry, 10203040,
+            "pubkey expiry should not have increased after retiring the key"
+        );
+        assert_eq!(
+            db.get_all_reports().unwrap(),
+            vec![(report_id2.into(), report2.clone())],
+            "a report should not be published for a retired key"
+        );
+
+        // Unretire the ingress public key
+        db.retire_ingress_key(&ingress_key, false).unwrap();
+
+        let report1 = ReportData {
+            ingest_invocation_id: Some(invoc_id1),
+            report: create_report(report_id1),
+            pubkey_expiry: 10203060,
+        };
+        let key_status = db.set_report(&ingress_key, report_id1, &report1).unwrap();
+        assert_eq!(
+            key_status.pubkey_expiry, 10203060,
+            "pubkey expiry should have increased again after unretiring the key"
+        );
+        assert_eq!(
+            db.get_all_reports().unwrap(),
+            vec![(report_id2.into(), report2), (report_id1.into(), report1)]
+        );
+
+        // Setting a report for an unknown ingress key fails.
+        let unknown_key = CompressedRistrettoPublic::from(RistrettoPublic::from_random(&mut rng));
+        assert!(matches!(
+            db.set_report(&unknown_key, "unknown", &report1),
+            Err(Error::MissingIngressKey(key)) if key == unknown_key
+        ));
+    }
+}
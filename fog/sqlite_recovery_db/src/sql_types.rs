@@ -0,0 +1,121 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+use diesel::{
+    deserialize::{self, FromSql},
+    serialize::{self, Output, ToSql},
+    sql_types::{Binary, Text},
+    sqlite::{Sqlite, SqliteValue},
+};
+use mc_crypto_keys::CompressedRistrettoPublic;
+use std::{fmt, io::Write, ops::Deref};
+
+/// The type of a user event. SQLite has no enum types, so this is stored as
+/// text, using the same names as the PostgreSQL enum.
+#[derive(AsExpression, FromSqlRow, Clone, Copy, Debug, Eq, PartialEq)]
+#[sql_type = "Text"]
+pub enum UserEventType {
+    NewIngestInvocation,
+    DecommissionIngestInvocation,
+    MissingBlocks,
+}
+
+impl UserEventType {
+    /// The text stored in the event_type column for this event type.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::NewIngestInvocation => "new_ingest_invocation",
+            Self::DecommissionIngestInvocation => "decommission_ingest_invocation",
+            Self::MissingBlocks => "missing_blocks",
+        }
+    }
+}
+
+impl FromSql<Text, Sqlite> for UserEventType {
+    fn from_sql(value: Option<&SqliteValue>) -> deserialize::Result<Self> {
+        let text = <String as FromSql<Text, Sqlite>>::from_sql(value)?;
+        match text.as_str() {
+            "new_ingest_invocation" => Ok(Self::NewIngestInvocation),
+            "decommission_ingest_invocation" => Ok(Self::DecommissionIngestInvocation),
+            "missing_blocks" => Ok(Self::MissingBlocks),
+            other => Err(format!("UserEventType: Unknown event type {}", other).into()),
+        }
+    }
+}
+
+impl ToSql<Text, Sqlite> for UserEventType {
+    fn to_sql<W: Write>(&self, out: &mut Output<W, Sqlite>) -> serialize::Result {
+        <str as ToSql<Text, Sqlite>>::to_sql(self.as_str(), out)
+    }
+}
+
+#[derive(AsExpression, FromSqlRow, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[sql_type = "Binary"]
+pub struct SqlCompressedRistrettoPublic(CompressedRistrettoPublic);
+
+impl Deref for SqlCompressedRistrettoPublic {
+    type Target = CompressedRistrettoPublic;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl From<CompressedRistrettoPublic> for SqlCompressedRistrettoPublic {
+    fn from(src: CompressedRistrettoPublic) -> Self {
+        Self(src)
+    }
+}
+
+impl From<&CompressedRistrettoPublic> for SqlCompressedRistrettoPublic {
+    fn from(src: &CompressedRistrettoPublic) -> Self {
+        Self(*src)
+    }
+}
+
+impl fmt::Display for SqlCompressedRistrettoPublic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromSql<Binary, Sqlite> for SqlCompressedRistrettoPublic {
+    fn from_sql(value: Option<&SqliteValue>) -> deserialize::Result<Self> {
+        let vec = <Vec<u8> as FromSql<Binary, Sqlite>>::from_sql(value)?;
+        if vec.len() != 32 {
+            return Err("SqlCompressedRistrettoPublic: Invalid array length".into());
+        }
+
+        let mut key = [0; 32];
+        key.copy_from_slice(&vec);
+
+        Ok(SqlCompressedRistrettoPublic(
+            CompressedRistrettoPublic::from(&key),
+        ))
+    }
+}
+
+impl ToSql<Binary, Sqlite> for SqlCompressedRistrettoPublic {
+    fn to_sql<W: Write>(&self, out: &mut Output<W, Sqlite>) -> serialize::Result {
+        <[u8] as ToSql<Binary, Sqlite>>::to_sql(self.0.as_ref(), out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn user_event_type_names_match_postgres_enum() {
+        // The decommission subquery in search_user_events relies on these
+        // names.
+        assert_eq!(
+            UserEventType::NewIngestInvocation.as_str(),
+            "new_ingest_invocation"
+        );
+        assert_eq!(
+            UserEventType::DecommissionIngestInvocation.as_str(),
+            "decommission_ingest_invocation"
+        );
+        assert_eq!(UserEventType::MissingBlocks.as_str(), "missing_blocks");
+    }
+}
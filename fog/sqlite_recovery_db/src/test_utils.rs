@@ -0,0 +1,39 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! Utilities for testing.
+
+use crate::SqliteRecoveryDb;
+use mc_common::logger::Logger;
+use std::path::PathBuf;
+use tempdir::TempDir;
+
+/// Context for tests, holding a database file in a temporary directory that
+/// is removed when the context is dropped.
+pub struct SqliteRecoveryDbTestContext {
+    db_dir: TempDir,
+    logger: Logger,
+}
+
+impl SqliteRecoveryDbTestContext {
+    /// Instantiate a context.
+    pub fn new(logger: Logger) -> Self {
+        let db_dir =
+            TempDir::new("fog_sqlite_recovery_db").expect("Could not make tempdir for sqlite db");
+
+        // Create the database and run the migrations.
+        let context = Self { db_dir, logger };
+        context.get_db_instance();
+        context
+    }
+
+    /// Get DB path.
+    pub fn db_path(&self) -> PathBuf {
+        self.db_dir.path().join("recovery.db")
+    }
+
+    /// Get DB instance.
+    pub fn get_db_instance(&self) -> SqliteRecoveryDb {
+        SqliteRecoveryDb::new_from_path(self.db_path(), self.logger.clone())
+            .expect("failed creating new SqliteRecoveryDb")
+    }
+}
@@ -487,6 +487,53 @@ pub fn recovery_db_rng_records_decommissioning<DB: RecoveryDb>(
     assert_eq!(12, decommissioned_invocs[1].last_ingested_block);
 }
 
+/// Test that paging through user events with a limit returns the same events
+/// as a single unlimited search
+pub fn recovery_db_user_events_pagination<DB: RecoveryDb>(
+    rng: &mut (impl RngCore + CryptoRng),
+    db: &DB,
+) {
+    const NUM_EVENTS: usize = 7;
+    const PAGE_SIZE: usize = 3;
+
+    let ingress_key = CompressedRistrettoPublic::from(RistrettoPublic::from_random(rng));
+    db.new_ingress_key(&ingress_key, 0).unwrap();
+
+    for block_index in 0..NUM_EVENTS as u64 {
+        db.new_ingest_invocation(None, &ingress_key, &random_kex_rng_pubkey(rng), block_index)
+            .unwrap();
+    }
+
+    let (all_events, last_user_event_id) = db.search_user_events(0).unwrap();
+    assert_eq!(all_events.len(), NUM_EVENTS);
+
+    let mut paged_events = Vec::new();
+    let mut start_from_user_event_id = 0;
+    loop {
+        let (user_events, next_start_from_user_event_id) = db
+            .search_user_events_with_limit(start_from_user_event_id, PAGE_SIZE)
+            .unwrap();
+        assert!(user_events.len() <= PAGE_SIZE);
+        assert!(next_start_from_user_event_id >= start_from_user_event_id);
+
+        let done = user_events.len() < PAGE_SIZE;
+        paged_events.extend(user_events);
+        start_from_user_event_id = next_start_from_user_event_id;
+        if done {
+            break;
+        }
+    }
+
+    assert_eq!(paged_events, all_events);
+    assert_eq!(start_from_user_event_id, last_user_event_id);
+
+    // A limit of zero returns nothing and doesn't move the cursor
+    assert_eq!(
+        db.search_user_events_with_limit(0, 0).unwrap(),
+        (Vec::new(), 0)
+    );
+}
+
 /// Basic tests that creating, checking on, and retiring ingress keys works as
 /// expected
 pub fn test_recovery_db_ingress_keys<DB: RecoveryDb>(
@@ -33,6 +33,11 @@ pub struct QueryRequestAAD {
     // TODO this is currently unused
     #[prost(uint64, tag = "2")]
     pub start_from_block_index: u64,
+
+    /// The maximum number of user events to return. The server may return
+    /// fewer than this, and applies its own limit if this is zero.
+    #[prost(uint64, tag = "3")]
+    pub max_user_events: u64,
 }
 
 /// The QueryRequest structure, which should be passed as the encrypted data
@@ -41,6 +46,9 @@ pub struct QueryRequestAAD {
 pub struct QueryRequest {
     /// The search keys to query for TxOut's
     /// These should all be values that came from KexRng's
+    ///
+    /// The server may only answer a prefix of these, if there are more than it
+    /// is willing to search for in one request.
     #[prost(bytes, repeated, tag = "1")]
     pub get_txos: Vec<Vec<u8>>,
 }
@@ -81,6 +89,10 @@ pub struct QueryResponse {
     pub decommissioned_ingest_invocations: Vec<DecommissionedIngestInvocation>,
 
     /// The results of each tx out search query
+    ///
+    /// These answer the search keys of the request in order. If there are
+    /// fewer results than search keys, the remaining search keys were not
+    /// searched and should be sent again in another request.
     #[prost(message, repeated, tag = "7")]
     pub tx_out_search_results: Vec<TxOutSearchResult>,
 
@@ -93,6 +105,12 @@ pub struct QueryResponse {
     /// clients sample for mixins.
     #[prost(uint64, tag = "9")]
     pub last_known_block_cumulative_txo_count: u64,
+
+    /// Whether the number of user events returned hit a limit, so that there
+    /// may be more events after next_start_from_user_event_id. If so, the user
+    /// should query again starting from next_start_from_user_event_id.
+    #[prost(bool, tag = "10")]
+    pub may_have_more_user_events: bool,
 }
 
 /// A record that can be used by the user to produce an Rng shared with fog
@@ -180,6 +198,27 @@ pub struct TxOutSearchResult {
     /// The ciphertext payload
     #[prost(bytes, tag = "3")]
     pub ciphertext: Vec<u8>,
+    /// If nonzero, the ciphertext is padded with zeros to
+    /// FIXED_CIPHERTEXT_LENGTH, and this is the length of the payload at its
+    /// start.
+    #[prost(fixed32, tag = "4")]
+    pub payload_length: u32,
+}
+
+/// The length that a view router pads the ciphertexts of the results it
+/// merges to, so that their length does not reveal which store found them.
+pub const FIXED_CIPHERTEXT_LENGTH: usize = 255;
+
+impl TxOutSearchResult {
+    /// The ciphertext payload, without the padding.
+    pub fn payload(&self) -> &[u8] {
+        let payload_length = self.payload_length as usize;
+        if payload_length == 0 || payload_length > self.ciphertext.len() {
+            &self.ciphertext
+        } else {
+            &self.ciphertext[..payload_length]
+        }
+    }
 }
 
 /// An enum capturing the Oneof in the proto file around masked token id bytes
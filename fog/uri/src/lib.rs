@@ -18,6 +18,20 @@ impl UriScheme for FogViewScheme {
     const DEFAULT_INSECURE_PORT: u16 = 3225;
 }
 
+/// Fog View Store Uri Scheme
+#[derive(Debug, Hash, Ord, PartialOrd, Eq, PartialEq, Clone)]
+pub struct FogViewStoreScheme {}
+
+impl UriScheme for FogViewStoreScheme {
+    /// The part before the '://' of a URL.
+    const SCHEME_SECURE: &'static str = "fog-view-store";
+    const SCHEME_INSECURE: &'static str = "insecure-fog-view-store";
+
+    /// Default port numbers
+    const DEFAULT_SECURE_PORT: u16 = 443;
+    const DEFAULT_INSECURE_PORT: u16 = 3225;
+}
+
 /// Fog Ledger Uri Scheme
 #[derive(Debug, Hash, Ord, PartialOrd, Eq, PartialEq, Clone)]
 pub struct FogLedgerScheme {}
@@ -32,6 +46,20 @@ impl UriScheme for FogLedgerScheme {
     const DEFAULT_INSECURE_PORT: u16 = 3223;
 }
 
+/// Key Image Store Uri Scheme
+#[derive(Debug, Hash, Ord, PartialOrd, Eq, PartialEq, Clone)]
+pub struct KeyImageStoreScheme {}
+
+impl UriScheme for KeyImageStoreScheme {
+    /// The part before the '://' of a URL.
+    const SCHEME_SECURE: &'static str = "key-image-store";
+    const SCHEME_INSECURE: &'static str = "insecure-key-image-store";
+
+    /// Default port numbers
+    const DEFAULT_SECURE_PORT: u16 = 443;
+    const DEFAULT_INSECURE_PORT: u16 = 3223;
+}
+
 /// Fog Ingest Uri Scheme
 #[derive(Debug, Hash, Ord, PartialOrd, Eq, PartialEq, Clone)]
 pub struct FogIngestScheme {}
@@ -62,9 +90,15 @@ impl UriScheme for IngestPeerScheme {
 /// Uri used when talking to fog-view service, with the right default ports and
 /// scheme.
 pub type FogViewUri = Uri<FogViewScheme>;
+/// Uri used by a fog-view router when talking to a fog-view store, with the
+/// right default ports and scheme.
+pub type FogViewStoreUri = Uri<FogViewStoreScheme>;
 /// Uri used when talking to fog-ledger service, with the right default ports
 /// and scheme.
 pub type FogLedgerUri = Uri<FogLedgerScheme>;
+/// Uri used by a key image router when talking to a fog-ledger server acting
+/// as a key image store, with the right default ports and scheme.
+pub type KeyImageStoreUri = Uri<KeyImageStoreScheme>;
 /// Uri used when talking to fog-ingest service, with the right default ports
 /// and scheme.
 pub type FogIngestUri = Uri<FogIngestScheme>;
@@ -73,7 +107,7 @@ pub type IngestPeerUri = Uri<IngestPeerScheme>;
 
 #[cfg(test)]
 mod tests {
-    use super::{FogLedgerUri, FogViewUri};
+    use super::{FogLedgerUri, FogViewStoreUri, FogViewUri, KeyImageStoreUri};
     use crate::ConnectionUri;
     use core::str::FromStr;
     use mc_common::ResponderId;
@@ -221,6 +255,46 @@ mod tests {
         assert!(!uri.use_tls());
     }
 
+    #[test]
+    fn test_valid_fog_view_store_uris() {
+        let uri = FogViewStoreUri::from_str("fog-view-store://node1.test.mobilecoin.com/").unwrap();
+        assert_eq!(uri.addr(), "node1.test.mobilecoin.com:443");
+        assert!(uri.use_tls());
+
+        let uri = FogViewStoreUri::from_str("insecure-fog-view-store://127.0.0.1:5200/").unwrap();
+        assert_eq!(uri.addr(), "127.0.0.1:5200");
+        assert_eq!(
+            uri.responder_id().unwrap(),
+            ResponderId::from_str("127.0.0.1:5200").unwrap()
+        );
+        assert!(!uri.use_tls());
+
+        let uri = FogViewStoreUri::from_str(
+            "insecure-fog-view-store://127.0.0.1:5200/?responder-id=store1.fog.com:443",
+        )
+        .unwrap();
+        assert_eq!(
+            uri.responder_id().unwrap(),
+            ResponderId::from_str("store1.fog.com:443").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_valid_key_image_store_uris() {
+        let uri =
+            KeyImageStoreUri::from_str("key-image-store://node1.test.mobilecoin.com/").unwrap();
+        assert_eq!(uri.addr(), "node1.test.mobilecoin.com:443");
+        assert!(uri.use_tls());
+
+        let uri = KeyImageStoreUri::from_str("insecure-key-image-store://127.0.0.1/").unwrap();
+        assert_eq!(uri.addr(), "127.0.0.1:3223");
+        assert_eq!(
+            uri.responder_id().unwrap(),
+            ResponderId::from_str("127.0.0.1:3223").unwrap()
+        );
+        assert!(!uri.use_tls());
+    }
+
     #[test]
     fn test_invalid_fog_view_uris() {
         assert!(FogViewUri::from_str("http://127.0.0.1/").is_err());
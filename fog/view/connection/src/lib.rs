@@ -96,9 +96,11 @@ impl FogViewConnection for FogViewGrpcClient {
                 get_txos: search_keys,
             };
 
+            // Leave the number of user events up to the server's limit
             let req_aad = QueryRequestAAD {
                 start_from_user_event_id,
                 start_from_block_index,
+                max_user_events: 0,
             };
 
             let aad_bytes = mc_util_serial::encode(&req_aad);
@@ -14,7 +14,7 @@ use displaydoc::Display;
 use mc_attest_core::{Quote, Report, SgxError, TargetInfo, VerificationReport};
 use mc_attest_enclave_api::{
     ClientAuthRequest, ClientAuthResponse, ClientSession, EnclaveMessage,
-    Error as AttestEnclaveError,
+    Error as AttestEnclaveError, PeerAuthRequest, PeerAuthResponse, PeerSession,
 };
 use mc_common::ResponderId;
 use mc_crypto_keys::X25519Public;
@@ -34,6 +34,10 @@ pub struct UntrustedQueryResponse {
     /// The next value the user should use for start_from_user_event_id.
     pub next_start_from_user_event_id: i64,
 
+    /// Whether the number of user events hit a limit, so that there may be
+    /// more events after next_start_from_user_event_id.
+    pub may_have_more_user_events: bool,
+
     /// The number of blocks at the time that the request was evaluated.
     pub highest_processed_block_count: u64,
 
@@ -83,6 +87,30 @@ pub enum ViewEnclaveRequest {
     Query(EnclaveMessage<ClientSession>, UntrustedQueryResponse),
     /// Request from untrusted to add encrypted tx out records to ORAM
     AddRecords(Vec<ETxOutRecord>),
+
+    // View store / router specific
+    /// Initiate peering with a view store enclave
+    PeerInit(ResponderId),
+    /// Accept a peering request from a view router enclave
+    PeerAccept(PeerAuthRequest),
+    /// Complete peering with a view store enclave
+    PeerConnect(ResponderId, PeerAuthResponse),
+    /// Close a peer connection
+    PeerClose(PeerSession),
+    /// An fog_types::view::QueryRequest encrypted by a view router enclave
+    /// Respond with a fog_types::view::QueryResponse containing only
+    /// tx_out_search_results, encrypted for the router
+    QueryStore(EnclaveMessage<PeerSession>),
+    /// An encrypted fog_types::view::QueryRequest from a client, to be
+    /// re-encrypted for each of the given view store sessions
+    CreateMultiViewStoreQuery(EnclaveMessage<ClientSession>, Vec<PeerSession>),
+    /// The encrypted view store responses to a query, to be merged into a
+    /// fog_types::view::QueryResponse for the client
+    CollateShardQueryResponses(
+        ClientSession,
+        Vec<EnclaveMessage<PeerSession>>,
+        UntrustedQueryResponse,
+    ),
 }
 
 /// The parameters needed to initialize the view enclave
@@ -94,8 +122,13 @@ pub struct ViewEnclaveInitParams {
     pub eid: sgx_enclave_id_t,
     /// The responder id for this enclave to use for client connections.
     pub self_client_id: ResponderId,
+    /// The responder id for this enclave to use for peer connections, i.e.
+    /// when a view router connects to it as a view store.
+    pub self_peer_id: ResponderId,
     /// The desired capacity of the store of records
     pub desired_capacity: u64,
+    /// The maximum number of search keys to answer in one client query.
+    pub max_search_keys_per_query: u64,
 }
 
 /// The API for the view enclave
@@ -134,6 +167,49 @@ pub trait ViewEnclaveApi: ReportableEnclave {
     /// Add encrypted tx out records from the fog recovery db to the view
     /// enclave's ORAM
     fn add_records(&self, records: Vec<ETxOutRecord>) -> Result<()>;
+
+    // VIEW STORE / ROUTER METHODS
+
+    /// Initiate peering with a view store enclave
+    fn peer_init(&self, peer_id: &ResponderId) -> Result<PeerAuthRequest>;
+
+    /// Accept a connection proposal from a view router enclave
+    fn peer_accept(&self, req: PeerAuthRequest) -> Result<(PeerAuthResponse, PeerSession)>;
+
+    /// Handle the remote peer_accept response to form the connection
+    fn peer_connect(
+        &self,
+        peer_id: &ResponderId,
+        msg: PeerAuthResponse,
+    ) -> Result<(PeerSession, VerificationReport)>;
+
+    /// Close a connection with a peer
+    fn peer_close(&self, session_id: &PeerSession) -> Result<()>;
+
+    /// Service a QueryRequest forwarded by a view router, looking up its
+    /// search keys in this enclave's ORAM
+    fn query_store(
+        &self,
+        payload: EnclaveMessage<PeerSession>,
+    ) -> Result<EnclaveMessage<PeerSession>>;
+
+    /// Decrypt a user's QueryRequest and re-encrypt it for each of the view
+    /// stores this router is connected to. The results are returned in the
+    /// same order as `store_sessions`.
+    fn create_multi_view_store_query(
+        &self,
+        payload: EnclaveMessage<ClientSession>,
+        store_sessions: Vec<PeerSession>,
+    ) -> Result<Vec<EnclaveMessage<PeerSession>>>;
+
+    /// Merge the view store responses to a user's query, and encrypt the
+    /// resulting QueryResponse for the user
+    fn collate_shard_query_responses(
+        &self,
+        client_session: ClientSession,
+        shard_query_responses: Vec<EnclaveMessage<PeerSession>>,
+        untrusted_query_response: UntrustedQueryResponse,
+    ) -> Result<Vec<u8>>;
 }
 
 /// Helper trait which reduces boiler-plate in untrusted side
@@ -187,6 +263,13 @@ pub enum Error {
     Poison,
     /// Enclave not initialized
     EnclaveNotInitialized,
+    /// Peer session is not known to the enclave
+    UnknownPeer,
+    /// Shard query responses did not answer the same search keys
+    ShardResponseMismatch,
+    /// Shard query response has a ciphertext longer than the fixed ciphertext
+    /// length
+    ShardCiphertextTooLong,
 }
 
 impl From<SgxError> for Error {
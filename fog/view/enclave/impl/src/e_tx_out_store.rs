@@ -139,6 +139,7 @@ impl<OSC: ORAMStorageCreator<StorageDataSize, StorageMetaSize>> ETxOutStore<OSC>
             search_key: search_key.to_vec(),
             result_code: TxOutSearchResultCode::InternalError as u32,
             ciphertext: vec![0u8; ValueSize::USIZE - 1 - self.last_ciphertext_size_byte as usize],
+            payload_length: 0,
         };
 
         // Early return for bad search key
@@ -9,15 +9,25 @@ extern crate alloc;
 mod e_tx_out_store;
 use e_tx_out_store::{ETxOutStore, StorageDataSize, StorageMetaSize};
 
+mod shard_responses;
+use shard_responses::collate_shard_tx_out_search_results;
+
 use alloc::vec::Vec;
+use core::sync::atomic::{AtomicUsize, Ordering};
 use mc_attest_core::{IasNonce, Quote, QuoteNonce, Report, TargetInfo, VerificationReport};
-use mc_attest_enclave_api::{ClientAuthRequest, ClientAuthResponse, ClientSession, EnclaveMessage};
-use mc_common::logger::{log, Logger};
+use mc_attest_enclave_api::{
+    ClientAuthRequest, ClientAuthResponse, ClientSession, EnclaveMessage, PeerAuthRequest,
+    PeerAuthResponse, PeerSession,
+};
+use mc_common::{
+    logger::{log, Logger},
+    ResponderId,
+};
 use mc_crypto_ake_enclave::{AkeEnclaveState, NullIdentity};
 use mc_crypto_keys::X25519Public;
 use mc_fog_recovery_db_iface::FogUserEvent;
 use mc_fog_types::{
-    view::{QueryRequest, QueryResponse},
+    view::{QueryRequest, QueryResponse, TxOutSearchResult},
     ETxOutRecord,
 };
 use mc_fog_view_enclave_api::{
@@ -37,6 +47,9 @@ where
     /// The state associated to attestation and key exchange
     ake: AkeEnclaveState<NullIdentity>,
 
+    /// The maximum number of search keys to answer in one client query
+    max_search_keys_per_query: AtomicUsize,
+
     /// Logger object
     logger: Logger,
 }
@@ -49,9 +62,73 @@ where
         Self {
             e_tx_out_store: Mutex::new(None),
             ake: Default::default(),
+            max_search_keys_per_query: AtomicUsize::new(usize::MAX),
             logger,
         }
     }
+
+    /// Decode a QueryRequest, logging if it is malformed
+    fn decode_query_request(&self, plaintext: &[u8]) -> Result<QueryRequest> {
+        mc_util_serial::decode(plaintext).map_err(|e| {
+            log::error!(self.logger, "Could not decode user request: {}", e);
+            Error::ProstDecode
+        })
+    }
+
+    /// Drop the search keys of a client QueryRequest beyond the number we
+    /// answer in one query. The client sends them again in another query.
+    fn limit_search_keys(&self, req: &mut QueryRequest) {
+        req.get_txos
+            .truncate(self.max_search_keys_per_query.load(Ordering::SeqCst));
+    }
+
+    /// Look up the search keys of a QueryRequest in the ORAM
+    fn find_records(&self, req: &QueryRequest) -> Result<Vec<TxOutSearchResult>> {
+        let mut lk = self.e_tx_out_store.lock()?;
+        let store = lk.as_mut().ok_or(Error::EnclaveNotInitialized)?;
+
+        Ok(req
+            .get_txos
+            .iter()
+            .map(|key| store.find_record(&key[..]))
+            .collect())
+    }
+
+    /// Build the parts of a QueryResponse which come from untrusted
+    fn untrusted_query_response_to_query_response(
+        untrusted_query_response: UntrustedQueryResponse,
+    ) -> QueryResponse {
+        let mut missed_block_ranges = Vec::new();
+        let mut rng_records = Vec::new();
+        let mut decommissioned_ingest_invocations = Vec::new();
+
+        for event in untrusted_query_response.user_events.into_iter() {
+            match event {
+                FogUserEvent::NewRngRecord(rng_record) => rng_records.push(rng_record),
+
+                FogUserEvent::DecommissionIngestInvocation(decommissioned_ingest_invocation) => {
+                    decommissioned_ingest_invocations.push(decommissioned_ingest_invocation)
+                }
+
+                FogUserEvent::MissingBlocks(range) => missed_block_ranges.push(range),
+            }
+        }
+
+        QueryResponse {
+            highest_processed_block_count: untrusted_query_response.highest_processed_block_count,
+            highest_processed_block_signature_timestamp: untrusted_query_response
+                .highest_processed_block_signature_timestamp,
+            next_start_from_user_event_id: untrusted_query_response.next_start_from_user_event_id,
+            may_have_more_user_events: untrusted_query_response.may_have_more_user_events,
+            missed_block_ranges,
+            rng_records,
+            decommissioned_ingest_invocations,
+            tx_out_search_results: Default::default(),
+            last_known_block_count: untrusted_query_response.last_known_block_count,
+            last_known_block_cumulative_txo_count: untrusted_query_response
+                .last_known_block_cumulative_txo_count,
+        }
+    }
 }
 
 impl<OSC> ReportableEnclave for ViewEnclave<OSC>
@@ -84,7 +161,11 @@ where
         // Note: eid is passed to sgx_enclave_id crate earlier in the system, because
         // that crate is not under sgx_compat and isn't meant to be used outside of
         // enclave
-        self.ake.init(Default::default(), params.self_client_id)?;
+        self.ake.init(params.self_peer_id, params.self_client_id)?;
+        self.max_search_keys_per_query.store(
+            usize::try_from(params.max_search_keys_per_query).unwrap_or(usize::MAX),
+            Ordering::SeqCst,
+        );
         {
             let mut lk = self.e_tx_out_store.lock()?;
             *lk = Some(ETxOutStore::new(
@@ -119,53 +200,11 @@ where
         let channel_id = msg.channel_id.clone();
         let user_plaintext = self.ake.client_decrypt(msg)?;
 
-        let req: QueryRequest = mc_util_serial::decode(&user_plaintext).map_err(|e| {
-            log::error!(self.logger, "Could not decode user request: {}", e);
-            Error::ProstDecode
-        })?;
-
-        // Prepare the untrusted part of the response.
-        let mut missed_block_ranges = Vec::new();
-        let mut rng_records = Vec::new();
-        let mut decommissioned_ingest_invocations = Vec::new();
-
-        for event in untrusted_query_response.user_events.into_iter() {
-            match event {
-                FogUserEvent::NewRngRecord(rng_record) => rng_records.push(rng_record),
-
-                FogUserEvent::DecommissionIngestInvocation(decommissioned_ingest_invocation) => {
-                    decommissioned_ingest_invocations.push(decommissioned_ingest_invocation)
-                }
-
-                FogUserEvent::MissingBlocks(range) => missed_block_ranges.push(range),
-            }
-        }
+        let mut req = self.decode_query_request(&user_plaintext)?;
+        self.limit_search_keys(&mut req);
 
-        let mut resp = QueryResponse {
-            highest_processed_block_count: untrusted_query_response.highest_processed_block_count,
-            highest_processed_block_signature_timestamp: untrusted_query_response
-                .highest_processed_block_signature_timestamp,
-            next_start_from_user_event_id: untrusted_query_response.next_start_from_user_event_id,
-            missed_block_ranges,
-            rng_records,
-            decommissioned_ingest_invocations,
-            tx_out_search_results: Default::default(),
-            last_known_block_count: untrusted_query_response.last_known_block_count,
-            last_known_block_cumulative_txo_count: untrusted_query_response
-                .last_known_block_cumulative_txo_count,
-        };
-
-        // Do the txos part, scope lock of e_tx_out_store
-        {
-            let mut lk = self.e_tx_out_store.lock()?;
-            let store = lk.as_mut().ok_or(Error::EnclaveNotInitialized)?;
-
-            resp.tx_out_search_results = req
-                .get_txos
-                .iter()
-                .map(|key| store.find_record(&key[..]))
-                .collect();
-        }
+        let mut resp = Self::untrusted_query_response_to_query_response(untrusted_query_response);
+        resp.tx_out_search_results = self.find_records(&req)?;
 
         let response_plaintext_bytes = mc_util_serial::encode(&resp);
 
@@ -184,4 +223,102 @@ where
         }
         Ok(())
     }
+
+    // View store / router specific
+
+    fn peer_init(&self, peer_id: &ResponderId) -> Result<PeerAuthRequest> {
+        Ok(self.ake.peer_init(peer_id)?)
+    }
+
+    fn peer_accept(&self, req: PeerAuthRequest) -> Result<(PeerAuthResponse, PeerSession)> {
+        Ok(self.ake.peer_accept(req)?)
+    }
+
+    fn peer_connect(
+        &self,
+        peer_id: &ResponderId,
+        msg: PeerAuthResponse,
+    ) -> Result<(PeerSession, VerificationReport)> {
+        Ok(self.ake.peer_connect(peer_id, msg)?)
+    }
+
+    fn peer_close(&self, session_id: &PeerSession) -> Result<()> {
+        Ok(self.ake.peer_close(session_id)?)
+    }
+
+    fn query_store(&self, msg: EnclaveMessage<PeerSession>) -> Result<EnclaveMessage<PeerSession>> {
+        let channel_id = msg.channel_id.clone();
+        let router_plaintext = self.ake.peer_decrypt(msg)?;
+        let req = self.decode_query_request(&router_plaintext)?;
+
+        // Only the TxOut part of the query is served by stores, the router
+        // fills in everything else.
+        let resp = QueryResponse {
+            tx_out_search_results: self.find_records(&req)?,
+            ..Default::default()
+        };
+
+        let response_plaintext_bytes = mc_util_serial::encode(&resp);
+
+        Ok(self
+            .ake
+            .peer_encrypt(&channel_id, &[], &response_plaintext_bytes)?)
+    }
+
+    fn create_multi_view_store_query(
+        &self,
+        msg: EnclaveMessage<ClientSession>,
+        store_sessions: Vec<PeerSession>,
+    ) -> Result<Vec<EnclaveMessage<PeerSession>>> {
+        let user_plaintext = self.ake.client_decrypt(msg)?;
+        // Reject malformed requests here rather than in every store, and only
+        // forward the search keys we are going to answer
+        let mut req = self.decode_query_request(&user_plaintext)?;
+        self.limit_search_keys(&mut req);
+        let store_plaintext = mc_util_serial::encode(&req);
+
+        // Check every session before encrypting for any of them, so that a bad
+        // session doesn't leave the others with skipped nonces
+        for session in store_sessions.iter() {
+            if !self.ake.is_peer_known(session)? {
+                return Err(Error::UnknownPeer);
+            }
+        }
+
+        store_sessions
+            .iter()
+            .map(|session| Ok(self.ake.peer_encrypt(session, &[], &store_plaintext)?))
+            .collect()
+    }
+
+    fn collate_shard_query_responses(
+        &self,
+        client_session: ClientSession,
+        shard_query_responses: Vec<EnclaveMessage<PeerSession>>,
+        untrusted_query_response: UntrustedQueryResponse,
+    ) -> Result<Vec<u8>> {
+        let shard_results = shard_query_responses
+            .into_iter()
+            .map(|msg| {
+                let plaintext = self.ake.peer_decrypt(msg)?;
+                let shard_response: QueryResponse =
+                    mc_util_serial::decode(&plaintext).map_err(|e| {
+                        log::error!(self.logger, "Could not decode store response: {}", e);
+                        Error::ProstDecode
+                    })?;
+                Ok(shard_response.tx_out_search_results)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut resp = Self::untrusted_query_response_to_query_response(untrusted_query_response);
+        resp.tx_out_search_results = collate_shard_tx_out_search_results(shard_results)?;
+
+        let response_plaintext_bytes = mc_util_serial::encode(&resp);
+
+        let response = self
+            .ake
+            .client_encrypt(&client_session, &[], &response_plaintext_bytes)?;
+
+        Ok(response.data)
+    }
 }
@@ -0,0 +1,186 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! Merging of the TxOutSearchResults returned by the view stores a view router
+//! fans a query out to.
+//!
+//! Each store answers every search key in the query, but at most one store
+//! holds a given record. The merge must not reveal (via branches, memory
+//! access patterns or the response length) which store, if any, found a
+//! record, so every result is combined with constant-time conditional
+//! assignments, and every ciphertext is padded to FIXED_CIPHERTEXT_LENGTH.
+
+use aligned_cmov::{subtle::ConditionallySelectable, CMov};
+use alloc::vec::Vec;
+use mc_fog_shard_responses::{collate_shard_results, take_other_result, ShardResponseMismatch};
+use mc_fog_types::view::{TxOutSearchResult, TxOutSearchResultCode, FIXED_CIPHERTEXT_LENGTH};
+use mc_fog_view_enclave_api::{Error, Result};
+
+/// Merge the tx_out_search_results of several view store responses.
+///
+/// Every shard must answer the same search keys in the same order. For each
+/// search key, a Found result from any shard wins. Otherwise an error code
+/// (e.g. BadSearchKey) from any shard wins over NotFound.
+///
+/// The merged ciphertexts are padded to FIXED_CIPHERTEXT_LENGTH, with the
+/// length of the selected ciphertext in payload_length.
+pub fn collate_shard_tx_out_search_results(
+    shard_results: Vec<Vec<TxOutSearchResult>>,
+) -> Result<Vec<TxOutSearchResult>> {
+    // The length of each store's ciphertexts is visible to the untrusted host
+    // in the size of its response, so padding them can branch on it.
+    let shard_results = shard_results
+        .into_iter()
+        .map(|results| results.into_iter().map(pad_result).collect())
+        .collect::<Result<Vec<Vec<TxOutSearchResult>>>>()?;
+
+    collate_shard_results(
+        shard_results,
+        |result| result.search_key.as_slice(),
+        merge_result,
+    )
+    .map_err(|ShardResponseMismatch| Error::ShardResponseMismatch)
+}
+
+/// Pad the ciphertext of a store's result to FIXED_CIPHERTEXT_LENGTH,
+/// recording its length in payload_length.
+fn pad_result(mut result: TxOutSearchResult) -> Result<TxOutSearchResult> {
+    let len = result.ciphertext.len();
+    if len > FIXED_CIPHERTEXT_LENGTH {
+        return Err(Error::ShardCiphertextTooLong);
+    }
+    result.payload_length = len as u32;
+    result.ciphertext.resize(FIXED_CIPHERTEXT_LENGTH, 0);
+    Ok(result)
+}
+
+/// Merge `other` into `acc` in constant time. Both ciphertexts must be padded
+/// to FIXED_CIPHERTEXT_LENGTH.
+fn merge_result(acc: &mut TxOutSearchResult, other: &TxOutSearchResult) {
+    let take_other = take_other_result(
+        acc.result_code,
+        other.result_code,
+        TxOutSearchResultCode::Found as u32,
+        TxOutSearchResultCode::NotFound as u32,
+    );
+
+    acc.result_code.cmov(take_other, &other.result_code);
+    acc.payload_length.cmov(take_other, &other.payload_length);
+    for (acc_byte, other_byte) in acc.ciphertext.iter_mut().zip(other.ciphertext.iter()) {
+        acc_byte.conditional_assign(other_byte, take_other);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::vec;
+
+    fn result(key: u8, code: TxOutSearchResultCode, ciphertext: &[u8]) -> TxOutSearchResult {
+        TxOutSearchResult {
+            search_key: vec![key; 16],
+            result_code: code as u32,
+            ciphertext: ciphertext.to_vec(),
+            payload_length: 0,
+        }
+    }
+
+    /// The merged result for a store's result.
+    fn padded(result: TxOutSearchResult) -> TxOutSearchResult {
+        pad_result(result).unwrap()
+    }
+
+    #[test]
+    fn found_result_wins() {
+        let merged = collate_shard_tx_out_search_results(vec![
+            vec![
+                result(1, TxOutSearchResultCode::NotFound, &[0; 4]),
+                result(2, TxOutSearchResultCode::Found, &[7; 5]),
+            ],
+            vec![
+                result(1, TxOutSearchResultCode::Found, &[9; 6]),
+                result(2, TxOutSearchResultCode::NotFound, &[0; 3]),
+            ],
+        ])
+        .unwrap();
+
+        assert_eq!(
+            merged,
+            vec![
+                padded(result(1, TxOutSearchResultCode::Found, &[9; 6])),
+                padded(result(2, TxOutSearchResultCode::Found, &[7; 5])),
+            ]
+        );
+
+        // Every merged ciphertext has the same length, and the payload is the
+        // store's ciphertext.
+        assert!(merged
+            .iter()
+            .all(|result| result.ciphertext.len() == FIXED_CIPHERTEXT_LENGTH));
+        assert_eq!(merged[0].payload(), &[9; 6]);
+        assert_eq!(merged[1].payload(), &[7; 5]);
+    }
+
+    #[test]
+    fn errors_win_over_not_found() {
+        let merged = collate_shard_tx_out_search_results(vec![
+            vec![result(1, TxOutSearchResultCode::NotFound, &[0; 4])],
+            vec![result(1, TxOutSearchResultCode::InternalError, &[0; 2])],
+            vec![result(1, TxOutSearchResultCode::NotFound, &[0; 4])],
+        ])
+        .unwrap();
+
+        assert_eq!(
+            merged,
+            vec![padded(result(
+                1,
+                TxOutSearchResultCode::InternalError,
+                &[0; 2]
+            ))]
+        );
+    }
+
+    #[test]
+    fn single_shard_is_padded() {
+        let merged = collate_shard_tx_out_search_results(vec![vec![result(
+            1,
+            TxOutSearchResultCode::Found,
+            &[3; 232],
+        )]])
+        .unwrap();
+
+        assert_eq!(merged[0].ciphertext.len(), FIXED_CIPHERTEXT_LENGTH);
+        assert_eq!(merged[0].payload_length, 232);
+        assert_eq!(merged[0].payload(), &[3; 232][..]);
+    }
+
+    #[test]
+    fn oversized_ciphertexts_are_rejected() {
+        assert!(matches!(
+            collate_shard_tx_out_search_results(vec![vec![result(
+                1,
+                TxOutSearchResultCode::Found,
+                &[3; FIXED_CIPHERTEXT_LENGTH + 1],
+            )]]),
+            Err(Error::ShardCiphertextTooLong)
+        ));
+    }
+
+    #[test]
+    fn mismatched_shards_are_rejected() {
+        assert!(matches!(
+            collate_shard_tx_out_search_results(vec![
+                vec![result(1, TxOutSearchResultCode::NotFound, &[])],
+                vec![result(2, TxOutSearchResultCode::NotFound, &[])],
+            ]),
+            Err(Error::ShardResponseMismatch)
+        ));
+        assert!(matches!(
+            collate_shard_tx_out_search_results(vec![
+                vec![result(1, TxOutSearchResultCode::NotFound, &[])],
+                vec![],
+            ]),
+            Err(Error::ShardResponseMismatch)
+        ));
+    }
+}
@@ -17,7 +17,9 @@ fn basic(logger: Logger) {
     let params = ViewEnclaveInitParams {
         eid: 0,
         self_client_id: ResponderId::from_str("abc:123").unwrap(),
+        self_peer_id: ResponderId::from_str("abc:124").unwrap(),
         desired_capacity: 1024 * 1024,
+        max_search_keys_per_query: 1000,
     };
 
     enclave.init(params).unwrap();
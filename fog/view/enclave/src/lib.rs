@@ -11,7 +11,10 @@ use std::{path, result::Result as StdResult, sync::Arc};
 use mc_attest_core::{
     IasNonce, Quote, QuoteNonce, Report, SgxError, TargetInfo, VerificationReport,
 };
-use mc_attest_enclave_api::{ClientAuthRequest, ClientAuthResponse, ClientSession, EnclaveMessage};
+use mc_attest_enclave_api::{
+    ClientAuthRequest, ClientAuthResponse, ClientSession, EnclaveMessage, PeerAuthRequest,
+    PeerAuthResponse, PeerSession,
+};
 use mc_attest_verifier::DEBUG_ENCLAVE;
 use mc_common::{logger::Logger, ResponderId};
 use mc_crypto_keys::X25519Public;
@@ -44,17 +47,23 @@ impl SgxViewEnclave {
     /// * enclave_path: The path to the signed enclave .so file
     /// * client_responder_id: The responder_id to be used when connecting to
     ///   clients
+    /// * peer_responder_id: The responder_id to be used when a view router
+    ///   connects to this enclave as a view store
     /// * db: The recovery db to read data from. This is used when servicing
     ///   seeds requests
     /// * desired_capacity: The desired capacity for ETxOutRecords in the
     ///   oblivious map. Must be a power of two. Actual capacity will be ~70% of
     ///   this. Memory utilization will be about 256 bytes * this + some
     ///   overhead
+    /// * max_search_keys_per_query: The maximum number of search keys to answer
+    ///   in one client query. Further search keys are left unanswered.
     /// * logger: Logger to use
     pub fn new(
         enclave_path: path::PathBuf,
         client_responder_id: ResponderId,
+        peer_responder_id: ResponderId,
         desired_capacity: u64,
+        max_search_keys_per_query: u64,
         _logger: Logger,
     ) -> Self {
         let mut launch_token: sgx_launch_token_t = [0; 1024];
@@ -88,7 +97,9 @@ impl SgxViewEnclave {
         let params = ViewEnclaveInitParams {
             eid,
             self_client_id: client_responder_id,
+            self_peer_id: peer_responder_id,
             desired_capacity,
+            max_search_keys_per_query,
         };
 
         result.init(params).expect("Could not initialize enclave");
@@ -179,4 +190,70 @@ impl ViewEnclaveApi for SgxViewEnclave {
         let outbuf = self.enclave_call(&inbuf)?;
         mc_util_serial::deserialize(&outbuf[..])?
     }
+
+    fn peer_init(&self, peer_id: &ResponderId) -> Result<PeerAuthRequest> {
+        let inbuf = mc_util_serial::serialize(&ViewEnclaveRequest::PeerInit(peer_id.clone()))?;
+        let outbuf = self.enclave_call(&inbuf)?;
+        mc_util_serial::deserialize(&outbuf[..])?
+    }
+
+    fn peer_accept(&self, req: PeerAuthRequest) -> Result<(PeerAuthResponse, PeerSession)> {
+        let inbuf = mc_util_serial::serialize(&ViewEnclaveRequest::PeerAccept(req))?;
+        let outbuf = self.enclave_call(&inbuf)?;
+        mc_util_serial::deserialize(&outbuf[..])?
+    }
+
+    fn peer_connect(
+        &self,
+        peer_id: &ResponderId,
+        msg: PeerAuthResponse,
+    ) -> Result<(PeerSession, VerificationReport)> {
+        let inbuf =
+            mc_util_serial::serialize(&ViewEnclaveRequest::PeerConnect(peer_id.clone(), msg))?;
+        let outbuf = self.enclave_call(&inbuf)?;
+        mc_util_serial::deserialize(&outbuf[..])?
+    }
+
+    fn peer_close(&self, session_id: &PeerSession) -> Result<()> {
+        let inbuf = mc_util_serial::serialize(&ViewEnclaveRequest::PeerClose(session_id.clone()))?;
+        let outbuf = self.enclave_call(&inbuf)?;
+        mc_util_serial::deserialize(&outbuf[..])?
+    }
+
+    fn query_store(
+        &self,
+        payload: EnclaveMessage<PeerSession>,
+    ) -> Result<EnclaveMessage<PeerSession>> {
+        let inbuf = mc_util_serial::serialize(&ViewEnclaveRequest::QueryStore(payload))?;
+        let outbuf = self.enclave_call(&inbuf)?;
+        mc_util_serial::deserialize(&outbuf[..])?
+    }
+
+    fn create_multi_view_store_query(
+        &self,
+        payload: EnclaveMessage<ClientSession>,
+        store_sessions: Vec<PeerSession>,
+    ) -> Result<Vec<EnclaveMessage<PeerSession>>> {
+        let inbuf = mc_util_serial::serialize(&ViewEnclaveRequest::CreateMultiViewStoreQuery(
+            payload,
+            store_sessions,
+        ))?;
+        let outbuf = self.enclave_call(&inbuf)?;
+        mc_util_serial::deserialize(&outbuf[..])?
+    }
+
+    fn collate_shard_query_responses(
+        &self,
+        client_session: ClientSession,
+        shard_query_responses: Vec<EnclaveMessage<PeerSession>>,
+        untrusted_query_response: UntrustedQueryResponse,
+    ) -> Result<Vec<u8>> {
+        let inbuf = mc_util_serial::serialize(&ViewEnclaveRequest::CollateShardQueryResponses(
+            client_session,
+            shard_query_responses,
+            untrusted_query_response,
+        ))?;
+        let outbuf = self.enclave_call(&inbuf)?;
+        mc_util_serial::deserialize(&outbuf[..])?
+    }
 }
@@ -11,12 +11,15 @@ use mc_fog_view_enclave_api::ViewEnclaveApi;
 use std::str::FromStr;
 
 const VIEW_OMAP_CAPACITY: u64 = 1024 * 1024;
+const MAX_SEARCH_KEYS_PER_QUERY: u64 = 1000;
 
 fn get_enclave(logger: Logger) -> SgxViewEnclave {
     SgxViewEnclave::new(
         get_enclave_path(mc_fog_view_enclave::ENCLAVE_FILE),
         ResponderId::from_str("abc:123").unwrap(),
+        ResponderId::from_str("abc:124").unwrap(),
         VIEW_OMAP_CAPACITY,
+        MAX_SEARCH_KEYS_PER_QUERY,
         logger.clone(),
     )
 }
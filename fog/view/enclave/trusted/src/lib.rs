@@ -120,6 +120,25 @@ pub fn ecall_dispatcher(inbuf: &[u8]) -> Result<Vec<u8>, sgx_status_t> {
             serialize(&ENCLAVE.query(req, untrusted_query_response))
         }
         ViewEnclaveRequest::AddRecords(records) => serialize(&ENCLAVE.add_records(records)),
+        ViewEnclaveRequest::PeerInit(peer_id) => serialize(&ENCLAVE.peer_init(&peer_id)),
+        ViewEnclaveRequest::PeerAccept(req) => serialize(&ENCLAVE.peer_accept(req)),
+        ViewEnclaveRequest::PeerConnect(peer_id, msg) => {
+            serialize(&ENCLAVE.peer_connect(&peer_id, msg))
+        }
+        ViewEnclaveRequest::PeerClose(session) => serialize(&ENCLAVE.peer_close(&session)),
+        ViewEnclaveRequest::QueryStore(msg) => serialize(&ENCLAVE.query_store(msg)),
+        ViewEnclaveRequest::CreateMultiViewStoreQuery(msg, store_sessions) => {
+            serialize(&ENCLAVE.create_multi_view_store_query(msg, store_sessions))
+        }
+        ViewEnclaveRequest::CollateShardQueryResponses(
+            client_session,
+            shard_query_responses,
+            untrusted_query_response,
+        ) => serialize(&ENCLAVE.collate_shard_query_responses(
+            client_session,
+            shard_query_responses,
+            untrusted_query_response,
+        )),
     }
     .or(Err(sgx_status_t::SGX_ERROR_UNEXPECTED))
 }
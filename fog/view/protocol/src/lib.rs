@@ -11,4 +11,6 @@ mod user_private;
 pub use user_private::UserPrivate;
 
 mod user_rng_set;
-pub use user_rng_set::{TxOutRecoveryError, UserRngSet};
+pub use user_rng_set::{
+    RngSetError, StoredNoncedRng, StoredUserRngSet, TxOutRecoveryError, UserRngSet,
+};
@@ -75,10 +75,14 @@ pub trait FogViewConnection {
         let mut missed_block_ranges = Vec::<BlockRange>::new();
 
         // Update seeds, get block count
-        let mut new_highest_processed_block_count = {
-            match self
+        // The server returns a limited number of events per request, so follow
+        // the cursor until it says there are no more.
+        let mut new_highest_processed_block_count = u64::MAX;
+        loop {
+            let start_from_user_event_id = user_rng_set.get_next_start_from_user_event_id();
+            let result = match self
                 .request(
-                    user_rng_set.get_next_start_from_user_event_id(),
+                    start_from_user_event_id,
                     user_rng_set.get_highest_processed_block_count().into(),
                     Default::default(),
                 )
@@ -87,28 +91,39 @@ pub trait FogViewConnection {
                 // If there's a connection error it's probably unrecoverable and we should not loop
                 // There are retries in the FogClient class
                 Err(err) => {
-                    return (vec![], vec![], vec![err]);
+                    errs.push(err);
+                    return (vec![], missed_block_ranges, errs);
                 }
-                Ok(result) => {
-                    // TODO: Handle decommissioning of ingest invocations
+                Ok(result) => result,
+            };
 
-                    for rng_record in result.rng_records.iter() {
-                        if let Err(err) = user_rng_set.ingest_rng_record(upriv, rng_record) {
-                            errs.push(TxOutPollingError::from(err));
-                        }
-                    }
+            // TODO: Handle decommissioning of ingest invocations
 
-                    // Missed block ranges are reported once, so we can add
-                    // directly without fear of repeating ranges.
-                    missed_block_ranges.extend(result.missed_block_ranges);
+            for rng_record in result.rng_records.iter() {
+                if let Err(err) = user_rng_set.ingest_rng_record(upriv, rng_record) {
+                    errs.push(TxOutPollingError::from(err));
+                }
+            }
 
-                    user_rng_set
-                        .set_next_start_from_user_event_id(result.next_start_from_user_event_id);
+            // Missed block ranges are reported once, so we can add
+            // directly without fear of repeating ranges.
+            missed_block_ranges.extend(result.missed_block_ranges);
 
-                    result.highest_processed_block_count
-                }
+            user_rng_set.set_next_start_from_user_event_id(result.next_start_from_user_event_id);
+
+            new_highest_processed_block_count = core::cmp::min(
+                new_highest_processed_block_count,
+                result.highest_processed_block_count,
+            );
+
+            // Stop if the cursor didn't move, so that a misbehaving server
+            // can't keep us here.
+            if !result.may_have_more_user_events
+                || result.next_start_from_user_event_id <= start_from_user_event_id
+            {
+                break;
             }
-        };
+        }
 
         // Optimization: If the num_blocks of the view server hasn't changed, or is
         // older, since last time we talked to it, then we don't have any new
@@ -116,7 +131,7 @@ pub trait FogViewConnection {
         if user_rng_set.get_highest_processed_block_count()
             >= BlockCount::from(new_highest_processed_block_count)
         {
-            return Default::default();
+            return (vec![], missed_block_ranges, errs);
         }
 
         // Get new tx's
@@ -148,35 +163,59 @@ pub trait FogViewConnection {
                 })
                 .collect();
 
-            // Make the request to the view node.
-            let resp = match self.request(
-                i64::MAX, // We don't care about any events, we just want to search for TXOs.
-                user_rng_set.get_highest_processed_block_count().into(),
-                search_keys,
-            ) {
-                Ok(resp) => resp,
-                Err(err) => {
-                    // If there's a connection error it's probly unrecoverable and we should not
-                    // loop There are retries in the FogClient class
-                    errs.push(TxOutPollingError::Conn(err));
+            // Make the requests to the view node.
+            // The server may only answer a prefix of the search keys, so send
+            // the rest again until every search key has a result.
+            let mut tx_out_search_results = Vec::with_capacity(search_keys.len());
+            let mut remaining_search_keys = &search_keys[..];
+            while !remaining_search_keys.is_empty() {
+                let resp = match self.request(
+                    i64::MAX, // We don't care about any events, we just want to search for TXOs.
+                    user_rng_set.get_highest_processed_block_count().into(),
+                    remaining_search_keys.to_vec(),
+                ) {
+                    Ok(resp) => resp,
+                    Err(err) => {
+                        // If there's a connection error it's probly unrecoverable and we should
+                        // not loop There are retries in the FogClient class
+                        errs.push(TxOutPollingError::Conn(err));
+                        return (results, missed_block_ranges, errs);
+                    }
+                };
+
+                // Without any results we can't make progress, and can't
+                // conclude anything about the rngs.
+                if resp.tx_out_search_results.is_empty() {
+                    errs.push(TxOutPollingError::NoTxOutSearchResults);
                     return (results, missed_block_ranges, errs);
                 }
-            };
 
-            // The new num blocks value is the minimum of all the num_blocks values we got
-            // back from the server in all queries we made to it. This is needed
-            // so that we can guarantee that if we don't find a transaction for
-            // some particular rng output, then it didn't land
-            // before num_blocks.
-            new_highest_processed_block_count = core::cmp::min(
-                new_highest_processed_block_count,
-                resp.highest_processed_block_count,
-            );
+                // The new num blocks value is the minimum of all the num_blocks values we got
+                // back from the server in all queries we made to it. This is needed
+                // so that we can guarantee that if we don't find a transaction for
+                // some particular rng output, then it didn't land
+                // before num_blocks.
+                new_highest_processed_block_count = core::cmp::min(
+                    new_highest_processed_block_count,
+                    resp.highest_processed_block_count,
+                );
+
+                let num_answered = core::cmp::min(
+                    resp.tx_out_search_results.len(),
+                    remaining_search_keys.len(),
+                );
+                remaining_search_keys = &remaining_search_keys[num_answered..];
+                tx_out_search_results.extend(resp.tx_out_search_results);
+
+                // Missed block ranges are reported once, so we can add
+                // directly without fear of repeating ranges.
+                missed_block_ranges.extend(resp.missed_block_ranges);
+            }
 
             // Feed all the new TxOutSearchResult objects into the user_rng_set at once,
             // which is more efficient
             let (this_round_results, mut this_round_errs) =
-                user_rng_set.ingest_tx_out_search_results(upriv, &resp.tx_out_search_results);
+                user_rng_set.ingest_tx_out_search_results(upriv, &tx_out_search_results);
             results.extend(this_round_results);
             errs.extend(this_round_errs.drain(..).map(TxOutPollingError::from));
 
@@ -203,9 +242,6 @@ pub trait FogViewConnection {
             if request_multiplier >= 1000 {
                 request_multiplier = 1000;
             }
-            // Missed block ranges are reported once, so we can add
-            // directly without fear of repeating ranges.
-            missed_block_ranges.extend(resp.missed_block_ranges);
         }
 
         // Don't update the num_blocks value in reverse. If this time the servers
@@ -233,6 +269,8 @@ pub enum TxOutPollingError<ConnError: Debug + Display> {
     ProstDecode(mc_util_serial::DecodeError),
     /// Key Error: {0}
     KeyError(KeyError),
+    /// The server did not answer any of the search keys in a request
+    NoTxOutSearchResults,
 }
 
 impl<ConnError: Debug + Display> From<TxOutRecoveryError> for TxOutPollingError<ConnError> {
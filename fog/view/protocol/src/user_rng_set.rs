@@ -6,12 +6,13 @@ use displaydoc::Display;
 use mc_common::HashMap;
 use mc_crypto_box::Error as CryptoBoxError;
 use mc_crypto_keys::KeyError;
-use mc_fog_kex_rng::{BufferedRng, Error as KexRngError, NewFromKex, VersionedKexRng};
+use mc_fog_kex_rng::{BufferedRng, Error as KexRngError, NewFromKex, StoredRng, VersionedKexRng};
 use mc_fog_types::{
     view::{RngRecord, TxOutRecord, TxOutSearchResult, TxOutSearchResultCode},
     BlockCount,
 };
 use mc_util_serial::DecodeError;
+use prost::Message;
 
 /// A set of kex_rngs. Together with a view node endpoint, this can be used to
 /// find the user's transactions.
@@ -90,7 +91,7 @@ impl UserRngSet {
             if let Ok(code) = TxOutSearchResultCode::try_from(result.result_code) {
                 if code == TxOutSearchResultCode::Found {
                     // TODO: Log any collision when inserting?
-                    tx_result_map.insert(result.search_key.clone(), result.ciphertext.clone());
+                    tx_result_map.insert(result.search_key.clone(), result.payload().to_vec());
                 } else if code != TxOutSearchResultCode::NotFound {
                     failures.push(TxOutRecoveryError::TxOutSearchFailure(
                         code,
@@ -125,6 +126,74 @@ impl UserRngSet {
     }
 }
 
+/// A stored, wire-stable representation of a UserRngSet, allowing a client to
+/// resume polling fog view where it left off.
+#[derive(Clone, Eq, Hash, PartialEq, Message)]
+pub struct StoredUserRngSet {
+    /// The rngs, with their nonces
+    #[prost(message, repeated, tag = 1)]
+    pub rngs: Vec<StoredNoncedRng>,
+
+    /// Last highest_processed_block_count reported by the server
+    #[prost(uint64, tag = 2)]
+    pub highest_processed_block_count: u64,
+
+    /// Last next_start_from_user_event_id reported by the server
+    #[prost(int64, tag = 3)]
+    pub next_start_from_user_event_id: i64,
+}
+
+/// A stored rng, together with the nonce identifying it in a UserRngSet
+#[derive(Clone, Eq, Hash, PartialEq, Message)]
+pub struct StoredNoncedRng {
+    /// The nonce of the rng
+    #[prost(bytes, tag = 1)]
+    pub nonce: Vec<u8>,
+
+    /// The rng
+    #[prost(message, required, tag = 2)]
+    pub rng: StoredRng,
+}
+
+impl From<&UserRngSet> for StoredUserRngSet {
+    fn from(src: &UserRngSet) -> Self {
+        let mut rngs = src
+            .rngs
+            .iter()
+            .map(|(nonce, rng)| StoredNoncedRng {
+                nonce: nonce.clone(),
+                rng: rng.clone().into(),
+            })
+            .collect::<Vec<_>>();
+        // Sort by nonce so that the same set is always stored the same way
+        rngs.sort_by(|a, b| a.nonce.cmp(&b.nonce));
+
+        Self {
+            rngs,
+            highest_processed_block_count: src.highest_processed_block_count,
+            next_start_from_user_event_id: src.next_start_from_user_event_id,
+        }
+    }
+}
+
+impl TryFrom<StoredUserRngSet> for UserRngSet {
+    type Error = RngSetError;
+
+    fn try_from(src: StoredUserRngSet) -> Result<Self, RngSetError> {
+        let rngs = src
+            .rngs
+            .into_iter()
+            .map(|stored| Ok((stored.nonce, VersionedKexRng::try_from(stored.rng)?)))
+            .collect::<Result<_, RngSetError>>()?;
+
+        Ok(Self {
+            rngs,
+            highest_processed_block_count: src.highest_processed_block_count,
+            next_start_from_user_event_id: src.next_start_from_user_event_id,
+        })
+    }
+}
+
 // Error type for a user rng set
 
 #[derive(Debug, Display)]
@@ -172,9 +241,11 @@ impl From<DecodeError> for TxOutRecoveryError {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Display)]
 pub enum RngSetError {
+    /// Decode error: {0}
     Decode(DecodeError),
+    /// KexRng error: {0}
     KexRng(KexRngError),
 }
 
@@ -189,3 +260,54 @@ impl From<KexRngError> for RngSetError {
         Self::KexRng(err)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mc_account_keys::AccountKey;
+    use mc_crypto_keys::{Ristretto, RistrettoPublic};
+    use mc_fog_kex_rng::{KexRngPubkey, LatestKexRngCore};
+    use mc_util_from_random::FromRandom;
+
+    #[test]
+    fn stored_user_rng_set_round_trip() {
+        mc_util_test_helper::run_with_several_seeds(|mut rng| {
+            let upriv = UserPrivate::from(&AccountKey::random(&mut rng));
+
+            let mut rng_set = UserRngSet::new();
+            for ingest_invocation_id in 0..3 {
+                let ingest_public = RistrettoPublic::from_random(&mut rng);
+                let rec = RngRecord {
+                    ingest_invocation_id,
+                    pubkey: KexRngPubkey::from_public_key::<LatestKexRngCore, Ristretto>(
+                        &ingest_public,
+                    ),
+                    start_block: 0,
+                };
+                rng_set.ingest_rng_record(&upriv, &rec).unwrap();
+            }
+            for (count, kex_rng) in rng_set.rngs.values_mut().enumerate() {
+                for _ in 0..count {
+                    kex_rng.advance();
+                }
+            }
+            rng_set.set_highest_processed_block_count(10);
+            rng_set.set_next_start_from_user_event_id(7);
+
+            let stored = StoredUserRngSet::from(&rng_set);
+            let decoded: StoredUserRngSet =
+                mc_util_serial::decode(&mc_util_serial::encode(&stored)).unwrap();
+            assert_eq!(stored, decoded);
+
+            let restored = UserRngSet::try_from(decoded).unwrap();
+            assert_eq!(restored.get_highest_processed_block_count(), 10.into());
+            assert_eq!(restored.get_next_start_from_user_event_id(), 7);
+            assert_eq!(restored.get_rngs().len(), rng_set.get_rngs().len());
+            for (nonce, kex_rng) in rng_set.get_rngs() {
+                let restored_rng = &restored.get_rngs()[nonce];
+                assert_eq!(restored_rng.index(), kex_rng.index());
+                assert_eq!(restored_rng.peek(), kex_rng.peek());
+            }
+        })
+    }
+}
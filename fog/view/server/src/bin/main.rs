@@ -5,6 +5,7 @@
 use mc_attest_net::{Client, RaClient};
 use mc_common::{logger::log, time::SystemTimeProvider};
 use mc_fog_sql_recovery_db::SqlRecoveryDb;
+use mc_fog_uri::ConnectionUri;
 use mc_fog_view_enclave::{SgxViewEnclave, ENCLAVE_FILE};
 use mc_fog_view_server::{config::MobileAcctViewConfig, server::ViewServer};
 use mc_util_cli::ParserWithBuildInfo;
@@ -49,10 +50,18 @@ fn main() {
         enclave_path.to_str().unwrap(),
         &config.client_responder_id
     );
+    // A fog view router addresses this server, as one of its view stores, by
+    // the host:port it listens on.
+    let peer_responder_id = config
+        .client_listen_uri
+        .responder_id()
+        .expect("Could not get responder id from client listen uri");
     let sgx_enclave = SgxViewEnclave::new(
         enclave_path,
         config.client_responder_id.clone(),
+        peer_responder_id,
         config.omap_capacity,
+        config.max_search_keys_per_query,
         logger.clone(),
     );
 
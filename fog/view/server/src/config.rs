@@ -3,6 +3,7 @@
 //! Configuration parameters for the MobileCoin Fog View Node
 #![deny(missing_docs)]
 
+use crate::sharding::ShardingStrategy;
 use clap::Parser;
 use mc_attest_core::ProviderId;
 use mc_common::ResponderId;
@@ -77,4 +78,28 @@ pub struct MobileAcctViewConfig {
     /// and should not much harm performance otherwise when loading the DB.
     #[clap(long, default_value = "1000", env = "MC_BLOCK_QUERY_BATCH_SIZE")]
     pub block_query_batch_size: usize,
+
+    /// The maximum number of user events to return in response to one query.
+    /// Clients page through any further events using the
+    /// next_start_from_user_event_id cursor.
+    #[clap(long, default_value = "1000", env = "MC_MAX_USER_EVENTS_PER_QUERY")]
+    pub max_user_events_per_query: usize,
+
+    /// The maximum number of search keys to search for in one query. Any
+    /// further search keys in a query are left unanswered, and clients send
+    /// them again in another query. Queries forwarded by a fog view router
+    /// are limited by the router instead.
+    #[clap(long, default_value = "2000", env = "MC_MAX_SEARCH_KEYS_PER_QUERY")]
+    pub max_search_keys_per_query: u64,
+
+    /// Which ETxOutRecords to load into the ORAM, when this server is one of
+    /// several view stores behind a fog view router.
+    ///
+    /// Either "blocks:START-END" to load the records of a range of blocks (END
+    /// may be omitted for an unbounded range), or "search-keys:START-END" to
+    /// load the records whose search keys start with a two-byte prefix in the
+    /// given hex range, e.g. "search-keys:0000-8000". Defaults to loading every
+    /// record.
+    #[clap(long, default_value = "blocks:0-", env = "MC_SHARDING_STRATEGY")]
+    pub sharding_strategy: ShardingStrategy,
 }
@@ -20,6 +20,9 @@ lazy_static::lazy_static! {
     // Number of txos added (to the enclave) since startup.
     pub static ref TXOS_ADDED_COUNT: IntCounter = OP_COUNTERS.counter("txos_added_count");
 
+    // Number of txos skipped (not added to the enclave) since startup, because they belong to another view store's shard.
+    pub static ref TXOS_SKIPPED_COUNT: IntCounter = OP_COUNTERS.counter("txos_skipped_count");
+
     // Time it takes to perform the enclave add_records call.
     pub static ref ENCLAVE_ADD_RECORDS_TIME: Histogram = OP_COUNTERS.histogram("enclave_add_records_time");
 
@@ -1,6 +1,8 @@
 // Copyright (c) 2018-2022 The MobileCoin Foundation
 
 use displaydoc::Display;
+use grpcio::Error as GrpcError;
+use mc_connection::AttestationError;
 use mc_fog_view_enclave::Error as ViewEnclaveError;
 use mc_sgx_report_cache_untrusted::Error as ReportCacheError;
 
@@ -27,3 +29,34 @@ impl From<ReportCacheError> for ViewServerError {
         Self::ReportCache(src)
     }
 }
+
+/// An error which can occur when attesting to a view store
+#[derive(Debug, Display)]
+pub enum ViewStoreAttestationError {
+    /// gRPC failure during attestation: {0}
+    Grpc(GrpcError),
+    /// Local enclave failure during attestation: {0}
+    Enclave(ViewEnclaveError),
+}
+
+impl From<GrpcError> for ViewStoreAttestationError {
+    fn from(src: GrpcError) -> Self {
+        Self::Grpc(src)
+    }
+}
+
+impl From<ViewEnclaveError> for ViewStoreAttestationError {
+    fn from(src: ViewEnclaveError) -> Self {
+        Self::Enclave(src)
+    }
+}
+
+impl AttestationError for ViewStoreAttestationError {
+    fn should_reattest(&self) -> bool {
+        true
+    }
+
+    fn should_retry(&self) -> bool {
+        true
+    }
+}
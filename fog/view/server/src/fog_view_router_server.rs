@@ -0,0 +1,186 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! Server object containing a view router
+//! Constructible from config (for testability) and with a mechanism for
+//! stopping it
+
+use crate::{
+    counters, fog_view_router_service::FogViewRouterService, router_config::FogViewRouterConfig,
+    view_store_connection::ViewStoreConnection,
+};
+use futures::executor::block_on;
+use mc_attest_net::RaClient;
+use mc_common::{
+    logger::{log, Logger},
+    time::TimeProvider,
+};
+use mc_fog_api::view_grpc;
+use mc_fog_recovery_db_iface::RecoveryDb;
+use mc_fog_uri::ConnectionUri;
+use mc_fog_view_enclave::ViewEnclaveProxy;
+use mc_sgx_report_cache_untrusted::ReportCacheThread;
+use mc_util_grpc::{
+    AnonymousAuthenticator, Authenticator, ConnectionUriGrpcioServer, ReadinessIndicator,
+    TokenAuthenticator,
+};
+use std::sync::{Arc, Mutex};
+
+pub struct FogViewRouterServer<E, RC>
+where
+    E: ViewEnclaveProxy,
+    RC: RaClient + Send + Sync + 'static,
+{
+    config: FogViewRouterConfig,
+    server: grpcio::Server,
+    enclave: E,
+    ra_client: RC,
+    report_cache_thread: Option<ReportCacheThread>,
+    readiness_indicator: ReadinessIndicator,
+    logger: Logger,
+}
+
+impl<E, RC> FogViewRouterServer<E, RC>
+where
+    E: ViewEnclaveProxy,
+    RC: RaClient + Send + Sync + 'static,
+{
+    /// Make a new view router instance
+    pub fn new<DB>(
+        config: FogViewRouterConfig,
+        enclave: E,
+        recovery_db: DB,
+        ra_client: RC,
+        time_provider: impl TimeProvider + 'static,
+        logger: Logger,
+    ) -> FogViewRouterServer<E, RC>
+    where
+        DB: RecoveryDb + Clone + Send + Sync + 'static,
+    {
+        let readiness_indicator = ReadinessIndicator::default();
+
+        let env = Arc::new(
+            grpcio::EnvBuilder::new()
+                .name_prefix("Main-RPC".to_string())
+                .build(),
+        );
+
+        let client_authenticator: Arc<dyn Authenticator + Sync + Send> =
+            if let Some(shared_secret) = config.client_auth_token_secret.as_ref() {
+                Arc::new(TokenAuthenticator::new(
+                    *shared_secret,
+                    config.client_auth_token_max_lifetime,
+                    time_provider,
+                ))
+            } else {
+                Arc::new(AnonymousAuthenticator::default())
+            };
+
+        let store_env = Arc::new(
+            grpcio::EnvBuilder::new()
+                .name_prefix("ViewStore-RPC".to_string())
+                .build(),
+        );
+        let view_store_lanes = Arc::new(
+            (0..config.view_store_sessions.max(1))
+                .map(|_| {
+                    Mutex::new(
+                        config
+                            .view_store_uris
+                            .iter()
+                            .map(|uri| {
+                                ViewStoreConnection::new(
+                                    enclave.clone(),
+                                    uri.clone(),
+                                    store_env.clone(),
+                                    logger.clone(),
+                                )
+                            })
+                            .collect(),
+                    )
+                })
+                .collect(),
+        );
+
+        let fog_view_router_service = view_grpc::create_fog_view_api(FogViewRouterService::new(
+            config.clone(),
+            enclave.clone(),
+            Arc::new(recovery_db),
+            view_store_lanes,
+            client_authenticator,
+            logger.clone(),
+        ));
+        log::debug!(logger, "Constructed View Router GRPC Service");
+
+        // Health check service
+        let health_service = mc_util_grpc::HealthService::new(
+            Some(readiness_indicator.clone().into()),
+            logger.clone(),
+        )
+        .into_service();
+
+        // Package service into grpc server
+        log::info!(
+            logger,
+            "Starting View Router server on {}",
+            config.client_listen_uri.addr(),
+        );
+        let server_builder = grpcio::ServerBuilder::new(env)
+            .register_service(fog_view_router_service)
+            .register_service(health_service)
+            .bind_using_uri(&config.client_listen_uri, logger.clone());
+
+        let server = server_builder.build().unwrap();
+
+        Self {
+            config,
+            server,
+            enclave,
+            ra_client,
+            report_cache_thread: None,
+            readiness_indicator,
+            logger,
+        }
+    }
+
+    /// Start the server, which starts all the worker threads
+    pub fn start(&mut self) {
+        self.report_cache_thread = Some(
+            ReportCacheThread::start(
+                self.enclave.clone(),
+                self.ra_client.clone(),
+                self.config.ias_spid,
+                &counters::ENCLAVE_REPORT_TIMESTAMP,
+                self.logger.clone(),
+            )
+            .expect("failed starting report cache thread"),
+        );
+
+        self.server.start();
+        for (host, port) in self.server.bind_addrs() {
+            log::info!(self.logger, "API listening on {}:{}", host, port);
+        }
+
+        // The router has no data of its own to load, view stores are attested
+        // lazily on the first query.
+        self.readiness_indicator.set_ready();
+    }
+
+    /// Stop the server and all worker threads
+    pub fn stop(&mut self) {
+        if let Some(ref mut thread) = self.report_cache_thread.take() {
+            thread.stop().expect("Could not stop report cache thread");
+        }
+
+        block_on(self.server.shutdown()).expect("Could not stop grpc server");
+    }
+}
+
+impl<E, RC> Drop for FogViewRouterServer<E, RC>
+where
+    E: ViewEnclaveProxy,
+    RC: RaClient + Send + Sync + 'static,
+{
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
@@ -0,0 +1,434 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! Serves client requests on a fog view router, by fanning the TxOut part of
+//! each query out to the view stores and merging their responses in the
+//! router's enclave.
+
+use crate::{
+    fog_view_service::search_user_events,
+    router_config::FogViewRouterConfig,
+    sharding::{shards_cover_all_records, ShardingStrategy},
+    view_store_connection::ViewStoreConnection,
+};
+use futures::{executor::block_on, future::try_join_all};
+use grpcio::{RpcContext, RpcStatus, RpcStatusCode, UnarySink};
+use mc_attest_api::attest;
+use mc_attest_enclave_api::{ClientSession, EnclaveMessage, PeerSession};
+use mc_common::logger::{log, Logger};
+use mc_connection::AttestedConnection;
+use mc_fog_api::{view::MultiViewStoreQueryResponse, view_grpc::FogViewApi};
+use mc_fog_recovery_db_iface::{FogUserEvent, RecoveryDb};
+use mc_fog_types::view::QueryRequestAAD;
+use mc_fog_view_enclave::{Error as ViewEnclaveError, ViewEnclaveProxy};
+use mc_fog_view_enclave_api::UntrustedQueryResponse;
+use mc_util_grpc::{
+    check_request_chain_id, rpc_internal_error, rpc_invalid_arg_error, rpc_logger,
+    rpc_permissions_error, rpc_unavailable_error, send_result, Authenticator,
+};
+use mc_util_metrics::SVC_COUNTERS;
+use mc_util_telemetry::{tracer, Tracer};
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc, Mutex, MutexGuard,
+};
+
+/// One attested connection to each view store.
+///
+/// The router's enclave encrypts messages for a store with sequential nonces,
+/// so a lane is only ever used by one query at a time.
+pub type ViewStoreLane<E> = Vec<ViewStoreConnection<E>>;
+
+#[derive(Clone)]
+pub struct FogViewRouterService<E: ViewEnclaveProxy, DB: RecoveryDb + Send + Sync> {
+    /// Router Config
+    config: FogViewRouterConfig,
+
+    /// The router's enclave
+    enclave: E,
+
+    /// Recovery DB.
+    db: Arc<DB>,
+
+    /// Connections to the view stores.
+    view_store_lanes: Arc<Vec<Mutex<ViewStoreLane<E>>>>,
+
+    /// The lane the next query should try first.
+    next_lane: Arc<AtomicUsize>,
+
+    /// GRPC request authenticator.
+    authenticator: Arc<dyn Authenticator + Send + Sync>,
+
+    /// Slog logger object
+    logger: Logger,
+}
+
+impl<E: ViewEnclaveProxy, DB: RecoveryDb + Send + Sync> FogViewRouterService<E, DB> {
+    /// Creates a new fog-view-router-service node (but does not create sockets
+    /// and start it etc.)
+    pub fn new(
+        config: FogViewRouterConfig,
+        enclave: E,
+        db: Arc<DB>,
+        view_store_lanes: Arc<Vec<Mutex<ViewStoreLane<E>>>>,
+        authenticator: Arc<dyn Authenticator + Send + Sync>,
+        logger: Logger,
+    ) -> Self {
+        assert!(
+            !view_store_lanes.is_empty(),
+            "A fog view router needs at least one lane of view store connections"
+        );
+        Self {
+            config,
+            enclave,
+            db,
+            view_store_lanes,
+            next_lane: Arc::new(AtomicUsize::new(0)),
+            authenticator,
+            logger,
+        }
+    }
+
+    /// Unwrap, fan out to the view stores, and collate in the enclave
+    pub fn query_impl(&mut self, request: attest::Message) -> Result<attest::Message, RpcStatus> {
+        log::trace!(self.logger, "Getting encrypted request");
+        let tracer = tracer!();
+
+        tracer.in_span("query_impl", |_cx| {
+            // Attempt and deserialize the untrusted portion of this request.
+            let query_request_aad: QueryRequestAAD = mc_util_serial::decode(request.get_aad())
+                .map_err(|err| {
+                    RpcStatus::with_message(
+                        RpcStatusCode::INVALID_ARGUMENT,
+                        format!("AAD deserialization error: {}", err),
+                    )
+                })?;
+
+            let (user_events, next_start_from_user_event_id, may_have_more_user_events) = tracer
+                .in_span("search_user_events", |_cx| {
+                    search_user_events(
+                        &*self.db,
+                        &query_request_aad,
+                        self.config.max_user_events_per_query,
+                    )
+                    .map_err(|e| rpc_internal_error("search_user_events", e, &self.logger))
+                })?;
+
+            let client_query: EnclaveMessage<ClientSession> = request.into();
+            let client_session = client_query.channel_id.clone();
+
+            let mut lane = self.lock_lane();
+            self.attest_lane(&mut lane)?;
+
+            let store_queries = tracer.in_span("create_multi_view_store_query", |_cx| {
+                let store_sessions = lane
+                    .iter()
+                    .filter_map(|conn| conn.channel_id().cloned())
+                    .collect();
+                self.enclave
+                    .create_multi_view_store_query(client_query, store_sessions)
+                    .map_err(|err| {
+                        if let ViewEnclaveError::UnknownPeer = err {
+                            Self::deattest_lane(&mut lane);
+                        }
+                        self.enclave_err_to_rpc_status("create_multi_view_store_query", err)
+                    })
+            })?;
+
+            // From here on, the router and the stores have advanced their
+            // session nonces. If the responses don't all make it back into the
+            // enclave the sessions are out of sync and must be re-established.
+            let result = tracer.in_span("query_view_stores", |_cx| {
+                let store_responses = self.query_view_stores(&lane, store_queries)?;
+
+                let untrusted_query_response = self.merge_store_responses(
+                    &store_responses,
+                    user_events,
+                    next_start_from_user_event_id,
+                    may_have_more_user_events,
+                )?;
+
+                let shard_query_responses = store_responses
+                    .into_iter()
+                    .map(|mut response| response.take_query_response().into())
+                    .collect();
+
+                self.enclave
+                    .collate_shard_query_responses(
+                        client_session,
+                        shard_query_responses,
+                        untrusted_query_response,
+                    )
+                    .map_err(|e| self.enclave_err_to_rpc_status("collate_shard_responses", e))
+            });
+
+            let result_blob = result.map_err(|err| {
+                Self::deattest_lane(&mut lane);
+                err
+            })?;
+
+            let mut resp = attest::Message::new();
+            resp.set_data(result_blob);
+            Ok(resp)
+        })
+    }
+
+    /// Take a lane of view store connections, preferring one nobody is using.
+    fn lock_lane(&self) -> MutexGuard<ViewStoreLane<E>> {
+        let num_lanes = self.view_store_lanes.len();
+        let first = self.next_lane.fetch_add(1, Ordering::Relaxed) % num_lanes;
+        for offset in 0..num_lanes {
+            if let Ok(lane) = self.view_store_lanes[(first + offset) % num_lanes].try_lock() {
+                return lane;
+            }
+        }
+        self.view_store_lanes[first]
+            .lock()
+            .expect("view store lane mutex poisoned")
+    }
+
+    /// Make sure every connection in the lane is attested.
+    fn attest_lane(&self, lane: &mut ViewStoreLane<E>) -> Result<(), RpcStatus> {
+        for conn in lane.iter_mut() {
+            if !conn.is_attested() {
+                conn.attest().map_err(|err| {
+                    rpc_unavailable_error(
+                        "attest_view_store",
+                        format!("{}: {}", conn, err),
+                        &self.logger,
+                    )
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Drop the sessions of every connection in the lane.
+    fn deattest_lane(lane: &mut ViewStoreLane<E>) {
+        for conn in lane.iter_mut() {
+            conn.deattest();
+        }
+    }
+
+    /// Send each store its query, and wait for all of them to respond.
+    fn query_view_stores(
+        &self,
+        lane: &ViewStoreLane<E>,
+        store_queries: Vec<EnclaveMessage<PeerSession>>,
+    ) -> Result<Vec<MultiViewStoreQueryResponse>, RpcStatus> {
+        let receivers = lane
+            .iter()
+            .zip(store_queries)
+            .map(|(conn, query)| {
+                conn.multi_view_store_query_async(query).map_err(|err| {
+                    rpc_unavailable_error(
+                        "multi_view_store_query",
+                        format!("{}: {}", conn, err),
+                        &self.logger,
+                    )
+                })
+            })
+            .collect::<Result<Vec<_>, RpcStatus>>()?;
+
+        block_on(try_join_all(receivers))
+            .map_err(|err| rpc_unavailable_error("multi_view_store_query", err, &self.logger))
+    }
+
+    /// Check that the stores cover every record, and combine their block
+    /// counts with the user events into the untrusted part of the response.
+    fn merge_store_responses(
+        &self,
+        store_responses: &[MultiViewStoreQueryResponse],
+        user_events: Vec<FogUserEvent>,
+        next_start_from_user_event_id: i64,
+        may_have_more_user_events: bool,
+    ) -> Result<UntrustedQueryResponse, RpcStatus> {
+        let shards = store_responses
+            .iter()
+            .map(|response| ShardingStrategy::try_from(response.get_shard()))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|err| rpc_internal_error("view_store_shard", err, &self.logger))?;
+        if !shards_cover_all_records(&shards) {
+            return Err(rpc_unavailable_error(
+                "view_store_shard",
+                format!(
+                    "view store shards do not cover every record: {}",
+                    shards
+                        .iter()
+                        .map(|shard| shard.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+                &self.logger,
+            ));
+        }
+
+        Ok(merge_store_block_counts(
+            store_responses,
+            user_events,
+            next_start_from_user_event_id,
+            may_have_more_user_events,
+        ))
+    }
+
+    // Helper function that is common
+    fn enclave_err_to_rpc_status(&self, context: &str, src: ViewEnclaveError) -> RpcStatus {
+        // Treat prost-decode error as an invalid arg,
+        // treat attest error as permission denied,
+        // everything else is an internal error
+        match src {
+            ViewEnclaveError::ProstDecode => {
+                rpc_invalid_arg_error(context, "Prost decode failed", &self.logger)
+            }
+            ViewEnclaveError::AttestEnclave(err) => {
+                rpc_permissions_error(context, err, &self.logger)
+            }
+            other => rpc_internal_error(context, format!("{}", &other), &self.logger),
+        }
+    }
+}
+
+/// The TxOut search results are only complete up to the block count every store
+/// has processed, so report the lowest one. The last known block is
+/// informational, so report the most recent one.
+fn merge_store_block_counts(
+    store_responses: &[MultiViewStoreQueryResponse],
+    user_events: Vec<FogUserEvent>,
+    next_start_from_user_event_id: i64,
+    may_have_more_user_events: bool,
+) -> UntrustedQueryResponse {
+    let least_processed = store_responses
+        .iter()
+        .min_by_key(|response| response.highest_processed_block_count);
+    let most_known = store_responses
+        .iter()
+        .max_by_key(|response| response.last_known_block_count);
+
+    UntrustedQueryResponse {
+        user_events,
+        next_start_from_user_event_id,
+        may_have_more_user_events,
+        highest_processed_block_count: least_processed
+            .map(|response| response.highest_processed_block_count)
+            .unwrap_or_default(),
+        highest_processed_block_signature_timestamp: least_processed
+            .map(|response| response.highest_processed_block_signature_timestamp)
+            .unwrap_or_default(),
+        last_known_block_count: most_known
+            .map(|response| response.last_known_block_count)
+            .unwrap_or_default(),
+        last_known_block_cumulative_txo_count: most_known
+            .map(|response| response.last_known_block_cumulative_txo_count)
+            .unwrap_or_default(),
+    }
+}
+
+// Implement grpc trait
+impl<E: ViewEnclaveProxy, DB: RecoveryDb + Send + Sync> FogViewApi for FogViewRouterService<E, DB> {
+    fn auth(
+        &mut self,
+        ctx: RpcContext,
+        mut request: attest::AuthMessage,
+        sink: UnarySink<attest::AuthMessage>,
+    ) {
+        let _timer = SVC_COUNTERS.req(&ctx);
+        mc_common::logger::scoped_global_logger(&rpc_logger(&ctx, &self.logger), |logger| {
+            if let Err(err) = check_request_chain_id(&self.config.chain_id, &ctx) {
+                return send_result(ctx, sink, Err(err), logger);
+            }
+
+            if let Err(err) = self.authenticator.authenticate_rpc(&ctx) {
+                return send_result(ctx, sink, err.into(), logger);
+            }
+
+            // TODO: Use the prost message directly, once available
+            match self.enclave.client_accept(request.take_data().into()) {
+                Ok((response, _)) => {
+                    let mut result = attest::AuthMessage::new();
+                    result.set_data(response.into());
+                    send_result(ctx, sink, Ok(result), logger);
+                }
+                Err(client_error) => {
+                    // This is debug because there's no requirement on the remote party to trigger
+                    // it.
+                    log::debug!(
+                        logger,
+                        "ViewEnclaveApi::client_accept failed: {}",
+                        client_error
+                    );
+                    send_result(
+                        ctx,
+                        sink,
+                        Err(rpc_permissions_error(
+                            "client_auth",
+                            format!("Permission denied: {}", client_error),
+                            logger,
+                        )),
+                        logger,
+                    );
+                }
+            }
+        });
+    }
+
+    fn query(
+        &mut self,
+        ctx: RpcContext,
+        request: attest::Message,
+        sink: UnarySink<attest::Message>,
+    ) {
+        let _timer = SVC_COUNTERS.req(&ctx);
+        mc_common::logger::scoped_global_logger(&rpc_logger(&ctx, &self.logger), |logger| {
+            if let Err(err) = check_request_chain_id(&self.config.chain_id, &ctx) {
+                return send_result(ctx, sink, Err(err), logger);
+            }
+
+            if let Err(err) = self.authenticator.authenticate_rpc(&ctx) {
+                return send_result(ctx, sink, err.into(), logger);
+            }
+
+            send_result(ctx, sink, self.query_impl(request), logger)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store_response(
+        highest_processed_block_count: u64,
+        highest_processed_block_signature_timestamp: u64,
+        last_known_block_count: u64,
+        last_known_block_cumulative_txo_count: u64,
+    ) -> MultiViewStoreQueryResponse {
+        let mut response = MultiViewStoreQueryResponse::new();
+        response.set_highest_processed_block_count(highest_processed_block_count);
+        response.set_highest_processed_block_signature_timestamp(
+            highest_processed_block_signature_timestamp,
+        );
+        response.set_last_known_block_count(last_known_block_count);
+        response.set_last_known_block_cumulative_txo_count(last_known_block_cumulative_txo_count);
+        response
+    }
+
+    #[test]
+    fn merge_block_counts() {
+        let merged = merge_store_block_counts(
+            &[
+                store_response(10, 1010, 12, 120),
+                store_response(8, 1008, 14, 140),
+                store_response(9, 1009, 9, 90),
+            ],
+            Vec::new(),
+            7,
+            true,
+        );
+
+        assert_eq!(merged.next_start_from_user_event_id, 7);
+        assert!(merged.may_have_more_user_events);
+        assert_eq!(merged.highest_processed_block_count, 8);
+        assert_eq!(merged.highest_processed_block_signature_timestamp, 1008);
+        assert_eq!(merged.last_known_block_count, 14);
+        assert_eq!(merged.last_known_block_cumulative_txo_count, 140);
+    }
+}
@@ -5,7 +5,7 @@ use grpcio::{RpcContext, RpcStatus, RpcStatusCode, UnarySink};
 use mc_attest_api::attest;
 use mc_common::logger::{log, Logger};
 use mc_fog_api::view_grpc::FogViewApi;
-use mc_fog_recovery_db_iface::RecoveryDb;
+use mc_fog_recovery_db_iface::{FogUserEvent, RecoveryDb};
 use mc_fog_types::view::QueryRequestAAD;
 use mc_fog_view_enclave::{Error as ViewEnclaveError, ViewEnclaveProxy};
 use mc_fog_view_enclave_api::UntrustedQueryResponse;
@@ -74,11 +74,14 @@ impl<E: ViewEnclaveProxy, DB: RecoveryDb + Send + Sync> FogViewService<E, DB> {
                     )
                 })?;
 
-            let (user_events, next_start_from_user_event_id) =
-                tracer.in_span("search_user_events", |_cx| {
-                    self.db
-                        .search_user_events(query_request_aad.start_from_user_event_id)
-                        .map_err(|e| rpc_internal_error("search_user_events", e, &self.logger))
+            let (user_events, next_start_from_user_event_id, may_have_more_user_events) = tracer
+                .in_span("search_user_events", |_cx| {
+                    search_user_events(
+                        &*self.db,
+                        &query_request_aad,
+                        self.config.max_user_events_per_query,
+                    )
+                    .map_err(|e| rpc_internal_error("search_user_events", e, &self.logger))
                 })?;
 
             let (
@@ -99,6 +102,7 @@ impl<E: ViewEnclaveProxy, DB: RecoveryDb + Send + Sync> FogViewService<E, DB> {
             let untrusted_query_response = UntrustedQueryResponse {
                 user_events,
                 next_start_from_user_event_id,
+                may_have_more_user_events,
                 highest_processed_block_count,
                 highest_processed_block_signature_timestamp,
                 last_known_block_count,
@@ -134,6 +138,35 @@ impl<E: ViewEnclaveProxy, DB: RecoveryDb + Send + Sync> FogViewService<E, DB> {
     }
 }
 
+/// Search for the user events a query asks for, returning at most the number
+/// the client asked for and at most max_user_events_per_query.
+///
+/// Returns the events, the next value of start_from_user_event_id, and whether
+/// there may be more events after that.
+pub(crate) fn search_user_events<DB: RecoveryDb>(
+    db: &DB,
+    query_request_aad: &QueryRequestAAD,
+    max_user_events_per_query: usize,
+) -> Result<(Vec<FogUserEvent>, i64, bool), DB::Error> {
+    let max_user_events = match usize::try_from(query_request_aad.max_user_events) {
+        Ok(0) => max_user_events_per_query,
+        Ok(requested) => requested.min(max_user_events_per_query),
+        Err(_) => max_user_events_per_query,
+    };
+
+    let (user_events, next_start_from_user_event_id) = db.search_user_events_with_limit(
+        query_request_aad.start_from_user_event_id,
+        max_user_events,
+    )?;
+    let may_have_more_user_events = !user_events.is_empty() && user_events.len() >= max_user_events;
+
+    Ok((
+        user_events,
+        next_start_from_user_event_id,
+        may_have_more_user_events,
+    ))
+}
+
 // Implement grpc trait
 impl<E: ViewEnclaveProxy, DB: RecoveryDb + Send + Sync> FogViewApi for FogViewService<E, DB> {
     fn auth(
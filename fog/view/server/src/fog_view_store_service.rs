@@ -0,0 +1,153 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! Serves the attested requests a fog view router makes to this server, when
+//! it acts as one of the router's view stores.
+
+use crate::{config::MobileAcctViewConfig, server::DbPollSharedState};
+use grpcio::{RpcContext, RpcStatus, UnarySink};
+use mc_attest_api::attest;
+use mc_common::logger::{log, Logger};
+use mc_fog_api::{
+    view::{MultiViewStoreQueryRequest, MultiViewStoreQueryResponse, ViewStoreShard},
+    view_grpc::FogViewStoreApi,
+};
+use mc_fog_view_enclave::{Error as ViewEnclaveError, ViewEnclaveProxy};
+use mc_util_grpc::{
+    rpc_internal_error, rpc_invalid_arg_error, rpc_logger, rpc_permissions_error, send_result,
+};
+use mc_util_metrics::SVC_COUNTERS;
+use mc_util_telemetry::{tracer, Tracer};
+use std::sync::{Arc, Mutex};
+
+#[derive(Clone)]
+pub struct FogViewStoreService<E: ViewEnclaveProxy> {
+    /// Enclave providing access to this store's shard of the Recovery DB
+    enclave: E,
+
+    /// The shard of the Recovery DB this store is responsible for
+    shard: ViewStoreShard,
+
+    /// Shared state from db polling thread.
+    db_poll_shared_state: Arc<Mutex<DbPollSharedState>>,
+
+    /// Slog logger object
+    logger: Logger,
+}
+
+impl<E: ViewEnclaveProxy> FogViewStoreService<E> {
+    /// Creates a new fog-view-store-service node (but does not create sockets
+    /// and start it etc.)
+    pub fn new(
+        config: MobileAcctViewConfig,
+        enclave: E,
+        db_poll_shared_state: Arc<Mutex<DbPollSharedState>>,
+        logger: Logger,
+    ) -> Self {
+        Self {
+            enclave,
+            shard: ViewStoreShard::from(&config.sharding_strategy),
+            db_poll_shared_state,
+            logger,
+        }
+    }
+
+    /// Unwrap and forward to enclave
+    pub fn multi_view_store_query_impl(
+        &mut self,
+        request: MultiViewStoreQueryRequest,
+    ) -> Result<MultiViewStoreQueryResponse, RpcStatus> {
+        let tracer = tracer!();
+
+        tracer.in_span("multi_view_store_query_impl", |_cx| {
+            let mut response = MultiViewStoreQueryResponse::new();
+
+            // Read the shared state before querying the enclave, so that the
+            // reported block counts never overstate what the ORAM contained.
+            {
+                let shared_state = self.db_poll_shared_state.lock().expect("mutex poisoned");
+                response
+                    .set_highest_processed_block_count(shared_state.highest_processed_block_count);
+                response.set_highest_processed_block_signature_timestamp(
+                    shared_state.highest_processed_block_signature_timestamp,
+                );
+                response.set_last_known_block_count(shared_state.last_known_block_count);
+                response.set_last_known_block_cumulative_txo_count(
+                    shared_state.last_known_block_cumulative_txo_count,
+                );
+            }
+
+            let query_response = tracer.in_span("enclave_query_store", |_cx| {
+                self.enclave
+                    .query_store(request.get_query().clone().into())
+                    .map_err(|e| self.enclave_err_to_rpc_status("enclave request", e))
+            })?;
+
+            response.set_query_response(query_response.into());
+            response.set_shard(self.shard.clone());
+            Ok(response)
+        })
+    }
+
+    // Helper function that is common
+    fn enclave_err_to_rpc_status(&self, context: &str, src: ViewEnclaveError) -> RpcStatus {
+        // Treat prost-decode error as an invalid arg,
+        // treat attest error as permission denied,
+        // everything else is an internal error
+        match src {
+            ViewEnclaveError::ProstDecode => {
+                rpc_invalid_arg_error(context, "Prost decode failed", &self.logger)
+            }
+            ViewEnclaveError::AttestEnclave(err) => {
+                rpc_permissions_error(context, err, &self.logger)
+            }
+            other => rpc_internal_error(context, format!("{}", &other), &self.logger),
+        }
+    }
+}
+
+// Implement grpc trait
+impl<E: ViewEnclaveProxy> FogViewStoreApi for FogViewStoreService<E> {
+    fn auth(
+        &mut self,
+        ctx: RpcContext,
+        request: attest::AuthMessage,
+        sink: UnarySink<attest::AuthMessage>,
+    ) {
+        let _timer = SVC_COUNTERS.req(&ctx);
+        mc_common::logger::scoped_global_logger(&rpc_logger(&ctx, &self.logger), |logger| {
+            // TODO: Use the prost message directly, once available
+            match self.enclave.peer_accept(request.into()) {
+                Ok((response, _session_id)) => {
+                    send_result(ctx, sink, Ok(response.into()), logger);
+                }
+                Err(peer_error) => {
+                    // This is debug because there's no requirement on the remote party to trigger
+                    // it.
+                    log::debug!(logger, "ViewEnclaveApi::peer_accept failed: {}", peer_error);
+                    send_result(
+                        ctx,
+                        sink,
+                        Err(rpc_permissions_error(
+                            "peer_auth",
+                            "Permission denied",
+                            logger,
+                        )),
+                        logger,
+                    );
+                }
+            }
+        });
+    }
+
+    fn multi_view_store_query(
+        &mut self,
+        ctx: RpcContext,
+        request: MultiViewStoreQueryRequest,
+        sink: UnarySink<MultiViewStoreQueryResponse>,
+    ) {
+        let _timer = SVC_COUNTERS.req(&ctx);
+        mc_common::logger::scoped_global_logger(&rpc_logger(&ctx, &self.logger), |logger| {
+            send_result(ctx, sink, self.multi_view_store_query_impl(request), logger)
+        })
+    }
+}
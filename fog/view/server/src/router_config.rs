@@ -0,0 +1,93 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! Configuration parameters for the MobileCoin Fog View Router
+#![deny(missing_docs)]
+
+use clap::Parser;
+use mc_attest_core::ProviderId;
+use mc_common::ResponderId;
+use mc_fog_sql_recovery_db::SqlRecoveryDbConnectionConfig;
+use mc_fog_uri::{FogViewStoreUri, FogViewUri};
+use mc_util_parse::parse_duration_in_seconds;
+use mc_util_uri::AdminUri;
+use serde::Serialize;
+use std::time::Duration;
+
+/// Configuration parameters for the MobileCoin Fog View Router
+#[derive(Clone, Parser, Serialize)]
+#[clap(version)]
+pub struct FogViewRouterConfig {
+    /// The chain id of the network we are a part of
+    #[clap(long, env = "MC_CHAIN_ID")]
+    pub chain_id: String,
+
+    /// The ID with which to respond to client attestation requests.
+    ///
+    /// This ID needs to match the host:port clients use in their URI when
+    /// referencing this node.
+    #[clap(long, env = "MC_CLIENT_RESPONDER_ID")]
+    pub client_responder_id: ResponderId,
+
+    /// PEM-formatted keypair to send with an Attestation Request.
+    #[clap(long, env = "MC_IAS_API_KEY")]
+    pub ias_api_key: String,
+
+    /// The IAS SPID to use when getting a quote
+    #[clap(long, env = "MC_IAS_SPID")]
+    pub ias_spid: ProviderId,
+
+    /// gRPC listening URI for client requests.
+    #[clap(long, env = "MC_CLIENT_LISTEN_URI")]
+    pub client_listen_uri: FogViewUri,
+
+    /// The view stores to fan queries out to, separated by commas.
+    ///
+    /// Each store's responder id (the host:port of the URI, or its
+    /// responder-id parameter) must match the client listen URI the store was
+    /// started with. Together the stores' sharding strategies must cover every
+    /// record, or queries will fail.
+    #[clap(
+        long,
+        required = true,
+        use_value_delimiter = true,
+        env = "MC_VIEW_STORE_URIS"
+    )]
+    pub view_store_uris: Vec<FogViewStoreUri>,
+
+    /// The number of independent sets of attested sessions to keep with the
+    /// view stores. Messages on a session must be processed in order, so this
+    /// bounds the number of queries the router can serve concurrently.
+    #[clap(long, default_value = "4", env = "MC_VIEW_STORE_SESSIONS")]
+    pub view_store_sessions: usize,
+
+    /// The maximum number of user events to return in response to one query.
+    /// Clients page through any further events using the
+    /// next_start_from_user_event_id cursor.
+    #[clap(long, default_value = "1000", env = "MC_MAX_USER_EVENTS_PER_QUERY")]
+    pub max_user_events_per_query: usize,
+
+    /// The maximum number of search keys to search for in one query. Any
+    /// further search keys in a query are left unanswered, and clients send
+    /// them again in another query.
+    #[clap(long, default_value = "2000", env = "MC_MAX_SEARCH_KEYS_PER_QUERY")]
+    pub max_search_keys_per_query: u64,
+
+    /// Optional admin listening URI.
+    #[clap(long, env = "MC_ADMIN_LISTEN_URI")]
+    pub admin_listen_uri: Option<AdminUri>,
+
+    /// Enables authenticating client requests using Authorization tokens using
+    /// the provided hex-encoded 32 bytes shared secret.
+    #[clap(long, value_parser = mc_util_parse::parse_hex::<[u8; 32]>, env = "MC_CLIENT_AUTH_TOKEN_SECRET")]
+    pub client_auth_token_secret: Option<[u8; 32]>,
+
+    /// Maximal client authentication token lifetime, in seconds (only relevant
+    /// when --client-auth-token-secret is used. Defaults to 86400 - 24
+    /// hours).
+    #[clap(long, default_value = "86400", value_parser = parse_duration_in_seconds, env = "MC_CLIENT_AUTH_TOKEN_MAX_LIFETIME")]
+    pub client_auth_token_max_lifetime: Duration,
+
+    /// Postgres config
+    #[clap(flatten)]
+    pub postgres_config: SqlRecoveryDbConnectionConfig,
+}
@@ -6,7 +6,8 @@
 
 use crate::{
     block_tracker::BlockTracker, config::MobileAcctViewConfig, counters, db_fetcher::DbFetcher,
-    fog_view_service::FogViewService,
+    fog_view_service::FogViewService, fog_view_store_service::FogViewStoreService,
+    sharding::ShardingStrategy,
 };
 use futures::executor::block_on;
 use mc_attest_net::RaClient;
@@ -105,6 +106,16 @@ where
         ));
         log::debug!(logger, "Constructed View GRPC Service");
 
+        // View store service, used when this server sits behind a fog view router
+        let fog_view_store_service =
+            view_grpc::create_fog_view_store_api(FogViewStoreService::new(
+                config.clone(),
+                enclave.clone(),
+                db_poll_thread.get_shared_state(),
+                logger.clone(),
+            ));
+        log::debug!(logger, "Constructed View Store GRPC Service");
+
         // Health check service
         let health_service =
             mc_util_grpc::HealthService::new(Some(readiness_indicator.into()), logger.clone())
@@ -118,6 +129,7 @@ where
         );
         let server_builder = grpcio::ServerBuilder::new(env)
             .register_service(fog_view_service)
+            .register_service(fog_view_store_service)
             .register_service(health_service)
             .bind_using_uri(&config.client_listen_uri, logger.clone());
 
@@ -388,6 +400,9 @@ where
     /// Keeps track of which blocks we have fed into the enclave.
     enclave_block_tracker: BlockTracker,
 
+    /// Which records belong in this server's ORAM.
+    sharding_strategy: ShardingStrategy,
+
     /// Keeps track how long ago it since we made progress, (or complained about
     /// not making progress) When this gets too distant in the past, we log
     /// a warning
@@ -432,6 +447,7 @@ where
                 logger.clone(),
             ),
             enclave_block_tracker: BlockTracker::new(logger.clone()),
+            sharding_strategy: config.sharding_strategy,
             last_unblocked_at: Instant::now(),
             logger,
         }
@@ -561,9 +577,17 @@ where
         &mut self,
         ingress_key: CompressedRistrettoPublic,
         block_index: u64,
-        records: Vec<ETxOutRecord>,
+        mut records: Vec<ETxOutRecord>,
     ) {
+        // Records belonging to other shards are skipped, but the block still
+        // counts as processed.
+        let num_fetched_records = records.len();
+        records.retain(|rec| {
+            self.sharding_strategy
+                .should_process_record(block_index, &rec.search_key)
+        });
         let num_records = records.len();
+        counters::TXOS_SKIPPED_COUNT.inc_by((num_fetched_records - num_records) as u64);
 
         let add_records_result = {
             trace_time!(
@@ -0,0 +1,349 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! Sharding of the ETxOutRecords in the recovery database across fog view
+//! stores.
+//!
+//! A fog view store only loads the records of its shard into its ORAM, so that
+//! the records of the whole recovery database can be spread over several
+//! machines. A fog view router asks every store about every search key, and
+//! uses the shards reported by the stores to check that together they cover
+//! all of the records.
+
+use displaydoc::Display;
+use mc_fog_api::{fog_common, view as view_api};
+use mc_fog_types::common::BlockRange;
+use serde::Serialize;
+use std::{fmt, str::FromStr};
+
+/// The number of distinct search key prefixes used by
+/// [ShardingStrategy::SearchKey].
+pub const SEARCH_KEY_PREFIX_COUNT: u32 = 1 << 16;
+
+/// A half-open [start, end) range of search key prefixes, where the prefix of
+/// a search key is its first two bytes interpreted as a big-endian integer.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct SearchKeyRange {
+    /// The first prefix in the range
+    pub start: u32,
+    /// One past the last prefix in the range
+    pub end: u32,
+}
+
+impl SearchKeyRange {
+    /// Test if a search key falls in the range.
+    ///
+    /// Search keys which are too short to have a prefix belong to the shard
+    /// starting at 0, so that exactly one store answers them.
+    pub fn contains(&self, search_key: &[u8]) -> bool {
+        match search_key {
+            [a, b, ..] => {
+                let prefix = u16::from_be_bytes([*a, *b]) as u32;
+                self.start <= prefix && prefix < self.end
+            }
+            _ => self.start == 0,
+        }
+    }
+}
+
+/// Describes which ETxOutRecords a fog view store loads into its ORAM.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub enum ShardingStrategy {
+    /// Load the records of the blocks in this range. An end_block of u64::MAX
+    /// means the range is unbounded.
+    Epoch(BlockRange),
+    /// Load the records whose search keys fall in this range.
+    SearchKey(SearchKeyRange),
+}
+
+impl Default for ShardingStrategy {
+    /// By default a store loads every record.
+    fn default() -> Self {
+        Self::Epoch(BlockRange::new(0, u64::MAX))
+    }
+}
+
+impl ShardingStrategy {
+    /// Test if a record in the given block, with the given search key, belongs
+    /// to this shard.
+    pub fn should_process_record(&self, block_index: u64, search_key: &[u8]) -> bool {
+        match self {
+            Self::Epoch(block_range) => block_range.contains(block_index),
+            Self::SearchKey(search_key_range) => search_key_range.contains(search_key),
+        }
+    }
+}
+
+/// An error parsing a sharding strategy
+#[derive(Debug, Display, Eq, PartialEq)]
+pub enum ShardingStrategyParseError {
+    /// Expected "blocks:START-[END]" or "search-keys:START-END", got "{0}"
+    UnknownFormat(String),
+    /// Invalid number: {0}
+    InvalidNumber(String),
+    /// Range is empty or out of bounds: {0}
+    InvalidRange(String),
+}
+
+impl std::error::Error for ShardingStrategyParseError {}
+
+impl FromStr for ShardingStrategy {
+    type Err = ShardingStrategyParseError;
+
+    /// Parse "blocks:START-END" (END may be omitted for an unbounded range) or
+    /// "search-keys:START-END" (hex prefixes, END at most 10000).
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        let unknown = || ShardingStrategyParseError::UnknownFormat(src.to_string());
+        let (kind, range) = src.split_once(':').ok_or_else(unknown)?;
+        let (start, end) = range.split_once('-').ok_or_else(unknown)?;
+        let invalid_number = |_| ShardingStrategyParseError::InvalidNumber(src.to_string());
+        let invalid_range = || ShardingStrategyParseError::InvalidRange(src.to_string());
+
+        match kind {
+            "blocks" => {
+                let start_block = start.parse::<u64>().map_err(invalid_number)?;
+                let end_block = if end.is_empty() {
+                    u64::MAX
+                } else {
+                    end.parse::<u64>().map_err(invalid_number)?
+                };
+                let block_range = BlockRange::new(start_block, end_block);
+                if !block_range.is_valid() {
+                    return Err(invalid_range());
+                }
+                Ok(Self::Epoch(block_range))
+            }
+            "search-keys" => {
+                let start = u32::from_str_radix(start, 16).map_err(invalid_number)?;
+                let end = u32::from_str_radix(end, 16).map_err(invalid_number)?;
+                if start >= end || end > SEARCH_KEY_PREFIX_COUNT {
+                    return Err(invalid_range());
+                }
+                Ok(Self::SearchKey(SearchKeyRange { start, end }))
+            }
+            _ => Err(unknown()),
+        }
+    }
+}
+
+impl fmt::Display for ShardingStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Epoch(block_range) if block_range.end_block == u64::MAX => {
+                write!(f, "blocks:{}-", block_range.start_block)
+            }
+            Self::Epoch(block_range) => {
+                write!(
+                    f,
+                    "blocks:{}-{}",
+                    block_range.start_block, block_range.end_block
+                )
+            }
+            Self::SearchKey(range) => {
+                write!(f, "search-keys:{:04x}-{:04x}", range.start, range.end)
+            }
+        }
+    }
+}
+
+impl From<&ShardingStrategy> for view_api::ViewStoreShard {
+    fn from(src: &ShardingStrategy) -> Self {
+        let mut shard = view_api::ViewStoreShard::new();
+        match src {
+            ShardingStrategy::Epoch(block_range) => {
+                shard.set_block_range(fog_common::BlockRange::from(block_range));
+            }
+            ShardingStrategy::SearchKey(range) => {
+                let mut search_key_range = view_api::SearchKeyRange::new();
+                search_key_range.set_start(range.start);
+                search_key_range.set_end(range.end);
+                shard.set_search_key_range(search_key_range);
+            }
+        }
+        shard
+    }
+}
+
+impl TryFrom<&view_api::ViewStoreShard> for ShardingStrategy {
+    type Error = ShardingStrategyParseError;
+
+    fn try_from(src: &view_api::ViewStoreShard) -> Result<Self, Self::Error> {
+        if src.has_block_range() {
+            let block_range = BlockRange::new(
+                src.get_block_range().start_block,
+                src.get_block_range().end_block,
+            );
+            if !block_range.is_valid() {
+                return Err(ShardingStrategyParseError::InvalidRange(format!(
+                    "{:?}",
+                    block_range
+                )));
+            }
+            Ok(Self::Epoch(block_range))
+        } else if src.has_search_key_range() {
+            let range = SearchKeyRange {
+                start: src.get_search_key_range().start,
+                end: src.get_search_key_range().end,
+            };
+            if range.start >= range.end || range.end > SEARCH_KEY_PREFIX_COUNT {
+                return Err(ShardingStrategyParseError::InvalidRange(format!(
+                    "{:?}",
+                    range
+                )));
+            }
+            Ok(Self::SearchKey(range))
+        } else {
+            Err(ShardingStrategyParseError::UnknownFormat(
+                "empty view store shard".to_string(),
+            ))
+        }
+    }
+}
+
+/// Test if a set of shards, each held by a different store, together cover
+/// every record in the recovery database.
+///
+/// The shards must all use the same kind of strategy.
+pub fn shards_cover_all_records(shards: &[ShardingStrategy]) -> bool {
+    let mut block_ranges = Vec::new();
+    let mut search_key_ranges = Vec::new();
+    for shard in shards {
+        match shard {
+            ShardingStrategy::Epoch(block_range) => {
+                block_ranges.push((block_range.start_block, block_range.end_block))
+            }
+            ShardingStrategy::SearchKey(range) => {
+                search_key_ranges.push((range.start as u64, range.end as u64))
+            }
+        }
+    }
+
+    match (block_ranges.is_empty(), search_key_ranges.is_empty()) {
+        (false, true) => ranges_cover(block_ranges, u64::MAX),
+        (true, false) => ranges_cover(search_key_ranges, SEARCH_KEY_PREFIX_COUNT as u64),
+        _ => false,
+    }
+}
+
+/// Test if a set of half-open ranges covers [0, end).
+fn ranges_cover(mut ranges: Vec<(u64, u64)>, end: u64) -> bool {
+    ranges.sort_unstable();
+    let mut covered_until = 0;
+    for (range_start, range_end) in ranges {
+        if range_start > covered_until {
+            return false;
+        }
+        covered_until = covered_until.max(range_end);
+    }
+    covered_until >= end
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_sharding_strategies() {
+        assert_eq!(
+            ShardingStrategy::from_str("blocks:0-100").unwrap(),
+            ShardingStrategy::Epoch(BlockRange::new(0, 100))
+        );
+        assert_eq!(
+            ShardingStrategy::from_str("blocks:100-").unwrap(),
+            ShardingStrategy::Epoch(BlockRange::new(100, u64::MAX))
+        );
+        assert_eq!(
+            ShardingStrategy::from_str("search-keys:8000-10000").unwrap(),
+            ShardingStrategy::SearchKey(SearchKeyRange {
+                start: 0x8000,
+                end: 0x10000
+            })
+        );
+
+        for bad in [
+            "blocks",
+            "blocks:100",
+            "blocks:100-100",
+            "blocks:x-",
+            "search-keys:0000-10001",
+            "search-keys:8000-",
+            "epoch:0-100",
+        ] {
+            assert!(ShardingStrategy::from_str(bad).is_err(), "{}", bad);
+        }
+
+        for good in ["blocks:0-", "blocks:5-10", "search-keys:0000-8000"] {
+            assert_eq!(ShardingStrategy::from_str(good).unwrap().to_string(), good);
+        }
+    }
+
+    #[test]
+    fn should_process_record() {
+        let epoch = ShardingStrategy::from_str("blocks:10-20").unwrap();
+        assert!(!epoch.should_process_record(9, &[0; 16]));
+        assert!(epoch.should_process_record(10, &[0; 16]));
+        assert!(epoch.should_process_record(19, &[0xff; 16]));
+        assert!(!epoch.should_process_record(20, &[0; 16]));
+
+        let low = ShardingStrategy::from_str("search-keys:0000-8000").unwrap();
+        let high = ShardingStrategy::from_str("search-keys:8000-10000").unwrap();
+        for key in [[0u8; 16], [0x7f; 16], [0x80; 16], [0xff; 16]] {
+            assert_ne!(
+                low.should_process_record(0, &key),
+                high.should_process_record(0, &key)
+            );
+        }
+        assert!(low.should_process_record(0, &[0x7f, 0xff]));
+        assert!(high.should_process_record(0, &[0x80, 0x00]));
+        assert!(low.should_process_record(0, &[0xff]));
+        assert!(!high.should_process_record(0, &[0xff]));
+    }
+
+    #[test]
+    fn proto_round_trip() {
+        for strategy in ["blocks:3-", "blocks:0-7", "search-keys:0100-0200"] {
+            let strategy = ShardingStrategy::from_str(strategy).unwrap();
+            let proto = view_api::ViewStoreShard::from(&strategy);
+            assert_eq!(ShardingStrategy::try_from(&proto).unwrap(), strategy);
+        }
+        assert!(ShardingStrategy::try_from(&view_api::ViewStoreShard::new()).is_err());
+    }
+
+    #[test]
+    fn coverage() {
+        let parse = |shards: &[&str]| -> Vec<ShardingStrategy> {
+            shards
+                .iter()
+                .map(|shard| ShardingStrategy::from_str(shard).unwrap())
+                .collect()
+        };
+
+        assert!(shards_cover_all_records(&[ShardingStrategy::default()]));
+        assert!(shards_cover_all_records(&parse(&[
+            "blocks:100-",
+            "blocks:0-100"
+        ])));
+        assert!(shards_cover_all_records(&parse(&[
+            "blocks:0-60",
+            "blocks:50-100",
+            "blocks:100-"
+        ])));
+        assert!(shards_cover_all_records(&parse(&[
+            "search-keys:8000-10000",
+            "search-keys:0000-8000"
+        ])));
+
+        assert!(!shards_cover_all_records(&[]));
+        assert!(!shards_cover_all_records(&parse(&["blocks:0-100"])));
+        assert!(!shards_cover_all_records(&parse(&[
+            "blocks:0-50",
+            "blocks:51-"
+        ])));
+        assert!(!shards_cover_all_records(&parse(&[
+            "search-keys:0001-10000"
+        ])));
+        assert!(!shards_cover_all_records(&parse(&[
+            "blocks:0-",
+            "search-keys:0000-10000"
+        ])));
+    }
+}
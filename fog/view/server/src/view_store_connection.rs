@@ -0,0 +1,171 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! An attested connection from a fog view router to a fog view store.
+
+use crate::error::ViewStoreAttestationError;
+use core::fmt::{Display, Formatter, Result as FmtResult};
+use grpcio::{ChannelBuilder, ClientUnaryReceiver, Environment, Error as GrpcError};
+use mc_attest_core::VerificationReport;
+use mc_attest_enclave_api::{EnclaveMessage, PeerSession};
+use mc_common::{
+    logger::{log, o, Logger},
+    ResponderId,
+};
+use mc_connection::{AttestedConnection, Connection};
+use mc_fog_api::{
+    view::{MultiViewStoreQueryRequest, MultiViewStoreQueryResponse},
+    view_grpc::FogViewStoreApiClient,
+};
+use mc_fog_uri::FogViewStoreUri;
+use mc_fog_view_enclave::ViewEnclaveProxy;
+use mc_util_grpc::ConnectionUriGrpcioChannel;
+use mc_util_uri::ConnectionUri;
+use std::{
+    cmp::Ordering,
+    hash::{Hash, Hasher},
+    result::Result as StdResult,
+    sync::Arc,
+};
+
+/// This is a connection to a view store which ensures transparent attestation
+/// between the router's enclave and the store's enclave.
+pub struct ViewStoreConnection<Enclave: ViewEnclaveProxy> {
+    /// The local (router) enclave.
+    enclave: Enclave,
+
+    /// When communicating with the remote enclave, this is the handshake hash /
+    /// session ID / channel ID.
+    channel_id: Option<PeerSession>,
+
+    /// The remote node ID
+    remote_responder_id: ResponderId,
+
+    /// The remote node's URI.
+    uri: FogViewStoreUri,
+
+    /// The logger instance we will be using.
+    logger: Logger,
+
+    /// The gRPC client used to talk to the view store.
+    view_store_api_client: FogViewStoreApiClient,
+}
+
+impl<Enclave: ViewEnclaveProxy> ViewStoreConnection<Enclave> {
+    /// Construct a new ViewStoreConnection, optionally with TLS enabled.
+    pub fn new(
+        enclave: Enclave,
+        uri: FogViewStoreUri,
+        env: Arc<Environment>,
+        logger: Logger,
+    ) -> Self {
+        let remote_responder_id = uri.responder_id().unwrap_or_else(|_| {
+            panic!("Could not get responder id from uri {:?}", uri.to_string())
+        });
+        let host_port = uri.addr();
+
+        let logger = logger.new(o!("mc.view_store.addr" => host_port));
+
+        let ch = ChannelBuilder::default_channel_builder(env)
+            .max_receive_message_len(std::i32::MAX)
+            .max_send_message_len(std::i32::MAX)
+            .connect_to_uri(&uri, &logger);
+
+        let view_store_api_client = FogViewStoreApiClient::new(ch);
+
+        Self {
+            enclave,
+            channel_id: None,
+            remote_responder_id,
+            uri,
+            logger,
+            view_store_api_client,
+        }
+    }
+
+    /// The session with the remote enclave, if attested.
+    pub fn channel_id(&self) -> Option<&PeerSession> {
+        self.channel_id.as_ref()
+    }
+
+    /// Send a query, which must have been encrypted for this connection's
+    /// session, to the view store without waiting for the response.
+    pub fn multi_view_store_query_async(
+        &self,
+        query: EnclaveMessage<PeerSession>,
+    ) -> StdResult<ClientUnaryReceiver<MultiViewStoreQueryResponse>, GrpcError> {
+        let mut request = MultiViewStoreQueryRequest::new();
+        request.set_query(query.into());
+        self.view_store_api_client
+            .multi_view_store_query_async(&request)
+    }
+}
+
+impl<Enclave: ViewEnclaveProxy> Display for ViewStoreConnection<Enclave> {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "{}", self.uri)
+    }
+}
+
+impl<Enclave: ViewEnclaveProxy> Eq for ViewStoreConnection<Enclave> {}
+
+impl<Enclave: ViewEnclaveProxy> Hash for ViewStoreConnection<Enclave> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.uri.addr().hash(state);
+    }
+}
+
+impl<Enclave: ViewEnclaveProxy> Ord for ViewStoreConnection<Enclave> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.uri.addr().cmp(&other.uri.addr())
+    }
+}
+
+impl<Enclave: ViewEnclaveProxy> PartialEq for ViewStoreConnection<Enclave> {
+    fn eq(&self, other: &Self) -> bool {
+        self.uri.addr() == other.uri.addr()
+    }
+}
+
+impl<Enclave: ViewEnclaveProxy> PartialOrd for ViewStoreConnection<Enclave> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.uri.addr().partial_cmp(&other.uri.addr())
+    }
+}
+
+impl<Enclave: ViewEnclaveProxy> Connection for ViewStoreConnection<Enclave> {
+    type Uri = FogViewStoreUri;
+
+    fn uri(&self) -> Self::Uri {
+        self.uri.clone()
+    }
+}
+
+impl<Enclave: ViewEnclaveProxy> AttestedConnection for ViewStoreConnection<Enclave> {
+    type Error = ViewStoreAttestationError;
+
+    fn is_attested(&self) -> bool {
+        self.channel_id.is_some()
+    }
+
+    fn attest(&mut self) -> StdResult<VerificationReport, Self::Error> {
+        self.deattest();
+        let req = self.enclave.peer_init(&self.remote_responder_id)?;
+        let res = self.view_store_api_client.auth(&req.into())?;
+        let (peer_session, verification_report) = self
+            .enclave
+            .peer_connect(&self.remote_responder_id, res.into())?;
+
+        self.channel_id = Some(peer_session);
+
+        Ok(verification_report)
+    }
+
+    fn deattest(&mut self) {
+        if let Some(peer_session) = self.channel_id.take() {
+            log::trace!(self.logger, "Tearing down existing attested connection.");
+            if let Err(err) = self.enclave.peer_close(&peer_session) {
+                log::debug!(self.logger, "Failed closing peer session: {}", err);
+            }
+        }
+    }
+}
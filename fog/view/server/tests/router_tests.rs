@@ -0,0 +1,345 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+// Integration tests for a fog view router in front of several sharded view
+// stores. Each test starts a set of view stores sharing one recovery database,
+// a router fanning out to them, and checks that a client talking to the router
+// sees every record exactly as if it had talked to a single view server.
+
+use mc_attest_net::{Client as AttestClient, RaClient};
+use mc_attest_verifier::{MrSignerVerifier, Verifier, DEBUG_ENCLAVE};
+use mc_blockchain_types::{Block, BlockID, BlockVersion};
+use mc_common::{
+    logger::{log, test_with_logger, Logger},
+    time::SystemTimeProvider,
+    ResponderId,
+};
+use mc_crypto_keys::{CompressedRistrettoPublic, RistrettoPublic};
+use mc_fog_kex_rng::KexRngPubkey;
+use mc_fog_recovery_db_iface::RecoveryDb;
+use mc_fog_sql_recovery_db::{test_utils::SqlRecoveryDbTestContext, SqlRecoveryDb};
+use mc_fog_test_infra::get_enclave_path;
+use mc_fog_types::{
+    view::{TxOutSearchResult, TxOutSearchResultCode, FIXED_CIPHERTEXT_LENGTH},
+    ETxOutRecord,
+};
+use mc_fog_uri::{ConnectionUri, FogViewStoreUri, FogViewUri};
+use mc_fog_view_connection::FogViewGrpcClient;
+use mc_fog_view_enclave::SgxViewEnclave;
+use mc_fog_view_protocol::FogViewConnection;
+use mc_fog_view_server::{
+    config::MobileAcctViewConfig as ViewConfig, fog_view_router_server::FogViewRouterServer,
+    router_config::FogViewRouterConfig, server::ViewServer, sharding::ShardingStrategy,
+};
+use mc_util_from_random::FromRandom;
+use mc_util_grpc::GrpcRetryConfig;
+use rand::{rngs::StdRng, SeedableRng};
+use std::{str::FromStr, sync::Arc, thread::sleep, time::Duration};
+
+const GRPC_RETRY_CONFIG: GrpcRetryConfig = GrpcRetryConfig {
+    grpc_retry_count: 3,
+    grpc_retry_millis: 20,
+};
+
+const OMAP_CAPACITY: u64 = 512;
+
+/// The number of blocks written to the database by each test.
+const NUM_BLOCKS: u64 = 4;
+
+struct RouterTestEnvironment {
+    db_test_context: SqlRecoveryDbTestContext,
+    stores: Vec<ViewServer<SgxViewEnclave, AttestClient, SqlRecoveryDb>>,
+    _router: FogViewRouterServer<SgxViewEnclave, AttestClient>,
+    client: FogViewGrpcClient,
+}
+
+fn start_view_store(
+    db: SqlRecoveryDb,
+    sharding_strategy: ShardingStrategy,
+    logger: Logger,
+) -> (
+    ViewServer<SgxViewEnclave, AttestClient, SqlRecoveryDb>,
+    FogViewStoreUri,
+) {
+    let port = portpicker::pick_unused_port().expect("pick_unused_port");
+    let uri = FogViewUri::from_str(&format!("insecure-fog-view://127.0.0.1:{}", port)).unwrap();
+    let store_uri =
+        FogViewStoreUri::from_str(&format!("insecure-fog-view-store://127.0.0.1:{}", port))
+            .unwrap();
+
+    let config = ViewConfig {
+        chain_id: "local".to_string(),
+        client_responder_id: ResponderId::from_str(&uri.addr()).unwrap(),
+        client_listen_uri: uri.clone(),
+        client_auth_token_secret: None,
+        omap_capacity: OMAP_CAPACITY,
+        ias_spid: Default::default(),
+        ias_api_key: Default::default(),
+        admin_listen_uri: Default::default(),
+        client_auth_token_max_lifetime: Default::default(),
+        postgres_config: Default::default(),
+        block_query_batch_size: 2,
+        max_user_events_per_query: 1000,
+        max_search_keys_per_query: 2000,
+        sharding_strategy,
+    };
+
+    let enclave = SgxViewEnclave::new(
+        get_enclave_path(mc_fog_view_enclave::ENCLAVE_FILE),
+        config.client_responder_id.clone(),
+        uri.responder_id().unwrap(),
+        config.omap_capacity,
+        config.max_search_keys_per_query,
+        logger.clone(),
+    );
+
+    let ra_client = AttestClient::new(&config.ias_api_key).expect("Could not create IAS client");
+
+    let mut server = ViewServer::new(
+        config,
+        enclave,
+        db,
+        ra_client,
+        SystemTimeProvider::default(),
+        logger,
+    );
+    server.start();
+
+    (server, store_uri)
+}
+
+fn get_test_environment(sharding_strategies: &[&str], logger: Logger) -> RouterTestEnvironment {
+    let db_test_context = SqlRecoveryDbTestContext::new(logger.clone());
+
+    let (stores, view_store_uris): (Vec<_>, Vec<_>) = sharding_strategies
+        .iter()
+        .map(|strategy| {
+            start_view_store(
+                db_test_context.get_db_instance(),
+                ShardingStrategy::from_str(strategy).unwrap(),
+                logger.clone(),
+            )
+        })
+        .unzip();
+
+    let port = portpicker::pick_unused_port().expect("pick_unused_port");
+    let uri = FogViewUri::from_str(&format!("insecure-fog-view://127.0.0.1:{}", port)).unwrap();
+
+    let router = {
+        let config = FogViewRouterConfig {
+            chain_id: "local".to_string(),
+            client_responder_id: ResponderId::from_str(&uri.addr()).unwrap(),
+            ias_api_key: Default::default(),
+            ias_spid: Default::default(),
+            client_listen_uri: uri.clone(),
+            view_store_uris,
+            view_store_sessions: 2,
+            max_user_events_per_query: 1000,
+            max_search_keys_per_query: 2000,
+            admin_listen_uri: Default::default(),
+            client_auth_token_secret: None,
+            client_auth_token_max_lifetime: Default::default(),
+            postgres_config: Default::default(),
+        };
+
+        let enclave = SgxViewEnclave::new(
+            get_enclave_path(mc_fog_view_enclave::ENCLAVE_FILE),
+            config.client_responder_id.clone(),
+            config.client_responder_id.clone(),
+            OMAP_CAPACITY,
+            config.max_search_keys_per_query,
+            logger.clone(),
+        );
+
+        let ra_client =
+            AttestClient::new(&config.ias_api_key).expect("Could not create IAS client");
+
+        let mut router = FogViewRouterServer::new(
+            config,
+            enclave,
+            db_test_context.get_db_instance(),
+            ra_client,
+            SystemTimeProvider::default(),
+            logger.clone(),
+        );
+        router.start();
+        router
+    };
+
+    let client = {
+        let grpcio_env = Arc::new(grpcio::EnvBuilder::new().build());
+        let mut mr_signer_verifier =
+            MrSignerVerifier::from(mc_fog_view_enclave_measurement::sigstruct());
+        mr_signer_verifier
+            .allow_hardening_advisories(mc_fog_view_enclave_measurement::HARDENING_ADVISORIES);
+
+        let mut verifier = Verifier::default();
+        verifier.mr_signer(mr_signer_verifier).debug(DEBUG_ENCLAVE);
+
+        FogViewGrpcClient::new(
+            "local".to_string(),
+            uri,
+            GRPC_RETRY_CONFIG,
+            verifier,
+            grpcio_env,
+            logger,
+        )
+    };
+
+    RouterTestEnvironment {
+        db_test_context,
+        stores,
+        _router: router,
+        client,
+    }
+}
+
+/// Writes NUM_BLOCKS blocks of records to the database, with search keys
+/// spread across the whole key space, and returns the records.
+fn add_records_to_db(db: &SqlRecoveryDb, rng: &mut StdRng) -> Vec<ETxOutRecord> {
+    let ingress_key = CompressedRistrettoPublic::from(RistrettoPublic::from_random(rng));
+    db.new_ingress_key(&ingress_key, 0).unwrap();
+
+    let pubkey = KexRngPubkey {
+        public_key: [1; 32].to_vec(),
+        version: 0,
+    };
+    let invoc_id = db
+        .new_ingest_invocation(None, &ingress_key, &pubkey, 0)
+        .unwrap();
+
+    // Search keys starting with 12, 24, ..., 240 land on both sides of any
+    // search key shard boundary used by the tests.
+    let records: Vec<ETxOutRecord> = (1u8..21u8)
+        .map(|x| ETxOutRecord {
+            search_key: vec![x * 12; 16],
+            payload: vec![x; 232],
+        })
+        .collect();
+
+    let records_per_block = records.len() / NUM_BLOCKS as usize;
+    for (block_index, block_records) in records.chunks(records_per_block).enumerate() {
+        let block_index = block_index as u64;
+        db.add_block_data(
+            &invoc_id,
+            &Block::new(
+                BlockVersion::ZERO,
+                &BlockID::default(),
+                block_index,
+                (block_index + 1) * records_per_block as u64,
+                &Default::default(),
+                &Default::default(),
+            ),
+            0,
+            block_records,
+        )
+        .unwrap();
+    }
+
+    records
+}
+
+/// Waits for every view store to load all the blocks in the database.
+fn wait_for_stores(stores: &[ViewServer<SgxViewEnclave, AttestClient, SqlRecoveryDb>]) {
+    let mut allowed_tries = 60usize;
+    while stores
+        .iter()
+        .any(|store| store.highest_processed_block_count() != NUM_BLOCKS)
+    {
+        if allowed_tries == 0 {
+            panic!("View stores did not catch up to database!");
+        }
+        allowed_tries -= 1;
+        sleep(Duration::from_millis(1000));
+    }
+}
+
+/// Checks that a query through the router finds every record, and doesn't find
+/// records which don't exist.
+fn test_router_integration(sharding_strategies: &[&str], logger: Logger) {
+    let mut rng: StdRng = SeedableRng::from_seed([123u8; 32]);
+    let mut env = get_test_environment(sharding_strategies, logger.clone());
+    let db = env.db_test_context.get_db_instance();
+
+    let records = add_records_to_db(&db, &mut rng);
+    wait_for_stores(&env.stores);
+
+    let mut expected_results: Vec<TxOutSearchResult> = records
+        .iter()
+        .map(|record| TxOutSearchResult {
+            search_key: record.search_key.clone(),
+            result_code: TxOutSearchResultCode::Found as u32,
+            ciphertext: record.payload.clone(),
+            payload_length: 0,
+        })
+        .collect();
+    for i in 0..3 {
+        expected_results.push(TxOutSearchResult {
+            search_key: vec![i * 100 + 1; 16],
+            result_code: TxOutSearchResultCode::NotFound as u32,
+            ciphertext: vec![0; 64],
+            payload_length: 0,
+        });
+    }
+
+    let search_keys: Vec<_> = expected_results
+        .iter()
+        .map(|result| result.search_key.clone())
+        .collect();
+
+    let result = env.client.request(0, 0, search_keys).unwrap();
+    log::info!(logger, "{:?}", result.tx_out_search_results);
+
+    assert_eq!(result.highest_processed_block_count, NUM_BLOCKS);
+    assert_eq!(result.last_known_block_count, NUM_BLOCKS);
+    assert_eq!(
+        result.last_known_block_cumulative_txo_count,
+        records.len() as u64
+    );
+    assert_eq!(result.rng_records.len(), 1);
+    // The router pads every ciphertext to a fixed length, so that the
+    // response doesn't reveal which store found a record.
+    assert!(result
+        .tx_out_search_results
+        .iter()
+        .all(|result| result.ciphertext.len() == FIXED_CIPHERTEXT_LENGTH));
+    let unpadded = |results: &[TxOutSearchResult]| {
+        results
+            .iter()
+            .map(|result| {
+                (
+                    result.search_key.clone(),
+                    result.result_code,
+                    result.payload().to_vec(),
+                )
+            })
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        unpadded(&result.tx_out_search_results),
+        unpadded(&expected_results)
+    );
+}
+
+#[test_with_logger]
+fn test_router_search_key_shards(logger: Logger) {
+    test_router_integration(&["search-keys:0000-8000", "search-keys:8000-10000"], logger);
+
+    // Sleep before exiting to give server threads time to join
+    sleep(Duration::from_millis(1000));
+}
+
+#[test_with_logger]
+fn test_router_block_shards(logger: Logger) {
+    test_router_integration(&["blocks:0-2", "blocks:2-"], logger);
+
+    // Sleep before exiting to give server threads time to join
+    sleep(Duration::from_millis(1000));
+}
+
+#[test_with_logger]
+fn test_router_single_store(logger: Logger) {
+    test_router_integration(&["blocks:0-"], logger);
+
+    // Sleep before exiting to give server threads time to join
+    sleep(Duration::from_millis(1000));
+}
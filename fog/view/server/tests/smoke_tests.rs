@@ -51,6 +51,19 @@ fn get_test_environment(
     SqlRecoveryDbTestContext,
     ViewServer<SgxViewEnclave, AttestClient, SqlRecoveryDb>,
     FogViewGrpcClient,
+) {
+    get_test_environment_with_limits(view_omap_capacity, 1000, 2000, logger)
+}
+
+fn get_test_environment_with_limits(
+    view_omap_capacity: u64,
+    max_user_events_per_query: usize,
+    max_search_keys_per_query: u64,
+    logger: Logger,
+) -> (
+    SqlRecoveryDbTestContext,
+    ViewServer<SgxViewEnclave, AttestClient, SqlRecoveryDb>,
+    FogViewGrpcClient,
 ) {
     let db_test_context = SqlRecoveryDbTestContext::new(logger.clone());
     let db = db_test_context.get_db_instance();
@@ -71,12 +84,17 @@ fn get_test_environment(
             client_auth_token_max_lifetime: Default::default(),
             postgres_config: Default::default(),
             block_query_batch_size: 2,
+            max_user_events_per_query,
+            max_search_keys_per_query,
+            sharding_strategy: Default::default(),
         };
 
         let enclave = SgxViewEnclave::new(
             get_enclave_path(mc_fog_view_enclave::ENCLAVE_FILE),
             config.client_responder_id.clone(),
+            config.client_responder_id.clone(),
             config.omap_capacity,
+            config.max_search_keys_per_query,
             logger.clone(),
         );
 
@@ -460,6 +478,7 @@ fn assert_e_tx_out_records_sanity(
             search_key: record.search_key.clone(),
             result_code: TxOutSearchResultCode::Found as u32,
             ciphertext: record.payload.clone(),
+            payload_length: 0,
         });
     }
     for i in 0..3 {
@@ -467,6 +486,7 @@ fn assert_e_tx_out_records_sanity(
             search_key: vec![i + 1; 16], // Search key if all zeros is invalid.
             result_code: TxOutSearchResultCode::NotFound as u32,
             ciphertext: vec![0; 64],
+            payload_length: 0,
         });
     }
 
@@ -849,3 +869,82 @@ fn test_middle_missing_range_with_decommission(logger: Logger) {
 
     assert_e_tx_out_records_sanity(&mut view_client, &expected_records, &logger);
 }
+
+/// Test that a view server pages through user events and search keys when a
+/// query asks for more than its limits.
+#[test_with_logger]
+fn test_query_limits(logger: Logger) {
+    let mut rng: StdRng = SeedableRng::from_seed([123u8; 32]);
+    let (db_context, server, mut view_client) =
+        get_test_environment_with_limits(512, 2, 3, logger.clone());
+    let db = db_context.get_db_instance();
+
+    let ingress_key = CompressedRistrettoPublic::from(RistrettoPublic::from_random(&mut rng));
+    db.new_ingress_key(&ingress_key, 0).unwrap();
+
+    // Five ingest invocations make five user events.
+    let pubkeys: Vec<KexRngPubkey> = (0..5).map(|_| random_kex_rng_pubkey(&mut rng)).collect();
+    let invoc_ids: Vec<_> = pubkeys
+        .iter()
+        .map(|pubkey| {
+            db.new_ingest_invocation(None, &ingress_key, pubkey, 0)
+                .unwrap()
+        })
+        .collect();
+
+    let (block, records) = random_block(&mut rng, 0, 5);
+    db.add_block_data(&invoc_ids[0], &block, 0, &records)
+        .unwrap();
+
+    let mut allowed_tries = 60usize;
+    while server.highest_processed_block_count() != 1 {
+        if allowed_tries == 0 {
+            panic!("Server did not catch up to database!");
+        }
+        allowed_tries -= 1;
+        sleep(Duration::from_millis(1000));
+    }
+
+    let search_keys: Vec<Vec<u8>> = records
+        .iter()
+        .map(|record| record.search_key.clone())
+        .collect();
+
+    // The first query gets the first two events and the first three search keys.
+    let result = view_client.request(0, 0, search_keys.clone()).unwrap();
+    assert_eq!(result.rng_records.len(), 2);
+    assert_eq!(result.rng_records[0].pubkey, pubkeys[0]);
+    assert_eq!(result.rng_records[1].pubkey, pubkeys[1]);
+    assert_eq!(result.next_start_from_user_event_id, 2);
+    assert!(result.may_have_more_user_events);
+    assert_eq!(result.tx_out_search_results.len(), 3);
+    for (result, search_key) in result.tx_out_search_results.iter().zip(search_keys.iter()) {
+        assert_eq!(&result.search_key, search_key);
+        assert_eq!(result.result_code, TxOutSearchResultCode::Found as u32);
+    }
+
+    // Following the cursor gets the rest.
+    let result = view_client
+        .request(2, 0, search_keys[3..].to_vec())
+        .unwrap();
+    assert_eq!(result.rng_records.len(), 2);
+    assert_eq!(result.rng_records[0].pubkey, pubkeys[2]);
+    assert_eq!(result.rng_records[1].pubkey, pubkeys[3]);
+    assert_eq!(result.next_start_from_user_event_id, 4);
+    assert!(result.may_have_more_user_events);
+    assert_eq!(result.tx_out_search_results.len(), 2);
+    for (result, search_key) in result
+        .tx_out_search_results
+        .iter()
+        .zip(search_keys[3..].iter())
+    {
+        assert_eq!(&result.search_key, search_key);
+        assert_eq!(result.result_code, TxOutSearchResultCode::Found as u32);
+    }
+
+    let result = view_client.request(4, 0, Default::default()).unwrap();
+    assert_eq!(result.rng_records.len(), 1);
+    assert_eq!(result.rng_records[0].pubkey, pubkeys[4]);
+    assert_eq!(result.next_start_from_user_event_id, 5);
+    assert!(!result.may_have_more_user_events);
+}
@@ -7,13 +7,20 @@
 
 use clap::Parser;
 use grpcio::ChannelBuilder;
-use mc_api::external::{CompressedRistretto, PublicAddress, RistrettoPrivate};
+use mc_api::external::{CompressedRistretto, PublicAddress};
 use mc_common::logger::{create_app_logger, log, o};
 use mc_mobilecoind_api::{self as api, mobilecoind_api_grpc::MobilecoindApiClient, MobilecoindUri};
-use mc_mobilecoind_json::data_types::*;
+use mc_mobilecoind_json::{data_types::*, openapi::openapi_document};
 use mc_util_grpc::ConnectionUriGrpcioChannel;
 use protobuf::RepeatedField;
-use rocket::{delete, get, post, routes, serde::json::Json};
+use rocket::{
+    delete,
+    futures::StreamExt,
+    get, post, put,
+    response::stream::{Event, EventStream},
+    routes,
+    serde::json::Json,
+};
 use std::sync::Arc;
 
 /// Command line config, set with defaults that will work with
@@ -54,6 +61,7 @@ fn set_password(
         hex::decode(password.password.clone())
             .map_err(|err| format!("Failed decoding password hex: {}", err))?,
     );
+    req.set_passphrase(password.passphrase.clone());
     let _resp = state
         .mobilecoind_api_client
         .set_db_password(&req)
@@ -72,6 +80,7 @@ fn unlock_db(
         hex::decode(password.password.clone())
             .map_err(|err| format!("Failed decoding password hex: {}", err))?,
     );
+    req.set_passphrase(password.passphrase.clone());
     let _resp = state
         .mobilecoind_api_client
         .unlock_db(&req)
@@ -79,6 +88,18 @@ fn unlock_db(
     Ok(Json(JsonUnlockDbResponse { success: true }))
 }
 
+/// Get the encryption status of the mobilecoind-db
+#[get("/db-encryption-status")]
+fn db_encryption_status(
+    state: &rocket::State<State>,
+) -> Result<Json<JsonDbEncryptionStatusResponse>, String> {
+    let resp = state
+        .mobilecoind_api_client
+        .get_db_encryption_status(&api::Empty::new())
+        .map_err(|err| format!("Failed getting db encryption status: {}", err))?;
+    Ok(Json(JsonDbEncryptionStatusResponse::from(&resp)))
+}
+
 /// Gets current mobilecoind version
 #[get("/version")]
 fn version(state: &rocket::State<State>) -> Result<Json<JsonMobilecoindVersionResponse>, String> {
@@ -89,6 +110,12 @@ fn version(state: &rocket::State<State>) -> Result<Json<JsonMobilecoindVersionRe
     Ok(Json(JsonMobilecoindVersionResponse::from(&resp)))
 }
 
+/// OpenAPI description of this gateway
+#[get("/openapi.json")]
+fn openapi() -> Json<serde_json::Value> {
+    Json(openapi_document())
+}
+
 /// Requests a new root entropy from mobilecoind
 #[post("/entropy")]
 fn entropy(state: &rocket::State<State>) -> Result<Json<JsonRootEntropyResponse>, String> {
@@ -151,25 +178,21 @@ fn add_monitor(
     state: &rocket::State<State>,
     monitor: Json<JsonMonitorRequest>,
 ) -> Result<Json<JsonMonitorResponse>, String> {
-    let mut account_key = api::external::AccountKey::new();
-    let mut view_private_key = RistrettoPrivate::new();
-    view_private_key.set_data(
-        hex::decode(&monitor.account_key.view_private_key)
-            .map_err(|err| format!("Failed to decode hex key: {}", err))?,
-    );
-    let mut spend_private_key = RistrettoPrivate::new();
-    spend_private_key.set_data(
-        hex::decode(&monitor.account_key.spend_private_key)
-            .map_err(|err| format!("Failed to decode hex key: {}", err))?,
-    );
-    account_key.set_view_private_key(view_private_key);
-    account_key.set_spend_private_key(spend_private_key);
-
     let mut req = api::AddMonitorRequest::new();
-    req.set_account_key(account_key);
+    match (&monitor.account_key, &monitor.view_account_key) {
+        (Some(account_key), None) => {
+            req.set_account_key(api::external::AccountKey::try_from(account_key)?)
+        }
+        (None, Some(view_account_key)) => {
+            req.set_view_account_key(api::ViewAccountKey::try_from(view_account_key)?)
+        }
+        _ => return Err("Exactly one of account_key and view_account_key must be set".to_string()),
+    }
     req.set_first_subaddress(monitor.first_subaddress);
     req.set_num_subaddresses(monitor.num_subaddresses);
-    req.set_first_block(0);
+    req.set_gap_limit(monitor.gap_limit);
+    req.set_first_block(monitor.first_block);
+    req.set_name(monitor.name.clone());
 
     let monitor_response = state
         .mobilecoind_api_client
@@ -196,6 +219,143 @@ fn remove_monitor(state: &rocket::State<State>, monitor_hex: String) -> Result<(
     Ok(())
 }
 
+/// Sync a monitor again from a given block
+#[post("/monitors/<monitor_hex>/rescan", format = "json", data = "<rescan>")]
+fn rescan_monitor(
+    state: &rocket::State<State>,
+    monitor_hex: String,
+    rescan: Json<JsonRescanMonitorRequest>,
+) -> Result<(), String> {
+    let monitor_id =
+        hex::decode(monitor_hex).map_err(|err| format!("Failed to decode monitor hex: {}", err))?;
+
+    let mut req = api::RescanMonitorRequest::new();
+    req.set_monitor_id(monitor_id);
+    req.set_first_block(rescan.first_block);
+
+    let _resp = state
+        .mobilecoind_api_client
+        .rescan_monitor(&req)
+        .map_err(|err| format!("Failed rescanning monitor: {}", err))?;
+
+    Ok(())
+}
+
+/// Change the subaddress range and first block of a monitor
+#[put("/monitors/<monitor_hex>", format = "json", data = "<monitor>")]
+fn update_monitor(
+    state: &rocket::State<State>,
+    monitor_hex: String,
+    monitor: Json<JsonUpdateMonitorRequest>,
+) -> Result<(), String> {
+    let monitor_id =
+        hex::decode(monitor_hex).map_err(|err| format!("Failed to decode monitor hex: {}", err))?;
+
+    let mut req = api::UpdateMonitorRequest::new();
+    req.set_monitor_id(monitor_id);
+    req.set_first_subaddress(monitor.first_subaddress);
+    req.set_num_subaddresses(monitor.num_subaddresses);
+    req.set_first_block(monitor.first_block);
+
+    let _resp = state
+        .mobilecoind_api_client
+        .update_monitor(&req)
+        .map_err(|err| format!("Failed updating monitor: {}", err))?;
+
+    Ok(())
+}
+
+/// Mark outputs of a view-only monitor as spent, using key images computed
+/// offline by the holder of the spend key
+#[post(
+    "/monitors/<monitor_hex>/key-images",
+    format = "json",
+    data = "<request>"
+)]
+fn import_key_images(
+    state: &rocket::State<State>,
+    monitor_hex: String,
+    request: Json<JsonImportKeyImagesRequest>,
+) -> Result<Json<JsonImportKeyImagesResponse>, String> {
+    let monitor_id =
+        hex::decode(monitor_hex).map_err(|err| format!("Failed to decode monitor hex: {}", err))?;
+
+    let key_images = request
+        .key_image_list
+        .iter()
+        .map(api::TxOutKeyImage::try_from)
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let mut req = api::ImportKeyImagesRequest::new();
+    req.set_monitor_id(monitor_id);
+    req.set_key_image_list(RepeatedField::from_vec(key_images));
+
+    let resp = state
+        .mobilecoind_api_client
+        .import_key_images(&req)
+        .map_err(|err| format!("Failed importing key images: {}", err))?;
+
+    Ok(Json(JsonImportKeyImagesResponse::from(&resp)))
+}
+
+/// Register, replace or clear the webhook payment events are delivered to
+#[put("/monitors/<monitor_hex>/webhook", format = "json", data = "<webhook>")]
+fn set_monitor_webhook(
+    state: &rocket::State<State>,
+    monitor_hex: String,
+    webhook: Json<JsonSetMonitorWebhookRequest>,
+) -> Result<(), String> {
+    let monitor_id =
+        hex::decode(monitor_hex).map_err(|err| format!("Failed to decode monitor hex: {}", err))?;
+
+    let mut req = api::SetMonitorWebhookRequest::new();
+    req.set_monitor_id(monitor_id);
+    req.set_url(webhook.url.clone());
+    req.set_secret(
+        hex::decode(&webhook.secret)
+            .map_err(|err| format!("Failed to decode secret hex: {}", err))?,
+    );
+
+    let _resp = state
+        .mobilecoind_api_client
+        .set_monitor_webhook(&req)
+        .map_err(|err| format!("Failed setting monitor webhook: {}", err))?;
+
+    Ok(())
+}
+
+/// Stream monitor events as server-sent events, replaying from first_block
+#[get("/monitors/<monitor_hex>/events?<first_block>")]
+fn monitor_events(
+    state: &rocket::State<State>,
+    monitor_hex: String,
+    first_block: Option<u64>,
+) -> Result<EventStream![], String> {
+    let monitor_id =
+        hex::decode(monitor_hex).map_err(|err| format!("Failed to decode monitor hex: {}", err))?;
+
+    let mut req = api::SubscribeMonitorEventsRequest::new();
+    req.set_monitor_id(monitor_id);
+    req.set_first_block(first_block.unwrap_or_default());
+
+    let mut receiver = state
+        .mobilecoind_api_client
+        .subscribe_monitor_events(&req)
+        .map_err(|err| format!("Failed subscribing to monitor events: {}", err))?;
+
+    Ok(EventStream! {
+        while let Some(result) = receiver.next().await {
+            match result {
+                Ok(event) => yield Event::json(&JsonMonitorEvent::from(&event)),
+                Err(err) => {
+                    yield Event::data(err.to_string()).event("error");
+                    break;
+                }
+            }
+        }
+    })
+}
+
 /// Gets a list of existing monitors
 #[get("/monitors")]
 fn monitors(state: &rocket::State<State>) -> Result<Json<JsonMonitorListResponse>, String> {
@@ -226,33 +386,39 @@ fn monitor_status(
     Ok(Json(JsonMonitorStatusResponse::from(&resp)))
 }
 
-/// Balance check using a created monitor and subaddress index
-#[get("/monitors/<monitor_hex>/subaddresses/<subaddress_index>/balance")]
+/// Balance check using a created monitor and subaddress index. The token
+/// defaults to MOB.
+#[get("/monitors/<monitor_hex>/subaddresses/<subaddress_index>/balance?<token_id>")]
 fn balance(
     state: &rocket::State<State>,
     monitor_hex: String,
     subaddress_index: u64,
+    token_id: Option<u64>,
 ) -> Result<Json<JsonBalanceResponse>, String> {
     let monitor_id =
         hex::decode(monitor_hex).map_err(|err| format!("Failed to decode monitor hex: {}", err))?;
+    let token_id = token_id.unwrap_or_default();
 
     let mut req = api::GetBalanceRequest::new();
     req.set_monitor_id(monitor_id);
     req.set_subaddress_index(subaddress_index);
+    req.set_token_id(token_id);
 
     let resp = state
         .mobilecoind_api_client
         .get_balance(&req)
         .map_err(|err| format!("Failed getting balance: {}", err))?;
 
-    Ok(Json(JsonBalanceResponse::from(&resp)))
+    Ok(Json(JsonBalanceResponse::new(&resp, token_id)))
 }
 
-#[get("/monitors/<monitor_hex>/subaddresses/<subaddress_index>/utxos")]
+/// Unspent outputs of a subaddress in a given token, defaulting to MOB.
+#[get("/monitors/<monitor_hex>/subaddresses/<subaddress_index>/utxos?<token_id>")]
 fn utxos(
     state: &rocket::State<State>,
     monitor_hex: String,
     subaddress_index: u64,
+    token_id: Option<u64>,
 ) -> Result<Json<JsonUtxosResponse>, String> {
     let monitor_id =
         hex::decode(monitor_hex).map_err(|err| format!("Failed to decode monitor hex: {}", err))?;
@@ -260,6 +426,7 @@ fn utxos(
     let mut req = api::GetUnspentTxOutListRequest::new();
     req.set_monitor_id(monitor_id);
     req.set_subaddress_index(subaddress_index);
+    req.set_token_id(token_id.unwrap_or_default());
 
     let resp = state
         .mobilecoind_api_client
@@ -310,6 +477,12 @@ fn create_request_code(
     if let Some(memo) = request.memo.clone() {
         req.set_memo(memo);
     }
+    if let Some(token_id) = request.token_id {
+        req.set_token_id(u64::from(token_id));
+    }
+    if let Some(payment_id) = request.payment_id {
+        req.set_payment_id(u64::from(payment_id));
+    }
 
     let resp = state
         .mobilecoind_api_client
@@ -376,6 +549,50 @@ fn parse_address_code(
     Ok(Json(JsonParseAddressCodeResponse::from(&resp)))
 }
 
+/// Generates a transfer code for funds held by a one-time account
+#[post("/codes/transfer", format = "json", data = "<request>")]
+fn create_transfer_code(
+    state: &rocket::State<State>,
+    request: Json<JsonCreateTransferCodeRequest>,
+) -> Result<Json<JsonCreateTransferCodeResponse>, String> {
+    let mut tx_public_key = CompressedRistretto::new();
+    tx_public_key.set_data(
+        hex::decode(&request.tx_public_key)
+            .map_err(|err| format!("Failed to decode tx public key hex: {}", err))?,
+    );
+
+    let mut req = api::CreateTransferCodeRequest::new();
+    req.set_bip39_entropy(
+        hex::decode(&request.bip39_entropy)
+            .map_err(|err| format!("Failed to decode bip39 entropy hex: {}", err))?,
+    );
+    req.set_tx_public_key(tx_public_key);
+    req.set_memo(request.memo.clone());
+
+    let resp = state
+        .mobilecoind_api_client
+        .create_transfer_code(&req)
+        .map_err(|err| format!("Failed creating transfer code: {}", err))?;
+
+    Ok(Json(JsonCreateTransferCodeResponse::from(&resp)))
+}
+
+/// Retrieves the data in a transfer b58_code, including the output it holds
+#[get("/codes/transfer/<b58_code>")]
+fn parse_transfer_code(
+    state: &rocket::State<State>,
+    b58_code: String,
+) -> Result<Json<JsonParseTransferCodeResponse>, String> {
+    let mut req = api::ParseTransferCodeRequest::new();
+    req.set_b58_code(b58_code);
+    let resp = state
+        .mobilecoind_api_client
+        .parse_transfer_code(&req)
+        .map_err(|err| format!("Failed parsing transfer code: {}", err))?;
+
+    Ok(Json(JsonParseTransferCodeResponse::from(&resp)))
+}
+
 /// Performs a transfer from a monitor and subaddress. The public keys and
 /// amount are in the POST data.
 #[post(
@@ -416,6 +633,10 @@ fn build_and_submit(
         req.set_override_change_subaddress(true);
         req.set_change_subaddress(u64::from(subaddress))
     }
+    req.set_fee(transfer.fee.map(u64::from).unwrap_or_default());
+    req.set_tombstone(transfer.tombstone.unwrap_or_default());
+    req.set_token_id(transfer.request_data.token_id.into());
+    req.set_payment_request_id(transfer.request_data.payment_id.into());
 
     let resp = state
         .mobilecoind_api_client
@@ -463,6 +684,9 @@ fn pay_address_code(
         req.set_override_change_subaddress(true);
         req.set_change_subaddress(u64::from(subaddress))
     }
+    req.set_fee(transfer.fee.map(u64::from).unwrap_or_default());
+    req.set_tombstone(transfer.tombstone.unwrap_or_default());
+    req.set_token_id(transfer.token_id.into());
 
     let resp = state
         .mobilecoind_api_client
@@ -512,12 +736,448 @@ fn generate_request_code_transaction(
     req.set_change_subaddress(subaddress_index);
     req.set_outlay_list(RepeatedField::from_vec(vec![outlay]));
     req.set_input_list(RepeatedField::from_vec(inputs));
+    req.set_token_id(request.transfer.token_id.into());
+
+    let resp = state
+        .mobilecoind_api_client
+        .generate_tx(&req)
+        .map_err(|err| format!("Failed to generate tx: {}", err))?;
+
+    Ok(Json(JsonCreateTxProposalResponse::try_from(&resp)?))
+}
+
+/// Creates a transaction proposal paying any number of outlays in one token.
+/// For view-only monitors the unsigned proposal is returned instead, to be
+/// signed offline.
+#[post(
+    "/monitors/<monitor_hex>/subaddresses/<subaddress_index>/generate-tx",
+    format = "json",
+    data = "<request>"
+)]
+fn generate_tx(
+    state: &rocket::State<State>,
+    monitor_hex: String,
+    subaddress_index: u64,
+    request: Json<JsonGenerateTxRequest>,
+) -> Result<Json<JsonCreateTxProposalResponse>, String> {
+    let monitor_id =
+        hex::decode(monitor_hex).map_err(|err| format!("Failed to decode monitor hex: {}", err))?;
+
+    let outlays = request
+        .outlay_list
+        .iter()
+        .map(api::Outlay::try_from)
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let mut req = api::GenerateTxRequest::new();
+    req.set_sender_monitor_id(monitor_id);
+    req.set_change_subaddress(subaddress_index);
+    req.set_input_list(utxos_from_json(&request.input_list)?);
+    req.set_outlay_list(RepeatedField::from_vec(outlays));
+    req.set_fee(request.fee.map(u64::from).unwrap_or_default());
+    req.set_tombstone(request.tombstone.unwrap_or_default());
+    req.set_token_id(request.token_id.into());
 
     let resp = state
         .mobilecoind_api_client
         .generate_tx(&req)
         .map_err(|err| format!("Failed to generate tx: {}", err))?;
 
+    Ok(Json(JsonCreateTxProposalResponse::try_from(&resp)?))
+}
+
+/// Creates a transaction that merges the smallest outputs of a subaddress
+#[post(
+    "/monitors/<monitor_hex>/subaddresses/<subaddress_index>/generate-optimization-tx",
+    format = "json",
+    data = "<request>"
+)]
+fn generate_optimization_tx(
+    state: &rocket::State<State>,
+    monitor_hex: String,
+    subaddress_index: u64,
+    request: Json<JsonGenerateOptimizationTxRequest>,
+) -> Result<Json<JsonCreateTxProposalResponse>, String> {
+    let monitor_id =
+        hex::decode(monitor_hex).map_err(|err| format!("Failed to decode monitor hex: {}", err))?;
+
+    let mut req = api::GenerateOptimizationTxRequest::new();
+    req.set_monitor_id(monitor_id);
+    req.set_subaddress(subaddress_index);
+    req.set_fee(request.fee.map(u64::from).unwrap_or_default());
+    req.set_token_id(request.token_id.into());
+
+    let resp = state
+        .mobilecoind_api_client
+        .generate_optimization_tx(&req)
+        .map_err(|err| format!("Failed to generate optimization tx: {}", err))?;
+
+    Ok(Json(JsonCreateTxProposalResponse::from(&resp)))
+}
+
+/// Creates a transaction funding a new transfer code
+#[post(
+    "/monitors/<monitor_hex>/subaddresses/<subaddress_index>/generate-transfer-code-tx",
+    format = "json",
+    data = "<request>"
+)]
+fn generate_transfer_code_tx(
+    state: &rocket::State<State>,
+    monitor_hex: String,
+    subaddress_index: u64,
+    request: Json<JsonGenerateTransferCodeTxRequest>,
+) -> Result<Json<JsonGenerateTransferCodeTxResponse>, String> {
+    let monitor_id =
+        hex::decode(monitor_hex).map_err(|err| format!("Failed to decode monitor hex: {}", err))?;
+
+    let mut req = api::GenerateTransferCodeTxRequest::new();
+    req.set_sender_monitor_id(monitor_id);
+    req.set_change_subaddress(subaddress_index);
+    req.set_input_list(utxos_from_json(&request.input_list)?);
+    req.set_value(request.value.into());
+    req.set_fee(request.fee.map(u64::from).unwrap_or_default());
+    req.set_tombstone(request.tombstone.unwrap_or_default());
+    req.set_memo(request.memo.clone());
+    req.set_token_id(request.token_id.into());
+
+    let resp = state
+        .mobilecoind_api_client
+        .generate_transfer_code_tx(&req)
+        .map_err(|err| format!("Failed to generate transfer code tx: {}", err))?;
+
+    Ok(Json(JsonGenerateTransferCodeTxResponse::from(&resp)))
+}
+
+/// Creates a transaction sending the given outputs of an account that is not
+/// monitored to a single receiver, e.g. to sweep a transfer code
+#[post("/generate-tx-from-tx-out-list", format = "json", data = "<request>")]
+fn generate_tx_from_tx_out_list(
+    state: &rocket::State<State>,
+    request: Json<JsonGenerateTxFromTxOutListRequest>,
+) -> Result<Json<JsonCreateTxProposalResponse>, String> {
+    let req = api::GenerateTxFromTxOutListRequest::try_from(&request.0)?;
+
+    let resp = state
+        .mobilecoind_api_client
+        .generate_tx_from_tx_out_list(&req)
+        .map_err(|err| format!("Failed to generate tx: {}", err))?;
+
+    Ok(Json(JsonCreateTxProposalResponse::from(&resp)))
+}
+
+/// Creates a transaction burning funds, to be redeemed on another chain
+#[post(
+    "/monitors/<monitor_hex>/subaddresses/<subaddress_index>/generate-burn-redemption-tx",
+    format = "json",
+    data = "<request>"
+)]
+fn generate_burn_redemption_tx(
+    state: &rocket::State<State>,
+    monitor_hex: String,
+    subaddress_index: u64,
+    request: Json<JsonGenerateBurnRedemptionTxRequest>,
+) -> Result<Json<JsonCreateTxProposalResponse>, String> {
+    let monitor_id =
+        hex::decode(monitor_hex).map_err(|err| format!("Failed to decode monitor hex: {}", err))?;
+
+    let mut req = api::GenerateBurnRedemptionTxRequest::new();
+    req.set_sender_monitor_id(monitor_id);
+    req.set_change_subaddress(subaddress_index);
+    req.set_input_list(utxos_from_json(&request.input_list)?);
+    req.set_burn_amount(request.burn_amount.into());
+    req.set_fee(request.fee.map(u64::from).unwrap_or_default());
+    req.set_tombstone(request.tombstone.unwrap_or_default());
+    req.set_token_id(request.token_id.into());
+    req.set_redemption_memo(
+        hex::decode(&request.redemption_memo)
+            .map_err(|err| format!("Failed to decode redemption memo hex: {}", err))?,
+    );
+    req.set_enable_destination_memo(request.enable_destination_memo);
+
+    let resp = state
+        .mobilecoind_api_client
+        .generate_burn_redemption_tx(&req)
+        .map_err(|err| format!("Failed to generate burn redemption tx: {}", err))?;
+
+    Ok(Json(JsonCreateTxProposalResponse::from(&resp)))
+}
+
+/// Creates a transaction that replaces a pending one, e.g. with a higher fee
+#[post(
+    "/monitors/<monitor_hex>/subaddresses/<subaddress_index>/generate-replacement-tx",
+    format = "json",
+    data = "<request>"
+)]
+fn generate_replacement_tx(
+    state: &rocket::State<State>,
+    monitor_hex: String,
+    subaddress_index: u64,
+    request: Json<JsonGenerateReplacementTxRequest>,
+) -> Result<Json<JsonCreateTxProposalResponse>, String> {
+    let monitor_id =
+        hex::decode(monitor_hex).map_err(|err| format!("Failed to decode monitor hex: {}", err))?;
+
+    let mut req = api::GenerateReplacementTxRequest::new();
+    req.set_sender_monitor_id(monitor_id);
+    req.set_tx_proposal(
+        api::TxProposal::try_from(&request.tx_proposal)
+            .map_err(|err| format!("Failed to convert tx proposal: {}", err))?,
+    );
+    req.set_change_subaddress(subaddress_index);
+    req.set_fee(request.fee.map(u64::from).unwrap_or_default());
+    req.set_tombstone(request.tombstone.unwrap_or_default());
+
+    let resp = state
+        .mobilecoind_api_client
+        .generate_replacement_tx(&req)
+        .map_err(|err| format!("Failed to generate replacement tx: {}", err))?;
+
+    Ok(Json(JsonCreateTxProposalResponse::from(&resp)))
+}
+
+/// Offers one output in exchange for an amount of another token, as a signed
+/// contingent input
+#[post(
+    "/monitors/<monitor_hex>/subaddresses/<subaddress_index>/generate-swap",
+    format = "json",
+    data = "<request>"
+)]
+fn generate_swap(
+    state: &rocket::State<State>,
+    monitor_hex: String,
+    subaddress_index: u64,
+    request: Json<JsonGenerateSwapRequest>,
+) -> Result<Json<JsonGenerateSwapResponse>, String> {
+    let monitor_id =
+        hex::decode(monitor_hex).map_err(|err| format!("Failed to decode monitor hex: {}", err))?;
+
+    let mut req = api::GenerateSwapRequest::new();
+    req.set_sender_monitor_id(monitor_id);
+    req.set_change_subaddress(subaddress_index);
+    req.set_utxo(
+        api::UnspentTxOut::try_from(&request.utxo)
+            .map_err(|err| format!("Failed to convert utxo: {}", err))?,
+    );
+    req.set_base_value(request.base_value.into());
+    req.set_counter_token_id(request.counter_token_id.into());
+    req.set_counter_value(request.counter_value.into());
+    req.set_allow_partial_fill(request.allow_partial_fill);
+    req.set_min_fill_value(request.min_fill_value.into());
+    req.set_tombstone(request.tombstone.unwrap_or_default());
+
+    let resp = state
+        .mobilecoind_api_client
+        .generate_swap(&req)
+        .map_err(|err| format!("Failed to generate swap: {}", err))?;
+
+    Ok(Json(JsonGenerateSwapResponse::try_from(&resp)?))
+}
+
+/// Quotes a list of swap offers and picks the fills for a given base value
+#[post("/swaps/fills", format = "json", data = "<request>")]
+fn get_swap_fills(
+    state: &rocket::State<State>,
+    request: Json<JsonGetSwapFillsRequest>,
+) -> Result<Json<JsonGetSwapFillsResponse>, String> {
+    let sci_list = request
+        .sci_list
+        .iter()
+        .map(|sci| decode_proto(sci))
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let mut req = api::GetSwapFillsRequest::new();
+    req.set_sci_list(RepeatedField::from_vec(sci_list));
+    req.set_base_token_id(request.base_token_id.into());
+    req.set_counter_token_id(request.counter_token_id.into());
+    req.set_base_value(request.base_value.into());
+
+    let resp = state
+        .mobilecoind_api_client
+        .get_swap_fills(&req)
+        .map_err(|err| format!("Failed getting swap fills: {}", err))?;
+
+    Ok(Json(JsonGetSwapFillsResponse::from(&resp)))
+}
+
+/// Creates a transaction filling one or more swap offers
+#[post(
+    "/monitors/<monitor_hex>/subaddresses/<subaddress_index>/generate-swap-fill-tx",
+    format = "json",
+    data = "<request>"
+)]
+fn generate_swap_fill_tx(
+    state: &rocket::State<State>,
+    monitor_hex: String,
+    subaddress_index: u64,
+    request: Json<JsonGenerateSwapFillTxRequest>,
+) -> Result<Json<JsonCreateTxProposalResponse>, String> {
+    let monitor_id =
+        hex::decode(monitor_hex).map_err(|err| format!("Failed to decode monitor hex: {}", err))?;
+
+    let sci_list = request
+        .sci_list
+        .iter()
+        .map(|sci| decode_proto(sci))
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let mut req = api::GenerateSwapFillTxRequest::new();
+    req.set_sender_monitor_id(monitor_id);
+    req.set_change_subaddress(subaddress_index);
+    req.set_sci_list(RepeatedField::from_vec(sci_list));
+    req.set_base_value_list(request.base_value_list.iter().map(u64::from).collect());
+    req.set_input_list(utxos_from_json(&request.input_list)?);
+    req.set_fee(request.fee.map(u64::from).unwrap_or_default());
+    req.set_tombstone(request.tombstone.unwrap_or_default());
+
+    let resp = state
+        .mobilecoind_api_client
+        .generate_swap_fill_tx(&req)
+        .map_err(|err| format!("Failed to generate swap fill tx: {}", err))?;
+
+    Ok(Json(JsonCreateTxProposalResponse::from(&resp)))
+}
+
+/// Creates a transaction funding a gift code. Once it lands, the returned
+/// tx_public_key is used to create the code itself
+#[post(
+    "/monitors/<monitor_hex>/subaddresses/<subaddress_index>/generate-gift-code-funding-tx",
+    format = "json",
+    data = "<request>"
+)]
+fn generate_gift_code_funding_tx(
+    state: &rocket::State<State>,
+    monitor_hex: String,
+    subaddress_index: u64,
+    request: Json<JsonGenerateGiftCodeFundingTxRequest>,
+) -> Result<Json<JsonGenerateGiftCodeFundingTxResponse>, String> {
+    let monitor_id =
+        hex::decode(monitor_hex).map_err(|err| format!("Failed to decode monitor hex: {}", err))?;
+
+    let mut req = api::GenerateGiftCodeFundingTxRequest::new();
+    req.set_sender_monitor_id(monitor_id);
+    req.set_change_subaddress(subaddress_index);
+    req.set_input_list(utxos_from_json(&request.input_list)?);
+    req.set_value(request.value.into());
+    req.set_token_id(request.token_id.into());
+    req.set_fee(request.fee.map(u64::from).unwrap_or_default());
+    req.set_tombstone(request.tombstone.unwrap_or_default());
+    req.set_memo(request.memo.clone());
+
+    let resp = state
+        .mobilecoind_api_client
+        .generate_gift_code_funding_tx(&req)
+        .map_err(|err| format!("Failed to generate gift code funding tx: {}", err))?;
+
+    Ok(Json(JsonGenerateGiftCodeFundingTxResponse::from(&resp)))
+}
+
+/// Creates a gift code from a landed funding transaction
+#[post(
+    "/monitors/<monitor_hex>/gift-codes",
+    format = "json",
+    data = "<request>"
+)]
+fn create_gift_code(
+    state: &rocket::State<State>,
+    monitor_hex: String,
+    request: Json<JsonCreateGiftCodeRequest>,
+) -> Result<Json<JsonGiftCodeResponse>, String> {
+    let monitor_id =
+        hex::decode(monitor_hex).map_err(|err| format!("Failed to decode monitor hex: {}", err))?;
+
+    let mut tx_public_key = CompressedRistretto::new();
+    tx_public_key.set_data(
+        hex::decode(&request.tx_public_key)
+            .map_err(|err| format!("Failed to decode tx public key hex: {}", err))?,
+    );
+
+    let mut req = api::CreateGiftCodeRequest::new();
+    req.set_monitor_id(monitor_id);
+    req.set_tx_public_key(tx_public_key);
+
+    let resp = state
+        .mobilecoind_api_client
+        .create_gift_code(&req)
+        .map_err(|err| format!("Failed creating gift code: {}", err))?;
+
+    Ok(Json(JsonGiftCodeResponse::from(&resp)))
+}
+
+/// Checks whether a gift code is still claimable
+#[get("/gift-codes/<b58_code>")]
+fn gift_code_status(
+    state: &rocket::State<State>,
+    b58_code: String,
+) -> Result<Json<JsonGiftCodeResponse>, String> {
+    let mut req = api::GetGiftCodeStatusRequest::new();
+    req.set_b58_code(b58_code.clone());
+
+    let resp = state
+        .mobilecoind_api_client
+        .get_gift_code_status(&req)
+        .map_err(|err| format!("Failed getting gift code status: {}", err))?;
+
+    Ok(Json(JsonGiftCodeResponse::new(&resp, &b58_code)))
+}
+
+/// Creates a transaction claiming a gift code into a subaddress
+#[post(
+    "/monitors/<monitor_hex>/subaddresses/<subaddress_index>/generate-gift-code-claim-tx",
+    format = "json",
+    data = "<request>"
+)]
+fn generate_gift_code_claim_tx(
+    state: &rocket::State<State>,
+    monitor_hex: String,
+    subaddress_index: u64,
+    request: Json<JsonGenerateGiftCodeClaimTxRequest>,
+) -> Result<Json<JsonCreateTxProposalResponse>, String> {
+    let monitor_id =
+        hex::decode(monitor_hex).map_err(|err| format!("Failed to decode monitor hex: {}", err))?;
+
+    let mut req = api::GenerateGiftCodeClaimTxRequest::new();
+    req.set_b58_code(request.b58_code.clone());
+    req.set_monitor_id(monitor_id);
+    req.set_subaddress(subaddress_index);
+    req.set_fee(request.fee.map(u64::from).unwrap_or_default());
+    req.set_tombstone(request.tombstone.unwrap_or_default());
+    req.set_memo(request.memo.clone());
+
+    let resp = state
+        .mobilecoind_api_client
+        .generate_gift_code_claim_tx(&req)
+        .map_err(|err| format!("Failed to generate gift code claim tx: {}", err))?;
+
+    Ok(Json(JsonCreateTxProposalResponse::from(&resp)))
+}
+
+/// Creates a transaction returning an unclaimed gift code to its funder
+#[post(
+    "/monitors/<monitor_hex>/subaddresses/<subaddress_index>/generate-gift-code-cancellation-tx",
+    format = "json",
+    data = "<request>"
+)]
+fn generate_gift_code_cancellation_tx(
+    state: &rocket::State<State>,
+    monitor_hex: String,
+    subaddress_index: u64,
+    request: Json<JsonGenerateGiftCodeCancellationTxRequest>,
+) -> Result<Json<JsonCreateTxProposalResponse>, String> {
+    let monitor_id =
+        hex::decode(monitor_hex).map_err(|err| format!("Failed to decode monitor hex: {}", err))?;
+
+    let mut req = api::GenerateGiftCodeCancellationTxRequest::new();
+    req.set_b58_code(request.b58_code.clone());
+    req.set_sender_monitor_id(monitor_id);
+    req.set_change_subaddress(subaddress_index);
+    req.set_fee(request.fee.map(u64::from).unwrap_or_default());
+    req.set_tombstone(request.tombstone.unwrap_or_default());
+
+    let resp = state
+        .mobilecoind_api_client
+        .generate_gift_code_cancellation_tx(&req)
+        .map_err(|err| format!("Failed to generate gift code cancellation tx: {}", err))?;
+
     Ok(Json(JsonCreateTxProposalResponse::from(&resp)))
 }
 
@@ -580,19 +1240,8 @@ fn check_receiver_transfer_status(
     let monitor_id =
         hex::decode(monitor_hex).map_err(|err| format!("Failed to decode monitor hex: {}", err))?;
 
-    let mut receiver_receipt = api::ReceiverTxReceipt::new();
-    let mut tx_public_key = CompressedRistretto::new();
-    tx_public_key.set_data(hex::decode(&receipt.tx_public_key).map_err(|err| format!("{}", err))?);
-    receiver_receipt.set_tx_public_key(tx_public_key);
-    receiver_receipt
-        .set_tx_out_hash(hex::decode(&receipt.tx_out_hash).map_err(|err| format!("{}", err))?);
-    receiver_receipt.set_tombstone(receipt.tombstone);
-    receiver_receipt.set_confirmation_number(
-        hex::decode(&receipt.confirmation_number).map_err(|err| format!("{}", err))?,
-    );
-
     let mut req = api::GetTxStatusAsReceiverRequest::new();
-    req.set_receipt(receiver_receipt);
+    req.set_receipt(api::ReceiverTxReceipt::try_from(&receipt.0)?);
     req.set_monitor_id(monitor_id);
 
     let resp = state
@@ -603,6 +1252,24 @@ fn check_receiver_transfer_status(
     Ok(Json(JsonStatusResponse::from(&resp)))
 }
 
+/// Checks whether the output described by a receiver receipt has landed,
+/// without validating the confirmation number
+#[post("/tx/status-as-receiver", format = "json", data = "<receipt>")]
+fn check_receipt_status(
+    state: &rocket::State<State>,
+    receipt: Json<JsonReceiverTxReceipt>,
+) -> Result<Json<JsonStatusResponse>, String> {
+    let mut req = api::GetTxStatusAsReceiverRequest::new();
+    req.set_receipt(api::ReceiverTxReceipt::try_from(&receipt.0)?);
+
+    let resp = state
+        .mobilecoind_api_client
+        .get_tx_status_as_receiver(&req)
+        .map_err(|err| format!("Failed getting status: {}", err))?;
+
+    Ok(Json(JsonStatusResponse::from(&resp)))
+}
+
 /// Gets information about the entire ledger
 #[get("/ledger/local")]
 fn ledger_info(state: &rocket::State<State>) -> Result<Json<JsonLedgerInfoResponse>, String> {
@@ -614,6 +1281,18 @@ fn ledger_info(state: &rocket::State<State>) -> Result<Json<JsonLedgerInfoRespon
     Ok(Json(JsonLedgerInfoResponse::from(&resp)))
 }
 
+/// Gets the sync state of the local ledger relative to the network, and the
+/// current minimum fee of each token
+#[get("/network-status")]
+fn network_status(state: &rocket::State<State>) -> Result<Json<JsonNetworkStatusResponse>, String> {
+    let resp = state
+        .mobilecoind_api_client
+        .get_network_status(&api::Empty::new())
+        .map_err(|err| format!("Failed getting network status: {}", err))?;
+
+    Ok(Json(JsonNetworkStatusResponse::from(&resp)))
+}
+
 /// Retrieves the data in a request code
 #[get("/ledger/blocks/<block_num>/header")]
 fn block_info(
@@ -669,6 +1348,105 @@ fn processed_block(
     Ok(Json(JsonProcessedBlockResponse::from(&resp)))
 }
 
+/// Retrieves the history of transactions sent by a monitor, most recent first.
+#[get("/monitors/<monitor_hex>/sent-txs?<offset>&<limit>")]
+fn sent_txs(
+    state: &rocket::State<State>,
+    monitor_hex: String,
+    offset: Option<u64>,
+    limit: Option<u64>,
+) -> Result<Json<JsonSentTxListResponse>, String> {
+    let monitor_id =
+        hex::decode(monitor_hex).map_err(|err| format!("Failed to decode monitor hex: {}", err))?;
+
+    let mut req = api::GetSentTxListRequest::new();
+    req.set_monitor_id(monitor_id);
+    req.set_offset(offset.unwrap_or_default());
+    req.set_limit(limit.unwrap_or_default());
+
+    let resp = state
+        .mobilecoind_api_client
+        .get_sent_tx_list(&req)
+        .map_err(|err| format!("Failed getting sent txs: {}", err))?;
+
+    Ok(Json(JsonSentTxListResponse::from(&resp)))
+}
+
+/// Issues a payment request for a subaddress of a monitor, whose payments are
+/// tracked by mobilecoind.
+#[post(
+    "/monitors/<monitor_hex>/subaddresses/<subaddress_index>/payment-requests",
+    format = "json",
+    data = "<request>"
+)]
+fn create_payment_request(
+    state: &rocket::State<State>,
+    monitor_hex: String,
+    subaddress_index: u64,
+    request: Json<JsonCreatePaymentRequestRequest>,
+) -> Result<Json<JsonPaymentRequest>, String> {
+    let monitor_id =
+        hex::decode(monitor_hex).map_err(|err| format!("Failed to decode monitor hex: {}", err))?;
+
+    let mut req = api::CreatePaymentRequestRequest::new();
+    req.set_monitor_id(monitor_id);
+    req.set_subaddress_index(subaddress_index);
+    req.set_value(request.value.into());
+    req.set_token_id(request.token_id.map(u64::from).unwrap_or_default());
+    req.set_memo(request.memo.clone().unwrap_or_default());
+    req.set_expiry_block(request.expiry_block.map(u64::from).unwrap_or_default());
+
+    let resp = state
+        .mobilecoind_api_client
+        .create_payment_request(&req)
+        .map_err(|err| format!("Failed creating payment request: {}", err))?;
+
+    Ok(Json(JsonPaymentRequest::from(resp.get_payment_request())))
+}
+
+/// Retrieves the payment requests issued by a monitor, ordered by id.
+#[get("/monitors/<monitor_hex>/payment-requests")]
+fn payment_requests(
+    state: &rocket::State<State>,
+    monitor_hex: String,
+) -> Result<Json<JsonPaymentRequestListResponse>, String> {
+    let monitor_id =
+        hex::decode(monitor_hex).map_err(|err| format!("Failed to decode monitor hex: {}", err))?;
+
+    let mut req = api::GetPaymentRequestListRequest::new();
+    req.set_monitor_id(monitor_id);
+
+    let resp = state
+        .mobilecoind_api_client
+        .get_payment_request_list(&req)
+        .map_err(|err| format!("Failed getting payment requests: {}", err))?;
+
+    Ok(Json(JsonPaymentRequestListResponse::from(&resp)))
+}
+
+/// Retrieves a payment request issued by a monitor, and the payments received
+/// for it.
+#[get("/monitors/<monitor_hex>/payment-requests/<payment_request_id>")]
+fn payment_request(
+    state: &rocket::State<State>,
+    monitor_hex: String,
+    payment_request_id: u64,
+) -> Result<Json<JsonPaymentRequest>, String> {
+    let monitor_id =
+        hex::decode(monitor_hex).map_err(|err| format!("Failed to decode monitor hex: {}", err))?;
+
+    let mut req = api::GetPaymentRequestRequest::new();
+    req.set_monitor_id(monitor_id);
+    req.set_payment_request_id(payment_request_id);
+
+    let resp = state
+        .mobilecoind_api_client
+        .get_payment_request(&req)
+        .map_err(|err| format!("Failed getting payment request: {}", err))?;
+
+    Ok(Json(JsonPaymentRequest::from(resp.get_payment_request())))
+}
+
 /// Get the block index of a given tx out, identified by its public key.
 #[get("/tx-out/<public_key_hex>/block-index")]
 fn tx_out_get_block_index_by_public_key(
@@ -776,6 +1554,72 @@ fn get_mixins(
     Ok(Json(response))
 }
 
+/// Every route served by mobilecoind-json.
+fn routes() -> Vec<rocket::Route> {
+    routes![
+        set_password,
+        unlock_db,
+        db_encryption_status,
+        version,
+        openapi,
+        entropy,
+        account_key_from_root_entropy,
+        mnemonic,
+        account_key_from_mnemonic,
+        add_monitor,
+        remove_monitor,
+        rescan_monitor,
+        update_monitor,
+        import_key_images,
+        set_monitor_webhook,
+        monitor_events,
+        monitors,
+        monitor_status,
+        balance,
+        utxos,
+        public_address,
+        create_request_code,
+        parse_request_code,
+        create_address_code,
+        parse_address_code,
+        create_transfer_code,
+        parse_transfer_code,
+        build_and_submit,
+        pay_address_code,
+        generate_request_code_transaction,
+        generate_tx,
+        generate_optimization_tx,
+        generate_transfer_code_tx,
+        generate_tx_from_tx_out_list,
+        generate_burn_redemption_tx,
+        generate_replacement_tx,
+        generate_swap,
+        get_swap_fills,
+        generate_swap_fill_tx,
+        generate_gift_code_funding_tx,
+        create_gift_code,
+        gift_code_status,
+        generate_gift_code_claim_tx,
+        generate_gift_code_cancellation_tx,
+        submit_tx,
+        check_transfer_status,
+        check_receiver_transfer_status,
+        check_receipt_status,
+        ledger_info,
+        network_status,
+        block_info,
+        block_details,
+        processed_block,
+        sent_txs,
+        create_payment_request,
+        payment_requests,
+        payment_request,
+        tx_out_get_block_index_by_public_key,
+        get_mixins,
+        get_proof_of_membership,
+    ]
+}
+
 #[rocket::main]
 async fn main() -> Result<(), rocket::Error> {
     let _sentry_guard = mc_common::sentry::init();
@@ -806,42 +1650,7 @@ async fn main() -> Result<(), rocket::Error> {
         .merge(("address", config.listen_host.clone()));
 
     let _rocket = rocket::custom(figment)
-        .mount(
-            "/",
-            routes![
-                set_password,
-                unlock_db,
-                version,
-                entropy,
-                account_key_from_root_entropy,
-                mnemonic,
-                account_key_from_mnemonic,
-                add_monitor,
-                remove_monitor,
-                monitors,
-                monitor_status,
-                balance,
-                utxos,
-                public_address,
-                create_request_code,
-                parse_request_code,
-                create_address_code,
-                parse_address_code,
-                build_and_submit,
-                pay_address_code,
-                generate_request_code_transaction,
-                submit_tx,
-                check_transfer_status,
-                check_receiver_transfer_status,
-                ledger_info,
-                block_info,
-                block_details,
-                processed_block,
-                tx_out_get_block_index_by_public_key,
-                get_mixins,
-                get_proof_of_membership,
-            ],
-        )
+        .mount("/", routes())
         .manage(State {
             mobilecoind_api_client,
         })
@@ -849,3 +1658,37 @@ async fn main() -> Result<(), rocket::Error> {
         .await?;
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use mc_mobilecoind_json::openapi::documented_routes;
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn test_openapi_document_matches_mounted_routes() {
+        // The OpenAPI document doesn't describe the route serving it.
+        let mounted = routes()
+            .into_iter()
+            .map(|route| (route.method.as_str().to_lowercase(), route.uri.to_string()))
+            .filter(|(_, path)| path != "/openapi.json")
+            .collect::<BTreeSet<_>>();
+        let documented = documented_routes()
+            .into_iter()
+            .map(|(method, path)| (method.to_string(), path))
+            .collect::<BTreeSet<_>>();
+
+        let undocumented = mounted.difference(&documented).collect::<Vec<_>>();
+        assert!(
+            undocumented.is_empty(),
+            "undocumented routes: {:?}",
+            undocumented
+        );
+        let unmounted = documented.difference(&mounted).collect::<Vec<_>>();
+        assert!(
+            unmounted.is_empty(),
+            "documented routes not mounted: {:?}",
+            unmounted
+        );
+    }
+}
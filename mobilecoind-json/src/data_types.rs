@@ -4,35 +4,88 @@
 
 use mc_api::external::{
     CompressedRistretto, EncryptedFogHint, EncryptedMemo, InputRules, KeyImage, MaskedAmount,
-    PublicAddress, RingMLSAG, SignatureRctBulletproofs, Tx, TxIn, TxOutMembershipElement,
-    TxOutMembershipHash, TxOutMembershipProof, TxPrefix,
+    PublicAddress, RingMLSAG, RistrettoPrivate, SignatureRctBulletproofs, Tx, TxIn,
+    TxOutMembershipElement, TxOutMembershipHash, TxOutMembershipProof, TxPrefix,
 };
 use mc_mobilecoind_api as api;
 use mc_util_serial::JsonU64;
-use protobuf::RepeatedField;
+use protobuf::{Message, RepeatedField};
+use schemars::JsonSchema;
 use serde_derive::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
-#[derive(Deserialize, Default, Debug)]
+#[derive(Deserialize, Default, Debug, JsonSchema)]
 pub struct JsonPasswordRequest {
+    #[serde(default)]
     pub password: String,
+    #[serde(default)]
+    pub passphrase: String,
 }
 
-#[derive(Serialize, Default, Debug)]
+#[derive(Serialize, Default, Debug, JsonSchema)]
 pub struct JsonPasswordResponse {
     pub success: bool,
 }
 
-#[derive(Deserialize, Default, Debug)]
+#[derive(Deserialize, Default, Debug, JsonSchema)]
 pub struct JsonUnlockDbRequest {
+    #[serde(default)]
     pub password: String,
+    #[serde(default)]
+    pub passphrase: String,
 }
 
-#[derive(Serialize, Default, Debug)]
+#[derive(Serialize, Default, Debug, JsonSchema)]
 pub struct JsonUnlockDbResponse {
     pub success: bool,
 }
 
-#[derive(Serialize, Default, Debug)]
+#[derive(Serialize, Default, Debug, JsonSchema)]
+pub struct JsonDbEncryptionAudit {
+    pub database: String,
+    pub num_records: String,
+    pub num_unreadable: String,
+}
+
+impl From<&api::DbEncryptionAudit> for JsonDbEncryptionAudit {
+    fn from(src: &api::DbEncryptionAudit) -> Self {
+        Self {
+            database: src.get_database().to_owned(),
+            num_records: src.num_records.to_string(),
+            num_unreadable: src.num_unreadable.to_string(),
+        }
+    }
+}
+
+#[derive(Serialize, Default, Debug, JsonSchema)]
+pub struct JsonDbEncryptionStatusResponse {
+    pub is_encrypted: bool,
+    pub is_unlocked: bool,
+    pub key_derivation: String,
+    pub audit: Vec<JsonDbEncryptionAudit>,
+}
+
+impl From<&api::GetDbEncryptionStatusResponse> for JsonDbEncryptionStatusResponse {
+    fn from(src: &api::GetDbEncryptionStatusResponse) -> Self {
+        let key_derivation_str = match src.key_derivation {
+            api::DbKeyDerivation::DbKeyRaw => "raw",
+            api::DbKeyDerivation::DbKeyArgon2id => "argon2id",
+        };
+
+        Self {
+            is_encrypted: src.is_encrypted,
+            is_unlocked: src.is_unlocked,
+            key_derivation: key_derivation_str.to_owned(),
+            audit: src
+                .get_audit_list()
+                .iter()
+                .map(JsonDbEncryptionAudit::from)
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize, Default, Debug, JsonSchema)]
 pub struct JsonRootEntropyResponse {
     pub entropy: String,
 }
@@ -45,7 +98,7 @@ impl From<&api::GenerateRootEntropyResponse> for JsonRootEntropyResponse {
     }
 }
 
-#[derive(Deserialize, Serialize, Default, Debug)]
+#[derive(Deserialize, Serialize, Default, Debug, JsonSchema)]
 pub struct JsonMnemonicResponse {
     pub mnemonic: String,
 }
@@ -58,7 +111,7 @@ impl From<&api::GenerateMnemonicResponse> for JsonMnemonicResponse {
     }
 }
 
-#[derive(Deserialize, Serialize, Default, Debug)]
+#[derive(Deserialize, Serialize, Default, Debug, JsonSchema)]
 pub struct JsonAccountKeyResponse {
     pub view_private_key: String,
     pub spend_private_key: String,
@@ -75,14 +128,45 @@ impl From<&api::GetAccountKeyResponse> for JsonAccountKeyResponse {
     }
 }
 
-#[derive(Deserialize, Default, Debug)]
+#[derive(Deserialize, Default, Debug, JsonSchema)]
 pub struct JsonMonitorRequest {
-    pub account_key: JsonAccountKeyResponse,
+    /// Full account key. Exactly one of `account_key` and `view_account_key`
+    /// must be provided.
+    pub account_key: Option<JsonAccountKeyResponse>,
+    /// View-only account key, for monitors that cannot spend.
+    pub view_account_key: Option<JsonViewAccountKey>,
+    pub first_subaddress: u64,
+    pub num_subaddresses: u64,
+    #[serde(default)]
+    pub first_block: u64,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub gap_limit: u64,
+}
+
+#[derive(Deserialize, Serialize, Default, Debug, JsonSchema)]
+pub struct JsonViewAccountKey {
+    /// Hex encoded ristretto private key bytes
+    pub view_private_key: String,
+
+    /// Hex encoded compressed ristretto bytes
+    pub spend_public_key: String,
+}
+
+#[derive(Deserialize, Default, Debug, JsonSchema)]
+pub struct JsonRescanMonitorRequest {
+    pub first_block: u64,
+}
+
+#[derive(Deserialize, Default, Debug, JsonSchema)]
+pub struct JsonUpdateMonitorRequest {
     pub first_subaddress: u64,
     pub num_subaddresses: u64,
+    pub first_block: u64,
 }
 
-#[derive(Serialize, Default, Debug)]
+#[derive(Serialize, Default, Debug, JsonSchema)]
 pub struct JsonMonitorResponse {
     pub monitor_id: String,
     pub is_new: bool,
@@ -97,7 +181,7 @@ impl From<&api::AddMonitorResponse> for JsonMonitorResponse {
     }
 }
 
-#[derive(Serialize, Default, Debug)]
+#[derive(Serialize, Default, Debug, JsonSchema)]
 pub struct JsonMonitorListResponse {
     pub monitor_ids: Vec<String>,
 }
@@ -110,12 +194,16 @@ impl From<&api::GetMonitorListResponse> for JsonMonitorListResponse {
     }
 }
 
-#[derive(Serialize, Default, Debug)]
+#[derive(Serialize, Default, Debug, JsonSchema)]
 pub struct JsonMonitorStatusResponse {
     pub first_subaddress: u64,
     pub num_subaddresses: u64,
     pub first_block: u64,
     pub next_block: u64,
+    pub gap_limit: u64,
+    pub is_rescanning: bool,
+    pub rescan_end_block: u64,
+    pub fog: bool,
 }
 
 impl From<&api::GetMonitorStatusResponse> for JsonMonitorStatusResponse {
@@ -127,32 +215,45 @@ impl From<&api::GetMonitorStatusResponse> for JsonMonitorStatusResponse {
             num_subaddresses: status.get_num_subaddresses(),
             first_block: status.get_first_block(),
             next_block: status.get_next_block(),
+            gap_limit: status.get_gap_limit(),
+            is_rescanning: status.get_is_rescanning(),
+            rescan_end_block: status.get_rescan_end_block(),
+            fog: status.get_fog(),
         }
     }
 }
 
-#[derive(Serialize, Default, Debug)]
+#[derive(Serialize, Default, Debug, JsonSchema)]
 pub struct JsonBalanceResponse {
     pub balance: String,
+    #[schemars(with = "String")]
+    pub token_id: JsonU64,
 }
 
-impl From<&api::GetBalanceResponse> for JsonBalanceResponse {
-    fn from(src: &api::GetBalanceResponse) -> Self {
+impl JsonBalanceResponse {
+    /// The balance response does not echo the token id, so it is taken from
+    /// the request.
+    pub fn new(src: &api::GetBalanceResponse, token_id: u64) -> Self {
         Self {
             balance: src.balance.to_string(),
+            token_id: JsonU64(token_id),
         }
     }
 }
 
-#[derive(Serialize, Deserialize, Default, Debug)]
+#[derive(Serialize, Deserialize, Default, Debug, JsonSchema)]
 pub struct JsonUnspentTxOut {
     pub tx_out: JsonTxOut,
     pub subaddress_index: u64,
     pub key_image: String,
+    #[schemars(with = "String")]
     pub value: JsonU64,
     pub attempted_spend_height: u64,
     pub attempted_spend_tombstone: u64,
     pub monitor_id: String,
+    #[serde(default)]
+    #[schemars(with = "String")]
+    pub token_id: JsonU64,
 }
 
 impl From<&api::UnspentTxOut> for JsonUnspentTxOut {
@@ -165,6 +266,7 @@ impl From<&api::UnspentTxOut> for JsonUnspentTxOut {
             attempted_spend_height: src.get_attempted_spend_height(),
             attempted_spend_tombstone: src.get_attempted_spend_tombstone(),
             monitor_id: hex::encode(&src.get_monitor_id()),
+            token_id: JsonU64(src.get_token_id()),
         }
     }
 }
@@ -195,12 +297,13 @@ impl TryFrom<&JsonUnspentTxOut> for api::UnspentTxOut {
             hex::decode(&src.monitor_id)
                 .map_err(|err| format!("Failed to decode monitor id hex: {}", err))?,
         );
+        utxo.set_token_id(src.token_id.into());
 
         Ok(utxo)
     }
 }
 
-#[derive(Serialize, Default, Debug)]
+#[derive(Serialize, Default, Debug, JsonSchema)]
 pub struct JsonUtxosResponse {
     pub output_list: Vec<JsonUnspentTxOut>,
 }
@@ -217,14 +320,19 @@ impl From<&api::GetUnspentTxOutListResponse> for JsonUtxosResponse {
     }
 }
 
-#[derive(Deserialize, Default, Debug)]
+#[derive(Deserialize, Default, Debug, JsonSchema)]
 pub struct JsonCreateRequestCodeRequest {
     pub receiver: JsonPublicAddress,
+    #[schemars(with = "Option<String>")]
     pub value: Option<JsonU64>,
     pub memo: Option<String>,
+    #[schemars(with = "Option<String>")]
+    pub token_id: Option<JsonU64>,
+    #[schemars(with = "Option<String>")]
+    pub payment_id: Option<JsonU64>,
 }
 
-#[derive(Serialize, Default, Debug)]
+#[derive(Serialize, Default, Debug, JsonSchema)]
 pub struct JsonCreateRequestCodeResponse {
     pub b58_request_code: String,
 }
@@ -237,7 +345,7 @@ impl From<&api::CreateRequestCodeResponse> for JsonCreateRequestCodeResponse {
     }
 }
 
-#[derive(Deserialize, Serialize, Default, Debug, Clone)]
+#[derive(Deserialize, Serialize, Default, Debug, Clone, JsonSchema)]
 pub struct JsonPublicAddress {
     /// Hex encoded compressed ristretto bytes
     pub view_public_key: String,
@@ -267,7 +375,7 @@ impl From<&PublicAddress> for JsonPublicAddress {
     }
 }
 
-#[derive(Deserialize, Serialize, Default, Debug)]
+#[derive(Deserialize, Serialize, Default, Debug, JsonSchema)]
 pub struct JsonPublicAddressResponse {
     /// Hex encoded compressed ristretto bytes
     pub view_public_key: String,
@@ -334,11 +442,18 @@ impl TryFrom<&JsonPublicAddress> for PublicAddress {
     }
 }
 
-#[derive(Deserialize, Serialize, Default, Debug)]
+#[derive(Deserialize, Serialize, Default, Debug, JsonSchema)]
 pub struct JsonParseRequestCodeResponse {
     pub receiver: JsonPublicAddress,
+    #[schemars(with = "String")]
     pub value: JsonU64,
     pub memo: String,
+    #[serde(default)]
+    #[schemars(with = "String")]
+    pub token_id: JsonU64,
+    #[serde(default)]
+    #[schemars(with = "String")]
+    pub payment_id: JsonU64,
 }
 
 impl From<&api::ParseRequestCodeResponse> for JsonParseRequestCodeResponse {
@@ -347,16 +462,18 @@ impl From<&api::ParseRequestCodeResponse> for JsonParseRequestCodeResponse {
             receiver: JsonPublicAddress::from(src.get_receiver()),
             value: JsonU64(src.get_value()),
             memo: src.get_memo().to_string(),
+            token_id: JsonU64(src.get_token_id()),
+            payment_id: JsonU64(src.get_payment_id()),
         }
     }
 }
 
-#[derive(Deserialize, Default, Debug)]
+#[derive(Deserialize, Default, Debug, JsonSchema)]
 pub struct JsonCreateAddressCodeRequest {
     pub receiver: JsonPublicAddress,
 }
 
-#[derive(Serialize, Default, Debug)]
+#[derive(Serialize, Default, Debug, JsonSchema)]
 pub struct JsonCreateAddressCodeResponse {
     pub b58_code: String,
 }
@@ -369,20 +486,24 @@ impl From<&api::CreateAddressCodeResponse> for JsonCreateAddressCodeResponse {
     }
 }
 
-#[derive(Deserialize, Serialize, Default, Debug)]
+#[derive(Deserialize, Serialize, Default, Debug, JsonSchema)]
 pub struct JsonParseAddressCodeResponse {
     pub receiver: JsonPublicAddress,
+    #[serde(default)]
+    #[schemars(with = "String")]
+    pub payment_id: JsonU64,
 }
 
 impl From<&api::ParseAddressCodeResponse> for JsonParseAddressCodeResponse {
     fn from(src: &api::ParseAddressCodeResponse) -> Self {
         Self {
             receiver: JsonPublicAddress::from(src.get_receiver()),
+            payment_id: JsonU64(src.get_payment_id()),
         }
     }
 }
 
-#[derive(Deserialize, Serialize, Default, Debug)]
+#[derive(Deserialize, Serialize, Default, Debug, JsonSchema)]
 pub struct JsonSenderTxReceipt {
     pub key_images: Vec<String>,
     pub tombstone: u64,
@@ -401,7 +522,7 @@ impl From<&api::SenderTxReceipt> for JsonSenderTxReceipt {
     }
 }
 
-#[derive(Deserialize, Serialize, Default, Debug)]
+#[derive(Deserialize, Serialize, Default, Debug, JsonSchema)]
 pub struct JsonReceiverTxReceipt {
     pub recipient: JsonPublicAddress,
     pub tx_public_key: String,
@@ -422,14 +543,48 @@ impl From<&api::ReceiverTxReceipt> for JsonReceiverTxReceipt {
     }
 }
 
-#[derive(Deserialize, Serialize, Default, Debug)]
+impl TryFrom<&JsonReceiverTxReceipt> for api::ReceiverTxReceipt {
+    type Error = String;
+
+    fn try_from(src: &JsonReceiverTxReceipt) -> Result<Self, String> {
+        let mut receiver_receipt = api::ReceiverTxReceipt::new();
+        receiver_receipt.set_recipient(
+            PublicAddress::try_from(&src.recipient)
+                .map_err(|err| format!("Failed to convert recipient: {}", err))?,
+        );
+        let mut pubkey = CompressedRistretto::new();
+        pubkey.set_data(
+            hex::decode(&src.tx_public_key)
+                .map_err(|err| format!("Failed to decode hex for tx_public_key: {}", err))?,
+        );
+        receiver_receipt.set_tx_public_key(pubkey);
+        receiver_receipt.set_tx_out_hash(
+            hex::decode(&src.tx_out_hash)
+                .map_err(|err| format!("Failed to decode hex for tx_out_hash: {}", err))?,
+        );
+        receiver_receipt.set_tombstone(src.tombstone);
+        receiver_receipt.set_confirmation_number(
+            hex::decode(&src.confirmation_number)
+                .map_err(|err| format!("Failed to decode hex for confirmation_number: {}", err))?,
+        );
+
+        Ok(receiver_receipt)
+    }
+}
+
+#[derive(Deserialize, Serialize, Default, Debug, JsonSchema)]
 pub struct JsonSendPaymentRequest {
     pub request_data: JsonParseRequestCodeResponse,
+    #[schemars(with = "Option<String>")]
     pub max_input_utxo_value: Option<JsonU64>,
+    #[schemars(with = "Option<String>")]
     pub change_subaddress: Option<JsonU64>,
+    #[schemars(with = "Option<String>")]
+    pub fee: Option<JsonU64>,
+    pub tombstone: Option<u64>,
 }
 
-#[derive(Deserialize, Serialize, Default, Debug)]
+#[derive(Deserialize, Serialize, Default, Debug, JsonSchema)]
 pub struct JsonSendPaymentResponse {
     pub sender_tx_receipt: JsonSenderTxReceipt,
     pub receiver_tx_receipt_list: Vec<JsonReceiverTxReceipt>,
@@ -448,16 +603,26 @@ impl From<&api::SendPaymentResponse> for JsonSendPaymentResponse {
     }
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, JsonSchema)]
 pub struct JsonPayAddressCodeRequest {
     pub receiver_b58_address_code: String,
+    #[schemars(with = "String")]
     pub value: JsonU64,
+    #[schemars(with = "Option<String>")]
     pub max_input_utxo_value: Option<JsonU64>,
+    #[schemars(with = "Option<String>")]
     pub change_subaddress: Option<JsonU64>,
+    #[schemars(with = "Option<String>")]
+    pub fee: Option<JsonU64>,
+    pub tombstone: Option<u64>,
+    #[serde(default)]
+    #[schemars(with = "String")]
+    pub token_id: JsonU64,
 }
 
-#[derive(Deserialize, Serialize, Default, Debug, Clone)]
+#[derive(Deserialize, Serialize, Default, Debug, Clone, JsonSchema)]
 pub struct JsonOutlay {
+    #[schemars(with = "String")]
     pub value: JsonU64,
     pub receiver: JsonPublicAddress,
 }
@@ -486,9 +651,10 @@ impl TryFrom<&JsonOutlay> for api::Outlay {
     }
 }
 
-#[derive(Deserialize, Serialize, Default, Debug, Clone)]
+#[derive(Deserialize, Serialize, Default, Debug, Clone, JsonSchema)]
 pub struct JsonMaskedAmount {
     pub commitment: String,
+    #[schemars(with = "String")]
     pub masked_value: JsonU64,
     pub masked_token_id: String,
     pub version: Option<u32>,
@@ -545,7 +711,7 @@ impl TryFrom<&JsonMaskedAmount> for mc_api::external::TxOut_oneof_masked_amount
     }
 }
 
-#[derive(Deserialize, Serialize, Default, Debug, Clone)]
+#[derive(Deserialize, Serialize, Default, Debug, Clone, JsonSchema)]
 pub struct JsonTxOut {
     pub masked_amount: Option<JsonMaskedAmount>,
     pub target_key: String,
@@ -609,13 +775,15 @@ impl TryFrom<&JsonTxOut> for mc_api::external::TxOut {
     }
 }
 
-#[derive(Deserialize, Serialize, Default, Debug, Clone)]
+#[derive(Deserialize, Serialize, Default, Debug, Clone, JsonSchema)]
 pub struct JsonRange {
+    #[schemars(with = "String")]
     pub from: JsonU64,
+    #[schemars(with = "String")]
     pub to: JsonU64,
 }
 
-#[derive(Deserialize, Serialize, Default, Debug, Clone)]
+#[derive(Deserialize, Serialize, Default, Debug, Clone, JsonSchema)]
 pub struct JsonTxOutMembershipElement {
     pub range: JsonRange,
     pub hash: String,
@@ -633,9 +801,11 @@ impl From<&TxOutMembershipElement> for JsonTxOutMembershipElement {
     }
 }
 
-#[derive(Deserialize, Serialize, Default, Debug, Clone)]
+#[derive(Deserialize, Serialize, Default, Debug, Clone, JsonSchema)]
 pub struct JsonTxOutMembershipProof {
+    #[schemars(with = "String")]
     pub index: JsonU64,
+    #[schemars(with = "String")]
     pub highest_index: JsonU64,
     pub elements: Vec<JsonTxOutMembershipElement>,
 }
@@ -685,7 +855,7 @@ impl TryFrom<&JsonTxOutMembershipProof> for TxOutMembershipProof {
     }
 }
 
-#[derive(Deserialize, Serialize, Default, Debug)]
+#[derive(Deserialize, Serialize, Default, Debug, JsonSchema)]
 /// A request for randomly sampled TxOuts for use as mixins.
 pub struct JsonMixinRequest {
     /// Number of mixins requested.
@@ -694,7 +864,7 @@ pub struct JsonMixinRequest {
     pub excluded: Vec<JsonTxOut>,
 }
 
-#[derive(Deserialize, Serialize, Default, Debug)]
+#[derive(Deserialize, Serialize, Default, Debug, JsonSchema)]
 /// Randomly sampled TxOuts for use as mixins, with membership proofs.
 pub struct JsonMixinResponse {
     /// TxOuts to use as mixins.
@@ -703,13 +873,13 @@ pub struct JsonMixinResponse {
     pub membership_proofs: Vec<JsonTxOutMembershipProof>,
 }
 
-#[derive(Deserialize, Serialize, Default, Debug)]
+#[derive(Deserialize, Serialize, Default, Debug, JsonSchema)]
 /// Requests Merkle proof-of-membership for each queried TxOut
 pub struct JsonMembershipProofRequest {
     pub outputs: Vec<JsonTxOut>,
 }
 
-#[derive(Deserialize, Serialize, Default, Debug)]
+#[derive(Deserialize, Serialize, Default, Debug, JsonSchema)]
 /// Outputs and their corresponding proofs of membership.
 pub struct JsonMembershipProofResponse {
     /// Queried outputs.
@@ -718,7 +888,7 @@ pub struct JsonMembershipProofResponse {
     pub membership_proofs: Vec<JsonTxOutMembershipProof>,
 }
 
-#[derive(Deserialize, Serialize, Default, Debug, Clone)]
+#[derive(Deserialize, Serialize, Default, Debug, Clone, JsonSchema)]
 pub struct JsonInputRules {
     pub required_outputs: Vec<JsonTxOut>,
     pub max_tombstone_block: u64,
@@ -756,7 +926,7 @@ impl TryFrom<&JsonInputRules> for InputRules {
     }
 }
 
-#[derive(Deserialize, Serialize, Default, Debug, Clone)]
+#[derive(Deserialize, Serialize, Default, Debug, Clone, JsonSchema)]
 pub struct JsonTxIn {
     pub ring: Vec<JsonTxOut>,
     pub proofs: Vec<JsonTxOutMembershipProof>,
@@ -812,12 +982,17 @@ impl TryFrom<&JsonTxIn> for TxIn {
     }
 }
 
-#[derive(Deserialize, Serialize, Default, Debug, Clone)]
+#[derive(Deserialize, Serialize, Default, Debug, Clone, JsonSchema)]
 pub struct JsonTxPrefix {
     pub inputs: Vec<JsonTxIn>,
     pub outputs: Vec<JsonTxOut>,
+    #[schemars(with = "String")]
     pub fee: JsonU64,
+    #[schemars(with = "String")]
     tombstone_block: JsonU64,
+    #[serde(default)]
+    #[schemars(with = "String")]
+    pub fee_token_id: JsonU64,
 }
 
 impl From<&TxPrefix> for JsonTxPrefix {
@@ -827,6 +1002,7 @@ impl From<&TxPrefix> for JsonTxPrefix {
             outputs: src.get_outputs().iter().map(JsonTxOut::from).collect(),
             fee: JsonU64(src.get_fee()),
             tombstone_block: JsonU64(src.get_tombstone_block()),
+            fee_token_id: JsonU64(src.get_fee_token_id()),
         }
     }
 }
@@ -854,12 +1030,13 @@ impl TryFrom<&JsonTxPrefix> for TxPrefix {
         prefix.set_outputs(RepeatedField::from_vec(outputs));
         prefix.set_fee(src.fee.into());
         prefix.set_tombstone_block(src.tombstone_block.into());
+        prefix.set_fee_token_id(src.fee_token_id.into());
 
         Ok(prefix)
     }
 }
 
-#[derive(Deserialize, Serialize, Default, Debug, Clone)]
+#[derive(Deserialize, Serialize, Default, Debug, Clone, JsonSchema)]
 pub struct JsonRingMLSAG {
     pub c_zero: String,
     pub responses: Vec<String>,
@@ -880,13 +1057,15 @@ impl From<&RingMLSAG> for JsonRingMLSAG {
     }
 }
 
-#[derive(Deserialize, Serialize, Default, Debug, Clone)]
+#[derive(Deserialize, Serialize, Default, Debug, Clone, JsonSchema)]
 pub struct JsonSignatureRctBulletproofs {
     pub ring_signatures: Vec<JsonRingMLSAG>,
     pub pseudo_output_commitments: Vec<String>,
     pub range_proof_bytes: String,
     pub range_proofs: Vec<String>,
+    #[schemars(with = "Vec<String>")]
     pub pseudo_output_token_ids: Vec<JsonU64>,
+    #[schemars(with = "Vec<String>")]
     pub output_token_ids: Vec<JsonU64>,
 }
 
@@ -989,7 +1168,7 @@ impl TryFrom<&JsonSignatureRctBulletproofs> for SignatureRctBulletproofs {
     }
 }
 
-#[derive(Deserialize, Serialize, Default, Debug, Clone)]
+#[derive(Deserialize, Serialize, Default, Debug, Clone, JsonSchema)]
 pub struct JsonTx {
     pub prefix: JsonTxPrefix,
     pub signature: JsonSignatureRctBulletproofs,
@@ -1023,7 +1202,7 @@ impl TryFrom<&JsonTx> for Tx {
     }
 }
 
-#[derive(Deserialize, Serialize, Default, Debug)]
+#[derive(Deserialize, Serialize, Default, Debug, JsonSchema)]
 pub struct JsonTxProposal {
     pub input_list: Vec<JsonUnspentTxOut>,
     pub outlay_list: Vec<JsonOutlay>,
@@ -1095,31 +1274,70 @@ impl TryFrom<&JsonTxProposal> for api::TxProposal {
     }
 }
 
-#[derive(Deserialize, Serialize, Default, Debug)]
+#[derive(Deserialize, Serialize, Default, Debug, JsonSchema)]
 pub struct JsonCreateTxProposalRequest {
     pub input_list: Vec<JsonUnspentTxOut>,
     pub transfer: JsonParseRequestCodeResponse,
 }
 
-#[derive(Deserialize, Serialize, Default, Debug)]
+#[derive(Deserialize, Serialize, Default, Debug, JsonSchema)]
 pub struct JsonCreateTxProposalResponse {
     pub tx_proposal: JsonTxProposal,
+    /// Hex encoded protobuf `UnsignedTxProposal`, set instead of
+    /// `tx_proposal` when the sender monitor is view-only.
+    #[serde(default)]
+    pub unsigned_tx_proposal: Option<String>,
 }
 
-impl From<&api::GenerateTxResponse> for JsonCreateTxProposalResponse {
-    fn from(src: &api::GenerateTxResponse) -> Self {
-        Self {
+impl TryFrom<&api::GenerateTxResponse> for JsonCreateTxProposalResponse {
+    type Error = String;
+
+    fn try_from(src: &api::GenerateTxResponse) -> Result<Self, String> {
+        let unsigned_tx_proposal = if src.has_unsigned_tx_proposal() {
+            Some(encode_proto(src.get_unsigned_tx_proposal())?)
+        } else {
+            None
+        };
+
+        Ok(Self {
             tx_proposal: src.get_tx_proposal().into(),
-        }
+            unsigned_tx_proposal,
+        })
     }
 }
 
-#[derive(Deserialize, Serialize, Default, Debug)]
+/// Responses that carry nothing but a signed TxProposal.
+macro_rules! impl_from_tx_proposal_response {
+    ($($response:ty),* $(,)?) => {
+        $(
+            impl From<&$response> for JsonCreateTxProposalResponse {
+                fn from(src: &$response) -> Self {
+                    Self {
+                        tx_proposal: src.get_tx_proposal().into(),
+                        unsigned_tx_proposal: None,
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_from_tx_proposal_response!(
+    api::GenerateOptimizationTxResponse,
+    api::GenerateTxFromTxOutListResponse,
+    api::GenerateBurnRedemptionTxResponse,
+    api::GenerateReplacementTxResponse,
+    api::GenerateSwapFillTxResponse,
+    api::GenerateGiftCodeClaimTxResponse,
+    api::GenerateGiftCodeCancellationTxResponse,
+);
+
+#[derive(Deserialize, Serialize, Default, Debug, JsonSchema)]
 pub struct JsonTxProposalRequest {
     pub tx_proposal: JsonTxProposal,
 }
 
-#[derive(Deserialize, Serialize, Default, Debug)]
+#[derive(Deserialize, Serialize, Default, Debug, JsonSchema)]
 pub struct JsonSubmitTxResponse {
     pub sender_tx_receipt: JsonSenderTxReceipt,
     pub receiver_tx_receipt_list: Vec<JsonReceiverTxReceipt>,
@@ -1161,31 +1379,11 @@ impl TryFrom<&JsonSubmitTxResponse> for api::SubmitTxResponse {
         sender_receipt.set_key_image_list(RepeatedField::from_vec(key_images));
         sender_receipt.set_tombstone(src.sender_tx_receipt.tombstone);
 
-        let mut receiver_receipts = Vec::new();
-        for r in src.receiver_tx_receipt_list.iter() {
-            let mut receiver_receipt = api::ReceiverTxReceipt::new();
-            receiver_receipt.set_recipient(
-                PublicAddress::try_from(&r.recipient)
-                    .map_err(|err| format!("Failed to convert recipient: {}", err))?,
-            );
-            let mut pubkey = mc_api::external::CompressedRistretto::new();
-            pubkey.set_data(
-                hex::decode(&r.tx_public_key)
-                    .map_err(|err| format!("Failed to decode hex for tx_public_key: {}", err))?,
-            );
-            receiver_receipt.set_tx_public_key(pubkey);
-            receiver_receipt.set_tx_out_hash(
-                hex::decode(&r.tx_out_hash)
-                    .map_err(|err| format!("Failed to decode hex for tx_out_hash: {}", err))?,
-            );
-            receiver_receipt.set_tombstone(r.tombstone);
-            receiver_receipt.set_confirmation_number(
-                hex::decode(&r.confirmation_number).map_err(|err| {
-                    format!("Failed to decode hex for confirmation_number: {}", err)
-                })?,
-            );
-            receiver_receipts.push(receiver_receipt);
-        }
+        let receiver_receipts = src
+            .receiver_tx_receipt_list
+            .iter()
+            .map(api::ReceiverTxReceipt::try_from)
+            .collect::<Result<Vec<_>, String>>()?;
 
         let mut resp = api::SubmitTxResponse::new();
         resp.set_sender_tx_receipt(sender_receipt);
@@ -1195,7 +1393,7 @@ impl TryFrom<&JsonSubmitTxResponse> for api::SubmitTxResponse {
     }
 }
 
-#[derive(Serialize, Default, Debug)]
+#[derive(Serialize, Default, Debug, JsonSchema)]
 pub struct JsonStatusResponse {
     pub status: String,
 }
@@ -1214,6 +1412,7 @@ impl From<&api::GetTxStatusAsSenderResponse> for JsonStatusResponse {
             api::TxStatus::TransactionFailureKeyImageAlreadySpent => {
                 "transaction_failure_key_image_already_spent"
             }
+            api::TxStatus::TransactionReplaced => "transaction_replaced",
         };
 
         Self {
@@ -1236,6 +1435,7 @@ impl From<&api::GetTxStatusAsReceiverResponse> for JsonStatusResponse {
             api::TxStatus::TransactionFailureKeyImageAlreadySpent => {
                 "transaction_failure_key_image_already_spent"
             }
+            api::TxStatus::TransactionReplaced => "transaction_replaced",
         };
 
         Self {
@@ -1244,9 +1444,11 @@ impl From<&api::GetTxStatusAsReceiverResponse> for JsonStatusResponse {
     }
 }
 
-#[derive(Serialize, Default, Debug)]
+#[derive(Serialize, Default, Debug, JsonSchema)]
 pub struct JsonLedgerInfoResponse {
+    #[schemars(with = "String")]
     pub block_count: JsonU64,
+    #[schemars(with = "String")]
     pub txo_count: JsonU64,
 }
 
@@ -1259,9 +1461,11 @@ impl From<&api::GetLedgerInfoResponse> for JsonLedgerInfoResponse {
     }
 }
 
-#[derive(Serialize, Default, Debug)]
+#[derive(Serialize, Default, Debug, JsonSchema)]
 pub struct JsonBlockInfoResponse {
+    #[schemars(with = "String")]
     pub key_image_count: JsonU64,
+    #[schemars(with = "String")]
     pub txo_count: JsonU64,
 }
 
@@ -1274,12 +1478,14 @@ impl From<&api::GetBlockInfoResponse> for JsonBlockInfoResponse {
     }
 }
 
-#[derive(Serialize, Default, Debug)]
+#[derive(Serialize, Default, Debug, JsonSchema)]
 pub struct JsonBlockDetailsResponse {
     pub block_id: String,
     pub version: u32,
     pub parent_id: String,
+    #[schemars(with = "String")]
     pub index: JsonU64,
+    #[schemars(with = "String")]
     pub cumulative_txo_count: JsonU64,
     pub contents_hash: String,
     pub key_images: Vec<String>,
@@ -1307,14 +1513,21 @@ impl From<&api::GetBlockResponse> for JsonBlockDetailsResponse {
     }
 }
 
-#[derive(Serialize, Default, Debug)]
+#[derive(Serialize, Default, Debug, JsonSchema)]
 pub struct JsonProcessedTxOut {
     pub monitor_id: String,
     pub subaddress_index: u64,
     pub public_key: String,
     pub key_image: String,
+    #[schemars(with = "String")]
     pub value: JsonU64,
+    #[schemars(with = "String")]
+    pub token_id: JsonU64,
     pub direction: String,
+    pub address_code: String,
+    pub memo_classification: String,
+    pub sender_address_hash: String,
+    pub sender_address_code: String,
 }
 
 impl From<&api::ProcessedTxOut> for JsonProcessedTxOut {
@@ -1324,6 +1537,11 @@ impl From<&api::ProcessedTxOut> for JsonProcessedTxOut {
             api::ProcessedTxOutDirection::Received => "received",
             api::ProcessedTxOutDirection::Spent => "spent",
         };
+        let memo_classification_str = match src.memo_classification {
+            api::ProcessedTxOutMemoClassification::MemoUnauthenticated => "unauthenticated",
+            api::ProcessedTxOutMemoClassification::MemoAuthenticated => "authenticated",
+            api::ProcessedTxOutMemoClassification::MemoUnknownSender => "unknown_sender",
+        };
 
         Self {
             monitor_id: hex::encode(&src.get_monitor_id()),
@@ -1331,12 +1549,17 @@ impl From<&api::ProcessedTxOut> for JsonProcessedTxOut {
             public_key: hex::encode(&src.get_public_key().get_data()),
             key_image: hex::encode(&src.get_key_image().get_data()),
             value: JsonU64(src.value),
+            token_id: JsonU64(src.token_id),
             direction: direction_str.to_owned(),
+            address_code: src.get_address_code().to_owned(),
+            memo_classification: memo_classification_str.to_owned(),
+            sender_address_hash: hex::encode(src.get_sender_address_hash()),
+            sender_address_code: src.get_sender_address_code().to_owned(),
         }
     }
 }
 
-#[derive(Serialize, Default, Debug)]
+#[derive(Serialize, Default, Debug, JsonSchema)]
 pub struct JsonProcessedBlockResponse {
     pub tx_outs: Vec<JsonProcessedTxOut>,
 }
@@ -1353,7 +1576,189 @@ impl From<&api::GetProcessedBlockResponse> for JsonProcessedBlockResponse {
     }
 }
 
-#[derive(Serialize, Default, Debug)]
+#[derive(Serialize, Default, Debug, JsonSchema)]
+pub struct JsonSentTxOutlay {
+    pub receiver: JsonPublicAddress,
+    #[schemars(with = "String")]
+    pub value: JsonU64,
+    pub tx_public_key: String,
+    pub confirmation_number: String,
+}
+
+impl From<&api::SentTxOutlay> for JsonSentTxOutlay {
+    fn from(src: &api::SentTxOutlay) -> Self {
+        Self {
+            receiver: JsonPublicAddress::from(src.get_receiver()),
+            value: JsonU64(src.value),
+            tx_public_key: hex::encode(&src.get_tx_public_key().get_data()),
+            confirmation_number: hex::encode(src.get_confirmation_number()),
+        }
+    }
+}
+
+#[derive(Serialize, Default, Debug, JsonSchema)]
+pub struct JsonSentTx {
+    pub index: String,
+    pub outlays: Vec<JsonSentTxOutlay>,
+    pub token_id: String,
+    #[schemars(with = "String")]
+    pub fee: JsonU64,
+    pub fee_token_id: String,
+    pub tombstone: String,
+    pub key_images: Vec<String>,
+    pub memos: Vec<String>,
+    pub submitted_block_count: String,
+    pub status: String,
+    pub landed_block: Option<String>,
+}
+
+impl From<&api::SentTx> for JsonSentTx {
+    fn from(src: &api::SentTx) -> Self {
+        let status_str = match src.status {
+            api::SentTxStatus::SentTxPending => "pending",
+            api::SentTxStatus::SentTxLanded => "landed",
+            api::SentTxStatus::SentTxExpired => "expired",
+        };
+
+        Self {
+            index: src.index.to_string(),
+            outlays: src
+                .get_outlay_list()
+                .iter()
+                .map(JsonSentTxOutlay::from)
+                .collect(),
+            token_id: src.token_id.to_string(),
+            fee: JsonU64(src.fee),
+            fee_token_id: src.fee_token_id.to_string(),
+            tombstone: src.tombstone.to_string(),
+            key_images: src
+                .get_key_image_list()
+                .iter()
+                .map(|key_image| hex::encode(key_image.get_data()))
+                .collect(),
+            memos: src.get_memo_list().iter().map(hex::encode).collect(),
+            submitted_block_count: src.submitted_block_count.to_string(),
+            status: status_str.to_owned(),
+            landed_block: (src.status == api::SentTxStatus::SentTxLanded)
+                .then(|| src.landed_block.to_string()),
+        }
+    }
+}
+
+#[derive(Serialize, Default, Debug, JsonSchema)]
+pub struct JsonSentTxListResponse {
+    pub sent_txs: Vec<JsonSentTx>,
+    pub total: String,
+}
+
+impl From<&api::GetSentTxListResponse> for JsonSentTxListResponse {
+    fn from(src: &api::GetSentTxListResponse) -> Self {
+        Self {
+            sent_txs: src
+                .get_sent_tx_list()
+                .iter()
+                .map(JsonSentTx::from)
+                .collect(),
+            total: src.total.to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize, Default, Debug, JsonSchema)]
+pub struct JsonCreatePaymentRequestRequest {
+    #[schemars(with = "String")]
+    pub value: JsonU64,
+    #[schemars(with = "Option<String>")]
+    pub token_id: Option<JsonU64>,
+    pub memo: Option<String>,
+    #[schemars(with = "Option<String>")]
+    pub expiry_block: Option<JsonU64>,
+}
+
+#[derive(Serialize, Default, Debug, JsonSchema)]
+pub struct JsonPaymentRequestPayment {
+    pub block_index: String,
+    pub tx_public_key: String,
+    #[schemars(with = "String")]
+    pub value: JsonU64,
+}
+
+impl From<&api::PaymentRequestPayment> for JsonPaymentRequestPayment {
+    fn from(src: &api::PaymentRequestPayment) -> Self {
+        Self {
+            block_index: src.block_index.to_string(),
+            tx_public_key: hex::encode(src.get_tx_public_key().get_data()),
+            value: JsonU64(src.value),
+        }
+    }
+}
+
+#[derive(Serialize, Default, Debug, JsonSchema)]
+pub struct JsonPaymentRequest {
+    pub id: String,
+    pub monitor_id: String,
+    pub subaddress_index: String,
+    #[schemars(with = "String")]
+    pub value: JsonU64,
+    pub token_id: String,
+    pub memo: String,
+    pub expiry_block: String,
+    pub created_block: String,
+    pub payments: Vec<JsonPaymentRequestPayment>,
+    #[schemars(with = "String")]
+    pub paid_value: JsonU64,
+    pub status: String,
+    pub b58_request_code: String,
+}
+
+impl From<&api::TrackedPaymentRequest> for JsonPaymentRequest {
+    fn from(src: &api::TrackedPaymentRequest) -> Self {
+        let status_str = match src.status {
+            api::PaymentRequestStatus::PaymentRequestPending => "pending",
+            api::PaymentRequestStatus::PaymentRequestPartiallyPaid => "partially_paid",
+            api::PaymentRequestStatus::PaymentRequestPaid => "paid",
+            api::PaymentRequestStatus::PaymentRequestExpired => "expired",
+        };
+
+        Self {
+            id: src.id.to_string(),
+            monitor_id: hex::encode(src.get_monitor_id()),
+            subaddress_index: src.subaddress_index.to_string(),
+            value: JsonU64(src.value),
+            token_id: src.token_id.to_string(),
+            memo: src.get_memo().to_string(),
+            expiry_block: src.expiry_block.to_string(),
+            created_block: src.created_block.to_string(),
+            payments: src
+                .get_payment_list()
+                .iter()
+                .map(JsonPaymentRequestPayment::from)
+                .collect(),
+            paid_value: JsonU64(src.paid_value),
+            status: status_str.to_owned(),
+            b58_request_code: src.get_b58_code().to_string(),
+        }
+    }
+}
+
+#[derive(Serialize, Default, Debug, JsonSchema)]
+pub struct JsonPaymentRequestListResponse {
+    pub payment_requests: Vec<JsonPaymentRequest>,
+}
+
+impl From<&api::GetPaymentRequestListResponse> for JsonPaymentRequestListResponse {
+    fn from(src: &api::GetPaymentRequestListResponse) -> Self {
+        Self {
+            payment_requests: src
+                .get_payment_request_list()
+                .iter()
+                .map(JsonPaymentRequest::from)
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize, Default, Debug, JsonSchema)]
 pub struct JsonBlockIndexByTxPubKeyResponse {
     pub block_index: String,
 }
@@ -1366,7 +1771,7 @@ impl From<&api::GetBlockIndexByTxPubKeyResponse> for JsonBlockIndexByTxPubKeyRes
     }
 }
 
-#[derive(Serialize, Default, Debug)]
+#[derive(Serialize, Default, Debug, JsonSchema)]
 pub struct JsonMobilecoindVersionResponse {
     pub version: String,
 }
@@ -1379,6 +1784,589 @@ impl From<&api::MobilecoindVersionResponse> for JsonMobilecoindVersionResponse {
     }
 }
 
+/// Hex encode a protobuf message that has no structured JSON representation,
+/// such as a signed contingent input.
+pub fn encode_proto<M: Message>(src: &M) -> Result<String, String> {
+    src.write_to_bytes()
+        .map(hex::encode)
+        .map_err(|err| format!("Failed to encode protobuf: {}", err))
+}
+
+/// Decode a protobuf message produced by [encode_proto].
+pub fn decode_proto<M: Message>(src: &str) -> Result<M, String> {
+    let bytes = hex::decode(src).map_err(|err| format!("Failed to decode hex: {}", err))?;
+    M::parse_from_bytes(&bytes).map_err(|err| format!("Failed to decode protobuf: {}", err))
+}
+
+fn compressed_ristretto_from_hex(src: &str) -> Result<CompressedRistretto, String> {
+    let mut key = CompressedRistretto::new();
+    key.set_data(hex::decode(src).map_err(|err| format!("Failed to decode key hex: {}", err))?);
+    Ok(key)
+}
+
+/// Convert a list of JSON inputs to the protobuf list the generate-tx requests
+/// take.
+pub fn utxos_from_json(
+    src: &[JsonUnspentTxOut],
+) -> Result<RepeatedField<api::UnspentTxOut>, String> {
+    src.iter()
+        .map(|input| {
+            api::UnspentTxOut::try_from(input)
+                .map_err(|err| format!("Failed to convert input: {}", err))
+        })
+        .collect::<Result<Vec<_>, String>>()
+        .map(RepeatedField::from_vec)
+}
+
+impl TryFrom<&JsonAccountKeyResponse> for mc_api::external::AccountKey {
+    type Error = String;
+
+    fn try_from(src: &JsonAccountKeyResponse) -> Result<Self, String> {
+        let mut view_private_key = RistrettoPrivate::new();
+        view_private_key.set_data(
+            hex::decode(&src.view_private_key)
+                .map_err(|err| format!("Failed to decode hex key: {}", err))?,
+        );
+        let mut spend_private_key = RistrettoPrivate::new();
+        spend_private_key.set_data(
+            hex::decode(&src.spend_private_key)
+                .map_err(|err| format!("Failed to decode hex key: {}", err))?,
+        );
+
+        let mut account_key = mc_api::external::AccountKey::new();
+        account_key.set_view_private_key(view_private_key);
+        account_key.set_spend_private_key(spend_private_key);
+        Ok(account_key)
+    }
+}
+
+impl TryFrom<&JsonViewAccountKey> for api::ViewAccountKey {
+    type Error = String;
+
+    fn try_from(src: &JsonViewAccountKey) -> Result<Self, String> {
+        let mut view_private_key = RistrettoPrivate::new();
+        view_private_key.set_data(
+            hex::decode(&src.view_private_key)
+                .map_err(|err| format!("Failed to decode hex key: {}", err))?,
+        );
+
+        let mut view_account_key = api::ViewAccountKey::new();
+        view_account_key.set_view_private_key(view_private_key);
+        view_account_key
+            .set_spend_public_key(compressed_ristretto_from_hex(&src.spend_public_key)?);
+        Ok(view_account_key)
+    }
+}
+
+#[derive(Deserialize, Serialize, Default, Debug, JsonSchema)]
+pub struct JsonTxOutKeyImage {
+    /// Hex encoded compressed ristretto bytes
+    pub tx_public_key: String,
+    /// Hex encoded key image bytes
+    pub key_image: String,
+}
+
+#[derive(Deserialize, Default, Debug, JsonSchema)]
+pub struct JsonImportKeyImagesRequest {
+    pub key_image_list: Vec<JsonTxOutKeyImage>,
+}
+
+impl TryFrom<&JsonTxOutKeyImage> for api::TxOutKeyImage {
+    type Error = String;
+
+    fn try_from(src: &JsonTxOutKeyImage) -> Result<Self, String> {
+        let mut tx_out_key_image = api::TxOutKeyImage::new();
+        tx_out_key_image.set_tx_public_key(compressed_ristretto_from_hex(&src.tx_public_key)?);
+        tx_out_key_image.set_key_image(KeyImage::from(
+            hex::decode(&src.key_image)
+                .map_err(|err| format!("Failed to decode key image hex: {}", err))?,
+        ));
+        Ok(tx_out_key_image)
+    }
+}
+
+#[derive(Serialize, Default, Debug, JsonSchema)]
+pub struct JsonImportKeyImagesResponse {
+    pub num_imported: u64,
+    pub spent_output_list: Vec<JsonUnspentTxOut>,
+}
+
+impl From<&api::ImportKeyImagesResponse> for JsonImportKeyImagesResponse {
+    fn from(src: &api::ImportKeyImagesResponse) -> Self {
+        Self {
+            num_imported: src.get_num_imported(),
+            spent_output_list: src
+                .get_spent_output_list()
+                .iter()
+                .map(JsonUnspentTxOut::from)
+                .collect(),
+        }
+    }
+}
+
+#[derive(Deserialize, Default, Debug, JsonSchema)]
+pub struct JsonSetMonitorWebhookRequest {
+    /// Endpoint payment events are POSTed to. Empty to stop deliveries.
+    pub url: String,
+    /// Hex encoded HMAC secret used to sign deliveries.
+    #[serde(default)]
+    pub secret: String,
+}
+
+#[derive(Deserialize, Default, Debug, JsonSchema)]
+pub struct JsonCreateTransferCodeRequest {
+    /// Hex encoded bip39 entropy of the one-time account holding the funds
+    pub bip39_entropy: String,
+    /// Hex encoded public key of the TxOut holding the funds
+    pub tx_public_key: String,
+    #[serde(default)]
+    pub memo: String,
+}
+
+#[derive(Serialize, Default, Debug, JsonSchema)]
+pub struct JsonCreateTransferCodeResponse {
+    pub b58_code: String,
+}
+
+impl From<&api::CreateTransferCodeResponse> for JsonCreateTransferCodeResponse {
+    fn from(src: &api::CreateTransferCodeResponse) -> Self {
+        Self {
+            b58_code: src.get_b58_code().to_string(),
+        }
+    }
+}
+
+#[derive(Serialize, Default, Debug, JsonSchema)]
+pub struct JsonParseTransferCodeResponse {
+    pub bip39_entropy: String,
+    pub tx_public_key: String,
+    pub memo: String,
+    pub utxo: JsonUnspentTxOut,
+}
+
+impl From<&api::ParseTransferCodeResponse> for JsonParseTransferCodeResponse {
+    fn from(src: &api::ParseTransferCodeResponse) -> Self {
+        Self {
+            bip39_entropy: hex::encode(src.get_bip39_entropy()),
+            tx_public_key: hex::encode(src.get_tx_public_key().get_data()),
+            memo: src.get_memo().to_string(),
+            utxo: src.get_utxo().into(),
+        }
+    }
+}
+
+#[derive(Deserialize, Default, Debug, JsonSchema)]
+pub struct JsonGenerateTxRequest {
+    pub input_list: Vec<JsonUnspentTxOut>,
+    pub outlay_list: Vec<JsonOutlay>,
+    #[schemars(with = "Option<String>")]
+    pub fee: Option<JsonU64>,
+    pub tombstone: Option<u64>,
+    #[serde(default)]
+    #[schemars(with = "String")]
+    pub token_id: JsonU64,
+}
+
+#[derive(Deserialize, Default, Debug, JsonSchema)]
+pub struct JsonGenerateOptimizationTxRequest {
+    #[schemars(with = "Option<String>")]
+    pub fee: Option<JsonU64>,
+    #[serde(default)]
+    #[schemars(with = "String")]
+    pub token_id: JsonU64,
+}
+
+#[derive(Deserialize, Default, Debug, JsonSchema)]
+pub struct JsonGenerateTransferCodeTxRequest {
+    pub input_list: Vec<JsonUnspentTxOut>,
+    #[schemars(with = "String")]
+    pub value: JsonU64,
+    #[schemars(with = "Option<String>")]
+    pub fee: Option<JsonU64>,
+    pub tombstone: Option<u64>,
+    #[serde(default)]
+    pub memo: String,
+    #[serde(default)]
+    #[schemars(with = "String")]
+    pub token_id: JsonU64,
+}
+
+#[derive(Serialize, Default, Debug, JsonSchema)]
+pub struct JsonGenerateTransferCodeTxResponse {
+    pub tx_proposal: JsonTxProposal,
+    pub tx_public_key: String,
+    pub memo: String,
+    pub b58_code: String,
+    pub bip39_entropy: String,
+}
+
+impl From<&api::GenerateTransferCodeTxResponse> for JsonGenerateTransferCodeTxResponse {
+    fn from(src: &api::GenerateTransferCodeTxResponse) -> Self {
+        Self {
+            tx_proposal: src.get_tx_proposal().into(),
+            tx_public_key: hex::encode(src.get_tx_public_key().get_data()),
+            memo: src.get_memo().to_string(),
+            b58_code: src.get_b58_code().to_string(),
+            bip39_entropy: hex::encode(src.get_bip39_entropy()),
+        }
+    }
+}
+
+#[derive(Deserialize, Default, Debug, JsonSchema)]
+pub struct JsonGenerateTxFromTxOutListRequest {
+    pub account_key: JsonAccountKeyResponse,
+    pub input_list: Vec<JsonUnspentTxOut>,
+    pub receiver: JsonPublicAddress,
+    #[schemars(with = "Option<String>")]
+    pub fee: Option<JsonU64>,
+    #[serde(default)]
+    #[schemars(with = "String")]
+    pub token_id: JsonU64,
+}
+
+impl TryFrom<&JsonGenerateTxFromTxOutListRequest> for api::GenerateTxFromTxOutListRequest {
+    type Error = String;
+
+    fn try_from(src: &JsonGenerateTxFromTxOutListRequest) -> Result<Self, String> {
+        let mut req = api::GenerateTxFromTxOutListRequest::new();
+        req.set_account_key(mc_api::external::AccountKey::try_from(&src.account_key)?);
+        req.set_input_list(utxos_from_json(&src.input_list)?);
+        req.set_receiver(PublicAddress::try_from(&src.receiver)?);
+        req.set_fee(src.fee.map(u64::from).unwrap_or_default());
+        req.set_token_id(src.token_id.into());
+        Ok(req)
+    }
+}
+
+#[derive(Deserialize, Default, Debug, JsonSchema)]
+pub struct JsonGenerateBurnRedemptionTxRequest {
+    pub input_list: Vec<JsonUnspentTxOut>,
+    #[schemars(with = "String")]
+    pub burn_amount: JsonU64,
+    #[schemars(with = "Option<String>")]
+    pub fee: Option<JsonU64>,
+    pub tombstone: Option<u64>,
+    #[serde(default)]
+    #[schemars(with = "String")]
+    pub token_id: JsonU64,
+    /// Hex encoded 64 byte redemption memo. Empty for an all-zeroes memo.
+    #[serde(default)]
+    pub redemption_memo: String,
+    #[serde(default)]
+    pub enable_destination_memo: bool,
+}
+
+#[derive(Deserialize, Default, Debug, JsonSchema)]
+pub struct JsonGenerateReplacementTxRequest {
+    pub tx_proposal: JsonTxProposal,
+    #[schemars(with = "Option<String>")]
+    pub fee: Option<JsonU64>,
+    pub tombstone: Option<u64>,
+}
+
+#[derive(Deserialize, Serialize, Default, Debug, JsonSchema)]
+pub struct JsonSwapQuote {
+    #[schemars(with = "String")]
+    pub base_token_id: JsonU64,
+    #[schemars(with = "String")]
+    pub base_value: JsonU64,
+    #[schemars(with = "String")]
+    pub counter_token_id: JsonU64,
+    #[schemars(with = "String")]
+    pub counter_value: JsonU64,
+    pub partial_fill: bool,
+    #[schemars(with = "String")]
+    pub min_fill_value: JsonU64,
+}
+
+impl From<&api::SwapQuote> for JsonSwapQuote {
+    fn from(src: &api::SwapQuote) -> Self {
+        Self {
+            base_token_id: JsonU64(src.get_base_token_id()),
+            base_value: JsonU64(src.get_base_value()),
+            counter_token_id: JsonU64(src.get_counter_token_id()),
+            counter_value: JsonU64(src.get_counter_value()),
+            partial_fill: src.get_partial_fill(),
+            min_fill_value: JsonU64(src.get_min_fill_value()),
+        }
+    }
+}
+
+#[derive(Deserialize, Default, Debug, JsonSchema)]
+pub struct JsonGenerateSwapRequest {
+    /// The output offered in the swap. Its token is the base token.
+    pub utxo: JsonUnspentTxOut,
+    #[schemars(with = "String")]
+    pub base_value: JsonU64,
+    #[schemars(with = "String")]
+    pub counter_token_id: JsonU64,
+    #[schemars(with = "String")]
+    pub counter_value: JsonU64,
+    #[serde(default)]
+    pub allow_partial_fill: bool,
+    #[serde(default)]
+    #[schemars(with = "String")]
+    pub min_fill_value: JsonU64,
+    pub tombstone: Option<u64>,
+}
+
+#[derive(Serialize, Default, Debug, JsonSchema)]
+pub struct JsonGenerateSwapResponse {
+    /// Hex encoded protobuf `SignedContingentInput`
+    pub sci: String,
+    pub quote: JsonSwapQuote,
+}
+
+impl TryFrom<&api::GenerateSwapResponse> for JsonGenerateSwapResponse {
+    type Error = String;
+
+    fn try_from(src: &api::GenerateSwapResponse) -> Result<Self, String> {
+        Ok(Self {
+            sci: encode_proto(src.get_sci())?,
+            quote: src.get_quote().into(),
+        })
+    }
+}
+
+#[derive(Deserialize, Default, Debug, JsonSchema)]
+pub struct JsonGetSwapFillsRequest {
+    /// Hex encoded protobuf `SignedContingentInput`s
+    pub sci_list: Vec<String>,
+    #[schemars(with = "String")]
+    pub base_token_id: JsonU64,
+    #[schemars(with = "String")]
+    pub counter_token_id: JsonU64,
+    #[schemars(with = "String")]
+    pub base_value: JsonU64,
+}
+
+#[derive(Serialize, Default, Debug, JsonSchema)]
+pub struct JsonSwapOffer {
+    pub quote: JsonSwapQuote,
+    pub error: String,
+}
+
+#[derive(Deserialize, Serialize, Default, Debug, JsonSchema)]
+pub struct JsonSwapFill {
+    pub index: u64,
+    #[schemars(with = "String")]
+    pub base_value: JsonU64,
+    #[schemars(with = "String")]
+    pub counter_value: JsonU64,
+}
+
+#[derive(Serialize, Default, Debug, JsonSchema)]
+pub struct JsonGetSwapFillsResponse {
+    pub offer_list: Vec<JsonSwapOffer>,
+    pub fill_list: Vec<JsonSwapFill>,
+}
+
+impl From<&api::GetSwapFillsResponse> for JsonGetSwapFillsResponse {
+    fn from(src: &api::GetSwapFillsResponse) -> Self {
+        Self {
+            offer_list: src
+                .get_offer_list()
+                .iter()
+                .map(|offer| JsonSwapOffer {
+                    quote: offer.get_quote().into(),
+                    error: offer.get_error().to_string(),
+                })
+                .collect(),
+            fill_list: src
+                .get_fill_list()
+                .iter()
+                .map(|fill| JsonSwapFill {
+                    index: fill.get_index(),
+                    base_value: JsonU64(fill.get_base_value()),
+                    counter_value: JsonU64(fill.get_counter_value()),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Deserialize, Default, Debug, JsonSchema)]
+pub struct JsonGenerateSwapFillTxRequest {
+    /// Hex encoded protobuf `SignedContingentInput`s to fill
+    pub sci_list: Vec<String>,
+    /// Base value to take from each SCI, zero for a full fill
+    #[schemars(with = "Vec<String>")]
+    pub base_value_list: Vec<JsonU64>,
+    pub input_list: Vec<JsonUnspentTxOut>,
+    #[schemars(with = "Option<String>")]
+    pub fee: Option<JsonU64>,
+    pub tombstone: Option<u64>,
+}
+
+#[derive(Deserialize, Default, Debug, JsonSchema)]
+pub struct JsonGenerateGiftCodeFundingTxRequest {
+    pub input_list: Vec<JsonUnspentTxOut>,
+    #[schemars(with = "String")]
+    pub value: JsonU64,
+    #[serde(default)]
+    #[schemars(with = "String")]
+    pub token_id: JsonU64,
+    #[schemars(with = "Option<String>")]
+    pub fee: Option<JsonU64>,
+    pub tombstone: Option<u64>,
+    #[serde(default)]
+    pub memo: String,
+}
+
+#[derive(Serialize, Default, Debug, JsonSchema)]
+pub struct JsonGenerateGiftCodeFundingTxResponse {
+    pub tx_proposal: JsonTxProposal,
+    /// Hex encoded public key of the funding TxOut, used to create the code
+    pub tx_public_key: String,
+}
+
+impl From<&api::GenerateGiftCodeFundingTxResponse> for JsonGenerateGiftCodeFundingTxResponse {
+    fn from(src: &api::GenerateGiftCodeFundingTxResponse) -> Self {
+        Self {
+            tx_proposal: src.get_tx_proposal().into(),
+            tx_public_key: hex::encode(src.get_tx_public_key().get_data()),
+        }
+    }
+}
+
+#[derive(Deserialize, Default, Debug, JsonSchema)]
+pub struct JsonCreateGiftCodeRequest {
+    pub tx_public_key: String,
+}
+
+#[derive(Serialize, Default, Debug, JsonSchema)]
+pub struct JsonGiftCodeResponse {
+    pub b58_code: String,
+    /// One of "unclaimed", "claimed" or "cancelled".
+    pub status: String,
+    #[schemars(with = "String")]
+    pub value: JsonU64,
+    #[schemars(with = "String")]
+    pub token_id: JsonU64,
+}
+
+fn gift_code_status_str(src: api::GiftCodeStatus) -> &'static str {
+    match src {
+        api::GiftCodeStatus::GiftCodeUnclaimed => "unclaimed",
+        api::GiftCodeStatus::GiftCodeClaimed => "claimed",
+        api::GiftCodeStatus::GiftCodeCancelled => "cancelled",
+    }
+}
+
+impl From<&api::CreateGiftCodeResponse> for JsonGiftCodeResponse {
+    fn from(src: &api::CreateGiftCodeResponse) -> Self {
+        Self {
+            b58_code: src.get_b58_code().to_string(),
+            status: gift_code_status_str(api::GiftCodeStatus::GiftCodeUnclaimed).to_owned(),
+            value: JsonU64(src.get_value()),
+            token_id: JsonU64(src.get_token_id()),
+        }
+    }
+}
+
+impl JsonGiftCodeResponse {
+    /// The status response does not echo the code, so it is taken from the
+    /// request.
+    pub fn new(src: &api::GetGiftCodeStatusResponse, b58_code: &str) -> Self {
+        Self {
+            b58_code: b58_code.to_string(),
+            status: gift_code_status_str(src.get_status()).to_owned(),
+            value: JsonU64(src.get_value()),
+            token_id: JsonU64(src.get_token_id()),
+        }
+    }
+}
+
+#[derive(Deserialize, Default, Debug, JsonSchema)]
+pub struct JsonGenerateGiftCodeClaimTxRequest {
+    pub b58_code: String,
+    #[schemars(with = "Option<String>")]
+    pub fee: Option<JsonU64>,
+    pub tombstone: Option<u64>,
+    #[serde(default)]
+    pub memo: String,
+}
+
+#[derive(Deserialize, Default, Debug, JsonSchema)]
+pub struct JsonGenerateGiftCodeCancellationTxRequest {
+    pub b58_code: String,
+    #[schemars(with = "Option<String>")]
+    pub fee: Option<JsonU64>,
+    pub tombstone: Option<u64>,
+}
+
+#[derive(Serialize, Default, Debug, JsonSchema)]
+pub struct JsonNetworkStatusResponse {
+    #[schemars(with = "String")]
+    pub network_highest_block_index: JsonU64,
+    #[schemars(with = "BTreeMap<String, String>")]
+    pub peer_block_index_map: BTreeMap<String, JsonU64>,
+    #[schemars(with = "String")]
+    pub local_block_index: JsonU64,
+    pub is_behind: bool,
+    /// Block version new transactions should be built for
+    pub network_block_version: u32,
+    /// Map of token id to minimum fee
+    #[schemars(with = "BTreeMap<String, String>")]
+    pub minimum_fees: BTreeMap<String, JsonU64>,
+}
+
+impl From<&api::GetNetworkStatusResponse> for JsonNetworkStatusResponse {
+    fn from(src: &api::GetNetworkStatusResponse) -> Self {
+        let last_block_info = src.get_last_block_info();
+        Self {
+            network_highest_block_index: JsonU64(src.get_network_highest_block_index()),
+            peer_block_index_map: src
+                .get_peer_block_index_map()
+                .iter()
+                .map(|(responder_id, block_index)| (responder_id.clone(), JsonU64(*block_index)))
+                .collect(),
+            local_block_index: JsonU64(src.get_local_block_index()),
+            is_behind: src.get_is_behind(),
+            network_block_version: last_block_info.get_network_block_version(),
+            minimum_fees: last_block_info
+                .get_minimum_fees()
+                .iter()
+                .map(|(token_id, fee)| (token_id.to_string(), JsonU64(*fee)))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize, Default, Debug, JsonSchema)]
+pub struct JsonMonitorEvent {
+    /// One of "tx_out_received", "tx_out_spent", "balance_changed" or
+    /// "synced".
+    pub event_type: String,
+    pub block: u64,
+    /// Set for "tx_out_received" and "tx_out_spent" events.
+    pub tx_out: Option<JsonProcessedTxOut>,
+    #[schemars(with = "String")]
+    pub token_id: JsonU64,
+    /// Set for "balance_changed" events.
+    #[schemars(with = "String")]
+    pub balance: JsonU64,
+}
+
+impl From<&api::MonitorEvent> for JsonMonitorEvent {
+    fn from(src: &api::MonitorEvent) -> Self {
+        let event_type_str = match src.get_event_type() {
+            api::MonitorEventType::MonitorTxOutReceived => "tx_out_received",
+            api::MonitorEventType::MonitorTxOutSpent => "tx_out_spent",
+            api::MonitorEventType::MonitorBalanceChanged => "balance_changed",
+            api::MonitorEventType::MonitorSynced => "synced",
+        };
+
+        Self {
+            event_type: event_type_str.to_owned(),
+            block: src.get_block(),
+            tx_out: src.tx_out.as_ref().map(JsonProcessedTxOut::from),
+            token_id: JsonU64(src.get_token_id()),
+            balance: JsonU64(src.get_balance()),
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -1557,4 +2545,46 @@ mod test {
         );
         assert_eq!(proto_proposal, proto2);
     }
+
+    /// Token ids survive a round trip through the JSON types.
+    #[test]
+    fn test_token_id_conversion() {
+        let mut utxo = api::UnspentTxOut::new();
+        utxo.set_value(1234);
+        utxo.set_token_id(7);
+
+        let json_utxo = JsonUnspentTxOut::from(&utxo);
+        assert_eq!(json_utxo.token_id, JsonU64(7));
+        assert_eq!(api::UnspentTxOut::try_from(&json_utxo).unwrap(), utxo);
+
+        let mut prefix = TxPrefix::new();
+        prefix.set_fee(400);
+        prefix.set_tombstone_block(10);
+        prefix.set_fee_token_id(7);
+
+        let json_prefix = JsonTxPrefix::from(&prefix);
+        assert_eq!(json_prefix.fee_token_id, JsonU64(7));
+        assert_eq!(TxPrefix::try_from(&json_prefix).unwrap(), prefix);
+
+        // Requests written before token ids were supported default to MOB.
+        let json_utxo: JsonUnspentTxOut = serde_json::from_str(
+            r#"{
+                "tx_out": {
+                    "masked_amount": null,
+                    "target_key": "",
+                    "public_key": "",
+                    "e_fog_hint": "",
+                    "e_memo": ""
+                },
+                "subaddress_index": 0,
+                "key_image": "",
+                "value": "1234",
+                "attempted_spend_height": 0,
+                "attempted_spend_tombstone": 0,
+                "monitor_id": ""
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(json_utxo.token_id, JsonU64(0));
+    }
 }
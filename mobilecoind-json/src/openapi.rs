@@ -0,0 +1,405 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! OpenAPI description of the mobilecoind-json REST API.
+//!
+//! Request and response bodies are described by the JSON schemas derived on
+//! the types in [crate::data_types]. The route table below mirrors the routes
+//! mounted by the mobilecoind-json binary and has to be kept in sync with it.
+
+use crate::data_types::*;
+use schemars::{
+    gen::{SchemaGenerator, SchemaSettings},
+    schema::Schema,
+    JsonSchema,
+};
+use serde_json::{json, Map, Value};
+
+/// A route served by mobilecoind-json.
+struct Route {
+    method: &'static str,
+    /// Path in rocket syntax, e.g. `/monitors/<monitor_hex>?<token_id>`.
+    path: String,
+    summary: &'static str,
+    request: Option<fn(&mut SchemaGenerator) -> Schema>,
+    response: Option<fn(&mut SchemaGenerator) -> Schema>,
+    /// The response is a stream of server-sent events rather than one JSON
+    /// document.
+    event_stream: bool,
+}
+
+fn schema<T: JsonSchema>(gen: &mut SchemaGenerator) -> Schema {
+    gen.subschema_for::<T>()
+}
+
+macro_rules! route {
+    ($method:literal $path:literal, $summary:literal, $request:ty => $response:ty) => {
+        Route {
+            method: $method,
+            path: $path.to_string(),
+            summary: $summary,
+            request: Some(schema::<$request>),
+            response: Some(schema::<$response>),
+            event_stream: false,
+        }
+    };
+    ($method:literal $path:literal, $summary:literal, => $response:ty) => {
+        Route {
+            method: $method,
+            path: $path.to_string(),
+            summary: $summary,
+            request: None,
+            response: Some(schema::<$response>),
+            event_stream: false,
+        }
+    };
+    ($method:literal $path:literal, $summary:literal, $request:ty =>) => {
+        Route {
+            method: $method,
+            path: $path.to_string(),
+            summary: $summary,
+            request: Some(schema::<$request>),
+            response: None,
+            event_stream: false,
+        }
+    };
+    ($method:literal $path:literal, $summary:literal, =>) => {
+        Route {
+            method: $method,
+            path: $path.to_string(),
+            summary: $summary,
+            request: None,
+            response: None,
+            event_stream: false,
+        }
+    };
+}
+
+const MONITOR_SUBADDRESS: &str = "/monitors/<monitor_hex>/subaddresses/<subaddress_index>";
+
+fn routes() -> Vec<Route> {
+    let mut routes = vec![
+        route!("post" "/set-password", "Set the password for the mobilecoind-db",
+            JsonPasswordRequest => JsonPasswordResponse),
+        route!("post" "/unlock-db", "Unlock a previously-encrypted mobilecoind-db",
+            JsonUnlockDbRequest => JsonUnlockDbResponse),
+        route!("get" "/db-encryption-status", "Get the encryption status of the mobilecoind-db",
+            => JsonDbEncryptionStatusResponse),
+        route!("get" "/version", "Get the mobilecoind version",
+            => JsonMobilecoindVersionResponse),
+        route!("post" "/entropy", "Generate a new root entropy",
+            => JsonRootEntropyResponse),
+        route!("get" "/entropy/<root_entropy>", "Get the account key for a root entropy",
+            => JsonAccountKeyResponse),
+        route!("post" "/mnemonic", "Generate a new mnemonic",
+            => JsonMnemonicResponse),
+        route!("post" "/account-key-from-mnemonic", "Get the account key for a mnemonic",
+            JsonMnemonicResponse => JsonAccountKeyResponse),
+        route!("post" "/monitors", "Add a monitor",
+            JsonMonitorRequest => JsonMonitorResponse),
+        route!("get" "/monitors", "List monitors",
+            => JsonMonitorListResponse),
+        route!("get" "/monitors/<monitor_hex>", "Get the status of a monitor",
+            => JsonMonitorStatusResponse),
+        route!("put" "/monitors/<monitor_hex>", "Change the subaddress range and first block of a monitor",
+            JsonUpdateMonitorRequest =>),
+        route!("delete" "/monitors/<monitor_hex>", "Remove a monitor",
+            =>),
+        route!("post" "/monitors/<monitor_hex>/rescan", "Sync a monitor again from a given block",
+            JsonRescanMonitorRequest =>),
+        route!("post" "/monitors/<monitor_hex>/key-images", "Import key images for a view-only monitor",
+            JsonImportKeyImagesRequest => JsonImportKeyImagesResponse),
+        route!("put" "/monitors/<monitor_hex>/webhook", "Set the webhook payment events are delivered to",
+            JsonSetMonitorWebhookRequest =>),
+        Route {
+            method: "get",
+            path: "/monitors/<monitor_hex>/events?<first_block>".to_string(),
+            summary: "Stream monitor events as server-sent events",
+            request: None,
+            response: Some(schema::<JsonMonitorEvent>),
+            event_stream: true,
+        },
+        route!("get" "/monitors/<monitor_hex>/processed-block/<block_num>", "Get the outputs a monitor received and spent in a block",
+            => JsonProcessedBlockResponse),
+        route!("get" "/monitors/<monitor_hex>/sent-txs?<offset>&<limit>", "List transactions sent by a monitor, most recent first",
+            => JsonSentTxListResponse),
+        route!("get" "/monitors/<monitor_hex>/payment-requests", "List the payment requests issued by a monitor",
+            => JsonPaymentRequestListResponse),
+        route!("get" "/monitors/<monitor_hex>/payment-requests/<payment_request_id>", "Get a payment request and the payments received for it",
+            => JsonPaymentRequest),
+        route!("post" "/monitors/<monitor_hex>/tx-status-as-receiver", "Check a receiver receipt, validating its confirmation number",
+            JsonReceiverTxReceipt => JsonStatusResponse),
+        route!("post" "/monitors/<monitor_hex>/gift-codes", "Create a gift code from a landed funding transaction",
+            JsonCreateGiftCodeRequest => JsonGiftCodeResponse),
+        route!("post" "/codes/request", "Create a request code",
+            JsonCreateRequestCodeRequest => JsonCreateRequestCodeResponse),
+        route!("get" "/codes/request/<b58_code>", "Parse a request code",
+            => JsonParseRequestCodeResponse),
+        route!("post" "/codes/address", "Create an address code",
+            JsonCreateAddressCodeRequest => JsonCreateAddressCodeResponse),
+        route!("get" "/codes/address/<b58_code>", "Parse an address code",
+            => JsonParseAddressCodeResponse),
+        route!("post" "/codes/transfer", "Create a transfer code",
+            JsonCreateTransferCodeRequest => JsonCreateTransferCodeResponse),
+        route!("get" "/codes/transfer/<b58_code>", "Parse a transfer code",
+            => JsonParseTransferCodeResponse),
+        route!("post" "/generate-tx-from-tx-out-list", "Build a transaction spending outputs of an unmonitored account",
+            JsonGenerateTxFromTxOutListRequest => JsonCreateTxProposalResponse),
+        route!("post" "/swaps/fills", "Quote swap offers and pick fills",
+            JsonGetSwapFillsRequest => JsonGetSwapFillsResponse),
+        route!("get" "/gift-codes/<b58_code>", "Get the status of a gift code",
+            => JsonGiftCodeResponse),
+        route!("post" "/submit-tx", "Submit a transaction proposal",
+            JsonTxProposalRequest => JsonSubmitTxResponse),
+        route!("post" "/tx/status-as-sender", "Check the status of a submitted transaction",
+            JsonSubmitTxResponse => JsonStatusResponse),
+        route!("post" "/tx/status-as-receiver", "Check whether the output of a receiver receipt landed",
+            JsonReceiverTxReceipt => JsonStatusResponse),
+        route!("get" "/ledger/local", "Get information about the local ledger",
+            => JsonLedgerInfoResponse),
+        route!("get" "/ledger/blocks/<block_num>/header", "Get a block header",
+            => JsonBlockInfoResponse),
+        route!("get" "/ledger/blocks/<block_num>", "Get the contents of a block",
+            => JsonBlockDetailsResponse),
+        route!("get" "/network-status", "Get the sync status of the local ledger and current minimum fees",
+            => JsonNetworkStatusResponse),
+        route!("get" "/tx-out/<public_key_hex>/block-index", "Get the block index of an output",
+            => JsonBlockIndexByTxPubKeyResponse),
+        route!("post" "/tx-out/proof-of-membership", "Get membership proofs for outputs",
+            JsonMembershipProofRequest => JsonMembershipProofResponse),
+        route!("post" "/tx-out/mixin", "Get outputs for use as mixins",
+            JsonMixinRequest => JsonMixinResponse),
+    ];
+
+    // Routes acting on a monitor's subaddress.
+    let subaddress_routes = [
+        route!("get" "/balance?<token_id>", "Get the balance of a subaddress in one token",
+            => JsonBalanceResponse),
+        route!("get" "/utxos?<token_id>", "List the unspent outputs of a subaddress in one token",
+            => JsonUtxosResponse),
+        route!("get" "/public-address", "Get the public address of a subaddress",
+            => JsonPublicAddressResponse),
+        route!("post" "/build-and-submit", "Pay a request code",
+            JsonSendPaymentRequest => JsonSendPaymentResponse),
+        route!("post" "/pay-address-code", "Pay an address code",
+            JsonPayAddressCodeRequest => JsonSendPaymentResponse),
+        route!("post" "/payment-requests", "Issue a payment request whose payments are tracked",
+            JsonCreatePaymentRequestRequest => JsonPaymentRequest),
+        route!("post" "/generate-request-code-transaction", "Build a transaction paying a request code",
+            JsonCreateTxProposalRequest => JsonCreateTxProposalResponse),
+        route!("post" "/generate-tx", "Build a transaction paying a list of outlays",
+            JsonGenerateTxRequest => JsonCreateTxProposalResponse),
+        route!("post" "/generate-optimization-tx", "Build a transaction merging small outputs",
+            JsonGenerateOptimizationTxRequest => JsonCreateTxProposalResponse),
+        route!("post" "/generate-transfer-code-tx", "Build a transaction funding a transfer code",
+            JsonGenerateTransferCodeTxRequest => JsonGenerateTransferCodeTxResponse),
+        route!("post" "/generate-burn-redemption-tx", "Build a transaction burning funds",
+            JsonGenerateBurnRedemptionTxRequest => JsonCreateTxProposalResponse),
+        route!("post" "/generate-replacement-tx", "Build a transaction replacing a pending one",
+            JsonGenerateReplacementTxRequest => JsonCreateTxProposalResponse),
+        route!("post" "/generate-swap", "Offer an output as a signed contingent input",
+            JsonGenerateSwapRequest => JsonGenerateSwapResponse),
+        route!("post" "/generate-swap-fill-tx", "Build a transaction filling swap offers",
+            JsonGenerateSwapFillTxRequest => JsonCreateTxProposalResponse),
+        route!("post" "/generate-gift-code-funding-tx", "Build a transaction funding a gift code",
+            JsonGenerateGiftCodeFundingTxRequest => JsonGenerateGiftCodeFundingTxResponse),
+        route!("post" "/generate-gift-code-claim-tx", "Build a transaction claiming a gift code",
+            JsonGenerateGiftCodeClaimTxRequest => JsonCreateTxProposalResponse),
+        route!("post" "/generate-gift-code-cancellation-tx", "Build a transaction cancelling a gift code",
+            JsonGenerateGiftCodeCancellationTxRequest => JsonCreateTxProposalResponse),
+    ];
+    routes.extend(subaddress_routes.into_iter().map(|route| Route {
+        path: format!("{}{}", MONITOR_SUBADDRESS, route.path),
+        ..route
+    }));
+
+    routes
+}
+
+/// Split a rocket route path into an OpenAPI path template and its path and
+/// query parameter names.
+fn parse_path(path: &str) -> (String, Vec<&str>, Vec<&str>) {
+    let (path, query) = path.split_once('?').unwrap_or((path, ""));
+
+    let mut path_params = Vec::new();
+    let template = path
+        .split('/')
+        .map(|segment| match segment.strip_prefix('<') {
+            Some(name) => {
+                let name = name.trim_end_matches('>');
+                path_params.push(name);
+                format!("{{{}}}", name)
+            }
+            None => segment.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("/");
+
+    let query_params = query
+        .split('&')
+        .filter(|param| !param.is_empty())
+        .map(|param| param.trim_start_matches('<').trim_end_matches('>'))
+        .collect();
+
+    (template, path_params, query_params)
+}
+
+fn operation(gen: &mut SchemaGenerator, route: &Route) -> Value {
+    let (_, path_params, query_params) = parse_path(&route.path);
+
+    let parameters = path_params
+        .into_iter()
+        .map(|name| json!({ "name": name, "in": "path", "required": true, "schema": { "type": "string" } }))
+        .chain(query_params.into_iter().map(|name| {
+            json!({ "name": name, "in": "query", "required": false, "schema": { "type": "integer", "format": "uint64" } })
+        }))
+        .collect::<Vec<_>>();
+
+    let mut operation = json!({
+        "summary": route.summary,
+        "parameters": parameters,
+    });
+
+    if let Some(request) = route.request {
+        operation["requestBody"] = json!({
+            "required": true,
+            "content": { "application/json": { "schema": request(gen) } },
+        });
+    }
+
+    let content_type = if route.event_stream {
+        "text/event-stream"
+    } else {
+        "application/json"
+    };
+    let success = match route.response {
+        Some(response) => json!({
+            "description": "Success",
+            "content": { content_type: { "schema": response(gen) } },
+        }),
+        None => json!({ "description": "Success" }),
+    };
+    operation["responses"] = json!({
+        "200": success,
+        "400": {
+            "description": "The request could not be handled, or mobilecoind returned an error",
+            "content": { "text/plain": { "schema": { "type": "string" } } },
+        },
+    });
+
+    operation
+}
+
+/// The method and rocket path of every route described by the OpenAPI
+/// document.
+pub fn documented_routes() -> Vec<(&'static str, String)> {
+    routes()
+        .into_iter()
+        .map(|route| (route.method, route.path))
+        .collect()
+}
+
+/// Build the OpenAPI 3 document describing every route of mobilecoind-json.
+pub fn openapi_document() -> Value {
+    let mut gen = SchemaSettings::openapi3().into_generator();
+
+    let mut paths = Map::new();
+    for route in routes() {
+        let (template, _, _) = parse_path(&route.path);
+        let operation = operation(&mut gen, &route);
+        paths
+            .entry(template)
+            .or_insert_with(|| json!({}))
+            .as_object_mut()
+            .expect("path items are objects")
+            .insert(route.method.to_string(), operation);
+    }
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "mobilecoind-json",
+            "description": "REST frontend for mobilecoind. Amounts and token ids are decimal strings.",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "paths": paths,
+        "components": { "schemas": gen.take_definitions() },
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Collect every `$ref` in a document.
+    fn refs<'a>(value: &'a Value, out: &mut Vec<&'a str>) {
+        match value {
+            Value::Object(map) => {
+                for (key, value) in map {
+                    match (key.as_str(), value) {
+                        ("$ref", Value::String(reference)) => out.push(reference),
+                        _ => refs(value, out),
+                    }
+                }
+            }
+            Value::Array(values) => values.iter().for_each(|value| refs(value, out)),
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn test_parse_path() {
+        assert_eq!(
+            parse_path("/monitors/<monitor_hex>/sent-txs?<offset>&<limit>"),
+            (
+                "/monitors/{monitor_hex}/sent-txs".to_string(),
+                vec!["monitor_hex"],
+                vec!["offset", "limit"]
+            )
+        );
+        assert_eq!(
+            parse_path("/version"),
+            ("/version".to_string(), vec![], vec![])
+        );
+    }
+
+    #[test]
+    fn test_openapi_document() {
+        let document = openapi_document();
+
+        let paths = document["paths"].as_object().unwrap();
+        let num_operations: usize = paths
+            .values()
+            .map(|item| item.as_object().unwrap().len())
+            .sum();
+        assert_eq!(num_operations, routes().len());
+
+        let balance = &paths["/monitors/{monitor_hex}/subaddresses/{subaddress_index}/balance"];
+        let parameters = balance["get"]["parameters"].as_array().unwrap();
+        assert_eq!(parameters.len(), 3);
+        assert_eq!(parameters[2]["name"], "token_id");
+
+        // Every referenced schema is defined, and token ids are described on the
+        // amounts that carry them.
+        let schemas = document["components"]["schemas"].as_object().unwrap();
+        let mut references = Vec::new();
+        refs(&document, &mut references);
+        assert!(!references.is_empty());
+        for reference in references {
+            let name = reference
+                .strip_prefix("#/components/schemas/")
+                .unwrap_or_else(|| panic!("unexpected reference {}", reference));
+            assert!(schemas.contains_key(name), "missing schema {}", name);
+        }
+        for name in [
+            "JsonUnspentTxOut",
+            "JsonBalanceResponse",
+            "JsonGenerateTxRequest",
+        ] {
+            assert_eq!(
+                schemas[name]["properties"]["token_id"]["type"], "string",
+                "{} token_id",
+                name
+            );
+        }
+    }
+}
@@ -0,0 +1,250 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! Accounting export of the activity of a monitor.
+//!
+//! The ExportMonitorActivity API call returns the TxOuts received and spent by
+//! a monitor as `MonitorActivityRecord`s. This module names the memo types
+//! and counterparties reported in these records, and renders the records as
+//! CSV or JSON Lines.
+
+use clap::ValueEnum;
+use hex_fmt::HexFmt;
+use mc_account_keys::ShortAddressHash;
+use mc_mobilecoind_api as api;
+use mc_transaction_extra::{HandledMemo, MemoType};
+use serde_json::json;
+use std::io::{self, Write};
+
+/// Memo type reported for TxOuts whose memo could not be decoded.
+pub const UNKNOWN_MEMO_TYPE: &str = "unknown";
+
+/// Names of the exported columns, in the order they are written in.
+pub const COLUMNS: [&str; 11] = [
+    "block_index",
+    "timestamp",
+    "direction",
+    "token_id",
+    "value",
+    "subaddress_index",
+    "public_key",
+    "key_image",
+    "memo_type",
+    "counterparty_address_hash",
+    "status",
+];
+
+/// The formats an activity export can be written in.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+pub enum ActivityExportFormat {
+    /// Comma-separated values, starting with a header row.
+    Csv,
+
+    /// One JSON object per line.
+    Jsonl,
+}
+
+/// The name of a memo type, as reported in activity exports.
+pub fn memo_type_name(memo_type: &MemoType) -> &'static str {
+    match memo_type {
+        MemoType::AuthenticatedSender(_) => "authenticated_sender",
+        MemoType::AuthenticatedSenderWithPaymentRequestId(_) => {
+            "authenticated_sender_with_payment_request_id"
+        }
+        MemoType::AuthenticatedSenderWithPaymentIntentId(_) => {
+            "authenticated_sender_with_payment_intent_id"
+        }
+        MemoType::AuthenticatedSenderWithInvoice(_) => "authenticated_sender_with_invoice",
+        MemoType::BurnRedemption(_) => "burn_redemption",
+        MemoType::Destination(_) => "destination",
+        MemoType::DestinationWithPaymentRequestId(_) => "destination_with_payment_request_id",
+        MemoType::DestinationWithPaymentIntentId(_) => "destination_with_payment_intent_id",
+        MemoType::DestinationWithInvoice(_) => "destination_with_invoice",
+        MemoType::GiftCodeCancellation(_) => "gift_code_cancellation",
+        MemoType::GiftCodeFunding(_) => "gift_code_funding",
+        MemoType::GiftCodeSender(_) => "gift_code_sender",
+        MemoType::Unused(_) => "unused",
+    }
+}
+
+/// The counterparty named by a memo: the sender of an authenticated sender
+/// memo, or the recipient of a destination memo.
+pub fn counterparty_address_hash(handled: &HandledMemo) -> Option<ShortAddressHash> {
+    match &handled.memo_type {
+        MemoType::Destination(memo) => Some(memo.get_address_hash().clone()),
+        MemoType::DestinationWithPaymentRequestId(memo) => Some(memo.get_address_hash().clone()),
+        MemoType::DestinationWithPaymentIntentId(memo) => Some(memo.get_address_hash().clone()),
+        MemoType::DestinationWithInvoice(memo) => Some(memo.get_address_hash().clone()),
+        _ => handled.sender_address_hash.clone(),
+    }
+}
+
+/// Writes activity records to an underlying writer, in a given format.
+pub struct ActivityWriter<W: Write> {
+    format: ActivityExportFormat,
+    writer: W,
+}
+
+impl<W: Write> ActivityWriter<W> {
+    /// Create a new writer, writing the CSV header if needed.
+    pub fn new(format: ActivityExportFormat, mut writer: W) -> io::Result<Self> {
+        if format == ActivityExportFormat::Csv {
+            writeln!(writer, "{}", COLUMNS.join(","))?;
+        }
+        Ok(Self { format, writer })
+    }
+
+    /// Write a single record.
+    pub fn write_record(&mut self, record: &api::MonitorActivityRecord) -> io::Result<()> {
+        let direction = match record.get_direction() {
+            api::ProcessedTxOutDirection::Received => "received",
+            api::ProcessedTxOutDirection::Spent => "spent",
+            api::ProcessedTxOutDirection::Invalid => "invalid",
+        };
+        let status = match record.get_status() {
+            api::MonitorActivityStatus::ActivityUnspent => "unspent",
+            api::MonitorActivityStatus::ActivitySpent => "spent",
+        };
+        let public_key = HexFmt(record.get_public_key().get_data()).to_string();
+        let key_image = HexFmt(record.get_key_image().get_data()).to_string();
+        let counterparty = HexFmt(record.get_counterparty_address_hash()).to_string();
+
+        match self.format {
+            ActivityExportFormat::Csv => {
+                // None of the fields can contain a comma or a quote, so they do not need
+                // escaping.
+                writeln!(
+                    self.writer,
+                    "{},{},{},{},{},{},{},{},{},{},{}",
+                    record.block_index,
+                    record.timestamp,
+                    direction,
+                    record.token_id,
+                    record.value,
+                    record.subaddress_index,
+                    public_key,
+                    key_image,
+                    record.get_memo_type(),
+                    counterparty,
+                    status,
+                )
+            }
+            ActivityExportFormat::Jsonl => {
+                let row = json!({
+                    "block_index": record.block_index,
+                    "timestamp": record.timestamp,
+                    "direction": direction,
+                    "token_id": record.token_id,
+                    "value": record.value,
+                    "subaddress_index": record.subaddress_index,
+                    "public_key": public_key,
+                    "key_image": key_image,
+                    "memo_type": record.get_memo_type(),
+                    "counterparty_address_hash": counterparty,
+                    "status": status,
+                });
+                writeln!(self.writer, "{}", row)
+            }
+        }
+    }
+
+    /// Flush the underlying writer and return it.
+    pub fn into_inner(mut self) -> io::Result<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use mc_account_keys::AccountKey;
+    use mc_transaction_extra::{DestinationMemo, MemoClassification, UnusedMemo};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    fn test_record() -> api::MonitorActivityRecord {
+        let mut record = api::MonitorActivityRecord::new();
+        record.set_block_index(12);
+        record.set_timestamp(1_650_000_000);
+        record.set_direction(api::ProcessedTxOutDirection::Received);
+        record.set_token_id(1);
+        record.set_value(1_000_000);
+        record.set_subaddress_index(3);
+        record.mut_public_key().set_data(vec![0xab; 32]);
+        record.mut_key_image().set_data(vec![0xcd; 32]);
+        record.set_memo_type("authenticated_sender".to_owned());
+        record.set_counterparty_address_hash(vec![0x01; 16]);
+        record.set_status(api::MonitorActivityStatus::ActivitySpent);
+        record
+    }
+
+    #[test]
+    fn test_write_csv() {
+        let mut writer = ActivityWriter::new(ActivityExportFormat::Csv, Vec::new()).unwrap();
+        writer.write_record(&test_record()).unwrap();
+        let output = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+
+        let lines = output.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0], COLUMNS.join(","));
+        assert_eq!(
+            lines[1],
+            format!(
+                "12,1650000000,received,1,1000000,3,{},{},authenticated_sender,{},spent",
+                "ab".repeat(32),
+                "cd".repeat(32),
+                "01".repeat(16),
+            )
+        );
+    }
+
+    #[test]
+    fn test_write_jsonl() {
+        let mut writer = ActivityWriter::new(ActivityExportFormat::Jsonl, Vec::new()).unwrap();
+        writer.write_record(&test_record()).unwrap();
+        writer.write_record(&test_record()).unwrap();
+        let output = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+
+        let lines = output.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+        let row: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(row["block_index"], 12);
+        assert_eq!(row["timestamp"], 1_650_000_000u64);
+        assert_eq!(row["direction"], "received");
+        assert_eq!(row["value"], 1_000_000);
+        assert_eq!(row["key_image"], "cd".repeat(32));
+        assert_eq!(row["counterparty_address_hash"], "01".repeat(16));
+        assert_eq!(row["status"], "spent");
+        assert_eq!(row.as_object().unwrap().len(), COLUMNS.len());
+    }
+
+    #[test]
+    fn test_counterparty_address_hash() {
+        let mut rng: StdRng = SeedableRng::from_seed([1u8; 32]);
+        let recipient = AccountKey::random(&mut rng).default_subaddress();
+        let recipient_hash = ShortAddressHash::from(&recipient);
+        let sender_hash =
+            ShortAddressHash::from(&AccountKey::random(&mut rng).default_subaddress());
+
+        // Destination memos name their recipient.
+        let handled = HandledMemo {
+            memo_type: MemoType::Destination(
+                DestinationMemo::new(recipient_hash.clone(), 10, 1).unwrap(),
+            ),
+            classification: MemoClassification::Authenticated,
+            sender_address_hash: None,
+            sender: None,
+        };
+        assert_eq!(memo_type_name(&handled.memo_type), "destination");
+        assert_eq!(counterparty_address_hash(&handled), Some(recipient_hash));
+
+        // Other memos name their sender, if any.
+        let handled = HandledMemo {
+            memo_type: MemoType::Unused(UnusedMemo),
+            classification: MemoClassification::Unauthenticated,
+            sender_address_hash: Some(sender_hash.clone()),
+            sender: None,
+        };
+        assert_eq!(memo_type_name(&handled.memo_type), "unused");
+        assert_eq!(counterparty_address_hash(&handled), Some(sender_hash));
+    }
+}
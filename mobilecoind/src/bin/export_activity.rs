@@ -0,0 +1,150 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+#![deny(missing_docs)]
+
+//! Exports the TxOuts received and spent by a mobilecoind monitor, for
+//! accounting purposes.
+
+use clap::Parser;
+use grpcio::ChannelBuilder;
+use mc_common::logger::create_null_logger;
+use mc_mobilecoind::activity_export::{ActivityExportFormat, ActivityWriter};
+use mc_mobilecoind_api::{self as api, mobilecoind_api_grpc::MobilecoindApiClient, MobilecoindUri};
+use mc_util_grpc::ConnectionUriGrpcioChannel;
+use protobuf::RepeatedField;
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::PathBuf,
+    sync::Arc,
+};
+
+/// Command line config
+#[derive(Clone, Debug, Parser)]
+#[clap(
+    name = "mobilecoind-export-activity",
+    about = "Export the activity of a mobilecoind monitor as CSV or JSON Lines"
+)]
+pub struct Config {
+    /// MobileCoinD URI.
+    #[clap(
+        long,
+        default_value = "insecure-mobilecoind://127.0.0.1/",
+        env = "MC_MOBILECOIND_URI"
+    )]
+    pub mobilecoind_uri: MobilecoindUri,
+
+    /// Hex-encoded id of the monitor to export.
+    #[clap(long, value_parser = parse_monitor_id, env = "MC_MONITOR_ID")]
+    pub monitor_id: Vec<u8>,
+
+    /// First block to export. Defaults to the monitor's first block.
+    #[clap(long, env = "MC_FIRST_BLOCK")]
+    pub first_block: Option<u64>,
+
+    /// Block to stop the export at (exclusive). Defaults to the next block the
+    /// monitor is going to process.
+    #[clap(long, env = "MC_END_BLOCK")]
+    pub end_block: Option<u64>,
+
+    /// Output format.
+    #[clap(long, value_enum, default_value = "csv", env = "MC_FORMAT")]
+    pub format: ActivityExportFormat,
+
+    /// b58-encoded address code of a known contact, against which
+    /// authenticated sender memos are validated. May be repeated.
+    #[clap(long = "contact", env = "MC_CONTACTS", value_delimiter = ',')]
+    pub contacts: Vec<String>,
+
+    /// File to write the export to. Defaults to stdout.
+    #[clap(long, env = "MC_OUTPUT")]
+    pub output: Option<PathBuf>,
+}
+
+fn parse_monitor_id(src: &str) -> Result<Vec<u8>, String> {
+    hex::decode(src).map_err(|err| format!("invalid monitor id: {}", err))
+}
+
+fn main() {
+    let config = Config::parse();
+
+    // The export may be written to stdout, so nothing else is logged there.
+    let logger = create_null_logger();
+
+    let env = Arc::new(grpcio::EnvBuilder::new().cq_count(1).build());
+    let ch = ChannelBuilder::new(env)
+        .max_receive_message_len(std::i32::MAX)
+        .max_send_message_len(std::i32::MAX)
+        .connect_to_uri(&config.mobilecoind_uri, &logger);
+    let client = MobilecoindApiClient::new(ch);
+
+    let contacts = config
+        .contacts
+        .iter()
+        .map(|code| {
+            let wrapper = api::printable::PrintableWrapper::b58_decode(code.clone())
+                .expect("Failed decoding contact address code");
+            assert!(
+                wrapper.has_public_address(),
+                "Contact address code does not contain a public address"
+            );
+            wrapper.get_public_address().clone()
+        })
+        .collect::<Vec<_>>();
+
+    let first_block = match config.first_block {
+        Some(first_block) => first_block,
+        None => {
+            let mut request = api::GetMonitorStatusRequest::new();
+            request.set_monitor_id(config.monitor_id.clone());
+            client
+                .get_monitor_status(&request)
+                .expect("Failed getting monitor status")
+                .get_status()
+                .first_block
+        }
+    };
+
+    let output: Box<dyn Write> = match config.output.as_ref() {
+        Some(path) => Box::new(File::create(path).expect("Failed creating output file")),
+        None => Box::new(io::stdout()),
+    };
+    let mut writer =
+        ActivityWriter::new(config.format, BufWriter::new(output)).expect("Failed writing export");
+
+    // mobilecoind caps the number of blocks exported per call, so the export is
+    // fetched range by range until it stops making progress.
+    let mut next_block = first_block;
+    let mut num_records = 0;
+    loop {
+        let mut request = api::ExportMonitorActivityRequest::new();
+        request.set_monitor_id(config.monitor_id.clone());
+        request.set_first_block(next_block);
+        request.set_end_block(config.end_block.unwrap_or(0));
+        request.set_contacts(RepeatedField::from_vec(contacts.clone()));
+        let response = client
+            .export_monitor_activity(&request)
+            .expect("Failed exporting monitor activity");
+
+        for record in response.get_records() {
+            writer.write_record(record).expect("Failed writing export");
+        }
+        num_records += response.get_records().len();
+
+        if response.get_end_block() <= next_block {
+            break;
+        }
+        next_block = response.get_end_block();
+        if config
+            .end_block
+            .map_or(false, |end_block| next_block >= end_block)
+        {
+            break;
+        }
+    }
+    writer.into_inner().expect("Failed writing export");
+
+    eprintln!(
+        "Exported {} records for blocks [{}, {})",
+        num_records, first_block, next_block
+    );
+}
@@ -134,6 +134,7 @@ fn main() {
                 network_state,
                 listen_uri,
                 config.num_workers,
+                config.get_fog_sync_config(),
                 logger,
             );
 
@@ -3,6 +3,7 @@
 
 //! Configuration parameters for mobilecoind
 
+use crate::fog_sync::FogSyncConfig;
 use clap::Parser;
 use displaydoc::Display;
 use mc_attest_verifier::{MrSignerVerifier, Verifier, DEBUG_ENCLAVE};
@@ -11,6 +12,7 @@ use mc_connection::{ConnectionManager, HardcodedCredentialsProvider, ThickClient
 use mc_consensus_scp::QuorumSet;
 use mc_fog_report_connection::GrpcFogReportConnection;
 use mc_fog_report_resolver::FogResolver;
+use mc_fog_uri::{FogLedgerUri, FogViewUri};
 use mc_mobilecoind_api::MobilecoindUri;
 use mc_sgx_css::Signature;
 use mc_util_parse::{load_css_file, parse_duration_in_seconds};
@@ -90,6 +92,28 @@ pub struct Config {
     #[clap(long, value_parser = load_css_file, env = "MC_FOG_INGEST_ENCLAVE_CSS")]
     pub fog_ingest_enclave_css: Option<Signature>,
 
+    /// Fog view service, used by fog monitors to discover the TxOuts of fog
+    /// accounts without scanning the ledger.
+    #[clap(
+        long,
+        requires_all = ["fog_ledger_uri", "fog_view_enclave_css", "fog_ledger_enclave_css"],
+        env = "MC_FOG_VIEW_URI"
+    )]
+    pub fog_view_uri: Option<FogViewUri>,
+
+    /// Fog ledger service, used by fog monitors to check whether their TxOuts
+    /// were spent.
+    #[clap(long, requires = "fog_view_uri", env = "MC_FOG_LEDGER_URI")]
+    pub fog_ledger_uri: Option<FogLedgerUri>,
+
+    /// Fog view enclave CSS file (needed in order to enable fog monitors).
+    #[clap(long, value_parser = load_css_file, env = "MC_FOG_VIEW_ENCLAVE_CSS")]
+    pub fog_view_enclave_css: Option<Signature>,
+
+    /// Fog ledger enclave CSS file (needed in order to enable fog monitors).
+    #[clap(long, value_parser = load_css_file, env = "MC_FOG_LEDGER_ENCLAVE_CSS")]
+    pub fog_ledger_enclave_css: Option<Signature>,
+
     /// Automatically migrate the ledger db (if it exists) into the most recent
     /// version.
     #[clap(long, env = "MC_LEDGER_DB_MIGRATE")]
@@ -100,6 +124,27 @@ pub struct Config {
     pub ip_info_token: String,
 }
 
+/// Get an attestation verifier for the enclave signed with a given CSS.
+fn get_css_verifier(signature: &Signature) -> Verifier {
+    let mr_signer_verifier = {
+        let mut mr_signer_verifier = MrSignerVerifier::new(
+            signature.mrsigner().into(),
+            signature.product_id(),
+            signature.version(),
+        );
+        mr_signer_verifier.allow_hardening_advisories(&[
+            "INTEL-SA-00334",
+            "INTEL-SA-00615",
+            "INTEL-SA-00657",
+        ]);
+        mr_signer_verifier
+    };
+
+    let mut verifier = Verifier::default();
+    verifier.debug(DEBUG_ENCLAVE).mr_signer(mr_signer_verifier);
+    verifier
+}
+
 fn parse_quorum_set_from_json(src: &str) -> Result<QuorumSet<ResponderId>, String> {
     let quorum_set: QuorumSet<ResponderId> = serde_json::from_str(src)
         .map_err(|err| format!("Error parsing quorum set {}: {:?}", src, err))?;
@@ -167,24 +212,41 @@ impl Config {
     /// Get the attestation verifier used to verify fog reports when sending to
     /// fog recipients
     pub fn get_fog_ingest_verifier(&self) -> Option<Verifier> {
-        self.fog_ingest_enclave_css.as_ref().map(|signature| {
-            let mr_signer_verifier = {
-                let mut mr_signer_verifier = MrSignerVerifier::new(
-                    signature.mrsigner().into(),
-                    signature.product_id(),
-                    signature.version(),
-                );
-                mr_signer_verifier.allow_hardening_advisories(&[
-                    "INTEL-SA-00334",
-                    "INTEL-SA-00615",
-                    "INTEL-SA-00657",
-                ]);
-                mr_signer_verifier
-            };
-
-            let mut verifier = Verifier::default();
-            verifier.debug(DEBUG_ENCLAVE).mr_signer(mr_signer_verifier);
-            verifier
+        self.fog_ingest_enclave_css.as_ref().map(get_css_verifier)
+    }
+
+    /// Get what is needed to sync fog monitors, if fog view and fog ledger
+    /// are configured.
+    pub fn get_fog_sync_config(&self) -> Option<FogSyncConfig> {
+        let view_uri = self.fog_view_uri.clone()?;
+        let ledger_uri = self.fog_ledger_uri.clone()?;
+
+        // Clap ensures the enclave CSS files are provided along with the uris.
+        let view_verifier = get_css_verifier(
+            self.fog_view_enclave_css
+                .as_ref()
+                .expect("missing fog view enclave css"),
+        );
+        let ledger_verifier = get_css_verifier(
+            self.fog_ledger_enclave_css
+                .as_ref()
+                .expect("missing fog ledger enclave css"),
+        );
+
+        let env = Arc::new(
+            grpcio::EnvBuilder::new()
+                .name_prefix("FogMonitor-RPC".to_string())
+                .build(),
+        );
+
+        Some(FogSyncConfig {
+            chain_id: self.peers_config.chain_id.to_owned(),
+            view_uri,
+            ledger_uri,
+            view_verifier,
+            ledger_verifier,
+            grpc_retry_config: Default::default(),
+            env,
         })
     }
 
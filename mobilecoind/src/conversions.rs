@@ -4,18 +4,20 @@
 //! types.
 
 use crate::{
-    payments::{Outlay, TxProposal},
+    payments::{Outlay, TxProposal, UnsignedTxProposal},
+    swaps::{SwapFill, SwapQuote},
     utxo_store::UnspentTxOut,
 };
-use mc_account_keys::PublicAddress;
+use mc_account_keys::{PublicAddress, ViewAccountKey};
 use mc_api::ConversionError;
 use mc_common::HashMap;
+use mc_crypto_keys::{RistrettoPrivate, RistrettoPublic};
 use mc_mobilecoind_api as api;
 use mc_transaction_core::{
     ring_signature::KeyImage,
     tx::{Tx, TxOut},
 };
-use mc_transaction_extra::TxOutConfirmationNumber;
+use mc_transaction_extra::{TxOutConfirmationNumber, UnsignedTx};
 use protobuf::RepeatedField;
 
 impl From<&UnspentTxOut> for api::UnspentTxOut {
@@ -171,6 +173,148 @@ impl TryFrom<&api::TxProposal> for TxProposal {
     }
 }
 
+impl From<&UnsignedTxProposal> for api::UnsignedTxProposal {
+    fn from(src: &UnsignedTxProposal) -> api::UnsignedTxProposal {
+        let mut dst = api::UnsignedTxProposal::new();
+
+        dst.set_input_list(RepeatedField::from_vec(
+            src.utxos.iter().map(|utxo| utxo.into()).collect(),
+        ));
+        dst.set_outlay_list(RepeatedField::from_vec(
+            src.outlays.iter().map(|outlay| outlay.into()).collect(),
+        ));
+        dst.set_unsigned_tx((&src.unsigned_tx).into());
+        dst.set_fee(src.unsigned_tx.tx_prefix.fee);
+        dst.set_outlay_index_to_tx_out_index(
+            src.outlay_index_to_tx_out_index
+                .iter()
+                .map(|(key, val)| (*key as u64, *val as u64))
+                .collect(),
+        );
+        dst.set_outlay_confirmation_numbers(
+            src.outlay_confirmation_numbers
+                .iter()
+                .map(|val| val.to_vec())
+                .collect(),
+        );
+
+        dst
+    }
+}
+
+impl TryFrom<&api::UnsignedTxProposal> for UnsignedTxProposal {
+    type Error = ConversionError;
+
+    fn try_from(src: &api::UnsignedTxProposal) -> Result<Self, Self::Error> {
+        if src.fee != src.get_unsigned_tx().get_tx_prefix().fee {
+            return Err(ConversionError::FeeMismatch);
+        }
+
+        let utxos = src
+            .get_input_list()
+            .iter()
+            .map(UnspentTxOut::try_from)
+            .collect::<Result<Vec<UnspentTxOut>, ConversionError>>()?;
+
+        let outlays = src
+            .get_outlay_list()
+            .iter()
+            .map(Outlay::try_from)
+            .collect::<Result<Vec<Outlay>, ConversionError>>()?;
+
+        let unsigned_tx = UnsignedTx::try_from(src.get_unsigned_tx())?;
+
+        let outlay_index_to_tx_out_index = src
+            .get_outlay_index_to_tx_out_index()
+            .iter()
+            .map(|(key, val)| (*key as usize, *val as usize))
+            .collect::<HashMap<_, _>>();
+
+        // Check that none of the indices are out of bound.
+        if outlay_index_to_tx_out_index.len() != outlays.len() {
+            return Err(ConversionError::IndexOutOfBounds);
+        }
+
+        for (outlay_index, tx_out_index) in outlay_index_to_tx_out_index.iter() {
+            if *outlay_index >= outlays.len()
+                || *tx_out_index >= unsigned_tx.tx_prefix.outputs.len()
+            {
+                return Err(ConversionError::IndexOutOfBounds);
+            }
+        }
+
+        let outlay_confirmation_numbers = src
+            .get_outlay_confirmation_numbers()
+            .iter()
+            .map(|src| match src.len() {
+                32 => {
+                    let mut bytes = [0u8; 32];
+                    bytes.copy_from_slice(src);
+                    Ok(TxOutConfirmationNumber::from(bytes))
+                }
+                _ => Err(ConversionError::IndexOutOfBounds),
+            })
+            .collect::<Result<Vec<TxOutConfirmationNumber>, ConversionError>>()?;
+
+        Ok(Self {
+            utxos,
+            outlays,
+            unsigned_tx,
+            outlay_index_to_tx_out_index,
+            outlay_confirmation_numbers,
+        })
+    }
+}
+
+impl From<&ViewAccountKey> for api::ViewAccountKey {
+    fn from(src: &ViewAccountKey) -> Self {
+        let mut dst = Self::new();
+
+        dst.set_view_private_key(src.view_private_key().into());
+        dst.set_spend_public_key(src.spend_public_key().into());
+
+        dst
+    }
+}
+
+impl TryFrom<&api::ViewAccountKey> for ViewAccountKey {
+    type Error = ConversionError;
+
+    fn try_from(src: &api::ViewAccountKey) -> Result<Self, Self::Error> {
+        let view_private_key = RistrettoPrivate::try_from(src.get_view_private_key())?;
+        let spend_public_key = RistrettoPublic::try_from(src.get_spend_public_key())?;
+
+        Ok(Self::new(view_private_key, spend_public_key))
+    }
+}
+
+impl From<&SwapQuote> for api::SwapQuote {
+    fn from(src: &SwapQuote) -> Self {
+        let mut dst = Self::new();
+
+        dst.set_base_token_id(*src.base_token_id);
+        dst.set_base_value(src.base_value);
+        dst.set_counter_token_id(*src.counter_token_id);
+        dst.set_counter_value(src.counter_value);
+        dst.set_partial_fill(src.partial_fill);
+        dst.set_min_fill_value(src.min_fill_value);
+
+        dst
+    }
+}
+
+impl From<&SwapFill> for api::SwapFill {
+    fn from(src: &SwapFill) -> Self {
+        let mut dst = Self::new();
+
+        dst.set_index(src.index as u64);
+        dst.set_base_value(src.base_value);
+        dst.set_counter_value(src.counter_value);
+
+        dst
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -351,4 +495,27 @@ mod test {
         // Proto -> Rust
         assert_eq!(rust, TxProposal::try_from(&proto).unwrap());
     }
+
+    #[test]
+    fn test_view_account_key_conversion() {
+        let mut rng: StdRng = SeedableRng::from_seed([1u8; 32]);
+        let account_key = AccountKey::random(&mut rng);
+
+        // Rust -> Proto
+        let rust = ViewAccountKey::from(&account_key);
+        let proto = api::ViewAccountKey::from(&rust);
+
+        assert_eq!(
+            &RistrettoPrivate::try_from(proto.get_view_private_key()).unwrap(),
+            account_key.view_private_key()
+        );
+
+        // Proto -> Rust
+        let recovered = ViewAccountKey::try_from(&proto).unwrap();
+        assert_eq!(recovered, rust);
+        assert_eq!(
+            recovered.subaddress(5).spend_public_key(),
+            account_key.subaddress(5).spend_public_key()
+        );
+    }
 }
@@ -3,23 +3,40 @@
 //! The mobilecoind database
 
 use crate::{
-    db_crypto::DbCryptoProvider,
+    db_crypto::{DbCryptoProvider, EncryptionAudit, KdfParams},
     error::Error,
-    monitor_store::{MonitorData, MonitorId, MonitorStore},
-    processed_block_store::{ProcessedBlockStore, ProcessedTxOut},
+    monitor_store::{
+        FogMonitorState, MonitorData, MonitorId, MonitorStore, MONITOR_ID_TO_MONITOR_DATA_DB_NAME,
+    },
+    payment_request_store::{PaymentRequest, PaymentRequestKey, PaymentRequestStore},
+    processed_block_store::{
+        ProcessedBlockKey, ProcessedBlockStore, ProcessedTxOut, ProcessedTxOutDirection,
+    },
+    replacement_store::{ReplacementStore, ReplacementTx},
+    sent_tx_store::{SentTx, SentTxKey, SentTxStore},
     subaddress_store::{SubaddressId, SubaddressSPKId, SubaddressStore},
     utxo_store::{UtxoId, UtxoStore},
+    webhook_store::{Webhook, WebhookDelivery, WebhookStore, MONITOR_ID_TO_WEBHOOK_DB_NAME},
 };
 
 use crate::utxo_store::UnspentTxOut;
-use lmdb::{Environment, Transaction};
+use hex_fmt::HexFmt;
+use lmdb::{Environment, RwTransaction, Transaction};
 use mc_common::{
     logger::{log, Logger},
     HashMap,
 };
+use mc_crypto_keys::CompressedRistrettoPublic;
+use mc_ledger_db::Ledger;
 use mc_transaction_core::ring_signature::KeyImage;
 use mc_util_lmdb::{MetadataStore, MetadataStoreSettings};
-use std::{path::Path, sync::Arc};
+use rand::RngCore;
+use std::{
+    collections::BTreeMap,
+    path::Path,
+    sync::{Arc, Condvar, Mutex},
+    time::Duration,
+};
 
 // LMDB Constants
 const MAX_LMDB_FILE_SIZE: usize = 1_099_511_627_776; // 1 TB
@@ -63,6 +80,22 @@ pub struct Database {
     /// Processed block store.
     processed_block_store: ProcessedBlockStore,
 
+    /// Replacement transactions store.
+    replacement_store: ReplacementStore,
+
+    /// Sent transactions store.
+    sent_tx_store: SentTxStore,
+
+    /// Webhook store.
+    webhook_store: WebhookStore,
+
+    /// Payment request store.
+    payment_request_store: PaymentRequestStore,
+
+    /// Counts the changes made to the blocks monitors processed, and wakes up
+    /// the threads waiting for them.
+    monitor_changes: Arc<(Mutex<u64>, Condvar)>,
+
     /// Logger.
     logger: Logger,
 }
@@ -71,7 +104,7 @@ impl Database {
     pub fn new<P: AsRef<Path>>(path: P, logger: Logger) -> Result<Self, Error> {
         let env = Arc::new(
             Environment::new()
-                .set_max_dbs(10)
+                .set_max_dbs(20)
                 .set_map_size(MAX_LMDB_FILE_SIZE)
                 .open(path.as_ref())?,
         );
@@ -97,6 +130,11 @@ impl Database {
         let subaddress_store = SubaddressStore::new(env.clone(), logger.clone())?;
         let utxo_store = UtxoStore::new(env.clone(), logger.clone())?;
         let processed_block_store = ProcessedBlockStore::new(env.clone(), logger.clone())?;
+        let replacement_store = ReplacementStore::new(env.clone(), logger.clone())?;
+        let sent_tx_store = SentTxStore::new(env.clone(), logger.clone())?;
+        let webhook_store =
+            WebhookStore::new(env.clone(), crypto_provider.clone(), logger.clone())?;
+        let payment_request_store = PaymentRequestStore::new(env.clone(), logger.clone())?;
 
         Ok(Self {
             env,
@@ -105,6 +143,11 @@ impl Database {
             subaddress_store,
             utxo_store,
             processed_block_store,
+            replacement_store,
+            sent_tx_store,
+            webhook_store,
+            payment_request_store,
+            monitor_changes: Arc::new((Mutex::new(0), Condvar::new())),
             logger,
         })
     }
@@ -127,22 +170,70 @@ impl Database {
         Ok(self.crypto_provider.check_and_store_password(password)?)
     }
 
+    /// Same as `check_and_store_password`, for a database whose password was
+    /// derived from a passphrase (see `re_encrypt_with_passphrase`).
+    pub fn check_and_store_passphrase(&self, passphrase: &[u8]) -> Result<(), Error> {
+        let password = self.crypto_provider.derive_password(passphrase)?;
+        self.check_and_store_password(&password)
+    }
+
+    /// Get the parameters the current password was derived with, if it was
+    /// derived from a passphrase.
+    pub fn get_kdf_params(&self) -> Result<Option<KdfParams>, Error> {
+        Ok(self.crypto_provider.get_kdf_params()?)
+    }
+
     /// Re-encrypt the encrypted parts of the database with a new password.
     /// This will fail if the current password is not set in the crypto_provider
     /// since part of the re-encryption process relies on being able to
     /// decrypt the existing data.
     pub fn re_encrypt(&self, new_password: &[u8]) -> Result<(), Error> {
+        self.re_encrypt_with_kdf_params(new_password, None)
+    }
+
+    /// Re-encrypt the encrypted parts of the database with a password derived
+    /// from a passphrase. A fresh salt is used every time, so calling this
+    /// with the current passphrase rotates the encryption key.
+    pub fn re_encrypt_with_passphrase(&self, passphrase: &[u8]) -> Result<(), Error> {
+        let kdf_params = KdfParams::random(&mut rand::thread_rng());
+        let new_password = kdf_params.derive_password(passphrase)?;
+        self.re_encrypt_with_kdf_params(&new_password, Some(&kdf_params))
+    }
+
+    fn re_encrypt_with_kdf_params(
+        &self,
+        new_password: &[u8],
+        kdf_params: Option<&KdfParams>,
+    ) -> Result<(), Error> {
         let mut db_txn = self.env.begin_rw_txn()?;
 
-        // Currently only the monitor store stores encrypted data.
+        // Monitors and webhooks are the only encrypted data.
         self.monitor_store.re_encrypt(&mut db_txn, new_password)?;
+        self.webhook_store.re_encrypt(&mut db_txn, new_password)?;
 
         // set_password consumes the transaction to ensure atomicity.
-        self.crypto_provider.change_password(db_txn, new_password)?;
+        self.crypto_provider
+            .change_password_with_kdf_params(db_txn, new_password, kdf_params)?;
 
         Ok(())
     }
 
+    /// Check which records of the encrypted parts of the database cannot be
+    /// decrypted with the current password, per LMDB database.
+    pub fn audit_encryption(&self) -> Result<Vec<(&'static str, EncryptionAudit)>, Error> {
+        let db_txn = self.env.begin_ro_txn()?;
+        Ok(vec![
+            (
+                MONITOR_ID_TO_MONITOR_DATA_DB_NAME,
+                self.monitor_store.audit_encryption(&db_txn)?,
+            ),
+            (
+                MONITOR_ID_TO_WEBHOOK_DB_NAME,
+                self.webhook_store.audit_encryption(&db_txn)?,
+            ),
+        ])
+    }
+
     pub fn add_monitor(&self, data: &MonitorData) -> Result<MonitorId, Error> {
         mc_common::trace_time!(self.logger, "add_monitor");
 
@@ -159,6 +250,171 @@ impl Database {
         Ok(id)
     }
 
+    /// Grow the subaddress range of a monitor to `num_subaddresses`.
+    /// `discovered_utxos` and `spent_utxos` are the TxOuts a rescan of the
+    /// blocks the monitor already processed found on the new subaddresses,
+    /// along with the index of the block they were received or spent in.
+    pub fn extend_monitor_subaddresses(
+        &self,
+        monitor_id: &MonitorId,
+        next_block: u64,
+        num_subaddresses: u64,
+        discovered_utxos: &[(u64, UnspentTxOut)],
+        spent_utxos: &[(u64, UnspentTxOut)],
+    ) -> Result<(), Error> {
+        mc_common::trace_time!(self.logger, "extend_monitor_subaddresses");
+
+        let mut db_txn = self.env.begin_rw_txn()?;
+
+        let mut data = self.monitor_store.get_data(&db_txn, monitor_id)?;
+
+        // The rescan is only valid if no blocks were processed since it started.
+        if next_block != data.next_block {
+            return Err(Error::InvalidArgument(
+                "next_block".to_string(),
+                format!(
+                    "Expected block {}, got block {}",
+                    data.next_block, next_block
+                ),
+            ));
+        }
+        if num_subaddresses <= data.num_subaddresses {
+            return Err(Error::InvalidArgument(
+                "num_subaddresses".to_string(),
+                "must be greater than the current number of subaddresses".to_string(),
+            ));
+        }
+
+        let old_indexes = data.subaddress_indexes();
+        data.num_subaddresses = num_subaddresses;
+        for index in old_indexes.end..data.subaddress_indexes().end {
+            self.subaddress_store
+                .insert(&mut db_txn, monitor_id, &data, index)?;
+        }
+
+        for (block_index, utxo) in discovered_utxos {
+            let is_spent = spent_utxos
+                .iter()
+                .any(|(_, spent_utxo)| spent_utxo.key_image == utxo.key_image);
+            if !is_spent {
+                self.utxo_store.append_utxo(
+                    &mut db_txn,
+                    monitor_id,
+                    utxo.subaddress_index,
+                    utxo,
+                )?;
+            }
+
+            self.record_block_events(
+                &mut db_txn,
+                monitor_id,
+                &data,
+                *block_index,
+                &[utxo.clone()],
+                &[],
+            )?;
+        }
+
+        for (block_index, utxo) in spent_utxos {
+            self.record_block_events(
+                &mut db_txn,
+                monitor_id,
+                &data,
+                *block_index,
+                &[],
+                &[utxo.clone()],
+            )?;
+        }
+
+        data.record_used_subaddresses(
+            discovered_utxos
+                .iter()
+                .map(|(_, utxo)| utxo.subaddress_index),
+        );
+        if !discovered_utxos.is_empty() {
+            data.rescan_generation += 1;
+        }
+        self.monitor_store
+            .set_data(&mut db_txn, monitor_id, &data)?;
+
+        db_txn.commit()?;
+        self.notify_monitor_changes();
+
+        log::info!(
+            self.logger,
+            "Extended monitor {} to {} subaddresses, rescan found {} utxos",
+            monitor_id,
+            num_subaddresses,
+            discovered_utxos.len(),
+        );
+        Ok(())
+    }
+
+    /// Change the subaddress range and first block of a monitor, and sync it
+    /// again from its new first block. Everything the monitor discovered is
+    /// discarded, while its id, name, webhook, sent transactions and payment
+    /// requests are kept. Payments found again by the rescan are not recorded
+    /// twice.
+    pub fn rescan_monitor(
+        &self,
+        id: &MonitorId,
+        first_subaddress: u64,
+        num_subaddresses: u64,
+        first_block: u64,
+    ) -> Result<MonitorData, Error> {
+        mc_common::trace_time!(self.logger, "rescan_monitor");
+
+        if num_subaddresses == 0 {
+            return Err(Error::InvalidArgument(
+                "num_subaddresses".to_string(),
+                "must be greater than zero".to_string(),
+            ));
+        }
+
+        let mut db_txn = self.env.begin_rw_txn()?;
+
+        let mut data = self.monitor_store.get_data(&db_txn, id)?;
+
+        for index in data.subaddress_indexes() {
+            self.subaddress_store.delete(&mut db_txn, &data, index)?;
+            self.utxo_store.remove_utxos(&mut db_txn, id, index)?;
+        }
+
+        self.processed_block_store.remove(&mut db_txn, id)?;
+
+        data.rescan_end_block = data.next_block.max(data.rescan_end_block);
+        data.first_subaddress = first_subaddress;
+        data.num_subaddresses = num_subaddresses;
+        data.first_block = first_block;
+        data.next_block = first_block;
+        data.highest_used_subaddress = None;
+        data.rescan_generation += 1;
+
+        // Fog monitors poll fog view from scratch.
+        if data.fog_state.is_some() {
+            data.fog_state = Some(FogMonitorState::default());
+        }
+
+        for index in data.subaddress_indexes() {
+            self.subaddress_store
+                .insert(&mut db_txn, id, &data, index)?;
+        }
+
+        self.monitor_store.set_data(&mut db_txn, id, &data)?;
+
+        db_txn.commit()?;
+        self.notify_monitor_changes();
+
+        log::info!(
+            self.logger,
+            "Rescanning monitor {} from block {}, it was at block {}",
+            id,
+            first_block,
+            data.rescan_end_block,
+        );
+        Ok(data)
+    }
+
     pub fn remove_monitor(&self, id: &MonitorId) -> Result<(), Error> {
         mc_common::trace_time!(self.logger, "remove_monitor");
 
@@ -173,9 +429,16 @@ impl Database {
 
         self.processed_block_store.remove(&mut db_txn, id)?;
 
+        self.sent_tx_store.remove(&mut db_txn, id)?;
+
+        self.webhook_store.remove(&mut db_txn, id)?;
+
+        self.payment_request_store.remove(&mut db_txn, id)?;
+
         self.monitor_store.remove(&mut db_txn, id)?;
 
         db_txn.commit()?;
+        self.notify_monitor_changes();
 
         Ok(())
     }
@@ -239,6 +502,239 @@ impl Database {
         Ok(())
     }
 
+    /// Record a replacement transaction that spends the given key images.
+    pub fn add_replacement_tx(
+        &self,
+        key_images: &[KeyImage],
+        replacement_tx: &ReplacementTx,
+    ) -> Result<(), Error> {
+        let mut db_txn = self.env.begin_rw_txn()?;
+        self.replacement_store
+            .insert(&mut db_txn, key_images, replacement_tx)?;
+        db_txn.commit()?;
+        Ok(())
+    }
+
+    /// Get the replacement transaction recorded for a given key image, if any.
+    pub fn get_replacement_tx(&self, key_image: &KeyImage) -> Result<Option<ReplacementTx>, Error> {
+        let db_txn = self.env.begin_ro_txn()?;
+        self.replacement_store.get(&db_txn, key_image)
+    }
+
+    /// Append a submitted transaction to the history of the monitor that sent
+    /// it.
+    pub fn add_sent_tx(
+        &self,
+        monitor_id: &MonitorId,
+        sent_tx: &SentTx,
+    ) -> Result<SentTxKey, Error> {
+        let mut db_txn = self.env.begin_rw_txn()?;
+
+        // Ensure the monitor exists.
+        self.monitor_store.get_data(&db_txn, monitor_id)?;
+
+        let key = self
+            .sent_tx_store
+            .insert(&mut db_txn, monitor_id, sent_tx)?;
+        db_txn.commit()?;
+        Ok(key)
+    }
+
+    /// Get a page of the transactions sent by a monitor, most recent first,
+    /// alongside the total number of transactions it sent.
+    pub fn get_sent_txs(
+        &self,
+        monitor_id: &MonitorId,
+        offset: u64,
+        limit: u64,
+    ) -> Result<(u64, Vec<(SentTxKey, SentTx)>), Error> {
+        let db_txn = self.env.begin_ro_txn()?;
+
+        // Ensure the monitor exists.
+        self.monitor_store.get_data(&db_txn, monitor_id)?;
+
+        let num_sent_txs = self.sent_tx_store.get_num_sent_txs(&db_txn, monitor_id)?;
+        let sent_txs = self
+            .sent_tx_store
+            .get_sent_txs(&db_txn, monitor_id, offset, limit)?;
+        Ok((num_sent_txs, sent_txs))
+    }
+
+    /// Issue a new payment request for a monitor, under a randomly chosen id.
+    /// The request's created block is set to the monitor's next block.
+    pub fn add_payment_request(
+        &self,
+        monitor_id: &MonitorId,
+        payment_request: &PaymentRequest,
+    ) -> Result<(PaymentRequestKey, PaymentRequest), Error> {
+        let mut db_txn = self.env.begin_rw_txn()?;
+
+        let data = self.monitor_store.get_data(&db_txn, monitor_id)?;
+        if !data
+            .subaddress_indexes()
+            .contains(&payment_request.subaddress_index)
+        {
+            return Err(Error::InvalidArgument(
+                "subaddress_index".to_string(),
+                "must be one of the monitor's subaddresses".to_string(),
+            ));
+        }
+
+        let mut payment_request = payment_request.clone();
+        payment_request.created_block = data.next_block;
+        payment_request.payments.clear();
+
+        // Ids are random so that they cannot be guessed. Zero is reserved to
+        // mean that no id is set.
+        let mut rng = rand::thread_rng();
+        let key = loop {
+            let id = rng.next_u64();
+            if id == 0 {
+                continue;
+            }
+            let key = PaymentRequestKey::new(monitor_id, id);
+            match self
+                .payment_request_store
+                .insert(&mut db_txn, &key, &payment_request)
+            {
+                Ok(()) => break key,
+                Err(Error::PaymentRequestIdExists) => continue,
+                Err(err) => return Err(err),
+            }
+        };
+        db_txn.commit()?;
+        Ok((key, payment_request))
+    }
+
+    /// Get a payment request of a monitor, alongside the next block the
+    /// monitor is going to process.
+    pub fn get_payment_request(
+        &self,
+        key: &PaymentRequestKey,
+    ) -> Result<(u64, PaymentRequest), Error> {
+        let db_txn = self.env.begin_ro_txn()?;
+        let data = self.monitor_store.get_data(&db_txn, &key.monitor_id)?;
+        let payment_request = self.payment_request_store.get(&db_txn, key)?;
+        Ok((data.next_block, payment_request))
+    }
+
+    /// Get all the payment requests of a monitor, alongside the next block the
+    /// monitor is going to process.
+    #[allow(clippy::type_complexity)]
+    pub fn get_payment_requests(
+        &self,
+        monitor_id: &MonitorId,
+    ) -> Result<(u64, Vec<(PaymentRequestKey, PaymentRequest)>), Error> {
+        let db_txn = self.env.begin_ro_txn()?;
+        let data = self.monitor_store.get_data(&db_txn, monitor_id)?;
+        let payment_requests = self.payment_request_store.list(&db_txn, monitor_id)?;
+        Ok((data.next_block, payment_requests))
+    }
+
+    /// Import the key images of UnspentTxOuts received by a view-only monitor,
+    /// replacing their placeholder key images. The key images are computed
+    /// offline by the holder of the spend private key, identified by the public
+    /// key of the TxOut they belong to.
+    ///
+    /// Key images that were already imported are ignored, including those of
+    /// UnspentTxOuts that were spent since. Any other key image that does not
+    /// belong to an UnspentTxOut of the monitor fails the whole import.
+    ///
+    /// UnspentTxOuts whose key image appears in a block the monitor has already
+    /// processed are removed, and their spend is recorded in that block, as if
+    /// the monitor had known the key image when it processed it. The ones
+    /// spent in later blocks are removed as usual when the monitor processes
+    /// those blocks.
+    ///
+    /// Returns the number of key images imported and the list of
+    /// UnspentTxOuts that were removed.
+    pub fn import_key_images(
+        &self,
+        monitor_id: &MonitorId,
+        key_images: &[(CompressedRistrettoPublic, KeyImage)],
+        ledger_db: &impl Ledger,
+    ) -> Result<(usize, Vec<UnspentTxOut>), Error> {
+        let mut db_txn = self.env.begin_rw_txn()?;
+
+        let monitor_data = self.monitor_store.get_data(&db_txn, monitor_id)?;
+
+        let mut num_imported = 0;
+        let mut spent_utxos = Vec::new();
+        for (tx_public_key, key_image) in key_images {
+            let placeholder_key_image = UnspentTxOut::placeholder_key_image(tx_public_key);
+            let utxos = self.utxo_store.remove_utxos_by_key_images(
+                &mut db_txn,
+                monitor_id,
+                &[placeholder_key_image],
+            )?;
+            let spent_block = ledger_db
+                .check_key_image(key_image)?
+                .filter(|block_index| *block_index < monitor_data.next_block);
+
+            if utxos.is_empty() {
+                // The key image was already imported, and the UnspentTxOut it belongs to is
+                // either still there or was spent in a block the monitor processed.
+                let is_known = match self
+                    .utxo_store
+                    .get_subaddress_id_by_utxo_id(&db_txn, &UtxoId::from(key_image))
+                {
+                    Ok(subaddress_id) => subaddress_id.monitor_id == *monitor_id,
+                    Err(Error::UtxoIdNotFound) => false,
+                    Err(err) => return Err(err),
+                };
+                if is_known || spent_block.is_some() {
+                    continue;
+                }
+                return Err(Error::InvalidArgument(
+                    "key_images".to_string(),
+                    format!(
+                        "No UnspentTxOut with public key {} is waiting for its key image",
+                        HexFmt(tx_public_key.as_bytes())
+                    ),
+                ));
+            }
+
+            for mut utxo in utxos {
+                utxo.key_image = *key_image;
+                num_imported += 1;
+
+                match spent_block {
+                    Some(block_index) => {
+                        self.record_block_events(
+                            &mut db_txn,
+                            monitor_id,
+                            &monitor_data,
+                            block_index,
+                            &[],
+                            &[utxo.clone()],
+                        )?;
+                        spent_utxos.push(utxo);
+                    }
+                    None => {
+                        self.utxo_store.append_utxo(
+                            &mut db_txn,
+                            monitor_id,
+                            utxo.subaddress_index,
+                            &utxo,
+                        )?;
+                    }
+                }
+            }
+        }
+
+        db_txn.commit()?;
+        self.notify_monitor_changes();
+
+        log::info!(
+            self.logger,
+            "Imported {} key images for monitor id {}, {} utxos were spent",
+            num_imported,
+            monitor_id,
+            spent_utxos.len()
+        );
+        Ok((num_imported, spent_utxos))
+    }
+
     /// Feed data processed from a given block into the various stores.
     pub fn block_processed(
         &self,
@@ -263,35 +759,26 @@ impl Database {
             ));
         }
 
-        // Store new utxos
-        for utxo in discovered_utxos {
-            self.utxo_store
-                .append_utxo(&mut db_txn, monitor_id, utxo.subaddress_index, utxo)?;
-        }
-
-        // Remove spent utxos
-        let removed_utxos = self.utxo_store.remove_utxos_by_key_images(
+        let removed_utxos = self.store_block_events(
             &mut db_txn,
             monitor_id,
+            &monitor_data,
+            block_num,
+            discovered_utxos,
             spent_key_images,
         )?;
 
-        // Update monitor data.
+        // Update monitor data. The highest used subaddress is recorded along with the
+        // block, so that the gap limit is applied even if growing the range fails.
         monitor_data.next_block += 1;
+        monitor_data
+            .record_used_subaddresses(discovered_utxos.iter().map(|utxo| utxo.subaddress_index));
         self.monitor_store
             .set_data(&mut db_txn, monitor_id, &monitor_data)?;
 
-        // Update processed blocks store.
-        self.processed_block_store.block_processed(
-            &mut db_txn,
-            monitor_id,
-            block_num,
-            discovered_utxos,
-            &removed_utxos,
-        )?;
-
         // Commit.
         db_txn.commit()?;
+        self.notify_monitor_changes();
 
         // Success.
         if discovered_utxos.is_empty() && removed_utxos.is_empty() {
@@ -316,6 +803,177 @@ impl Database {
         Ok(())
     }
 
+    /// Feed the data a fog monitor found in blocks `start_block..end_block`
+    /// into the various stores, and save the fog state it reached.
+    /// `blocks` maps block indexes to the utxos received and the key images
+    /// spent in that block. Blocks without any are omitted.
+    pub fn fog_blocks_processed(
+        &self,
+        monitor_id: &MonitorId,
+        start_block: u64,
+        end_block: u64,
+        blocks: &BTreeMap<u64, (Vec<UnspentTxOut>, Vec<KeyImage>)>,
+        fog_state: &FogMonitorState,
+    ) -> Result<(), Error> {
+        let mut db_txn = self.env.begin_rw_txn()?;
+
+        let mut monitor_data = self.monitor_store.get_data(&db_txn, monitor_id)?;
+
+        // The monitor may have been rescanned since its blocks were fetched.
+        if start_block != monitor_data.next_block {
+            return Err(Error::InvalidArgument(
+                "start_block".to_string(),
+                format!(
+                    "Expected block {}, got block {}",
+                    monitor_data.next_block, start_block
+                ),
+            ));
+        }
+        if end_block < start_block
+            || blocks
+                .keys()
+                .any(|block_num| !(start_block..end_block).contains(block_num))
+        {
+            return Err(Error::InvalidArgument(
+                "blocks".to_string(),
+                format!("Blocks must be in range {}..{}", start_block, end_block),
+            ));
+        }
+
+        let mut num_utxos = 0;
+        let mut num_spent_utxos = 0;
+        for (block_num, (discovered_utxos, spent_key_images)) in blocks {
+            let removed_utxos = self.store_block_events(
+                &mut db_txn,
+                monitor_id,
+                &monitor_data,
+                *block_num,
+                discovered_utxos,
+                spent_key_images,
+            )?;
+            num_utxos += discovered_utxos.len();
+            num_spent_utxos += removed_utxos.len();
+        }
+
+        monitor_data.next_block = end_block;
+        monitor_data.fog_state = Some(fog_state.clone());
+        self.monitor_store
+            .set_data(&mut db_txn, monitor_id, &monitor_data)?;
+
+        db_txn.commit()?;
+        self.notify_monitor_changes();
+
+        if num_utxos == 0 && num_spent_utxos == 0 {
+            log::debug!(
+                self.logger,
+                "Processed {} utxos and {} key images in blocks {}..{} for fog monitor id {}",
+                num_utxos,
+                num_spent_utxos,
+                start_block,
+                end_block,
+                monitor_id
+            )
+        } else {
+            log::info!(
+                self.logger,
+                "Processed {} utxos and {} key images in blocks {}..{} for fog monitor id {}",
+                num_utxos,
+                num_spent_utxos,
+                start_block,
+                end_block,
+                monitor_id
+            )
+        };
+        Ok(())
+    }
+
+    /// Store the utxos a monitor received and remove the ones it spent in a
+    /// given block, and record the block's events.
+    /// Returns the utxos that were removed.
+    fn store_block_events<'env>(
+        &self,
+        db_txn: &mut RwTransaction<'env>,
+        monitor_id: &MonitorId,
+        monitor_data: &MonitorData,
+        block_num: u64,
+        discovered_utxos: &[UnspentTxOut],
+        spent_key_images: &[KeyImage],
+    ) -> Result<Vec<UnspentTxOut>, Error> {
+        // Store new utxos
+        for utxo in discovered_utxos {
+            self.utxo_store
+                .append_utxo(db_txn, monitor_id, utxo.subaddress_index, utxo)?;
+        }
+
+        // Remove spent utxos
+        let removed_utxos =
+            self.utxo_store
+                .remove_utxos_by_key_images(db_txn, monitor_id, spent_key_images)?;
+
+        self.record_block_events(
+            db_txn,
+            monitor_id,
+            monitor_data,
+            block_num,
+            discovered_utxos,
+            &removed_utxos,
+        )?;
+
+        Ok(removed_utxos)
+    }
+
+    /// Record the UnspentTxOuts a monitor received and spent in a given block
+    /// in the processed block store, the payment request store and the webhook
+    /// outbox. The block may be one the monitor already processed, when events
+    /// in it are found late.
+    fn record_block_events<'env>(
+        &self,
+        db_txn: &mut RwTransaction<'env>,
+        monitor_id: &MonitorId,
+        monitor_data: &MonitorData,
+        block_num: u64,
+        received_utxos: &[UnspentTxOut],
+        spent_utxos: &[UnspentTxOut],
+    ) -> Result<(), Error> {
+        // Update processed blocks store.
+        self.processed_block_store.block_processed(
+            db_txn,
+            monitor_id,
+            block_num,
+            received_utxos,
+            spent_utxos,
+        )?;
+
+        // Record payments of the monitor's payment requests.
+        self.payment_request_store.block_processed(
+            db_txn,
+            monitor_id,
+            monitor_data.view_private_key()?,
+            block_num,
+            received_utxos,
+        )?;
+
+        // Queue the events of this block for delivery, if the monitor has a webhook.
+        // Blocks processed while the monitor is rescanning are not delivered.
+        if block_num >= monitor_data.rescan_end_block
+            && (!received_utxos.is_empty() || !spent_utxos.is_empty())
+            && self
+                .webhook_store
+                .get_webhook(&*db_txn, monitor_id)?
+                .is_some()
+        {
+            self.webhook_store.enqueue_events(
+                db_txn,
+                monitor_id,
+                block_num,
+                received_utxos,
+                spent_utxos,
+            )?;
+        }
+
+        Ok(())
+    }
+
     /// Get processed block information for a given (monitor id, block number).
     pub fn get_processed_block(
         &self,
@@ -342,6 +1000,182 @@ impl Database {
         self.processed_block_store
             .get_processed_block(&db_txn, monitor_id, block_num)
     }
+
+    /// Set the webhook payment events of a monitor are delivered to, or remove
+    /// it alongside its undelivered events.
+    pub fn set_monitor_webhook(
+        &self,
+        monitor_id: &MonitorId,
+        webhook: Option<&Webhook>,
+    ) -> Result<(), Error> {
+        let mut db_txn = self.env.begin_rw_txn()?;
+
+        // Ensure the monitor exists.
+        self.monitor_store.get_data(&db_txn, monitor_id)?;
+
+        match webhook {
+            Some(webhook) => self
+                .webhook_store
+                .set_webhook(&mut db_txn, monitor_id, webhook)?,
+            None => self.webhook_store.remove(&mut db_txn, monitor_id)?,
+        }
+
+        db_txn.commit()?;
+        Ok(())
+    }
+
+    /// Get the webhook of a monitor, if any.
+    pub fn get_monitor_webhook(&self, monitor_id: &MonitorId) -> Result<Option<Webhook>, Error> {
+        let db_txn = self.env.begin_ro_txn()?;
+        self.webhook_store.get_webhook(&db_txn, monitor_id)
+    }
+
+    /// Get the oldest undelivered webhook event of each monitor, alongside the
+    /// webhook it should be delivered to.
+    pub fn get_next_webhook_deliveries(
+        &self,
+    ) -> Result<Vec<(ProcessedBlockKey, Webhook, WebhookDelivery)>, Error> {
+        let db_txn = self.env.begin_ro_txn()?;
+
+        let mut deliveries = Vec::new();
+        for (key, delivery) in self.webhook_store.get_next_deliveries(&db_txn)? {
+            if let Some(webhook) = self.webhook_store.get_webhook(&db_txn, &key.monitor_id)? {
+                deliveries.push((key, webhook, delivery));
+            }
+        }
+        Ok(deliveries)
+    }
+
+    /// Remove a webhook event from the outbox once it got delivered.
+    pub fn webhook_delivered(&self, key: &ProcessedBlockKey) -> Result<(), Error> {
+        let mut db_txn = self.env.begin_rw_txn()?;
+        self.webhook_store.remove_delivery(&mut db_txn, key)?;
+        db_txn.commit()?;
+        Ok(())
+    }
+
+    /// Update a webhook event in the outbox after a failed delivery attempt.
+    pub fn webhook_delivery_failed(
+        &self,
+        key: &ProcessedBlockKey,
+        delivery: &WebhookDelivery,
+    ) -> Result<(), Error> {
+        let mut db_txn = self.env.begin_rw_txn()?;
+        self.webhook_store
+            .update_delivery(&mut db_txn, key, delivery)?;
+        db_txn.commit()?;
+        Ok(())
+    }
+
+    /// Get the number of changes made to the blocks monitors processed so far,
+    /// for waiting on the next ones with `wait_for_monitor_changes`.
+    pub fn get_monitor_changes(&self) -> u64 {
+        let (changes, _) = &*self.monitor_changes;
+        *changes.lock().expect("mutex poisoned")
+    }
+
+    /// Wait until the number of changes made to the blocks monitors processed
+    /// differs from `changes`, or until the timeout elapses. Returns the
+    /// number of changes.
+    pub fn wait_for_monitor_changes(&self, changes: u64, timeout: Duration) -> u64 {
+        let (current_changes, condvar) = &*self.monitor_changes;
+        let current_changes = current_changes.lock().expect("mutex poisoned");
+        let (current_changes, _) = condvar
+            .wait_timeout_while(current_changes, timeout, |current_changes| {
+                *current_changes == changes
+            })
+            .expect("mutex poisoned");
+        *current_changes
+    }
+
+    /// Wake up the threads waiting for monitors to process blocks.
+    fn notify_monitor_changes(&self) {
+        let (changes, condvar) = &*self.monitor_changes;
+        *changes.lock().expect("mutex poisoned") += 1;
+        condvar.notify_all();
+    }
+
+    /// Get the balance of each token a monitor had before processing a given
+    /// block, which is clamped to the blocks the monitor processed. The
+    /// balances are computed from the monitor's UnspentTxOuts and the blocks it
+    /// processed after the given one, rather than from its entire history.
+    /// Returns the monitor data, the clamped block and the balances.
+    pub fn get_monitor_balances(
+        &self,
+        monitor_id: &MonitorId,
+        block: u64,
+    ) -> Result<(MonitorData, u64, BTreeMap<u64, u128>), Error> {
+        let db_txn = self.env.begin_ro_txn()?;
+
+        let monitor_data = self.monitor_store.get_data(&db_txn, monitor_id)?;
+        let block = block.clamp(monitor_data.first_block, monitor_data.next_block);
+
+        let mut balances = BTreeMap::<u64, u128>::new();
+        for index in monitor_data.subaddress_indexes() {
+            for utxo in self.utxo_store.get_utxos(&db_txn, monitor_id, index)? {
+                *balances.entry(utxo.token_id).or_default() += utxo.value as u128;
+            }
+        }
+
+        // Undo what the monitor received and spent since the given block.
+        let mut received = BTreeMap::<u64, u128>::new();
+        let mut spent = BTreeMap::<u64, u128>::new();
+        for (_, processed_tx_outs) in self.processed_block_store.get_processed_blocks(
+            &db_txn,
+            monitor_id,
+            block,
+            monitor_data.next_block,
+        )? {
+            for src in processed_tx_outs {
+                let totals = if src.direction == ProcessedTxOutDirection::Spent as i32 {
+                    &mut spent
+                } else {
+                    &mut received
+                };
+                *totals.entry(src.token_id).or_default() += src.value as u128;
+            }
+        }
+        for (token_id, value) in spent {
+            *balances.entry(token_id).or_default() += value;
+        }
+        for (token_id, value) in received {
+            let balance = balances.entry(token_id).or_default();
+            *balance = balance.saturating_sub(value);
+        }
+
+        Ok((monitor_data, block, balances))
+    }
+
+    /// Get processed block information for a given monitor id, for blocks in
+    /// the range [start_block, end_block) that have any data. end_block is
+    /// capped at the next block the monitor is going to process, and returned
+    /// alongside the data.
+    pub fn get_processed_blocks(
+        &self,
+        monitor_id: &MonitorId,
+        start_block: u64,
+        end_block: u64,
+    ) -> Result<(u64, Vec<(u64, Vec<ProcessedTxOut>)>), Error> {
+        let db_txn = self.env.begin_ro_txn()?;
+
+        // Get monitor data to see which blocks the monitor has synced.
+        let monitor_data = self.monitor_store.get_data(&db_txn, monitor_id)?;
+        if start_block < monitor_data.first_block {
+            return Err(Error::BlockIndexTooSmall(
+                start_block,
+                monitor_data.first_block,
+            ));
+        }
+        let end_block = end_block.min(monitor_data.next_block);
+
+        let processed_blocks = self.processed_block_store.get_processed_blocks(
+            &db_txn,
+            monitor_id,
+            start_block,
+            end_block,
+        )?;
+        Ok((end_block, processed_blocks))
+    }
 }
 
 #[cfg(test)]
@@ -351,6 +1185,7 @@ mod test {
     use mc_account_keys::AccountKey;
     use mc_blockchain_types::BlockVersion;
     use mc_common::logger::{test_with_logger, Logger};
+    use mc_fog_types::common::BlockRange;
     use rand::{rngs::StdRng, SeedableRng};
     use tempdir::TempDir;
 
@@ -518,6 +1353,141 @@ mod test {
         );
     }
 
+    // Test that passphrase-derived encryption, key rotation and the encryption
+    // audit work as expected.
+    #[test_with_logger]
+    fn test_passphrase_encryption(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([123u8; 32]);
+        let account_key = AccountKey::random(&mut rng);
+
+        let mobilecoind_db_tmp =
+            TempDir::new("mobilecoind_db").expect("Could not make tempdir for mobilecoind db");
+        let mobilecoind_db_path = mobilecoind_db_tmp
+            .path()
+            .to_str()
+            .expect("Could not get path as string");
+
+        let mobilecoind_db = Database::new(mobilecoind_db_path, logger.clone())
+            .expect("failed creating new mobilecoind db");
+
+        let monitor_data = MonitorData::new(
+            account_key,
+            0,  // first_subaddress
+            10, // num_subaddresses
+            0,  // first_block
+            "", // name
+        )
+        .unwrap();
+        let monitor_id = mobilecoind_db
+            .add_monitor(&monitor_data)
+            .expect("failed adding monitor");
+        let webhook = Webhook {
+            url: "https://example.com/payments".to_owned(),
+            secret: b"secret".to_vec(),
+        };
+        mobilecoind_db
+            .set_monitor_webhook(&monitor_id, Some(&webhook))
+            .unwrap();
+
+        let expected_audit = |num_unreadable| {
+            vec![
+                (
+                    MONITOR_ID_TO_MONITOR_DATA_DB_NAME,
+                    EncryptionAudit {
+                        num_records: 1,
+                        num_unreadable,
+                    },
+                ),
+                (
+                    MONITOR_ID_TO_WEBHOOK_DB_NAME,
+                    EncryptionAudit {
+                        num_records: 1,
+                        num_unreadable,
+                    },
+                ),
+            ]
+        };
+        assert_eq!(
+            mobilecoind_db.audit_encryption().unwrap(),
+            expected_audit(0)
+        );
+
+        // Passphrases cannot be used until the password was derived from one.
+        assert!(mobilecoind_db.get_kdf_params().unwrap().is_none());
+        assert!(mobilecoind_db
+            .check_and_store_passphrase(b"correct horse")
+            .is_err());
+
+        // Encrypt with a passphrase.
+        mobilecoind_db
+            .re_encrypt_with_passphrase(b"correct horse")
+            .unwrap();
+        let kdf_params = mobilecoind_db.get_kdf_params().unwrap().unwrap();
+        assert!(mobilecoind_db.is_db_encrypted());
+        assert!(mobilecoind_db.is_unlocked());
+        assert_eq!(
+            mobilecoind_db.audit_encryption().unwrap(),
+            expected_audit(0)
+        );
+
+        // Re-open the db. Until it is unlocked, no record is readable.
+        let mobilecoind_db = Database::new(mobilecoind_db_path, logger.clone())
+            .expect("failed creating new mobilecoind db");
+        assert!(!mobilecoind_db.is_unlocked());
+        assert_eq!(
+            mobilecoind_db.audit_encryption().unwrap(),
+            expected_audit(1)
+        );
+
+        assert!(mobilecoind_db
+            .check_and_store_passphrase(b"battery staple")
+            .is_err());
+        assert!(!mobilecoind_db.is_unlocked());
+
+        mobilecoind_db
+            .check_and_store_passphrase(b"correct horse")
+            .unwrap();
+        assert!(mobilecoind_db.is_unlocked());
+        assert_eq!(
+            mobilecoind_db.audit_encryption().unwrap(),
+            expected_audit(0)
+        );
+        assert_eq!(
+            mobilecoind_db.get_monitor_data(&monitor_id).unwrap(),
+            monitor_data
+        );
+
+        // Setting the same passphrase again rotates the key.
+        mobilecoind_db
+            .re_encrypt_with_passphrase(b"correct horse")
+            .unwrap();
+        let rotated_kdf_params = mobilecoind_db.get_kdf_params().unwrap().unwrap();
+        assert_ne!(kdf_params.salt, rotated_kdf_params.salt);
+        assert_eq!(
+            mobilecoind_db.audit_encryption().unwrap(),
+            expected_audit(0)
+        );
+        assert_eq!(
+            mobilecoind_db.get_monitor_webhook(&monitor_id).unwrap(),
+            Some(webhook)
+        );
+
+        // Switching to a plain password forgets the passphrase.
+        mobilecoind_db.re_encrypt(&[10; 32]).unwrap();
+        assert!(mobilecoind_db.get_kdf_params().unwrap().is_none());
+
+        let mobilecoind_db =
+            Database::new(mobilecoind_db_path, logger).expect("failed creating new mobilecoind db");
+        assert!(mobilecoind_db
+            .check_and_store_passphrase(b"correct horse")
+            .is_err());
+        mobilecoind_db.check_and_store_password(&[10; 32]).unwrap();
+        assert_eq!(
+            mobilecoind_db.audit_encryption().unwrap(),
+            expected_audit(0)
+        );
+    }
+
     // Inserting a monitor that overlaps subaddresses of another monitor should
     // result in an error.
     #[test_with_logger]
@@ -617,4 +1587,69 @@ mod test {
             .add_monitor(&initial_data)
             .expect("failed adding monitor");
     }
+
+    // Fog sync results only apply to the monitor's next block.
+    #[test_with_logger]
+    fn test_fog_blocks_processed(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([123u8; 32]);
+
+        let (_ledger_db, mobilecoind_db) =
+            get_test_databases(BlockVersion::ZERO, 3, &[], 10, logger.clone(), &mut rng);
+
+        let data = MonitorData::new_fog(
+            AccountKey::random_with_fog(&mut rng),
+            0,  // first_subaddress
+            10, // num_subaddresses
+            0,  // first_block
+            "", // name
+        )
+        .unwrap();
+        let monitor_id = mobilecoind_db
+            .add_monitor(&data)
+            .expect("failed adding monitor");
+
+        let fog_state = FogMonitorState {
+            pending_missed_block_ranges: vec![BlockRange::new(5, 6)],
+            ..Default::default()
+        };
+        let no_blocks = BTreeMap::new();
+
+        // Starting after the monitor's next block should fail.
+        match mobilecoind_db.fog_blocks_processed(&monitor_id, 1, 2, &no_blocks, &fog_state) {
+            Ok(_) => panic!("unexpected success!"),
+            Err(Error::InvalidArgument(arg, _)) => assert_eq!(arg, "start_block"),
+            Err(err) => panic!("unexpected error {:?}", err),
+        };
+
+        // Events outside of the processed blocks should fail.
+        let mut blocks = BTreeMap::new();
+        blocks.insert(2, (vec![], vec![KeyImage::from(1u64)]));
+        match mobilecoind_db.fog_blocks_processed(&monitor_id, 0, 2, &blocks, &fog_state) {
+            Ok(_) => panic!("unexpected success!"),
+            Err(Error::InvalidArgument(arg, _)) => assert_eq!(arg, "blocks"),
+            Err(err) => panic!("unexpected error {:?}", err),
+        };
+
+        // Nothing was changed by the failed calls.
+        let monitor_data = mobilecoind_db.get_monitor_data(&monitor_id).unwrap();
+        assert_eq!(monitor_data.next_block, 0);
+        assert_eq!(monitor_data.fog_state, Some(FogMonitorState::default()));
+
+        // Starting at the monitor's next block should succeed, and store the fog
+        // state.
+        mobilecoind_db
+            .fog_blocks_processed(&monitor_id, 0, 2, &no_blocks, &fog_state)
+            .expect("fog_blocks_processed failed");
+
+        let monitor_data = mobilecoind_db.get_monitor_data(&monitor_id).unwrap();
+        assert_eq!(monitor_data.next_block, 2);
+        assert_eq!(monitor_data.fog_state, Some(fog_state.clone()));
+
+        // The same blocks cannot be processed twice.
+        match mobilecoind_db.fog_blocks_processed(&monitor_id, 0, 2, &no_blocks, &fog_state) {
+            Ok(_) => panic!("unexpected success!"),
+            Err(Error::InvalidArgument(arg, _)) => assert_eq!(arg, "start_block"),
+            Err(err) => panic!("unexpected error {:?}", err),
+        };
+    }
 }
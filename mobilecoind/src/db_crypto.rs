@@ -1,6 +1,14 @@
 // Copyright (c) 2018-2022 The MobileCoin Foundation
 
 //! Helper for managing database encryption.
+//!
+//! The encrypted parts of the database are encrypted with AES-256-GCM, using
+//! a key expanded from a 32 bytes password. The password is either provided
+//! as-is by the user, who is expected to have hashed their actual password, or
+//! derived by us from a passphrase using Argon2id. In the latter case, the
+//! Argon2id parameters (including a random salt) are stored alongside the
+//! encryption state, so that the passphrase alone is enough to unlock the
+//! database.
 
 use aes_gcm::{
     aead::{
@@ -9,12 +17,16 @@ use aes_gcm::{
     },
     AeadCore, Aes256Gcm, Error as AeadError, NewAead,
 };
+use argon2::{Algorithm, Argon2, Params, Version};
 use displaydoc::Display;
 use lmdb::{
     Database, DatabaseFlags, Environment, Error as LmdbError, RwTransaction, Transaction,
     WriteFlags,
 };
 use mc_crypto_hashes::{Blake2b512, Digest};
+use mc_util_serial::DecodeError;
+use prost::Message;
+use rand_core::{CryptoRng, RngCore};
 use std::sync::{Arc, Mutex};
 
 /// Domain tag for database-wide encryption.
@@ -32,6 +44,22 @@ const CRYPTO_DB_NAME: &str = "db_crypto";
 const ENCRYPTION_STATE_KEY: &str = "db_encrypted";
 const ENCRYPTION_STATE_VAL: &str = "true";
 
+/// Key used for storing the parameters used to derive the password from a
+/// passphrase, when the password was derived from one.
+const KDF_PARAMS_KEY: &str = "kdf_params";
+
+/// Default Argon2id memory cost, in KiB.
+pub const DEFAULT_KDF_M_COST: u32 = 19 * 1024;
+
+/// Default Argon2id number of iterations.
+pub const DEFAULT_KDF_T_COST: u32 = 2;
+
+/// Default Argon2id degree of parallelism.
+pub const DEFAULT_KDF_P_COST: u32 = 1;
+
+/// Length of the random salt used when deriving a password from a passphrase.
+pub const KDF_SALT_LEN: usize = 16;
+
 /// Possible db crypto error types.
 #[derive(Debug, Display)]
 pub enum DbCryptoError {
@@ -44,6 +72,15 @@ pub enum DbCryptoError {
     /// Password needed
     PasswordNeeded,
 
+    /// The password was not derived from a passphrase
+    PassphraseNotSupported,
+
+    /// Key derivation: {0}
+    Kdf(String),
+
+    /// Decode: {0}
+    Decode(DecodeError),
+
     /// AEAD: {0}
     Aead(AeadError),
 
@@ -63,6 +100,70 @@ impl From<LmdbError> for DbCryptoError {
     }
 }
 
+impl From<DecodeError> for DbCryptoError {
+    fn from(src: DecodeError) -> Self {
+        Self::Decode(src)
+    }
+}
+
+/// Argon2id parameters used to derive the password from a passphrase.
+#[derive(Clone, Eq, PartialEq, Message)]
+pub struct KdfParams {
+    /// Random salt.
+    #[prost(bytes, tag = "1")]
+    pub salt: Vec<u8>,
+
+    /// Memory cost, in KiB.
+    #[prost(uint32, tag = "2")]
+    pub m_cost: u32,
+
+    /// Number of iterations.
+    #[prost(uint32, tag = "3")]
+    pub t_cost: u32,
+
+    /// Degree of parallelism.
+    #[prost(uint32, tag = "4")]
+    pub p_cost: u32,
+}
+
+impl KdfParams {
+    /// Default parameters, with a fresh random salt.
+    pub fn random<R: CryptoRng + RngCore>(rng: &mut R) -> Self {
+        let mut salt = vec![0u8; KDF_SALT_LEN];
+        rng.fill_bytes(&mut salt);
+        Self {
+            salt,
+            m_cost: DEFAULT_KDF_M_COST,
+            t_cost: DEFAULT_KDF_T_COST,
+            p_cost: DEFAULT_KDF_P_COST,
+        }
+    }
+
+    /// Derive a password from a passphrase.
+    pub fn derive_password(&self, passphrase: &[u8]) -> Result<Vec<u8>, DbCryptoError> {
+        let params = Params::new(self.m_cost, self.t_cost, self.p_cost, Some(PASSWORD_LEN))
+            .map_err(|err| DbCryptoError::Kdf(err.to_string()))?;
+        let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+
+        let mut password = vec![0u8; PASSWORD_LEN];
+        argon2
+            .hash_password_into(passphrase, &self.salt, &mut password)
+            .map_err(|err| DbCryptoError::Kdf(err.to_string()))?;
+        Ok(password)
+    }
+}
+
+/// The result of checking whether the records of a database can be decrypted
+/// with the current password.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct EncryptionAudit {
+    /// Number of records in the database.
+    pub num_records: u64,
+
+    /// Number of records that could not be decrypted.
+    pub num_unreadable: u64,
+}
+
 /// Database crypto state that is shared between multiple threads.
 struct DbCryptoProviderState {
     /// Is the database currently encrypted?
@@ -129,6 +230,25 @@ impl DbCryptoProvider {
         state.is_db_encrypted
     }
 
+    /// Get the parameters the current password was derived with, if it was
+    /// derived from a passphrase.
+    pub fn get_kdf_params(&self) -> Result<Option<KdfParams>, DbCryptoError> {
+        let db_txn = self.env.begin_ro_txn()?;
+        match db_txn.get(self.database, &KDF_PARAMS_KEY.as_bytes()) {
+            Ok(value_bytes) => Ok(Some(mc_util_serial::decode(value_bytes)?)),
+            Err(LmdbError::NotFound) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Derive the current password from a passphrase, using the stored
+    /// parameters.
+    pub fn derive_password(&self, passphrase: &[u8]) -> Result<Vec<u8>, DbCryptoError> {
+        self.get_kdf_params()?
+            .ok_or(DbCryptoError::PassphraseNotSupported)?
+            .derive_password(passphrase)
+    }
+
     /// Check if a given password is the password used to encrypt data in the
     /// db, and if so store it for future encryption/decryption operations.
     pub fn check_and_store_password(&self, password: &[u8]) -> Result<(), DbCryptoError> {
@@ -176,9 +296,21 @@ impl DbCryptoProvider {
     /// encryption/decryption operations. This should only be called after
     /// all existing data has been re-encrypted to the new password!
     pub fn change_password<'env>(
+        &self,
+        db_txn: RwTransaction<'env>,
+        password: &[u8],
+    ) -> Result<(), DbCryptoError> {
+        self.change_password_with_kdf_params(db_txn, password, None)
+    }
+
+    /// Same as `change_password`, for a password that was derived from a
+    /// passphrase with the given parameters. The parameters are stored so that
+    /// the password can later be derived again from the passphrase.
+    pub fn change_password_with_kdf_params<'env>(
         &self,
         mut db_txn: RwTransaction<'env>,
         password: &[u8],
+        kdf_params: Option<&KdfParams>,
     ) -> Result<(), DbCryptoError> {
         let mut state = self.state.lock().expect("muted poisoned");
 
@@ -200,6 +332,19 @@ impl DbCryptoProvider {
             )?;
         }
 
+        match kdf_params {
+            Some(kdf_params) if !password.is_empty() => db_txn.put(
+                self.database,
+                &KDF_PARAMS_KEY.as_bytes(),
+                &mc_util_serial::encode(kdf_params),
+                WriteFlags::empty(),
+            )?,
+            _ => match db_txn.del(self.database, &KDF_PARAMS_KEY.as_bytes(), None) {
+                Ok(()) | Err(LmdbError::NotFound) => {}
+                Err(err) => return Err(err.into()),
+            },
+        }
+
         db_txn.commit()?;
 
         if password.is_empty() {
@@ -317,6 +462,8 @@ impl DbCryptoProvider {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+    use std::assert_matches::assert_matches;
     use tempdir::TempDir;
     const TEST_DATA: &[u8; 10] = &[1, 2, 3, 4, 5, 6, 7, 8, 9, 10];
 
@@ -565,6 +712,120 @@ mod tests {
             .is_ok());
     }
 
+    #[test]
+    fn test_passphrase() {
+        let (_crypto_provider, path) = get_test_db_crypto_provider();
+        let mut rng: StdRng = SeedableRng::from_seed([1u8; 32]);
+
+        let open_crypto_provider = || {
+            let env = Arc::new(
+                Environment::new()
+                    .set_max_dbs(10)
+                    .set_map_size(10000000)
+                    .open(path.as_ref())
+                    .unwrap(),
+            );
+            DbCryptoProvider::new(env).unwrap()
+        };
+
+        // Use cheap parameters to keep the test fast.
+        let kdf_params = KdfParams {
+            m_cost: 64,
+            t_cost: 1,
+            ..KdfParams::random(&mut rng)
+        };
+        assert_eq!(kdf_params.salt.len(), KDF_SALT_LEN);
+
+        // Derivation is deterministic, and depends on the passphrase and salt.
+        let password = kdf_params.derive_password(b"correct horse").unwrap();
+        assert_eq!(password.len(), PASSWORD_LEN);
+        assert_eq!(
+            password,
+            kdf_params.derive_password(b"correct horse").unwrap()
+        );
+        assert_ne!(
+            password,
+            kdf_params.derive_password(b"battery staple").unwrap()
+        );
+        let other_kdf_params = KdfParams {
+            m_cost: 64,
+            t_cost: 1,
+            ..KdfParams::random(&mut rng)
+        };
+        assert_ne!(
+            password,
+            other_kdf_params.derive_password(b"correct horse").unwrap()
+        );
+
+        // Without a passphrase-derived password, there is nothing to derive.
+        let crypto_provider = open_crypto_provider();
+        assert_eq!(crypto_provider.get_kdf_params().unwrap(), None);
+        assert_matches!(
+            crypto_provider.derive_password(b"correct horse"),
+            Err(DbCryptoError::PassphraseNotSupported)
+        );
+
+        crypto_provider
+            .change_password_with_kdf_params(
+                crypto_provider.env.begin_rw_txn().unwrap(),
+                &password,
+                Some(&kdf_params),
+            )
+            .unwrap();
+        let encrypted_data = crypto_provider.encrypt(&TEST_DATA[..]).unwrap();
+
+        // After reopening, the passphrase alone unlocks the database.
+        let crypto_provider = open_crypto_provider();
+        assert!(crypto_provider.is_db_encrypted());
+        assert!(!crypto_provider.is_unlocked());
+        assert_eq!(
+            crypto_provider.get_kdf_params().unwrap(),
+            Some(kdf_params.clone())
+        );
+
+        let wrong_password = crypto_provider.derive_password(b"battery staple").unwrap();
+        assert!(crypto_provider
+            .check_and_store_password(&wrong_password)
+            .is_err());
+
+        let derived_password = crypto_provider.derive_password(b"correct horse").unwrap();
+        crypto_provider
+            .check_and_store_password(&derived_password)
+            .unwrap();
+        assert!(crypto_provider.is_unlocked());
+        assert_eq!(
+            crypto_provider.decrypt(&encrypted_data).unwrap(),
+            TEST_DATA.to_vec()
+        );
+
+        // Switching to a plain password forgets the parameters.
+        crypto_provider
+            .change_password(
+                crypto_provider.env.begin_rw_txn().unwrap(),
+                &[3; PASSWORD_LEN],
+            )
+            .unwrap();
+        assert_eq!(crypto_provider.get_kdf_params().unwrap(), None);
+
+        // As does removing encryption.
+        crypto_provider
+            .change_password_with_kdf_params(
+                crypto_provider.env.begin_rw_txn().unwrap(),
+                &password,
+                Some(&kdf_params),
+            )
+            .unwrap();
+        crypto_provider
+            .change_password_with_kdf_params(
+                crypto_provider.env.begin_rw_txn().unwrap(),
+                &[],
+                Some(&kdf_params),
+            )
+            .unwrap();
+        assert!(!crypto_provider.is_db_encrypted());
+        assert_eq!(crypto_provider.get_kdf_params().unwrap(), None);
+    }
+
     #[test]
     fn test_db_reopen() {
         // Get the initial db.
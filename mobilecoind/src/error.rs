@@ -8,8 +8,12 @@ use lmdb::Error as LmdbError;
 use mc_connection::Error as ConnectionError;
 use mc_consensus_api::ConversionError;
 use mc_crypto_keys::KeyError;
+use mc_fog_ledger_connection::Error as FogLedgerError;
+use mc_fog_types::view::FogTxOutError;
+use mc_fog_view_protocol::RngSetError;
 use mc_ledger_db::Error as LedgerDbError;
 use mc_transaction_core::FeeMapError;
+use mc_transaction_extra::SignedContingentInputError;
 use mc_util_lmdb::MetadataStoreError;
 use mc_util_serial::{decode::Error as DecodeError, encode::Error as EncodeError};
 use prost::DecodeError as ProstDecodeError;
@@ -115,6 +119,51 @@ pub enum Error {
 
     /// Fee map: {0}
     FeeMap(FeeMapError),
+
+    /// Signed contingent input: {0}
+    SignedContingentInput(SignedContingentInputError),
+
+    /// The key image of the signed contingent input is already spent
+    KeyImageAlreadySpent,
+
+    /// Signed contingent input is stale: {0}
+    StaleSignedContingentInput(String),
+
+    /// Unsupported swap: {0}
+    UnsupportedSwap(String),
+
+    /// The gift code was already claimed or cancelled
+    GiftCodeAlreadySpent,
+
+    /// The monitor is view-only and cannot spend
+    ViewOnlyMonitor,
+
+    /// The monitor has neither an account key nor a view account key
+    MonitorHasNoKeys,
+
+    /// Webhook delivery failed: {0}
+    Webhook(String),
+
+    /// A payment request with this id already exists for this monitor
+    PaymentRequestIdExists,
+
+    /// No matching payment request was found
+    PaymentRequestNotFound,
+
+    /// Fog view: {0}
+    FogView(String),
+
+    /// Fog ledger: {0}
+    FogLedger(FogLedgerError),
+
+    /// Fog rng set: {0}
+    FogRngSet(RngSetError),
+
+    /// Fog TxOut: {0}
+    FogTxOut(FogTxOutError),
+
+    /// Fog monitors need a fog view uri and a fog ledger uri to be configured
+    FogNotConfigured,
 }
 
 impl From<RetryError<ConnectionError>> for Error {
@@ -194,3 +243,27 @@ impl From<FeeMapError> for Error {
         Self::FeeMap(e)
     }
 }
+
+impl From<SignedContingentInputError> for Error {
+    fn from(e: SignedContingentInputError) -> Self {
+        Self::SignedContingentInput(e)
+    }
+}
+
+impl From<FogLedgerError> for Error {
+    fn from(e: FogLedgerError) -> Self {
+        Self::FogLedger(e)
+    }
+}
+
+impl From<RngSetError> for Error {
+    fn from(e: RngSetError) -> Self {
+        Self::FogRngSet(e)
+    }
+}
+
+impl From<FogTxOutError> for Error {
+    fn from(e: FogTxOutError) -> Self {
+        Self::FogTxOut(e)
+    }
+}
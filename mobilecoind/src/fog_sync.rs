@@ -0,0 +1,754 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! Syncs fog monitors, which discover their TxOuts through fog view and check
+//! whether they were spent through fog ledger, instead of scanning the ledger.
+//!
+//! Each poll of fog view returns the TxOuts fog ingest found for the account
+//! since the previous poll, as well as the block ranges fog ingest missed,
+//! which are scanned locally. A fog monitor processes blocks up to the
+//! smallest of the number of blocks fog view processed, the number of blocks
+//! fog ledger knows about, and the number of blocks in the local ledger.
+//! TxOuts fog view found in later blocks are kept in the monitor's fog state
+//! until the monitor gets to them.
+
+use crate::{
+    database::Database, error::Error, monitor_store::MonitorId, sync::match_tx_outs_into_utxos,
+    utxo_store::UnspentTxOut,
+};
+use mc_attest_verifier::Verifier;
+use mc_common::logger::{log, Logger};
+use mc_fog_ledger_connection::{FogKeyImageGrpcClient, KeyImageResultExtension};
+use mc_fog_types::{common::BlockRange, ledger::CheckKeyImagesResponse};
+use mc_fog_uri::{FogLedgerUri, FogViewUri};
+use mc_fog_view_connection::FogViewGrpcClient;
+use mc_fog_view_protocol::{FogViewConnection, StoredUserRngSet, UserPrivate, UserRngSet};
+use mc_ledger_db::{Ledger, LedgerDB};
+use mc_transaction_core::ring_signature::KeyImage;
+use mc_util_grpc::GrpcRetryConfig;
+use std::{collections::BTreeMap, sync::Arc};
+
+/// The maximal number of key images checked by a single fog ledger request.
+const MAX_KEY_IMAGES_PER_QUERY: usize = 100;
+
+/// What mobilecoind needs to connect to fog view and fog ledger.
+#[derive(Clone)]
+pub struct FogSyncConfig {
+    /// The id of the network we expect fog to run on. Ignored if empty.
+    pub chain_id: String,
+
+    /// Fog view uri.
+    pub view_uri: FogViewUri,
+
+    /// Fog ledger uri.
+    pub ledger_uri: FogLedgerUri,
+
+    /// Attestation verifier for the fog view enclave.
+    pub view_verifier: Verifier,
+
+    /// Attestation verifier for the fog ledger enclave.
+    pub ledger_verifier: Verifier,
+
+    /// Retry policy for fog requests.
+    pub grpc_retry_config: GrpcRetryConfig,
+
+    /// The grpc environment (thread pool) used by fog connections.
+    pub env: Arc<grpcio::Environment>,
+}
+
+/// Attested connections to fog view and fog ledger.
+/// Requests need exclusive access to the connections, so each sync worker
+/// thread holds its own.
+pub struct FogClients {
+    view_client: FogViewGrpcClient,
+    key_image_client: FogKeyImageGrpcClient,
+}
+
+/// Checks whether key images were spent, as fog ledger does.
+pub trait KeyImageChecker {
+    /// Check a list of key images.
+    fn check_key_images(
+        &mut self,
+        key_images: &[KeyImage],
+    ) -> Result<CheckKeyImagesResponse, Error>;
+}
+
+impl KeyImageChecker for FogKeyImageGrpcClient {
+    fn check_key_images(
+        &mut self,
+        key_images: &[KeyImage],
+    ) -> Result<CheckKeyImagesResponse, Error> {
+        Ok(FogKeyImageGrpcClient::check_key_images(self, key_images)?)
+    }
+}
+
+impl FogClients {
+    pub fn new(config: &FogSyncConfig, logger: &Logger) -> Self {
+        Self {
+            view_client: FogViewGrpcClient::new(
+                config.chain_id.clone(),
+                config.view_uri.clone(),
+                config.grpc_retry_config,
+                config.view_verifier.clone(),
+                config.env.clone(),
+                logger.clone(),
+            ),
+            key_image_client: FogKeyImageGrpcClient::new(
+                config.chain_id.clone(),
+                config.ledger_uri.clone(),
+                config.grpc_retry_config,
+                config.ledger_verifier.clone(),
+                config.env.clone(),
+                logger.clone(),
+            ),
+        }
+    }
+}
+
+/// Sync a single fog monitor, up to the block fog and the local ledger both
+/// reached.
+pub fn sync_fog_monitor(
+    ledger_db: &LedgerDB,
+    mobilecoind_db: &Database,
+    monitor_id: &MonitorId,
+    clients: &mut FogClients,
+    logger: &Logger,
+) -> Result<(), Error> {
+    sync_fog_monitor_with(
+        ledger_db,
+        mobilecoind_db,
+        monitor_id,
+        &mut clients.view_client,
+        &mut clients.key_image_client,
+        logger,
+    )
+}
+
+/// Sync a single fog monitor with the given fog view and fog ledger
+/// connections.
+fn sync_fog_monitor_with(
+    ledger_db: &LedgerDB,
+    mobilecoind_db: &Database,
+    monitor_id: &MonitorId,
+    view_client: &mut impl FogViewConnection,
+    key_image_client: &mut impl KeyImageChecker,
+    logger: &Logger,
+) -> Result<(), Error> {
+    let monitor_data = mobilecoind_db.get_monitor_data(monitor_id)?;
+    let mut fog_state = monitor_data.fog_state.clone().ok_or_else(|| {
+        Error::InvalidArgument("monitor_id".to_string(), "not a fog monitor".to_string())
+    })?;
+    let start_block = monitor_data.next_block;
+
+    // Poll fog view for the TxOuts received since the last poll. Errors leave
+    // the rng set where it can be polled from again, except for TxOuts that
+    // could not be decrypted, which are lost.
+    let mut rng_set = UserRngSet::try_from(fog_state.rng_set.clone())?;
+    let (tx_out_records, missed_block_ranges, errors) = view_client.poll(
+        &mut rng_set,
+        &UserPrivate::from(monitor_data.get_account_key()?),
+    );
+    for err in errors {
+        log::error!(logger, "fog view error for monitor {}: {}", monitor_id, err);
+    }
+    fog_state.rng_set = StoredUserRngSet::from(&rng_set);
+    fog_state.pending_tx_out_records.extend(tx_out_records);
+    fog_state
+        .pending_missed_block_ranges
+        .extend(missed_block_ranges);
+
+    let mut end_block = u64::from(rng_set.get_highest_processed_block_count())
+        .min(ledger_db.num_blocks()?)
+        .max(start_block);
+
+    // Find the TxOuts received in the blocks the monitor can process, from fog
+    // view and from scanning the blocks fog ingest missed.
+    let view_private_key = monitor_data.view_private_key()?;
+    let mut received_utxos = Vec::new();
+    for record in &fog_state.pending_tx_out_records {
+        if !(start_block..end_block).contains(&record.block_index) {
+            continue;
+        }
+        // Records that cannot be turned back into a TxOut are lost as well, rather
+        // than stopping the monitor at their block for good.
+        let tx_out = match record
+            .get_fog_tx_out()
+            .and_then(|fog_tx_out| fog_tx_out.try_recover_tx_out(view_private_key))
+        {
+            Ok(tx_out) => tx_out,
+            Err(err) => {
+                log::error!(
+                    logger,
+                    "skipping fog view record in block {} for monitor {}: {}",
+                    record.block_index,
+                    monitor_id,
+                    err
+                );
+                continue;
+            }
+        };
+        for utxo in
+            match_tx_outs_into_utxos(mobilecoind_db, &[tx_out], monitor_id, &monitor_data, logger)?
+        {
+            received_utxos.push((record.block_index, utxo));
+        }
+    }
+    for range in &fog_state.pending_missed_block_ranges {
+        for block_index in range.start_block.max(start_block)..range.end_block.min(end_block) {
+            let block_contents = ledger_db.get_block_contents(block_index)?;
+            for utxo in match_tx_outs_into_utxos(
+                mobilecoind_db,
+                &block_contents.outputs,
+                monitor_id,
+                &monitor_data,
+                logger,
+            )? {
+                received_utxos.push((block_index, utxo));
+            }
+        }
+    }
+
+    // Check which of the monitor's utxos were spent.
+    let mut key_images = Vec::new();
+    for index in monitor_data.subaddress_indexes() {
+        for utxo in mobilecoind_db.get_utxos_for_subaddress(monitor_id, index)? {
+            key_images.push(utxo.key_image);
+        }
+    }
+    key_images.extend(received_utxos.iter().map(|(_, utxo)| utxo.key_image));
+
+    let mut spent_key_images = Vec::new();
+    for chunk in key_images.chunks(MAX_KEY_IMAGES_PER_QUERY) {
+        let response = key_image_client.check_key_images(chunk)?;
+
+        // Spends in blocks fog ledger does not know about yet are not reported.
+        end_block = end_block.min(response.num_blocks).max(start_block);
+
+        for result in response.results {
+            match result.status() {
+                Ok(Some(spent_at)) => spent_key_images.push((spent_at, result.key_image)),
+                Ok(None) => {}
+                Err(err) => log::warn!(
+                    logger,
+                    "fog ledger could not check key image {:?} for monitor {}: {}",
+                    result.key_image,
+                    monitor_id,
+                    err
+                ),
+            }
+        }
+    }
+
+    // Group the events by block. Spends fog ledger reports before the monitor's
+    // next block were missed by earlier syncs, and are recorded in that block.
+    let mut blocks = BTreeMap::<u64, (Vec<UnspentTxOut>, Vec<KeyImage>)>::new();
+    for (block_index, utxo) in received_utxos {
+        if block_index < end_block {
+            blocks.entry(block_index).or_default().0.push(utxo);
+        }
+    }
+    for (spent_at, key_image) in spent_key_images {
+        if spent_at < end_block {
+            blocks
+                .entry(spent_at.max(start_block))
+                .or_default()
+                .1
+                .push(key_image);
+        }
+    }
+
+    // Keep what the monitor found past its new next block for the next sync.
+    fog_state
+        .pending_tx_out_records
+        .retain(|record| record.block_index >= end_block);
+    fog_state.pending_missed_block_ranges = fog_state
+        .pending_missed_block_ranges
+        .iter()
+        .filter(|range| range.end_block > end_block)
+        .map(|range| BlockRange::new(range.start_block.max(end_block), range.end_block))
+        .collect();
+
+    mobilecoind_db.fog_blocks_processed(monitor_id, start_block, end_block, &blocks, &fog_state)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        monitor_store::{FogMonitorState, MonitorData},
+        processed_block_store::ProcessedTxOutDirection,
+        test_utils::{
+            add_block_to_ledger, get_test_databases, BlockVersion, DEFAULT_PER_RECIPIENT_AMOUNT,
+        },
+    };
+    use mc_account_keys::{AccountKey, DEFAULT_SUBADDRESS_INDEX};
+    use mc_common::{
+        logger::{test_with_logger, Logger},
+        HashSet,
+    };
+    use mc_fog_types::{
+        ledger::{KeyImageResult, KeyImageResultCode},
+        view::{FogTxOut, FogTxOutMetadata, QueryResponse, TxOutRecord},
+    };
+    use mc_fog_view_protocol::TxOutPollingError;
+    use mc_transaction_core::{tokens::Mob, Amount, Token};
+    use rand::{rngs::StdRng, CryptoRng, RngCore, SeedableRng};
+
+    /// A fog view that returns the TxOut records and missed block ranges it was
+    /// given, once.
+    #[derive(Default)]
+    struct MockFogView {
+        tx_out_records: Vec<TxOutRecord>,
+        missed_block_ranges: Vec<BlockRange>,
+        num_blocks: u64,
+    }
+
+    impl FogViewConnection for MockFogView {
+        type Error = String;
+
+        fn request(
+            &mut self,
+            _start_from_user_event_id: i64,
+            _start_from_block_index: u64,
+            _search_keys: Vec<Vec<u8>>,
+        ) -> Result<QueryResponse, Self::Error> {
+            unimplemented!()
+        }
+
+        fn poll(
+            &mut self,
+            user_rng_set: &mut UserRngSet,
+            _upriv: &UserPrivate,
+        ) -> (
+            Vec<TxOutRecord>,
+            Vec<BlockRange>,
+            Vec<TxOutPollingError<Self::Error>>,
+        ) {
+            user_rng_set.set_highest_processed_block_count(self.num_blocks);
+            (
+                std::mem::take(&mut self.tx_out_records),
+                std::mem::take(&mut self.missed_block_ranges),
+                vec![],
+            )
+        }
+    }
+
+    /// A fog ledger that knows about the first `num_blocks` blocks of a ledger,
+    /// and fails to check the key images in `failing_key_images`.
+    struct MockKeyImageChecker {
+        ledger_db: LedgerDB,
+        num_blocks: u64,
+        failing_key_images: HashSet<KeyImage>,
+    }
+
+    impl KeyImageChecker for MockKeyImageChecker {
+        fn check_key_images(
+            &mut self,
+            key_images: &[KeyImage],
+        ) -> Result<CheckKeyImagesResponse, Error> {
+            let results = key_images
+                .iter()
+                .map(|key_image| {
+                    let spent_at = self
+                        .ledger_db
+                        .check_key_image(key_image)
+                        .unwrap()
+                        .filter(|block_index| *block_index < self.num_blocks);
+                    let key_image_result_code = if self.failing_key_images.contains(key_image) {
+                        KeyImageResultCode::KeyImageError
+                    } else if spent_at.is_some() {
+                        KeyImageResultCode::Spent
+                    } else {
+                        KeyImageResultCode::NotSpent
+                    };
+                    KeyImageResult {
+                        key_image: *key_image,
+                        spent_at: spent_at.unwrap_or_default(),
+                        timestamp: 0,
+                        timestamp_result_code: 0,
+                        key_image_result_code: key_image_result_code as u32,
+                    }
+                })
+                .collect();
+
+            Ok(CheckKeyImagesResponse {
+                num_blocks: self.num_blocks,
+                results,
+                ..Default::default()
+            })
+        }
+    }
+
+    /// Set up a ledger where every block pays a fog account, and a fog
+    /// monitor for that account.
+    fn setup(
+        num_blocks: usize,
+        logger: &Logger,
+        rng: &mut (impl CryptoRng + RngCore),
+    ) -> (AccountKey, LedgerDB, Database, MonitorId) {
+        let account_key = AccountKey::random_with_fog(rng);
+        let (ledger_db, mobilecoind_db) = get_test_databases(
+            BlockVersion::MAX,
+            0,
+            &[account_key.default_subaddress()],
+            num_blocks,
+            logger.clone(),
+            rng,
+        );
+
+        let data = MonitorData::new_fog(
+            account_key.clone(),
+            DEFAULT_SUBADDRESS_INDEX, // first_subaddress
+            1,                        // num_subaddresses
+            0,                        // first_block
+            "",                       // name
+        )
+        .unwrap();
+        let monitor_id = mobilecoind_db.add_monitor(&data).unwrap();
+
+        (account_key, ledger_db, mobilecoind_db, monitor_id)
+    }
+
+    /// The record fog view would return for the TxOut paying the monitor in a
+    /// block.
+    fn tx_out_record(ledger_db: &LedgerDB, block_index: u64) -> TxOutRecord {
+        let block_contents = ledger_db.get_block_contents(block_index).unwrap();
+        TxOutRecord::new(
+            FogTxOut::try_from(&block_contents.outputs[0]).unwrap(),
+            FogTxOutMetadata {
+                global_index: 0,
+                block_index,
+                timestamp: 0,
+            },
+        )
+    }
+
+    fn get_fog_state(mobilecoind_db: &Database, monitor_id: &MonitorId) -> FogMonitorState {
+        mobilecoind_db
+            .get_monitor_data(monitor_id)
+            .unwrap()
+            .fog_state
+            .unwrap()
+    }
+
+    fn num_utxos(mobilecoind_db: &Database, monitor_id: &MonitorId) -> usize {
+        mobilecoind_db
+            .get_utxos_for_subaddress(monitor_id, DEFAULT_SUBADDRESS_INDEX)
+            .unwrap()
+            .len()
+    }
+
+    // TxOuts fog view found in blocks fog ledger does not know about yet are
+    // kept until the monitor gets to them.
+    #[test_with_logger]
+    fn test_sync_fog_monitor_keeps_pending_records(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([42u8; 32]);
+        let (_account_key, ledger_db, mobilecoind_db, monitor_id) = setup(4, &logger, &mut rng);
+
+        let mut view_client = MockFogView {
+            tx_out_records: (0..4)
+                .map(|block_index| tx_out_record(&ledger_db, block_index))
+                .collect(),
+            missed_block_ranges: vec![],
+            num_blocks: 4,
+        };
+        let mut key_image_client = MockKeyImageChecker {
+            ledger_db: ledger_db.clone(),
+            num_blocks: 2,
+            failing_key_images: Default::default(),
+        };
+
+        sync_fog_monitor_with(
+            &ledger_db,
+            &mobilecoind_db,
+            &monitor_id,
+            &mut view_client,
+            &mut key_image_client,
+            &logger,
+        )
+        .unwrap();
+
+        let monitor_data = mobilecoind_db.get_monitor_data(&monitor_id).unwrap();
+        assert_eq!(monitor_data.next_block, 2);
+        assert_eq!(num_utxos(&mobilecoind_db, &monitor_id), 2);
+
+        let fog_state = get_fog_state(&mobilecoind_db, &monitor_id);
+        assert_eq!(
+            fog_state.rng_set.highest_processed_block_count, 4,
+            "the rng set should be saved even though the monitor did not get to block 4"
+        );
+        let pending_blocks: Vec<u64> = fog_state
+            .pending_tx_out_records
+            .iter()
+            .map(|record| record.block_index)
+            .collect();
+        assert_eq!(pending_blocks, vec![2, 3]);
+
+        // Once fog ledger catches up, the pending records are processed even
+        // though fog view has nothing new.
+        key_image_client.num_blocks = 4;
+        sync_fog_monitor_with(
+            &ledger_db,
+            &mobilecoind_db,
+            &monitor_id,
+            &mut view_client,
+            &mut key_image_client,
+            &logger,
+        )
+        .unwrap();
+
+        let monitor_data = mobilecoind_db.get_monitor_data(&monitor_id).unwrap();
+        assert_eq!(monitor_data.next_block, 4);
+        assert_eq!(num_utxos(&mobilecoind_db, &monitor_id), 4);
+        assert!(get_fog_state(&mobilecoind_db, &monitor_id)
+            .pending_tx_out_records
+            .is_empty());
+
+        let processed_tx_outs = mobilecoind_db.get_processed_block(&monitor_id, 3).unwrap();
+        assert_eq!(processed_tx_outs.len(), 1);
+        assert_eq!(
+            processed_tx_outs[0].direction,
+            ProcessedTxOutDirection::Received as i32
+        );
+    }
+
+    // Records that cannot be decrypted are skipped, and the monitor keeps
+    // making progress past them.
+    #[test_with_logger]
+    fn test_sync_fog_monitor_skips_corrupt_records(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([45u8; 32]);
+        let (_account_key, ledger_db, mobilecoind_db, monitor_id) = setup(3, &logger, &mut rng);
+
+        let mut corrupt_record = tx_out_record(&ledger_db, 1);
+        corrupt_record.tx_out_public_key_data = vec![0xff; 32];
+        let mut view_client = MockFogView {
+            tx_out_records: vec![
+                tx_out_record(&ledger_db, 0),
+                corrupt_record,
+                tx_out_record(&ledger_db, 2),
+            ],
+            missed_block_ranges: vec![],
+            num_blocks: 3,
+        };
+        let mut key_image_client = MockKeyImageChecker {
+            ledger_db: ledger_db.clone(),
+            num_blocks: 3,
+            failing_key_images: Default::default(),
+        };
+
+        sync_fog_monitor_with(
+            &ledger_db,
+            &mobilecoind_db,
+            &monitor_id,
+            &mut view_client,
+            &mut key_image_client,
+            &logger,
+        )
+        .unwrap();
+
+        let monitor_data = mobilecoind_db.get_monitor_data(&monitor_id).unwrap();
+        assert_eq!(monitor_data.next_block, 3);
+        assert_eq!(num_utxos(&mobilecoind_db, &monitor_id), 2);
+        assert_eq!(
+            mobilecoind_db
+                .get_processed_block(&monitor_id, 2)
+                .unwrap()
+                .len(),
+            1
+        );
+
+        let fog_state = get_fog_state(&mobilecoind_db, &monitor_id);
+        assert_eq!(fog_state.rng_set.highest_processed_block_count, 3);
+        assert!(fog_state.pending_tx_out_records.is_empty());
+    }
+
+    // Blocks fog ingest missed are scanned locally, including those the local
+    // ledger only gets later.
+    #[test_with_logger]
+    fn test_sync_fog_monitor_scans_missed_blocks(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([43u8; 32]);
+        let (account_key, mut ledger_db, mobilecoind_db, monitor_id) = setup(4, &logger, &mut rng);
+
+        let mut view_client = MockFogView {
+            tx_out_records: [0, 2, 3]
+                .iter()
+                .map(|block_index| tx_out_record(&ledger_db, *block_index))
+                .collect(),
+            missed_block_ranges: vec![BlockRange::new(1, 2), BlockRange::new(4, 6)],
+            num_blocks: 6,
+        };
+        let mut key_image_client = MockKeyImageChecker {
+            ledger_db: ledger_db.clone(),
+            num_blocks: 10,
+            failing_key_images: Default::default(),
+        };
+
+        sync_fog_monitor_with(
+            &ledger_db,
+            &mobilecoind_db,
+            &monitor_id,
+            &mut view_client,
+            &mut key_image_client,
+            &logger,
+        )
+        .unwrap();
+
+        // The local ledger only has 4 blocks.
+        let monitor_data = mobilecoind_db.get_monitor_data(&monitor_id).unwrap();
+        assert_eq!(monitor_data.next_block, 4);
+        assert_eq!(num_utxos(&mobilecoind_db, &monitor_id), 4);
+
+        let processed_tx_outs = mobilecoind_db.get_processed_block(&monitor_id, 1).unwrap();
+        assert_eq!(processed_tx_outs.len(), 1);
+
+        assert_eq!(
+            get_fog_state(&mobilecoind_db, &monitor_id).pending_missed_block_ranges,
+            vec![BlockRange::new(4, 6)]
+        );
+
+        // The rest of the missed blocks are scanned once they arrive.
+        for _ in 0..2 {
+            add_block_to_ledger(
+                &mut ledger_db,
+                BlockVersion::MAX,
+                &[account_key.default_subaddress()],
+                Amount::new(DEFAULT_PER_RECIPIENT_AMOUNT, Mob::ID),
+                &[KeyImage::from(rng.next_u64())],
+                &mut rng,
+            )
+            .unwrap();
+        }
+
+        sync_fog_monitor_with(
+            &ledger_db,
+            &mobilecoind_db,
+            &monitor_id,
+            &mut view_client,
+            &mut key_image_client,
+            &logger,
+        )
+        .unwrap();
+
+        let monitor_data = mobilecoind_db.get_monitor_data(&monitor_id).unwrap();
+        assert_eq!(monitor_data.next_block, 6);
+        assert_eq!(num_utxos(&mobilecoind_db, &monitor_id), 6);
+        assert!(get_fog_state(&mobilecoind_db, &monitor_id)
+            .pending_missed_block_ranges
+            .is_empty());
+    }
+
+    // Spends fog ledger reports in blocks the monitor already processed are
+    // recorded in the monitor's next block.
+    #[test_with_logger]
+    fn test_sync_fog_monitor_records_early_spends_in_next_block(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([44u8; 32]);
+        let (_account_key, mut ledger_db, mobilecoind_db, monitor_id) = setup(3, &logger, &mut rng);
+
+        let mut view_client = MockFogView {
+            tx_out_records: (0..3)
+                .map(|block_index| tx_out_record(&ledger_db, block_index))
+                .collect(),
+            missed_block_ranges: vec![],
+            num_blocks: 3,
+        };
+        let mut key_image_client = MockKeyImageChecker {
+            ledger_db: ledger_db.clone(),
+            num_blocks: 3,
+            failing_key_images: Default::default(),
+        };
+
+        sync_fog_monitor_with(
+            &ledger_db,
+            &mobilecoind_db,
+            &monitor_id,
+            &mut view_client,
+            &mut key_image_client,
+            &logger,
+        )
+        .unwrap();
+        assert_eq!(num_utxos(&mobilecoind_db, &monitor_id), 3);
+
+        // Spend the TxOut received in block 0 in block 3, and have fog ledger fail
+        // to check it.
+        let spent_public_key = ledger_db.get_block_contents(0).unwrap().outputs[0].public_key;
+        let spent_utxo = mobilecoind_db
+            .get_utxos_for_subaddress(&monitor_id, DEFAULT_SUBADDRESS_INDEX)
+            .unwrap()
+            .into_iter()
+            .find(|utxo| utxo.tx_out.public_key == spent_public_key)
+            .unwrap();
+
+        let other_address = AccountKey::random(&mut rng).default_subaddress();
+        add_block_to_ledger(
+            &mut ledger_db,
+            BlockVersion::MAX,
+            &[other_address.clone()],
+            Amount::new(DEFAULT_PER_RECIPIENT_AMOUNT, Mob::ID),
+            &[spent_utxo.key_image],
+            &mut rng,
+        )
+        .unwrap();
+
+        view_client.num_blocks = 4;
+        key_image_client.num_blocks = 4;
+        key_image_client
+            .failing_key_images
+            .insert(spent_utxo.key_image);
+
+        sync_fog_monitor_with(
+            &ledger_db,
+            &mobilecoind_db,
+            &monitor_id,
+            &mut view_client,
+            &mut key_image_client,
+            &logger,
+        )
+        .unwrap();
+
+        let monitor_data = mobilecoind_db.get_monitor_data(&monitor_id).unwrap();
+        assert_eq!(monitor_data.next_block, 4);
+        assert_eq!(num_utxos(&mobilecoind_db, &monitor_id), 3);
+
+        // Fog ledger now reports the spend in block 3, which the monitor already
+        // processed.
+        add_block_to_ledger(
+            &mut ledger_db,
+            BlockVersion::MAX,
+            &[other_address],
+            Amount::new(DEFAULT_PER_RECIPIENT_AMOUNT, Mob::ID),
+            &[KeyImage::from(rng.next_u64())],
+            &mut rng,
+        )
+        .unwrap();
+
+        view_client.num_blocks = 5;
+        key_image_client.num_blocks = 5;
+        key_image_client.failing_key_images.clear();
+
+        sync_fog_monitor_with(
+            &ledger_db,
+            &mobilecoind_db,
+            &monitor_id,
+            &mut view_client,
+            &mut key_image_client,
+            &logger,
+        )
+        .unwrap();
+
+        let monitor_data = mobilecoind_db.get_monitor_data(&monitor_id).unwrap();
+        assert_eq!(monitor_data.next_block, 5);
+        assert_eq!(num_utxos(&mobilecoind_db, &monitor_id), 2);
+
+        assert!(mobilecoind_db
+            .get_processed_block(&monitor_id, 3)
+            .unwrap()
+            .is_empty());
+
+        let processed_tx_outs = mobilecoind_db.get_processed_block(&monitor_id, 4).unwrap();
+        assert_eq!(processed_tx_outs.len(), 1);
+        assert_eq!(processed_tx_outs[0].key_image, spent_utxo.key_image);
+        assert_eq!(
+            processed_tx_outs[0].direction,
+            ProcessedTxOutDirection::Spent as i32
+        );
+    }
+}
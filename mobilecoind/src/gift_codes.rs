@@ -0,0 +1,403 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! Utilities for gift codes (see MCIP #32).
+//!
+//! A gift code is funded by sending a TxOut to the reserved gift code
+//! subaddress of the sender. The gift code itself reveals the global index,
+//! one-time private key and shared secret of that TxOut, which is everything
+//! needed to find it in the ledger, un-blind its amount and spend it. The
+//! recipient claims the gift code by spending the TxOut to themselves, while
+//! the sender may cancel it by spending the TxOut back to themselves before it
+//! is claimed.
+
+use crate::error::Error;
+use mc_account_keys::AccountKey;
+use mc_crypto_keys::{RistrettoPrivate, RistrettoPublic};
+use mc_ledger_db::Ledger;
+use mc_transaction_core::{
+    get_tx_out_shared_secret, onetime_keys::recover_onetime_private_key, ring_signature::KeyImage,
+    tx::TxOut, Amount,
+};
+use mc_transaction_extra::{MemoType, TxOutGiftCode};
+
+/// The status of a gift code.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum GiftCodeStatus {
+    /// The gift code TxOut has not been spent.
+    Unclaimed,
+
+    /// The gift code TxOut was spent by the recipient.
+    Claimed,
+
+    /// The gift code TxOut was spent back to the sender.
+    Cancelled,
+}
+
+/// Create the gift code for a TxOut that was sent to the gift code subaddress
+/// of an account.
+///
+/// # Arguments
+/// * `account_key` - The account that funded the gift code.
+/// * `tx_out` - The gift code TxOut.
+/// * `global_index` - The index of the gift code TxOut in the ledger.
+pub fn create_gift_code(
+    account_key: &AccountKey,
+    tx_out: &TxOut,
+    global_index: u64,
+) -> Result<TxOutGiftCode, Error> {
+    let tx_public_key = RistrettoPublic::try_from(&tx_out.public_key)?;
+    let onetime_private_key = recover_onetime_private_key(
+        &tx_public_key,
+        account_key.view_private_key(),
+        &account_key.gift_code_subaddress_spend_private(),
+    );
+    if RistrettoPublic::from(&onetime_private_key) != RistrettoPublic::try_from(&tx_out.target_key)?
+    {
+        return Err(Error::InvalidArgument(
+            "tx_out".to_string(),
+            "Not sent to the gift code subaddress of the account".to_string(),
+        ));
+    }
+
+    let shared_secret = get_tx_out_shared_secret(account_key.view_private_key(), &tx_public_key);
+    Ok(TxOutGiftCode::new(
+        global_index,
+        onetime_private_key,
+        shared_secret,
+    ))
+}
+
+/// Look up the TxOut of a gift code in the ledger, and check that the gift
+/// code can spend it. Returns the TxOut and its amount.
+pub fn get_gift_code_tx_out(
+    gift_code: &TxOutGiftCode,
+    ledger: &impl Ledger,
+) -> Result<(TxOut, Amount), Error> {
+    let tx_out = ledger.get_tx_out_by_index(gift_code.global_index)?;
+
+    if RistrettoPublic::from(&gift_code.onetime_private_key)
+        != RistrettoPublic::try_from(&tx_out.target_key)?
+    {
+        return Err(Error::InvalidArgument(
+            "gift_code".to_string(),
+            "One-time private key does not match the TxOut".to_string(),
+        ));
+    }
+
+    let amount = tx_out
+        .get_masked_amount()
+        .map_err(|err| Error::InvalidArgument("gift_code".to_string(), err.to_string()))
+        .and_then(|masked_amount| {
+            gift_code.unblind_amount(masked_amount).map_err(|err| {
+                Error::InvalidArgument(
+                    "gift_code".to_string(),
+                    format!("Shared secret does not match the TxOut: {}", err),
+                )
+            })
+        })?;
+
+    Ok((tx_out, amount))
+}
+
+/// Get the status of a gift code.
+///
+/// Once the gift code TxOut is spent, the status is `Cancelled` if the block
+/// that spent it contains an output received by one of the accounts of
+/// `view_private_keys` with a gift code cancellation memo for it, and `Claimed`
+/// otherwise. This means that cancellations are only detected when the account
+/// of the sender is known.
+pub fn get_gift_code_status<'a>(
+    gift_code: &TxOutGiftCode,
+    ledger: &impl Ledger,
+    view_private_keys: impl IntoIterator<Item = &'a RistrettoPrivate>,
+) -> Result<GiftCodeStatus, Error> {
+    let key_image = KeyImage::from(&gift_code.onetime_private_key);
+    let block_index = match ledger.check_key_image(&key_image)? {
+        Some(block_index) => block_index,
+        None => return Ok(GiftCodeStatus::Unclaimed),
+    };
+
+    let block_contents = ledger.get_block_contents(block_index)?;
+    let view_private_keys = view_private_keys.into_iter().collect::<Vec<_>>();
+    let cancelled = block_contents.outputs.iter().any(|tx_out| {
+        view_private_keys.iter().any(|view_private_key| {
+            is_cancellation_of(tx_out, view_private_key, gift_code.global_index)
+        })
+    });
+
+    if cancelled {
+        Ok(GiftCodeStatus::Cancelled)
+    } else {
+        Ok(GiftCodeStatus::Claimed)
+    }
+}
+
+/// Check whether a TxOut was received by the account of `view_private_key` with
+/// a gift code cancellation memo for the gift code TxOut at `global_index`.
+fn is_cancellation_of(
+    tx_out: &TxOut,
+    view_private_key: &RistrettoPrivate,
+    global_index: u64,
+) -> bool {
+    let shared_secret = match tx_out.view_key_match(view_private_key) {
+        Ok((_amount, shared_secret)) => shared_secret,
+        Err(_) => return false,
+    };
+
+    matches!(
+        MemoType::try_from(&tx_out.decrypt_memo(&shared_secret)),
+        Ok(MemoType::GiftCodeCancellation(memo)) if memo.cancelled_gift_code_index() == global_index
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use mc_ledger_db::{
+        test_utils::{add_txos_and_key_images_to_ledger, create_ledger, initialize_ledger},
+        LedgerDB,
+    };
+    use mc_transaction_core::{tokens::Mob, BlockVersion, Token};
+    use mc_transaction_extra::GiftCodeCancellationMemo;
+    use mc_util_from_random::FromRandom;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    const GIFT_CODE_VALUE: u64 = 1_000_000;
+
+    /// Create a ledger holding a gift code TxOut funded by `sender`, and
+    /// return it along with the TxOut and its global index.
+    fn ledger_with_gift_code_tx_out(
+        sender: &AccountKey,
+        rng: &mut StdRng,
+    ) -> (LedgerDB, TxOut, u64) {
+        let mut ledger_db = create_ledger();
+        initialize_ledger(
+            BlockVersion::MAX,
+            &mut ledger_db,
+            1,
+            &AccountKey::random(rng),
+            rng,
+        );
+
+        let tx_out = TxOut::new(
+            BlockVersion::MAX,
+            Amount::new(GIFT_CODE_VALUE, Mob::ID),
+            &sender.gift_code_subaddress(),
+            &RistrettoPrivate::from_random(rng),
+            Default::default(),
+        )
+        .unwrap();
+        add_txos_and_key_images_to_ledger(
+            &mut ledger_db,
+            BlockVersion::MAX,
+            vec![tx_out.clone()],
+            vec![],
+            rng,
+        )
+        .unwrap();
+        let global_index = ledger_db
+            .get_tx_out_index_by_public_key(&tx_out.public_key)
+            .unwrap();
+
+        (ledger_db, tx_out, global_index)
+    }
+
+    /// Add a block spending the gift code TxOut, with a single output to
+    /// `recipient` carrying an optional gift code cancellation memo.
+    fn spend_gift_code(
+        ledger_db: &mut LedgerDB,
+        gift_code: &TxOutGiftCode,
+        recipient: &AccountKey,
+        cancelled_gift_code_index: Option<u64>,
+        rng: &mut StdRng,
+    ) {
+        let tx_out = TxOut::new_with_memo(
+            BlockVersion::MAX,
+            Amount::new(GIFT_CODE_VALUE / 2, Mob::ID),
+            &recipient.default_subaddress(),
+            &RistrettoPrivate::from_random(rng),
+            Default::default(),
+            |_memo_ctxt| {
+                Ok(match cancelled_gift_code_index {
+                    Some(index) => GiftCodeCancellationMemo::new(index, 0).unwrap().into(),
+                    None => Default::default(),
+                })
+            },
+        )
+        .unwrap();
+        add_txos_and_key_images_to_ledger(
+            ledger_db,
+            BlockVersion::MAX,
+            vec![tx_out],
+            vec![KeyImage::from(&gift_code.onetime_private_key)],
+            rng,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_create_gift_code() {
+        let mut rng: StdRng = SeedableRng::from_seed([1u8; 32]);
+        let sender = AccountKey::random(&mut rng);
+        let (ledger_db, tx_out, global_index) = ledger_with_gift_code_tx_out(&sender, &mut rng);
+
+        let gift_code = create_gift_code(&sender, &tx_out, global_index).unwrap();
+        assert_eq!(gift_code.global_index, global_index);
+
+        // The gift code finds its TxOut, and un-blinds its amount.
+        let (found_tx_out, amount) = get_gift_code_tx_out(&gift_code, &ledger_db).unwrap();
+        assert_eq!(found_tx_out, tx_out);
+        assert_eq!(amount, Amount::new(GIFT_CODE_VALUE, Mob::ID));
+    }
+
+    #[test]
+    fn test_create_gift_code_rejects_other_tx_outs() {
+        let mut rng: StdRng = SeedableRng::from_seed([2u8; 32]);
+        let sender = AccountKey::random(&mut rng);
+        let (_ledger_db, tx_out, global_index) = ledger_with_gift_code_tx_out(&sender, &mut rng);
+
+        // Another account didn't fund the gift code.
+        assert!(matches!(
+            create_gift_code(&AccountKey::random(&mut rng), &tx_out, global_index),
+            Err(Error::InvalidArgument(name, _)) if name == "tx_out"
+        ));
+
+        // A TxOut sent to the default subaddress isn't a gift code.
+        let default_tx_out = TxOut::new(
+            BlockVersion::MAX,
+            Amount::new(GIFT_CODE_VALUE, Mob::ID),
+            &sender.default_subaddress(),
+            &RistrettoPrivate::from_random(&mut rng),
+            Default::default(),
+        )
+        .unwrap();
+        assert!(matches!(
+            create_gift_code(&sender, &default_tx_out, global_index),
+            Err(Error::InvalidArgument(name, _)) if name == "tx_out"
+        ));
+    }
+
+    #[test]
+    fn test_get_gift_code_tx_out_rejects_invalid_gift_codes() {
+        let mut rng: StdRng = SeedableRng::from_seed([3u8; 32]);
+        let sender = AccountKey::random(&mut rng);
+        let (ledger_db, tx_out, global_index) = ledger_with_gift_code_tx_out(&sender, &mut rng);
+        let gift_code = create_gift_code(&sender, &tx_out, global_index).unwrap();
+
+        // A gift code pointing at another TxOut.
+        let wrong_index =
+            TxOutGiftCode::new(0, gift_code.onetime_private_key, gift_code.shared_secret);
+        assert!(matches!(
+            get_gift_code_tx_out(&wrong_index, &ledger_db),
+            Err(Error::InvalidArgument(name, _)) if name == "gift_code"
+        ));
+
+        // A gift code that can't un-blind the amount.
+        let wrong_shared_secret = TxOutGiftCode::new(
+            global_index,
+            gift_code.onetime_private_key,
+            RistrettoPublic::from_random(&mut rng),
+        );
+        assert!(matches!(
+            get_gift_code_tx_out(&wrong_shared_secret, &ledger_db),
+            Err(Error::InvalidArgument(name, _)) if name == "gift_code"
+        ));
+
+        // A gift code pointing past the end of the ledger.
+        let missing = TxOutGiftCode::new(
+            ledger_db.num_txos().unwrap(),
+            gift_code.onetime_private_key,
+            gift_code.shared_secret,
+        );
+        assert!(matches!(
+            get_gift_code_tx_out(&missing, &ledger_db),
+            Err(Error::LedgerDB(_))
+        ));
+    }
+
+    #[test]
+    fn test_get_gift_code_status_claimed() {
+        let mut rng: StdRng = SeedableRng::from_seed([4u8; 32]);
+        let sender = AccountKey::random(&mut rng);
+        let (mut ledger_db, tx_out, global_index) = ledger_with_gift_code_tx_out(&sender, &mut rng);
+        let gift_code = create_gift_code(&sender, &tx_out, global_index).unwrap();
+
+        assert_eq!(
+            get_gift_code_status(&gift_code, &ledger_db, [sender.view_private_key()]).unwrap(),
+            GiftCodeStatus::Unclaimed
+        );
+
+        // The recipient spends the gift code TxOut to themselves.
+        let recipient = AccountKey::random(&mut rng);
+        spend_gift_code(&mut ledger_db, &gift_code, &recipient, None, &mut rng);
+
+        assert_eq!(
+            get_gift_code_status(&gift_code, &ledger_db, [sender.view_private_key()]).unwrap(),
+            GiftCodeStatus::Claimed
+        );
+        assert_eq!(
+            get_gift_code_status(&gift_code, &ledger_db, [recipient.view_private_key()]).unwrap(),
+            GiftCodeStatus::Claimed
+        );
+    }
+
+    #[test]
+    fn test_get_gift_code_status_cancelled() {
+        let mut rng: StdRng = SeedableRng::from_seed([5u8; 32]);
+        let sender = AccountKey::random(&mut rng);
+        let (mut ledger_db, tx_out, global_index) = ledger_with_gift_code_tx_out(&sender, &mut rng);
+        let gift_code = create_gift_code(&sender, &tx_out, global_index).unwrap();
+
+        // The sender spends the gift code TxOut back to themselves.
+        spend_gift_code(
+            &mut ledger_db,
+            &gift_code,
+            &sender,
+            Some(global_index),
+            &mut rng,
+        );
+
+        let other = AccountKey::random(&mut rng);
+        assert_eq!(
+            get_gift_code_status(
+                &gift_code,
+                &ledger_db,
+                [other.view_private_key(), sender.view_private_key()]
+            )
+            .unwrap(),
+            GiftCodeStatus::Cancelled
+        );
+
+        // The cancellation is only visible to the sender.
+        assert_eq!(
+            get_gift_code_status(&gift_code, &ledger_db, [other.view_private_key()]).unwrap(),
+            GiftCodeStatus::Claimed
+        );
+        assert_eq!(
+            get_gift_code_status(&gift_code, &ledger_db, []).unwrap(),
+            GiftCodeStatus::Claimed
+        );
+    }
+
+    #[test]
+    fn test_get_gift_code_status_cancellation_of_another_gift_code() {
+        let mut rng: StdRng = SeedableRng::from_seed([6u8; 32]);
+        let sender = AccountKey::random(&mut rng);
+        let (mut ledger_db, tx_out, global_index) = ledger_with_gift_code_tx_out(&sender, &mut rng);
+        let gift_code = create_gift_code(&sender, &tx_out, global_index).unwrap();
+
+        // The memo of the output cancels a different gift code.
+        spend_gift_code(
+            &mut ledger_db,
+            &gift_code,
+            &sender,
+            Some(global_index + 1),
+            &mut rng,
+        );
+
+        assert_eq!(
+            get_gift_code_status(&gift_code, &ledger_db, [sender.view_private_key()]).unwrap(),
+            GiftCodeStatus::Claimed
+        );
+    }
+}
@@ -4,20 +4,30 @@
 
 extern crate alloc;
 
+pub mod activity_export;
 pub mod config;
 pub mod database;
+pub mod gift_codes;
 pub mod payments;
 pub mod service;
+pub mod swaps;
 
 mod conversions;
 mod database_key;
 mod db_crypto;
 mod error;
+mod fog_sync;
 mod monitor_store;
+mod payment_request_store;
 mod processed_block_store;
+mod replacement_store;
+mod sent_tx_store;
 mod subaddress_store;
 mod sync;
 mod utxo_store;
+mod webhook_store;
+mod webhooks;
+pub use fog_sync::FogSyncConfig;
 pub use utxo_store::UnspentTxOut;
 
 #[cfg(any(test, feature = "test_utils"))]
@@ -4,16 +4,22 @@
 //! * Provides monitor configuration and status from MonitorId.
 //! * MonitorId is a hash of the instantiation parameters.
 
-use crate::{database_key::DatabaseByteArrayKey, db_crypto::DbCryptoProvider, error::Error};
+use crate::{
+    database_key::DatabaseByteArrayKey,
+    db_crypto::{DbCryptoProvider, EncryptionAudit},
+    error::Error,
+};
 
 use lmdb::{Cursor, Database, DatabaseFlags, Environment, RwTransaction, Transaction, WriteFlags};
-use mc_account_keys::AccountKey;
+use mc_account_keys::{AccountKey, PublicAddress, ViewAccountKey, DEFAULT_SUBADDRESS_INDEX};
 use mc_common::{
     logger::{log, Logger},
     HashMap,
 };
 use mc_crypto_digestible::{Digestible, MerlinTranscript};
-use mc_crypto_keys::RistrettoPublic;
+use mc_crypto_keys::{RistrettoPrivate, RistrettoPublic};
+use mc_fog_types::{common::BlockRange, view::TxOutRecord};
+use mc_fog_view_protocol::StoredUserRngSet;
 use mc_util_serial::Message;
 use std::{ops::Range, sync::Arc};
 
@@ -25,8 +31,9 @@ pub const MONITOR_ID_TO_MONITOR_DATA_DB_NAME: &str =
 #[derive(Clone, Eq, Hash, PartialEq, Message)]
 pub struct MonitorData {
     /// The private key pair for the account this monitor watches.
-    #[prost(message, required, tag = "1")]
-    pub account_key: AccountKey,
+    /// This is not set for view-only monitors.
+    #[prost(message, optional, tag = "1")]
+    pub account_key: Option<AccountKey>,
 
     /// The smallest subaddress index in the range this monitor watches.
     #[prost(uint64, tag = "2")]
@@ -47,6 +54,57 @@ pub struct MonitorData {
     /// Optional monitor name.
     #[prost(string, tag = "6")]
     pub name: String,
+
+    /// The view private key and spend public key of the account this monitor
+    /// watches. This is only set for view-only monitors, which can detect
+    /// incoming TxOuts but cannot spend them.
+    #[prost(message, optional, tag = "7")]
+    pub view_account_key: Option<ViewAccountKey>,
+
+    /// When non-zero, the subaddress range grows whenever a TxOut is received
+    /// on one of its last `gap_limit` subaddresses, so that there are always
+    /// at least `gap_limit` unused subaddresses past the highest used one.
+    #[prost(uint64, tag = "8")]
+    pub gap_limit: u64,
+
+    /// The block the monitor had reached when it was last rescanned. The
+    /// monitor is rescanning until its next block catches up with it.
+    #[prost(uint64, tag = "9")]
+    pub rescan_end_block: u64,
+
+    /// Set for monitors that discover their TxOuts through fog view and check
+    /// their spends through fog ledger, instead of scanning the ledger.
+    #[prost(message, optional, tag = "10")]
+    pub fog_state: Option<FogMonitorState>,
+
+    /// The highest subaddress index a TxOut was received on, which the gap
+    /// limit is applied to.
+    #[prost(uint64, optional, tag = "11")]
+    pub highest_used_subaddress: Option<u64>,
+
+    /// Incremented whenever the events of blocks the monitor already processed
+    /// change, because it was rescanned or because growing its subaddress
+    /// range found TxOuts in those blocks.
+    #[prost(uint64, tag = "12")]
+    pub rescan_generation: u64,
+}
+
+/// How far a fog monitor got polling fog view.
+#[derive(Clone, Eq, Hash, PartialEq, Message)]
+pub struct FogMonitorState {
+    /// The rngs used to search fog view, and the events the monitor already
+    /// received.
+    #[prost(message, required, tag = "1")]
+    pub rng_set: StoredUserRngSet,
+
+    /// TxOuts found by fog view in blocks the monitor could not process yet.
+    #[prost(message, repeated, tag = "2")]
+    pub pending_tx_out_records: Vec<TxOutRecord>,
+
+    /// Block ranges fog ingest did not process, which the monitor still has to
+    /// scan itself.
+    #[prost(message, repeated, tag = "3")]
+    pub pending_missed_block_ranges: Vec<BlockRange>,
 }
 
 impl MonitorData {
@@ -65,31 +123,181 @@ impl MonitorData {
         }
 
         Ok(Self {
-            account_key,
+            account_key: Some(account_key),
+            first_subaddress,
+            num_subaddresses,
+            first_block,
+            // The next block we need to sync is our first block.
+            next_block: first_block,
+            name: name.to_owned(),
+            view_account_key: None,
+            gap_limit: 0,
+            rescan_end_block: 0,
+            fog_state: None,
+            highest_used_subaddress: None,
+            rescan_generation: 0,
+        })
+    }
+
+    /// Create a view-only monitor, which never holds the spend private key.
+    /// Key images of the TxOuts it receives have to be computed offline and
+    /// imported, and transactions it generates have to be signed offline.
+    pub fn new_view_only(
+        view_account_key: ViewAccountKey,
+        first_subaddress: u64,
+        num_subaddresses: u64,
+        first_block: u64,
+        name: &str,
+    ) -> Result<Self, Error> {
+        if num_subaddresses == 0 {
+            return Err(Error::InvalidArgument(
+                "num_subaddresses".to_string(),
+                "must be greater than zero".to_string(),
+            ));
+        }
+
+        Ok(Self {
+            account_key: None,
             first_subaddress,
             num_subaddresses,
             first_block,
             // The next block we need to sync is our first block.
             next_block: first_block,
             name: name.to_owned(),
+            view_account_key: Some(view_account_key),
+            gap_limit: 0,
+            rescan_end_block: 0,
+            fog_state: None,
+            highest_used_subaddress: None,
+            rescan_generation: 0,
         })
     }
 
+    /// Create a fog monitor, which discovers the TxOuts of a fog account
+    /// through fog view, and checks whether they were spent through fog
+    /// ledger, instead of scanning the ledger.
+    /// Fog view can only be searched with the spend private key, so fog
+    /// monitors cannot be view-only.
+    pub fn new_fog(
+        account_key: AccountKey,
+        first_subaddress: u64,
+        num_subaddresses: u64,
+        first_block: u64,
+        name: &str,
+    ) -> Result<Self, Error> {
+        if account_key.fog_report_url().is_none() {
+            return Err(Error::InvalidArgument(
+                "account_key".to_string(),
+                "must have a fog report url".to_string(),
+            ));
+        }
+
+        let mut data = Self::new(
+            account_key,
+            first_subaddress,
+            num_subaddresses,
+            first_block,
+            name,
+        )?;
+        data.fog_state = Some(FogMonitorState::default());
+        Ok(data)
+    }
+
     pub fn subaddress_indexes(&self) -> Range<u64> {
         self.first_subaddress..self.first_subaddress + self.num_subaddresses
     }
+
+    /// The number of subaddresses this monitor should watch once a TxOut has
+    /// been received on subaddress `used_index`, if its gap limit requires the
+    /// range to grow.
+    pub fn gap_limit_num_subaddresses(&self, used_index: u64) -> Option<u64> {
+        if self.gap_limit == 0 || !self.subaddress_indexes().contains(&used_index) {
+            return None;
+        }
+
+        let num_subaddresses = used_index - self.first_subaddress + 1 + self.gap_limit;
+        if num_subaddresses > self.num_subaddresses {
+            Some(num_subaddresses)
+        } else {
+            None
+        }
+    }
+
+    /// Remember that TxOuts were received on the given subaddresses.
+    pub fn record_used_subaddresses(&mut self, indexes: impl IntoIterator<Item = u64>) {
+        self.highest_used_subaddress = indexes
+            .into_iter()
+            .chain(self.highest_used_subaddress)
+            .max();
+    }
+
+    /// The number of subaddresses this monitor should watch given the highest
+    /// subaddress it received a TxOut on, if its gap limit requires the range
+    /// to grow.
+    pub fn gap_limit_pending_num_subaddresses(&self) -> Option<u64> {
+        self.highest_used_subaddress
+            .and_then(|used_index| self.gap_limit_num_subaddresses(used_index))
+    }
+
+    /// Whether this monitor is processing blocks it already processed before
+    /// being rescanned.
+    pub fn is_rescanning(&self) -> bool {
+        self.next_block < self.rescan_end_block
+    }
+
+    /// Whether this monitor discovers its TxOuts through fog.
+    pub fn is_fog(&self) -> bool {
+        self.fog_state.is_some()
+    }
+
+    /// Whether this monitor is view-only, i.e. does not hold the spend private
+    /// key.
+    pub fn is_view_only(&self) -> bool {
+        self.account_key.is_none()
+    }
+
+    /// Get the account key of this monitor, which is needed for spending.
+    /// Fails for view-only monitors.
+    pub fn get_account_key(&self) -> Result<&AccountKey, Error> {
+        self.account_key.as_ref().ok_or(Error::ViewOnlyMonitor)
+    }
+
+    /// Get the view private key of the account this monitor watches.
+    /// Fails if the monitor data has neither key, which the constructors never
+    /// produce but a corrupted record could.
+    pub fn view_private_key(&self) -> Result<&RistrettoPrivate, Error> {
+        match (&self.account_key, &self.view_account_key) {
+            (Some(account_key), _) => Ok(account_key.view_private_key()),
+            (None, Some(view_account_key)) => Ok(view_account_key.view_private_key()),
+            (None, None) => Err(Error::MonitorHasNoKeys),
+        }
+    }
+
+    /// Get the i^th subaddress of the account this monitor watches.
+    /// Fails if the monitor data has neither key.
+    pub fn subaddress(&self, index: u64) -> Result<PublicAddress, Error> {
+        match (&self.account_key, &self.view_account_key) {
+            (Some(account_key), _) => Ok(account_key.subaddress(index)),
+            (None, Some(view_account_key)) => Ok(view_account_key.subaddress(index)),
+            (None, None) => Err(Error::MonitorHasNoKeys),
+        }
+    }
 }
 
 /// Type used as the key in the monitor_id_to_monitor_data database
 pub type MonitorId = DatabaseByteArrayKey;
 
-impl From<&MonitorData> for MonitorId {
+impl TryFrom<&MonitorData> for MonitorId {
+    type Error = Error;
+
     // When constructing a MonitorId from a given MonitorData object we only want to
     // hash the data that doesn't change over time.
     // Name isn't included here - two monitors with identical address/subaddress
     // range/first_block should have the same id even if they have a different
     // name,
-    fn from(src: &MonitorData) -> MonitorId {
+    // Neither is the gap limit, and the range is the one the monitor was created
+    // with - a monitor keeps its id when its gap limit makes the range grow.
+    fn try_from(src: &MonitorData) -> Result<MonitorId, Error> {
         // The structure of mc_account_keys::PublicAddress changed when the fog
         // signature scheme was implemented. This re-implements the original
         // structure in order to maintain a consistent hash in the database.
@@ -119,7 +327,7 @@ impl From<&MonitorData> for MonitorId {
             pub first_block: u64,
         }
 
-        let real_subaddress = src.account_key.default_subaddress();
+        let real_subaddress = src.subaddress(DEFAULT_SUBADDRESS_INDEX)?;
 
         let const_data = ConstMonitorData {
             address: PublicAddress {
@@ -145,7 +353,7 @@ impl From<&MonitorData> for MonitorId {
 
         let temp: [u8; 32] = const_data.digest32::<MerlinTranscript>(b"monitor_data");
 
-        Self::from(temp)
+        Ok(Self::from(temp))
     }
 }
 
@@ -191,7 +399,7 @@ impl MonitorStore {
         db_txn: &mut RwTransaction<'env>,
         data: &MonitorData,
     ) -> Result<MonitorId, Error> {
-        let monitor_id = MonitorId::from(data);
+        let monitor_id = MonitorId::try_from(data)?;
         let key_bytes = monitor_id.as_bytes();
 
         let value_bytes = self
@@ -325,6 +533,29 @@ impl MonitorStore {
 
         Ok(())
     }
+
+    /// Count the records that cannot be decrypted with the current password.
+    pub fn audit_encryption(&self, db_txn: &impl Transaction) -> Result<EncryptionAudit, Error> {
+        let mut cursor = db_txn.open_ro_cursor(self.monitor_id_to_monitor_data)?;
+
+        let mut audit = EncryptionAudit::default();
+        for result in cursor.iter() {
+            let (_key_bytes, value_bytes) = result?;
+            audit.num_records += 1;
+
+            let readable = self
+                .crypto_provider
+                .decrypt(value_bytes)
+                .ok()
+                .and_then(|value_bytes| mc_util_serial::decode::<MonitorData>(&value_bytes).ok())
+                .is_some();
+            if !readable {
+                audit.num_unreadable += 1;
+            }
+        }
+
+        Ok(audit)
+    }
 }
 
 #[cfg(test)]
@@ -374,7 +605,7 @@ pKZkdp8MQU5TLFOE9qjNeVsCAwEAAQ==
         let key = AccountKey::try_from(&identity)
             .expect("Could not create account key from non-fog identity");
         let data = MonitorData::new(key, 1, 10, 1, "test").expect("Could not create monitor data");
-        let id = MonitorId::from(&data);
+        let id = MonitorId::try_from(&data).unwrap();
         let expected = hex::decode(HEXPECTED).expect("Could not decode expected data to bytes");
         assert_eq!(expected, id.as_bytes().to_vec(), "{}", hex_fmt::HexFmt(id));
 
@@ -390,7 +621,7 @@ pKZkdp8MQU5TLFOE9qjNeVsCAwEAAQ==
         let fog_key = AccountKey::from(&fog_identity);
         let fog_data = MonitorData::new(fog_key, 10, 100, 10, "fog test")
             .expect("Could not create monitor data");
-        let fog_id = MonitorId::from(&fog_data);
+        let fog_id = MonitorId::try_from(&fog_data).unwrap();
         let fog_expected =
             hex::decode(FOG_HEXPECTED).expect("Could not decode expected data to bytes");
         assert_eq!(
@@ -402,6 +633,76 @@ pKZkdp8MQU5TLFOE9qjNeVsCAwEAAQ==
         );
     }
 
+    #[test]
+    fn test_gap_limit_num_subaddresses() {
+        let mut rng = ChaChaRng::from_seed([123u8; 32]);
+        let (mut data, _monitor_id) = get_test_monitor_data_and_id(&mut rng);
+        data.first_subaddress = 10;
+        data.num_subaddresses = 5;
+
+        // Without a gap limit the range never grows.
+        assert_eq!(data.gap_limit_num_subaddresses(14), None);
+
+        data.gap_limit = 3;
+        assert_eq!(data.gap_limit_num_subaddresses(10), None);
+        assert_eq!(data.gap_limit_num_subaddresses(11), None);
+        assert_eq!(data.gap_limit_num_subaddresses(12), Some(6));
+        assert_eq!(data.gap_limit_num_subaddresses(14), Some(8));
+
+        // Subaddresses outside of the range are ignored.
+        assert_eq!(data.gap_limit_num_subaddresses(9), None);
+        assert_eq!(data.gap_limit_num_subaddresses(15), None);
+    }
+
+    #[test]
+    fn test_new_fog() {
+        let mut rng = ChaChaRng::from_seed([123u8; 32]);
+
+        // Fog monitors need an account with fog.
+        let key = AccountKey::random(&mut rng);
+        assert_matches!(
+            MonitorData::new_fog(key, 0, 10, 0, ""),
+            Err(Error::InvalidArgument(_, _))
+        );
+
+        let fog_authority_spki = pem::parse(AUTHORITY_PUBKEY)
+            .expect("Could not parse pubkey")
+            .contents;
+        let fog_key = AccountKey::from(&RootIdentity::random_with_fog(
+            &mut rng,
+            "fog://fog.unittest.mobilecoin.com",
+            "",
+            &fog_authority_spki,
+        ));
+        let data = MonitorData::new_fog(fog_key.clone(), 0, 10, 5, "").unwrap();
+        assert!(data.is_fog());
+        assert_eq!(data.fog_state, Some(FogMonitorState::default()));
+        assert_eq!(data.next_block, 5);
+
+        // A fog monitor has the same id as a monitor scanning the ledger for the
+        // same account.
+        let ledger_data = MonitorData::new(fog_key, 0, 10, 5, "").unwrap();
+        assert!(!ledger_data.is_fog());
+        assert_eq!(
+            MonitorId::try_from(&data).unwrap(),
+            MonitorId::try_from(&ledger_data).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_monitor_data_without_keys() {
+        let mut rng = ChaChaRng::from_seed([123u8; 32]);
+        let (mut data, _monitor_id) = get_test_monitor_data_and_id(&mut rng);
+
+        // A record with neither key, e.g. a corrupted one, is reported as an
+        // error instead of crashing mobilecoind.
+        data.account_key = None;
+        data.view_account_key = None;
+        assert_matches!(data.view_private_key(), Err(Error::MonitorHasNoKeys));
+        assert_matches!(data.subaddress(0), Err(Error::MonitorHasNoKeys));
+        assert_matches!(MonitorId::try_from(&data), Err(Error::MonitorHasNoKeys));
+    }
+
     // MonitorStore basic functionality tests
     #[test_with_logger]
     fn test_monitor_store(logger: Logger) {
@@ -0,0 +1,581 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! Database storage for the payment requests issued by monitors.
+//! * Stores a map of (monitor id, payment request id) -> data about a payment
+//!   request issued by that monitor, along with the payments received for it.
+//!
+//! Payers reference a payment request by putting its id in the authenticated
+//! sender memo of the TxOuts paying it. When a monitor receives such a TxOut
+//! on the subaddress and in the token of the request, it is recorded as a
+//! payment of that request.
+
+use crate::{error::Error, monitor_store::MonitorId, utxo_store::UnspentTxOut};
+use lmdb::{Cursor, Database, DatabaseFlags, Environment, RwTransaction, Transaction, WriteFlags};
+use mc_common::logger::{log, Logger};
+use mc_crypto_keys::{CompressedRistrettoPublic, RistrettoPrivate, RistrettoPublic};
+use mc_transaction_core::get_tx_out_shared_secret;
+use mc_transaction_extra::MemoType;
+use prost::Message;
+use std::sync::Arc;
+
+// LMDB Database Names
+pub const PAYMENT_REQUEST_KEY_TO_PAYMENT_REQUEST_DB_NAME: &str =
+    "mobilecoind_db:payment_request_store:payment_request_key_to_payment_request";
+
+/// Type used as the key in the payment_request_key_to_payment_request
+/// database.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct PaymentRequestKey {
+    /// The monitor_id that issued the payment request.
+    pub monitor_id: MonitorId,
+
+    /// The id of the payment request.
+    pub id: u64,
+}
+impl PaymentRequestKey {
+    pub fn new(monitor_id: &MonitorId, id: u64) -> Self {
+        Self {
+            monitor_id: *monitor_id,
+            id,
+        }
+    }
+
+    // 40 bytes: 32 for MonitorId, 8 for id.
+    pub fn to_bytes(&self) -> [u8; 40] {
+        let mut buf = [0u8; 40];
+        buf[0..32].copy_from_slice(self.monitor_id.as_bytes());
+        buf[32..40].copy_from_slice(&self.id.to_be_bytes());
+        buf
+    }
+
+    pub fn to_vec(&self) -> Vec<u8> {
+        self.to_bytes().to_vec()
+    }
+}
+
+impl TryFrom<&[u8]> for PaymentRequestKey {
+    type Error = Error;
+
+    fn try_from(src: &[u8]) -> Result<Self, Self::Error> {
+        if src.len() != 40 {
+            return Err(Error::InvalidArgument(
+                "src".to_string(),
+                "src length must be exactly 40".to_string(),
+            ));
+        }
+
+        let monitor_id = MonitorId::try_from(&src[0..32])?;
+
+        let mut id_bytes = [0u8; 8];
+        id_bytes.copy_from_slice(&src[32..40]);
+        let id = u64::from_be_bytes(id_bytes);
+
+        Ok(Self { monitor_id, id })
+    }
+}
+
+/// A payment received for a payment request.
+#[derive(Clone, Eq, Hash, PartialEq, Message)]
+pub struct PaymentRequestPayment {
+    /// The block the payment was received in.
+    #[prost(uint64, tag = "1")]
+    pub block_index: u64,
+
+    /// The public key of the TxOut paying the request.
+    #[prost(message, required, tag = "2")]
+    pub tx_out_public_key: CompressedRistrettoPublic,
+
+    /// The value paid.
+    #[prost(uint64, tag = "3")]
+    pub value: u64,
+}
+
+/// Type used as the stored data in the payment_request_key_to_payment_request
+/// database.
+#[derive(Clone, Eq, Hash, PartialEq, Message)]
+pub struct PaymentRequest {
+    /// The subaddress payments are requested to.
+    #[prost(uint64, tag = "1")]
+    pub subaddress_index: u64,
+
+    /// The requested value.
+    #[prost(uint64, tag = "2")]
+    pub value: u64,
+
+    /// The token id of the requested value.
+    #[prost(uint64, tag = "3")]
+    pub token_id: u64,
+
+    /// Text explaining the request.
+    #[prost(string, tag = "4")]
+    pub memo: String,
+
+    /// The block at which the request expires, or 0 if it never does.
+    /// Payments received in or after this block are not recorded.
+    #[prost(uint64, tag = "5")]
+    pub expiry_block: u64,
+
+    /// The next block of the monitor when the request was created.
+    #[prost(uint64, tag = "6")]
+    pub created_block: u64,
+
+    /// The payments received for the request.
+    #[prost(message, repeated, tag = "7")]
+    pub payments: Vec<PaymentRequestPayment>,
+}
+
+impl PaymentRequest {
+    /// The total value paid for the request.
+    pub fn paid_value(&self) -> u128 {
+        self.payments
+            .iter()
+            .map(|payment| payment.value as u128)
+            .sum()
+    }
+
+    /// The status of the request, once the monitor that issued it processed
+    /// all blocks before `next_block`.
+    pub fn status(&self, next_block: u64) -> PaymentRequestStatus {
+        let paid_value = self.paid_value();
+        if !self.payments.is_empty() && paid_value >= self.value as u128 {
+            PaymentRequestStatus::Paid
+        } else if self.expiry_block != 0 && next_block >= self.expiry_block {
+            PaymentRequestStatus::Expired
+        } else if paid_value > 0 {
+            PaymentRequestStatus::PartiallyPaid
+        } else {
+            PaymentRequestStatus::Pending
+        }
+    }
+}
+
+/// The status of a payment request.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum PaymentRequestStatus {
+    /// Nothing was paid yet.
+    Pending,
+
+    /// Some, but not all, of the requested value was paid.
+    PartiallyPaid,
+
+    /// The requested value was paid in full.
+    Paid,
+
+    /// The request expired before being paid in full.
+    Expired,
+}
+
+#[derive(Clone)]
+pub struct PaymentRequestStore {
+    /// Retain a reference to the Environment so the Database handles are valid.
+    _env: Arc<Environment>,
+
+    /// Mapping of PaymentRequestKey -> PaymentRequest
+    payment_request_key_to_payment_request: Database,
+
+    /// Logger.
+    logger: Logger,
+}
+
+impl PaymentRequestStore {
+    pub fn new(env: Arc<Environment>, logger: Logger) -> Result<Self, Error> {
+        let payment_request_key_to_payment_request = env.create_db(
+            Some(PAYMENT_REQUEST_KEY_TO_PAYMENT_REQUEST_DB_NAME),
+            DatabaseFlags::empty(),
+        )?;
+
+        Ok(Self {
+            _env: env,
+            payment_request_key_to_payment_request,
+            logger,
+        })
+    }
+
+    /// Insert a new payment request. Fails with `PaymentRequestIdExists` if
+    /// the monitor already has a payment request with the same id.
+    pub fn insert<'env>(
+        &self,
+        db_txn: &mut RwTransaction<'env>,
+        key: &PaymentRequestKey,
+        payment_request: &PaymentRequest,
+    ) -> Result<(), Error> {
+        match db_txn.put(
+            self.payment_request_key_to_payment_request,
+            &key.to_vec(),
+            &mc_util_serial::encode(payment_request),
+            WriteFlags::NO_OVERWRITE,
+        ) {
+            Ok(()) => Ok(()),
+            Err(lmdb::Error::KeyExist) => Err(Error::PaymentRequestIdExists),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Get a payment request.
+    pub fn get(
+        &self,
+        db_txn: &impl Transaction,
+        key: &PaymentRequestKey,
+    ) -> Result<PaymentRequest, Error> {
+        match db_txn.get(self.payment_request_key_to_payment_request, &key.to_vec()) {
+            Ok(value_bytes) => Ok(mc_util_serial::decode(value_bytes)?),
+            Err(lmdb::Error::NotFound) => Err(Error::PaymentRequestNotFound),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Get all the payment requests issued by a monitor, ordered by id.
+    pub fn list(
+        &self,
+        db_txn: &impl Transaction,
+        monitor_id: &MonitorId,
+    ) -> Result<Vec<(PaymentRequestKey, PaymentRequest)>, Error> {
+        let mut cursor = db_txn.open_ro_cursor(self.payment_request_key_to_payment_request)?;
+        let start_key_bytes = PaymentRequestKey::new(monitor_id, 0).to_vec();
+
+        let mut results = Vec::new();
+        for result in cursor.iter_from(&start_key_bytes) {
+            let (db_key, db_value) = result?;
+            let key = PaymentRequestKey::try_from(db_key)?;
+            if key.monitor_id != *monitor_id {
+                break;
+            }
+            results.push((key, mc_util_serial::decode(db_value)?));
+        }
+        Ok(results)
+    }
+
+    /// Record the payments found among the TxOuts a monitor received in a
+    /// block. A TxOut pays a payment request of the monitor if its memo
+    /// carries the id of the request, and it was received on the subaddress
+    /// and in the token of the request before it expired. The memo is not
+    /// authenticated, since that requires knowing the payer, but the value is
+    /// received by the monitor regardless of who sent it.
+    pub fn block_processed<'env>(
+        &self,
+        db_txn: &mut RwTransaction<'env>,
+        monitor_id: &MonitorId,
+        view_private_key: &RistrettoPrivate,
+        block_index: u64,
+        discovered_utxos: &[UnspentTxOut],
+    ) -> Result<(), Error> {
+        for utxo in discovered_utxos {
+            let id = match payment_request_id(&utxo.tx_out, view_private_key) {
+                Some(id) => id,
+                None => continue,
+            };
+
+            let key = PaymentRequestKey::new(monitor_id, id);
+            let mut payment_request = match self.get(db_txn, &key) {
+                Ok(payment_request) => payment_request,
+                Err(Error::PaymentRequestNotFound) => continue,
+                Err(err) => return Err(err),
+            };
+
+            if payment_request.subaddress_index != utxo.subaddress_index
+                || payment_request.token_id != utxo.token_id
+                || (payment_request.expiry_block != 0
+                    && block_index >= payment_request.expiry_block)
+            {
+                continue;
+            }
+
+            // A rescan of the monitor finds the same TxOuts again.
+            if payment_request
+                .payments
+                .iter()
+                .any(|payment| payment.tx_out_public_key == utxo.tx_out.public_key)
+            {
+                continue;
+            }
+
+            payment_request.payments.push(PaymentRequestPayment {
+                block_index,
+                tx_out_public_key: utxo.tx_out.public_key,
+                value: utxo.value,
+            });
+            db_txn.put(
+                self.payment_request_key_to_payment_request,
+                &key.to_vec(),
+                &mc_util_serial::encode(&payment_request),
+                WriteFlags::empty(),
+            )?;
+
+            log::debug!(
+                self.logger,
+                "Monitor {} received {} for payment request {} in block {}",
+                monitor_id,
+                utxo.value,
+                id,
+                block_index,
+            );
+        }
+        Ok(())
+    }
+
+    /// Remove the payment requests of a given monitor id.
+    pub fn remove<'env>(
+        &self,
+        db_txn: &mut RwTransaction<'env>,
+        monitor_id: &MonitorId,
+    ) -> Result<(), Error> {
+        let start_key_bytes = PaymentRequestKey::new(monitor_id, 0).to_vec();
+
+        let mut cursor = db_txn.open_rw_cursor(self.payment_request_key_to_payment_request)?;
+        for (db_key, _db_value) in cursor.iter_from(&start_key_bytes).filter_map(|r| r.ok()) {
+            let key = PaymentRequestKey::try_from(db_key)?;
+            if key.monitor_id == *monitor_id {
+                cursor.del(WriteFlags::empty())?;
+            } else {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The payment request id carried by the memo of a TxOut, if any.
+fn payment_request_id(
+    tx_out: &mc_transaction_core::tx::TxOut,
+    view_private_key: &RistrettoPrivate,
+) -> Option<u64> {
+    tx_out.e_memo.as_ref()?;
+    let tx_public_key = RistrettoPublic::try_from(&tx_out.public_key).ok()?;
+    let shared_secret = get_tx_out_shared_secret(view_private_key, &tx_public_key);
+    match MemoType::try_from(&tx_out.decrypt_memo(&shared_secret)).ok()? {
+        MemoType::AuthenticatedSenderWithPaymentRequestId(memo) => Some(memo.payment_request_id()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use mc_account_keys::{AccountKey, PublicAddress};
+    use mc_common::logger::{test_with_logger, Logger};
+    use mc_transaction_core::{
+        ring_signature::KeyImage, tokens::Mob, tx::TxOut, Amount, BlockVersion, Token,
+    };
+    use mc_transaction_extra::{AuthenticatedSenderWithPaymentRequestIdMemo, SenderMemoCredential};
+    use mc_util_from_random::FromRandom;
+    use rand::{rngs::StdRng, RngCore, SeedableRng};
+    use tempdir::TempDir;
+
+    fn setup_test_payment_request_store(
+        logger: &Logger,
+    ) -> (Arc<Environment>, PaymentRequestStore) {
+        let db_tmp = TempDir::new("payment_request_store_db")
+            .expect("Could not make tempdir for payment request store db");
+        let db_path = db_tmp
+            .path()
+            .to_str()
+            .expect("Could not get path as string");
+
+        let env = Arc::new(
+            Environment::new()
+                .set_max_dbs(10)
+                .set_map_size(10000000)
+                .open(db_path.as_ref())
+                .unwrap(),
+        );
+
+        let payment_request_store = PaymentRequestStore::new(env.clone(), logger.clone()).unwrap();
+
+        (env, payment_request_store)
+    }
+
+    /// Create an UnspentTxOut received on a subaddress of `account_key`,
+    /// carrying a memo that references `payment_request_id`.
+    fn utxo_paying_request(
+        account_key: &AccountKey,
+        subaddress_index: u64,
+        amount: Amount,
+        payment_request_id: u64,
+        rng: &mut StdRng,
+    ) -> UnspentTxOut {
+        let sender = AccountKey::random(rng);
+        let recipient: PublicAddress = account_key.subaddress(subaddress_index);
+        let tx_out = TxOut::new_with_memo(
+            BlockVersion::MAX,
+            amount,
+            &recipient,
+            &RistrettoPrivate::from_random(rng),
+            Default::default(),
+            |memo_ctxt| {
+                Ok(AuthenticatedSenderWithPaymentRequestIdMemo::new(
+                    &SenderMemoCredential::from(&sender),
+                    recipient.view_public_key(),
+                    &CompressedRistrettoPublic::from(memo_ctxt.tx_public_key),
+                    payment_request_id,
+                )
+                .into())
+            },
+        )
+        .unwrap();
+
+        UnspentTxOut {
+            tx_out,
+            subaddress_index,
+            key_image: KeyImage::from(rng.next_u64()),
+            value: amount.value,
+            attempted_spend_height: 0,
+            attempted_spend_tombstone: 0,
+            token_id: *amount.token_id,
+        }
+    }
+
+    #[test_with_logger]
+    fn test_payment_request_lifecycle(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([1u8; 32]);
+        let (env, payment_request_store) = setup_test_payment_request_store(&logger);
+
+        let account_key = AccountKey::random(&mut rng);
+        let view_private_key = account_key.view_private_key();
+        let monitor_id1 = MonitorId::try_from(&[1u8; 32][..]).unwrap();
+        let monitor_id2 = MonitorId::try_from(&[2u8; 32][..]).unwrap();
+
+        let payment_request = PaymentRequest {
+            subaddress_index: 1,
+            value: 1000,
+            token_id: *Mob::ID,
+            memo: "invoice #1".to_owned(),
+            expiry_block: 20,
+            created_block: 10,
+            payments: vec![],
+        };
+        let key = PaymentRequestKey::new(&monitor_id1, 42);
+
+        // Insert the request. Ids are unique per monitor.
+        {
+            let mut db_txn = env.begin_rw_txn().unwrap();
+            payment_request_store
+                .insert(&mut db_txn, &key, &payment_request)
+                .unwrap();
+            assert!(matches!(
+                payment_request_store.insert(&mut db_txn, &key, &payment_request),
+                Err(Error::PaymentRequestIdExists)
+            ));
+            payment_request_store
+                .insert(
+                    &mut db_txn,
+                    &PaymentRequestKey::new(&monitor_id2, 42),
+                    &payment_request,
+                )
+                .unwrap();
+            db_txn.commit().unwrap();
+        }
+        assert_eq!(payment_request.status(10), PaymentRequestStatus::Pending);
+        assert_eq!(payment_request.status(20), PaymentRequestStatus::Expired);
+
+        // Only TxOuts matching the id, subaddress and token of the request pay it.
+        let partial_payment =
+            utxo_paying_request(&account_key, 1, Amount::new(400, Mob::ID), 42, &mut rng);
+        let non_matching_utxos = vec![
+            utxo_paying_request(&account_key, 1, Amount::new(400, Mob::ID), 43, &mut rng),
+            utxo_paying_request(&account_key, 2, Amount::new(400, Mob::ID), 42, &mut rng),
+            utxo_paying_request(&account_key, 1, Amount::new(400, 1.into()), 42, &mut rng),
+        ];
+        {
+            let mut db_txn = env.begin_rw_txn().unwrap();
+            let mut utxos = non_matching_utxos.clone();
+            utxos.push(partial_payment.clone());
+            payment_request_store
+                .block_processed(&mut db_txn, &monitor_id1, view_private_key, 12, &utxos)
+                .unwrap();
+            // Processing the same TxOuts again, e.g. during a rescan, records nothing new.
+            payment_request_store
+                .block_processed(&mut db_txn, &monitor_id1, view_private_key, 12, &utxos)
+                .unwrap();
+            db_txn.commit().unwrap();
+        }
+        {
+            let db_txn = env.begin_ro_txn().unwrap();
+            let stored = payment_request_store.get(&db_txn, &key).unwrap();
+            assert_eq!(
+                stored.payments,
+                vec![PaymentRequestPayment {
+                    block_index: 12,
+                    tx_out_public_key: partial_payment.tx_out.public_key,
+                    value: 400,
+                }]
+            );
+            assert_eq!(stored.status(13), PaymentRequestStatus::PartiallyPaid);
+            assert_eq!(stored.status(20), PaymentRequestStatus::Expired);
+
+            // The other monitor's request is untouched.
+            let other = payment_request_store
+                .get(&db_txn, &PaymentRequestKey::new(&monitor_id2, 42))
+                .unwrap();
+            assert_eq!(other, payment_request);
+        }
+
+        // Payments after the expiry block are ignored, the ones before complete the
+        // request.
+        {
+            let mut db_txn = env.begin_rw_txn().unwrap();
+            let late_payment =
+                utxo_paying_request(&account_key, 1, Amount::new(600, Mob::ID), 42, &mut rng);
+            payment_request_store
+                .block_processed(
+                    &mut db_txn,
+                    &monitor_id1,
+                    view_private_key,
+                    20,
+                    &[late_payment],
+                )
+                .unwrap();
+            assert_eq!(
+                payment_request_store.get(&db_txn, &key).unwrap().status(21),
+                PaymentRequestStatus::Expired
+            );
+
+            let final_payment =
+                utxo_paying_request(&account_key, 1, Amount::new(600, Mob::ID), 42, &mut rng);
+            payment_request_store
+                .block_processed(
+                    &mut db_txn,
+                    &monitor_id1,
+                    view_private_key,
+                    19,
+                    &[final_payment],
+                )
+                .unwrap();
+            let stored = payment_request_store.get(&db_txn, &key).unwrap();
+            assert_eq!(stored.paid_value(), 1000);
+            assert_eq!(stored.status(21), PaymentRequestStatus::Paid);
+            db_txn.commit().unwrap();
+        }
+
+        // Listing and removal are per monitor.
+        {
+            let mut db_txn = env.begin_rw_txn().unwrap();
+            assert_eq!(
+                payment_request_store
+                    .list(&db_txn, &monitor_id1)
+                    .unwrap()
+                    .into_iter()
+                    .map(|(key, _)| key)
+                    .collect::<Vec<_>>(),
+                vec![key]
+            );
+            payment_request_store
+                .remove(&mut db_txn, &monitor_id1)
+                .unwrap();
+            assert!(payment_request_store
+                .list(&db_txn, &monitor_id1)
+                .unwrap()
+                .is_empty());
+            assert!(matches!(
+                payment_request_store.get(&db_txn, &key),
+                Err(Error::PaymentRequestNotFound)
+            ));
+            assert_eq!(
+                payment_request_store
+                    .list(&db_txn, &monitor_id2)
+                    .unwrap()
+                    .len(),
+                1
+            );
+        }
+    }
+}
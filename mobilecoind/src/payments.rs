@@ -2,8 +2,11 @@
 
 //! Construct and submit transactions to the validator network.
 
-use crate::{database::Database, error::Error, monitor_store::MonitorId, utxo_store::UnspentTxOut};
-use mc_account_keys::{AccountKey, PublicAddress};
+use crate::{
+    database::Database, error::Error, gift_codes, monitor_store::MonitorId, swaps,
+    utxo_store::UnspentTxOut,
+};
+use mc_account_keys::{AccountKey, PublicAddress, ViewAccountKey, GIFT_CODE_SUBADDRESS_INDEX};
 use mc_blockchain_types::{BlockIndex, BlockVersion};
 use mc_common::{
     logger::{log, o, Logger},
@@ -14,12 +17,13 @@ use mc_connection::{
 };
 use mc_crypto_keys::RistrettoPublic;
 use mc_crypto_rand::{CryptoRng, RngCore};
-use mc_crypto_ring_signature_signer::NoKeysRingSigner;
+use mc_crypto_ring_signature_signer::{LocalRingSigner, NoKeysRingSigner, OneTimeKeyDeriveData};
 use mc_fog_report_validation::FogPubkeyResolver;
 use mc_ledger_db::{Error as LedgerError, Ledger, LedgerDB};
 use mc_transaction_builder::{
-    EmptyMemoBuilder, InputCredentials, MemoBuilder, ReservedSubaddresses, TransactionBuilder,
-    TxOutContext,
+    DefaultTxOutputsOrdering, EmptyMemoBuilder, GiftCodeCancellationMemoBuilder,
+    GiftCodeFundingMemoBuilder, GiftCodeSenderMemoBuilder, InputCredentials, MemoBuilder,
+    ReservedSubaddresses, SignedContingentInputBuilder, TransactionBuilder, TxOutContext,
 };
 use mc_transaction_core::{
     constants::{MAX_INPUTS, MILLIMOB_TO_PICOMOB, RING_SIZE},
@@ -28,7 +32,9 @@ use mc_transaction_core::{
     tx::{Tx, TxOut, TxOutMembershipProof},
     Amount, FeeMap, TokenId,
 };
-use mc_transaction_extra::TxOutConfirmationNumber;
+use mc_transaction_extra::{
+    SignedContingentInput, TxOutConfirmationNumber, TxOutGiftCode, UnsignedTx,
+};
 use mc_util_uri::FogUri;
 use rand::Rng;
 use std::{
@@ -90,6 +96,97 @@ impl TxProposal {
     }
 }
 
+/// A pending transaction generated by a view-only monitor, which has not been
+/// signed yet.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UnsignedTxProposal {
+    /// UTXOs used as inputs for this transaction.
+    pub utxos: Vec<UnspentTxOut>,
+
+    /// Destinations the transaction is being sent to.
+    pub outlays: Vec<Outlay>,
+
+    /// The transaction, together with the data needed to sign it.
+    pub unsigned_tx: UnsignedTx,
+
+    /// A map of outlay index -> TxOut index in the Tx object.
+    /// This is needed to map recipients to their respective TxOuts.
+    pub outlay_index_to_tx_out_index: HashMap<usize, usize>,
+
+    /// A list of the confirmation numbers, in the same order
+    /// as the outlays.
+    pub outlay_confirmation_numbers: Vec<TxOutConfirmationNumber>,
+}
+
+impl UnsignedTxProposal {
+    pub fn fee(&self) -> u64 {
+        self.unsigned_tx.tx_prefix.fee
+    }
+
+    /// Sign the transaction, producing a TxProposal that can be submitted.
+    /// This is meant to be done offline by the holder of the account key of
+    /// the view-only monitor that generated the proposal. The key images of
+    /// the inputs are filled in, so that they can be imported into the monitor.
+    pub fn sign(
+        &self,
+        account_key: &AccountKey,
+        rng: &mut (impl RngCore + CryptoRng),
+    ) -> Result<TxProposal, Error> {
+        // The fee map digest is left empty, which consensus accepts.
+        let tx = self
+            .unsigned_tx
+            .sign(&LocalRingSigner::from(account_key), None, rng)
+            .map_err(|err| Error::TxBuild(format!("sign tx failed: {}", err)))?;
+
+        let utxos = self
+            .utxos
+            .iter()
+            .map(|utxo| {
+                let tx_public_key = RistrettoPublic::try_from(&utxo.tx_out.public_key)?;
+                let onetime_private_key = recover_onetime_private_key(
+                    &tx_public_key,
+                    account_key.view_private_key(),
+                    &account_key.subaddress_spend_private(utxo.subaddress_index),
+                );
+                Ok(UnspentTxOut {
+                    key_image: KeyImage::from(&onetime_private_key),
+                    ..utxo.clone()
+                })
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        Ok(TxProposal {
+            utxos,
+            outlays: self.outlays.clone(),
+            tx,
+            outlay_index_to_tx_out_index: self.outlay_index_to_tx_out_index.clone(),
+            outlay_confirmation_numbers: self.outlay_confirmation_numbers.clone(),
+        })
+    }
+}
+
+/// The UTXOs selected to be spent by a transaction, together with everything
+/// else needed to build it.
+struct SelectedInputs {
+    /// The selected UTXOs with their proofs of membership.
+    utxos_with_proofs: Vec<(UnspentTxOut, TxOutMembershipProof)>,
+
+    /// A ring of mixins for each UTXO.
+    rings: Vec<Vec<(TxOut, TxOutMembershipProof)>>,
+
+    /// The block version to target.
+    block_version: BlockVersion,
+
+    /// The transaction fee.
+    fee: u64,
+
+    /// The minimum fee map of the network.
+    fee_map: FeeMap,
+
+    /// The tombstone block of the transaction.
+    tombstone_block: BlockIndex,
+}
+
 pub struct TransactionsManager<
     T: BlockchainConnection + UserTxConnection + 'static,
     FPR: FogPubkeyResolver,
@@ -250,6 +347,138 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
         let logger = self.logger.new(o!("sender_monitor_id" => sender_monitor_id.to_string(), "outlays" => format!("{:?}", outlays)));
         log::trace!(logger, "Building pending transaction...");
 
+        // Get sender monitor data.
+        let sender_monitor_data = self.mobilecoind_db.get_monitor_data(sender_monitor_id)?;
+        let account_key = sender_monitor_data.get_account_key()?;
+
+        let selected_inputs = self.select_inputs(
+            token_id,
+            inputs,
+            outlays,
+            last_block_infos,
+            opt_fee,
+            opt_tombstone,
+            &logger,
+        )?;
+
+        // Build and return the TxProposal object
+        let mut rng = rand::thread_rng();
+        let tx_proposal = Self::build_tx_proposal(
+            &selected_inputs.utxos_with_proofs,
+            selected_inputs.rings,
+            selected_inputs.block_version,
+            token_id,
+            selected_inputs.fee,
+            account_key,
+            change_subaddress,
+            outlays,
+            selected_inputs.tombstone_block,
+            &self.fog_resolver_factory,
+            opt_memo_builder,
+            selected_inputs.fee_map,
+            &mut rng,
+            &self.logger,
+        )?;
+        log::trace!(logger, "Tx constructed, hash={}", tx_proposal.tx.tx_hash());
+
+        Ok(tx_proposal)
+    }
+
+    /// Create an UnsignedTxProposal for a view-only monitor. The transaction
+    /// has to be signed by the holder of the spend private key of the monitor
+    /// before it can be submitted.
+    ///
+    /// # Arguments
+    /// * `sender_monitor_id` - The view-only monitor that owns the txo's.
+    /// * `token_id` - The token id to transact in.
+    /// * `change_subaddress` - Recipient of any change.
+    /// * `inputs` - UTXOs that will be spent by the transaction.
+    /// * `outlays` - Output amounts and recipients.
+    /// * `last_block_infos` - Last block info responses from the network, for
+    ///   determining fees. This should normally come from polling_network_state
+    /// * `opt_fee` - Transaction fee in picoMOB. If zero, defaults to MIN_FEE.
+    /// * `opt_tombstone` - Tombstone block. If zero, sets to default.
+    pub fn build_unsigned_transaction(
+        &self,
+        sender_monitor_id: &MonitorId,
+        token_id: TokenId,
+        change_subaddress: u64,
+        inputs: &[UnspentTxOut],
+        outlays: &[Outlay],
+        last_block_infos: &[BlockInfo],
+        opt_fee: u64,
+        opt_tombstone: u64,
+    ) -> Result<UnsignedTxProposal, Error> {
+        let logger = self.logger.new(o!("sender_monitor_id" => sender_monitor_id.to_string(), "outlays" => format!("{:?}", outlays)));
+        log::trace!(logger, "Building unsigned transaction...");
+
+        // Get sender monitor data.
+        let sender_monitor_data = self.mobilecoind_db.get_monitor_data(sender_monitor_id)?;
+        let view_account_key = sender_monitor_data
+            .view_account_key
+            .as_ref()
+            .ok_or_else(|| {
+                Error::InvalidArgument(
+                    "sender_monitor_id".to_string(),
+                    "Not a view-only monitor".to_string(),
+                )
+            })?;
+
+        let selected_inputs = self.select_inputs(
+            token_id,
+            inputs,
+            outlays,
+            last_block_infos,
+            opt_fee,
+            opt_tombstone,
+            &logger,
+        )?;
+
+        // Build and return the UnsignedTxProposal object
+        let mut rng = rand::thread_rng();
+        let unsigned_tx_proposal = Self::build_unsigned_tx_proposal(
+            &selected_inputs.utxos_with_proofs,
+            selected_inputs.rings,
+            selected_inputs.block_version,
+            token_id,
+            selected_inputs.fee,
+            view_account_key,
+            change_subaddress,
+            outlays,
+            selected_inputs.tombstone_block,
+            &self.fog_resolver_factory,
+            selected_inputs.fee_map,
+            &mut rng,
+            &self.logger,
+        )?;
+        log::trace!(logger, "Unsigned tx constructed");
+
+        Ok(unsigned_tx_proposal)
+    }
+
+    /// Select the UTXOs to spend in order to pay for the given outlays, and
+    /// get everything else needed to build a transaction spending them.
+    ///
+    /// # Arguments
+    /// * `token_id` - The token id to transact in.
+    /// * `inputs` - UTXOs that may be spent by the transaction.
+    /// * `outlays` - Output amounts and recipients.
+    /// * `last_block_infos` - Last block info responses from the network, for
+    ///   determining fees.
+    /// * `opt_fee` - Transaction fee in picoMOB. If zero, defaults to MIN_FEE.
+    /// * `opt_tombstone` - Tombstone block. If zero, sets to default.
+    /// * `logger` - Logger
+    #[allow(clippy::too_many_arguments)]
+    fn select_inputs(
+        &self,
+        token_id: TokenId,
+        inputs: &[UnspentTxOut],
+        outlays: &[Outlay],
+        last_block_infos: &[BlockInfo],
+        opt_fee: u64,
+        opt_tombstone: u64,
+        logger: &Logger,
+    ) -> Result<SelectedInputs, Error> {
         // All inputs must be of the correct token id.
         if inputs.iter().any(|utxo| utxo.token_id != *token_id) {
             return Err(Error::InvalidArgument(
@@ -263,9 +492,6 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
             return Err(Error::TxBuild("Must have at least one destination".into()));
         }
 
-        // Get sender monitor data.
-        let sender_monitor_data = self.mobilecoind_db.get_monitor_data(sender_monitor_id)?;
-
         // Figure out total amount of transaction (excluding fee).
         let total_value: u64 = outlays.iter().map(|outlay| outlay.value).sum();
         log::trace!(
@@ -324,27 +550,14 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
         };
         log::trace!(logger, "Tombstone block set to {}", tombstone_block);
 
-        // Build and return the TxProposal object
-        let mut rng = rand::thread_rng();
-        let tx_proposal = Self::build_tx_proposal(
-            &selected_utxos_with_proofs,
+        Ok(SelectedInputs {
+            utxos_with_proofs: selected_utxos_with_proofs,
             rings,
             block_version,
-            token_id,
             fee,
-            &sender_monitor_data.account_key,
-            change_subaddress,
-            outlays,
-            tombstone_block,
-            &self.fog_resolver_factory,
-            opt_memo_builder,
             fee_map,
-            &mut rng,
-            &self.logger,
-        )?;
-        log::trace!(logger, "Tx constructed, hash={}", tx_proposal.tx.tx_hash());
-
-        Ok(tx_proposal)
+            tombstone_block,
+        })
     }
 
     /// Create a TxProposal that attempts to merge multiple UTXOs into a single
@@ -373,6 +586,7 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
 
         // Get monitor data.
         let monitor_data = self.mobilecoind_db.get_monitor_data(monitor_id)?;
+        let account_key = monitor_data.get_account_key()?;
 
         let num_blocks_in_ledger = self.ledger_db.num_blocks()?;
 
@@ -442,7 +656,7 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
 
         // We are paying ourselves the entire amount.
         let outlays = vec![Outlay {
-            receiver: monitor_data.account_key.subaddress(subaddress_index),
+            receiver: account_key.subaddress(subaddress_index),
             value: total_value - fee,
         }];
 
@@ -454,7 +668,7 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
             block_version,
             token_id,
             fee,
-            &monitor_data.account_key,
+            account_key,
             subaddress_index,
             &outlays,
             tombstone_block,
@@ -576,90 +790,1095 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
         Ok(tx_proposal)
     }
 
-    /// Submit a previously built tx proposal to the network.
-    pub fn submit_tx_proposal(&self, tx_proposal: &TxProposal) -> Result<u64, Error> {
-        // Pick a peer to submit to.
-        let responder_ids = self.peer_manager.responder_ids();
-        if responder_ids.is_empty() {
-            return Err(Error::NoPeersConfigured);
-        }
+    /// Create a TxProposal that cancels a pending TxProposal by spending one of
+    /// its inputs back to ourselves with a higher fee. Since the two
+    /// transactions share a key image, at most one of them can land in the
+    /// ledger.
+    ///
+    /// # Arguments
+    /// * `sender_monitor_id` - Monitor ID of the pending transaction's inputs.
+    /// * `tx_proposal` - The pending transaction to replace.
+    /// * `change_subaddress` - Subaddress that receives the replacement's
+    ///   output.
+    /// * `last_block_infos` - Last block info responses from the network, for
+    ///   determining fees. This should normally come from polling_network_state
+    /// * `opt_fee` - Transaction fee. Must be higher than the fee of the
+    ///   pending transaction. If zero, defaults to twice the pending fee or the
+    ///   network fee, whichever is higher.
+    /// * `opt_tombstone` - Tombstone block. If zero, sets to default.
+    pub fn generate_replacement_tx(
+        &self,
+        sender_monitor_id: &MonitorId,
+        tx_proposal: &TxProposal,
+        change_subaddress: u64,
+        last_block_infos: &[BlockInfo],
+        opt_fee: u64,
+        opt_tombstone: u64,
+    ) -> Result<TxProposal, Error> {
+        let logger = self.logger.new(o!("sender_monitor_id" => sender_monitor_id.to_string(), "replaced_tx" => tx_proposal.tx.tx_hash().to_string()));
+        log::trace!(logger, "Generating replacement transaction...");
 
-        let idx = self.submit_node_offset.fetch_add(1, Ordering::SeqCst);
-        let responder_id = &responder_ids[idx % responder_ids.len()];
+        let token_id = TokenId::from(tx_proposal.tx.prefix.fee_token_id);
+        let replaced_fee = tx_proposal.fee();
 
-        // Try and submit.
-        let block_height = self
-            .peer_manager
-            .conn(responder_id)
-            .ok_or(Error::NodeNotFound)?
-            .propose_tx(&tx_proposal.tx, empty())
-            .map_err(Error::from)?;
+        if opt_fee != 0 && opt_fee <= replaced_fee {
+            return Err(Error::InvalidArgument(
+                "fee".to_string(),
+                format!(
+                    "Replacement fee must be higher than the replaced fee {}",
+                    replaced_fee
+                ),
+            ));
+        }
 
-        log::info!(
-            self.logger,
-            "Tx {} submitted at block height {}",
-            tx_proposal.tx,
-            block_height
-        );
+        // Get sender monitor data.
+        let sender_monitor_data = self.mobilecoind_db.get_monitor_data(sender_monitor_id)?;
+        let account_key = sender_monitor_data.get_account_key()?;
 
-        // Successfully submitted.
-        Ok(block_height)
-    }
+        // Figure out the block version, fee and minimum fee map.
+        let (fee, fee_map, block_version) =
+            self.get_fee_info_and_block_version(last_block_infos, token_id, opt_fee)?;
+        let fee = if opt_fee != 0 {
+            fee
+        } else {
+            max(fee, replaced_fee.saturating_mul(2))
+        };
 
-    /// Returns a subset of UTXOs totalling at least the given amount.
-    // TODO: This method should take attempted_spend_height into account.
-    fn select_utxos_for_value(
-        token_id: TokenId,
-        utxos: &[UnspentTxOut],
-        value: u64,
-        max_inputs: usize,
-    ) -> Result<Vec<UnspentTxOut>, Error> {
-        // Sort the utxos in descending order by value.
-        let mut sorted_utxos: Vec<UnspentTxOut> = utxos
+        // Spending a single input of the pending transaction is enough to make the
+        // two transactions conflict. Pick the largest one so that it can cover the
+        // fee.
+        let selected_utxo = tx_proposal
+            .utxos
             .iter()
-            .filter(|utxo| utxo.token_id == token_id)
+            .filter(|utxo| utxo.token_id == *token_id)
+            .max_by_key(|utxo| utxo.value)
             .cloned()
-            .collect();
-        sorted_utxos.sort_by_key(|utxo| Reverse(utxo.value));
-
-        // The maximum spendable is limited by the maximal number of inputs we can use.
-        let max_spendable_amount = sorted_utxos
-            .iter()
-            .take(max_inputs)
-            .map(|utxo| utxo.value)
-            .sum();
-        if value > max_spendable_amount {
-            // See if we merged the UTXOs we would be able to spend this amount.
-            let total_utxos_value: u64 = sorted_utxos.iter().map(|utxo| utxo.value).sum();
-            if total_utxos_value >= value {
-                return Err(Error::InsufficientFundsFragmentedUtxos);
-            } else {
-                return Err(Error::InsufficientFunds);
-            }
+            .ok_or_else(|| {
+                Error::InvalidArgument(
+                    "tx_proposal".to_string(),
+                    format!("No inputs of token_id {}", token_id),
+                )
+            })?;
+        if selected_utxo.value <= fee {
+            return Err(Error::InsufficientFunds);
         }
+        log::trace!(logger, "Selected utxo {:?}", selected_utxo);
 
-        // Choose utxos to spend.
-        let mut selected_utxos: Vec<UnspentTxOut> = Vec::new();
-        loop {
-            let total: u64 = selected_utxos.iter().map(|utxo| utxo.value).sum();
-            if total >= value {
-                break;
-            }
+        // The selected utxo with its proof of membership.
+        let selected_utxos_with_proofs: Vec<(UnspentTxOut, TxOutMembershipProof)> = {
+            let proofs = self.get_membership_proofs(&[selected_utxo.tx_out.clone()])?;
+            vec![selected_utxo]
+                .into_iter()
+                .zip(proofs.into_iter())
+                .collect()
+        };
+        log::trace!(logger, "Got membership proofs");
 
-            // Grab the next (smallest utxo)
-            let next_utxo = sorted_utxos.pop().ok_or(Error::InsufficientFunds)?;
-            selected_utxos.push(next_utxo.clone());
+        // A ring of mixins for the selected UTXO.
+        let rings = {
+            let excluded_tx_out_indices: Vec<u64> = selected_utxos_with_proofs
+                .iter()
+                .map(|(_, proof)| proof.index)
+                .collect();
 
-            // Cap at maximum allowed inputs.
-            if selected_utxos.len() > max_inputs {
-                // Remove the lowest utxo.
-                selected_utxos.remove(0);
-            }
-        }
+            self.get_rings(
+                DEFAULT_RING_SIZE, // TODO configurable ring size
+                selected_utxos_with_proofs.len(),
+                &excluded_tx_out_indices,
+            )?
+        };
+        log::trace!(logger, "Got {} rings", rings.len());
 
-        // Sanity.
-        assert!(!selected_utxos.is_empty());
-        assert!(selected_utxos.len() <= max_inputs);
+        // Come up with tombstone block.
+        let tombstone_block = if opt_tombstone > 0 {
+            opt_tombstone
+        } else {
+            let num_blocks_in_ledger = self.ledger_db.num_blocks()?;
+            num_blocks_in_ledger + DEFAULT_NEW_TX_BLOCK_ATTEMPTS
+        };
+        log::trace!(logger, "Tombstone block set to {}", tombstone_block);
+
+        // We are paying ourselves the entire amount.
+        let total_value: u64 = selected_utxos_with_proofs
+            .iter()
+            .map(|(utxo, _proof)| utxo.value)
+            .sum();
+        let outlays = vec![Outlay {
+            receiver: account_key.subaddress(change_subaddress),
+            value: total_value - fee,
+        }];
+
+        // Build and return the TxProposal object
+        let mut rng = rand::thread_rng();
+        let replacement_tx_proposal = Self::build_tx_proposal(
+            &selected_utxos_with_proofs,
+            rings,
+            block_version,
+            token_id,
+            fee,
+            account_key,
+            change_subaddress,
+            &outlays,
+            tombstone_block,
+            &self.fog_resolver_factory,
+            None,
+            fee_map,
+            &mut rng,
+            &self.logger,
+        )?;
+        log::trace!(
+            logger,
+            "Replacement tx constructed, hash={}",
+            replacement_tx_proposal.tx.tx_hash()
+        );
+
+        Ok(replacement_tx_proposal)
+    }
+
+    /// Create a signed contingent input which offers to swap a UTXO, or part
+    /// of it, for another token. This is a limit order which a counterparty
+    /// can fill using `generate_swap_fill_tx`.
+    ///
+    /// # Arguments
+    /// * `sender_monitor_id` - Monitor ID of the UTXO to offer.
+    /// * `change_subaddress` - Subaddress that receives the counter tokens and
+    ///   any change.
+    /// * `utxo` - The UTXO to offer.
+    /// * `base_value` - Value of the UTXO to offer. If zero, the whole UTXO is
+    ///   offered.
+    /// * `counter_amount` - Amount asked for in exchange, when the offer is
+    ///   filled completely.
+    /// * `allow_partial_fill` - Whether the offer may be filled partially.
+    /// * `min_fill_value` - The minimum value of the UTXO a partial fill must
+    ///   take.
+    /// * `last_block_infos` - Last block info responses from the network, for
+    ///   determining the block version. This should normally come from
+    ///   polling_network_state
+    /// * `opt_tombstone` - Max tombstone block of a transaction filling the
+    ///   offer. If zero, no limit is imposed.
+    #[allow(clippy::too_many_arguments)]
+    pub fn generate_swap(
+        &self,
+        sender_monitor_id: &MonitorId,
+        change_subaddress: u64,
+        utxo: &UnspentTxOut,
+        base_value: u64,
+        counter_amount: Amount,
+        allow_partial_fill: bool,
+        min_fill_value: u64,
+        last_block_infos: &[BlockInfo],
+        opt_tombstone: u64,
+    ) -> Result<SignedContingentInput, Error> {
+        let logger = self.logger.new(o!("sender_monitor_id" => sender_monitor_id.to_string(), "counter_amount" => format!("{:?}", counter_amount)));
+        log::trace!(logger, "Generating swap offer...");
+
+        let base_token_id = TokenId::from(utxo.token_id);
+        if counter_amount.token_id == base_token_id {
+            return Err(Error::InvalidArgument(
+                "counter_token_id".to_string(),
+                format!(
+                    "Must differ from the token id of the utxo {}",
+                    base_token_id
+                ),
+            ));
+        }
+        if counter_amount.value == 0 {
+            return Err(Error::InvalidArgument(
+                "counter_value".to_string(),
+                "Must be nonzero".to_string(),
+            ));
+        }
+
+        let base_value = if base_value == 0 {
+            utxo.value
+        } else {
+            base_value
+        };
+        if base_value > utxo.value {
+            return Err(Error::InsufficientFunds);
+        }
+        if min_fill_value > base_value || (!allow_partial_fill && min_fill_value != 0) {
+            return Err(Error::InvalidArgument(
+                "min_fill_value".to_string(),
+                "Must be zero unless partial fills are allowed, and at most the offered value"
+                    .to_string(),
+            ));
+        }
+
+        // Get sender monitor data.
+        let sender_monitor_data = self.mobilecoind_db.get_monitor_data(sender_monitor_id)?;
+        let account_key = sender_monitor_data.get_account_key()?;
+
+        // The offer is signed using the block version rules of the network.
+        let (_fee, _fee_map, block_version) =
+            self.get_fee_info_and_block_version(last_block_infos, base_token_id, 0)?;
+
+        // The UTXO with its proof of membership, and a ring of mixins for it.
+        let proof = self
+            .get_membership_proofs(&[utxo.tx_out.clone()])?
+            .pop()
+            .ok_or_else(|| Error::TxBuild("Missing membership proof".into()))?;
+        let (ring, membership_proofs): (Vec<TxOut>, Vec<TxOutMembershipProof>) = self
+            .get_rings(DEFAULT_RING_SIZE, 1, &[proof.index])?
+            .pop()
+            .ok_or_else(|| Error::TxBuild("Missing ring".into()))?
+            .into_iter()
+            .unzip();
+        let input_credentials = Self::get_input_credentials(
+            utxo,
+            &proof,
+            ring,
+            membership_proofs,
+            account_key,
+            &logger,
+        )?;
+
+        let receiver = account_key.subaddress(change_subaddress);
+        let change_dest =
+            ReservedSubaddresses::from_subaddress_index(account_key, Some(change_subaddress), None);
+        let fog_resolver = {
+            let fog_uris = extract_fog_uri(&receiver)?.into_iter().collect::<Vec<_>>();
+            (self.fog_resolver_factory)(&fog_uris).map_err(Error::Fog)?
+        };
+
+        let mut rng = rand::thread_rng();
+        let mut sci_builder = SignedContingentInputBuilder::new(
+            block_version,
+            input_credentials,
+            fog_resolver,
+            EmptyMemoBuilder::default(),
+        )
+        .map_err(|err| {
+            Error::TxBuild(format!(
+                "Error creating signed contingent input builder: {}",
+                err
+            ))
+        })?;
+
+        if allow_partial_fill {
+            sci_builder
+                .add_partial_fill_output(counter_amount, &receiver, &mut rng)
+                .map_err(|err| Error::TxBuild(format!("failed adding output: {}", err)))?;
+            sci_builder
+                .add_partial_fill_change_output(
+                    Amount::new(base_value, base_token_id),
+                    &change_dest,
+                    &mut rng,
+                )
+                .map_err(|err| Error::TxBuild(format!("failed adding output (change): {}", err)))?;
+            sci_builder.set_min_partial_fill_value(min_fill_value);
+        } else {
+            sci_builder
+                .add_required_output(counter_amount, &receiver, &mut rng)
+                .map_err(|err| Error::TxBuild(format!("failed adding output: {}", err)))?;
+        }
+
+        // Whatever is not offered is returned to us as change.
+        if utxo.value > base_value {
+            sci_builder
+                .add_required_change_output(
+                    Amount::new(utxo.value - base_value, base_token_id),
+                    &change_dest,
+                    &mut rng,
+                )
+                .map_err(|err| Error::TxBuild(format!("failed adding output (change): {}", err)))?;
+        }
+
+        if opt_tombstone > 0 {
+            sci_builder.set_tombstone_block(opt_tombstone);
+        }
+
+        let sci = sci_builder
+            .build(&NoKeysRingSigner {}, &mut rng)
+            .map_err(|err| {
+                Error::TxBuild(format!("build signed contingent input failed: {}", err))
+            })?;
+        log::trace!(
+            logger,
+            "Swap offer constructed, key image={:?}",
+            sci.key_image()
+        );
+
+        Ok(sci)
+    }
+
+    /// Create a TxProposal that fills swap offers made by signed contingent
+    /// inputs, paying for them and the fee in the counter token.
+    ///
+    /// # Arguments
+    /// * `sender_monitor_id` - Monitor ID of the inputs to pay with.
+    /// * `change_subaddress` - Subaddress that receives the swapped tokens and
+    ///   any change.
+    /// * `fills` - The offers to fill, together with the base value to take
+    ///   from each. All offers must be for the same token pair.
+    /// * `inputs` - UTXOs that may be spent to pay for the offers.
+    /// * `last_block_infos` - Last block info responses from the network, for
+    ///   determining fees. This should normally come from polling_network_state
+    /// * `opt_fee` - Transaction fee in the counter token. If zero, defaults to
+    ///   the network fee.
+    /// * `opt_tombstone` - Tombstone block. If zero, sets to default.
+    #[allow(clippy::too_many_arguments)]
+    pub fn generate_swap_fill_tx(
+        &self,
+        sender_monitor_id: &MonitorId,
+        change_subaddress: u64,
+        fills: &[(SignedContingentInput, u64)],
+        inputs: &[UnspentTxOut],
+        last_block_infos: &[BlockInfo],
+        opt_fee: u64,
+        opt_tombstone: u64,
+    ) -> Result<TxProposal, Error> {
+        let logger = self.logger.new(
+            o!("sender_monitor_id" => sender_monitor_id.to_string(), "num_fills" => fills.len()),
+        );
+        log::trace!(logger, "Building swap fill transaction...");
+
+        if fills.is_empty() {
+            return Err(Error::TxBuild("Must fill at least one offer".into()));
+        }
+        if fills.len() >= MAX_INPUTS as usize {
+            return Err(Error::InvalidArgument(
+                "fills".to_string(),
+                format!("Can fill at most {} offers", MAX_INPUTS - 1),
+            ));
+        }
+
+        // Validate the offers against the ledger, and figure out what they cost.
+        let quotes = fills
+            .iter()
+            .map(|(sci, base_fill_value)| -> Result<_, Error> {
+                let quote = swaps::validate_sci(sci, &self.ledger_db)?;
+                let counter_value = quote.counter_value_for_fill(*base_fill_value)?;
+                Ok((quote, counter_value))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let base_token_id = quotes[0].0.base_token_id;
+        let counter_token_id = quotes[0].0.counter_token_id;
+        if quotes.iter().any(|(quote, _)| {
+            quote.base_token_id != base_token_id || quote.counter_token_id != counter_token_id
+        }) {
+            return Err(Error::InvalidArgument(
+                "fills".to_string(),
+                "All offers must be for the same token pair".to_string(),
+            ));
+        }
+
+        let key_images: HashSet<KeyImage> = fills.iter().map(|(sci, _)| sci.key_image()).collect();
+        if key_images.len() != fills.len() {
+            return Err(Error::InvalidArgument(
+                "fills".to_string(),
+                "Each offer can only be filled once".to_string(),
+            ));
+        }
+
+        // Get sender monitor data.
+        let sender_monitor_data = self.mobilecoind_db.get_monitor_data(sender_monitor_id)?;
+        let account_key = sender_monitor_data.get_account_key()?;
+
+        // Figure out the block version, fee and minimum fee map. The offers must have
+        // been signed using the block version rules of the network.
+        let (fee, fee_map, block_version) =
+            self.get_fee_info_and_block_version(last_block_infos, counter_token_id, opt_fee)?;
+        if let Some((sci, _)) = fills
+            .iter()
+            .find(|(sci, _)| sci.block_version != *block_version)
+        {
+            return Err(Error::TxBuild(format!(
+                "Offer was signed for block version {}, but the network is at block version {}",
+                sci.block_version, block_version
+            )));
+        }
+
+        // Offers are signed by untrusted parties, so their values may add up to more
+        // than fits in a u64.
+        let total_base_value = fills
+            .iter()
+            .try_fold(0u64, |total, (_, value)| total.checked_add(*value))
+            .ok_or_else(|| {
+                Error::InvalidArgument(
+                    "fills".to_string(),
+                    "Total filled value overflows".to_string(),
+                )
+            })?;
+        let total_counter_value = quotes
+            .iter()
+            .try_fold(0u64, |total, (_, value)| total.checked_add(*value))
+            .ok_or_else(|| {
+                Error::InvalidArgument(
+                    "fills".to_string(),
+                    "Total value paid for the offers overflows".to_string(),
+                )
+            })?;
+        let total_value_with_fee = total_counter_value.checked_add(fee).ok_or_else(|| {
+            Error::InvalidArgument(
+                "fills".to_string(),
+                "Total value paid for the offers and the fee overflows".to_string(),
+            )
+        })?;
+        log::trace!(
+            logger,
+            "Filling {} of token id {} for {} of token id {}",
+            total_base_value,
+            base_token_id,
+            total_counter_value,
+            counter_token_id,
+        );
+
+        // Select the UTXOs paying for the offers and the fee.
+        let selected_utxos = Self::select_utxos_for_value(
+            counter_token_id,
+            inputs,
+            total_value_with_fee,
+            MAX_INPUTS as usize - fills.len(),
+        )?;
+        log::trace!(
+            logger,
+            "Selected {} utxos ({:?})",
+            selected_utxos.len(),
+            selected_utxos,
+        );
+
+        // The selected_utxos with corresponding proofs of membership.
+        let selected_utxos_with_proofs: Vec<(UnspentTxOut, TxOutMembershipProof)> = {
+            let outputs: Vec<TxOut> = selected_utxos
+                .iter()
+                .map(|utxo| utxo.tx_out.clone())
+                .collect();
+            let proofs = self.get_membership_proofs(&outputs)?;
+
+            selected_utxos.into_iter().zip(proofs.into_iter()).collect()
+        };
+        log::trace!(logger, "Got membership proofs");
+
+        // A ring of mixins for each UTXO.
+        let rings = {
+            let excluded_tx_out_indices: Vec<u64> = selected_utxos_with_proofs
+                .iter()
+                .map(|(_, proof)| proof.index)
+                .collect();
+
+            self.get_rings(
+                DEFAULT_RING_SIZE, // TODO configurable ring size
+                selected_utxos_with_proofs.len(),
+                &excluded_tx_out_indices,
+            )?
+        };
+        log::trace!(logger, "Got {} rings", rings.len());
+
+        // Come up with tombstone block. This is further limited by the offers.
+        let tombstone_block = if opt_tombstone > 0 {
+            opt_tombstone
+        } else {
+            let num_blocks_in_ledger = self.ledger_db.num_blocks()?;
+            num_blocks_in_ledger + DEFAULT_NEW_TX_BLOCK_ATTEMPTS
+        };
+
+        let receiver = account_key.subaddress(change_subaddress);
+        let fog_resolver = {
+            let fog_uris = extract_fog_uri(&receiver)?.into_iter().collect::<Vec<_>>();
+            (self.fog_resolver_factory)(&fog_uris).map_err(Error::Fog)?
+        };
+
+        let mut tx_builder = TransactionBuilder::new(
+            block_version,
+            Amount::new(fee, counter_token_id),
+            fog_resolver,
+            EmptyMemoBuilder::default(),
+        )
+        .map_err(|err| Error::TxBuild(format!("Error creating transaction builder: {}", err)))?;
+        tx_builder.set_fee_map(fee_map);
+
+        // Add our inputs to the tx.
+        for ((utxo, proof), ring) in selected_utxos_with_proofs.iter().zip(rings.into_iter()) {
+            let (ring, membership_proofs) = ring.into_iter().unzip();
+            tx_builder.add_input(Self::get_input_credentials(
+                utxo,
+                proof,
+                ring,
+                membership_proofs,
+                account_key,
+                &logger,
+            )?);
+        }
+
+        // Add the offers, with proofs of membership against the current ledger. This
+        // also adds the outputs paying their originators.
+        for ((sci, base_fill_value), (quote, _)) in fills.iter().zip(quotes.iter()) {
+            let mut sci = sci.clone();
+            swaps::add_membership_proofs(&mut sci, &self.ledger_db)?;
+            if quote.partial_fill {
+                tx_builder.add_presigned_partial_fill_input(
+                    sci,
+                    Amount::new(quote.base_value - base_fill_value, base_token_id),
+                )?;
+            } else {
+                tx_builder.add_presigned_input(sci)?;
+            }
+        }
+
+        // Pay ourselves the swapped tokens.
+        let mut rng = rand::thread_rng();
+        let TxOutContext {
+            tx_out,
+            confirmation,
+            ..
+        } = tx_builder
+            .add_output(
+                Amount::new(total_base_value, base_token_id),
+                &receiver,
+                &mut rng,
+            )
+            .map_err(|err| Error::TxBuild(format!("failed adding output: {}", err)))?;
+
+        // If we do have nonzero change, add an output for that as well.
+        let input_value: u64 = selected_utxos_with_proofs
+            .iter()
+            .map(|(utxo, _proof)| utxo.value)
+            .sum();
+        let change = input_value - total_counter_value - tx_builder.get_fee();
+        if change > 0 {
+            let change_dest = ReservedSubaddresses::from_subaddress_index(
+                account_key,
+                Some(change_subaddress),
+                None,
+            );
+            tx_builder
+                .add_change_output(
+                    Amount::new(change, counter_token_id),
+                    &change_dest,
+                    &mut rng,
+                )
+                .map_err(|err| Error::TxBuild(format!("failed adding output (change): {}", err)))?;
+        }
+
+        let tombstone_block = tx_builder.set_tombstone_block(tombstone_block);
+        log::trace!(logger, "Tombstone block set to {}", tombstone_block);
+
+        // Build tx.
+        let tx = tx_builder
+            .build(&NoKeysRingSigner {}, &mut rng)
+            .map_err(|err| Error::TxBuild(format!("build tx failed: {}", err)))?;
+        log::trace!(logger, "Tx constructed, hash={}", tx.tx_hash());
+
+        let tx_out_index = tx
+            .prefix
+            .outputs
+            .iter()
+            .position(|output| *output == tx_out)
+            .ok_or_else(|| Error::TxBuild("Swapped output missing from tx".into()))?;
+
+        Ok(TxProposal {
+            utxos: selected_utxos_with_proofs
+                .into_iter()
+                .map(|(utxo, _proof)| utxo)
+                .collect(),
+            outlays: vec![Outlay {
+                value: total_base_value,
+                receiver,
+            }],
+            tx,
+            outlay_index_to_tx_out_index: [(0, tx_out_index)].into_iter().collect(),
+            outlay_confirmation_numbers: vec![confirmation],
+        })
+    }
+
+    /// Create a TxProposal that funds a gift code, by sending an amount to the
+    /// reserved gift code subaddress of the sender. A gift code funding memo
+    /// is written to the change output, so a change output is always added.
+    /// Once the transaction lands, the gift code can be created from the
+    /// gift code TxOut using `gift_codes::create_gift_code`.
+    ///
+    /// # Arguments
+    /// * `sender_monitor_id` - Monitor ID of the inputs to spend.
+    /// * `change_subaddress` - Recipient of any change.
+    /// * `inputs` - UTXOs that may be spent by the transaction.
+    /// * `amount` - The amount of the gift code.
+    /// * `note` - Note recorded in the gift code funding memo.
+    /// * `last_block_infos` - Last block info responses from the network, for
+    ///   determining fees. This should normally come from polling_network_state
+    /// * `opt_fee` - Transaction fee. If zero, defaults to the network fee.
+    /// * `opt_tombstone` - Tombstone block. If zero, sets to default.
+    #[allow(clippy::too_many_arguments)]
+    pub fn generate_gift_code_funding_tx(
+        &self,
+        sender_monitor_id: &MonitorId,
+        change_subaddress: u64,
+        inputs: &[UnspentTxOut],
+        amount: Amount,
+        note: &str,
+        last_block_infos: &[BlockInfo],
+        opt_fee: u64,
+        opt_tombstone: u64,
+    ) -> Result<TxProposal, Error> {
+        let logger = self.logger.new(o!("sender_monitor_id" => sender_monitor_id.to_string(), "amount" => format!("{:?}", amount)));
+        log::trace!(logger, "Generating gift code funding transaction...");
+
+        if amount.value == 0 {
+            return Err(Error::InvalidArgument(
+                "value".to_string(),
+                "Must be nonzero".to_string(),
+            ));
+        }
+        let memo_builder = GiftCodeFundingMemoBuilder::new(note)
+            .map_err(|err| Error::InvalidArgument("memo".to_string(), err.to_string()))?;
+
+        // Get sender monitor data.
+        let sender_monitor_data = self.mobilecoind_db.get_monitor_data(sender_monitor_id)?;
+        let account_key = sender_monitor_data.get_account_key()?;
+
+        // Figure out the block version, fee and minimum fee map.
+        let (fee, fee_map, block_version) =
+            self.get_fee_info_and_block_version(last_block_infos, amount.token_id, opt_fee)?;
+
+        // Select the UTXOs to be used for this transaction.
+        let selected_utxos = Self::select_utxos_for_value(
+            amount.token_id,
+            inputs,
+            amount.value + fee,
+            MAX_INPUTS as usize,
+        )?;
+        log::trace!(
+            logger,
+            "Selected {} utxos ({:?})",
+            selected_utxos.len(),
+            selected_utxos,
+        );
+
+        // The selected_utxos with corresponding proofs of membership.
+        let selected_utxos_with_proofs: Vec<(UnspentTxOut, TxOutMembershipProof)> = {
+            let outputs: Vec<TxOut> = selected_utxos
+                .iter()
+                .map(|utxo| utxo.tx_out.clone())
+                .collect();
+            let proofs = self.get_membership_proofs(&outputs)?;
+
+            selected_utxos.into_iter().zip(proofs.into_iter()).collect()
+        };
+        log::trace!(logger, "Got membership proofs");
+
+        // A ring of mixins for each UTXO.
+        let rings = {
+            let excluded_tx_out_indices: Vec<u64> = selected_utxos_with_proofs
+                .iter()
+                .map(|(_, proof)| proof.index)
+                .collect();
+
+            self.get_rings(
+                DEFAULT_RING_SIZE, // TODO configurable ring size
+                selected_utxos_with_proofs.len(),
+                &excluded_tx_out_indices,
+            )?
+        };
+        log::trace!(logger, "Got {} rings", rings.len());
+
+        // Come up with tombstone block.
+        let tombstone_block = if opt_tombstone > 0 {
+            opt_tombstone
+        } else {
+            let num_blocks_in_ledger = self.ledger_db.num_blocks()?;
+            num_blocks_in_ledger + DEFAULT_NEW_TX_BLOCK_ATTEMPTS
+        };
+        log::trace!(logger, "Tombstone block set to {}", tombstone_block);
+
+        // Both outputs use our primary address for their fog hint.
+        let reserved_subaddresses =
+            ReservedSubaddresses::from_subaddress_index(account_key, Some(change_subaddress), None);
+        let fog_resolver = {
+            let fog_uris = extract_fog_uri(&reserved_subaddresses.primary_address)?
+                .into_iter()
+                .collect::<Vec<_>>();
+            (self.fog_resolver_factory)(&fog_uris).map_err(Error::Fog)?
+        };
+
+        let mut tx_builder = TransactionBuilder::new(
+            block_version,
+            Amount::new(fee, amount.token_id),
+            fog_resolver,
+            memo_builder,
+        )
+        .map_err(|err| Error::TxBuild(format!("Error creating transaction builder: {}", err)))?;
+        tx_builder.set_fee_map(fee_map);
+
+        // Add inputs to the tx.
+        for ((utxo, proof), ring) in selected_utxos_with_proofs.iter().zip(rings.into_iter()) {
+            let (ring, membership_proofs) = ring.into_iter().unzip();
+            tx_builder.add_input(Self::get_input_credentials(
+                utxo,
+                proof,
+                ring,
+                membership_proofs,
+                account_key,
+                &logger,
+            )?);
+        }
+
+        // Send the gift code amount to our gift code subaddress.
+        let mut rng = rand::thread_rng();
+        let TxOutContext {
+            tx_out,
+            confirmation,
+            ..
+        } = tx_builder
+            .add_gift_code_output(amount, &reserved_subaddresses, &mut rng)
+            .map_err(|err| Error::TxBuild(format!("failed adding output: {}", err)))?;
+
+        // The funding memo is written to the change output, even if the change is
+        // zero.
+        let input_value: u64 = selected_utxos_with_proofs
+            .iter()
+            .map(|(utxo, _proof)| utxo.value)
+            .sum();
+        let change = input_value - amount.value - tx_builder.get_fee();
+        tx_builder
+            .add_change_output(
+                Amount::new(change, amount.token_id),
+                &reserved_subaddresses,
+                &mut rng,
+            )
+            .map_err(|err| Error::TxBuild(format!("failed adding output (change): {}", err)))?;
+
+        tx_builder.set_tombstone_block(tombstone_block);
+
+        // Build tx.
+        let tx = tx_builder
+            .build(&NoKeysRingSigner {}, &mut rng)
+            .map_err(|err| Error::TxBuild(format!("build tx failed: {}", err)))?;
+        log::trace!(logger, "Tx constructed, hash={}", tx.tx_hash());
+
+        let tx_out_index = tx
+            .prefix
+            .outputs
+            .iter()
+            .position(|output| *output == tx_out)
+            .ok_or_else(|| Error::TxBuild("Gift code output missing from tx".into()))?;
+
+        Ok(TxProposal {
+            utxos: selected_utxos_with_proofs
+                .into_iter()
+                .map(|(utxo, _proof)| utxo)
+                .collect(),
+            outlays: vec![Outlay {
+                value: amount.value,
+                receiver: reserved_subaddresses.gift_code_subaddress,
+            }],
+            tx,
+            outlay_index_to_tx_out_index: [(0, tx_out_index)].into_iter().collect(),
+            outlay_confirmation_numbers: vec![confirmation],
+        })
+    }
+
+    /// Create a TxProposal that claims a gift code, by spending its TxOut to a
+    /// subaddress of the recipient. A gift code sender memo is written to the
+    /// output.
+    ///
+    /// # Arguments
+    /// * `recipient_monitor_id` - Monitor ID of the recipient.
+    /// * `subaddress_index` - Subaddress that receives the gift code value.
+    /// * `gift_code` - The gift code to claim.
+    /// * `note` - Note recorded in the gift code sender memo.
+    /// * `last_block_infos` - Last block info responses from the network, for
+    ///   determining fees. This should normally come from polling_network_state
+    /// * `opt_fee` - Transaction fee, paid out of the gift code value. If zero,
+    ///   defaults to the network fee.
+    /// * `opt_tombstone` - Tombstone block. If zero, sets to default.
+    #[allow(clippy::too_many_arguments)]
+    pub fn generate_gift_code_claim_tx(
+        &self,
+        recipient_monitor_id: &MonitorId,
+        subaddress_index: u64,
+        gift_code: &TxOutGiftCode,
+        note: &str,
+        last_block_infos: &[BlockInfo],
+        opt_fee: u64,
+        opt_tombstone: u64,
+    ) -> Result<TxProposal, Error> {
+        let logger = self.logger.new(o!("recipient_monitor_id" => recipient_monitor_id.to_string(), "gift_code_global_index" => gift_code.global_index));
+        log::trace!(logger, "Generating gift code claim transaction...");
+
+        let memo_builder = GiftCodeSenderMemoBuilder::new(note)
+            .map_err(|err| Error::InvalidArgument("memo".to_string(), err.to_string()))?;
+
+        // Get recipient monitor data.
+        let recipient_monitor_data = self.mobilecoind_db.get_monitor_data(recipient_monitor_id)?;
+
+        self.build_gift_code_spend_tx(
+            gift_code,
+            recipient_monitor_data.get_account_key()?,
+            subaddress_index,
+            memo_builder,
+            last_block_infos,
+            opt_fee,
+            opt_tombstone,
+            &logger,
+        )
+    }
+
+    /// Create a TxProposal that cancels a gift code that has not been claimed
+    /// yet, by spending its TxOut back to the sender. A gift code cancellation
+    /// memo is written to the output.
+    ///
+    /// # Arguments
+    /// * `sender_monitor_id` - Monitor ID of the account that funded the gift
+    ///   code.
+    /// * `change_subaddress` - Subaddress that receives the gift code value.
+    /// * `gift_code_global_index` - Index of the gift code TxOut in the ledger.
+    /// * `last_block_infos` - Last block info responses from the network, for
+    ///   determining fees. This should normally come from polling_network_state
+    /// * `opt_fee` - Transaction fee, paid out of the gift code value. If zero,
+    ///   defaults to the network fee.
+    /// * `opt_tombstone` - Tombstone block. If zero, sets to default.
+    pub fn generate_gift_code_cancellation_tx(
+        &self,
+        sender_monitor_id: &MonitorId,
+        change_subaddress: u64,
+        gift_code_global_index: u64,
+        last_block_infos: &[BlockInfo],
+        opt_fee: u64,
+        opt_tombstone: u64,
+    ) -> Result<TxProposal, Error> {
+        let logger = self.logger.new(o!("sender_monitor_id" => sender_monitor_id.to_string(), "gift_code_global_index" => gift_code_global_index));
+        log::trace!(logger, "Generating gift code cancellation transaction...");
+
+        // Get sender monitor data.
+        let sender_monitor_data = self.mobilecoind_db.get_monitor_data(sender_monitor_id)?;
+        let account_key = sender_monitor_data.get_account_key()?;
+
+        // Only the account that funded the gift code can recover it.
+        let tx_out = self.ledger_db.get_tx_out_by_index(gift_code_global_index)?;
+        let gift_code = gift_codes::create_gift_code(account_key, &tx_out, gift_code_global_index)?;
+
+        self.build_gift_code_spend_tx(
+            &gift_code,
+            account_key,
+            change_subaddress,
+            GiftCodeCancellationMemoBuilder::new(gift_code_global_index),
+            last_block_infos,
+            opt_fee,
+            opt_tombstone,
+            &logger,
+        )
+    }
+
+    /// Create a TxProposal that spends the TxOut of a gift code to a
+    /// subaddress of an account, as a single change output carrying the memo
+    /// written by `memo_builder`.
+    ///
+    /// The gift code TxOut is reported as the only UTXO of the TxProposal, so
+    /// that the sender receipt of the submitted transaction contains its key
+    /// image.
+    #[allow(clippy::too_many_arguments)]
+    fn build_gift_code_spend_tx(
+        &self,
+        gift_code: &TxOutGiftCode,
+        account_key: &AccountKey,
+        subaddress_index: u64,
+        memo_builder: impl MemoBuilder + 'static + Send + Sync,
+        last_block_infos: &[BlockInfo],
+        opt_fee: u64,
+        opt_tombstone: u64,
+        logger: &Logger,
+    ) -> Result<TxProposal, Error> {
+        let (tx_out, amount) = gift_codes::get_gift_code_tx_out(gift_code, &self.ledger_db)?;
+        let key_image = KeyImage::from(&gift_code.onetime_private_key);
+        if self.ledger_db.contains_key_image(&key_image)? {
+            return Err(Error::GiftCodeAlreadySpent);
+        }
+
+        // Figure out the block version, fee and minimum fee map.
+        let (fee, fee_map, block_version) =
+            self.get_fee_info_and_block_version(last_block_infos, amount.token_id, opt_fee)?;
+        if amount.value <= fee {
+            return Err(Error::InsufficientFunds);
+        }
+
+        // The gift code TxOut with its proof of membership, and a ring of mixins for
+        // it.
+        let proof = self
+            .get_membership_proofs(&[tx_out.clone()])?
+            .pop()
+            .ok_or_else(|| Error::TxBuild("Missing membership proof".into()))?;
+        let (mut ring, mut membership_proofs): (Vec<TxOut>, Vec<TxOutMembershipProof>) = self
+            .get_rings(DEFAULT_RING_SIZE, 1, &[gift_code.global_index])?
+            .pop()
+            .ok_or_else(|| Error::TxBuild("Missing ring".into()))?
+            .into_iter()
+            .unzip();
+        log::trace!(logger, "Got membership proofs and ring");
+
+        // Replace the first element of the ring with the real input. This is safe
+        // because InputCredentials sorts the ring.
+        if ring.is_empty() {
+            ring.push(tx_out.clone());
+            membership_proofs.push(proof);
+        } else {
+            ring[0] = tx_out.clone();
+            membership_proofs[0] = proof;
+        }
+        let input_credentials = InputCredentials::new_with_shared_secret(
+            ring,
+            membership_proofs,
+            0,
+            gift_code.onetime_private_key,
+            gift_code.shared_secret,
+        )
+        .map_err(|_| Error::TxBuild("failed creating InputCredentials".into()))?;
+
+        // Come up with tombstone block.
+        let tombstone_block = if opt_tombstone > 0 {
+            opt_tombstone
+        } else {
+            let num_blocks_in_ledger = self.ledger_db.num_blocks()?;
+            num_blocks_in_ledger + DEFAULT_NEW_TX_BLOCK_ATTEMPTS
+        };
+        log::trace!(logger, "Tombstone block set to {}", tombstone_block);
+
+        let receiver = account_key.subaddress(subaddress_index);
+        let change_dest =
+            ReservedSubaddresses::from_subaddress_index(account_key, Some(subaddress_index), None);
+        let fog_resolver = {
+            let fog_uris = extract_fog_uri(&change_dest.primary_address)?
+                .into_iter()
+                .collect::<Vec<_>>();
+            (self.fog_resolver_factory)(&fog_uris).map_err(Error::Fog)?
+        };
+
+        let mut tx_builder = TransactionBuilder::new(
+            block_version,
+            Amount::new(fee, amount.token_id),
+            fog_resolver,
+            memo_builder,
+        )
+        .map_err(|err| Error::TxBuild(format!("Error creating transaction builder: {}", err)))?;
+        tx_builder.set_fee_map(fee_map);
+        tx_builder.add_input(input_credentials);
+
+        let value = amount.value - tx_builder.get_fee();
+        let mut rng = rand::thread_rng();
+        let TxOutContext {
+            tx_out: output,
+            confirmation,
+            ..
+        } = tx_builder
+            .add_change_output(Amount::new(value, amount.token_id), &change_dest, &mut rng)
+            .map_err(|err| Error::TxBuild(format!("failed adding output: {}", err)))?;
+
+        tx_builder.set_tombstone_block(tombstone_block);
+
+        // Build tx.
+        let tx = tx_builder
+            .build(&NoKeysRingSigner {}, &mut rng)
+            .map_err(|err| Error::TxBuild(format!("build tx failed: {}", err)))?;
+        log::trace!(logger, "Tx constructed, hash={}", tx.tx_hash());
+
+        let tx_out_index = tx
+            .prefix
+            .outputs
+            .iter()
+            .position(|tx_out| *tx_out == output)
+            .ok_or_else(|| Error::TxBuild("Gift code output missing from tx".into()))?;
+
+        Ok(TxProposal {
+            utxos: vec![UnspentTxOut {
+                key_image,
+                tx_out,
+                subaddress_index: GIFT_CODE_SUBADDRESS_INDEX,
+                value: amount.value,
+                attempted_spend_height: 0,
+                attempted_spend_tombstone: 0,
+                token_id: *amount.token_id,
+            }],
+            outlays: vec![Outlay { value, receiver }],
+            tx,
+            outlay_index_to_tx_out_index: [(0, tx_out_index)].into_iter().collect(),
+            outlay_confirmation_numbers: vec![confirmation],
+        })
+    }
+
+    /// Submit a previously built tx proposal to the network.
+    pub fn submit_tx_proposal(&self, tx_proposal: &TxProposal) -> Result<u64, Error> {
+        // Pick a peer to submit to.
+        let responder_ids = self.peer_manager.responder_ids();
+        if responder_ids.is_empty() {
+            return Err(Error::NoPeersConfigured);
+        }
+
+        let idx = self.submit_node_offset.fetch_add(1, Ordering::SeqCst);
+        let responder_id = &responder_ids[idx % responder_ids.len()];
+
+        // Try and submit.
+        let block_height = self
+            .peer_manager
+            .conn(responder_id)
+            .ok_or(Error::NodeNotFound)?
+            .propose_tx(&tx_proposal.tx, empty())
+            .map_err(Error::from)?;
+
+        log::info!(
+            self.logger,
+            "Tx {} submitted at block height {}",
+            tx_proposal.tx,
+            block_height
+        );
+
+        // Successfully submitted.
+        Ok(block_height)
+    }
+
+    /// Returns a subset of UTXOs totalling at least the given amount.
+    // TODO: This method should take attempted_spend_height into account.
+    fn select_utxos_for_value(
+        token_id: TokenId,
+        utxos: &[UnspentTxOut],
+        value: u64,
+        max_inputs: usize,
+    ) -> Result<Vec<UnspentTxOut>, Error> {
+        // Sort the utxos in descending order by value.
+        let mut sorted_utxos: Vec<UnspentTxOut> = utxos
+            .iter()
+            .filter(|utxo| utxo.token_id == token_id)
+            .cloned()
+            .collect();
+        sorted_utxos.sort_by_key(|utxo| Reverse(utxo.value));
+
+        // The maximum spendable is limited by the maximal number of inputs we can use.
+        let max_spendable_amount = sorted_utxos
+            .iter()
+            .take(max_inputs)
+            .map(|utxo| utxo.value)
+            .sum();
+        if value > max_spendable_amount {
+            // See if we merged the UTXOs we would be able to spend this amount.
+            let total_utxos_value: u64 = sorted_utxos.iter().map(|utxo| utxo.value).sum();
+            if total_utxos_value >= value {
+                return Err(Error::InsufficientFundsFragmentedUtxos);
+            } else {
+                return Err(Error::InsufficientFunds);
+            }
+        }
+
+        // Choose utxos to spend.
+        let mut selected_utxos: Vec<UnspentTxOut> = Vec::new();
+        loop {
+            let total: u64 = selected_utxos.iter().map(|utxo| utxo.value).sum();
+            if total >= value {
+                break;
+            }
+
+            // Grab the next (smallest utxo)
+            let next_utxo = sorted_utxos.pop().ok_or(Error::InsufficientFunds)?;
+            selected_utxos.push(next_utxo.clone());
+
+            // Cap at maximum allowed inputs.
+            if selected_utxos.len() > max_inputs {
+                // Remove the lowest utxo.
+                selected_utxos.remove(0);
+            }
+        }
+
+        // Sanity.
+        assert!(!selected_utxos.is_empty());
+        assert!(selected_utxos.len() <= max_inputs);
 
         // Return selected utxos.
         Ok(selected_utxos)
@@ -825,6 +2044,149 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
         Ok(result)
     }
 
+    /// Add a UTXO to its ring of mixins. Returns the ring, its membership
+    /// proofs and the index of the UTXO in the ring.
+    ///
+    /// # Arguments
+    /// * `utxo` - The UTXO to spend.
+    /// * `proof` - The membership proof of the UTXO.
+    /// * `ring` - Mixins for the UTXO.
+    /// * `membership_proofs` - Membership proofs for each of the mixins.
+    fn add_input_to_ring(
+        utxo: &UnspentTxOut,
+        proof: &TxOutMembershipProof,
+        mut ring: Vec<TxOut>,
+        mut membership_proofs: Vec<TxOutMembershipProof>,
+    ) -> (Vec<TxOut>, Vec<TxOutMembershipProof>, usize) {
+        assert_eq!(
+            ring.len(),
+            membership_proofs.len(),
+            "Each ring element must have a corresponding membership proof."
+        );
+
+        // Add the input to the ring.
+        let position_opt = ring.iter().position(|tx_out| *tx_out == utxo.tx_out);
+        let real_key_index = match position_opt {
+            Some(position) => {
+                // The input is already present in the ring.
+                // This could happen if ring elements are sampled randomly from the ledger.
+                position
+            }
+            None => {
+                // The input is not already in the ring.
+                if ring.is_empty() {
+                    // Append the input and its proof of membership.
+                    ring.push(utxo.tx_out.clone());
+                    membership_proofs.push(proof.clone());
+                } else {
+                    // Replace the first element of the ring.
+                    ring[0] = utxo.tx_out.clone();
+                    membership_proofs[0] = proof.clone();
+                }
+                // The real input is always the first element. This is safe because
+                // TransactionBuilder sorts each ring.
+                0
+            }
+        };
+
+        assert_eq!(
+            ring.len(),
+            membership_proofs.len(),
+            "Each ring element must have a corresponding membership proof."
+        );
+
+        (ring, membership_proofs, real_key_index)
+    }
+
+    /// Create the input credentials needed to spend a UTXO.
+    ///
+    /// # Arguments
+    /// * `utxo` - The UTXO to spend.
+    /// * `proof` - The membership proof of the UTXO.
+    /// * `ring` - Mixins for the UTXO.
+    /// * `membership_proofs` - Membership proofs for each of the mixins.
+    /// * `from_account_key` - Owns the UTXO.
+    /// * `logger` - Logger
+    fn get_input_credentials(
+        utxo: &UnspentTxOut,
+        proof: &TxOutMembershipProof,
+        ring: Vec<TxOut>,
+        membership_proofs: Vec<TxOutMembershipProof>,
+        from_account_key: &AccountKey,
+        logger: &Logger,
+    ) -> Result<InputCredentials, Error> {
+        let (ring, membership_proofs, real_key_index) =
+            Self::add_input_to_ring(utxo, proof, ring, membership_proofs);
+
+        let public_key = RistrettoPublic::try_from(&utxo.tx_out.public_key).unwrap();
+        let onetime_private_key = recover_onetime_private_key(
+            &public_key,
+            from_account_key.view_private_key(),
+            &from_account_key.subaddress_spend_private(utxo.subaddress_index),
+        );
+
+        let key_image = KeyImage::from(&onetime_private_key);
+        log::debug!(
+            logger,
+            "Adding input: ring {:?}, utxo index {:?}, key image {:?}, pubkey {:?}",
+            ring,
+            real_key_index,
+            key_image,
+            public_key
+        );
+
+        InputCredentials::new(
+            ring,
+            membership_proofs,
+            real_key_index,
+            onetime_private_key,
+            *from_account_key.view_private_key(),
+        )
+        .map_err(|_| Error::TxBuild("failed creating InputCredentials".into()))
+    }
+
+    /// Create the input credentials needed to build an unsigned transaction
+    /// spending a UTXO of a view-only account. Instead of the one-time private
+    /// key, these hold the subaddress index of the UTXO, from which the signer
+    /// derives the one-time private key.
+    ///
+    /// # Arguments
+    /// * `utxo` - The UTXO to spend.
+    /// * `proof` - The membership proof of the UTXO.
+    /// * `ring` - Mixins for the UTXO.
+    /// * `membership_proofs` - Membership proofs for each of the mixins.
+    /// * `from_view_account_key` - Owns the UTXO.
+    /// * `logger` - Logger
+    fn get_view_only_input_credentials(
+        utxo: &UnspentTxOut,
+        proof: &TxOutMembershipProof,
+        ring: Vec<TxOut>,
+        membership_proofs: Vec<TxOutMembershipProof>,
+        from_view_account_key: &ViewAccountKey,
+        logger: &Logger,
+    ) -> Result<InputCredentials, Error> {
+        let (ring, membership_proofs, real_key_index) =
+            Self::add_input_to_ring(utxo, proof, ring, membership_proofs);
+
+        log::debug!(
+            logger,
+            "Adding view-only input: ring {:?}, utxo index {:?}, subaddress index {}, pubkey {:?}",
+            ring,
+            real_key_index,
+            utxo.subaddress_index,
+            utxo.tx_out.public_key
+        );
+
+        InputCredentials::new(
+            ring,
+            membership_proofs,
+            real_key_index,
+            OneTimeKeyDeriveData::SubaddressIndex(utxo.subaddress_index),
+            *from_view_account_key.view_private_key(),
+        )
+        .map_err(|_| Error::TxBuild("failed creating InputCredentials".into()))
+    }
+
     /// Create a TxProposal.
     ///
     /// # Arguments
@@ -861,6 +2223,177 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
         rng: &mut (impl RngCore + CryptoRng),
         logger: &Logger,
     ) -> Result<TxProposal, Error> {
+        let input_credentials = Self::zip_inputs_and_rings(inputs, rings, logger)?
+            .into_iter()
+            .map(|((utxo, proof), (ring, membership_proofs))| {
+                Self::get_input_credentials(
+                    utxo,
+                    proof,
+                    ring,
+                    membership_proofs,
+                    from_account_key,
+                    logger,
+                )
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let change_dest = ReservedSubaddresses::from_subaddress_index(
+            from_account_key,
+            Some(change_subaddress),
+            None,
+        );
+
+        let (tx_builder, tx_out_to_outlay_index, outlay_confirmation_numbers) =
+            Self::create_tx_builder(
+                input_credentials,
+                inputs,
+                block_version,
+                token_id,
+                fee,
+                &change_dest,
+                destinations,
+                tombstone_block,
+                fog_resolver_factory,
+                opt_memo_builder,
+                fee_map,
+                rng,
+            )?;
+
+        // Build tx.
+        let tx = tx_builder
+            .build(&NoKeysRingSigner {}, rng)
+            .map_err(|err| Error::TxBuild(format!("build tx failed: {}", err)))?;
+
+        let outlay_index_to_tx_out_index = Self::get_outlay_index_to_tx_out_index(
+            &tx.prefix.outputs,
+            &tx_out_to_outlay_index,
+            destinations.len(),
+        );
+
+        // Return the TxProposal
+        let selected_utxos = inputs
+            .iter()
+            .map(|(utxo, _membership_proof)| utxo.clone())
+            .collect();
+
+        Ok(TxProposal {
+            utxos: selected_utxos,
+            outlays: destinations.to_vec(),
+            tx,
+            outlay_index_to_tx_out_index,
+            outlay_confirmation_numbers,
+        })
+    }
+
+    /// Create an UnsignedTxProposal, spending the inputs of a view-only
+    /// account.
+    ///
+    /// # Arguments
+    /// * `inputs` - UTXOs to spend, with membership proofs.
+    /// * `rings` - A set of mixins for each input, with membership proofs.
+    /// * `block_version` - The block version to target for this transaction
+    /// * `token_id` - The token id to transact in
+    /// * `fee` - Transaction fee, in picoMOB.
+    /// * `from_view_account_key` - Owns the inputs. Also the recipient of any
+    ///   change.
+    /// * `change_subaddress` - Subaddress for change recipient.
+    /// * `destinations` - Outputs of the transaction.
+    /// * `tombstone_block` - Tombstone block of the transaciton.
+    /// * `fog_pubkey_resolver` - Provides Fog key report, when Fog is enabled.
+    /// * `fee_map` - The current minimum fee map consensus is configured with.
+    /// * `rng` - randomness
+    /// * `logger` - Logger
+    #[allow(clippy::too_many_arguments)]
+    fn build_unsigned_tx_proposal(
+        inputs: &[(UnspentTxOut, TxOutMembershipProof)],
+        rings: Vec<Vec<(TxOut, TxOutMembershipProof)>>,
+        block_version: BlockVersion,
+        token_id: TokenId,
+        fee: u64,
+        from_view_account_key: &ViewAccountKey,
+        change_subaddress: u64,
+        destinations: &[Outlay],
+        tombstone_block: BlockIndex,
+        fog_resolver_factory: &Arc<dyn Fn(&[FogUri]) -> Result<FPR, String> + Send + Sync>,
+        fee_map: FeeMap,
+        rng: &mut (impl RngCore + CryptoRng),
+        logger: &Logger,
+    ) -> Result<UnsignedTxProposal, Error> {
+        let input_credentials = Self::zip_inputs_and_rings(inputs, rings, logger)?
+            .into_iter()
+            .map(|((utxo, proof), (ring, membership_proofs))| {
+                Self::get_view_only_input_credentials(
+                    utxo,
+                    proof,
+                    ring,
+                    membership_proofs,
+                    from_view_account_key,
+                    logger,
+                )
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let change_dest = ReservedSubaddresses {
+            change_subaddress: from_view_account_key.subaddress(change_subaddress),
+            ..ReservedSubaddresses::from(from_view_account_key)
+        };
+
+        let (tx_builder, tx_out_to_outlay_index, outlay_confirmation_numbers) =
+            Self::create_tx_builder(
+                input_credentials,
+                inputs,
+                block_version,
+                token_id,
+                fee,
+                &change_dest,
+                destinations,
+                tombstone_block,
+                fog_resolver_factory,
+                None,
+                fee_map,
+                rng,
+            )?;
+
+        // Build the unsigned tx.
+        let unsigned_tx = tx_builder
+            .build_unsigned::<DefaultTxOutputsOrdering>()
+            .map_err(|err| Error::TxBuild(format!("build unsigned tx failed: {}", err)))?;
+
+        let outlay_index_to_tx_out_index = Self::get_outlay_index_to_tx_out_index(
+            &unsigned_tx.tx_prefix.outputs,
+            &tx_out_to_outlay_index,
+            destinations.len(),
+        );
+
+        // Return the UnsignedTxProposal
+        let selected_utxos = inputs
+            .iter()
+            .map(|(utxo, _membership_proof)| utxo.clone())
+            .collect();
+
+        Ok(UnsignedTxProposal {
+            utxos: selected_utxos,
+            outlays: destinations.to_vec(),
+            unsigned_tx,
+            outlay_index_to_tx_out_index,
+            outlay_confirmation_numbers,
+        })
+    }
+
+    /// Pair each input with its ring of mixins, unzipping each ring into a
+    /// list of TxOuts and a list of membership proofs.
+    #[allow(clippy::type_complexity)]
+    fn zip_inputs_and_rings<'a>(
+        inputs: &'a [(UnspentTxOut, TxOutMembershipProof)],
+        rings: Vec<Vec<(TxOut, TxOutMembershipProof)>>,
+        logger: &Logger,
+    ) -> Result<
+        Vec<(
+            (&'a UnspentTxOut, &'a TxOutMembershipProof),
+            (Vec<TxOut>, Vec<TxOutMembershipProof>),
+        )>,
+        Error,
+    > {
         // Check that number of rings matches number of inputs.
         if rings.len() != inputs.len() {
             let err = format!(
@@ -872,6 +2405,60 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
             return Err(Error::TxBuild(err));
         }
 
+        // Unzip each vec of tuples into a tuple of vecs.
+        let rings_and_proofs: Vec<(Vec<TxOut>, Vec<TxOutMembershipProof>)> = rings
+            .into_iter()
+            .map(|tuples| tuples.into_iter().unzip())
+            .collect();
+
+        // Rings are assigned to the inputs in reverse order.
+        Ok(inputs
+            .iter()
+            .map(|(utxo, proof)| (utxo, proof))
+            .zip(rings_and_proofs.into_iter().rev())
+            .collect())
+    }
+
+    /// Create a transaction builder with the given inputs, outputs to our
+    /// destinations and change. Returns the builder, a map of TxOut -> outlay
+    /// index and the confirmation numbers of the outlays.
+    ///
+    /// # Arguments
+    /// * `input_credentials` - Credentials for each input.
+    /// * `inputs` - UTXOs to spend, with membership proofs.
+    /// * `block_version` - The block version to target for this transaction
+    /// * `token_id` - The token id to transact in
+    /// * `fee` - Transaction fee, in picoMOB.
+    /// * `change_dest` - The recipient of any change.
+    /// * `destinations` - Outputs of the transaction.
+    /// * `tombstone_block` - Tombstone block of the transaciton.
+    /// * `fog_pubkey_resolver` - Provides Fog key report, when Fog is enabled.
+    /// * `opt_memo_builder` - Optional memo builder to use instead of the
+    ///   default one (EmptyMemoBuilder).
+    /// * `fee_map` - The current minimum fee map consensus is configured with.
+    /// * `rng` - randomness
+    #[allow(clippy::too_many_arguments, clippy::type_complexity)]
+    fn create_tx_builder(
+        input_credentials: Vec<InputCredentials>,
+        inputs: &[(UnspentTxOut, TxOutMembershipProof)],
+        block_version: BlockVersion,
+        token_id: TokenId,
+        fee: u64,
+        change_dest: &ReservedSubaddresses,
+        destinations: &[Outlay],
+        tombstone_block: BlockIndex,
+        fog_resolver_factory: &Arc<dyn Fn(&[FogUri]) -> Result<FPR, String> + Send + Sync>,
+        opt_memo_builder: Option<Box<dyn MemoBuilder + 'static + Send + Sync>>,
+        fee_map: FeeMap,
+        rng: &mut (impl RngCore + CryptoRng),
+    ) -> Result<
+        (
+            TransactionBuilder<FPR>,
+            HashMap<TxOut, usize>,
+            Vec<TxOutConfirmationNumber>,
+        ),
+        Error,
+    > {
         // Check that we have at least one destination.
         if destinations.is_empty() {
             return Err(Error::TxBuild("Must have at least one destination".into()));
@@ -880,8 +2467,7 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
         // Collect all required FogUris from public addresses, then pass to resolver
         // factory
         let fog_resolver = {
-            let change_address = from_account_key.subaddress(change_subaddress);
-            let fog_uris = core::slice::from_ref(&change_address)
+            let fog_uris = core::slice::from_ref(&change_dest.change_subaddress)
                 .iter()
                 .chain(destinations.iter().map(|x| &x.receiver))
                 .filter_map(|x| extract_fog_uri(x).transpose())
@@ -902,81 +2488,9 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
                 })?;
         tx_builder.set_fee_map(fee_map);
 
-        // Unzip each vec of tuples into a tuple of vecs.
-        let mut rings_and_proofs: Vec<(Vec<TxOut>, Vec<TxOutMembershipProof>)> = rings
-            .into_iter()
-            .map(|tuples| tuples.into_iter().unzip())
-            .collect();
-
         // Add inputs to the tx.
-        for (utxo, proof) in inputs {
-            let (mut ring, mut membership_proofs) = rings_and_proofs
-                .pop()
-                .ok_or_else(|| Error::TxBuild("rings_and_proofs was empty".to_string()))?;
-            assert_eq!(
-                ring.len(),
-                membership_proofs.len(),
-                "Each ring element must have a corresponding membership proof."
-            );
-
-            // Add the input to the ring.
-            let position_opt = ring.iter().position(|tx_out| *tx_out == utxo.tx_out);
-            let real_key_index = match position_opt {
-                Some(position) => {
-                    // The input is already present in the ring.
-                    // This could happen if ring elements are sampled randomly from the ledger.
-                    position
-                }
-                None => {
-                    // The input is not already in the ring.
-                    if ring.is_empty() {
-                        // Append the input and its proof of membership.
-                        ring.push(utxo.tx_out.clone());
-                        membership_proofs.push(proof.clone());
-                    } else {
-                        // Replace the first element of the ring.
-                        ring[0] = utxo.tx_out.clone();
-                        membership_proofs[0] = proof.clone();
-                    }
-                    // The real input is always the first element. This is safe because
-                    // TransactionBuilder sorts each ring.
-                    0
-                }
-            };
-
-            assert_eq!(
-                ring.len(),
-                membership_proofs.len(),
-                "Each ring element must have a corresponding membership proof."
-            );
-
-            let public_key = RistrettoPublic::try_from(&utxo.tx_out.public_key).unwrap();
-            let onetime_private_key = recover_onetime_private_key(
-                &public_key,
-                from_account_key.view_private_key(),
-                &from_account_key.subaddress_spend_private(utxo.subaddress_index),
-            );
-
-            let key_image = KeyImage::from(&onetime_private_key);
-            log::debug!(
-                logger,
-                "Adding input: ring {:?}, utxo index {:?}, key image {:?}, pubkey {:?}",
-                ring,
-                real_key_index,
-                key_image,
-                public_key
-            );
-
-            tx_builder.add_input(
-                InputCredentials::new(
-                    ring,
-                    membership_proofs,
-                    real_key_index,
-                    onetime_private_key,
-                    *from_account_key.view_private_key(),
-                )
-                .map_err(|_| Error::TxBuild("failed creating InputCredentials".into()))?,
-            );
+        for input in input_credentials {
+            tx_builder.add_input(input);
         }
 
         // Add outputs to our destinations.
@@ -1024,29 +2538,33 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
                 token_id,
             };
 
-            let change_dest = ReservedSubaddresses::from_subaddress_index(
-                from_account_key,
-                Some(change_subaddress),
-                None,
-            );
-
             tx_builder
-                .add_change_output(change_amount, &change_dest, rng)
+                .add_change_output(change_amount, change_dest, rng)
                 .map_err(|err| Error::TxBuild(format!("failed adding output (change): {}", err)))?;
         }
 
         // Set tombstone block.
         tx_builder.set_tombstone_block(tombstone_block);
 
-        // Build tx.
-        let tx = tx_builder
-            .build(&NoKeysRingSigner {}, rng)
-            .map_err(|err| Error::TxBuild(format!("build tx failed: {}", err)))?;
+        Ok((
+            tx_builder,
+            tx_out_to_outlay_index,
+            outlay_confirmation_numbers,
+        ))
+    }
 
-        // Map each TxOut in the constructed transaction to its respective outlay.
-        let outlay_index_to_tx_out_index = tx
-            .prefix
-            .outputs
+    /// Map each TxOut in a constructed transaction to its respective outlay.
+    ///
+    /// # Arguments
+    /// * `outputs` - The outputs of the constructed transaction.
+    /// * `tx_out_to_outlay_index` - Map of TxOut -> outlay index.
+    /// * `num_destinations` - The number of outlays.
+    fn get_outlay_index_to_tx_out_index(
+        outputs: &[TxOut],
+        tx_out_to_outlay_index: &HashMap<TxOut, usize>,
+        num_destinations: usize,
+    ) -> HashMap<usize, usize> {
+        let outlay_index_to_tx_out_index = outputs
             .iter()
             .enumerate()
             .filter_map(|(tx_out_index, tx_out)| {
@@ -1057,9 +2575,9 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
             .collect::<HashMap<_, _>>();
 
         // Sanity check: All of our outlays should have a unique index in the map.
-        assert_eq!(outlay_index_to_tx_out_index.len(), destinations.len());
+        assert_eq!(outlay_index_to_tx_out_index.len(), num_destinations);
         let mut found_tx_out_indices = HashSet::default();
-        for i in 0..destinations.len() {
+        for i in 0..num_destinations {
             let tx_out_index = outlay_index_to_tx_out_index
                 .get(&i)
                 .expect("index not in map");
@@ -1068,19 +2586,7 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
             }
         }
 
-        // Return the TxProposal
-        let selected_utxos = inputs
-            .iter()
-            .map(|(utxo, _membership_proof)| utxo.clone())
-            .collect();
-
-        Ok(TxProposal {
-            utxos: selected_utxos,
-            outlays: destinations.to_vec(),
-            tx,
-            outlay_index_to_tx_out_index,
-            outlay_confirmation_numbers,
-        })
+        outlay_index_to_tx_out_index
     }
 }
 
@@ -17,7 +17,7 @@ pub const PROCESSED_BLOCK_KEY_TO_PROCESSED_TX_OUTS_DB_NAME: &str =
     "mobilecoind_db:processed_block_store:processed_block_key_to_processed_tx_outs";
 
 /// Type used as the key in the databases managed by the processed block store.
-#[derive(Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub struct ProcessedBlockKey {
     /// The monitor_id for which the data belongs to.
     pub monitor_id: MonitorId,
@@ -181,6 +181,40 @@ impl ProcessedBlockStore {
             .collect::<Result<Vec<_>, Error>>()
     }
 
+    /// Get processed block information for a given monitor id, for all blocks
+    /// in the range [start_block, end_block) that have any data.
+    pub fn get_processed_blocks(
+        &self,
+        db_txn: &impl Transaction,
+        monitor_id: &MonitorId,
+        start_block: u64,
+        end_block: u64,
+    ) -> Result<Vec<(u64, Vec<ProcessedTxOut>)>, Error> {
+        let mut cursor = db_txn.open_ro_cursor(self.processed_block_key_to_processed_tx_outs)?;
+
+        let start_key = ProcessedBlockKey::new(monitor_id, start_block);
+        let start_key_bytes = start_key.to_vec();
+
+        let mut processed_blocks: Vec<(u64, Vec<ProcessedTxOut>)> = Vec::new();
+        for result in cursor.iter_from(&start_key_bytes) {
+            let (db_key, db_value) = result?;
+            let key = ProcessedBlockKey::try_from(db_key)?;
+            if key.monitor_id != *monitor_id || key.block_index >= end_block {
+                break;
+            }
+
+            let processed_tx_out: ProcessedTxOut = mc_util_serial::decode(db_value)?;
+            match processed_blocks.last_mut() {
+                Some((block_index, processed_tx_outs)) if *block_index == key.block_index => {
+                    processed_tx_outs.push(processed_tx_out)
+                }
+                _ => processed_blocks.push((key.block_index, vec![processed_tx_out])),
+            }
+        }
+
+        Ok(processed_blocks)
+    }
+
     /// Remove the data associated with a given monitor id.
     pub fn remove<'env>(
         &self,
@@ -375,7 +409,7 @@ mod test {
         )
         .expect("failed to create data");
 
-        let monitor_id = MonitorId::from(&monitor_data);
+        let monitor_id = MonitorId::try_from(&monitor_data).unwrap();
 
         // Initially, we should have no data for any of our blocks.
         {
@@ -456,7 +490,7 @@ mod test {
             )
             .expect("failed to create data");
 
-            let monitor_id = MonitorId::from(&monitor_data);
+            let monitor_id = MonitorId::try_from(&monitor_data).unwrap();
 
             let mut db_txn = env.begin_rw_txn().unwrap();
 
@@ -524,7 +558,7 @@ mod test {
             )
             .expect("failed to create data");
 
-            let monitor_id2 = MonitorId::from(&monitor_data2);
+            let monitor_id2 = MonitorId::try_from(&monitor_data2).unwrap();
 
             store
                 .block_processed(&mut db_txn, &monitor_id2, 0, &utxos[0..1], &utxos[1..2])
@@ -583,6 +617,35 @@ mod test {
                 .expect("get_processed_block failed");
             assert!(processed_tx_outs.is_empty());
 
+            // Ranges of blocks only include blocks with data, for the requested monitor id.
+            let processed_blocks = store
+                .get_processed_blocks(&db_txn, &monitor_id, 0, num_blocks)
+                .expect("get_processed_blocks failed");
+            assert_eq!(
+                processed_blocks
+                    .iter()
+                    .map(|(block_index, processed_tx_outs)| (*block_index, processed_tx_outs.len()))
+                    .collect::<Vec<_>>(),
+                vec![(0, 4), (1, utxos.len() - 5)]
+            );
+
+            let processed_blocks = store
+                .get_processed_blocks(&db_txn, &monitor_id, 1, num_blocks)
+                .expect("get_processed_blocks failed");
+            assert_eq!(processed_blocks.len(), 1);
+            assert_eq!(processed_blocks[0].0, 1);
+
+            let processed_blocks = store
+                .get_processed_blocks(&db_txn, &monitor_id2, 0, 1)
+                .expect("get_processed_blocks failed");
+            assert_eq!(processed_blocks.len(), 1);
+            assert_eq!(processed_blocks[0].1.len(), 2);
+
+            let processed_blocks = store
+                .get_processed_blocks(&db_txn, &monitor_id2, 1, num_blocks)
+                .expect("get_processed_blocks failed");
+            assert!(processed_blocks.is_empty());
+
             db_txn.commit().unwrap();
         }
     }
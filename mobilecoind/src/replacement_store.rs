@@ -0,0 +1,193 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! Database storage for replacement transactions.
+//! * Stores a map of key image -> data about a replacement transaction that was
+//!   built to cancel a pending transaction by spending the same input. This is
+//!   used when checking transaction status as the sender, allowing us to tell
+//!   whether the original transaction or its replacement landed.
+
+use crate::error::Error;
+use lmdb::{Database, DatabaseFlags, Environment, RwTransaction, Transaction, WriteFlags};
+use mc_common::logger::{log, Logger};
+use mc_crypto_keys::CompressedRistrettoPublic;
+use mc_transaction_core::ring_signature::KeyImage;
+use prost::Message;
+use std::sync::Arc;
+
+// LMDB Database Names
+pub const KEY_IMAGE_TO_REPLACEMENT_TX_DB_NAME: &str =
+    "mobilecoind_db:replacement_store:key_image_to_replacement_tx";
+
+/// Type used as the stored data in the key_image_to_replacement_tx database.
+#[derive(Clone, Eq, Hash, PartialEq, Message)]
+pub struct ReplacementTx {
+    /// The public keys of the outputs created by the replacement transaction.
+    #[prost(message, repeated, tag = "1")]
+    pub tx_out_public_keys: Vec<CompressedRistrettoPublic>,
+
+    /// The tombstone block of the replacement transaction.
+    #[prost(uint64, tag = "2")]
+    pub tombstone_block: u64,
+
+    /// The fee paid by the replacement transaction.
+    #[prost(uint64, tag = "3")]
+    pub fee: u64,
+
+    /// The fee paid by the transaction being replaced.
+    #[prost(uint64, tag = "4")]
+    pub replaced_fee: u64,
+}
+
+#[derive(Clone)]
+pub struct ReplacementStore {
+    /// Retain a reference to the Environment so the Database handles are valid.
+    _env: Arc<Environment>,
+
+    /// Mapping of KeyImage -> ReplacementTx
+    key_image_to_replacement_tx: Database,
+
+    /// Logger.
+    logger: Logger,
+}
+
+impl ReplacementStore {
+    pub fn new(env: Arc<Environment>, logger: Logger) -> Result<Self, Error> {
+        let key_image_to_replacement_tx = env.create_db(
+            Some(KEY_IMAGE_TO_REPLACEMENT_TX_DB_NAME),
+            DatabaseFlags::empty(),
+        )?;
+
+        Ok(Self {
+            _env: env,
+            key_image_to_replacement_tx,
+            logger,
+        })
+    }
+
+    /// Record a replacement transaction that spends the given key images.
+    /// Recording a newer replacement for the same key image overwrites the
+    /// previous one, since only the latest replacement is expected to be
+    /// submitted.
+    pub fn insert<'env>(
+        &self,
+        db_txn: &mut RwTransaction<'env>,
+        key_images: &[KeyImage],
+        replacement_tx: &ReplacementTx,
+    ) -> Result<(), Error> {
+        let value_bytes = mc_util_serial::encode(replacement_tx);
+
+        for key_image in key_images {
+            db_txn.put(
+                self.key_image_to_replacement_tx,
+                key_image.as_bytes(),
+                &value_bytes,
+                WriteFlags::empty(),
+            )?;
+
+            log::trace!(
+                self.logger,
+                "Recorded replacement tx (fee {}, tombstone {}) for key image {:?}",
+                replacement_tx.fee,
+                replacement_tx.tombstone_block,
+                key_image,
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Get the replacement transaction recorded for a given key image, if any.
+    pub fn get(
+        &self,
+        db_txn: &impl Transaction,
+        key_image: &KeyImage,
+    ) -> Result<Option<ReplacementTx>, Error> {
+        match db_txn.get(self.key_image_to_replacement_tx, key_image.as_bytes()) {
+            Ok(value_bytes) => Ok(Some(mc_util_serial::decode(value_bytes)?)),
+            Err(lmdb::Error::NotFound) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use mc_common::logger::{test_with_logger, Logger};
+    use mc_crypto_keys::RistrettoPublic;
+    use mc_util_from_random::FromRandom;
+    use rand::{rngs::StdRng, SeedableRng};
+    use tempdir::TempDir;
+
+    fn setup_test_replacement_store(logger: &Logger) -> (Arc<Environment>, ReplacementStore) {
+        let db_tmp = TempDir::new("replacement_store_db")
+            .expect("Could not make tempdir for replacement store db");
+        let db_path = db_tmp
+            .path()
+            .to_str()
+            .expect("Could not get path as string");
+
+        let env = Arc::new(
+            Environment::new()
+                .set_max_dbs(10)
+                .set_map_size(10000000)
+                .open(db_path.as_ref())
+                .unwrap(),
+        );
+
+        let replacement_store = ReplacementStore::new(env.clone(), logger.clone()).unwrap();
+
+        (env, replacement_store)
+    }
+
+    #[test_with_logger]
+    fn test_insert_and_get(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([1u8; 32]);
+        let (env, replacement_store) = setup_test_replacement_store(&logger);
+
+        let replacement_tx = ReplacementTx {
+            tx_out_public_keys: vec![CompressedRistrettoPublic::from(
+                &RistrettoPublic::from_random(&mut rng),
+            )],
+            tombstone_block: 123,
+            fee: 800,
+            replaced_fee: 400,
+        };
+
+        // Nothing recorded initially.
+        {
+            let db_txn = env.begin_ro_txn().unwrap();
+            assert_eq!(
+                replacement_store.get(&db_txn, &KeyImage::from(1)).unwrap(),
+                None
+            );
+        }
+
+        // Record the replacement for two key images.
+        {
+            let mut db_txn = env.begin_rw_txn().unwrap();
+            replacement_store
+                .insert(
+                    &mut db_txn,
+                    &[KeyImage::from(1), KeyImage::from(2)],
+                    &replacement_tx,
+                )
+                .unwrap();
+            db_txn.commit().unwrap();
+        }
+
+        let db_txn = env.begin_ro_txn().unwrap();
+        assert_eq!(
+            replacement_store.get(&db_txn, &KeyImage::from(1)).unwrap(),
+            Some(replacement_tx.clone())
+        );
+        assert_eq!(
+            replacement_store.get(&db_txn, &KeyImage::from(2)).unwrap(),
+            Some(replacement_tx)
+        );
+        assert_eq!(
+            replacement_store.get(&db_txn, &KeyImage::from(3)).unwrap(),
+            None
+        );
+    }
+}
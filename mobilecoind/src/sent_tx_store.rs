@@ -0,0 +1,558 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! Database storage for the history of transactions sent by monitors.
+//! * Stores a map of (monitor id, index) -> data about a transaction that was
+//!   submitted to the network, spending outputs belonging to that monitor.
+//!   Indexes are assigned sequentially, in submission order.
+//! * Stores a map of monitor id -> number of transactions sent by it, used to
+//!   assign indexes and to paginate over the history.
+
+use crate::{error::Error, monitor_store::MonitorId, payments::TxProposal};
+use lmdb::{Cursor, Database, DatabaseFlags, Environment, RwTransaction, Transaction, WriteFlags};
+use mc_account_keys::PublicAddress;
+use mc_common::logger::{log, Logger};
+use mc_crypto_keys::{CompressedRistrettoPublic, RistrettoPrivate, RistrettoPublic};
+use mc_ledger_db::{Error as LedgerError, Ledger};
+use mc_transaction_core::{get_tx_out_shared_secret, ring_signature::KeyImage};
+use prost::Message;
+use std::{collections::HashSet, sync::Arc};
+
+// LMDB Database Names
+pub const SENT_TX_KEY_TO_SENT_TX_DB_NAME: &str =
+    "mobilecoind_db:sent_tx_store:sent_tx_key_to_sent_tx";
+pub const MONITOR_ID_TO_NUM_SENT_TXS_DB_NAME: &str =
+    "mobilecoind_db:sent_tx_store:monitor_id_to_num_sent_txs";
+
+/// Type used as the key in the sent_tx_key_to_sent_tx database.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct SentTxKey {
+    /// The monitor_id that sent the transaction.
+    pub monitor_id: MonitorId,
+
+    /// The index of the transaction in the history of the monitor.
+    pub index: u64,
+}
+impl SentTxKey {
+    pub fn new(monitor_id: &MonitorId, index: u64) -> Self {
+        Self {
+            monitor_id: *monitor_id,
+            index,
+        }
+    }
+
+    // 40 bytes: 32 for MonitorId, 8 for index.
+    pub fn to_bytes(&self) -> [u8; 40] {
+        let mut buf = [0u8; 40];
+        buf[0..32].copy_from_slice(self.monitor_id.as_bytes());
+        buf[32..40].copy_from_slice(&self.index.to_be_bytes());
+        buf
+    }
+
+    pub fn to_vec(&self) -> Vec<u8> {
+        self.to_bytes().to_vec()
+    }
+}
+
+impl TryFrom<&[u8]> for SentTxKey {
+    type Error = Error;
+
+    fn try_from(src: &[u8]) -> Result<Self, Self::Error> {
+        if src.len() != 40 {
+            return Err(Error::InvalidArgument(
+                "src".to_string(),
+                "src length must be exactly 40".to_string(),
+            ));
+        }
+
+        let monitor_id = MonitorId::try_from(&src[0..32])?;
+
+        let mut index_bytes = [0u8; 8];
+        index_bytes.copy_from_slice(&src[32..40]);
+        let index = u64::from_be_bytes(index_bytes);
+
+        Ok(Self { monitor_id, index })
+    }
+}
+
+/// A payment made by a sent transaction.
+#[derive(Clone, Eq, Hash, PartialEq, Message)]
+pub struct SentTxOutlay {
+    /// The recipient of the payment.
+    #[prost(message, required, tag = "1")]
+    pub receiver: PublicAddress,
+
+    /// The value paid.
+    #[prost(uint64, tag = "2")]
+    pub value: u64,
+
+    /// The public key of the TxOut paying the recipient.
+    #[prost(message, required, tag = "3")]
+    pub tx_out_public_key: CompressedRistrettoPublic,
+
+    /// The confirmation number of the TxOut paying the recipient.
+    #[prost(bytes, tag = "4")]
+    pub confirmation_number: Vec<u8>,
+}
+
+/// Type used as the stored data in the sent_tx_key_to_sent_tx database.
+#[derive(Clone, Eq, Hash, PartialEq, Message)]
+pub struct SentTx {
+    /// The payments made by the transaction.
+    #[prost(message, repeated, tag = "1")]
+    pub outlays: Vec<SentTxOutlay>,
+
+    /// The token id of the payments.
+    #[prost(uint64, tag = "2")]
+    pub token_id: u64,
+
+    /// The fee paid by the transaction.
+    #[prost(uint64, tag = "3")]
+    pub fee: u64,
+
+    /// The token id of the fee.
+    #[prost(uint64, tag = "4")]
+    pub fee_token_id: u64,
+
+    /// The tombstone block of the transaction.
+    #[prost(uint64, tag = "5")]
+    pub tombstone_block: u64,
+
+    /// The key images of the inputs spent by the transaction.
+    #[prost(message, repeated, tag = "6")]
+    pub key_images: Vec<KeyImage>,
+
+    /// The public keys of all the outputs created by the transaction.
+    #[prost(message, repeated, tag = "7")]
+    pub tx_out_public_keys: Vec<CompressedRistrettoPublic>,
+
+    /// The memos of the outputs returning to the sender, such as change, which
+    /// describe the transaction. Each memo is a 66 bytes memo payload.
+    #[prost(bytes, repeated, tag = "8")]
+    pub memos: Vec<Vec<u8>>,
+
+    /// The number of blocks in the ledger of the node the transaction was
+    /// submitted to, at submission time.
+    #[prost(uint64, tag = "9")]
+    pub submitted_block_count: u64,
+}
+
+impl SentTx {
+    /// Record a submitted TxProposal. The view private key of the sender is
+    /// used to decrypt the memos of the outputs that return to it.
+    pub fn new(
+        tx_proposal: &TxProposal,
+        view_private_key: &RistrettoPrivate,
+        submitted_block_count: u64,
+    ) -> Self {
+        let outputs = &tx_proposal.tx.prefix.outputs;
+
+        let outlays = tx_proposal
+            .outlays
+            .iter()
+            .enumerate()
+            .filter_map(|(outlay_index, outlay)| {
+                let tx_out = outputs.get(
+                    *tx_proposal
+                        .outlay_index_to_tx_out_index
+                        .get(&outlay_index)?,
+                )?;
+                Some(SentTxOutlay {
+                    receiver: outlay.receiver.clone(),
+                    value: outlay.value,
+                    tx_out_public_key: tx_out.public_key,
+                    confirmation_number: tx_proposal
+                        .outlay_confirmation_numbers
+                        .get(outlay_index)
+                        .map(|confirmation_number| confirmation_number.to_vec())
+                        .unwrap_or_default(),
+                })
+            })
+            .collect();
+
+        // Outputs that do not pay an outlay return to the sender.
+        let outlay_tx_out_indices: HashSet<usize> = tx_proposal
+            .outlay_index_to_tx_out_index
+            .values()
+            .cloned()
+            .collect();
+        let memos = outputs
+            .iter()
+            .enumerate()
+            .filter(|(tx_out_index, tx_out)| {
+                !outlay_tx_out_indices.contains(tx_out_index) && tx_out.e_memo.is_some()
+            })
+            .filter_map(|(_, tx_out)| {
+                let tx_public_key = RistrettoPublic::try_from(&tx_out.public_key).ok()?;
+                let shared_secret = get_tx_out_shared_secret(view_private_key, &tx_public_key);
+                Some(tx_out.decrypt_memo(&shared_secret).as_ref().to_vec())
+            })
+            .collect();
+
+        Self {
+            outlays,
+            token_id: tx_proposal
+                .utxos
+                .first()
+                .map(|utxo| utxo.token_id)
+                .unwrap_or_default(),
+            fee: tx_proposal.tx.prefix.fee,
+            fee_token_id: tx_proposal.tx.prefix.fee_token_id,
+            tombstone_block: tx_proposal.tx.prefix.tombstone_block,
+            key_images: tx_proposal
+                .utxos
+                .iter()
+                .map(|utxo| utxo.key_image)
+                .collect(),
+            tx_out_public_keys: outputs.iter().map(|tx_out| tx_out.public_key).collect(),
+            memos,
+            submitted_block_count,
+        }
+    }
+
+    /// Resolve the status of the transaction against the ledger. A
+    /// transaction landed if its outputs are in the ledger, and expired once
+    /// the ledger reached its tombstone block without it landing.
+    pub fn status(&self, ledger_db: &impl Ledger) -> Result<SentTxStatus, Error> {
+        if let Some(public_key) = self.tx_out_public_keys.first() {
+            match ledger_db.get_tx_out_index_by_public_key(public_key) {
+                Ok(tx_out_index) => {
+                    let block_index = ledger_db.get_block_index_by_tx_out_index(tx_out_index)?;
+                    return Ok(SentTxStatus::Landed(block_index));
+                }
+                Err(LedgerError::NotFound) => {}
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        if ledger_db.num_blocks()? >= self.tombstone_block {
+            Ok(SentTxStatus::Expired)
+        } else {
+            Ok(SentTxStatus::Pending)
+        }
+    }
+}
+
+/// The status of a sent transaction.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum SentTxStatus {
+    /// The transaction may still land.
+    Pending,
+
+    /// The transaction landed in the given block.
+    Landed(u64),
+
+    /// The transaction can no longer land.
+    Expired,
+}
+
+#[derive(Clone)]
+pub struct SentTxStore {
+    /// Retain a reference to the Environment so the Database handles are valid.
+    _env: Arc<Environment>,
+
+    /// Mapping of SentTxKey -> SentTx
+    sent_tx_key_to_sent_tx: Database,
+
+    /// Mapping of MonitorId -> number of sent transactions
+    monitor_id_to_num_sent_txs: Database,
+
+    /// Logger.
+    logger: Logger,
+}
+
+impl SentTxStore {
+    pub fn new(env: Arc<Environment>, logger: Logger) -> Result<Self, Error> {
+        let sent_tx_key_to_sent_tx =
+            env.create_db(Some(SENT_TX_KEY_TO_SENT_TX_DB_NAME), DatabaseFlags::empty())?;
+        let monitor_id_to_num_sent_txs = env.create_db(
+            Some(MONITOR_ID_TO_NUM_SENT_TXS_DB_NAME),
+            DatabaseFlags::empty(),
+        )?;
+
+        Ok(Self {
+            _env: env,
+            sent_tx_key_to_sent_tx,
+            monitor_id_to_num_sent_txs,
+            logger,
+        })
+    }
+
+    /// Append a transaction to the history of a monitor.
+    pub fn insert<'env>(
+        &self,
+        db_txn: &mut RwTransaction<'env>,
+        monitor_id: &MonitorId,
+        sent_tx: &SentTx,
+    ) -> Result<SentTxKey, Error> {
+        let key = SentTxKey::new(monitor_id, self.get_num_sent_txs(db_txn, monitor_id)?);
+
+        db_txn.put(
+            self.sent_tx_key_to_sent_tx,
+            &key.to_vec(),
+            &mc_util_serial::encode(sent_tx),
+            WriteFlags::NO_OVERWRITE,
+        )?;
+        db_txn.put(
+            self.monitor_id_to_num_sent_txs,
+            monitor_id,
+            &(key.index + 1).to_be_bytes(),
+            WriteFlags::empty(),
+        )?;
+
+        log::trace!(
+            self.logger,
+            "Recorded sent tx {} of monitor {} (fee {}, tombstone {})",
+            key.index,
+            monitor_id,
+            sent_tx.fee,
+            sent_tx.tombstone_block,
+        );
+
+        Ok(key)
+    }
+
+    /// Get the number of transactions sent by a monitor.
+    pub fn get_num_sent_txs(
+        &self,
+        db_txn: &impl Transaction,
+        monitor_id: &MonitorId,
+    ) -> Result<u64, Error> {
+        match db_txn.get(self.monitor_id_to_num_sent_txs, monitor_id) {
+            Ok(value_bytes) => {
+                let mut num_bytes = [0u8; 8];
+                if value_bytes.len() != num_bytes.len() {
+                    return Err(Error::InvalidArgument(
+                        "value_bytes".to_string(),
+                        "value length must be exactly 8".to_string(),
+                    ));
+                }
+                num_bytes.copy_from_slice(value_bytes);
+                Ok(u64::from_be_bytes(num_bytes))
+            }
+            Err(lmdb::Error::NotFound) => Ok(0),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Get a page of the history of a monitor, most recent transactions first.
+    /// Skips the `offset` most recent transactions, and returns at most
+    /// `limit` transactions.
+    pub fn get_sent_txs(
+        &self,
+        db_txn: &impl Transaction,
+        monitor_id: &MonitorId,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<(SentTxKey, SentTx)>, Error> {
+        let num_sent_txs = self.get_num_sent_txs(db_txn, monitor_id)?;
+        let end = num_sent_txs.saturating_sub(offset);
+        let start = end.saturating_sub(limit);
+
+        (start..end)
+            .rev()
+            .map(|index| {
+                let key = SentTxKey::new(monitor_id, index);
+                let value_bytes = db_txn.get(self.sent_tx_key_to_sent_tx, &key.to_vec())?;
+                Ok((key, mc_util_serial::decode(value_bytes)?))
+            })
+            .collect()
+    }
+
+    /// Remove the history of a given monitor id.
+    pub fn remove<'env>(
+        &self,
+        db_txn: &mut RwTransaction<'env>,
+        monitor_id: &MonitorId,
+    ) -> Result<(), Error> {
+        let start_key = SentTxKey::new(monitor_id, 0);
+        let start_key_bytes = start_key.to_vec();
+
+        {
+            let mut cursor = db_txn.open_rw_cursor(self.sent_tx_key_to_sent_tx)?;
+            for (db_key, _db_value) in cursor.iter_from(&start_key_bytes).filter_map(|r| r.ok()) {
+                let key = SentTxKey::try_from(db_key)?;
+                if key.monitor_id == *monitor_id {
+                    cursor.del(WriteFlags::empty())?;
+                } else {
+                    break;
+                }
+            }
+        }
+
+        match db_txn.del(self.monitor_id_to_num_sent_txs, monitor_id, None) {
+            Ok(()) | Err(lmdb::Error::NotFound) => Ok(()),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use mc_account_keys::AccountKey;
+    use mc_common::logger::{test_with_logger, Logger};
+    use mc_util_from_random::FromRandom;
+    use rand::{rngs::StdRng, SeedableRng};
+    use tempdir::TempDir;
+
+    fn setup_test_sent_tx_store(logger: &Logger) -> (Arc<Environment>, SentTxStore) {
+        let db_tmp =
+            TempDir::new("sent_tx_store_db").expect("Could not make tempdir for sent tx store db");
+        let db_path = db_tmp
+            .path()
+            .to_str()
+            .expect("Could not get path as string");
+
+        let env = Arc::new(
+            Environment::new()
+                .set_max_dbs(10)
+                .set_map_size(10000000)
+                .open(db_path.as_ref())
+                .unwrap(),
+        );
+
+        let sent_tx_store = SentTxStore::new(env.clone(), logger.clone()).unwrap();
+
+        (env, sent_tx_store)
+    }
+
+    fn random_sent_tx(rng: &mut StdRng, fee: u64) -> SentTx {
+        SentTx {
+            outlays: vec![SentTxOutlay {
+                receiver: AccountKey::random(rng).default_subaddress(),
+                value: 1000,
+                tx_out_public_key: CompressedRistrettoPublic::from(&RistrettoPublic::from_random(
+                    rng,
+                )),
+                confirmation_number: vec![1; 32],
+            }],
+            token_id: 0,
+            fee,
+            fee_token_id: 0,
+            tombstone_block: 100,
+            key_images: vec![KeyImage::from(fee)],
+            tx_out_public_keys: vec![CompressedRistrettoPublic::from(
+                &RistrettoPublic::from_random(rng),
+            )],
+            memos: vec![vec![0; 66]],
+            submitted_block_count: 10,
+        }
+    }
+
+    #[test_with_logger]
+    fn test_insert_get_and_remove(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([1u8; 32]);
+        let (env, sent_tx_store) = setup_test_sent_tx_store(&logger);
+
+        let monitor_id1 = MonitorId::try_from(&[1u8; 32][..]).unwrap();
+        let monitor_id2 = MonitorId::try_from(&[2u8; 32][..]).unwrap();
+
+        let sent_txs1: Vec<SentTx> = (0..5).map(|i| random_sent_tx(&mut rng, i)).collect();
+        let sent_txs2: Vec<SentTx> = (5..7).map(|i| random_sent_tx(&mut rng, i)).collect();
+
+        // Nothing was sent yet.
+        {
+            let db_txn = env.begin_ro_txn().unwrap();
+            assert_eq!(
+                sent_tx_store
+                    .get_num_sent_txs(&db_txn, &monitor_id1)
+                    .unwrap(),
+                0
+            );
+            assert_eq!(
+                sent_tx_store
+                    .get_sent_txs(&db_txn, &monitor_id1, 0, 10)
+                    .unwrap(),
+                vec![]
+            );
+        }
+
+        // Insert transactions for both monitors, interleaved.
+        {
+            let mut db_txn = env.begin_rw_txn().unwrap();
+            for (i, sent_tx) in sent_txs1.iter().enumerate() {
+                let key = sent_tx_store
+                    .insert(&mut db_txn, &monitor_id1, sent_tx)
+                    .unwrap();
+                assert_eq!(key, SentTxKey::new(&monitor_id1, i as u64));
+
+                if let Some(sent_tx) = sent_txs2.get(i) {
+                    sent_tx_store
+                        .insert(&mut db_txn, &monitor_id2, sent_tx)
+                        .unwrap();
+                }
+            }
+            db_txn.commit().unwrap();
+        }
+
+        // Pages are returned most recent first.
+        {
+            let db_txn = env.begin_ro_txn().unwrap();
+            assert_eq!(
+                sent_tx_store
+                    .get_num_sent_txs(&db_txn, &monitor_id1)
+                    .unwrap(),
+                5
+            );
+            assert_eq!(
+                sent_tx_store
+                    .get_num_sent_txs(&db_txn, &monitor_id2)
+                    .unwrap(),
+                2
+            );
+
+            let fees = |offset, limit| {
+                sent_tx_store
+                    .get_sent_txs(&db_txn, &monitor_id1, offset, limit)
+                    .unwrap()
+                    .into_iter()
+                    .map(|(_key, sent_tx)| sent_tx.fee)
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(fees(0, 10), vec![4, 3, 2, 1, 0]);
+            assert_eq!(fees(0, 2), vec![4, 3]);
+            assert_eq!(fees(2, 2), vec![2, 1]);
+            assert_eq!(fees(4, 2), vec![0]);
+            assert_eq!(fees(5, 2), Vec::<u64>::new());
+            assert_eq!(fees(0, 0), Vec::<u64>::new());
+
+            let (key, sent_tx) = sent_tx_store
+                .get_sent_txs(&db_txn, &monitor_id2, 0, 1)
+                .unwrap()
+                .remove(0);
+            assert_eq!(key, SentTxKey::new(&monitor_id2, 1));
+            assert_eq!(sent_tx, sent_txs2[1]);
+        }
+
+        // Removing a monitor's history leaves the other one untouched.
+        {
+            let mut db_txn = env.begin_rw_txn().unwrap();
+            sent_tx_store.remove(&mut db_txn, &monitor_id1).unwrap();
+            db_txn.commit().unwrap();
+        }
+        {
+            let db_txn = env.begin_ro_txn().unwrap();
+            assert_eq!(
+                sent_tx_store
+                    .get_num_sent_txs(&db_txn, &monitor_id1)
+                    .unwrap(),
+                0
+            );
+            assert_eq!(
+                sent_tx_store
+                    .get_sent_txs(&db_txn, &monitor_id1, 0, 10)
+                    .unwrap(),
+                vec![]
+            );
+            assert_eq!(
+                sent_tx_store
+                    .get_sent_txs(&db_txn, &monitor_id2, 0, 10)
+                    .unwrap()
+                    .into_iter()
+                    .map(|(_key, sent_tx)| sent_tx)
+                    .collect::<Vec<_>>(),
+                vec![sent_txs2[1].clone(), sent_txs2[0].clone()]
+            );
+        }
+    }
+}
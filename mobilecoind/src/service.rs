@@ -7,17 +7,32 @@
 //! * writes matching transactions to a local DB, organized by subaddress_id
 
 use crate::{
+    activity_export,
     database::Database,
     error::Error,
+    fog_sync::FogSyncConfig,
+    gift_codes::{self, GiftCodeStatus},
     monitor_store::{MonitorData, MonitorId},
+    payment_request_store::{PaymentRequest, PaymentRequestKey, PaymentRequestStatus},
     payments::{Outlay, TransactionsManager, TxProposal},
+    processed_block_store::{ProcessedTxOut, ProcessedTxOutDirection},
+    replacement_store::ReplacementTx,
+    sent_tx_store::{SentTx, SentTxStatus},
+    swaps::{self, SwapQuote},
     sync::SyncThread,
     utxo_store::{UnspentTxOut, UtxoId},
+    webhook_store::Webhook,
+    webhooks::WebhookThread,
 };
 use bip39::{Language, Mnemonic, MnemonicType};
-use grpcio::{EnvBuilder, RpcContext, RpcStatus, RpcStatusCode, ServerBuilder, UnarySink};
+use futures::{executor::block_on, FutureExt, SinkExt, TryFutureExt};
+use grpcio::{
+    EnvBuilder, RpcContext, RpcStatus, RpcStatusCode, ServerBuilder, ServerStreamingSink,
+    UnarySink, WriteFlags,
+};
 use mc_account_keys::{
-    burn_address, AccountKey, PublicAddress, RootIdentity, DEFAULT_SUBADDRESS_INDEX,
+    burn_address, AccountKey, PublicAddress, RootIdentity, ShortAddressHash, ViewAccountKey,
+    DEFAULT_SUBADDRESS_INDEX,
 };
 use mc_common::{
     logger::{log, Logger},
@@ -34,15 +49,18 @@ use mc_mobilecoind_api::{
     mobilecoind_api_grpc::{create_mobilecoind_api, MobilecoindApi},
     MobilecoindUri,
 };
-use mc_transaction_builder::BurnRedemptionMemoBuilder;
+use mc_transaction_builder::{BurnRedemptionMemoBuilder, MemoBuilder, RTHMemoBuilder};
 use mc_transaction_core::{
     get_tx_out_shared_secret,
     onetime_keys::recover_onetime_private_key,
     ring_signature::KeyImage,
     tx::{TxOut, TxOutMembershipProof},
-    TokenId,
+    Amount, TokenId,
+};
+use mc_transaction_extra::{
+    BurnRedemptionMemo, HandledMemo, MemoClassification, MemoHandler, SenderMemoCredential,
+    SignedContingentInput, TxOutConfirmationNumber, TxOutGiftCode,
 };
-use mc_transaction_extra::{BurnRedemptionMemo, TxOutConfirmationNumber};
 use mc_util_from_random::FromRandom;
 use mc_util_grpc::{
     rpc_internal_error, rpc_invalid_arg_error, rpc_logger, send_result, AdminService,
@@ -50,12 +68,44 @@ use mc_util_grpc::{
 };
 use mc_watcher::watcher_db::WatcherDB;
 use protobuf::{ProtobufEnum, RepeatedField};
-use std::sync::{Arc, Mutex, RwLock};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, RwLock,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Maximum number of sent transactions returned by a single GetSentTxList call.
+const MAX_SENT_TX_LIST_LIMIT: u64 = 1000;
+
+/// Maximum number of blocks read at once when streaming monitor events.
+const MONITOR_EVENTS_BATCH_SIZE: u64 = 1000;
+
+/// Maximum number of monitor event streams served at once. Each of them is
+/// served from its own thread.
+const MAX_MONITOR_EVENT_STREAMS: usize = 100;
+
+/// How often a MonitorSynced event is repeated while a monitor does not process
+/// new blocks, so that streams whose client went away are noticed and closed.
+const MONITOR_EVENTS_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Maximum number of blocks exported by a single ExportMonitorActivity call.
+const MAX_EXPORT_MONITOR_ACTIVITY_BLOCKS: u64 = 10000;
+
+/// A MemoHandler validating authenticated sender memos against the contacts
+/// provided in a request.
+type ContactMemoHandler = MemoHandler<BTreeMap<ShortAddressHash, PublicAddress>>;
 
 pub struct Service {
     /// Sync thread.
     _sync_thread: Arc<Mutex<Option<SyncThread>>>,
 
+    /// Webhook thread.
+    _webhook_thread: Arc<Mutex<Option<WebhookThread>>>,
+
     /// GRPC server.
     _server: grpcio::Server,
 }
@@ -72,19 +122,28 @@ impl Service {
         network_state: Arc<RwLock<PollingNetworkState<T>>>,
         listen_uri: &MobilecoindUri,
         num_workers: Option<usize>,
+        fog_sync_config: Option<FogSyncConfig>,
         logger: Logger,
     ) -> Self {
-        let sync_thread = if mobilecoind_db.is_db_encrypted() {
+        let fog_enabled = fog_sync_config.is_some();
+        let (sync_thread, webhook_thread) = if mobilecoind_db.is_db_encrypted() {
             log::info!(logger, "Db encryption enabled, sync task would start once password is provided via the API.");
-            Arc::new(Mutex::new(None))
+            (Arc::new(Mutex::new(None)), Arc::new(Mutex::new(None)))
         } else {
             log::info!(logger, "Starting mobilecoind sync task thread");
-            Arc::new(Mutex::new(Some(SyncThread::start(
-                ledger_db.clone(),
-                mobilecoind_db.clone(),
-                num_workers,
-                logger.clone(),
-            ))))
+            (
+                Arc::new(Mutex::new(Some(SyncThread::start(
+                    ledger_db.clone(),
+                    mobilecoind_db.clone(),
+                    num_workers,
+                    fog_sync_config.clone(),
+                    logger.clone(),
+                )))),
+                Arc::new(Mutex::new(Some(WebhookThread::start(
+                    mobilecoind_db.clone(),
+                    logger.clone(),
+                )))),
+            )
         };
 
         let start_sync_thread = {
@@ -92,6 +151,7 @@ impl Service {
             let mobilecoind_db = mobilecoind_db.clone();
             let logger = logger.clone();
             let sync_thread = sync_thread.clone();
+            let webhook_thread = webhook_thread.clone();
             Arc::new(move || {
                 let mut sync_thread = sync_thread.lock().expect("mutex poisoned");
                 assert!(sync_thread.is_none());
@@ -100,8 +160,13 @@ impl Service {
                     ledger_db.clone(),
                     mobilecoind_db.clone(),
                     num_workers,
+                    fog_sync_config.clone(),
                     logger.clone(),
                 ));
+
+                let mut webhook_thread = webhook_thread.lock().expect("mutex poisoned");
+                *webhook_thread =
+                    Some(WebhookThread::start(mobilecoind_db.clone(), logger.clone()));
             })
         };
 
@@ -112,6 +177,7 @@ impl Service {
             watcher_db,
             network_state,
             start_sync_thread,
+            fog_enabled,
             logger.clone(),
         );
 
@@ -155,6 +221,7 @@ impl Service {
         Self {
             _server: server,
             _sync_thread: sync_thread,
+            _webhook_thread: webhook_thread,
         }
     }
 }
@@ -169,6 +236,9 @@ pub struct ServiceApi<
     watcher_db: Option<WatcherDB>,
     network_state: Arc<RwLock<PollingNetworkState<T>>>,
     start_sync_thread: Arc<dyn Fn() + Send + Sync>,
+    fog_enabled: bool,
+    /// The number of monitor event streams being served.
+    num_monitor_event_streams: Arc<AtomicUsize>,
     logger: Logger,
 }
 
@@ -183,6 +253,8 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
             watcher_db: self.watcher_db.clone(),
             network_state: self.network_state.clone(),
             start_sync_thread: self.start_sync_thread.clone(),
+            fog_enabled: self.fog_enabled,
+            num_monitor_event_streams: self.num_monitor_event_streams.clone(),
             logger: self.logger.clone(),
         }
     }
@@ -198,6 +270,7 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
         watcher_db: Option<WatcherDB>,
         network_state: Arc<RwLock<PollingNetworkState<T>>>,
         start_sync_thread: Arc<dyn Fn() + Send + Sync>,
+        fog_enabled: bool,
         logger: Logger,
     ) -> Self {
         Self {
@@ -207,6 +280,8 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
             watcher_db,
             network_state,
             start_sync_thread,
+            fog_enabled,
+            num_monitor_event_streams: Arc::new(AtomicUsize::new(0)),
             logger,
         }
     }
@@ -223,6 +298,55 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
             .collect()
     }
 
+    // Check whether a replacement transaction recorded for any of the given key
+    // images has landed in the ledger.
+    fn is_replacement_tx_in_ledger(&self, key_images: &[KeyImage]) -> Result<bool, RpcStatus> {
+        for key_image in key_images {
+            let replacement_tx =
+                self.mobilecoind_db
+                    .get_replacement_tx(key_image)
+                    .map_err(|err| {
+                        rpc_internal_error("mobilecoind_db.get_replacement_tx", err, &self.logger)
+                    })?;
+
+            if let Some(replacement_tx) = replacement_tx {
+                for public_key in replacement_tx.tx_out_public_keys.iter() {
+                    if self
+                        .ledger_db
+                        .contains_tx_out_public_key(public_key)
+                        .map_err(|err| {
+                            rpc_internal_error(
+                                "ledger_db.contains_tx_out_public_key",
+                                err,
+                                &self.logger,
+                            )
+                        })?
+                    {
+                        return Ok(true);
+                    }
+                }
+            }
+        }
+
+        Ok(false)
+    }
+
+    // Decode a base-58 encoded gift code.
+    fn parse_gift_code(&self, b58_code: &str) -> Result<TxOutGiftCode, RpcStatus> {
+        let wrapper = api::printable::PrintableWrapper::b58_decode(b58_code.to_string())
+            .map_err(|err| rpc_internal_error("PrintableWrapper.b58_decode", err, &self.logger))?;
+
+        if !wrapper.has_tx_out_gift_code() {
+            return Err(RpcStatus::with_message(
+                RpcStatusCode::INVALID_ARGUMENT,
+                "has_tx_out_gift_code".into(),
+            ));
+        }
+
+        TxOutGiftCode::try_from(wrapper.get_tx_out_gift_code())
+            .map_err(|err| rpc_internal_error("tx_out_gift_code.try_from", err, &self.logger))
+    }
+
     fn get_version_impl(
         &self,
         _request: api::Empty,
@@ -236,27 +360,89 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
         &mut self,
         request: api::AddMonitorRequest,
     ) -> Result<api::AddMonitorResponse, RpcStatus> {
-        // Get the AccountKey from the GRPC request.
-        let proto_account_key = request.account_key.as_ref().ok_or_else(|| {
-            RpcStatus::with_message(RpcStatusCode::INVALID_ARGUMENT, "account_key".into())
-        })?;
-        let account_key = AccountKey::try_from(proto_account_key)
-            .map_err(|err| rpc_internal_error("account_key.try_from", err, &self.logger))?;
+        // Populate a new `MonitorData` instance, from either the AccountKey or the
+        // ViewAccountKey in the GRPC request.
+        let mut data = match (
+            request.account_key.as_ref(),
+            request.view_account_key.as_ref(),
+        ) {
+            (Some(proto_account_key), None) if request.fog => {
+                if !self.fog_enabled {
+                    return Err(rpc_invalid_arg_error(
+                        "fog",
+                        Error::FogNotConfigured,
+                        &self.logger,
+                    ));
+                }
+                if request.gap_limit != 0 {
+                    return Err(RpcStatus::with_message(
+                        RpcStatusCode::INVALID_ARGUMENT,
+                        "gap_limit".into(),
+                    ));
+                }
 
-        // Populate a new `MonitorData` instance.
-        let data = MonitorData::new(
-            account_key,
-            request.first_subaddress,
-            request.num_subaddresses,
-            request.first_block,
-            &request.name,
-        )
-        .map_err(|err| rpc_internal_error("monitor_data.new", err, &self.logger))?;
+                let account_key = AccountKey::try_from(proto_account_key)
+                    .map_err(|err| rpc_internal_error("account_key.try_from", err, &self.logger))?;
+
+                MonitorData::new_fog(
+                    account_key,
+                    request.first_subaddress,
+                    request.num_subaddresses,
+                    request.first_block,
+                    &request.name,
+                )
+                .map_err(|err| rpc_invalid_arg_error("monitor_data.new_fog", err, &self.logger))?
+            }
+            (Some(proto_account_key), None) => {
+                let account_key = AccountKey::try_from(proto_account_key)
+                    .map_err(|err| rpc_internal_error("account_key.try_from", err, &self.logger))?;
+
+                MonitorData::new(
+                    account_key,
+                    request.first_subaddress,
+                    request.num_subaddresses,
+                    request.first_block,
+                    &request.name,
+                )
+                .map_err(|err| rpc_internal_error("monitor_data.new", err, &self.logger))?
+            }
+            // Fog monitors need the spend private key to search fog view.
+            (None, Some(_)) if request.fog => {
+                return Err(RpcStatus::with_message(
+                    RpcStatusCode::INVALID_ARGUMENT,
+                    "view_account_key".into(),
+                ));
+            }
+            (None, Some(proto_view_account_key)) => {
+                let view_account_key =
+                    ViewAccountKey::try_from(proto_view_account_key).map_err(|err| {
+                        rpc_internal_error("view_account_key.try_from", err, &self.logger)
+                    })?;
+
+                MonitorData::new_view_only(
+                    view_account_key,
+                    request.first_subaddress,
+                    request.num_subaddresses,
+                    request.first_block,
+                    &request.name,
+                )
+                .map_err(|err| {
+                    rpc_internal_error("monitor_data.new_view_only", err, &self.logger)
+                })?
+            }
+            _ => {
+                return Err(RpcStatus::with_message(
+                    RpcStatusCode::INVALID_ARGUMENT,
+                    "account_key".into(),
+                ));
+            }
+        };
+        data.gap_limit = request.gap_limit;
 
         // Insert into database. Return the id and flag if the monitor already existed.
         let (id, is_new) = match self.mobilecoind_db.add_monitor(&data) {
             Ok(id) => Ok((id, true)),
-            Err(Error::MonitorIdExists) => Ok((MonitorId::from(&data), false)),
+            Err(Error::MonitorIdExists) => MonitorId::try_from(&data).map(|id| (id, false)),
             Err(err) => Err(err),
         }
         .map_err(|err| rpc_internal_error("mobilecoind_db.add_monitor", err, &self.logger))?;
@@ -319,11 +505,30 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
             })?;
 
         let mut status = api::MonitorStatus::new();
-        status.set_account_key(mc_api::external::AccountKey::from(&data.account_key));
+        if let Some(account_key) = data.account_key.as_ref() {
+            status.set_account_key(mc_api::external::AccountKey::from(account_key));
+        }
+        if let Some(view_account_key) = data.view_account_key.as_ref() {
+            status.set_view_account_key(api::ViewAccountKey::from(view_account_key));
+        }
         status.set_first_subaddress(data.first_subaddress);
         status.set_num_subaddresses(data.num_subaddresses);
         status.set_first_block(data.first_block);
         status.set_next_block(data.next_block);
+        status.set_gap_limit(data.gap_limit);
+        status.set_is_rescanning(data.is_rescanning());
+        status.set_rescan_end_block(data.rescan_end_block);
+        status.set_fog(data.is_fog());
+
+        let webhook = self
+            .mobilecoind_db
+            .get_monitor_webhook(&monitor_id)
+            .map_err(|err| {
+                rpc_internal_error("mobilecoind_db.get_monitor_webhook", err, &self.logger)
+            })?;
+        if let Some(webhook) = webhook {
+            status.set_webhook_url(webhook.url);
+        }
 
         let mut response = api::GetMonitorStatusResponse::new();
         response.set_status(status);
@@ -361,6 +566,163 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
         Ok(response)
     }
 
+    fn import_key_images_impl(
+        &mut self,
+        request: api::ImportKeyImagesRequest,
+    ) -> Result<api::ImportKeyImagesResponse, RpcStatus> {
+        // Get MonitorId from from the GRPC request.
+        let monitor_id = MonitorId::try_from(&request.monitor_id)
+            .map_err(|err| rpc_internal_error("monitor_id.try_from.bytes", err, &self.logger))?;
+
+        // Key images are only imported into view-only monitors, other monitors compute
+        // them on their own.
+        let monitor_data = self
+            .mobilecoind_db
+            .get_monitor_data(&monitor_id)
+            .map_err(|err| {
+                rpc_internal_error("mobilecoind_db.get_monitor_data", err, &self.logger)
+            })?;
+        if !monitor_data.is_view_only() {
+            return Err(RpcStatus::with_message(
+                RpcStatusCode::INVALID_ARGUMENT,
+                "monitor_id".into(),
+            ));
+        }
+
+        // Get the key images.
+        let key_images = request
+            .get_key_image_list()
+            .iter()
+            .enumerate()
+            .map(|(i, proto_key_image)| {
+                let tx_public_key =
+                    CompressedRistrettoPublic::try_from(proto_key_image.get_tx_public_key())
+                        .map_err(|_| {
+                            RpcStatus::with_message(
+                                RpcStatusCode::INVALID_ARGUMENT,
+                                format!("key_image_list[{}].tx_public_key", i),
+                            )
+                        })?;
+                let key_image =
+                    KeyImage::try_from(proto_key_image.get_key_image()).map_err(|_| {
+                        RpcStatus::with_message(
+                            RpcStatusCode::INVALID_ARGUMENT,
+                            format!("key_image_list[{}].key_image", i),
+                        )
+                    })?;
+                Ok((tx_public_key, key_image))
+            })
+            .collect::<Result<Vec<_>, RpcStatus>>()?;
+
+        // Import them.
+        let (num_imported, spent_utxos) = self
+            .mobilecoind_db
+            .import_key_images(&monitor_id, &key_images, &self.ledger_db)
+            .map_err(|err| match err {
+                Error::InvalidArgument(..) => {
+                    rpc_invalid_arg_error("key_images", err, &self.logger)
+                }
+                err => rpc_internal_error("mobilecoind_db.import_key_images", err, &self.logger),
+            })?;
+
+        // Return response.
+        let mut response = api::ImportKeyImagesResponse::new();
+        response.set_num_imported(num_imported as u64);
+        response.set_spent_output_list(RepeatedField::from_vec(
+            spent_utxos.iter().map(|utxo| utxo.into()).collect(),
+        ));
+        Ok(response)
+    }
+
+    fn set_monitor_webhook_impl(
+        &mut self,
+        request: api::SetMonitorWebhookRequest,
+    ) -> Result<api::Empty, RpcStatus> {
+        // Get MonitorId from from the GRPC request.
+        let monitor_id = MonitorId::try_from(&request.monitor_id)
+            .map_err(|err| rpc_internal_error("monitor_id.try_from.bytes", err, &self.logger))?;
+
+        // An empty url removes the webhook.
+        let webhook = if request.url.is_empty() {
+            None
+        } else {
+            reqwest::Url::parse(&request.url).map_err(|_| {
+                RpcStatus::with_message(RpcStatusCode::INVALID_ARGUMENT, "url".into())
+            })?;
+            Some(Webhook {
+                url: request.url,
+                secret: request.secret,
+            })
+        };
+
+        self.mobilecoind_db
+            .set_monitor_webhook(&monitor_id, webhook.as_ref())
+            .map_err(|err| {
+                rpc_internal_error("mobilecoind_db.set_monitor_webhook", err, &self.logger)
+            })?;
+
+        Ok(api::Empty::new())
+    }
+
+    fn rescan_monitor_impl(
+        &mut self,
+        request: api::RescanMonitorRequest,
+    ) -> Result<api::Empty, RpcStatus> {
+        // Get MonitorId from from the GRPC request.
+        let monitor_id = MonitorId::try_from(&request.monitor_id)
+            .map_err(|err| rpc_internal_error("monitor_id.try_from.bytes", err, &self.logger))?;
+
+        let data = self
+            .mobilecoind_db
+            .get_monitor_data(&monitor_id)
+            .map_err(|err| {
+                rpc_internal_error("mobilecoind_db.get_monitor_data", err, &self.logger)
+            })?;
+
+        // The subaddress range is kept as is.
+        self.mobilecoind_db
+            .rescan_monitor(
+                &monitor_id,
+                data.first_subaddress,
+                data.num_subaddresses,
+                request.first_block,
+            )
+            .map_err(|err| {
+                rpc_internal_error("mobilecoind_db.rescan_monitor", err, &self.logger)
+            })?;
+
+        Ok(api::Empty::new())
+    }
+
+    fn update_monitor_impl(
+        &mut self,
+        request: api::UpdateMonitorRequest,
+    ) -> Result<api::Empty, RpcStatus> {
+        // Get MonitorId from from the GRPC request.
+        let monitor_id = MonitorId::try_from(&request.monitor_id)
+            .map_err(|err| rpc_internal_error("monitor_id.try_from.bytes", err, &self.logger))?;
+
+        if request.num_subaddresses == 0 {
+            return Err(RpcStatus::with_message(
+                RpcStatusCode::INVALID_ARGUMENT,
+                "num_subaddresses".into(),
+            ));
+        }
+
+        self.mobilecoind_db
+            .rescan_monitor(
+                &monitor_id,
+                request.first_subaddress,
+                request.num_subaddresses,
+                request.first_block,
+            )
+            .map_err(|err| {
+                rpc_internal_error("mobilecoind_db.rescan_monitor", err, &self.logger)
+            })?;
+
+        Ok(api::Empty::new())
+    }
+
     fn generate_root_entropy_impl(
         &mut self,
         _request: api::Empty,
@@ -451,7 +813,9 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
         }
 
         // Get the subaddress.
-        let subaddress = data.account_key.subaddress(request.subaddress_index);
+        let subaddress = data
+            .subaddress(request.subaddress_index)
+            .map_err(|err| rpc_internal_error("monitor_data.subaddress", err, &self.logger))?;
 
         // Also build the b58 wrapper
         let mut wrapper = api::printable::PrintableWrapper::new();
@@ -487,6 +851,7 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
             response.set_value(payment_request.get_value());
             response.set_memo(payment_request.get_memo().to_string());
             response.set_token_id(payment_request.get_token_id());
+            response.set_payment_id(payment_request.get_payment_id());
             Ok(response)
         } else if wrapper.has_public_address() {
             let public_address = wrapper.get_public_address();
@@ -515,6 +880,7 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
         payment_request.set_value(request.get_value());
         payment_request.set_memo(request.get_memo().to_string());
         payment_request.set_token_id(request.get_token_id());
+        payment_request.set_payment_id(request.get_payment_id());
 
         let mut wrapper = api::printable::PrintableWrapper::new();
         wrapper.set_payment_request(payment_request);
@@ -706,6 +1072,7 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
             let payment_request = wrapper.get_payment_request();
             let mut response = api::ParseAddressCodeResponse::new();
             response.set_receiver(payment_request.get_public_address().clone());
+            response.set_payment_id(payment_request.get_payment_id());
             Ok(response)
         } else if wrapper.has_public_address() {
             let public_address = wrapper.get_public_address();
@@ -739,6 +1106,174 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
         Ok(response)
     }
 
+    fn create_payment_request_impl(
+        &mut self,
+        request: api::CreatePaymentRequestRequest,
+    ) -> Result<api::CreatePaymentRequestResponse, RpcStatus> {
+        let monitor_id = MonitorId::try_from(&request.monitor_id)
+            .map_err(|err| rpc_internal_error("monitor_id.try_from.bytes", err, &self.logger))?;
+
+        if request.value == 0 {
+            return Err(RpcStatus::with_message(
+                RpcStatusCode::INVALID_ARGUMENT,
+                "value".into(),
+            ));
+        }
+
+        let payment_request = PaymentRequest {
+            subaddress_index: request.subaddress_index,
+            value: request.value,
+            token_id: request.token_id,
+            memo: request.get_memo().to_string(),
+            expiry_block: request.expiry_block,
+            created_block: 0,
+            payments: Vec::new(),
+        };
+
+        let (key, payment_request) = self
+            .mobilecoind_db
+            .add_payment_request(&monitor_id, &payment_request)
+            .map_err(|err| match err {
+                Error::InvalidArgument(..) => {
+                    rpc_invalid_arg_error("subaddress_index", err, &self.logger)
+                }
+                err => rpc_internal_error("mobilecoind_db.add_payment_request", err, &self.logger),
+            })?;
+        let next_block = payment_request.created_block;
+
+        let mut response = api::CreatePaymentRequestResponse::new();
+        response.set_payment_request(self.payment_request_to_proto(
+            &key,
+            &payment_request,
+            next_block,
+        )?);
+        Ok(response)
+    }
+
+    fn get_payment_request_impl(
+        &mut self,
+        request: api::GetPaymentRequestRequest,
+    ) -> Result<api::GetPaymentRequestResponse, RpcStatus> {
+        let monitor_id = MonitorId::try_from(&request.monitor_id)
+            .map_err(|err| rpc_internal_error("monitor_id.try_from.bytes", err, &self.logger))?;
+
+        let key = PaymentRequestKey::new(&monitor_id, request.payment_request_id);
+        let (next_block, payment_request) =
+            self.mobilecoind_db
+                .get_payment_request(&key)
+                .map_err(|err| match err {
+                    Error::PaymentRequestNotFound => {
+                        rpc_invalid_arg_error("payment_request_id", err, &self.logger)
+                    }
+                    err => {
+                        rpc_internal_error("mobilecoind_db.get_payment_request", err, &self.logger)
+                    }
+                })?;
+
+        let mut response = api::GetPaymentRequestResponse::new();
+        response.set_payment_request(self.payment_request_to_proto(
+            &key,
+            &payment_request,
+            next_block,
+        )?);
+        Ok(response)
+    }
+
+    fn get_payment_request_list_impl(
+        &mut self,
+        request: api::GetPaymentRequestListRequest,
+    ) -> Result<api::GetPaymentRequestListResponse, RpcStatus> {
+        let monitor_id = MonitorId::try_from(&request.monitor_id)
+            .map_err(|err| rpc_internal_error("monitor_id.try_from.bytes", err, &self.logger))?;
+
+        let (next_block, payment_requests) = self
+            .mobilecoind_db
+            .get_payment_requests(&monitor_id)
+            .map_err(|err| {
+                rpc_internal_error("mobilecoind_db.get_payment_requests", err, &self.logger)
+            })?;
+
+        let payment_request_list = payment_requests
+            .iter()
+            .map(|(key, payment_request)| {
+                self.payment_request_to_proto(key, payment_request, next_block)
+            })
+            .collect::<Result<Vec<_>, RpcStatus>>()?;
+
+        let mut response = api::GetPaymentRequestListResponse::new();
+        response.set_payment_request_list(RepeatedField::from_vec(payment_request_list));
+        Ok(response)
+    }
+
+    /// Convert a payment request of a monitor into its API representation,
+    /// including the request code payers are handed.
+    fn payment_request_to_proto(
+        &self,
+        key: &PaymentRequestKey,
+        src: &PaymentRequest,
+        next_block: u64,
+    ) -> Result<api::TrackedPaymentRequest, RpcStatus> {
+        let monitor_data = self
+            .mobilecoind_db
+            .get_monitor_data(&key.monitor_id)
+            .map_err(|err| {
+                rpc_internal_error("mobilecoind_db.get_monitor_data", err, &self.logger)
+            })?;
+        let subaddress = monitor_data
+            .subaddress(src.subaddress_index)
+            .map_err(|err| rpc_internal_error("monitor_data.subaddress", err, &self.logger))?;
+
+        let mut payment_request = api::printable::PaymentRequest::new();
+        payment_request.set_public_address((&subaddress).into());
+        payment_request.set_value(src.value);
+        payment_request.set_memo(src.memo.clone());
+        payment_request.set_token_id(src.token_id);
+        payment_request.set_payment_id(key.id);
+
+        let mut wrapper = api::printable::PrintableWrapper::new();
+        wrapper.set_payment_request(payment_request);
+        let encoded = wrapper
+            .b58_encode()
+            .map_err(|err| rpc_internal_error("b58_encode", err, &self.logger))?;
+
+        // The paid value may exceed the requested one, in which case it is capped
+        // so that it fits into a u64.
+        let paid_value = src.paid_value().min(u64::MAX as u128) as u64;
+
+        let mut dst = api::TrackedPaymentRequest::new();
+        dst.set_id(key.id);
+        dst.set_monitor_id(key.monitor_id.to_vec());
+        dst.set_subaddress_index(src.subaddress_index);
+        dst.set_value(src.value);
+        dst.set_token_id(src.token_id);
+        dst.set_memo(src.memo.clone());
+        dst.set_expiry_block(src.expiry_block);
+        dst.set_created_block(src.created_block);
+        dst.set_payment_list(RepeatedField::from_vec(
+            src.payments
+                .iter()
+                .map(|payment| {
+                    let mut dst = api::PaymentRequestPayment::new();
+                    dst.set_block_index(payment.block_index);
+                    dst.set_tx_public_key((&payment.tx_out_public_key).into());
+                    dst.set_value(payment.value);
+                    dst
+                })
+                .collect(),
+        ));
+        dst.set_paid_value(paid_value);
+        dst.set_status(match src.status(next_block) {
+            PaymentRequestStatus::Pending => api::PaymentRequestStatus::PaymentRequestPending,
+            PaymentRequestStatus::PartiallyPaid => {
+                api::PaymentRequestStatus::PaymentRequestPartiallyPaid
+            }
+            PaymentRequestStatus::Paid => api::PaymentRequestStatus::PaymentRequestPaid,
+            PaymentRequestStatus::Expired => api::PaymentRequestStatus::PaymentRequestExpired,
+        });
+        dst.set_b58_code(encoded);
+        Ok(dst)
+    }
+
     /// Get mixins
     fn get_mixins_impl(
         &mut self,
@@ -895,6 +1430,34 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
             })
             .collect::<Result<Vec<Outlay>, RpcStatus>>()?;
 
+        let mut response = api::GenerateTxResponse::new();
+
+        // View-only monitors cannot sign, so they get an unsigned transaction.
+        if sender_monitor_data.is_view_only() {
+            let unsigned_tx_proposal = self
+                .transactions_manager
+                .build_unsigned_transaction(
+                    &sender_monitor_id,
+                    TokenId::from(request.token_id),
+                    request.change_subaddress,
+                    &input_list,
+                    &outlays,
+                    &self.get_last_block_infos(),
+                    request.fee,
+                    request.tombstone,
+                )
+                .map_err(|err| {
+                    rpc_internal_error(
+                        "transactions_manager.build_unsigned_transaction",
+                        err,
+                        &self.logger,
+                    )
+                })?;
+
+            response.set_unsigned_tx_proposal((&unsigned_tx_proposal).into());
+            return Ok(response);
+        }
+
         // Attempt to construct a transaction.
         let tx_proposal = self
             .transactions_manager
@@ -914,7 +1477,6 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
             })?;
 
         // Success.
-        let mut response = api::GenerateTxResponse::new();
         response.set_tx_proposal((&tx_proposal).into());
         Ok(response)
     }
@@ -1126,1250 +1688,3519 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
         Ok(response)
     }
 
-    fn generate_transfer_code_tx_impl(
+    fn generate_replacement_tx_impl(
         &mut self,
-        request: api::GenerateTransferCodeTxRequest,
-    ) -> Result<api::GenerateTransferCodeTxResponse, RpcStatus> {
-        // Generate entropy.
-        let mnemonic_response = self.generate_mnemonic_impl(api::Empty::new())?;
-        let mnemonic_str = mnemonic_response.get_mnemonic().to_string();
-        let bip39_entropy = mnemonic_response.get_bip39_entropy();
+        request: api::GenerateReplacementTxRequest,
+    ) -> Result<api::GenerateReplacementTxResponse, RpcStatus> {
+        // Get sender monitor id from request.
+        let sender_monitor_id = MonitorId::try_from(&request.sender_monitor_id)
+            .map_err(|err| rpc_internal_error("monitor_id.try_from.bytes", err, &self.logger))?;
 
-        // Generate a new account using this mnemonic.
-        let mut account_key_request = api::GetAccountKeyFromMnemonicRequest::new();
-        account_key_request.set_mnemonic(mnemonic_str);
-
-        let account_key_response = self.get_account_key_from_mnemonic_impl(account_key_request)?;
-        let account_key = AccountKey::try_from(account_key_response.get_account_key())
-            .map_err(|err| rpc_internal_error("account_key.try_from", err, &self.logger))?;
+        // Get monitor data for this monitor.
+        let sender_monitor_data = self
+            .mobilecoind_db
+            .get_monitor_data(&sender_monitor_id)
+            .map_err(|err| {
+                rpc_internal_error("mobilecoind_db.get_monitor_data", err, &self.logger)
+            })?;
 
-        // The outlay we are sending the money to.
-        let outlay = Outlay {
-            receiver: account_key.default_subaddress(),
-            value: request.value,
-        };
+        // Check that change_subaddress is covered by this monitor.
+        if !sender_monitor_data
+            .subaddress_indexes()
+            .contains(&request.change_subaddress)
+        {
+            return Err(RpcStatus::with_message(
+                RpcStatusCode::INVALID_ARGUMENT,
+                "change_subaddress".into(),
+            ));
+        }
 
-        // Generate transaction.
-        let mut generate_tx_request = api::GenerateTxRequest::new();
-        generate_tx_request.set_sender_monitor_id(request.get_sender_monitor_id().to_vec());
-        generate_tx_request.set_change_subaddress(request.change_subaddress);
-        generate_tx_request.set_input_list(RepeatedField::from_vec(request.input_list.to_vec()));
-        generate_tx_request.set_outlay_list(RepeatedField::from_vec(vec![(&outlay).into()]));
-        generate_tx_request.set_fee(request.fee);
-        generate_tx_request.set_tombstone(request.tombstone);
-        generate_tx_request.set_token_id(request.token_id);
+        // Get the pending TxProposal from the request.
+        let tx_proposal = TxProposal::try_from(request.get_tx_proposal())
+            .map_err(|err| rpc_internal_error("tx_proposal.try_from", err, &self.logger))?;
 
-        let mut generate_tx_response = self.generate_tx_impl(generate_tx_request)?;
-        let tx_proposal = generate_tx_response.take_tx_proposal();
+        // Verify the inputs of the pending transaction belong to the monitor.
+        for (i, utxo) in tx_proposal.utxos.iter().enumerate() {
+            let subaddress_id = self
+                .mobilecoind_db
+                .get_subaddress_id_by_utxo_id(&UtxoId::from(utxo))
+                .map_err(|err| {
+                    rpc_internal_error(
+                        "mobilecoind_db.get_subaddress_id_by_utxo_id",
+                        err,
+                        &self.logger,
+                    )
+                })?;
 
-        // Grab the public key of the relevant tx out.
-        let proto_tx_public_key = {
-            // We expect only a single outlay.
-            if tx_proposal.get_outlay_index_to_tx_out_index().len() != 1 {
+            if subaddress_id.monitor_id != sender_monitor_id {
                 return Err(RpcStatus::with_message(
-                    RpcStatusCode::INTERNAL,
-                    format!(
-                        "outlay_index_to_tx_out_index contains {} elements, was expecting 1",
-                        tx_proposal.get_outlay_index_to_tx_out_index().len()
-                    ),
+                    RpcStatusCode::INVALID_ARGUMENT,
+                    format!("tx_proposal.input_list[{}].monitor_id", i),
                 ));
             }
+        }
 
-            // Get the TxOut index of our single outlay.
-            let tx_out_index = tx_proposal
-                .get_outlay_index_to_tx_out_index()
-                .get(&0)
-                .ok_or_else(|| {
-                    RpcStatus::with_message(
-                        RpcStatusCode::INTERNAL,
-                        "outlay_index_to_tx_out_index doesn't contain index 0".to_owned(),
-                    )
-                })?;
-
-            // Get the TxOut
-            let tx_out = tx_proposal
-                .get_tx()
-                .get_prefix()
-                .get_outputs()
-                .get(*tx_out_index as usize)
-                .ok_or_else(|| {
-                    RpcStatus::with_message(
-                        RpcStatusCode::INTERNAL,
-                        format!("tx out index {} not found", tx_out_index),
-                    )
-                })?;
+        // Attempt to construct the replacement transaction.
+        let replacement_tx_proposal = self
+            .transactions_manager
+            .generate_replacement_tx(
+                &sender_monitor_id,
+                &tx_proposal,
+                request.change_subaddress,
+                &self.get_last_block_infos(),
+                request.fee,
+                request.tombstone,
+            )
+            .map_err(|err| {
+                rpc_internal_error(
+                    "transactions_manager.generate_replacement_tx",
+                    err,
+                    &self.logger,
+                )
+            })?;
 
-            // Get the public key
-            tx_out.get_public_key().clone()
+        // Remember the replacement so that GetTxStatusAsSender can tell which of the
+        // two transactions landed.
+        let key_images: Vec<KeyImage> = replacement_tx_proposal
+            .utxos
+            .iter()
+            .map(|utxo| utxo.key_image)
+            .collect();
+        let replacement_tx = ReplacementTx {
+            tx_out_public_keys: replacement_tx_proposal
+                .tx
+                .prefix
+                .outputs
+                .iter()
+                .map(|tx_out| tx_out.public_key)
+                .collect(),
+            tombstone_block: replacement_tx_proposal.tx.prefix.tombstone_block,
+            fee: replacement_tx_proposal.fee(),
+            replaced_fee: tx_proposal.fee(),
         };
+        self.mobilecoind_db
+            .add_replacement_tx(&key_images, &replacement_tx)
+            .map_err(|err| {
+                rpc_internal_error("mobilecoind_db.add_replacement_tx", err, &self.logger)
+            })?;
 
-        let tx_public_key = RistrettoPublic::try_from(&proto_tx_public_key)
-            .map_err(|err| rpc_internal_error("ristretto_public.try_from", err, &self.logger))?;
-
-        let mut transfer_payload = api::printable::TransferPayload::new();
-        transfer_payload.set_bip39_entropy(bip39_entropy.to_vec());
-        transfer_payload.set_tx_out_public_key((&tx_public_key).into());
-        transfer_payload.set_memo(request.get_memo().to_string());
-
-        let mut transfer_wrapper = api::printable::PrintableWrapper::new();
-        transfer_wrapper.set_transfer_payload(transfer_payload);
-
-        let b58_code = transfer_wrapper
-            .b58_encode()
-            .map_err(|err| rpc_internal_error("b58_encode", err, &self.logger))?;
-
-        // Construct response.
-        let mut response = api::GenerateTransferCodeTxResponse::new();
-        response.set_tx_proposal(tx_proposal);
-        response.set_bip39_entropy(bip39_entropy.to_vec());
-        response.set_tx_public_key(proto_tx_public_key);
-        response.set_memo(request.get_memo().to_string());
-        response.set_b58_code(b58_code);
+        // Success.
+        let mut response = api::GenerateReplacementTxResponse::new();
+        response.set_tx_proposal((&replacement_tx_proposal).into());
         Ok(response)
     }
 
-    fn submit_tx_impl(
+    fn generate_swap_impl(
         &mut self,
-        request: api::SubmitTxRequest,
-    ) -> Result<api::SubmitTxResponse, RpcStatus> {
-        // Get TxProposal from request.
-        let tx_proposal = TxProposal::try_from(request.get_tx_proposal())
-            .map_err(|err| rpc_internal_error("tx_proposal.try_from", err, &self.logger))?;
+        request: api::GenerateSwapRequest,
+    ) -> Result<api::GenerateSwapResponse, RpcStatus> {
+        // Get sender monitor id from request.
+        let sender_monitor_id = MonitorId::try_from(&request.sender_monitor_id)
+            .map_err(|err| rpc_internal_error("monitor_id.try_from.bytes", err, &self.logger))?;
 
-        // Submit to network.
-        let block_height = self
-            .transactions_manager
-            .submit_tx_proposal(&tx_proposal)
+        // Get monitor data for this monitor.
+        let sender_monitor_data = self
+            .mobilecoind_db
+            .get_monitor_data(&sender_monitor_id)
             .map_err(|err| {
-                rpc_internal_error("transactions_manager.submit_tx_proposal", err, &self.logger)
+                rpc_internal_error("mobilecoind_db.get_monitor_data", err, &self.logger)
             })?;
 
-        // Update the attempted spend block height in db. Note that we swallow the error
-        // here since our transaction did get sent to the network, and its
-        // better to have the user attempt a double spend by having stale
-        // UnspentTxOut data than having them not be aware that the transaction
-        // was submitted.
-        let utxo_ids: Vec<UtxoId> = tx_proposal.utxos.iter().map(UtxoId::from).collect();
-        if let Err(err) = self.mobilecoind_db.update_attempted_spend(
-            &utxo_ids,
-            block_height,
-            tx_proposal.tx.prefix.tombstone_block,
-        ) {
-            log::error!(
-                self.logger,
-                "failed updating attempted_spend_height after submitting tx {}: {:?}",
-                tx_proposal.tx,
-                err
-            );
+        // Check that change_subaddress is covered by this monitor.
+        if !sender_monitor_data
+            .subaddress_indexes()
+            .contains(&request.change_subaddress)
+        {
+            return Err(RpcStatus::with_message(
+                RpcStatusCode::INVALID_ARGUMENT,
+                "change_subaddress".into(),
+            ));
         }
 
-        // Construct sender receipt.
-        let mut sender_tx_receipt = api::SenderTxReceipt::new();
-        sender_tx_receipt.set_key_image_list(RepeatedField::from_vec(
-            tx_proposal
-                .utxos
-                .iter()
-                .map(|utxo| (&utxo.key_image).into())
-                .collect(),
-        ));
-        sender_tx_receipt.set_tombstone(tx_proposal.tx.prefix.tombstone_block);
+        // Get the UnspentTxOut being offered, and verify it belongs to the monitor.
+        let utxo = UnspentTxOut::try_from(request.get_utxo())
+            .map_err(|err| rpc_internal_error("unspent_tx_out.try_from", err, &self.logger))?;
+        let subaddress_id = self
+            .mobilecoind_db
+            .get_subaddress_id_by_utxo_id(&UtxoId::from(&utxo))
+            .map_err(|err| {
+                rpc_internal_error(
+                    "mobilecoind_db.get_subaddress_id_by_utxo_id",
+                    err,
+                    &self.logger,
+                )
+            })?;
+        if subaddress_id.monitor_id != sender_monitor_id {
+            return Err(RpcStatus::with_message(
+                RpcStatusCode::INVALID_ARGUMENT,
+                "utxo.monitor_id".into(),
+            ));
+        }
 
-        // Construct receiver receipts.
-        let receiver_tx_receipts: Vec<_> = tx_proposal
-            .outlays
-            .iter()
-            .enumerate()
-            .map(|(outlay_index, outlay)| {
-                let tx_out_index = tx_proposal
-                    .outlay_index_to_tx_out_index
-                    .get(&outlay_index)
-                    .ok_or_else(|| {
-                        RpcStatus::with_message(
-                            RpcStatusCode::INVALID_ARGUMENT,
-                            "outlay_index_to_tx_out_index".into(),
-                        )
-                    })?;
+        // Attempt to construct the offer.
+        let sci = self
+            .transactions_manager
+            .generate_swap(
+                &sender_monitor_id,
+                request.change_subaddress,
+                &utxo,
+                request.base_value,
+                Amount::new(
+                    request.counter_value,
+                    TokenId::from(request.counter_token_id),
+                ),
+                request.allow_partial_fill,
+                request.min_fill_value,
+                &self.get_last_block_infos(),
+                request.tombstone,
+            )
+            .map_err(|err| {
+                rpc_internal_error("transactions_manager.generate_swap", err, &self.logger)
+            })?;
 
-                let tx_out = tx_proposal
-                    .tx
-                    .prefix
-                    .outputs
-                    .get(*tx_out_index)
-                    .ok_or_else(|| {
-                        RpcStatus::with_message(
-                            RpcStatusCode::INVALID_ARGUMENT,
-                            "outlay_index_to_tx_out_index".into(),
-                        )
-                    })?;
+        let quote = SwapQuote::from_sci(&sci)
+            .map_err(|err| rpc_internal_error("swap_quote.from_sci", err, &self.logger))?;
 
-                let mut receiver_tx_receipt = api::ReceiverTxReceipt::new();
-                receiver_tx_receipt.set_recipient((&outlay.receiver).into());
-                receiver_tx_receipt.set_tx_public_key((&tx_out.public_key).into());
-                receiver_tx_receipt.set_tx_out_hash(tx_out.hash().to_vec());
-                receiver_tx_receipt.set_tombstone(tx_proposal.tx.prefix.tombstone_block);
+        // Success.
+        let mut response = api::GenerateSwapResponse::new();
+        response.set_sci((&sci).into());
+        response.set_quote((&quote).into());
+        Ok(response)
+    }
 
-                if tx_proposal.outlay_confirmation_numbers.len() > outlay_index {
-                    receiver_tx_receipt.set_confirmation_number(
-                        tx_proposal.outlay_confirmation_numbers[outlay_index].to_vec(),
-                    );
-                }
+    fn get_swap_fills_impl(
+        &mut self,
+        request: api::GetSwapFillsRequest,
+    ) -> Result<api::GetSwapFillsResponse, RpcStatus> {
+        // Validate each offer against the ledger. Invalid offers are reported, but
+        // are not an error.
+        let mut offer_list = Vec::new();
+        let mut valid_offers = Vec::new();
+        for (i, proto_sci) in request.get_sci_list().iter().enumerate() {
+            let result = SignedContingentInput::try_from(proto_sci)
+                .map_err(|err| err.to_string())
+                .and_then(|sci| {
+                    swaps::validate_sci(&sci, &self.ledger_db).map_err(|err| err.to_string())
+                });
 
-                Ok(receiver_tx_receipt)
-            })
-            .collect::<Result<Vec<api::ReceiverTxReceipt>, RpcStatus>>()?;
+            let mut offer = api::SwapOffer::new();
+            match result {
+                Ok(quote) => {
+                    offer.set_quote((&quote).into());
+                    valid_offers.push((i, quote));
+                }
+                Err(err) => {
+                    log::debug!(self.logger, "Swap offer {} is not valid: {}", i, err);
+                    offer.set_error(err);
+                }
+            }
+            offer_list.push(offer);
+        }
 
-        // Return response.
-        let mut response = api::SubmitTxResponse::new();
-        response.set_sender_tx_receipt(sender_tx_receipt);
-        response.set_receiver_tx_receipt_list(RepeatedField::from_vec(receiver_tx_receipts));
+        let quotes: Vec<SwapQuote> = valid_offers
+            .iter()
+            .map(|(_, quote)| quote.clone())
+            .collect();
+        let fill_list: Vec<api::SwapFill> = swaps::compute_best_fills(
+            &quotes,
+            TokenId::from(request.base_token_id),
+            TokenId::from(request.counter_token_id),
+            request.base_value,
+        )
+        .into_iter()
+        .map(|mut fill| {
+            // Refer to the offer by its index in the request.
+            fill.index = valid_offers[fill.index].0;
+            api::SwapFill::from(&fill)
+        })
+        .collect();
+
+        let mut response = api::GetSwapFillsResponse::new();
+        response.set_offer_list(RepeatedField::from_vec(offer_list));
+        response.set_fill_list(RepeatedField::from_vec(fill_list));
         Ok(response)
     }
 
-    fn get_ledger_info_impl(
+    fn generate_swap_fill_tx_impl(
         &mut self,
-        _request: api::Empty,
-    ) -> Result<api::GetLedgerInfoResponse, RpcStatus> {
-        let num_blocks = self
-            .ledger_db
-            .num_blocks()
-            .map_err(|err| rpc_internal_error("ledger_db.num_blocks", err, &self.logger))?;
-
-        let num_txos = self
-            .ledger_db
-            .num_txos()
-            .map_err(|err| rpc_internal_error("ledger_db.num_txos", err, &self.logger))?;
+        request: api::GenerateSwapFillTxRequest,
+    ) -> Result<api::GenerateSwapFillTxResponse, RpcStatus> {
+        // Get sender monitor id from request.
+        let sender_monitor_id = MonitorId::try_from(&request.sender_monitor_id)
+            .map_err(|err| rpc_internal_error("monitor_id.try_from.bytes", err, &self.logger))?;
 
-        let mut response = api::GetLedgerInfoResponse::new();
-        response.set_block_count(num_blocks);
-        response.set_txo_count(num_txos);
-        Ok(response)
-    }
+        // Get monitor data for this monitor.
+        let sender_monitor_data = self
+            .mobilecoind_db
+            .get_monitor_data(&sender_monitor_id)
+            .map_err(|err| {
+                rpc_internal_error("mobilecoind_db.get_monitor_data", err, &self.logger)
+            })?;
 
-    fn get_block_info_impl(
-        &mut self,
-        request: api::GetBlockInfoRequest,
-    ) -> Result<api::GetBlockInfoResponse, RpcStatus> {
-        let block_contents = self
-            .ledger_db
-            .get_block_contents(request.block)
-            .map_err(|err| rpc_internal_error("ledger_db.get_block_contents", err, &self.logger))?;
+        // Check that change_subaddress is covered by this monitor.
+        if !sender_monitor_data
+            .subaddress_indexes()
+            .contains(&request.change_subaddress)
+        {
+            return Err(RpcStatus::with_message(
+                RpcStatusCode::INVALID_ARGUMENT,
+                "change_subaddress".into(),
+            ));
+        }
 
-        let num_tx_outs = block_contents.outputs.len();
-        let num_key_images = block_contents.key_images.len();
+        // Get the offers to fill.
+        if request.get_sci_list().len() != request.get_base_value_list().len() {
+            return Err(RpcStatus::with_message(
+                RpcStatusCode::INVALID_ARGUMENT,
+                "base_value_list".into(),
+            ));
+        }
+        let fills: Vec<(SignedContingentInput, u64)> = request
+            .get_sci_list()
+            .iter()
+            .zip(request.get_base_value_list().iter())
+            .map(|(proto_sci, base_value)| {
+                let sci = SignedContingentInput::try_from(proto_sci).map_err(|err| {
+                    rpc_internal_error("signed_contingent_input.try_from", err, &self.logger)
+                })?;
+                Ok((sci, *base_value))
+            })
+            .collect::<Result<Vec<_>, RpcStatus>>()?;
 
-        // Return response.
-        let mut response = api::GetBlockInfoResponse::new();
-        response.set_key_image_count(num_key_images as u64);
-        response.set_txo_count(num_tx_outs as u64);
-        Ok(response)
-    }
+        // Get the list of potential inputs passed to.
+        let input_list: Vec<UnspentTxOut> = request
+            .get_input_list()
+            .iter()
+            .enumerate()
+            .map(|(i, proto_utxo)| {
+                // Proto -> Rust struct conversion.
+                let utxo = UnspentTxOut::try_from(proto_utxo).map_err(|err| {
+                    rpc_internal_error("unspent_tx_out.try_from", err, &self.logger)
+                })?;
 
-    fn get_block_impl(
-        &mut self,
-        request: api::GetBlockRequest,
-    ) -> Result<api::GetBlockResponse, RpcStatus> {
-        let mut response = api::GetBlockResponse::new();
+                // Verify this output belongs to the monitor.
+                let subaddress_id = self
+                    .mobilecoind_db
+                    .get_subaddress_id_by_utxo_id(&UtxoId::from(&utxo))
+                    .map_err(|err| {
+                        rpc_internal_error(
+                            "mobilecoind_db.get_subaddress_id_by_utxo_id",
+                            err,
+                            &self.logger,
+                        )
+                    })?;
 
-        let block_data = self
-            .ledger_db
-            .get_block_data(request.block)
-            .map_err(|err| rpc_internal_error("ledger_db.get_block_data", err, &self.logger))?;
+                if subaddress_id.monitor_id != sender_monitor_id {
+                    return Err(RpcStatus::with_message(
+                        RpcStatusCode::INVALID_ARGUMENT,
+                        format!("input_list.{}", i),
+                    ));
+                }
 
-        response.set_block(mc_consensus_api::blockchain::Block::from(
-            block_data.block(),
-        ));
+                // Success.
+                Ok(utxo)
+            })
+            .collect::<Result<Vec<UnspentTxOut>, RpcStatus>>()?;
 
-        for key_image in &block_data.contents().key_images {
-            response
-                .mut_key_images()
-                .push(mc_consensus_api::external::KeyImage::from(key_image));
-        }
-        for output in &block_data.contents().outputs {
-            response
-                .mut_txos()
-                .push(mc_consensus_api::external::TxOut::from(output));
-        }
+        // Attempt to construct a transaction.
+        let tx_proposal = self
+            .transactions_manager
+            .generate_swap_fill_tx(
+                &sender_monitor_id,
+                request.change_subaddress,
+                &fills,
+                &input_list,
+                &self.get_last_block_infos(),
+                request.fee,
+                request.tombstone,
+            )
+            .map_err(|err| match err {
+                Error::InvalidArgument(..) => rpc_invalid_arg_error("fills", err, &self.logger),
+                err => rpc_internal_error(
+                    "transactions_manager.generate_swap_fill_tx",
+                    err,
+                    &self.logger,
+                ),
+            })?;
 
-        if let Some(watcher_db) = self.watcher_db.as_ref() {
-            let signatures = watcher_db
-                .get_block_signatures(request.block)
-                .map_err(|err| {
-                    rpc_internal_error("watcher_db.get_block_signatures", err, &self.logger)
-                })?;
-            for signature_data in signatures.iter() {
-                let mut signature_message = api::ArchiveBlockSignatureData::new();
-                signature_message.set_src_url(signature_data.src_url.clone());
-                signature_message.set_filename(signature_data.archive_filename.clone());
-                signature_message.set_signature(
-                    mc_consensus_api::blockchain::BlockSignature::from(
-                        &signature_data.block_signature,
-                    ),
-                );
-                response.mut_signatures().push(signature_message);
-            }
-        }
+        // Success.
+        let mut response = api::GenerateSwapFillTxResponse::new();
+        response.set_tx_proposal((&tx_proposal).into());
         Ok(response)
     }
 
-    fn get_tx_status_as_sender_impl(
+    fn generate_gift_code_funding_tx_impl(
         &mut self,
-        request: api::SubmitTxResponse,
-    ) -> Result<api::GetTxStatusAsSenderResponse, RpcStatus> {
-        // Sanity-test the request.
-        if request
-            .get_sender_tx_receipt()
-            .get_key_image_list()
-            .is_empty()
-        {
-            return Err(RpcStatus::with_message(
-                RpcStatusCode::INVALID_ARGUMENT,
-                "sender_receipt.key_image_list".into(),
-            ));
-        }
+        request: api::GenerateGiftCodeFundingTxRequest,
+    ) -> Result<api::GenerateGiftCodeFundingTxResponse, RpcStatus> {
+        // Get sender monitor id from request.
+        let sender_monitor_id = MonitorId::try_from(&request.sender_monitor_id)
+            .map_err(|err| rpc_internal_error("monitor_id.try_from.bytes", err, &self.logger))?;
 
-        if request.get_sender_tx_receipt().tombstone == 0 {
-            return Err(RpcStatus::with_message(
-                RpcStatusCode::INVALID_ARGUMENT,
-                "sender_receipt.tombstone".into(),
-            ));
-        }
+        // Get monitor data for this monitor.
+        let sender_monitor_data = self
+            .mobilecoind_db
+            .get_monitor_data(&sender_monitor_id)
+            .map_err(|err| {
+                rpc_internal_error("mobilecoind_db.get_monitor_data", err, &self.logger)
+            })?;
 
-        // Receiver receipt should have at least one output
-        if request.get_receiver_tx_receipt_list().is_empty() {
+        // Check that change_subaddress is covered by this monitor.
+        if !sender_monitor_data
+            .subaddress_indexes()
+            .contains(&request.change_subaddress)
+        {
             return Err(RpcStatus::with_message(
                 RpcStatusCode::INVALID_ARGUMENT,
-                "receiver_receipt.receiver_tx_receipt_list".into(),
+                "change_subaddress".into(),
             ));
         }
 
-        // Get list of key images from the request.
-        let key_images: Vec<KeyImage> = request
-            .get_sender_tx_receipt()
-            .get_key_image_list()
+        // Get the list of potential inputs, and verify they belong to the monitor.
+        let input_list: Vec<UnspentTxOut> = request
+            .get_input_list()
             .iter()
-            .map(|key_image| {
-                KeyImage::try_from(key_image)
-                    .map_err(|err| rpc_internal_error("key_image.try_from", err, &self.logger))
-            })
-            .collect::<Result<Vec<KeyImage>, RpcStatus>>()?;
+            .enumerate()
+            .map(|(i, proto_utxo)| {
+                let utxo = UnspentTxOut::try_from(proto_utxo).map_err(|err| {
+                    rpc_internal_error("unspent_tx_out.try_from", err, &self.logger)
+                })?;
 
-        // Get list of tx_public_keys from the request.
-        let compressed_pubkeys: Vec<CompressedRistrettoPublic> = request
-            .get_receiver_tx_receipt_list()
-            .iter()
-            .map(|r| {
-                RistrettoPublic::try_from(r.get_tx_public_key())
+                if utxo.token_id != request.token_id {
+                    return Err(RpcStatus::with_message(
+                        RpcStatusCode::INVALID_ARGUMENT,
+                        format!("input_list[{}].token_id", i),
+                    ));
+                }
+
+                let subaddress_id = self
+                    .mobilecoind_db
+                    .get_subaddress_id_by_utxo_id(&UtxoId::from(&utxo))
                     .map_err(|err| {
-                        rpc_internal_error("RistrettoPublic.try_from", err, &self.logger)
-                    })
-                    .map(|pubkey| CompressedRistrettoPublic::from(&pubkey))
-            })
-            .collect::<Result<Vec<CompressedRistrettoPublic>, RpcStatus>>()?;
+                        rpc_internal_error(
+                            "mobilecoind_db.get_subaddress_id_by_utxo_id",
+                            err,
+                            &self.logger,
+                        )
+                    })?;
+                if subaddress_id.monitor_id != sender_monitor_id {
+                    return Err(RpcStatus::with_message(
+                        RpcStatusCode::INVALID_ARGUMENT,
+                        format!("input_list.{}", i),
+                    ));
+                }
 
-        // Check the tx_public_keys in the receiver receipt, to also get the
-        // block_height. Note that if the transaction has not yet landed, the
-        // result will be a vec of LedgerDb::NotFound errors.
-        let found_pubkey_indices: Vec<u64> = compressed_pubkeys
-            .iter()
-            .map(|compressed_tx_public_key| {
-                self.ledger_db
-                    .get_tx_out_index_by_public_key(compressed_tx_public_key)
-                    .and_then(|txo_index| self.ledger_db.get_block_index_by_tx_out_index(txo_index))
+                Ok(utxo)
             })
-            .filter_map(Result::ok)
-            .collect();
+            .collect::<Result<Vec<UnspentTxOut>, RpcStatus>>()?;
 
-        // If we didn't find any of the tx_public_keys, then the transaction is either
-        // still pending, or the inputs were spent in another transaction and
-        // this transaction will never land.
-        if found_pubkey_indices.is_empty() {
-            // Verify that the key images are not anywhere else in the ledger.
-            let key_image_in_ledger: Vec<bool> = key_images
-                .iter()
-                .map(|key_image| {
-                    self.ledger_db.contains_key_image(key_image).map_err(|err| {
-                        rpc_internal_error("ledger_db.contains_key_image", err, &self.logger)
-                    })
-                })
-                .collect::<Result<Vec<bool>, RpcStatus>>()?;
-            if key_image_in_ledger
-                .iter()
-                .any(|key_image_in_ledger| *key_image_in_ledger)
-            {
-                let mut response = api::GetTxStatusAsSenderResponse::new();
-                response.set_status(api::TxStatus::TransactionFailureKeyImageAlreadySpent);
-                return Ok(response);
-            }
-
-            // Otherwise, the transaction is still pending or otherwise status unknown.
-            let mut response = api::GetTxStatusAsSenderResponse::new();
-            response.set_status(api::TxStatus::Unknown);
-            return Ok(response);
-        }
-
-        // Verify that all block indices are the same value. If this fails, the receipt
-        // is likely malformed, because it should be impossible to construct a
-        // transaction containing output public keys that somehow end up landing
-        // in different blocks.
-        if found_pubkey_indices.iter().min() != found_pubkey_indices.iter().max() {
-            let mut response = api::GetTxStatusAsSenderResponse::new();
-            response.set_status(api::TxStatus::PublicKeysInDifferentBlocks);
-            return Ok(response);
-        }
-
-        // Get the block in which this transaction landed.
-        let block_index = found_pubkey_indices[0];
-        let block_contents = self
-            .ledger_db
-            .get_block_contents(block_index)
-            .map_err(|err| rpc_internal_error("ledger_db.get_block_contents", err, &self.logger))?;
-
-        // Convert key images to a list of booleans indicating whether they were found
-        // in the block or not. All key_images from the same transaction should
-        // land in the same block.
-        let key_image_found: Vec<bool> = key_images
-            .iter()
-            .map(|key_image| block_contents.key_images.contains(key_image))
-            .collect::<Vec<bool>>();
-
-        // If all key images are in the block, the transaction was completed.
-        if key_image_found
-            .iter()
-            .all(|key_image_found| *key_image_found)
-        {
-            let mut response = api::GetTxStatusAsSenderResponse::new();
-            response.set_status(api::TxStatus::Verified);
-            return Ok(response);
-        }
-
-        // If only some key images found their way to the block, they were likely spent
-        // from another transaction.
-        if key_image_found
-            .iter()
-            .any(|key_image_found| *key_image_found)
-        {
-            let mut response = api::GetTxStatusAsSenderResponse::new();
-            response.set_status(api::TxStatus::TransactionFailureKeyImageBlockMismatch);
-            return Ok(response);
-        }
-
-        // Check if the tombstone block was exceeded.
-        let num_blocks = self
-            .ledger_db
-            .num_blocks()
-            .map_err(|err| rpc_internal_error("ledger_db.num_blocks", err, &self.logger))?;
+        // Attempt to construct a transaction.
+        let tx_proposal = self
+            .transactions_manager
+            .generate_gift_code_funding_tx(
+                &sender_monitor_id,
+                request.change_subaddress,
+                &input_list,
+                Amount::new(request.value, TokenId::from(request.token_id)),
+                request.get_memo(),
+                &self.get_last_block_infos(),
+                request.fee,
+                request.tombstone,
+            )
+            .map_err(|err| {
+                rpc_internal_error(
+                    "transactions_manager.generate_gift_code_funding_tx",
+                    err,
+                    &self.logger,
+                )
+            })?;
 
-        if num_blocks >= request.get_sender_tx_receipt().tombstone {
-            let mut response = api::GetTxStatusAsSenderResponse::new();
-            response.set_status(api::TxStatus::TombstoneBlockExceeded);
-            return Ok(response);
-        }
+        // The gift code TxOut is the single outlay.
+        let tx_out = tx_proposal
+            .outlay_index_to_tx_out_index
+            .get(&0)
+            .and_then(|tx_out_index| tx_proposal.tx.prefix.outputs.get(*tx_out_index))
+            .ok_or_else(|| {
+                RpcStatus::with_message(
+                    RpcStatusCode::INTERNAL,
+                    "gift code TxOut not found".to_owned(),
+                )
+            })?;
 
-        // No key images in ledger, tombstone block not yet exceeded.
-        let mut response = api::GetTxStatusAsSenderResponse::new();
-        response.set_status(api::TxStatus::Unknown);
+        // Success.
+        let mut response = api::GenerateGiftCodeFundingTxResponse::new();
+        response.set_tx_public_key((&tx_out.public_key).into());
+        response.set_tx_proposal((&tx_proposal).into());
         Ok(response)
     }
 
-    fn get_tx_status_as_receiver_impl(
+    fn create_gift_code_impl(
         &mut self,
-        request: api::GetTxStatusAsReceiverRequest,
-    ) -> Result<api::GetTxStatusAsReceiverResponse, RpcStatus> {
-        // Sanity-test the request.
-        if request.get_receipt().get_tx_out_hash().len() != 32 {
-            return Err(RpcStatus::with_message(
-                RpcStatusCode::INVALID_ARGUMENT,
-                "receipt.tx_out_hash".into(),
-            ));
-        }
+        request: api::CreateGiftCodeRequest,
+    ) -> Result<api::CreateGiftCodeResponse, RpcStatus> {
+        // Get monitor id from request.
+        let monitor_id = MonitorId::try_from(&request.monitor_id)
+            .map_err(|err| rpc_internal_error("monitor_id.try_from.bytes", err, &self.logger))?;
 
-        if request.get_receipt().tombstone == 0 {
-            return Err(RpcStatus::with_message(
-                RpcStatusCode::INVALID_ARGUMENT,
-                "receipt.tombstone".into(),
-            ));
-        }
+        // Get monitor data for this monitor.
+        let monitor_data = self
+            .mobilecoind_db
+            .get_monitor_data(&monitor_id)
+            .map_err(|err| {
+                rpc_internal_error("mobilecoind_db.get_monitor_data", err, &self.logger)
+            })?;
 
-        // Check if the hash landed in the ledger.
-        let mut hash_bytes = [0u8; 32];
-        hash_bytes.copy_from_slice(&request.get_receipt().tx_out_hash);
+        // Find the gift code TxOut in the ledger.
+        let tx_public_key = CompressedRistrettoPublic::try_from(request.get_tx_public_key())
+            .map_err(|err| {
+                rpc_internal_error("CompressedRistrettoPublic.try_from", err, &self.logger)
+            })?;
+        let global_index = self
+            .ledger_db
+            .get_tx_out_index_by_public_key(&tx_public_key)
+            .map_err(|err| {
+                rpc_internal_error(
+                    "ledger_db.get_tx_out_index_by_public_key",
+                    err,
+                    &self.logger,
+                )
+            })?;
+        let tx_out = self
+            .ledger_db
+            .get_tx_out_by_index(global_index)
+            .map_err(|err| {
+                rpc_internal_error("ledger_db.get_tx_out_by_index", err, &self.logger)
+            })?;
 
-        match self.ledger_db.get_tx_out_index_by_hash(&hash_bytes) {
-            Ok(_) => {
-                // If a monitor ID was given then validate the confirmation number
-                match request.get_monitor_id().len() {
-                    0 => { /* no monitor ID given */ }
-                    32 => {
-                        let monitor_id =
-                            MonitorId::try_from(&request.monitor_id).map_err(|err| {
-                                rpc_internal_error("monitor_id.try_from.bytes", err, &self.logger)
-                            })?;
+        let account_key = monitor_data
+            .get_account_key()
+            .map_err(|err| rpc_invalid_arg_error("monitor_id", err, &self.logger))?;
+        let gift_code = gift_codes::create_gift_code(account_key, &tx_out, global_index)
+            .map_err(|err| rpc_invalid_arg_error("create_gift_code", err, &self.logger))?;
+        let (_tx_out, amount) = gift_codes::get_gift_code_tx_out(&gift_code, &self.ledger_db)
+            .map_err(|err| rpc_internal_error("get_gift_code_tx_out", err, &self.logger))?;
 
-                        // Get monitor data for this monitor.
-                        let monitor_data = self
-                            .mobilecoind_db
-                            .get_monitor_data(&monitor_id)
-                            .map_err(|err| {
-                                rpc_internal_error(
-                                    "mobilecoind_db.get_monitor_data",
-                                    err,
-                                    &self.logger,
-                                )
-                            })?;
-                        let tx_public_key =
-                            RistrettoPublic::try_from(request.get_receipt().get_tx_public_key())
-                                .map_err(|err| {
-                                    rpc_internal_error(
-                                        "RistrettoPublic.try_from",
-                                        err,
-                                        &self.logger,
-                                    )
-                                })?;
-                        let view_private_key = monitor_data.account_key.view_private_key();
+        let mut wrapper = api::printable::PrintableWrapper::new();
+        wrapper.set_tx_out_gift_code((&gift_code).into());
 
-                        if request.get_receipt().get_confirmation_number().len() != 32 {
-                            return Err(RpcStatus::with_message(
-                                RpcStatusCode::INVALID_ARGUMENT,
-                                "receipt.confirmation_number".into(),
-                            ));
-                        }
+        let b58_code = wrapper
+            .b58_encode()
+            .map_err(|err| rpc_internal_error("b58_encode", err, &self.logger))?;
 
-                        // Test that the confirmation number is valid. Only the party constructing
-                        // the transaction could have created the correct confirmation number.
-                        let confirmation_number = {
-                            let mut confirmation_bytes = [0u8; 32];
-                            confirmation_bytes
-                                .copy_from_slice(request.get_receipt().get_confirmation_number());
-                            TxOutConfirmationNumber::from(confirmation_bytes)
-                        };
-                        if !confirmation_number.validate(&tx_public_key, view_private_key) {
-                            // If the confirmation number is invalid, this means that the
-                            // transaction did get added to the ledger
-                            // but the party constructing the receipt failed
-                            // to prove that they created it. This prevents a third-party observer
-                            // from taking credit for someone elses
-                            // payment.
-                            let mut response = api::GetTxStatusAsReceiverResponse::new();
-                            response.set_status(api::TxStatus::InvalidConfirmationNumber);
-                            return Ok(response);
-                        }
-                    }
-                    _ => {
-                        return Err(RpcStatus::with_message(
-                            RpcStatusCode::INVALID_ARGUMENT,
-                            "monitor_id".into(),
-                        ));
-                    }
-                }
+        // Construct response.
+        let mut response = api::CreateGiftCodeResponse::new();
+        response.set_b58_code(b58_code);
+        response.set_value(amount.value);
+        response.set_token_id(*amount.token_id);
+        Ok(response)
+    }
 
-                // The hash found its way into the ledger, so the transaction succeeded.
-                let mut response = api::GetTxStatusAsReceiverResponse::new();
-                response.set_status(api::TxStatus::Verified);
-                return Ok(response);
-            }
-            Err(mc_ledger_db::Error::NotFound) => {}
-            Err(err) => {
-                return Err(rpc_internal_error(
-                    "ledger_db.get_tx_out_index_by_hash",
-                    err,
-                    &self.logger,
-                ));
-            }
-        };
+    fn get_gift_code_status_impl(
+        &mut self,
+        request: api::GetGiftCodeStatusRequest,
+    ) -> Result<api::GetGiftCodeStatusResponse, RpcStatus> {
+        let gift_code = self.parse_gift_code(request.get_b58_code())?;
 
-        // Check if the tombstone block was exceeded.
-        let num_blocks = self
-            .ledger_db
-            .num_blocks()
-            .map_err(|err| rpc_internal_error("ledger_db.num_blocks", err, &self.logger))?;
+        let (_tx_out, amount) = gift_codes::get_gift_code_tx_out(&gift_code, &self.ledger_db)
+            .map_err(|err| rpc_invalid_arg_error("get_gift_code_tx_out", err, &self.logger))?;
 
-        if num_blocks >= request.get_receipt().tombstone {
-            let mut response = api::GetTxStatusAsReceiverResponse::new();
-            response.set_status(api::TxStatus::TombstoneBlockExceeded);
-            return Ok(response);
-        }
+        // Any of our monitors may have cancelled the gift code.
+        let monitor_map = self.mobilecoind_db.get_monitor_map().map_err(|err| {
+            rpc_internal_error("mobilecoind_db.get_monitor_map", err, &self.logger)
+        })?;
+        let status = gift_codes::get_gift_code_status(
+            &gift_code,
+            &self.ledger_db,
+            monitor_map.values().map(MonitorData::view_private_key),
+        )
+        .map_err(|err| rpc_internal_error("get_gift_code_status", err, &self.logger))?;
 
-        // Tx out not in ledger, tombstone block not yet exceeded.
-        let mut response = api::GetTxStatusAsReceiverResponse::new();
-        response.set_status(api::TxStatus::Unknown);
+        // Construct response.
+        let mut response = api::GetGiftCodeStatusResponse::new();
+        response.set_status(match status {
+            GiftCodeStatus::Unclaimed => api::GiftCodeStatus::GiftCodeUnclaimed,
+            GiftCodeStatus::Claimed => api::GiftCodeStatus::GiftCodeClaimed,
+            GiftCodeStatus::Cancelled => api::GiftCodeStatus::GiftCodeCancelled,
+        });
+        response.set_value(amount.value);
+        response.set_token_id(*amount.token_id);
         Ok(response)
     }
 
-    fn get_processed_block_impl(
+    fn generate_gift_code_claim_tx_impl(
         &mut self,
-        request: api::GetProcessedBlockRequest,
-    ) -> Result<api::GetProcessedBlockResponse, RpcStatus> {
-        // Get MonitorId from from the GRPC request.
+        request: api::GenerateGiftCodeClaimTxRequest,
+    ) -> Result<api::GenerateGiftCodeClaimTxResponse, RpcStatus> {
+        let gift_code = self.parse_gift_code(request.get_b58_code())?;
+
+        // Get monitor id from request.
         let monitor_id = MonitorId::try_from(&request.monitor_id)
             .map_err(|err| rpc_internal_error("monitor_id.try_from.bytes", err, &self.logger))?;
 
-        // We will use the AccountKey to compute the Address Code
-        let account_key = self
+        // Get monitor data for this monitor.
+        let monitor_data = self
             .mobilecoind_db
             .get_monitor_data(&monitor_id)
             .map_err(|err| {
                 rpc_internal_error("mobilecoind_db.get_monitor_data", err, &self.logger)
-            })?
-            .account_key;
+            })?;
 
-        // Get all processed block data for the requested block.
-        let processed_tx_outs = self
-            .mobilecoind_db
-            .get_processed_block(&monitor_id, request.block)
-            .map_err(|err| {
-                rpc_internal_error("mobilecoind_db.get_processed_block", err, &self.logger)
-            })?
-            .iter()
-            .map(|src| {
-                let mut dst = api::ProcessedTxOut::new();
-                dst.set_monitor_id(monitor_id.to_vec());
-                dst.set_subaddress_index(src.subaddress_index);
-                dst.set_public_key((&src.public_key).into());
-                dst.set_key_image((&src.key_image).into());
-                dst.set_value(src.value);
-                dst.set_direction(
-                    api::ProcessedTxOutDirection::from_i32(src.direction)
-                        .unwrap_or(api::ProcessedTxOutDirection::Invalid),
-                );
-
-                let subaddress = account_key.subaddress(src.subaddress_index);
-                let mut wrapper = api::printable::PrintableWrapper::new();
-                wrapper.set_public_address((&subaddress).into());
-                let encoded = wrapper
-                    .b58_encode()
-                    .map_err(|err| rpc_internal_error("wrapper.b58_encode", err, &self.logger))?;
-                dst.set_address_code(encoded);
-                dst.set_token_id(src.token_id);
-                Ok(dst)
-            })
-            .collect::<Result<Vec<_>, _>>()?;
-
-        // Return response
-        let mut response = api::GetProcessedBlockResponse::new();
-        response.set_tx_outs(RepeatedField::from_vec(processed_tx_outs));
-        Ok(response)
-    }
-
-    fn get_block_index_by_tx_pub_key_impl(
-        &mut self,
-        request: api::GetBlockIndexByTxPubKeyRequest,
-    ) -> Result<api::GetBlockIndexByTxPubKeyResponse, RpcStatus> {
-        let tx_public_key = RistrettoPublic::try_from(request.get_tx_public_key())
-            .map_err(|err| rpc_internal_error("RistrettoPublic.try_from", err, &self.logger))?;
-
-        let compressed_tx_public_key = CompressedRistrettoPublic::from(&tx_public_key);
-
-        let tx_out_index = self
-            .ledger_db
-            .get_tx_out_index_by_public_key(&compressed_tx_public_key)
-            .map_err(|err| {
-                rpc_internal_error(
-                    "ledger_db.get_tx_out_index_by_public_key",
-                    err,
-                    &self.logger,
-                )
-            })?;
+        // Check that subaddress is covered by this monitor.
+        if !monitor_data
+            .subaddress_indexes()
+            .contains(&request.subaddress)
+        {
+            return Err(RpcStatus::with_message(
+                RpcStatusCode::INVALID_ARGUMENT,
+                "subaddress".into(),
+            ));
+        }
 
-        let block_index = self
-            .ledger_db
-            .get_block_index_by_tx_out_index(tx_out_index)
+        // Attempt to construct a transaction.
+        let tx_proposal = self
+            .transactions_manager
+            .generate_gift_code_claim_tx(
+                &monitor_id,
+                request.subaddress,
+                &gift_code,
+                request.get_memo(),
+                &self.get_last_block_infos(),
+                request.fee,
+                request.tombstone,
+            )
             .map_err(|err| {
                 rpc_internal_error(
-                    "ledger_db.get_block_index_by_tx_out_index",
+                    "transactions_manager.generate_gift_code_claim_tx",
                     err,
                     &self.logger,
                 )
             })?;
 
-        let mut response = api::GetBlockIndexByTxPubKeyResponse::new();
-        response.set_block(block_index);
+        // Success.
+        let mut response = api::GenerateGiftCodeClaimTxResponse::new();
+        response.set_tx_proposal((&tx_proposal).into());
         Ok(response)
     }
 
-    fn get_balance_impl(
+    fn generate_gift_code_cancellation_tx_impl(
         &mut self,
-        request: api::GetBalanceRequest,
-    ) -> Result<api::GetBalanceResponse, RpcStatus> {
-        // Get MonitorId from from the GRPC request.
-        let monitor_id = MonitorId::try_from(&request.monitor_id)
-            .map_err(|err| rpc_internal_error("monitor_id.try_from.bytes", err, &self.logger))?;
-
-        // Get all utxos for this monitor id.
-        let utxos = self
-            .mobilecoind_db
-            .get_utxos_for_subaddress(&monitor_id, request.subaddress_index)
-            .map_err(|err| {
-                rpc_internal_error("mobilecoind_db.get_utxos_for_subaddress", err, &self.logger)
-            })?;
-
-        // Sum them up.
-        let balance = utxos
-            .iter()
-            // Filter only to the requested token id.
-            .filter(|utxo| utxo.token_id == request.token_id)
-            .map(|utxo| utxo.value as u128)
-            .sum::<u128>();
-
-        // It's possible the balance does not fit into a u64.
-        if balance > u64::max_value().into() {
-            return Err(RpcStatus::with_message(
-                RpcStatusCode::INTERNAL,
-                format!(
-                    "balance of {} won't fit in u64, fetch utxo list instead",
-                    balance
-                ),
-            ));
-        }
-
-        // Return response.
-        let mut response = api::GetBalanceResponse::new();
-        response.set_balance(balance as u64);
-        Ok(response)
-    }
+        request: api::GenerateGiftCodeCancellationTxRequest,
+    ) -> Result<api::GenerateGiftCodeCancellationTxResponse, RpcStatus> {
+        let gift_code = self.parse_gift_code(request.get_b58_code())?;
 
-    fn send_payment_impl(
-        &mut self,
-        request: api::SendPaymentRequest,
-    ) -> Result<api::SendPaymentResponse, RpcStatus> {
         // Get sender monitor id from request.
         let sender_monitor_id = MonitorId::try_from(&request.sender_monitor_id)
             .map_err(|err| rpc_internal_error("monitor_id.try_from.bytes", err, &self.logger))?;
 
-        // Get all utxos for this monitor id.
-        let mut utxos = self
+        // Get monitor data for this monitor.
+        let sender_monitor_data = self
             .mobilecoind_db
-            .get_utxos_for_subaddress(&sender_monitor_id, request.sender_subaddress)
+            .get_monitor_data(&sender_monitor_id)
             .map_err(|err| {
-                rpc_internal_error("mobilecoind_db.get_utxos_for_subaddress", err, &self.logger)
+                rpc_internal_error("mobilecoind_db.get_monitor_data", err, &self.logger)
             })?;
 
-        // Optionally filter for max value.
-        if request.max_input_utxo_value > 0 {
-            utxos.retain(|utxo| utxo.value <= request.max_input_utxo_value);
+        // Check that change_subaddress is covered by this monitor.
+        if !sender_monitor_data
+            .subaddress_indexes()
+            .contains(&request.change_subaddress)
+        {
+            return Err(RpcStatus::with_message(
+                RpcStatusCode::INVALID_ARGUMENT,
+                "change_subaddress".into(),
+            ));
         }
 
-        // Filter for requested token id.
-        utxos.retain(|utxo| utxo.token_id == request.token_id);
-
-        // Get the list of outlays.
-        let outlays: Vec<Outlay> = request
-            .get_outlay_list()
-            .iter()
-            .map(|outlay_proto| {
-                Outlay::try_from(outlay_proto)
-                    .map_err(|err| rpc_internal_error("outlay.try_from", err, &self.logger))
-            })
-            .collect::<Result<Vec<Outlay>, RpcStatus>>()?;
-
-        // Set change address to sender address unless it has been overridden
-        let change_subaddress = if request.override_change_subaddress {
-            request.change_subaddress
-        } else {
-            request.sender_subaddress
-        };
-
         // Attempt to construct a transaction.
         let tx_proposal = self
             .transactions_manager
-            .build_transaction(
+            .generate_gift_code_cancellation_tx(
                 &sender_monitor_id,
-                TokenId::from(request.token_id),
-                change_subaddress,
-                &utxos,
-                &outlays,
+                request.change_subaddress,
+                gift_code.global_index,
                 &self.get_last_block_infos(),
                 request.fee,
                 request.tombstone,
-                None,
             )
             .map_err(|err| {
-                rpc_internal_error("transactions_manager.build_transaction", err, &self.logger)
+                rpc_internal_error(
+                    "transactions_manager.generate_gift_code_cancellation_tx",
+                    err,
+                    &self.logger,
+                )
             })?;
 
-        let proto_tx_proposal = api::TxProposal::from(&tx_proposal);
-
-        // Submit transaction.
-        let mut submit_tx_request = api::SubmitTxRequest::new();
-        submit_tx_request.set_tx_proposal(proto_tx_proposal.clone());
-        let mut submit_tx_response = self.submit_tx_impl(submit_tx_request)?;
-
-        // Return response.
-        let mut response = api::SendPaymentResponse::new();
-        response.set_sender_tx_receipt(submit_tx_response.take_sender_tx_receipt());
-        response.set_receiver_tx_receipt_list(submit_tx_response.take_receiver_tx_receipt_list());
-        response.set_tx_proposal(proto_tx_proposal);
+        // Success.
+        let mut response = api::GenerateGiftCodeCancellationTxResponse::new();
+        response.set_tx_proposal((&tx_proposal).into());
         Ok(response)
     }
 
-    fn pay_address_code_impl(
+    fn generate_transfer_code_tx_impl(
         &mut self,
-        request: api::PayAddressCodeRequest,
-    ) -> Result<api::SendPaymentResponse, RpcStatus> {
-        // Sanity check.
-        if request.get_amount() == 0 {
-            return Err(RpcStatus::with_message(
-                RpcStatusCode::INVALID_ARGUMENT,
-                "amount".into(),
-            ));
-        }
+        request: api::GenerateTransferCodeTxRequest,
+    ) -> Result<api::GenerateTransferCodeTxResponse, RpcStatus> {
+        // Generate entropy.
+        let mnemonic_response = self.generate_mnemonic_impl(api::Empty::new())?;
+        let mnemonic_str = mnemonic_response.get_mnemonic().to_string();
+        let bip39_entropy = mnemonic_response.get_bip39_entropy();
 
-        // Try and decode the address code.
-        let mut parse_address_code_request = api::ParseAddressCodeRequest::new();
-        parse_address_code_request.set_b58_code(request.get_receiver_b58_code().to_owned());
-        let parse_address_code_response =
-            self.parse_address_code_impl(parse_address_code_request)?;
+        // Generate a new account using this mnemonic.
+        let mut account_key_request = api::GetAccountKeyFromMnemonicRequest::new();
+        account_key_request.set_mnemonic(mnemonic_str);
 
-        // Forward to SendPayment
-        let mut outlay = api::Outlay::new();
-        outlay.set_value(request.get_amount());
-        outlay.set_receiver(parse_address_code_response.get_receiver().clone());
+        let account_key_response = self.get_account_key_from_mnemonic_impl(account_key_request)?;
+        let account_key = AccountKey::try_from(account_key_response.get_account_key())
+            .map_err(|err| rpc_internal_error("account_key.try_from", err, &self.logger))?;
 
-        let mut send_payment_request = api::SendPaymentRequest::new();
-        send_payment_request.set_sender_monitor_id(request.get_sender_monitor_id().to_vec());
-        send_payment_request.set_sender_subaddress(request.get_sender_subaddress());
-        send_payment_request.set_outlay_list(RepeatedField::from_vec(vec![outlay]));
-        send_payment_request.set_fee(request.get_fee());
-        send_payment_request.set_tombstone(request.get_tombstone());
-        send_payment_request.set_max_input_utxo_value(request.get_max_input_utxo_value());
-        send_payment_request.set_override_change_subaddress(request.override_change_subaddress);
-        send_payment_request.set_change_subaddress(request.change_subaddress);
-        send_payment_request.set_token_id(request.token_id);
+        // The outlay we are sending the money to.
+        let outlay = Outlay {
+            receiver: account_key.default_subaddress(),
+            value: request.value,
+        };
 
-        self.send_payment_impl(send_payment_request)
-    }
+        // Generate transaction.
+        let mut generate_tx_request = api::GenerateTxRequest::new();
+        generate_tx_request.set_sender_monitor_id(request.get_sender_monitor_id().to_vec());
+        generate_tx_request.set_change_subaddress(request.change_subaddress);
+        generate_tx_request.set_input_list(RepeatedField::from_vec(request.input_list.to_vec()));
+        generate_tx_request.set_outlay_list(RepeatedField::from_vec(vec![(&outlay).into()]));
+        generate_tx_request.set_fee(request.fee);
+        generate_tx_request.set_tombstone(request.tombstone);
+        generate_tx_request.set_token_id(request.token_id);
 
-    fn get_network_status_impl(
-        &mut self,
-        _request: api::Empty,
-    ) -> Result<api::GetNetworkStatusResponse, RpcStatus> {
-        let network_state = self.network_state.read().expect("lock poisoned");
-        let num_blocks = self
-            .ledger_db
-            .num_blocks()
-            .map_err(|err| rpc_internal_error("ledger_db.num_blocks", err, &self.logger))?;
-        if num_blocks == 0 {
-            return Err(RpcStatus::with_message(
-                RpcStatusCode::INTERNAL,
-                "no bootstrap block".to_owned(),
-            ));
-        }
-        let local_block_index = num_blocks - 1;
+        let mut generate_tx_response = self.generate_tx_impl(generate_tx_request)?;
+        let tx_proposal = generate_tx_response.take_tx_proposal();
 
-        // Get LastBlockInfo from our peers
-        let block_infos = self.get_last_block_infos();
-        // choose the block info which is latest in terms of block index (we may be
-        // isolated from some of the nodes)
-        let last_block_info = block_infos
-            .into_iter()
-            .max_by_key(|info| info.block_index)
-            .ok_or_else(|| {
-                RpcStatus::with_message(RpcStatusCode::INTERNAL, "no peers reachable".to_owned())
+        // Grab the public key of the relevant tx out.
+        let proto_tx_public_key = {
+            // We expect only a single outlay.
+            if tx_proposal.get_outlay_index_to_tx_out_index().len() != 1 {
+                return Err(RpcStatus::with_message(
+                    RpcStatusCode::INTERNAL,
+                    format!(
+                        "outlay_index_to_tx_out_index contains {} elements, was expecting 1",
+                        tx_proposal.get_outlay_index_to_tx_out_index().len()
+                    ),
+                ));
+            }
+
+            // Get the TxOut index of our single outlay.
+            let tx_out_index = tx_proposal
+                .get_outlay_index_to_tx_out_index()
+                .get(&0)
+                .ok_or_else(|| {
+                    RpcStatus::with_message(
+                        RpcStatusCode::INTERNAL,
+                        "outlay_index_to_tx_out_index doesn't contain index 0".to_owned(),
+                    )
+                })?;
+
+            // Get the TxOut
+            let tx_out = tx_proposal
+                .get_tx()
+                .get_prefix()
+                .get_outputs()
+                .get(*tx_out_index as usize)
+                .ok_or_else(|| {
+                    RpcStatus::with_message(
+                        RpcStatusCode::INTERNAL,
+                        format!("tx out index {} not found", tx_out_index),
+                    )
+                })?;
+
+            // Get the public key
+            tx_out.get_public_key().clone()
+        };
+
+        let tx_public_key = RistrettoPublic::try_from(&proto_tx_public_key)
+            .map_err(|err| rpc_internal_error("ristretto_public.try_from", err, &self.logger))?;
+
+        let mut transfer_payload = api::printable::TransferPayload::new();
+        transfer_payload.set_bip39_entropy(bip39_entropy.to_vec());
+        transfer_payload.set_tx_out_public_key((&tx_public_key).into());
+        transfer_payload.set_memo(request.get_memo().to_string());
+
+        let mut transfer_wrapper = api::printable::PrintableWrapper::new();
+        transfer_wrapper.set_transfer_payload(transfer_payload);
+
+        let b58_code = transfer_wrapper
+            .b58_encode()
+            .map_err(|err| rpc_internal_error("b58_encode", err, &self.logger))?;
+
+        // Construct response.
+        let mut response = api::GenerateTransferCodeTxResponse::new();
+        response.set_tx_proposal(tx_proposal);
+        response.set_bip39_entropy(bip39_entropy.to_vec());
+        response.set_tx_public_key(proto_tx_public_key);
+        response.set_memo(request.get_memo().to_string());
+        response.set_b58_code(b58_code);
+        Ok(response)
+    }
+
+    fn submit_tx_impl(
+        &mut self,
+        request: api::SubmitTxRequest,
+    ) -> Result<api::SubmitTxResponse, RpcStatus> {
+        // Get TxProposal from request.
+        let tx_proposal = TxProposal::try_from(request.get_tx_proposal())
+            .map_err(|err| rpc_internal_error("tx_proposal.try_from", err, &self.logger))?;
+
+        // Submit to network.
+        let block_height = self
+            .transactions_manager
+            .submit_tx_proposal(&tx_proposal)
+            .map_err(|err| {
+                rpc_internal_error("transactions_manager.submit_tx_proposal", err, &self.logger)
             })?;
 
-        let mut response = api::GetNetworkStatusResponse::new();
+        // Update the attempted spend block height in db. Note that we swallow the error
+        // here since our transaction did get sent to the network, and its
+        // better to have the user attempt a double spend by having stale
+        // UnspentTxOut data than having them not be aware that the transaction
+        // was submitted.
+        let utxo_ids: Vec<UtxoId> = tx_proposal.utxos.iter().map(UtxoId::from).collect();
+        if let Err(err) = self.mobilecoind_db.update_attempted_spend(
+            &utxo_ids,
+            block_height,
+            tx_proposal.tx.prefix.tombstone_block,
+        ) {
+            log::error!(
+                self.logger,
+                "failed updating attempted_spend_height after submitting tx {}: {:?}",
+                tx_proposal.tx,
+                err
+            );
+        }
 
-        response.set_network_highest_block_index(
-            network_state.highest_block_index_on_network().unwrap_or(0),
-        );
-        response.set_peer_block_index_map(
-            network_state
-                .peer_to_current_block_index()
+        // Record the transaction in the history of the monitor it spends from. As
+        // above, we swallow the error since the transaction did get sent.
+        if let Err(err) = self.record_sent_tx(&tx_proposal, block_height) {
+            log::error!(
+                self.logger,
+                "failed recording sent tx {}: {:?}",
+                tx_proposal.tx,
+                err
+            );
+        }
+
+        // Construct sender receipt.
+        let mut sender_tx_receipt = api::SenderTxReceipt::new();
+        sender_tx_receipt.set_key_image_list(RepeatedField::from_vec(
+            tx_proposal
+                .utxos
                 .iter()
-                .map(|(responder_id, block_index)| (responder_id.to_string(), *block_index))
+                .map(|utxo| (&utxo.key_image).into())
                 .collect(),
-        );
-        response.set_local_block_index(local_block_index);
-        response.set_is_behind(network_state.is_behind(local_block_index));
-        response.set_last_block_info(last_block_info.into());
+        ));
+        sender_tx_receipt.set_tombstone(tx_proposal.tx.prefix.tombstone_block);
+
+        // Construct receiver receipts.
+        let receiver_tx_receipts: Vec<_> = tx_proposal
+            .outlays
+            .iter()
+            .enumerate()
+            .map(|(outlay_index, outlay)| {
+                let tx_out_index = tx_proposal
+                    .outlay_index_to_tx_out_index
+                    .get(&outlay_index)
+                    .ok_or_else(|| {
+                        RpcStatus::with_message(
+                            RpcStatusCode::INVALID_ARGUMENT,
+                            "outlay_index_to_tx_out_index".into(),
+                        )
+                    })?;
+
+                let tx_out = tx_proposal
+                    .tx
+                    .prefix
+                    .outputs
+                    .get(*tx_out_index)
+                    .ok_or_else(|| {
+                        RpcStatus::with_message(
+                            RpcStatusCode::INVALID_ARGUMENT,
+                            "outlay_index_to_tx_out_index".into(),
+                        )
+                    })?;
+
+                let mut receiver_tx_receipt = api::ReceiverTxReceipt::new();
+                receiver_tx_receipt.set_recipient((&outlay.receiver).into());
+                receiver_tx_receipt.set_tx_public_key((&tx_out.public_key).into());
+                receiver_tx_receipt.set_tx_out_hash(tx_out.hash().to_vec());
+                receiver_tx_receipt.set_tombstone(tx_proposal.tx.prefix.tombstone_block);
+
+                if tx_proposal.outlay_confirmation_numbers.len() > outlay_index {
+                    receiver_tx_receipt.set_confirmation_number(
+                        tx_proposal.outlay_confirmation_numbers[outlay_index].to_vec(),
+                    );
+                }
+
+                Ok(receiver_tx_receipt)
+            })
+            .collect::<Result<Vec<api::ReceiverTxReceipt>, RpcStatus>>()?;
 
+        // Return response.
+        let mut response = api::SubmitTxResponse::new();
+        response.set_sender_tx_receipt(sender_tx_receipt);
+        response.set_receiver_tx_receipt_list(RepeatedField::from_vec(receiver_tx_receipts));
         Ok(response)
     }
 
-    fn set_db_password_impl(
+    /// Append a submitted TxProposal to the history of the monitor owning its
+    /// inputs.
+    fn record_sent_tx(&self, tx_proposal: &TxProposal, block_height: u64) -> Result<(), Error> {
+        let utxo = match tx_proposal.utxos.first() {
+            Some(utxo) => utxo,
+            None => return Ok(()),
+        };
+        let monitor_id = self
+            .mobilecoind_db
+            .get_subaddress_id_by_utxo_id(&UtxoId::from(utxo))?
+            .monitor_id;
+        let monitor_data = self.mobilecoind_db.get_monitor_data(&monitor_id)?;
+
+        let sent_tx = SentTx::new(tx_proposal, monitor_data.view_private_key()?, block_height);
+        self.mobilecoind_db.add_sent_tx(&monitor_id, &sent_tx)?;
+        Ok(())
+    }
+
+    fn get_sent_tx_list_impl(
         &mut self,
-        request: api::SetDbPasswordRequest,
-    ) -> Result<api::Empty, RpcStatus> {
-        // Check if the database is unlocked and allowing this operation.
-        if !self.mobilecoind_db.is_unlocked() {
-            return Err(RpcStatus::with_message(
-                RpcStatusCode::INTERNAL,
-                "must unlock before changing current password".to_owned(),
-            ));
-        }
+        request: api::GetSentTxListRequest,
+    ) -> Result<api::GetSentTxListResponse, RpcStatus> {
+        let monitor_id = MonitorId::try_from(&request.monitor_id)
+            .map_err(|err| rpc_internal_error("monitor_id.try_from.bytes", err, &self.logger))?;
 
-        // Re-encrypt data using the new password.
-        self.mobilecoind_db
-            .re_encrypt(request.get_password())
-            .map_err(|err| rpc_internal_error("mobilecoind_db.re_encrypt", err, &self.logger))?;
+        let limit = if request.limit == 0 {
+            MAX_SENT_TX_LIST_LIMIT
+        } else {
+            request.limit.min(MAX_SENT_TX_LIST_LIMIT)
+        };
 
-        log::info!(self.logger, "DB encryption password updated successfully.");
+        let (total, sent_txs) = self
+            .mobilecoind_db
+            .get_sent_txs(&monitor_id, request.offset, limit)
+            .map_err(|err| rpc_internal_error("mobilecoind_db.get_sent_txs", err, &self.logger))?;
 
-        Ok(api::Empty::default())
+        let sent_tx_list = sent_txs
+            .iter()
+            .map(|(key, sent_tx)| {
+                let mut dst = api::SentTx::new();
+                dst.set_index(key.index);
+                dst.set_outlay_list(RepeatedField::from_vec(
+                    sent_tx
+                        .outlays
+                        .iter()
+                        .map(|outlay| {
+                            let mut dst = api::SentTxOutlay::new();
+                            dst.set_receiver((&outlay.receiver).into());
+                            dst.set_value(outlay.value);
+                            dst.set_tx_public_key((&outlay.tx_out_public_key).into());
+                            dst.set_confirmation_number(outlay.confirmation_number.clone());
+                            dst
+                        })
+                        .collect(),
+                ));
+                dst.set_token_id(sent_tx.token_id);
+                dst.set_fee(sent_tx.fee);
+                dst.set_fee_token_id(sent_tx.fee_token_id);
+                dst.set_tombstone(sent_tx.tombstone_block);
+                dst.set_key_image_list(RepeatedField::from_vec(
+                    sent_tx
+                        .key_images
+                        .iter()
+                        .map(|key_image| key_image.into())
+                        .collect(),
+                ));
+                dst.set_memo_list(RepeatedField::from_vec(sent_tx.memos.clone()));
+                dst.set_submitted_block_count(sent_tx.submitted_block_count);
+
+                match sent_tx
+                    .status(&self.ledger_db)
+                    .map_err(|err| rpc_internal_error("sent_tx.status", err, &self.logger))?
+                {
+                    SentTxStatus::Pending => dst.set_status(api::SentTxStatus::SentTxPending),
+                    SentTxStatus::Landed(block_index) => {
+                        dst.set_status(api::SentTxStatus::SentTxLanded);
+                        dst.set_landed_block(block_index);
+                    }
+                    SentTxStatus::Expired => dst.set_status(api::SentTxStatus::SentTxExpired),
+                }
+
+                Ok(dst)
+            })
+            .collect::<Result<Vec<_>, RpcStatus>>()?;
+
+        let mut response = api::GetSentTxListResponse::new();
+        response.set_sent_tx_list(RepeatedField::from_vec(sent_tx_list));
+        response.set_total(total);
+        Ok(response)
     }
 
-    fn unlock_db_impl(&mut self, request: api::UnlockDbRequest) -> Result<api::Empty, RpcStatus> {
-        if self.mobilecoind_db.is_unlocked() {
+    fn export_monitor_activity_impl(
+        &mut self,
+        request: api::ExportMonitorActivityRequest,
+    ) -> Result<api::ExportMonitorActivityResponse, RpcStatus> {
+        let monitor_id = MonitorId::try_from(&request.monitor_id)
+            .map_err(|err| rpc_internal_error("monitor_id.try_from.bytes", err, &self.logger))?;
+
+        let monitor_data = self
+            .mobilecoind_db
+            .get_monitor_data(&monitor_id)
+            .map_err(|err| {
+                rpc_internal_error("mobilecoind_db.get_monitor_data", err, &self.logger)
+            })?;
+        if request.first_block < monitor_data.first_block {
             return Err(RpcStatus::with_message(
-                RpcStatusCode::INTERNAL,
-                "already unlocked".to_owned(),
+                RpcStatusCode::INVALID_ARGUMENT,
+                "first_block".into(),
             ));
         }
 
-        self.mobilecoind_db
-            .check_and_store_password(request.get_password())
+        // Authenticated sender memos are validated against the contacts provided in
+        // the request.
+        let memo_handler = self.get_memo_handler(request.get_contacts())?;
+
+        let mut end_block = request
+            .first_block
+            .saturating_add(MAX_EXPORT_MONITOR_ACTIVITY_BLOCKS);
+        if request.end_block != 0 {
+            end_block = end_block.min(request.end_block);
+        }
+        let (end_block, processed_blocks) = self
+            .mobilecoind_db
+            .get_processed_blocks(&monitor_id, request.first_block, end_block)
             .map_err(|err| {
-                rpc_internal_error("mobilecoind_db.check_and_store_password", err, &self.logger)
+                rpc_internal_error("mobilecoind_db.get_processed_blocks", err, &self.logger)
             })?;
 
-        log::info!(self.logger, "Successfully unlocked, starting sync thread.");
-        (self.start_sync_thread)();
+        // Received TxOuts are unspent as long as they are in the monitor's utxo set.
+        // The set is read once per subaddress.
+        let mut unspent_public_keys = BTreeMap::<u64, HashSet<CompressedRistrettoPublic>>::new();
+
+        let mut records = Vec::new();
+        for (block_index, processed_tx_outs) in processed_blocks {
+            let timestamp = self.get_block_timestamp(block_index)?;
+
+            for src in processed_tx_outs.iter() {
+                let mut record = api::MonitorActivityRecord::new();
+                record.set_block_index(block_index);
+                record.set_timestamp(timestamp);
+                record.set_token_id(src.token_id);
+                record.set_value(src.value);
+                record.set_subaddress_index(src.subaddress_index);
+                record.set_public_key((&src.public_key).into());
+                record.set_key_image((&src.key_image).into());
+
+                if src.direction == ProcessedTxOutDirection::Spent as i32 {
+                    record.set_direction(api::ProcessedTxOutDirection::Spent);
+                    record.set_status(api::MonitorActivityStatus::ActivitySpent);
+                } else {
+                    record.set_direction(api::ProcessedTxOutDirection::Received);
+                    if !unspent_public_keys.contains_key(&src.subaddress_index) {
+                        let utxos = self
+                            .mobilecoind_db
+                            .get_utxos_for_subaddress(&monitor_id, src.subaddress_index)
+                            .map_err(|err| {
+                                rpc_internal_error(
+                                    "mobilecoind_db.get_utxos_for_subaddress",
+                                    err,
+                                    &self.logger,
+                                )
+                            })?;
+                        unspent_public_keys.insert(
+                            src.subaddress_index,
+                            utxos
+                                .into_iter()
+                                .map(|utxo| utxo.tx_out.public_key)
+                                .collect(),
+                        );
+                    }
+                    let unspent = &unspent_public_keys[&src.subaddress_index];
+                    record.set_status(if unspent.contains(&src.public_key) {
+                        api::MonitorActivityStatus::ActivityUnspent
+                    } else {
+                        api::MonitorActivityStatus::ActivitySpent
+                    });
+                }
 
-        Ok(api::Empty::default())
+                match self.handle_processed_tx_out_memo(&monitor_data, &memo_handler, src)? {
+                    Some(handled) => {
+                        record.set_memo_type(
+                            activity_export::memo_type_name(&handled.memo_type).to_owned(),
+                        );
+                        if let Some(address_hash) =
+                            activity_export::counterparty_address_hash(&handled)
+                        {
+                            record.set_counterparty_address_hash(address_hash.as_ref().to_vec());
+                        }
+                    }
+                    None => record.set_memo_type(activity_export::UNKNOWN_MEMO_TYPE.to_owned()),
+                }
+
+                records.push(record);
+            }
+        }
+
+        let mut response = api::ExportMonitorActivityResponse::new();
+        response.set_records(RepeatedField::from_vec(records));
+        response.set_end_block(end_block.max(request.first_block));
+        Ok(response)
     }
-}
 
-macro_rules! build_api {
-    ($( $service_function_name:ident $service_request_type:ident $service_response_type:ident $service_function_impl:ident $(,)?)+)
-    =>
-    (
-        impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolver> MobilecoindApi for ServiceApi<T, FPR> {
-            $(
-                fn $service_function_name(
-                    &mut self,
-                    ctx: RpcContext,
-                    request: api::$service_request_type,
-                    sink: UnarySink<api::$service_response_type>,
-                ) {
-                    let logger = rpc_logger(&ctx, &self.logger);
-                    send_result(
-                        ctx,
-                        sink,
-                        self.$service_function_impl(request),
-                        &logger,
-                    )
+    /// The time at which a block was signed, in seconds since the Unix epoch,
+    /// or 0 if no signature of the block is known.
+    fn get_block_timestamp(&self, block_index: u64) -> Result<u64, RpcStatus> {
+        match self.ledger_db.get_block_signature(block_index) {
+            Ok(signature) => return Ok(signature.signed_at()),
+            Err(LedgerError::NotFound) => {}
+            Err(err) => {
+                return Err(rpc_internal_error(
+                    "ledger_db.get_block_signature",
+                    err,
+                    &self.logger,
+                ))
+            }
+        }
+
+        // Blocks synced from peers carry no signature, but the watcher may have
+        // collected some from the archive.
+        if let Some(watcher_db) = self.watcher_db.as_ref() {
+            let signatures = watcher_db
+                .get_block_signatures(block_index)
+                .map_err(|err| {
+                    rpc_internal_error("watcher_db.get_block_signatures", err, &self.logger)
+                })?;
+            if let Some(signed_at) = signatures
+                .iter()
+                .map(|signature_data| signature_data.block_signature.signed_at())
+                .min()
+            {
+                return Ok(signed_at);
+            }
+        }
+
+        Ok(0)
+    }
+
+    fn get_ledger_info_impl(
+        &mut self,
+        _request: api::Empty,
+    ) -> Result<api::GetLedgerInfoResponse, RpcStatus> {
+        let num_blocks = self
+            .ledger_db
+            .num_blocks()
+            .map_err(|err| rpc_internal_error("ledger_db.num_blocks", err, &self.logger))?;
+
+        let num_txos = self
+            .ledger_db
+            .num_txos()
+            .map_err(|err| rpc_internal_error("ledger_db.num_txos", err, &self.logger))?;
+
+        let mut response = api::GetLedgerInfoResponse::new();
+        response.set_block_count(num_blocks);
+        response.set_txo_count(num_txos);
+        Ok(response)
+    }
+
+    fn get_block_info_impl(
+        &mut self,
+        request: api::GetBlockInfoRequest,
+    ) -> Result<api::GetBlockInfoResponse, RpcStatus> {
+        let block_contents = self
+            .ledger_db
+            .get_block_contents(request.block)
+            .map_err(|err| rpc_internal_error("ledger_db.get_block_contents", err, &self.logger))?;
+
+        let num_tx_outs = block_contents.outputs.len();
+        let num_key_images = block_contents.key_images.len();
+
+        // Return response.
+        let mut response = api::GetBlockInfoResponse::new();
+        response.set_key_image_count(num_key_images as u64);
+        response.set_txo_count(num_tx_outs as u64);
+        Ok(response)
+    }
+
+    fn get_block_impl(
+        &mut self,
+        request: api::GetBlockRequest,
+    ) -> Result<api::GetBlockResponse, RpcStatus> {
+        let mut response = api::GetBlockResponse::new();
+
+        let block_data = self
+            .ledger_db
+            .get_block_data(request.block)
+            .map_err(|err| rpc_internal_error("ledger_db.get_block_data", err, &self.logger))?;
+
+        response.set_block(mc_consensus_api::blockchain::Block::from(
+            block_data.block(),
+        ));
+
+        for key_image in &block_data.contents().key_images {
+            response
+                .mut_key_images()
+                .push(mc_consensus_api::external::KeyImage::from(key_image));
+        }
+        for output in &block_data.contents().outputs {
+            response
+                .mut_txos()
+                .push(mc_consensus_api::external::TxOut::from(output));
+        }
+
+        if let Some(watcher_db) = self.watcher_db.as_ref() {
+            let signatures = watcher_db
+                .get_block_signatures(request.block)
+                .map_err(|err| {
+                    rpc_internal_error("watcher_db.get_block_signatures", err, &self.logger)
+                })?;
+            for signature_data in signatures.iter() {
+                let mut signature_message = api::ArchiveBlockSignatureData::new();
+                signature_message.set_src_url(signature_data.src_url.clone());
+                signature_message.set_filename(signature_data.archive_filename.clone());
+                signature_message.set_signature(
+                    mc_consensus_api::blockchain::BlockSignature::from(
+                        &signature_data.block_signature,
+                    ),
+                );
+                response.mut_signatures().push(signature_message);
+            }
+        }
+        Ok(response)
+    }
+
+    fn get_tx_status_as_sender_impl(
+        &mut self,
+        request: api::SubmitTxResponse,
+    ) -> Result<api::GetTxStatusAsSenderResponse, RpcStatus> {
+        // Sanity-test the request.
+        if request
+            .get_sender_tx_receipt()
+            .get_key_image_list()
+            .is_empty()
+        {
+            return Err(RpcStatus::with_message(
+                RpcStatusCode::INVALID_ARGUMENT,
+                "sender_receipt.key_image_list".into(),
+            ));
+        }
+
+        if request.get_sender_tx_receipt().tombstone == 0 {
+            return Err(RpcStatus::with_message(
+                RpcStatusCode::INVALID_ARGUMENT,
+                "sender_receipt.tombstone".into(),
+            ));
+        }
+
+        // Receiver receipt should have at least one output
+        if request.get_receiver_tx_receipt_list().is_empty() {
+            return Err(RpcStatus::with_message(
+                RpcStatusCode::INVALID_ARGUMENT,
+                "receiver_receipt.receiver_tx_receipt_list".into(),
+            ));
+        }
+
+        // Get list of key images from the request.
+        let key_images: Vec<KeyImage> = request
+            .get_sender_tx_receipt()
+            .get_key_image_list()
+            .iter()
+            .map(|key_image| {
+                KeyImage::try_from(key_image)
+                    .map_err(|err| rpc_internal_error("key_image.try_from", err, &self.logger))
+            })
+            .collect::<Result<Vec<KeyImage>, RpcStatus>>()?;
+
+        // Get list of tx_public_keys from the request.
+        let compressed_pubkeys: Vec<CompressedRistrettoPublic> = request
+            .get_receiver_tx_receipt_list()
+            .iter()
+            .map(|r| {
+                RistrettoPublic::try_from(r.get_tx_public_key())
+                    .map_err(|err| {
+                        rpc_internal_error("RistrettoPublic.try_from", err, &self.logger)
+                    })
+                    .map(|pubkey| CompressedRistrettoPublic::from(&pubkey))
+            })
+            .collect::<Result<Vec<CompressedRistrettoPublic>, RpcStatus>>()?;
+
+        // Check the tx_public_keys in the receiver receipt, to also get the
+        // block_height. Note that if the transaction has not yet landed, the
+        // result will be a vec of LedgerDb::NotFound errors.
+        let found_pubkey_indices: Vec<u64> = compressed_pubkeys
+            .iter()
+            .map(|compressed_tx_public_key| {
+                self.ledger_db
+                    .get_tx_out_index_by_public_key(compressed_tx_public_key)
+                    .and_then(|txo_index| self.ledger_db.get_block_index_by_tx_out_index(txo_index))
+            })
+            .filter_map(Result::ok)
+            .collect();
+
+        // If we didn't find any of the tx_public_keys, then the transaction is either
+        // still pending, or the inputs were spent in another transaction and
+        // this transaction will never land.
+        if found_pubkey_indices.is_empty() {
+            // Verify that the key images are not anywhere else in the ledger.
+            let key_image_in_ledger: Vec<bool> = key_images
+                .iter()
+                .map(|key_image| {
+                    self.ledger_db.contains_key_image(key_image).map_err(|err| {
+                        rpc_internal_error("ledger_db.contains_key_image", err, &self.logger)
+                    })
+                })
+                .collect::<Result<Vec<bool>, RpcStatus>>()?;
+            if key_image_in_ledger
+                .iter()
+                .any(|key_image_in_ledger| *key_image_in_ledger)
+            {
+                // If a replacement transaction generated by us landed, the key images were
+                // spent by it.
+                if self.is_replacement_tx_in_ledger(&key_images)? {
+                    let mut response = api::GetTxStatusAsSenderResponse::new();
+                    response.set_status(api::TxStatus::TransactionReplaced);
+                    return Ok(response);
                 }
-            )+
+
+                let mut response = api::GetTxStatusAsSenderResponse::new();
+                response.set_status(api::TxStatus::TransactionFailureKeyImageAlreadySpent);
+                return Ok(response);
+            }
+
+            // Otherwise, the transaction is still pending or otherwise status unknown.
+            let mut response = api::GetTxStatusAsSenderResponse::new();
+            response.set_status(api::TxStatus::Unknown);
+            return Ok(response);
         }
-    );
-}
 
-build_api! {
-    // Monitors
-    add_monitor AddMonitorRequest AddMonitorResponse add_monitor_impl,
-    remove_monitor RemoveMonitorRequest Empty remove_monitor_impl,
-    get_monitor_list Empty GetMonitorListResponse get_monitor_list_impl,
-    get_monitor_status GetMonitorStatusRequest GetMonitorStatusResponse get_monitor_status_impl,
-    get_unspent_tx_out_list GetUnspentTxOutListRequest GetUnspentTxOutListResponse get_unspent_tx_out_list_impl,
+        // Verify that all block indices are the same value. If this fails, the receipt
+        // is likely malformed, because it should be impossible to construct a
+        // transaction containing output public keys that somehow end up landing
+        // in different blocks.
+        if found_pubkey_indices.iter().min() != found_pubkey_indices.iter().max() {
+            let mut response = api::GetTxStatusAsSenderResponse::new();
+            response.set_status(api::TxStatus::PublicKeysInDifferentBlocks);
+            return Ok(response);
+        }
+
+        // Get the block in which this transaction landed.
+        let block_index = found_pubkey_indices[0];
+        let block_contents = self
+            .ledger_db
+            .get_block_contents(block_index)
+            .map_err(|err| rpc_internal_error("ledger_db.get_block_contents", err, &self.logger))?;
+
+        // Convert key images to a list of booleans indicating whether they were found
+        // in the block or not. All key_images from the same transaction should
+        // land in the same block.
+        let key_image_found: Vec<bool> = key_images
+            .iter()
+            .map(|key_image| block_contents.key_images.contains(key_image))
+            .collect::<Vec<bool>>();
+
+        // If all key images are in the block, the transaction was completed.
+        if key_image_found
+            .iter()
+            .all(|key_image_found| *key_image_found)
+        {
+            let mut response = api::GetTxStatusAsSenderResponse::new();
+            response.set_status(api::TxStatus::Verified);
+            return Ok(response);
+        }
+
+        // If only some key images found their way to the block, they were likely spent
+        // from another transaction.
+        if key_image_found
+            .iter()
+            .any(|key_image_found| *key_image_found)
+        {
+            let mut response = api::GetTxStatusAsSenderResponse::new();
+            response.set_status(api::TxStatus::TransactionFailureKeyImageBlockMismatch);
+            return Ok(response);
+        }
+
+        // Check if the tombstone block was exceeded.
+        let num_blocks = self
+            .ledger_db
+            .num_blocks()
+            .map_err(|err| rpc_internal_error("ledger_db.num_blocks", err, &self.logger))?;
+
+        if num_blocks >= request.get_sender_tx_receipt().tombstone {
+            let mut response = api::GetTxStatusAsSenderResponse::new();
+            response.set_status(api::TxStatus::TombstoneBlockExceeded);
+            return Ok(response);
+        }
+
+        // No key images in ledger, tombstone block not yet exceeded.
+        let mut response = api::GetTxStatusAsSenderResponse::new();
+        response.set_status(api::TxStatus::Unknown);
+        Ok(response)
+    }
+
+    fn get_tx_status_as_receiver_impl(
+        &mut self,
+        request: api::GetTxStatusAsReceiverRequest,
+    ) -> Result<api::GetTxStatusAsReceiverResponse, RpcStatus> {
+        // Sanity-test the request.
+        if request.get_receipt().get_tx_out_hash().len() != 32 {
+            return Err(RpcStatus::with_message(
+                RpcStatusCode::INVALID_ARGUMENT,
+                "receipt.tx_out_hash".into(),
+            ));
+        }
+
+        if request.get_receipt().tombstone == 0 {
+            return Err(RpcStatus::with_message(
+                RpcStatusCode::INVALID_ARGUMENT,
+                "receipt.tombstone".into(),
+            ));
+        }
+
+        // Check if the hash landed in the ledger.
+        let mut hash_bytes = [0u8; 32];
+        hash_bytes.copy_from_slice(&request.get_receipt().tx_out_hash);
+
+        match self.ledger_db.get_tx_out_index_by_hash(&hash_bytes) {
+            Ok(_) => {
+                // If a monitor ID was given then validate the confirmation number
+                match request.get_monitor_id().len() {
+                    0 => { /* no monitor ID given */ }
+                    32 => {
+                        let monitor_id =
+                            MonitorId::try_from(&request.monitor_id).map_err(|err| {
+                                rpc_internal_error("monitor_id.try_from.bytes", err, &self.logger)
+                            })?;
+
+                        // Get monitor data for this monitor.
+                        let monitor_data = self
+                            .mobilecoind_db
+                            .get_monitor_data(&monitor_id)
+                            .map_err(|err| {
+                                rpc_internal_error(
+                                    "mobilecoind_db.get_monitor_data",
+                                    err,
+                                    &self.logger,
+                                )
+                            })?;
+                        let tx_public_key =
+                            RistrettoPublic::try_from(request.get_receipt().get_tx_public_key())
+                                .map_err(|err| {
+                                    rpc_internal_error(
+                                        "RistrettoPublic.try_from",
+                                        err,
+                                        &self.logger,
+                                    )
+                                })?;
+                        let view_private_key = monitor_data.view_private_key().map_err(|err| {
+                            rpc_internal_error("monitor_data.view_private_key", err, &self.logger)
+                        })?;
+
+                        if request.get_receipt().get_confirmation_number().len() != 32 {
+                            return Err(RpcStatus::with_message(
+                                RpcStatusCode::INVALID_ARGUMENT,
+                                "receipt.confirmation_number".into(),
+                            ));
+                        }
+
+                        // Test that the confirmation number is valid. Only the party constructing
+                        // the transaction could have created the correct confirmation number.
+                        let confirmation_number = {
+                            let mut confirmation_bytes = [0u8; 32];
+                            confirmation_bytes
+                                .copy_from_slice(request.get_receipt().get_confirmation_number());
+                            TxOutConfirmationNumber::from(confirmation_bytes)
+                        };
+                        if !confirmation_number.validate(&tx_public_key, view_private_key) {
+                            // If the confirmation number is invalid, this means that the
+                            // transaction did get added to the ledger
+                            // but the party constructing the receipt failed
+                            // to prove that they created it. This prevents a third-party observer
+                            // from taking credit for someone elses
+                            // payment.
+                            let mut response = api::GetTxStatusAsReceiverResponse::new();
+                            response.set_status(api::TxStatus::InvalidConfirmationNumber);
+                            return Ok(response);
+                        }
+                    }
+                    _ => {
+                        return Err(RpcStatus::with_message(
+                            RpcStatusCode::INVALID_ARGUMENT,
+                            "monitor_id".into(),
+                        ));
+                    }
+                }
+
+                // The hash found its way into the ledger, so the transaction succeeded.
+                let mut response = api::GetTxStatusAsReceiverResponse::new();
+                response.set_status(api::TxStatus::Verified);
+                return Ok(response);
+            }
+            Err(mc_ledger_db::Error::NotFound) => {}
+            Err(err) => {
+                return Err(rpc_internal_error(
+                    "ledger_db.get_tx_out_index_by_hash",
+                    err,
+                    &self.logger,
+                ));
+            }
+        };
+
+        // Check if the tombstone block was exceeded.
+        let num_blocks = self
+            .ledger_db
+            .num_blocks()
+            .map_err(|err| rpc_internal_error("ledger_db.num_blocks", err, &self.logger))?;
+
+        if num_blocks >= request.get_receipt().tombstone {
+            let mut response = api::GetTxStatusAsReceiverResponse::new();
+            response.set_status(api::TxStatus::TombstoneBlockExceeded);
+            return Ok(response);
+        }
+
+        // Tx out not in ledger, tombstone block not yet exceeded.
+        let mut response = api::GetTxStatusAsReceiverResponse::new();
+        response.set_status(api::TxStatus::Unknown);
+        Ok(response)
+    }
+
+    /// Create a MemoHandler that validates authenticated sender memos against
+    /// the given contacts.
+    fn get_memo_handler(
+        &self,
+        contacts: &[api::external::PublicAddress],
+    ) -> Result<ContactMemoHandler, RpcStatus> {
+        let contacts = contacts
+            .iter()
+            .map(|src| {
+                let addr = PublicAddress::try_from(src).map_err(|err| {
+                    rpc_internal_error("PublicAddress.try_from", err, &self.logger)
+                })?;
+                Ok((ShortAddressHash::from(&addr), addr))
+            })
+            .collect::<Result<BTreeMap<_, _>, RpcStatus>>()?;
+        Ok(MemoHandler::new(contacts))
+    }
+
+    /// Convert a ProcessedTxOut of a monitor into its API representation,
+    /// classifying its memo with the given MemoHandler.
+    fn processed_tx_out_to_proto(
+        &self,
+        monitor_id: &MonitorId,
+        monitor_data: &MonitorData,
+        memo_handler: &ContactMemoHandler,
+        src: &ProcessedTxOut,
+    ) -> Result<api::ProcessedTxOut, RpcStatus> {
+        let mut dst = api::ProcessedTxOut::new();
+        dst.set_monitor_id(monitor_id.to_vec());
+        dst.set_subaddress_index(src.subaddress_index);
+        dst.set_public_key((&src.public_key).into());
+        dst.set_key_image((&src.key_image).into());
+        dst.set_value(src.value);
+        dst.set_direction(
+            api::ProcessedTxOutDirection::from_i32(src.direction)
+                .unwrap_or(api::ProcessedTxOutDirection::Invalid),
+        );
+
+        let subaddress = monitor_data
+            .subaddress(src.subaddress_index)
+            .map_err(|err| rpc_internal_error("monitor_data.subaddress", err, &self.logger))?;
+        let mut wrapper = api::printable::PrintableWrapper::new();
+        wrapper.set_public_address((&subaddress).into());
+        let encoded = wrapper
+            .b58_encode()
+            .map_err(|err| rpc_internal_error("wrapper.b58_encode", err, &self.logger))?;
+        dst.set_address_code(encoded);
+        dst.set_token_id(src.token_id);
+
+        if let Some(handled) = self.handle_processed_tx_out_memo(monitor_data, memo_handler, src)? {
+            dst.set_memo_classification(match handled.classification {
+                MemoClassification::Authenticated => {
+                    api::ProcessedTxOutMemoClassification::MemoAuthenticated
+                }
+                MemoClassification::Unauthenticated => {
+                    api::ProcessedTxOutMemoClassification::MemoUnauthenticated
+                }
+                MemoClassification::UnknownSender => {
+                    api::ProcessedTxOutMemoClassification::MemoUnknownSender
+                }
+            });
+            if let Some(address_hash) = handled.sender_address_hash {
+                dst.set_sender_address_hash(address_hash.as_ref().to_vec());
+            }
+            if let Some(sender) = handled.sender {
+                let mut wrapper = api::printable::PrintableWrapper::new();
+                wrapper.set_public_address((&sender).into());
+                let encoded = wrapper
+                    .b58_encode()
+                    .map_err(|err| rpc_internal_error("wrapper.b58_encode", err, &self.logger))?;
+                dst.set_sender_address_code(encoded);
+            }
+        }
+        Ok(dst)
+    }
+
+    /// Decode and classify the memo of a ProcessedTxOut of a monitor.
+    /// Returns None when the memo could not be decoded.
+    fn handle_processed_tx_out_memo(
+        &self,
+        monitor_data: &MonitorData,
+        memo_handler: &ContactMemoHandler,
+        src: &ProcessedTxOut,
+    ) -> Result<Option<HandledMemo>, RpcStatus> {
+        // Validating memos requires the account key, so view-only monitors report
+        // them as unauthenticated.
+        let account_key = match monitor_data.account_key.as_ref() {
+            Some(account_key) => account_key,
+            None => return Ok(None),
+        };
+
+        let tx_out = self
+            .ledger_db
+            .get_tx_out_index_by_public_key(&src.public_key)
+            .and_then(|index| self.ledger_db.get_tx_out_by_index(index))
+            .map_err(|err| rpc_internal_error("ledger_db.get_tx_out", err, &self.logger))?;
+        match memo_handler.handle_memo(&tx_out, account_key, src.subaddress_index) {
+            Ok(handled) => Ok(Some(handled)),
+            Err(err) => {
+                // Memos we are unable to decode are reported as unauthenticated.
+                log::debug!(
+                    self.logger,
+                    "Failed handling memo of TxOut {:?}: {}",
+                    src.public_key,
+                    err
+                );
+                Ok(None)
+            }
+        }
+    }
+
+    fn get_processed_block_impl(
+        &mut self,
+        request: api::GetProcessedBlockRequest,
+    ) -> Result<api::GetProcessedBlockResponse, RpcStatus> {
+        // Get MonitorId from from the GRPC request.
+        let monitor_id = MonitorId::try_from(&request.monitor_id)
+            .map_err(|err| rpc_internal_error("monitor_id.try_from.bytes", err, &self.logger))?;
+
+        // We will use the monitor data to compute the Address Code
+        let monitor_data = self
+            .mobilecoind_db
+            .get_monitor_data(&monitor_id)
+            .map_err(|err| {
+                rpc_internal_error("mobilecoind_db.get_monitor_data", err, &self.logger)
+            })?;
+
+        // Authenticated sender memos are validated against the contacts provided in
+        // the request.
+        let memo_handler = self.get_memo_handler(request.get_contacts())?;
+
+        // Get all processed block data for the requested block.
+        let processed_tx_outs = self
+            .mobilecoind_db
+            .get_processed_block(&monitor_id, request.block)
+            .map_err(|err| {
+                rpc_internal_error("mobilecoind_db.get_processed_block", err, &self.logger)
+            })?
+            .iter()
+            .map(|src| {
+                self.processed_tx_out_to_proto(&monitor_id, &monitor_data, &memo_handler, src)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Return response
+        let mut response = api::GetProcessedBlockResponse::new();
+        response.set_tx_outs(RepeatedField::from_vec(processed_tx_outs));
+        Ok(response)
+    }
+
+    fn get_block_index_by_tx_pub_key_impl(
+        &mut self,
+        request: api::GetBlockIndexByTxPubKeyRequest,
+    ) -> Result<api::GetBlockIndexByTxPubKeyResponse, RpcStatus> {
+        let tx_public_key = RistrettoPublic::try_from(request.get_tx_public_key())
+            .map_err(|err| rpc_internal_error("RistrettoPublic.try_from", err, &self.logger))?;
+
+        let compressed_tx_public_key = CompressedRistrettoPublic::from(&tx_public_key);
+
+        let tx_out_index = self
+            .ledger_db
+            .get_tx_out_index_by_public_key(&compressed_tx_public_key)
+            .map_err(|err| {
+                rpc_internal_error(
+                    "ledger_db.get_tx_out_index_by_public_key",
+                    err,
+                    &self.logger,
+                )
+            })?;
+
+        let block_index = self
+            .ledger_db
+            .get_block_index_by_tx_out_index(tx_out_index)
+            .map_err(|err| {
+                rpc_internal_error(
+                    "ledger_db.get_block_index_by_tx_out_index",
+                    err,
+                    &self.logger,
+                )
+            })?;
+
+        let mut response = api::GetBlockIndexByTxPubKeyResponse::new();
+        response.set_block(block_index);
+        Ok(response)
+    }
+
+    fn subscribe_monitor_events_impl(
+        &mut self,
+        ctx: RpcContext,
+        request: api::SubscribeMonitorEventsRequest,
+        mut sink: ServerStreamingSink<api::MonitorEvent>,
+    ) {
+        let logger = rpc_logger(&ctx, &self.logger);
+
+        // The stream lives for as long as the client stays subscribed, so it is served
+        // from its own thread, and the number of streams is capped.
+        if self
+            .num_monitor_event_streams
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |num_streams| {
+                (num_streams < MAX_MONITOR_EVENT_STREAMS).then(|| num_streams + 1)
+            })
+            .is_err()
+        {
+            let status = RpcStatus::with_message(
+                RpcStatusCode::RESOURCE_EXHAUSTED,
+                format!(
+                    "Too many monitor event streams, at most {} are served at once",
+                    MAX_MONITOR_EVENT_STREAMS
+                ),
+            );
+            ctx.spawn(
+                sink.fail(status)
+                    .map_err(move |err| log::error!(logger, "failed to reply: {:?}", err))
+                    .map(|_| ()),
+            );
+            return;
+        }
+
+        let service = self.clone();
+        thread::Builder::new()
+            .name("monitor_events".to_string())
+            .spawn(move || {
+                let result = service.stream_monitor_events(request, &mut sink);
+                service
+                    .num_monitor_event_streams
+                    .fetch_sub(1, Ordering::SeqCst);
+
+                if let Err(status) = result {
+                    if let Err(err) = block_on(sink.fail(status)) {
+                        log::error!(logger, "failed to reply: {:?}", err);
+                    }
+                }
+            })
+            .expect("failed starting monitor events thread");
+    }
+
+    /// Stream the events of a monitor into a sink, until the client goes away.
+    fn stream_monitor_events(
+        &self,
+        request: api::SubscribeMonitorEventsRequest,
+        sink: &mut ServerStreamingSink<api::MonitorEvent>,
+    ) -> Result<(), RpcStatus> {
+        // Get MonitorId from from the GRPC request.
+        let monitor_id = MonitorId::try_from(&request.monitor_id)
+            .map_err(|err| rpc_internal_error("monitor_id.try_from.bytes", err, &self.logger))?;
+
+        // Authenticated sender memos are validated against the contacts provided in
+        // the request.
+        let memo_handler = self.get_memo_handler(request.get_contacts())?;
+
+        // Balances are tracked from the requested block, or from the monitor's next
+        // block if it did not process the requested one yet. In that case, the
+        // blocks before the requested one are processed without sending their
+        // events.
+        let mut changes = self.mobilecoind_db.get_monitor_changes();
+        let (monitor_data, mut next_block, mut balances) = self
+            .mobilecoind_db
+            .get_monitor_balances(&monitor_id, request.first_block)
+            .map_err(|err| {
+                rpc_internal_error("mobilecoind_db.get_monitor_balances", err, &self.logger)
+            })?;
+        let mut last_sent = Instant::now();
+        loop {
+            let processed_blocks = self.mobilecoind_db.get_processed_blocks(
+                &monitor_id,
+                next_block,
+                next_block + MONITOR_EVENTS_BATCH_SIZE,
+            );
+
+            // A rescan changes the events of blocks the stream already went through, so the
+            // events and balances it sent no longer hold. This is checked after reading,
+            // since the blocks read may belong to the new scan.
+            let rescan_generation = self
+                .mobilecoind_db
+                .get_monitor_data(&monitor_id)
+                .map_err(|err| {
+                    rpc_internal_error("mobilecoind_db.get_monitor_data", err, &self.logger)
+                })?
+                .rescan_generation;
+            if rescan_generation != monitor_data.rescan_generation {
+                return Err(RpcStatus::with_message(
+                    RpcStatusCode::ABORTED,
+                    "monitor was rescanned, subscribe again".into(),
+                ));
+            }
+
+            let (end_block, processed_blocks) = processed_blocks.map_err(|err| {
+                rpc_internal_error("mobilecoind_db.get_processed_blocks", err, &self.logger)
+            })?;
+
+            // Nothing new, wait for the monitor to process more blocks. Sending fails once
+            // the client is gone, so a heartbeat is sent every now and then to find out.
+            if end_block == next_block {
+                if last_sent.elapsed() >= MONITOR_EVENTS_HEARTBEAT_INTERVAL {
+                    let mut event = api::MonitorEvent::new();
+                    event.set_event_type(api::MonitorEventType::MonitorSynced);
+                    event.set_block(next_block.max(request.first_block));
+                    if !self.send_monitor_event(sink, &monitor_id, event) {
+                        return Ok(());
+                    }
+                    last_sent = Instant::now();
+                }
+                changes = self.mobilecoind_db.wait_for_monitor_changes(
+                    changes,
+                    MONITOR_EVENTS_HEARTBEAT_INTERVAL.saturating_sub(last_sent.elapsed()),
+                );
+                continue;
+            }
+
+            let mut events = Vec::new();
+            for (block_index, processed_tx_outs) in processed_blocks {
+                let mut changed_token_ids = BTreeSet::new();
+                for src in processed_tx_outs.iter() {
+                    let balance = balances.entry(src.token_id).or_default();
+                    let event_type = if src.direction == ProcessedTxOutDirection::Spent as i32 {
+                        *balance = balance.saturating_sub(src.value as u128);
+                        api::MonitorEventType::MonitorTxOutSpent
+                    } else {
+                        *balance += src.value as u128;
+                        api::MonitorEventType::MonitorTxOutReceived
+                    };
+                    changed_token_ids.insert(src.token_id);
+
+                    if block_index >= request.first_block {
+                        let mut event = api::MonitorEvent::new();
+                        event.set_event_type(event_type);
+                        event.set_block(block_index);
+                        event.set_tx_out(self.processed_tx_out_to_proto(
+                            &monitor_id,
+                            &monitor_data,
+                            &memo_handler,
+                            src,
+                        )?);
+                        events.push(event);
+                    }
+                }
+
+                if block_index >= request.first_block {
+                    for token_id in changed_token_ids {
+                        let balance = balances[&token_id];
+                        // It's possible the balance does not fit into a u64.
+                        let balance = u64::try_from(balance).map_err(|_| {
+                            RpcStatus::with_message(
+                                RpcStatusCode::INTERNAL,
+                                format!("balance of {} won't fit in u64", balance),
+                            )
+                        })?;
+
+                        let mut event = api::MonitorEvent::new();
+                        event.set_event_type(api::MonitorEventType::MonitorBalanceChanged);
+                        event.set_block(block_index);
+                        event.set_token_id(token_id);
+                        event.set_balance(balance);
+                        events.push(event);
+                    }
+                }
+            }
+
+            // Let the client know it has seen all events up to this point.
+            if end_block > request.first_block {
+                let mut event = api::MonitorEvent::new();
+                event.set_event_type(api::MonitorEventType::MonitorSynced);
+                event.set_block(end_block);
+                events.push(event);
+            }
+
+            for event in events {
+                if !self.send_monitor_event(sink, &monitor_id, event) {
+                    return Ok(());
+                }
+                last_sent = Instant::now();
+            }
+
+            next_block = end_block;
+        }
+    }
+
+    /// Send an event to a monitor events stream. Returns false if the client
+    /// went away.
+    fn send_monitor_event(
+        &self,
+        sink: &mut ServerStreamingSink<api::MonitorEvent>,
+        monitor_id: &MonitorId,
+        event: api::MonitorEvent,
+    ) -> bool {
+        match block_on(sink.send((event, WriteFlags::default()))) {
+            Ok(()) => true,
+            Err(err) => {
+                log::debug!(
+                    self.logger,
+                    "Stopped streaming events for monitor {}: {}",
+                    monitor_id,
+                    err
+                );
+                false
+            }
+        }
+    }
+
+    fn get_balance_impl(
+        &mut self,
+        request: api::GetBalanceRequest,
+    ) -> Result<api::GetBalanceResponse, RpcStatus> {
+        // Get MonitorId from from the GRPC request.
+        let monitor_id = MonitorId::try_from(&request.monitor_id)
+            .map_err(|err| rpc_internal_error("monitor_id.try_from.bytes", err, &self.logger))?;
+
+        // Get all utxos for this monitor id.
+        let utxos = self
+            .mobilecoind_db
+            .get_utxos_for_subaddress(&monitor_id, request.subaddress_index)
+            .map_err(|err| {
+                rpc_internal_error("mobilecoind_db.get_utxos_for_subaddress", err, &self.logger)
+            })?;
+
+        // Sum them up.
+        let balance = utxos
+            .iter()
+            // Filter only to the requested token id.
+            .filter(|utxo| utxo.token_id == request.token_id)
+            .map(|utxo| utxo.value as u128)
+            .sum::<u128>();
+
+        // It's possible the balance does not fit into a u64.
+        if balance > u64::max_value().into() {
+            return Err(RpcStatus::with_message(
+                RpcStatusCode::INTERNAL,
+                format!(
+                    "balance of {} won't fit in u64, fetch utxo list instead",
+                    balance
+                ),
+            ));
+        }
+
+        // Return response.
+        let mut response = api::GetBalanceResponse::new();
+        response.set_balance(balance as u64);
+        Ok(response)
+    }
+
+    fn send_payment_impl(
+        &mut self,
+        request: api::SendPaymentRequest,
+    ) -> Result<api::SendPaymentResponse, RpcStatus> {
+        // Get sender monitor id from request.
+        let sender_monitor_id = MonitorId::try_from(&request.sender_monitor_id)
+            .map_err(|err| rpc_internal_error("monitor_id.try_from.bytes", err, &self.logger))?;
+
+        // Get all utxos for this monitor id.
+        let mut utxos = self
+            .mobilecoind_db
+            .get_utxos_for_subaddress(&sender_monitor_id, request.sender_subaddress)
+            .map_err(|err| {
+                rpc_internal_error("mobilecoind_db.get_utxos_for_subaddress", err, &self.logger)
+            })?;
+
+        // Optionally filter for max value.
+        if request.max_input_utxo_value > 0 {
+            utxos.retain(|utxo| utxo.value <= request.max_input_utxo_value);
+        }
+
+        // Filter for requested token id.
+        utxos.retain(|utxo| utxo.token_id == request.token_id);
+
+        // Get the list of outlays.
+        let outlays: Vec<Outlay> = request
+            .get_outlay_list()
+            .iter()
+            .map(|outlay_proto| {
+                Outlay::try_from(outlay_proto)
+                    .map_err(|err| rpc_internal_error("outlay.try_from", err, &self.logger))
+            })
+            .collect::<Result<Vec<Outlay>, RpcStatus>>()?;
+
+        // Set change address to sender address unless it has been overridden
+        let change_subaddress = if request.override_change_subaddress {
+            request.change_subaddress
+        } else {
+            request.sender_subaddress
+        };
+
+        // When paying a payment request, reference it in an authenticated sender memo
+        // so that the recipient can match the payment to its request.
+        let opt_memo_builder: Option<Box<dyn MemoBuilder + 'static + Send + Sync>> =
+            if request.payment_request_id != 0 {
+                let monitor_data = self
+                    .mobilecoind_db
+                    .get_monitor_data(&sender_monitor_id)
+                    .map_err(|err| {
+                        rpc_internal_error("mobilecoind_db.get_monitor_data", err, &self.logger)
+                    })?;
+                let account_key = monitor_data
+                    .get_account_key()
+                    .map_err(|err| rpc_invalid_arg_error("sender_monitor_id", err, &self.logger))?;
+
+                let mut memo_builder = RTHMemoBuilder::default();
+                memo_builder.set_sender_credential(
+                    SenderMemoCredential::new_from_address_and_spend_private_key(
+                        &account_key.subaddress(request.sender_subaddress),
+                        account_key.subaddress_spend_private(request.sender_subaddress),
+                    ),
+                );
+                memo_builder.set_payment_request_id(request.payment_request_id);
+                memo_builder.enable_destination_memo();
+                Some(Box::new(memo_builder))
+            } else {
+                None
+            };
+
+        // Attempt to construct a transaction.
+        let tx_proposal = self
+            .transactions_manager
+            .build_transaction(
+                &sender_monitor_id,
+                TokenId::from(request.token_id),
+                change_subaddress,
+                &utxos,
+                &outlays,
+                &self.get_last_block_infos(),
+                request.fee,
+                request.tombstone,
+                opt_memo_builder,
+            )
+            .map_err(|err| {
+                rpc_internal_error("transactions_manager.build_transaction", err, &self.logger)
+            })?;
+
+        let proto_tx_proposal = api::TxProposal::from(&tx_proposal);
+
+        // Submit transaction.
+        let mut submit_tx_request = api::SubmitTxRequest::new();
+        submit_tx_request.set_tx_proposal(proto_tx_proposal.clone());
+        let mut submit_tx_response = self.submit_tx_impl(submit_tx_request)?;
+
+        // Return response.
+        let mut response = api::SendPaymentResponse::new();
+        response.set_sender_tx_receipt(submit_tx_response.take_sender_tx_receipt());
+        response.set_receiver_tx_receipt_list(submit_tx_response.take_receiver_tx_receipt_list());
+        response.set_tx_proposal(proto_tx_proposal);
+        Ok(response)
+    }
+
+    fn pay_address_code_impl(
+        &mut self,
+        request: api::PayAddressCodeRequest,
+    ) -> Result<api::SendPaymentResponse, RpcStatus> {
+        // Sanity check.
+        if request.get_amount() == 0 {
+            return Err(RpcStatus::with_message(
+                RpcStatusCode::INVALID_ARGUMENT,
+                "amount".into(),
+            ));
+        }
+
+        // Try and decode the address code.
+        let mut parse_address_code_request = api::ParseAddressCodeRequest::new();
+        parse_address_code_request.set_b58_code(request.get_receiver_b58_code().to_owned());
+        let parse_address_code_response =
+            self.parse_address_code_impl(parse_address_code_request)?;
+
+        // Forward to SendPayment
+        let mut outlay = api::Outlay::new();
+        outlay.set_value(request.get_amount());
+        outlay.set_receiver(parse_address_code_response.get_receiver().clone());
+
+        let mut send_payment_request = api::SendPaymentRequest::new();
+        send_payment_request.set_sender_monitor_id(request.get_sender_monitor_id().to_vec());
+        send_payment_request.set_sender_subaddress(request.get_sender_subaddress());
+        send_payment_request.set_outlay_list(RepeatedField::from_vec(vec![outlay]));
+        send_payment_request.set_fee(request.get_fee());
+        send_payment_request.set_tombstone(request.get_tombstone());
+        send_payment_request.set_max_input_utxo_value(request.get_max_input_utxo_value());
+        send_payment_request.set_override_change_subaddress(request.override_change_subaddress);
+        send_payment_request.set_change_subaddress(request.change_subaddress);
+        send_payment_request.set_token_id(request.token_id);
+        send_payment_request.set_payment_request_id(parse_address_code_response.get_payment_id());
+
+        self.send_payment_impl(send_payment_request)
+    }
+
+    fn get_network_status_impl(
+        &mut self,
+        _request: api::Empty,
+    ) -> Result<api::GetNetworkStatusResponse, RpcStatus> {
+        let network_state = self.network_state.read().expect("lock poisoned");
+        let num_blocks = self
+            .ledger_db
+            .num_blocks()
+            .map_err(|err| rpc_internal_error("ledger_db.num_blocks", err, &self.logger))?;
+        if num_blocks == 0 {
+            return Err(RpcStatus::with_message(
+                RpcStatusCode::INTERNAL,
+                "no bootstrap block".to_owned(),
+            ));
+        }
+        let local_block_index = num_blocks - 1;
+
+        // Get LastBlockInfo from our peers
+        let block_infos = self.get_last_block_infos();
+        // choose the block info which is latest in terms of block index (we may be
+        // isolated from some of the nodes)
+        let last_block_info = block_infos
+            .into_iter()
+            .max_by_key(|info| info.block_index)
+            .ok_or_else(|| {
+                RpcStatus::with_message(RpcStatusCode::INTERNAL, "no peers reachable".to_owned())
+            })?;
+
+        let mut response = api::GetNetworkStatusResponse::new();
+
+        response.set_network_highest_block_index(
+            network_state.highest_block_index_on_network().unwrap_or(0),
+        );
+        response.set_peer_block_index_map(
+            network_state
+                .peer_to_current_block_index()
+                .iter()
+                .map(|(responder_id, block_index)| (responder_id.to_string(), *block_index))
+                .collect(),
+        );
+        response.set_local_block_index(local_block_index);
+        response.set_is_behind(network_state.is_behind(local_block_index));
+        response.set_last_block_info(last_block_info.into());
+
+        Ok(response)
+    }
+
+    fn set_db_password_impl(
+        &mut self,
+        request: api::SetDbPasswordRequest,
+    ) -> Result<api::Empty, RpcStatus> {
+        // Check if the database is unlocked and allowing this operation.
+        if !self.mobilecoind_db.is_unlocked() {
+            return Err(RpcStatus::with_message(
+                RpcStatusCode::INTERNAL,
+                "must unlock before changing current password".to_owned(),
+            ));
+        }
+
+        // Re-encrypt data using the new password.
+        if request.get_passphrase().is_empty() {
+            self.mobilecoind_db
+                .re_encrypt(request.get_password())
+                .map_err(|err| {
+                    rpc_internal_error("mobilecoind_db.re_encrypt", err, &self.logger)
+                })?;
+        } else {
+            if !request.get_password().is_empty() {
+                return Err(RpcStatus::with_message(
+                    RpcStatusCode::INVALID_ARGUMENT,
+                    "password".into(),
+                ));
+            }
+            self.mobilecoind_db
+                .re_encrypt_with_passphrase(request.get_passphrase().as_bytes())
+                .map_err(|err| {
+                    rpc_internal_error(
+                        "mobilecoind_db.re_encrypt_with_passphrase",
+                        err,
+                        &self.logger,
+                    )
+                })?;
+        }
+
+        log::info!(self.logger, "DB encryption password updated successfully.");
+
+        Ok(api::Empty::default())
+    }
+
+    fn unlock_db_impl(&mut self, request: api::UnlockDbRequest) -> Result<api::Empty, RpcStatus> {
+        if self.mobilecoind_db.is_unlocked() {
+            return Err(RpcStatus::with_message(
+                RpcStatusCode::INTERNAL,
+                "already unlocked".to_owned(),
+            ));
+        }
+
+        if request.get_passphrase().is_empty() {
+            self.mobilecoind_db
+                .check_and_store_password(request.get_password())
+                .map_err(|err| {
+                    rpc_internal_error("mobilecoind_db.check_and_store_password", err, &self.logger)
+                })?;
+        } else {
+            self.mobilecoind_db
+                .check_and_store_passphrase(request.get_passphrase().as_bytes())
+                .map_err(|err| {
+                    rpc_internal_error(
+                        "mobilecoind_db.check_and_store_passphrase",
+                        err,
+                        &self.logger,
+                    )
+                })?;
+        }
+
+        log::info!(self.logger, "Successfully unlocked, starting sync thread.");
+        (self.start_sync_thread)();
+
+        Ok(api::Empty::default())
+    }
+
+    fn get_db_encryption_status_impl(
+        &mut self,
+        _request: api::Empty,
+    ) -> Result<api::GetDbEncryptionStatusResponse, RpcStatus> {
+        let mut response = api::GetDbEncryptionStatusResponse::new();
+        response.set_is_encrypted(self.mobilecoind_db.is_db_encrypted());
+        response.set_is_unlocked(self.mobilecoind_db.is_unlocked());
+
+        let kdf_params = self.mobilecoind_db.get_kdf_params().map_err(|err| {
+            rpc_internal_error("mobilecoind_db.get_kdf_params", err, &self.logger)
+        })?;
+        if let Some(kdf_params) = kdf_params {
+            response.set_key_derivation(api::DbKeyDerivation::DbKeyArgon2id);
+            response.set_kdf_m_cost(kdf_params.m_cost);
+            response.set_kdf_t_cost(kdf_params.t_cost);
+            response.set_kdf_p_cost(kdf_params.p_cost);
+        }
+
+        let audits = self.mobilecoind_db.audit_encryption().map_err(|err| {
+            rpc_internal_error("mobilecoind_db.audit_encryption", err, &self.logger)
+        })?;
+        response.set_audit_list(RepeatedField::from_vec(
+            audits
+                .into_iter()
+                .map(|(database, audit)| {
+                    let mut dst = api::DbEncryptionAudit::new();
+                    dst.set_database(database.to_owned());
+                    dst.set_num_records(audit.num_records);
+                    dst.set_num_unreadable(audit.num_unreadable);
+                    dst
+                })
+                .collect(),
+        ));
+
+        Ok(response)
+    }
+}
+
+macro_rules! build_api {
+    (
+        $( $service_function_name:ident $service_request_type:ident $service_response_type:ident $service_function_impl:ident $(,)?)+
+        $( ; streaming $( $streaming_function_name:ident $streaming_request_type:ident $streaming_response_type:ident $streaming_function_impl:ident $(,)?)+ )?
+    )
+    =>
+    (
+        impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolver> MobilecoindApi for ServiceApi<T, FPR> {
+            $(
+                fn $service_function_name(
+                    &mut self,
+                    ctx: RpcContext,
+                    request: api::$service_request_type,
+                    sink: UnarySink<api::$service_response_type>,
+                ) {
+                    let logger = rpc_logger(&ctx, &self.logger);
+                    send_result(
+                        ctx,
+                        sink,
+                        self.$service_function_impl(request),
+                        &logger,
+                    )
+                }
+            )+
+
+            $($(
+                fn $streaming_function_name(
+                    &mut self,
+                    ctx: RpcContext,
+                    request: api::$streaming_request_type,
+                    sink: ServerStreamingSink<api::$streaming_response_type>,
+                ) {
+                    self.$streaming_function_impl(ctx, request, sink)
+                }
+            )+)?
+        }
+    );
+}
+
+build_api! {
+    // Monitors
+    add_monitor AddMonitorRequest AddMonitorResponse add_monitor_impl,
+    remove_monitor RemoveMonitorRequest Empty remove_monitor_impl,
+    get_monitor_list Empty GetMonitorListResponse get_monitor_list_impl,
+    get_monitor_status GetMonitorStatusRequest GetMonitorStatusResponse get_monitor_status_impl,
+    get_unspent_tx_out_list GetUnspentTxOutListRequest GetUnspentTxOutListResponse get_unspent_tx_out_list_impl,
+    import_key_images ImportKeyImagesRequest ImportKeyImagesResponse import_key_images_impl,
+    set_monitor_webhook SetMonitorWebhookRequest Empty set_monitor_webhook_impl,
+    rescan_monitor RescanMonitorRequest Empty rescan_monitor_impl,
+    update_monitor UpdateMonitorRequest Empty update_monitor_impl,
+
+    // Utilities
+    generate_root_entropy Empty GenerateRootEntropyResponse generate_root_entropy_impl,
+    generate_mnemonic Empty GenerateMnemonicResponse generate_mnemonic_impl,
+    get_account_key_from_root_entropy GetAccountKeyFromRootEntropyRequest GetAccountKeyResponse get_account_key_from_root_entropy_impl,
+    get_account_key_from_mnemonic GetAccountKeyFromMnemonicRequest GetAccountKeyResponse get_account_key_from_mnemonic_impl,
+    get_public_address GetPublicAddressRequest GetPublicAddressResponse get_public_address_impl,
+
+    // b58 codes
+    parse_request_code ParseRequestCodeRequest ParseRequestCodeResponse parse_request_code_impl,
+    create_request_code CreateRequestCodeRequest CreateRequestCodeResponse create_request_code_impl,
+    parse_transfer_code ParseTransferCodeRequest ParseTransferCodeResponse parse_transfer_code_impl,
+    create_transfer_code CreateTransferCodeRequest CreateTransferCodeResponse create_transfer_code_impl,
+    parse_address_code ParseAddressCodeRequest ParseAddressCodeResponse parse_address_code_impl,
+    create_address_code CreateAddressCodeRequest CreateAddressCodeResponse create_address_code_impl,
+
+    // Payment requests
+    create_payment_request CreatePaymentRequestRequest CreatePaymentRequestResponse create_payment_request_impl,
+    get_payment_request GetPaymentRequestRequest GetPaymentRequestResponse get_payment_request_impl,
+    get_payment_request_list GetPaymentRequestListRequest GetPaymentRequestListResponse get_payment_request_list_impl,
+
+    // Transactions
+    get_mixins GetMixinsRequest GetMixinsResponse get_mixins_impl,
+    get_membership_proofs GetMembershipProofsRequest GetMembershipProofsResponse get_membership_proofs_impl,
+    generate_tx GenerateTxRequest GenerateTxResponse generate_tx_impl,
+    generate_optimization_tx GenerateOptimizationTxRequest GenerateOptimizationTxResponse generate_optimization_tx_impl,
+    generate_transfer_code_tx GenerateTransferCodeTxRequest GenerateTransferCodeTxResponse generate_transfer_code_tx_impl,
+    generate_tx_from_tx_out_list GenerateTxFromTxOutListRequest GenerateTxFromTxOutListResponse generate_tx_from_tx_out_list_impl,
+    generate_burn_redemption_tx GenerateBurnRedemptionTxRequest GenerateBurnRedemptionTxResponse generate_burn_redemption_tx_impl,
+    generate_replacement_tx GenerateReplacementTxRequest GenerateReplacementTxResponse generate_replacement_tx_impl,
+    generate_swap GenerateSwapRequest GenerateSwapResponse generate_swap_impl,
+    get_swap_fills GetSwapFillsRequest GetSwapFillsResponse get_swap_fills_impl,
+    generate_swap_fill_tx GenerateSwapFillTxRequest GenerateSwapFillTxResponse generate_swap_fill_tx_impl,
+    generate_gift_code_funding_tx GenerateGiftCodeFundingTxRequest GenerateGiftCodeFundingTxResponse generate_gift_code_funding_tx_impl,
+    create_gift_code CreateGiftCodeRequest CreateGiftCodeResponse create_gift_code_impl,
+    get_gift_code_status GetGiftCodeStatusRequest GetGiftCodeStatusResponse get_gift_code_status_impl,
+    generate_gift_code_claim_tx GenerateGiftCodeClaimTxRequest GenerateGiftCodeClaimTxResponse generate_gift_code_claim_tx_impl,
+    generate_gift_code_cancellation_tx GenerateGiftCodeCancellationTxRequest GenerateGiftCodeCancellationTxResponse generate_gift_code_cancellation_tx_impl,
+    submit_tx SubmitTxRequest SubmitTxResponse submit_tx_impl,
+
+    // Databases
+    get_ledger_info Empty GetLedgerInfoResponse get_ledger_info_impl,
+    get_block_info GetBlockInfoRequest GetBlockInfoResponse get_block_info_impl,
+    get_block GetBlockRequest GetBlockResponse get_block_impl,
+    get_tx_status_as_sender SubmitTxResponse GetTxStatusAsSenderResponse get_tx_status_as_sender_impl,
+    get_tx_status_as_receiver GetTxStatusAsReceiverRequest GetTxStatusAsReceiverResponse get_tx_status_as_receiver_impl,
+    get_processed_block GetProcessedBlockRequest GetProcessedBlockResponse get_processed_block_impl,
+    get_block_index_by_tx_pub_key GetBlockIndexByTxPubKeyRequest GetBlockIndexByTxPubKeyResponse get_block_index_by_tx_pub_key_impl,
+    get_sent_tx_list GetSentTxListRequest GetSentTxListResponse get_sent_tx_list_impl,
+    export_monitor_activity ExportMonitorActivityRequest ExportMonitorActivityResponse export_monitor_activity_impl,
+
+    // Convenience calls
+    get_balance GetBalanceRequest GetBalanceResponse get_balance_impl,
+    send_payment SendPaymentRequest SendPaymentResponse send_payment_impl,
+    pay_address_code PayAddressCodeRequest SendPaymentResponse pay_address_code_impl,
+
+    // Network status
+    get_network_status Empty GetNetworkStatusResponse get_network_status_impl,
+
+    // Database encryption
+    set_db_password SetDbPasswordRequest Empty set_db_password_impl,
+    unlock_db UnlockDbRequest Empty unlock_db_impl,
+    get_db_encryption_status Empty GetDbEncryptionStatusResponse get_db_encryption_status_impl,
+
+    get_version Empty MobilecoindVersionResponse get_version_impl;
+
+    streaming
+    subscribe_monitor_events SubscribeMonitorEventsRequest MonitorEvent subscribe_monitor_events_impl,
+}
+
+#[cfg(test)]
+#[allow(clippy::needless_collect)]
+mod test {
+    use super::*;
+    use crate::{
+        payments::{UnsignedTxProposal, DEFAULT_NEW_TX_BLOCK_ATTEMPTS},
+        subaddress_store::SubaddressSPKId,
+        test_utils::{
+            self, add_block_to_ledger, add_txos_to_ledger, get_testing_environment,
+            wait_for_monitors, DEFAULT_PER_RECIPIENT_AMOUNT,
+        },
+        utxo_store::UnspentTxOut,
+    };
+    use futures::StreamExt;
+    use grpcio::{ClientSStreamReceiver, Error as GrpcError};
+    use mc_account_keys::{
+        burn_address_view_private, AccountKey, PublicAddress, ShortAddressHash,
+        DEFAULT_SUBADDRESS_INDEX,
+    };
+    use mc_blockchain_types::{Block, BlockVersion};
+    use mc_common::{logger::test_with_logger, HashSet};
+    use mc_crypto_keys::RistrettoPrivate;
+    use mc_crypto_rand::RngCore;
+    use mc_fog_report_validation::{FullyValidatedFogPubkey, MockFogPubkeyResolver};
+    use mc_fog_report_validation_test_utils::MockFogResolver;
+    use mc_ledger_db::test_utils::add_txos_and_key_images_to_ledger;
+    use mc_transaction_builder::{EmptyMemoBuilder, TransactionBuilder, TxOutContext};
+    use mc_transaction_core::{
+        constants::{MAX_INPUTS, RING_SIZE},
+        fog_hint::FogHint,
+        get_tx_out_shared_secret,
+        onetime_keys::{recover_onetime_private_key, recover_public_subaddress_spend_key},
+        tokens::Mob,
+        tx::{Tx, TxOut},
+        Amount, Token,
+    };
+    use mc_transaction_extra::{
+        AuthenticatedSenderMemo, AuthenticatedSenderWithPaymentRequestIdMemo, MemoType,
+        SenderMemoCredential,
+    };
+    use mc_util_repr_bytes::{typenum::U32, GenericArray, ReprBytes};
+    use mc_util_uri::FogUri;
+    use rand::{rngs::StdRng, SeedableRng};
+    use std::{assert_matches::assert_matches, str::FromStr};
+
+    const BLOCK_VERSION: BlockVersion = BlockVersion::MAX;
+
+    #[test_with_logger]
+    fn test_add_monitor_impl(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([20u8; 32]);
+
+        // Three random recipients and no monitors.
+        let (ledger_db, mobilecoind_db, client, _server, _server_conn_manager) =
+            get_testing_environment(BLOCK_VERSION, 3, &[], &[], logger.clone(), &mut rng);
+
+        // Create request for adding a new monitor.
+        let data = MonitorData::new(
+            AccountKey::random(&mut rng),
+            DEFAULT_SUBADDRESS_INDEX, // first_subaddress
+            1,                        // num_subaddresses
+            0,                        // first_block
+            "",                       // name
+        )
+        .expect("failed to create data");
+
+        let mut request = api::AddMonitorRequest::new();
+        request.set_account_key(mc_api::external::AccountKey::from(
+            data.account_key.as_ref().unwrap(),
+        ));
+        request.set_first_subaddress(data.first_subaddress);
+        request.set_num_subaddresses(data.num_subaddresses);
+        request.set_first_block(data.first_block);
+
+        // Send request.
+        let response = client.add_monitor(&request).expect("failed to add monitor");
+
+        // Allow the new monitor to process the ledger.
+        wait_for_monitors(&mobilecoind_db, &ledger_db, &logger);
+
+        // Compare the MonitorId we got back to the value we expected.
+        let monitor_id = MonitorId::try_from(&response.monitor_id)
+            .expect("failed to convert response to MonitorId");
+        let expected_monitor_id = MonitorId::try_from(&data).unwrap();
+
+        assert_eq!(expected_monitor_id, monitor_id);
+
+        // Check that the monitor is reported as new
+        assert!(response.is_new);
+
+        // Add the same monitor again
+        let repeated_response = client.add_monitor(&request).expect("failed to add monitor");
+
+        // Compare the MonitorId we got back to the value we expected.
+        let repeated_monitor_id = MonitorId::try_from(&repeated_response.monitor_id)
+            .expect("failed to convert repeated_response to MonitorId");
+
+        assert_eq!(expected_monitor_id, repeated_monitor_id);
+
+        // Check that the monitor is not reported as new
+        assert!(!repeated_response.is_new);
+
+        // Fog monitors are rejected when fog is not configured.
+        request.set_fog(true);
+        assert!(client.add_monitor(&request).is_err());
+    }
+
+    #[test_with_logger]
+    fn test_remove_monitor_impl(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([22u8; 32]);
+
+        // 10 random recipients and no monitors.
+        let (_ledger_db, mobilecoind_db, client, _server, _server_conn_manager) =
+            get_testing_environment(BLOCK_VERSION, 10, &[], &[], logger.clone(), &mut rng);
+
+        let monitors_map = mobilecoind_db.get_monitor_map().unwrap();
+        assert_eq!(0, monitors_map.len());
+
+        // Add some monitors directly to the database.
+        let monitors_to_add = 10;
+        let monitor_ids: Vec<MonitorId> = (0..monitors_to_add)
+            .map(|_i| {
+                let data = MonitorData::new(
+                    AccountKey::random(&mut rng),
+                    DEFAULT_SUBADDRESS_INDEX, // first_subaddress
+                    1,                        // num_subaddresses
+                    0,                        // first_block
+                    "",                       // name
+                )
+                .unwrap();
+                mobilecoind_db.add_monitor(&data).unwrap()
+            })
+            .collect();
+
+        let monitors_map = mobilecoind_db.get_monitor_map().unwrap();
+        assert_eq!(monitors_to_add, monitors_map.len());
+
+        // Remove all the monitors we added.
+        for id in monitor_ids {
+            let mut request = api::RemoveMonitorRequest::new();
+            request.set_monitor_id(id.to_vec());
+            client
+                .remove_monitor(&request)
+                .unwrap_or_else(|_| panic!("failed to remove monitor {}", id));
+        }
+
+        // Check that no monitors remain.
+        let monitors_map = mobilecoind_db.get_monitor_map().unwrap();
+        assert_eq!(0, monitors_map.len());
+    }
+
+    #[test_with_logger]
+    fn test_get_monitor_list_impl(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([21u8; 32]);
+
+        // 10 random recipients and no monitors.
+        let (_ledger_db, mobilecoind_db, client, _server, _server_conn_manager) =
+            get_testing_environment(BLOCK_VERSION, 10, &[], &[], logger.clone(), &mut rng);
+
+        // Add some new monitors directly to the database.
+        let monitors_to_add = 10;
+        let monitor_ids: Vec<MonitorId> = (0..monitors_to_add)
+            .map(|_i| {
+                let data = MonitorData::new(
+                    AccountKey::random(&mut rng),
+                    DEFAULT_SUBADDRESS_INDEX, // first_subaddress
+                    1,                        // num_subaddresses
+                    0,                        // first_block
+                    "",                       // name
+                )
+                .unwrap();
+                let id = mobilecoind_db.add_monitor(&data).unwrap();
+                log::debug!(logger, "adding monitor {}", id,);
+                id
+            })
+            .collect();
+
+        // Ask the api for a list of all monitors.
+        let response = client
+            .get_monitor_list(&api::Empty::new())
+            .expect("failed to get monitor list");
+
+        let monitor_id_list: Vec<MonitorId> = response
+            .monitor_id_list
+            .iter()
+            .map(|bytes| {
+                let id =
+                    MonitorId::try_from(bytes).expect("failed to convert response to MonitorId");
+                log::debug!(logger, "found monitor {}", id,);
+                id
+            })
+            .collect();
+
+        // Check monitor count.
+        assert_eq!(monitors_to_add, monitor_id_list.len());
+
+        // Check that all new monitors are present.
+        assert_eq!(
+            HashSet::from_iter(monitor_id_list.iter()),
+            HashSet::from_iter(monitor_ids.iter()),
+        );
+    }
+
+    #[test_with_logger]
+    fn test_get_monitor_status_impl(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
+
+        // 10 random recipients and no monitors.
+        let (ledger_db, mobilecoind_db, client, _server, _server_conn_manager) =
+            get_testing_environment(BLOCK_VERSION, 10, &[], &[], logger.clone(), &mut rng);
+
+        let data = MonitorData::new(
+            AccountKey::random(&mut rng),
+            10, // first_subaddress
+            20, // num_subaddresses
+            30, // first_block
+            "", // name
+        )
+        .unwrap();
+
+        // Insert into database.
+        let id = mobilecoind_db.add_monitor(&data).unwrap();
+
+        // Allow the new monitor to process the ledger.
+        wait_for_monitors(&mobilecoind_db, &ledger_db, &logger);
+
+        // Query monitor status.
+        let mut request = api::GetMonitorStatusRequest::new();
+        request.set_monitor_id(id.to_vec());
+
+        let response = client
+            .get_monitor_status(&request)
+            .expect("failed to get monitor status");
+        let status = response.status.as_ref().expect("no status in response");
+
+        // Verify the data we got matches what we expected
+        assert_eq!(
+            data.account_key.as_ref().unwrap(),
+            &AccountKey::try_from(status.account_key.as_ref().unwrap()).unwrap(),
+        );
+        assert_eq!(status.first_subaddress, data.first_subaddress);
+        assert_eq!(status.num_subaddresses, data.num_subaddresses);
+        assert_eq!(status.first_block, data.first_block);
+        assert_eq!(status.next_block, data.next_block);
+
+        // Calling get_monitor_status for nonexistent or invalid monitor_id should
+        // return an error.
+        mobilecoind_db.remove_monitor(&id).unwrap();
+
+        let mut request = api::GetMonitorStatusRequest::new();
+        request.set_monitor_id(id.to_vec());
+        assert!(client.get_monitor_status(&request).is_err());
+
+        let request = api::GetMonitorStatusRequest::new();
+        assert!(client.get_monitor_status(&request).is_err());
+
+        let mut request = api::GetMonitorStatusRequest::new();
+        request.set_monitor_id(vec![3; 3]);
+        assert!(client.get_monitor_status(&request).is_err());
+    }
+
+    #[test_with_logger]
+    fn test_set_monitor_webhook_impl(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
+
+        // 10 random recipients and no monitors.
+        let (_ledger_db, mobilecoind_db, client, _server, _server_conn_manager) =
+            get_testing_environment(BLOCK_VERSION, 10, &[], &[], logger.clone(), &mut rng);
+
+        let data = MonitorData::new(
+            AccountKey::random(&mut rng),
+            DEFAULT_SUBADDRESS_INDEX, // first_subaddress
+            1,                        // num_subaddresses
+            0,                        // first_block
+            "",                       // name
+        )
+        .unwrap();
+        let id = mobilecoind_db.add_monitor(&data).unwrap();
+
+        let get_webhook_url = || {
+            let mut request = api::GetMonitorStatusRequest::new();
+            request.set_monitor_id(id.to_vec());
+            client
+                .get_monitor_status(&request)
+                .expect("failed to get monitor status")
+                .get_status()
+                .get_webhook_url()
+                .to_owned()
+        };
+        assert_eq!(get_webhook_url(), "");
+
+        // Set a webhook.
+        let mut request = api::SetMonitorWebhookRequest::new();
+        request.set_monitor_id(id.to_vec());
+        request.set_url("https://example.com/payments".to_owned());
+        request.set_secret(b"secret".to_vec());
+        client.set_monitor_webhook(&request).unwrap();
+
+        assert_eq!(get_webhook_url(), "https://example.com/payments");
+        assert_eq!(
+            mobilecoind_db.get_monitor_webhook(&id).unwrap(),
+            Some(Webhook {
+                url: "https://example.com/payments".to_owned(),
+                secret: b"secret".to_vec(),
+            })
+        );
+
+        // An invalid url should fail.
+        request.set_url("not a url".to_owned());
+        assert!(client.set_monitor_webhook(&request).is_err());
+        assert_eq!(get_webhook_url(), "https://example.com/payments");
+
+        // An empty url removes the webhook.
+        request.set_url("".to_owned());
+        client.set_monitor_webhook(&request).unwrap();
+        assert_eq!(get_webhook_url(), "");
+        assert_eq!(mobilecoind_db.get_monitor_webhook(&id).unwrap(), None);
+
+        // A nonexistent monitor should fail.
+        mobilecoind_db.remove_monitor(&id).unwrap();
+        request.set_url("https://example.com/payments".to_owned());
+        assert!(client.set_monitor_webhook(&request).is_err());
+    }
+
+    #[test_with_logger]
+    fn test_rescan_monitor_impl(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
+
+        let account_key = AccountKey::random(&mut rng);
+        let data = MonitorData::new(
+            account_key.clone(),
+            DEFAULT_SUBADDRESS_INDEX, // first_subaddress
+            1,                        // num_subaddresses
+            5,                        // first_block
+            "test",                   // name
+        )
+        .unwrap();
+
+        // 1 known recipient, 3 random recipients and one monitor that was added with
+        // the wrong first block.
+        let (ledger_db, mobilecoind_db, client, _server, _server_conn_manager) =
+            get_testing_environment(
+                BLOCK_VERSION,
+                3,
+                &[account_key.default_subaddress()],
+                &[data.clone()],
+                logger.clone(),
+                &mut rng,
+            );
+        let id = MonitorId::try_from(&data).unwrap();
+        let num_blocks = ledger_db.num_blocks().unwrap();
+
+        // Allow the new monitor to process the ledger.
+        wait_for_monitors(&mobilecoind_db, &ledger_db, &logger);
+
+        let utxos = mobilecoind_db
+            .get_utxos_for_subaddress(&id, DEFAULT_SUBADDRESS_INDEX)
+            .unwrap();
+        assert_eq!(utxos.len() as u64, num_blocks - 5);
+
+        // Rescan from the origin block.
+        let mut request = api::RescanMonitorRequest::new();
+        request.set_monitor_id(id.to_vec());
+        request.set_first_block(0);
+        client.rescan_monitor(&request).unwrap();
+
+        wait_for_monitors(&mobilecoind_db, &ledger_db, &logger);
+
+        let mut status_request = api::GetMonitorStatusRequest::new();
+        status_request.set_monitor_id(id.to_vec());
+        let status = client
+            .get_monitor_status(&status_request)
+            .unwrap()
+            .take_status();
+        assert_eq!(status.get_first_block(), 0);
+        assert_eq!(status.get_next_block(), num_blocks);
+        assert!(!status.get_is_rescanning());
+        assert_eq!(status.get_rescan_end_block(), num_blocks);
+
+        let utxos = mobilecoind_db
+            .get_utxos_for_subaddress(&id, DEFAULT_SUBADDRESS_INDEX)
+            .unwrap();
+        assert_eq!(utxos.len() as u64, num_blocks);
+
+        // The monitor keeps its name.
+        let monitor_data = mobilecoind_db.get_monitor_data(&id).unwrap();
+        assert_eq!(monitor_data.name, "test");
+
+        // Move the monitor to a range that does not contain the known recipient.
+        let mut request = api::UpdateMonitorRequest::new();
+        request.set_monitor_id(id.to_vec());
+        request.set_first_subaddress(1);
+        request.set_num_subaddresses(10);
+        request.set_first_block(0);
+        client.update_monitor(&request).unwrap();
+
+        wait_for_monitors(&mobilecoind_db, &ledger_db, &logger);
+
+        let status = client
+            .get_monitor_status(&status_request)
+            .unwrap()
+            .take_status();
+        assert_eq!(status.get_first_subaddress(), 1);
+        assert_eq!(status.get_num_subaddresses(), 10);
+        assert_eq!(status.get_next_block(), num_blocks);
+
+        let utxos = mobilecoind_db
+            .get_utxos_for_subaddress(&id, DEFAULT_SUBADDRESS_INDEX)
+            .unwrap();
+        assert!(utxos.is_empty());
+
+        // An empty range should fail.
+        request.set_num_subaddresses(0);
+        assert!(client.update_monitor(&request).is_err());
+
+        // A nonexistent monitor should fail.
+        mobilecoind_db.remove_monitor(&id).unwrap();
+        let mut request = api::RescanMonitorRequest::new();
+        request.set_monitor_id(id.to_vec());
+        assert!(client.rescan_monitor(&request).is_err());
+    }
+
+    #[test_with_logger]
+    fn test_get_unspent_tx_out_list_impl(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
+
+        let account_key = AccountKey::random(&mut rng);
+        let data = MonitorData::new(
+            account_key.clone(),
+            0,  // first_subaddress
+            20, // num_subaddresses
+            0,  // first_block
+            "", // name
+        )
+        .unwrap();
+
+        // 1 known recipient, 3 random recipients and no monitors.
+        let (mut ledger_db, mobilecoind_db, client, _server, _server_conn_manager) =
+            get_testing_environment(
+                BLOCK_VERSION,
+                3,
+                &[account_key.default_subaddress()],
+                &[],
+                logger.clone(),
+                &mut rng,
+            );
+
+        // Add a block with a non-MOB token ID.
+        add_block_to_ledger(
+            &mut ledger_db,
+            BlockVersion::THREE,
+            &vec![
+                AccountKey::random(&mut rng).default_subaddress(),
+                AccountKey::random(&mut rng).default_subaddress(),
+                AccountKey::random(&mut rng).default_subaddress(),
+                account_key.default_subaddress(),
+            ],
+            Amount::new(1000, 2.into()),
+            &[KeyImage::from(101)],
+            &mut rng,
+        )
+        .unwrap();
+
+        // Insert into database.
+        let id = mobilecoind_db.add_monitor(&data).unwrap();
+
+        // Allow the new monitor to process the ledger.
+        wait_for_monitors(&mobilecoind_db, &ledger_db, &logger);
+
+        // Query for unspent tx outs for a subaddress that did not receive any tx outs.
+        let mut request = api::GetUnspentTxOutListRequest::new();
+        request.set_monitor_id(id.to_vec());
+        request.set_subaddress_index(1);
+
+        let response = client
+            .get_unspent_tx_out_list(&request)
+            .expect("failed to get unspent tx out list");
+
+        assert_eq!(response.output_list.to_vec(), vec![]);
+
+        // Query with the correct subaddress index.
+        let mut request = api::GetUnspentTxOutListRequest::new();
+        request.set_monitor_id(id.to_vec());
+        request.set_subaddress_index(0);
+
+        let response = client
+            .get_unspent_tx_out_list(&request)
+            .expect("failed to get unspent tx out list");
+
+        let utxos: Vec<UnspentTxOut> = response
+            .output_list
+            .iter()
+            .map(|proto_utxo| {
+                UnspentTxOut::try_from(proto_utxo).expect("failed converting proto utxo")
+            })
+            .collect();
+
+        // Verify the data we got matches what we expected. This assumes knowledge about
+        // how the test ledger is constructed by the test utils.
+        let num_blocks = ledger_db.num_blocks().unwrap();
+        let account_tx_outs: Vec<TxOut> = (0..num_blocks)
+            .map(|idx| {
+                let block_contents = ledger_db.get_block_contents(idx as u64).unwrap();
+                // We grab the 4th tx out in each block since the test ledger had 3 random
+                // recipients, followed by our known recipient.
+                // See the call to `get_testing_environment` at the beginning of the test.
+                block_contents.outputs[3].clone()
+            })
+            .collect();
+
+        let expected_utxos: Vec<UnspentTxOut> = account_tx_outs
+            .iter()
+            .map(|tx_out| {
+                let (amount, _) = tx_out
+                    .view_key_match(account_key.view_private_key())
+                    .unwrap();
+
+                // Calculate the key image for this tx out.
+                let tx_public_key = RistrettoPublic::try_from(&tx_out.public_key).unwrap();
+                let onetime_private_key = recover_onetime_private_key(
+                    &tx_public_key,
+                    account_key.view_private_key(),
+                    &account_key.subaddress_spend_private(0),
+                );
+                let key_image = KeyImage::from(&onetime_private_key);
+
+                // Craft the expected UnspentTxOut
+                UnspentTxOut {
+                    tx_out: tx_out.clone(),
+                    subaddress_index: 0,
+                    key_image,
+                    value: amount.value,
+                    token_id: *amount.token_id,
+                    attempted_spend_height: 0,
+                    attempted_spend_tombstone: 0,
+                }
+            })
+            .collect();
+
+        // Compare - we should have one less utxo than number of blocks, since the last
+        // one we added is a different token id.
+        assert_eq!(utxos.len(), num_blocks as usize - 1);
+        assert_eq!(
+            HashSet::from_iter(utxos.iter()),
+            HashSet::from_iter(
+                expected_utxos
+                    .iter()
+                    .filter(|utxo| utxo.token_id == *Mob::ID)
+            )
+        );
+
+        // Try with the non-MOB token id.
+        request.set_token_id(2);
+        let response = client
+            .get_unspent_tx_out_list(&request)
+            .expect("failed to get unspent tx out list");
+
+        let utxos: Vec<UnspentTxOut> = response
+            .output_list
+            .iter()
+            .map(|proto_utxo| {
+                UnspentTxOut::try_from(proto_utxo).expect("failed converting proto utxo")
+            })
+            .collect();
+
+        assert_eq!(utxos.len(), 1);
+        assert_eq!(
+            HashSet::from_iter(utxos.iter()),
+            HashSet::from_iter(expected_utxos.iter().filter(|utxo| utxo.token_id == 2))
+        );
+    }
+
+    #[test_with_logger]
+    fn test_generate_root_entropy_impl(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
+
+        // no known recipient, 3 random recipients and no monitors.
+        let (_ledger_db, _mobilecoind_db, client, _server, _server_conn_manager) =
+            get_testing_environment(BLOCK_VERSION, 3, &[], &[], logger.clone(), &mut rng);
+
+        // call get entropy
+        let response = client
+            .generate_root_entropy(&api::Empty::default())
+            .unwrap();
+        let entropy = response.get_root_entropy().to_vec();
+        assert_eq!(entropy.len(), 32);
+        assert_ne!(entropy, vec![0; 32]);
+    }
+
+    #[test_with_logger]
+    fn test_generate_mnemonic_impl(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
+
+        // no known recipient, 3 random recipients and no monitors.
+        let (_ledger_db, _mobilecoind_db, client, _server, _server_conn_manager) =
+            get_testing_environment(BLOCK_VERSION, 3, &[], &[], logger.clone(), &mut rng);
+
+        // call get entropy
+        let response = client.generate_mnemonic(&api::Empty::default()).unwrap();
+        let mnemonic_str = response.get_mnemonic();
+        assert_ne!(mnemonic_str, "");
+
+        // Should be a valid mnemonic.
+        let mnemonic =
+            Mnemonic::from_phrase(mnemonic_str, Language::English).expect("invalid mnemonic_str");
+        assert_eq!(mnemonic.entropy().len(), 32);
+
+        assert_eq!(mnemonic.entropy(), response.get_bip39_entropy());
+    }
+
+    #[test_with_logger]
+    fn test_get_account_key_from_mnemonic_impl(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
+
+        // no known recipient, 3 random recipients and no monitors.
+        let (_ledger_db, _mobilecoind_db, client, _server, _server_conn_manager) =
+            get_testing_environment(BLOCK_VERSION, 3, &[], &[], logger.clone(), &mut rng);
+
+        // Use mnemonic to construct AccountKey.
+        let mnemonic_str =
+            "legal winner thank year wave sausage worth useful legal winner thank yellow";
+        let expected_account_key = {
+            let mnemonic =
+                Mnemonic::from_phrase(mnemonic_str, Language::English).expect("from_phrase failed");
+            let key = mnemonic.derive_slip10_key(666);
+            AccountKey::from(key)
+        };
+
+        let mut request = api::GetAccountKeyFromMnemonicRequest::new();
+        request.set_mnemonic(mnemonic_str.to_string());
+        request.set_account_index(666);
+
+        let response = client.get_account_key_from_mnemonic(&request).unwrap();
+
+        assert_eq!(
+            expected_account_key,
+            AccountKey::try_from(response.get_account_key()).unwrap(),
+        );
+
+        // Calling with no mnemonic or invalid mnemonic should error.
+        let request = api::GetAccountKeyFromMnemonicRequest::new();
+        assert!(client.get_account_key_from_mnemonic(&request).is_err());
+
+        let mut request = api::GetAccountKeyFromMnemonicRequest::new();
+        request.set_mnemonic("lol".to_string());
+        assert!(client.get_account_key_from_mnemonic(&request).is_err());
+    }
+
+    #[test_with_logger]
+    fn test_get_account_key_from_root_entropy_impl(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
+
+        // no known recipient, 3 random recipients and no monitors.
+        let (_ledger_db, _mobilecoind_db, client, _server, _server_conn_manager) =
+            get_testing_environment(BLOCK_VERSION, 3, &[], &[], logger.clone(), &mut rng);
+
+        // Use root entropy to construct AccountKey.
+        let root_entropy = [123u8; 32];
+        let root_id = RootIdentity::from(&root_entropy);
+        let account_key = AccountKey::from(&root_id);
+
+        let mut request = api::GetAccountKeyFromRootEntropyRequest::new();
+        request.set_root_entropy(root_entropy.to_vec());
+
+        let response = client.get_account_key_from_root_entropy(&request).unwrap();
+
+        assert_eq!(
+            account_key,
+            AccountKey::try_from(response.get_account_key()).unwrap(),
+        );
+
+        // Calling with no root entropy or invalid root entropy should error.
+        let request = api::GetAccountKeyFromRootEntropyRequest::new();
+        assert!(client.get_account_key_from_root_entropy(&request).is_err());
+
+        let root_entropy = [123u8; 31];
+        let mut request = api::GetAccountKeyFromRootEntropyRequest::new();
+        request.set_root_entropy(root_entropy.to_vec());
+        assert!(client.get_account_key_from_root_entropy(&request).is_err());
+    }
+
+    #[test_with_logger]
+    fn test_get_public_address_impl(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
+        let account_key = AccountKey::random(&mut rng);
+        let data = MonitorData::new(
+            account_key.clone(),
+            10, // first_subaddress
+            20, // num_subaddresses
+            0,  // first_block
+            "", // name
+        )
+        .unwrap();
+
+        // no known recipient, 3 random recipients and no monitors.
+        let (_ledger_db, mobilecoind_db, client, _server, _server_conn_manager) =
+            get_testing_environment(BLOCK_VERSION, 3, &[], &[], logger.clone(), &mut rng);
+
+        // Insert into database.
+        let id = mobilecoind_db.add_monitor(&data).unwrap();
+
+        // Call get public address.
+        let mut request = api::GetPublicAddressRequest::new();
+        request.set_monitor_id(id.to_vec());
+        request.set_subaddress_index(10);
+        let response = client.get_public_address(&request).unwrap();
+
+        assert_eq!(
+            PublicAddress::try_from(response.get_public_address()).unwrap(),
+            account_key.subaddress(10)
+        );
+
+        // Test that the b58 encoding is correct
+        let mut wrapper = api::printable::PrintableWrapper::new();
+        wrapper.set_public_address((&account_key.subaddress(10)).into());
+        let b58_code = wrapper.b58_encode().unwrap();
+        assert_eq!(response.get_b58_code(), b58_code,);
+
+        // Subaddress that is out of index or an invalid monitor id should error.
+        let request = api::GetPublicAddressRequest::new();
+        assert!(client.get_public_address(&request).is_err());
+
+        let mut request = api::GetPublicAddressRequest::new();
+        request.set_monitor_id(vec![3; 3]);
+        request.set_subaddress_index(10);
+        assert!(client.get_public_address(&request).is_err());
+
+        let mut request = api::GetPublicAddressRequest::new();
+        request.set_monitor_id(id.to_vec());
+        request.set_subaddress_index(0);
+        assert!(client.get_public_address(&request).is_err());
+
+        let mut request = api::GetPublicAddressRequest::new();
+        request.set_monitor_id(id.to_vec());
+        request.set_subaddress_index(1000);
+        assert!(client.get_public_address(&request).is_err());
+    }
+
+    #[test_with_logger]
+    fn test_get_ledger_info_impl(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
+
+        // no known recipient, 3 random recipients and no monitors.
+        let (ledger_db, _mobilecoind_db, client, _server, _server_conn_manager) =
+            get_testing_environment(BLOCK_VERSION, 3, &[], &[], logger.clone(), &mut rng);
+
+        // Call get ledger info.
+        let response = client.get_ledger_info(&api::Empty::new()).unwrap();
+        assert_eq!(response.block_count, ledger_db.num_blocks().unwrap());
+        assert_eq!(response.txo_count, ledger_db.num_txos().unwrap());
+    }
+
+    #[test_with_logger]
+    fn test_get_block_info_impl(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
+
+        // no known recipient, 3 random recipients and no monitors.
+        let (ledger_db, _mobilecoind_db, client, _server, _server_conn_manager) =
+            get_testing_environment(BLOCK_VERSION, 3, &[], &[], logger.clone(), &mut rng);
+
+        // Call get block info for a valid block.
+        let mut request = api::GetBlockInfoRequest::new();
+        request.set_block(0);
+
+        let response = client.get_block_info(&request).unwrap();
+        assert_eq!(response.key_image_count, 0); // test code does not generate any key images
+        assert_eq!(response.txo_count, 3); // 3 recipients = 3 tx outs
+
+        // Call with an invalid block number.
+        let mut request = api::GetBlockInfoRequest::new();
+        request.set_block(ledger_db.num_blocks().unwrap());
+
+        assert!(client.get_block_info(&request).is_err());
+    }
+
+    #[test_with_logger]
+    fn test_get_block_impl(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
+
+        // no known recipient, 3 random recipients and no monitors.
+        let (ledger_db, _mobilecoind_db, client, _server, _server_conn_manager) =
+            get_testing_environment(BLOCK_VERSION, 3, &[], &[], logger.clone(), &mut rng);
+
+        // Call get block info for a valid block.
+        let mut request = api::GetBlockRequest::new();
+        request.set_block(0);
+
+        let response = client.get_block(&request).unwrap();
+        assert_eq!(
+            Block::try_from(response.get_block()).unwrap(),
+            ledger_db.get_block(0).unwrap()
+        );
+        // FIXME: Implement block signatures for mobilecoind and test
+        assert_eq!(response.txos.len(), 3); // 3 recipients = 3 tx outs
+        assert_eq!(response.key_images.len(), 0); // test code does not generate
+                                                  // any key images
+    }
+
+    #[test_with_logger]
+    fn test_get_tx_status_as_sender_impl(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
+
+        // no known recipient, 3 random recipients and no monitors.
+        let (mut ledger_db, _mobilecoind_db, client, _server, _server_conn_manager) =
+            get_testing_environment(BLOCK_VERSION, 3, &[], &[], logger.clone(), &mut rng);
+
+        // Insert a block with some key images in it.
+        let recipient = AccountKey::random(&mut rng).default_subaddress();
+        add_block_to_ledger(
+            &mut ledger_db,
+            BLOCK_VERSION,
+            &[recipient.clone()],
+            Amount::new(DEFAULT_PER_RECIPIENT_AMOUNT, Mob::ID),
+            &[KeyImage::from(1), KeyImage::from(2), KeyImage::from(3)],
+            &mut rng,
+        )
+        .unwrap();
+
+        // Create receiver_tx_receipt based on the txout created in
+        // add_block_to_ledger
+        let block = ledger_db
+            .get_block_contents(ledger_db.num_blocks().unwrap() - 1)
+            .unwrap();
+        let output = block.outputs[0].clone();
+
+        let mut receiver_receipt = api::ReceiverTxReceipt::new();
+        receiver_receipt.set_recipient(api::external::PublicAddress::from(&recipient));
+        receiver_receipt
+            .set_tx_public_key(api::external::CompressedRistretto::from(&output.public_key));
+        receiver_receipt.set_tx_out_hash(output.hash().into());
+        receiver_receipt.set_tombstone(1);
+        // For this test, confirmation number is irrelevant, so left blank
 
-    // Utilities
-    generate_root_entropy Empty GenerateRootEntropyResponse generate_root_entropy_impl,
-    generate_mnemonic Empty GenerateMnemonicResponse generate_mnemonic_impl,
-    get_account_key_from_root_entropy GetAccountKeyFromRootEntropyRequest GetAccountKeyResponse get_account_key_from_root_entropy_impl,
-    get_account_key_from_mnemonic GetAccountKeyFromMnemonicRequest GetAccountKeyResponse get_account_key_from_mnemonic_impl,
-    get_public_address GetPublicAddressRequest GetPublicAddressResponse get_public_address_impl,
+        // A receipt with all key images in the same block is verified.
+        {
+            let mut sender_receipt = api::SenderTxReceipt::new();
+            sender_receipt.set_key_image_list(RepeatedField::from_vec(vec![
+                (&KeyImage::from(1)).into(),
+                (&KeyImage::from(2)).into(),
+                (&KeyImage::from(3)).into(),
+            ]));
+            sender_receipt.set_tombstone(1);
 
-    // b58 codes
-    parse_request_code ParseRequestCodeRequest ParseRequestCodeResponse parse_request_code_impl,
-    create_request_code CreateRequestCodeRequest CreateRequestCodeResponse create_request_code_impl,
-    parse_transfer_code ParseTransferCodeRequest ParseTransferCodeResponse parse_transfer_code_impl,
-    create_transfer_code CreateTransferCodeRequest CreateTransferCodeResponse create_transfer_code_impl,
-    parse_address_code ParseAddressCodeRequest ParseAddressCodeResponse parse_address_code_impl,
-    create_address_code CreateAddressCodeRequest CreateAddressCodeResponse create_address_code_impl,
+            let mut request = api::SubmitTxResponse::new();
+            request.set_sender_tx_receipt(sender_receipt);
+            request.set_receiver_tx_receipt_list(RepeatedField::from_vec(vec![
+                receiver_receipt.clone()
+            ]));
 
-    // Transactions
-    get_mixins GetMixinsRequest GetMixinsResponse get_mixins_impl,
-    get_membership_proofs GetMembershipProofsRequest GetMembershipProofsResponse get_membership_proofs_impl,
-    generate_tx GenerateTxRequest GenerateTxResponse generate_tx_impl,
-    generate_optimization_tx GenerateOptimizationTxRequest GenerateOptimizationTxResponse generate_optimization_tx_impl,
-    generate_transfer_code_tx GenerateTransferCodeTxRequest GenerateTransferCodeTxResponse generate_transfer_code_tx_impl,
-    generate_tx_from_tx_out_list GenerateTxFromTxOutListRequest GenerateTxFromTxOutListResponse generate_tx_from_tx_out_list_impl,
-    generate_burn_redemption_tx GenerateBurnRedemptionTxRequest GenerateBurnRedemptionTxResponse generate_burn_redemption_tx_impl,
-    submit_tx SubmitTxRequest SubmitTxResponse submit_tx_impl,
+            let response = client.get_tx_status_as_sender(&request).unwrap();
 
-    // Databases
-    get_ledger_info Empty GetLedgerInfoResponse get_ledger_info_impl,
-    get_block_info GetBlockInfoRequest GetBlockInfoResponse get_block_info_impl,
-    get_block GetBlockRequest GetBlockResponse get_block_impl,
-    get_tx_status_as_sender SubmitTxResponse GetTxStatusAsSenderResponse get_tx_status_as_sender_impl,
-    get_tx_status_as_receiver GetTxStatusAsReceiverRequest GetTxStatusAsReceiverResponse get_tx_status_as_receiver_impl,
-    get_processed_block GetProcessedBlockRequest GetProcessedBlockResponse get_processed_block_impl,
-    get_block_index_by_tx_pub_key GetBlockIndexByTxPubKeyRequest GetBlockIndexByTxPubKeyResponse get_block_index_by_tx_pub_key_impl,
+            assert_eq!(response.get_status(), api::TxStatus::Verified);
+        }
 
-    // Convenience calls
-    get_balance GetBalanceRequest GetBalanceResponse get_balance_impl,
-    send_payment SendPaymentRequest SendPaymentResponse send_payment_impl,
-    pay_address_code PayAddressCodeRequest SendPaymentResponse pay_address_code_impl,
+        // A receipt with an extra key image should be
+        // TransactionFailureKeyImageBlockMismatch.
+        {
+            let mut sender_receipt = api::SenderTxReceipt::new();
+            sender_receipt.set_key_image_list(RepeatedField::from_vec(vec![
+                (&KeyImage::from(1)).into(),
+                (&KeyImage::from(2)).into(),
+                (&KeyImage::from(3)).into(),
+                (&KeyImage::from(4)).into(),
+            ]));
+            sender_receipt.set_tombstone(1);
 
-    // Network status
-    get_network_status Empty GetNetworkStatusResponse get_network_status_impl,
+            let mut request = api::SubmitTxResponse::new();
+            request.set_sender_tx_receipt(sender_receipt);
+            request.set_receiver_tx_receipt_list(RepeatedField::from_vec(vec![
+                receiver_receipt.clone()
+            ]));
 
-    // Database encryption
-    set_db_password SetDbPasswordRequest Empty set_db_password_impl,
-    unlock_db UnlockDbRequest Empty unlock_db_impl,
+            let response = client.get_tx_status_as_sender(&request).unwrap();
 
-    get_version Empty MobilecoindVersionResponse get_version_impl,
-}
+            assert_eq!(
+                response.get_status(),
+                api::TxStatus::TransactionFailureKeyImageBlockMismatch
+            );
+        }
 
-#[cfg(test)]
-#[allow(clippy::needless_collect)]
-mod test {
-    use super::*;
-    use crate::{
-        payments::DEFAULT_NEW_TX_BLOCK_ATTEMPTS,
-        subaddress_store::SubaddressSPKId,
-        test_utils::{
-            self, add_block_to_ledger, add_txos_to_ledger, get_testing_environment,
-            wait_for_monitors, DEFAULT_PER_RECIPIENT_AMOUNT,
-        },
-        utxo_store::UnspentTxOut,
-    };
-    use grpcio::Error as GrpcError;
-    use mc_account_keys::{
-        burn_address_view_private, AccountKey, PublicAddress, ShortAddressHash,
-        DEFAULT_SUBADDRESS_INDEX,
-    };
-    use mc_blockchain_types::{Block, BlockVersion};
-    use mc_common::{logger::test_with_logger, HashSet};
-    use mc_crypto_keys::RistrettoPrivate;
-    use mc_crypto_rand::RngCore;
-    use mc_fog_report_validation::{FullyValidatedFogPubkey, MockFogPubkeyResolver};
-    use mc_fog_report_validation_test_utils::MockFogResolver;
-    use mc_ledger_db::test_utils::add_txos_and_key_images_to_ledger;
-    use mc_transaction_builder::{EmptyMemoBuilder, TransactionBuilder, TxOutContext};
-    use mc_transaction_core::{
-        constants::{MAX_INPUTS, RING_SIZE},
-        fog_hint::FogHint,
-        get_tx_out_shared_secret,
-        onetime_keys::{recover_onetime_private_key, recover_public_subaddress_spend_key},
-        tokens::Mob,
-        tx::{Tx, TxOut},
-        Amount, Token,
-    };
-    use mc_transaction_extra::MemoType;
-    use mc_util_repr_bytes::{typenum::U32, GenericArray, ReprBytes};
-    use mc_util_uri::FogUri;
-    use rand::{rngs::StdRng, SeedableRng};
-    use std::{assert_matches::assert_matches, str::FromStr};
+        // A receipt with key images that are not in the ledger is pending (unknown) if
+        // its tombstone block has not been exceeded.
+        {
+            let mut sender_receipt = api::SenderTxReceipt::new();
+            sender_receipt.set_key_image_list(RepeatedField::from_vec(vec![
+                (&KeyImage::from(4)).into(),
+                (&KeyImage::from(5)).into(),
+            ]));
+            sender_receipt.set_tombstone(ledger_db.num_blocks().unwrap() as u64 + 1);
 
-    const BLOCK_VERSION: BlockVersion = BlockVersion::MAX;
+            let mut request = api::SubmitTxResponse::new();
+            request.set_sender_tx_receipt(sender_receipt);
+            request.set_receiver_tx_receipt_list(RepeatedField::from_vec(vec![
+                receiver_receipt.clone()
+            ]));
 
-    #[test_with_logger]
-    fn test_add_monitor_impl(logger: Logger) {
-        let mut rng: StdRng = SeedableRng::from_seed([20u8; 32]);
+            let response = client.get_tx_status_as_sender(&request).unwrap();
 
-        // Three random recipients and no monitors.
-        let (ledger_db, mobilecoind_db, client, _server, _server_conn_manager) =
-            get_testing_environment(BLOCK_VERSION, 3, &[], &[], logger.clone(), &mut rng);
+            assert_eq!(response.get_status(), api::TxStatus::Unknown);
+        }
 
-        // Create request for adding a new monitor.
-        let data = MonitorData::new(
-            AccountKey::random(&mut rng),
-            DEFAULT_SUBADDRESS_INDEX, // first_subaddress
-            1,                        // num_subaddresses
-            0,                        // first_block
-            "",                       // name
+        // A receipt with key images that are not in the ledger having its tombstone
+        // block exceeded.
+        {
+            let mut sender_receipt = api::SenderTxReceipt::new();
+            sender_receipt.set_key_image_list(RepeatedField::from_vec(vec![
+                (&KeyImage::from(4)).into(),
+                (&KeyImage::from(5)).into(),
+            ]));
+            sender_receipt.set_tombstone(ledger_db.num_blocks().unwrap() as u64);
+
+            let mut request = api::SubmitTxResponse::new();
+            request.set_sender_tx_receipt(sender_receipt);
+            request.set_receiver_tx_receipt_list(RepeatedField::from_vec(vec![
+                receiver_receipt.clone()
+            ]));
+
+            let response = client.get_tx_status_as_sender(&request).unwrap();
+
+            assert_eq!(response.get_status(), api::TxStatus::TombstoneBlockExceeded);
+        }
+
+        // Add another block to the ledger with different key images, to the same
+        // recipient
+        add_block_to_ledger(
+            &mut ledger_db,
+            BLOCK_VERSION,
+            &[recipient.clone()],
+            Amount::new(DEFAULT_PER_RECIPIENT_AMOUNT, Mob::ID),
+            &[KeyImage::from(4), KeyImage::from(5), KeyImage::from(6)],
+            &mut rng,
         )
-        .expect("failed to create data");
+        .unwrap();
 
-        let mut request = api::AddMonitorRequest::new();
-        request.set_account_key(mc_api::external::AccountKey::from(&data.account_key));
-        request.set_first_subaddress(data.first_subaddress);
-        request.set_num_subaddresses(data.num_subaddresses);
-        request.set_first_block(data.first_block);
+        // A receipt with all the key_images in the ledger, but in different blocks,
+        // should fail.
+        {
+            let mut sender_receipt = api::SenderTxReceipt::new();
+            sender_receipt.set_key_image_list(RepeatedField::from_vec(vec![
+                (&KeyImage::from(1)).into(),
+                (&KeyImage::from(2)).into(),
+                (&KeyImage::from(4)).into(),
+            ]));
+            sender_receipt.set_tombstone(1);
 
-        // Send request.
-        let response = client.add_monitor(&request).expect("failed to add monitor");
+            let mut request = api::SubmitTxResponse::new();
+            request.set_sender_tx_receipt(sender_receipt);
+            request.set_receiver_tx_receipt_list(RepeatedField::from_vec(vec![
+                receiver_receipt.clone()
+            ]));
 
-        // Allow the new monitor to process the ledger.
-        wait_for_monitors(&mobilecoind_db, &ledger_db, &logger);
+            let response = client.get_tx_status_as_sender(&request).unwrap();
 
-        // Compare the MonitorId we got back to the value we expected.
-        let monitor_id = MonitorId::try_from(&response.monitor_id)
-            .expect("failed to convert response to MonitorId");
-        let expected_monitor_id = MonitorId::from(&data);
+            assert_eq!(
+                response.get_status(),
+                api::TxStatus::TransactionFailureKeyImageBlockMismatch
+            );
+        }
+
+        // Create receiver_tx_receipt based on the txout created in
+        // add_block_to_ledger
+        let block2 = ledger_db
+            .get_block_contents(ledger_db.num_blocks().unwrap() - 1)
+            .unwrap();
+        let output2 = block2.outputs[0].clone();
+
+        let mut receiver_receipt2 = api::ReceiverTxReceipt::new();
+        receiver_receipt2.set_recipient(api::external::PublicAddress::from(&recipient));
+        receiver_receipt2.set_tx_public_key(api::external::CompressedRistretto::from(
+            &output2.public_key,
+        ));
+        receiver_receipt2.set_tx_out_hash(output2.hash().into());
+        receiver_receipt2.set_tombstone(1);
+        // For this test, confirmation number is irrelevant, so left blank
+
+        // A receiver receipt with multiple public keys in different blocks should fail
+        {
+            let mut sender_receipt = api::SenderTxReceipt::new();
+            sender_receipt.set_key_image_list(RepeatedField::from_vec(vec![
+                (&KeyImage::from(1)).into(),
+                (&KeyImage::from(2)).into(),
+            ]));
+            sender_receipt.set_tombstone(1);
+
+            let mut request = api::SubmitTxResponse::new();
+            request.set_sender_tx_receipt(sender_receipt);
+            request
+                .mut_receiver_tx_receipt_list()
+                .push(receiver_receipt.clone());
+            request
+                .mut_receiver_tx_receipt_list()
+                .push(receiver_receipt2);
 
-        assert_eq!(expected_monitor_id, monitor_id);
+            let response = client.get_tx_status_as_sender(&request).unwrap();
 
-        // Check that the monitor is reported as new
-        assert!(response.is_new);
+            assert_eq!(
+                response.get_status(),
+                api::TxStatus::PublicKeysInDifferentBlocks
+            );
+        }
 
-        // Add the same monitor again
-        let repeated_response = client.add_monitor(&request).expect("failed to add monitor");
+        // A receipt with a public key which has not landed in the ledger, but
+        // key_images which have should fail.
+        // A receiver receipt with multiple public keys in different blocks should fail
+        {
+            let mut sender_receipt = api::SenderTxReceipt::new();
+            sender_receipt.set_key_image_list(RepeatedField::from_vec(vec![
+                (&KeyImage::from(1)).into(),
+                (&KeyImage::from(4)).into(),
+            ]));
+            sender_receipt.set_tombstone(1);
 
-        // Compare the MonitorId we got back to the value we expected.
-        let repeated_monitor_id = MonitorId::try_from(&repeated_response.monitor_id)
-            .expect("failed to convert repeated_response to MonitorId");
+            let mut request = api::SubmitTxResponse::new();
+            request.set_sender_tx_receipt(sender_receipt);
+            // Modify the receiver_receipt to have a public key not in the ledger
+            receiver_receipt.set_tx_public_key(api::external::CompressedRistretto::from(
+                &CompressedRistrettoPublic::from(&RistrettoPublic::from_random(&mut rng)),
+            ));
+            request.set_receiver_tx_receipt_list(RepeatedField::from_vec(vec![receiver_receipt]));
 
-        assert_eq!(expected_monitor_id, repeated_monitor_id);
+            let response = client.get_tx_status_as_sender(&request).unwrap();
 
-        // Check that the monitor is not reported as new
-        assert!(!repeated_response.is_new);
+            assert_eq!(
+                response.get_status(),
+                api::TxStatus::TransactionFailureKeyImageAlreadySpent
+            );
+        }
     }
 
     #[test_with_logger]
-    fn test_remove_monitor_impl(logger: Logger) {
-        let mut rng: StdRng = SeedableRng::from_seed([22u8; 32]);
-
-        // 10 random recipients and no monitors.
-        let (_ledger_db, mobilecoind_db, client, _server, _server_conn_manager) =
-            get_testing_environment(BLOCK_VERSION, 10, &[], &[], logger.clone(), &mut rng);
+    fn test_get_tx_status_as_receiver_impl(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
 
-        let monitors_map = mobilecoind_db.get_monitor_map().unwrap();
-        assert_eq!(0, monitors_map.len());
+        // no known recipient, 3 random recipients and no monitors.
+        let (mut ledger_db, mobilecoind_db, client, _server, _server_conn_manager) =
+            get_testing_environment(BLOCK_VERSION, 3, &[], &[], logger.clone(), &mut rng);
 
-        // Add some monitors directly to the database.
-        let monitors_to_add = 10;
-        let monitor_ids: Vec<MonitorId> = (0..monitors_to_add)
-            .map(|_i| {
-                let data = MonitorData::new(
-                    AccountKey::random(&mut rng),
-                    DEFAULT_SUBADDRESS_INDEX, // first_subaddress
-                    1,                        // num_subaddresses
-                    0,                        // first_block
-                    "",                       // name
-                )
-                .unwrap();
-                mobilecoind_db.add_monitor(&data).unwrap()
-            })
-            .collect();
+        // A call with an invalid hash should fail
+        {
+            let mut receipt = api::ReceiverTxReceipt::new();
+            receipt.set_tombstone(1);
 
-        let monitors_map = mobilecoind_db.get_monitor_map().unwrap();
-        assert_eq!(monitors_to_add, monitors_map.len());
+            let mut request = api::GetTxStatusAsReceiverRequest::new();
+            request.set_receipt(receipt);
 
-        // Remove all the monitors we added.
-        for id in monitor_ids {
-            let mut request = api::RemoveMonitorRequest::new();
-            request.set_monitor_id(id.to_vec());
-            client
-                .remove_monitor(&request)
-                .unwrap_or_else(|_| panic!("failed to remove monitor {}", id));
+            assert!(client.get_tx_status_as_receiver(&request).is_err());
         }
 
-        // Check that no monitors remain.
-        let monitors_map = mobilecoind_db.get_monitor_map().unwrap();
-        assert_eq!(0, monitors_map.len());
-    }
+        // A call with a hash thats in the ledger should return Verified
+        {
+            let tx_out = ledger_db.get_tx_out_by_index(1).unwrap();
+            let hash = tx_out.hash();
 
-    #[test_with_logger]
-    fn test_get_monitor_list_impl(logger: Logger) {
-        let mut rng: StdRng = SeedableRng::from_seed([21u8; 32]);
+            let mut receipt = api::ReceiverTxReceipt::new();
+            receipt.set_tx_out_hash(hash.to_vec());
+            receipt.set_tombstone(1);
 
-        // 10 random recipients and no monitors.
-        let (_ledger_db, mobilecoind_db, client, _server, _server_conn_manager) =
-            get_testing_environment(BLOCK_VERSION, 10, &[], &[], logger.clone(), &mut rng);
+            let mut request = api::GetTxStatusAsReceiverRequest::new();
+            request.set_receipt(receipt);
 
-        // Add some new monitors directly to the database.
-        let monitors_to_add = 10;
-        let monitor_ids: Vec<MonitorId> = (0..monitors_to_add)
-            .map(|_i| {
-                let data = MonitorData::new(
-                    AccountKey::random(&mut rng),
-                    DEFAULT_SUBADDRESS_INDEX, // first_subaddress
-                    1,                        // num_subaddresses
-                    0,                        // first_block
-                    "",                       // name
-                )
-                .unwrap();
-                let id = mobilecoind_db.add_monitor(&data).unwrap();
-                log::debug!(logger, "adding monitor {}", id,);
-                id
-            })
-            .collect();
+            let response = client.get_tx_status_as_receiver(&request).unwrap();
+            assert_eq!(response.get_status(), api::TxStatus::Verified);
+        }
 
-        // Ask the api for a list of all monitors.
-        let response = client
-            .get_monitor_list(&api::Empty::new())
-            .expect("failed to get monitor list");
+        // A call with a hash thats is not in the ledger and hasn't exceeded tombstone
+        // block should return Unknown
+        {
+            let hash = [0; 32];
 
-        let monitor_id_list: Vec<MonitorId> = response
-            .monitor_id_list
-            .iter()
-            .map(|bytes| {
-                let id =
-                    MonitorId::try_from(bytes).expect("failed to convert response to MonitorId");
-                log::debug!(logger, "found monitor {}", id,);
-                id
-            })
-            .collect();
+            let mut receipt = api::ReceiverTxReceipt::new();
+            receipt.set_tx_out_hash(hash.to_vec());
+            receipt.set_tombstone(ledger_db.num_blocks().unwrap() as u64 + 1);
 
-        // Check monitor count.
-        assert_eq!(monitors_to_add, monitor_id_list.len());
+            let mut request = api::GetTxStatusAsReceiverRequest::new();
+            request.set_receipt(receipt);
 
-        // Check that all new monitors are present.
-        assert_eq!(
-            HashSet::from_iter(monitor_id_list.iter()),
-            HashSet::from_iter(monitor_ids.iter()),
-        );
-    }
+            let response = client.get_tx_status_as_receiver(&request).unwrap();
+            assert_eq!(response.get_status(), api::TxStatus::Unknown);
+        }
 
-    #[test_with_logger]
-    fn test_get_monitor_status_impl(logger: Logger) {
-        let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
+        // A call with a hash thats is not in the ledger and has exceeded tombstone
+        // block should return TombstoneBlockExceeded
+        {
+            let hash = [0; 32];
 
-        // 10 random recipients and no monitors.
-        let (ledger_db, mobilecoind_db, client, _server, _server_conn_manager) =
-            get_testing_environment(BLOCK_VERSION, 10, &[], &[], logger.clone(), &mut rng);
+            let mut receipt = api::ReceiverTxReceipt::new();
+            receipt.set_tx_out_hash(hash.to_vec());
+            receipt.set_tombstone(ledger_db.num_blocks().unwrap() as u64);
 
+            let mut request = api::GetTxStatusAsReceiverRequest::new();
+            request.set_receipt(receipt);
+
+            let response = client.get_tx_status_as_receiver(&request).unwrap();
+            assert_eq!(response.get_status(), api::TxStatus::TombstoneBlockExceeded);
+        }
+
+        // Now create a monitor for the receiver to test confirmation numbers
+        let receiver = AccountKey::random(&mut rng);
         let data = MonitorData::new(
-            AccountKey::random(&mut rng),
-            10, // first_subaddress
+            receiver.clone(),
+            0,  // first_subaddress
             20, // num_subaddresses
-            30, // first_block
+            0,  // first_block
             "", // name
         )
         .unwrap();
 
         // Insert into database.
-        let id = mobilecoind_db.add_monitor(&data).unwrap();
+        let monitor_id = mobilecoind_db.add_monitor(&data).unwrap();
+        let mut transaction_builder = TransactionBuilder::new(
+            BLOCK_VERSION,
+            Amount::new(Mob::MINIMUM_FEE, Mob::ID),
+            MockFogResolver::default(),
+            EmptyMemoBuilder::default(),
+        )
+        .unwrap();
+        let TxOutContext {
+            tx_out,
+            confirmation,
+            ..
+        } = transaction_builder
+            .add_output(Amount::new(10, Mob::ID), &receiver.subaddress(0), &mut rng)
+            .unwrap();
 
-        // Allow the new monitor to process the ledger.
-        wait_for_monitors(&mobilecoind_db, &ledger_db, &logger);
+        add_txos_to_ledger(&mut ledger_db, BLOCK_VERSION, &[tx_out.clone()], &mut rng).unwrap();
 
-        // Query monitor status.
-        let mut request = api::GetMonitorStatusRequest::new();
-        request.set_monitor_id(id.to_vec());
+        // A request with a valid confirmation number and monitor ID should return
+        // Verified
+        {
+            let hash = tx_out.hash();
 
-        let response = client
-            .get_monitor_status(&request)
-            .expect("failed to get monitor status");
-        let status = response.status.as_ref().expect("no status in response");
+            let mut receipt = api::ReceiverTxReceipt::new();
+            receipt.set_tx_public_key(api::external::CompressedRistretto::from(&tx_out.public_key));
+            receipt.set_tx_out_hash(hash.to_vec());
+            receipt.set_tombstone(10);
+            receipt.set_confirmation_number(confirmation.to_vec());
 
-        // Verify the data we got matches what we expected
-        assert_eq!(
-            data.account_key,
-            AccountKey::try_from(status.account_key.as_ref().unwrap()).unwrap(),
-        );
-        assert_eq!(status.first_subaddress, data.first_subaddress);
-        assert_eq!(status.num_subaddresses, data.num_subaddresses);
-        assert_eq!(status.first_block, data.first_block);
-        assert_eq!(status.next_block, data.next_block);
+            let mut request = api::GetTxStatusAsReceiverRequest::new();
+            request.set_receipt(receipt);
+            request.set_monitor_id(monitor_id.to_vec());
 
-        // Calling get_monitor_status for nonexistent or invalid monitor_id should
-        // return an error.
-        mobilecoind_db.remove_monitor(&id).unwrap();
+            let response = client.get_tx_status_as_receiver(&request).unwrap();
+            assert_eq!(response.get_status(), api::TxStatus::Verified);
+        }
 
-        let mut request = api::GetMonitorStatusRequest::new();
-        request.set_monitor_id(id.to_vec());
-        assert!(client.get_monitor_status(&request).is_err());
+        // A request with an a bad confirmation number and a monitor ID should return
+        // InvalidConfirmationNumber
+        {
+            let hash = tx_out.hash();
 
-        let request = api::GetMonitorStatusRequest::new();
-        assert!(client.get_monitor_status(&request).is_err());
+            let mut receipt = api::ReceiverTxReceipt::new();
+            receipt.set_tx_public_key(api::external::CompressedRistretto::from(&tx_out.public_key));
+            receipt.set_tx_out_hash(hash.to_vec());
+            receipt.set_tombstone(10);
+            receipt.set_confirmation_number(vec![0u8; 32]);
 
-        let mut request = api::GetMonitorStatusRequest::new();
-        request.set_monitor_id(vec![3; 3]);
-        assert!(client.get_monitor_status(&request).is_err());
+            let mut request = api::GetTxStatusAsReceiverRequest::new();
+            request.set_receipt(receipt);
+            request.set_monitor_id(monitor_id.to_vec());
+
+            let response = client.get_tx_status_as_receiver(&request).unwrap();
+            assert_eq!(
+                response.get_status(),
+                api::TxStatus::InvalidConfirmationNumber
+            );
+        }
     }
 
     #[test_with_logger]
-    fn test_get_unspent_tx_out_list_impl(logger: Logger) {
+    fn test_get_processed_block(logger: Logger) {
         let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
 
         let account_key = AccountKey::random(&mut rng);
-        let data = MonitorData::new(
+        // Note: we skip the first block to test what happens when we try and query a
+        // block that will never get processed.
+        let monitor_data = MonitorData::new(
             account_key.clone(),
             0,  // first_subaddress
             20, // num_subaddresses
-            0,  // first_block
+            1,  // first_block
             "", // name
         )
         .unwrap();
@@ -2385,59 +5216,15 @@ mod test {
                 &mut rng,
             );
 
-        // Add a block with a non-MOB token ID.
-        add_block_to_ledger(
-            &mut ledger_db,
-            BlockVersion::THREE,
-            &vec![
-                AccountKey::random(&mut rng).default_subaddress(),
-                AccountKey::random(&mut rng).default_subaddress(),
-                AccountKey::random(&mut rng).default_subaddress(),
-                account_key.default_subaddress(),
-            ],
-            Amount::new(1000, 2.into()),
-            &[KeyImage::from(101)],
-            &mut rng,
-        )
-        .unwrap();
-
         // Insert into database.
-        let id = mobilecoind_db.add_monitor(&data).unwrap();
+        let monitor_id = mobilecoind_db.add_monitor(&monitor_data).unwrap();
 
         // Allow the new monitor to process the ledger.
         wait_for_monitors(&mobilecoind_db, &ledger_db, &logger);
 
-        // Query for unspent tx outs for a subaddress that did not receive any tx outs.
-        let mut request = api::GetUnspentTxOutListRequest::new();
-        request.set_monitor_id(id.to_vec());
-        request.set_subaddress_index(1);
-
-        let response = client
-            .get_unspent_tx_out_list(&request)
-            .expect("failed to get unspent tx out list");
-
-        assert_eq!(response.output_list.to_vec(), vec![]);
-
-        // Query with the correct subaddress index.
-        let mut request = api::GetUnspentTxOutListRequest::new();
-        request.set_monitor_id(id.to_vec());
-        request.set_subaddress_index(0);
-
-        let response = client
-            .get_unspent_tx_out_list(&request)
-            .expect("failed to get unspent tx out list");
-
-        let utxos: Vec<UnspentTxOut> = response
-            .output_list
-            .iter()
-            .map(|proto_utxo| {
-                UnspentTxOut::try_from(proto_utxo).expect("failed converting proto utxo")
-            })
-            .collect();
-
         // Verify the data we got matches what we expected. This assumes knowledge about
         // how the test ledger is constructed by the test utils.
-        let num_blocks = ledger_db.num_blocks().unwrap();
+        let num_blocks = ledger_db.num_blocks().expect("failed getting num blocks");
         let account_tx_outs: Vec<TxOut> = (0..num_blocks)
             .map(|idx| {
                 let block_contents = ledger_db.get_block_contents(idx as u64).unwrap();
@@ -2451,10 +5238,6 @@ mod test {
         let expected_utxos: Vec<UnspentTxOut> = account_tx_outs
             .iter()
             .map(|tx_out| {
-                let (amount, _) = tx_out
-                    .view_key_match(account_key.view_private_key())
-                    .unwrap();
-
                 // Calculate the key image for this tx out.
                 let tx_public_key = RistrettoPublic::try_from(&tx_out.public_key).unwrap();
                 let onetime_private_key = recover_onetime_private_key(
@@ -2469,575 +5252,1152 @@ mod test {
                     tx_out: tx_out.clone(),
                     subaddress_index: 0,
                     key_image,
-                    value: amount.value,
-                    token_id: *amount.token_id,
+                    value: test_utils::DEFAULT_PER_RECIPIENT_AMOUNT,
                     attempted_spend_height: 0,
                     attempted_spend_tombstone: 0,
+                    token_id: *Mob::ID,
                 }
             })
             .collect();
 
-        // Compare - we should have one less utxo than number of blocks, since the last
-        // one we added is a different token id.
-        assert_eq!(utxos.len(), num_blocks as usize - 1);
-        assert_eq!(
-            HashSet::from_iter(utxos.iter()),
-            HashSet::from_iter(
-                expected_utxos
-                    .iter()
-                    .filter(|utxo| utxo.token_id == *Mob::ID)
+        // Query a bunch of blocks and verify the data.
+        for block_index in 1..num_blocks {
+            let mut request = api::GetProcessedBlockRequest::new();
+            request.set_monitor_id(monitor_id.to_vec());
+            request.set_block(block_index);
+
+            let response = client
+                .get_processed_block(&request)
+                .expect("failed to get processed block");
+
+            // We expect one utxo per block for our monitor.
+            let tx_outs = response.get_tx_outs();
+            assert_eq!(tx_outs.len(), 1);
+            let tx_out = &tx_outs[0];
+
+            let expected_utxo = &expected_utxos[block_index as usize];
+
+            assert_eq!(tx_out.get_monitor_id().to_vec(), monitor_id.to_vec());
+            assert_eq!(
+                tx_out.get_subaddress_index(),
+                expected_utxo.subaddress_index
+            );
+            assert_eq!(
+                tx_out.get_public_key(),
+                &(&expected_utxo.tx_out.public_key).into(),
+            );
+            assert_eq!(tx_out.get_key_image(), &(&expected_utxo.key_image).into());
+            assert_eq!(tx_out.value, expected_utxo.value);
+            assert_eq!(
+                tx_out.get_direction(),
+                api::ProcessedTxOutDirection::Received,
+            );
+
+            // test address code
+            let mut request = api::GetPublicAddressRequest::new();
+            request.set_monitor_id(monitor_id.to_vec());
+            request.set_subaddress_index(expected_utxo.subaddress_index);
+            let response = client.get_public_address(&request).unwrap();
+            let public_address = PublicAddress::try_from(response.get_public_address()).unwrap();
+
+            let mut request = api::CreateAddressCodeRequest::new();
+            request.set_receiver(mc_api::external::PublicAddress::from(&public_address));
+            let response = client.create_address_code(&request).unwrap();
+            let b58_code = response.get_b58_code();
+
+            assert_eq!(tx_out.get_address_code(), b58_code);
+
+            assert_eq!(tx_out.token_id, *Mob::ID);
+        }
+
+        // Add a block with a key images that spend the first two utxos and see that we
+        // get the data we expect.
+        {
+            let recipient = AccountKey::random(&mut rng).default_subaddress();
+            add_block_to_ledger(
+                &mut ledger_db,
+                BLOCK_VERSION,
+                &[recipient],
+                Amount::new(DEFAULT_PER_RECIPIENT_AMOUNT, Mob::ID),
+                &[
+                    expected_utxos[monitor_data.first_block as usize].key_image,
+                    expected_utxos[monitor_data.first_block as usize + 1].key_image,
+                ],
+                &mut rng,
             )
-        );
+            .unwrap();
 
-        // Try with the non-MOB token id.
-        request.set_token_id(2);
-        let response = client
-            .get_unspent_tx_out_list(&request)
-            .expect("failed to get unspent tx out list");
+            wait_for_monitors(&mobilecoind_db, &ledger_db, &logger);
 
-        let utxos: Vec<UnspentTxOut> = response
-            .output_list
-            .iter()
-            .map(|proto_utxo| {
-                UnspentTxOut::try_from(proto_utxo).expect("failed converting proto utxo")
-            })
-            .collect();
+            let mut request = api::GetProcessedBlockRequest::new();
+            request.set_monitor_id(monitor_id.to_vec());
+            request.set_block(num_blocks);
 
-        assert_eq!(utxos.len(), 1);
-        assert_eq!(
-            HashSet::from_iter(utxos.iter()),
-            HashSet::from_iter(expected_utxos.iter().filter(|utxo| utxo.token_id == 2))
-        );
-    }
+            let response = client
+                .get_processed_block(&request)
+                .expect("failed to get processed block");
 
-    #[test_with_logger]
-    fn test_generate_root_entropy_impl(logger: Logger) {
-        let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
+            let tx_outs = response.get_tx_outs();
+            assert_eq!(tx_outs.len(), 2);
 
-        // no known recipient, 3 random recipients and no monitors.
-        let (_ledger_db, _mobilecoind_db, client, _server, _server_conn_manager) =
-            get_testing_environment(BLOCK_VERSION, 3, &[], &[], logger.clone(), &mut rng);
+            let expected_utxos_by_key_image = HashMap::from_iter(
+                expected_utxos
+                    .iter()
+                    .skip(monitor_data.first_block as usize)
+                    .take(2)
+                    .map(|utxo| (utxo.key_image, utxo.clone())),
+            );
 
-        // call get entropy
-        let response = client
-            .generate_root_entropy(&api::Empty::default())
+            for tx_out in tx_outs.iter() {
+                let expected_utxo = expected_utxos_by_key_image
+                    .get(
+                        &KeyImage::try_from(tx_out.get_key_image().get_data())
+                            .expect("failed constructing key image"),
+                    )
+                    .expect("failed getting expected utxo");
+
+                assert_eq!(tx_out.get_monitor_id().to_vec(), monitor_id.to_vec());
+                assert_eq!(
+                    tx_out.get_subaddress_index(),
+                    expected_utxo.subaddress_index
+                );
+                assert_eq!(
+                    tx_out.get_public_key(),
+                    &(&expected_utxo.tx_out.public_key).into(),
+                );
+                assert_eq!(tx_out.get_key_image(), &(&expected_utxo.key_image).into());
+                assert_eq!(tx_out.value, expected_utxo.value);
+                assert_eq!(tx_out.get_direction(), api::ProcessedTxOutDirection::Spent,);
+            }
+        }
+
+        // Add a block with a non-MOB token id and see that it gets picked up
+        // correctly.
+        {
+            add_block_to_ledger(
+                &mut ledger_db,
+                BlockVersion::THREE,
+                &[account_key.subaddress(5)],
+                Amount::new(102030, 2.into()),
+                &[KeyImage::from(101)],
+                &mut rng,
+            )
             .unwrap();
-        let entropy = response.get_root_entropy().to_vec();
-        assert_eq!(entropy.len(), 32);
-        assert_ne!(entropy, vec![0; 32]);
-    }
 
-    #[test_with_logger]
-    fn test_generate_mnemonic_impl(logger: Logger) {
-        let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
+            wait_for_monitors(&mobilecoind_db, &ledger_db, &logger);
+
+            let mut request = api::GetProcessedBlockRequest::new();
+            request.set_monitor_id(monitor_id.to_vec());
+            request.set_block(num_blocks + 1);
+
+            let response = client
+                .get_processed_block(&request)
+                .expect("failed to get processed block");
+
+            let tx_outs = response.get_tx_outs();
+            assert_eq!(tx_outs.len(), 1);
+
+            let tx_out = &tx_outs[0];
+            assert_eq!(tx_out.get_monitor_id().to_vec(), monitor_id.to_vec());
+            assert_eq!(tx_out.get_value(), 102030);
+            assert_eq!(
+                tx_out.get_direction(),
+                api::ProcessedTxOutDirection::Received
+            );
+            assert_eq!(tx_out.get_token_id(), 2);
+        }
+
+        // Query a block that will never get processed since its before the monitor's
+        // first block.
+        let mut request = api::GetProcessedBlockRequest::new();
+        request.set_monitor_id(monitor_id.to_vec());
+        request.set_block(0);
 
-        // no known recipient, 3 random recipients and no monitors.
-        let (_ledger_db, _mobilecoind_db, client, _server, _server_conn_manager) =
-            get_testing_environment(BLOCK_VERSION, 3, &[], &[], logger.clone(), &mut rng);
+        assert!(client.get_processed_block(&request).is_err());
 
-        // call get entropy
-        let response = client.generate_mnemonic(&api::Empty::default()).unwrap();
-        let mnemonic_str = response.get_mnemonic();
-        assert_ne!(mnemonic_str, "");
+        // Query a block that hasn't been processed yet.
+        let mut request = api::GetProcessedBlockRequest::new();
+        request.set_monitor_id(monitor_id.to_vec());
+        request.set_block(num_blocks + 2);
 
-        // Should be a valid mnemonic.
-        let mnemonic =
-            Mnemonic::from_phrase(mnemonic_str, Language::English).expect("invalid mnemonic_str");
-        assert_eq!(mnemonic.entropy().len(), 32);
+        assert!(client.get_processed_block(&request).is_err());
 
-        assert_eq!(mnemonic.entropy(), response.get_bip39_entropy());
+        // Query with an unknown monitor id.
+        let mut request = api::GetProcessedBlockRequest::new();
+        request.set_monitor_id(vec![1; 32]);
+        request.set_block(1);
+
+        assert!(client.get_processed_block(&request).is_err());
     }
 
     #[test_with_logger]
-    fn test_get_account_key_from_mnemonic_impl(logger: Logger) {
+    fn test_get_processed_block_sender_memo(logger: Logger) {
         let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
 
-        // no known recipient, 3 random recipients and no monitors.
-        let (_ledger_db, _mobilecoind_db, client, _server, _server_conn_manager) =
-            get_testing_environment(BLOCK_VERSION, 3, &[], &[], logger.clone(), &mut rng);
-
-        // Use mnemonic to construct AccountKey.
-        let mnemonic_str =
-            "legal winner thank year wave sausage worth useful legal winner thank yellow";
-        let expected_account_key = {
-            let mnemonic =
-                Mnemonic::from_phrase(mnemonic_str, Language::English).expect("from_phrase failed");
-            let key = mnemonic.derive_slip10_key(666);
-            AccountKey::from(key)
-        };
+        let account_key = AccountKey::random(&mut rng);
+        let monitor_data = MonitorData::new(
+            account_key.clone(),
+            0,  // first_subaddress
+            20, // num_subaddresses
+            0,  // first_block
+            "", // name
+        )
+        .unwrap();
 
-        let mut request = api::GetAccountKeyFromMnemonicRequest::new();
-        request.set_mnemonic(mnemonic_str.to_string());
-        request.set_account_index(666);
+        let (mut ledger_db, mobilecoind_db, client, _server, _server_conn_manager) =
+            get_testing_environment(BLOCK_VERSION, 3, &[], &[], logger.clone(), &mut rng);
+        let monitor_id = mobilecoind_db.add_monitor(&monitor_data).unwrap();
 
-        let response = client.get_account_key_from_mnemonic(&request).unwrap();
+        // Add a TxOut for our monitor carrying an authenticated sender memo.
+        let sender = AccountKey::random(&mut rng);
+        let sender_addr = sender.default_subaddress();
+        let recipient_addr = account_key.default_subaddress();
+        let tx_out = TxOut::new_with_memo(
+            BLOCK_VERSION,
+            Amount::new(DEFAULT_PER_RECIPIENT_AMOUNT, Mob::ID),
+            &recipient_addr,
+            &RistrettoPrivate::from_random(&mut rng),
+            Default::default(),
+            |memo_ctxt| {
+                Ok(AuthenticatedSenderMemo::new(
+                    &SenderMemoCredential::from(&sender),
+                    recipient_addr.view_public_key(),
+                    &CompressedRistrettoPublic::from(memo_ctxt.tx_public_key),
+                )
+                .into())
+            },
+        )
+        .unwrap();
+        let block_index = ledger_db.num_blocks().unwrap();
+        add_txos_and_key_images_to_ledger(
+            &mut ledger_db,
+            BLOCK_VERSION,
+            vec![tx_out],
+            vec![],
+            &mut rng,
+        )
+        .unwrap();
+        wait_for_monitors(&mobilecoind_db, &ledger_db, &logger);
 
+        // Without contacts, the sender is unknown.
+        let mut request = api::GetProcessedBlockRequest::new();
+        request.set_monitor_id(monitor_id.to_vec());
+        request.set_block(block_index);
+        let response = client.get_processed_block(&request).unwrap();
+        let tx_outs = response.get_tx_outs();
+        assert_eq!(tx_outs.len(), 1);
         assert_eq!(
-            expected_account_key,
-            AccountKey::try_from(response.get_account_key()).unwrap(),
+            tx_outs[0].get_memo_classification(),
+            api::ProcessedTxOutMemoClassification::MemoUnknownSender
+        );
+        assert_eq!(
+            tx_outs[0].get_sender_address_hash(),
+            ShortAddressHash::from(&sender_addr).as_ref()
         );
+        assert_eq!(tx_outs[0].get_sender_address_code(), "");
 
-        // Calling with no mnemonic or invalid mnemonic should error.
-        let request = api::GetAccountKeyFromMnemonicRequest::new();
-        assert!(client.get_account_key_from_mnemonic(&request).is_err());
+        // With the sender as a contact, the memo is authenticated.
+        request.set_contacts(RepeatedField::from_vec(vec![(&sender_addr).into()]));
+        let response = client.get_processed_block(&request).unwrap();
+        let tx_outs = response.get_tx_outs();
+        assert_eq!(tx_outs.len(), 1);
+        assert_eq!(
+            tx_outs[0].get_memo_classification(),
+            api::ProcessedTxOutMemoClassification::MemoAuthenticated
+        );
 
-        let mut request = api::GetAccountKeyFromMnemonicRequest::new();
-        request.set_mnemonic("lol".to_string());
-        assert!(client.get_account_key_from_mnemonic(&request).is_err());
+        let mut wrapper = api::printable::PrintableWrapper::new();
+        wrapper.set_public_address((&sender_addr).into());
+        assert_eq!(
+            tx_outs[0].get_sender_address_code(),
+            wrapper.b58_encode().unwrap()
+        );
     }
 
     #[test_with_logger]
-    fn test_get_account_key_from_root_entropy_impl(logger: Logger) {
+    fn test_export_monitor_activity(logger: Logger) {
         let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
 
-        // no known recipient, 3 random recipients and no monitors.
-        let (_ledger_db, _mobilecoind_db, client, _server, _server_conn_manager) =
-            get_testing_environment(BLOCK_VERSION, 3, &[], &[], logger.clone(), &mut rng);
+        let account_key = AccountKey::random(&mut rng);
+        let monitor_data = MonitorData::new(
+            account_key.clone(),
+            0,  // first_subaddress
+            20, // num_subaddresses
+            1,  // first_block
+            "", // name
+        )
+        .unwrap();
 
-        // Use root entropy to construct AccountKey.
-        let root_entropy = [123u8; 32];
-        let root_id = RootIdentity::from(&root_entropy);
-        let account_key = AccountKey::from(&root_id);
+        // 1 known recipient, 3 random recipients and no monitors.
+        let (mut ledger_db, mobilecoind_db, client, _server, _server_conn_manager) =
+            get_testing_environment(
+                BLOCK_VERSION,
+                3,
+                &[account_key.default_subaddress()],
+                &[],
+                logger.clone(),
+                &mut rng,
+            );
+        let monitor_id = mobilecoind_db.add_monitor(&monitor_data).unwrap();
+        wait_for_monitors(&mobilecoind_db, &ledger_db, &logger);
+        let num_blocks = ledger_db.num_blocks().unwrap();
 
-        let mut request = api::GetAccountKeyFromRootEntropyRequest::new();
-        request.set_root_entropy(root_entropy.to_vec());
+        // Add a TxOut carrying an authenticated sender memo, and a block spending the
+        // TxOut received in the monitor's first block.
+        let sender = AccountKey::random(&mut rng);
+        let sender_addr = sender.default_subaddress();
+        let recipient_addr = account_key.default_subaddress();
+        let tx_out = TxOut::new_with_memo(
+            BLOCK_VERSION,
+            Amount::new(DEFAULT_PER_RECIPIENT_AMOUNT, Mob::ID),
+            &recipient_addr,
+            &RistrettoPrivate::from_random(&mut rng),
+            Default::default(),
+            |memo_ctxt| {
+                Ok(AuthenticatedSenderMemo::new(
+                    &SenderMemoCredential::from(&sender),
+                    recipient_addr.view_public_key(),
+                    &CompressedRistrettoPublic::from(memo_ctxt.tx_public_key),
+                )
+                .into())
+            },
+        )
+        .unwrap();
+        add_txos_and_key_images_to_ledger(
+            &mut ledger_db,
+            BLOCK_VERSION,
+            vec![tx_out],
+            vec![],
+            &mut rng,
+        )
+        .unwrap();
 
-        let response = client.get_account_key_from_root_entropy(&request).unwrap();
+        let first_tx_out = &ledger_db.get_block_contents(1).unwrap().outputs[3];
+        let spent_utxo = mobilecoind_db
+            .get_utxos_for_subaddress(&monitor_id, 0)
+            .unwrap()
+            .into_iter()
+            .find(|utxo| utxo.tx_out.public_key == first_tx_out.public_key)
+            .unwrap();
+        add_block_to_ledger(
+            &mut ledger_db,
+            BLOCK_VERSION,
+            &[AccountKey::random(&mut rng).default_subaddress()],
+            Amount::new(DEFAULT_PER_RECIPIENT_AMOUNT, Mob::ID),
+            &[spent_utxo.key_image],
+            &mut rng,
+        )
+        .unwrap();
+        wait_for_monitors(&mobilecoind_db, &ledger_db, &logger);
+
+        // Export everything the monitor processed.
+        let mut request = api::ExportMonitorActivityRequest::new();
+        request.set_monitor_id(monitor_id.to_vec());
+        request.set_first_block(1);
+        request.set_contacts(RepeatedField::from_vec(vec![(&sender_addr).into()]));
+        let response = client.export_monitor_activity(&request).unwrap();
+        assert_eq!(response.get_end_block(), num_blocks + 2);
+
+        // One TxOut received per block, followed by the spent one.
+        let records = response.get_records();
+        assert_eq!(records.len(), num_blocks as usize + 1);
+        for (i, record) in records.iter().enumerate() {
+            let block_index = i as u64 + 1;
+            let expected_timestamp = ledger_db
+                .get_block_signature(record.get_block_index())
+                .map(|signature| signature.signed_at())
+                .unwrap_or(0);
+            assert_eq!(record.get_timestamp(), expected_timestamp);
+            assert_eq!(record.get_token_id(), *Mob::ID);
+            assert_eq!(record.get_value(), DEFAULT_PER_RECIPIENT_AMOUNT);
+            assert_eq!(record.get_subaddress_index(), 0);
+
+            if block_index <= num_blocks {
+                assert_eq!(record.get_block_index(), block_index);
+                assert_eq!(
+                    record.get_direction(),
+                    api::ProcessedTxOutDirection::Received
+                );
+                let expected_status = if block_index == 1 {
+                    api::MonitorActivityStatus::ActivitySpent
+                } else {
+                    api::MonitorActivityStatus::ActivityUnspent
+                };
+                assert_eq!(record.get_status(), expected_status);
+            } else {
+                assert_eq!(record.get_block_index(), num_blocks + 1);
+                assert_eq!(record.get_direction(), api::ProcessedTxOutDirection::Spent);
+                assert_eq!(
+                    record.get_status(),
+                    api::MonitorActivityStatus::ActivitySpent
+                );
+                assert_eq!(
+                    record.get_public_key(),
+                    &(&spent_utxo.tx_out.public_key).into()
+                );
+                assert_eq!(record.get_key_image(), &(&spent_utxo.key_image).into());
+            }
+        }
 
+        // The TxOut with a sender memo names its sender.
+        let record = &records[num_blocks as usize - 1];
+        assert_eq!(record.get_memo_type(), "authenticated_sender");
         assert_eq!(
-            account_key,
-            AccountKey::try_from(response.get_account_key()).unwrap(),
+            record.get_counterparty_address_hash(),
+            ShortAddressHash::from(&sender_addr).as_ref()
         );
 
-        // Calling with no root entropy or invalid root entropy should error.
-        let request = api::GetAccountKeyFromRootEntropyRequest::new();
-        assert!(client.get_account_key_from_root_entropy(&request).is_err());
-
-        let root_entropy = [123u8; 31];
-        let mut request = api::GetAccountKeyFromRootEntropyRequest::new();
-        request.set_root_entropy(root_entropy.to_vec());
-        assert!(client.get_account_key_from_root_entropy(&request).is_err());
+        // Exports can be split into several calls.
+        request.set_end_block(3);
+        let response = client.export_monitor_activity(&request).unwrap();
+        assert_eq!(response.get_end_block(), 3);
+        assert_eq!(response.get_records(), &records[..2]);
+
+        request.set_first_block(3);
+        request.set_end_block(0);
+        let response = client.export_monitor_activity(&request).unwrap();
+        assert_eq!(response.get_end_block(), num_blocks + 2);
+        assert_eq!(response.get_records(), &records[2..]);
+
+        // Blocks before the monitor's first block cannot be exported.
+        request.set_first_block(0);
+        assert!(client.export_monitor_activity(&request).is_err());
     }
 
     #[test_with_logger]
-    fn test_get_public_address_impl(logger: Logger) {
-        let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
+    fn test_payment_requests(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([24u8; 32]);
+
         let account_key = AccountKey::random(&mut rng);
-        let data = MonitorData::new(
+        let monitor_data = MonitorData::new(
             account_key.clone(),
-            10, // first_subaddress
+            0,  // first_subaddress
             20, // num_subaddresses
             0,  // first_block
             "", // name
         )
         .unwrap();
 
-        // no known recipient, 3 random recipients and no monitors.
-        let (_ledger_db, mobilecoind_db, client, _server, _server_conn_manager) =
-            get_testing_environment(BLOCK_VERSION, 3, &[], &[], logger.clone(), &mut rng);
-
-        // Insert into database.
-        let id = mobilecoind_db.add_monitor(&data).unwrap();
+        // 1 known recipient, 3 random recipients and no monitors.
+        let (mut ledger_db, mobilecoind_db, client, _server, _server_conn_manager) =
+            get_testing_environment(
+                BLOCK_VERSION,
+                3,
+                &[account_key.default_subaddress()],
+                &[],
+                logger.clone(),
+                &mut rng,
+            );
+        let monitor_id = mobilecoind_db.add_monitor(&monitor_data).unwrap();
+        wait_for_monitors(&mobilecoind_db, &ledger_db, &logger);
 
-        // Call get public address.
-        let mut request = api::GetPublicAddressRequest::new();
-        request.set_monitor_id(id.to_vec());
-        request.set_subaddress_index(10);
-        let response = client.get_public_address(&request).unwrap();
+        // Requests can only be issued to the monitor's subaddresses.
+        let mut request = api::CreatePaymentRequestRequest::new();
+        request.set_monitor_id(monitor_id.to_vec());
+        request.set_subaddress_index(20);
+        request.set_value(2 * DEFAULT_PER_RECIPIENT_AMOUNT);
+        request.set_token_id(*Mob::ID);
+        request.set_memo("invoice #1".to_owned());
+        assert!(client.create_payment_request(&request).is_err());
 
+        request.set_subaddress_index(1);
+        let response = client.create_payment_request(&request).unwrap();
+        let payment_request = response.get_payment_request();
+        assert_ne!(payment_request.get_id(), 0);
         assert_eq!(
-            PublicAddress::try_from(response.get_public_address()).unwrap(),
-            account_key.subaddress(10)
+            payment_request.get_monitor_id().to_vec(),
+            monitor_id.to_vec()
+        );
+        assert_eq!(payment_request.get_subaddress_index(), 1);
+        assert_eq!(
+            payment_request.get_created_block(),
+            ledger_db.num_blocks().unwrap()
+        );
+        assert_eq!(
+            payment_request.get_status(),
+            api::PaymentRequestStatus::PaymentRequestPending
         );
 
-        // Test that the b58 encoding is correct
-        let mut wrapper = api::printable::PrintableWrapper::new();
-        wrapper.set_public_address((&account_key.subaddress(10)).into());
-        let b58_code = wrapper.b58_encode().unwrap();
-        assert_eq!(response.get_b58_code(), b58_code,);
-
-        // Subaddress that is out of index or an invalid monitor id should error.
-        let request = api::GetPublicAddressRequest::new();
-        assert!(client.get_public_address(&request).is_err());
+        // The request code carries the id, and paying it references the request.
+        let mut parse_request = api::ParseRequestCodeRequest::new();
+        parse_request.set_b58_code(payment_request.get_b58_code().to_owned());
+        let parsed = client.parse_request_code(&parse_request).unwrap();
+        assert_eq!(parsed.get_payment_id(), payment_request.get_id());
+        assert_eq!(
+            PublicAddress::try_from(parsed.get_receiver()).unwrap(),
+            account_key.subaddress(1)
+        );
+        assert_eq!(parsed.get_value(), 2 * DEFAULT_PER_RECIPIENT_AMOUNT);
 
-        let mut request = api::GetPublicAddressRequest::new();
-        request.set_monitor_id(vec![3; 3]);
-        request.set_subaddress_index(10);
-        assert!(client.get_public_address(&request).is_err());
+        // Add a TxOut paying half the request, and one carrying another id.
+        let sender = AccountKey::random(&mut rng);
+        let recipient_addr = account_key.subaddress(1);
+        let tx_out_paying_request = |payment_request_id, rng: &mut StdRng| {
+            TxOut::new_with_memo(
+                BLOCK_VERSION,
+                Amount::new(DEFAULT_PER_RECIPIENT_AMOUNT, Mob::ID),
+                &recipient_addr,
+                &RistrettoPrivate::from_random(rng),
+                Default::default(),
+                |memo_ctxt| {
+                    Ok(AuthenticatedSenderWithPaymentRequestIdMemo::new(
+                        &SenderMemoCredential::from(&sender),
+                        recipient_addr.view_public_key(),
+                        &CompressedRistrettoPublic::from(memo_ctxt.tx_public_key),
+                        payment_request_id,
+                    )
+                    .into())
+                },
+            )
+            .unwrap()
+        };
+        let tx_outs = vec![
+            tx_out_paying_request(payment_request.get_id(), &mut rng),
+            tx_out_paying_request(payment_request.get_id().wrapping_add(1), &mut rng),
+        ];
+        let payment_public_key = tx_outs[0].public_key;
+        add_txos_and_key_images_to_ledger(&mut ledger_db, BLOCK_VERSION, tx_outs, vec![], &mut rng)
+            .unwrap();
+        wait_for_monitors(&mobilecoind_db, &ledger_db, &logger);
 
-        let mut request = api::GetPublicAddressRequest::new();
-        request.set_monitor_id(id.to_vec());
-        request.set_subaddress_index(0);
-        assert!(client.get_public_address(&request).is_err());
+        let mut get_request = api::GetPaymentRequestRequest::new();
+        get_request.set_monitor_id(monitor_id.to_vec());
+        get_request.set_payment_request_id(payment_request.get_id());
+        let response = client.get_payment_request(&get_request).unwrap();
+        let tracked = response.get_payment_request();
+        assert_eq!(
+            tracked.get_status(),
+            api::PaymentRequestStatus::PaymentRequestPartiallyPaid
+        );
+        assert_eq!(tracked.get_paid_value(), DEFAULT_PER_RECIPIENT_AMOUNT);
+        assert_eq!(tracked.get_payment_list().len(), 1);
+        assert_eq!(
+            tracked.get_payment_list()[0].get_block_index(),
+            ledger_db.num_blocks().unwrap() - 1
+        );
+        assert_eq!(
+            CompressedRistrettoPublic::try_from(tracked.get_payment_list()[0].get_tx_public_key())
+                .unwrap(),
+            payment_public_key
+        );
 
-        let mut request = api::GetPublicAddressRequest::new();
-        request.set_monitor_id(id.to_vec());
-        request.set_subaddress_index(1000);
-        assert!(client.get_public_address(&request).is_err());
-    }
+        // Pay the rest of the request.
+        let tx_out = tx_out_paying_request(payment_request.get_id(), &mut rng);
+        add_txos_and_key_images_to_ledger(
+            &mut ledger_db,
+            BLOCK_VERSION,
+            vec![tx_out],
+            vec![],
+            &mut rng,
+        )
+        .unwrap();
+        wait_for_monitors(&mobilecoind_db, &ledger_db, &logger);
 
-    #[test_with_logger]
-    fn test_get_ledger_info_impl(logger: Logger) {
-        let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
+        let mut list_request = api::GetPaymentRequestListRequest::new();
+        list_request.set_monitor_id(monitor_id.to_vec());
+        let response = client.get_payment_request_list(&list_request).unwrap();
+        let payment_request_list = response.get_payment_request_list();
+        assert_eq!(payment_request_list.len(), 1);
+        assert_eq!(payment_request_list[0].get_id(), payment_request.get_id());
+        assert_eq!(
+            payment_request_list[0].get_status(),
+            api::PaymentRequestStatus::PaymentRequestPaid
+        );
+        assert_eq!(
+            payment_request_list[0].get_paid_value(),
+            2 * DEFAULT_PER_RECIPIENT_AMOUNT
+        );
 
-        // no known recipient, 3 random recipients and no monitors.
-        let (ledger_db, _mobilecoind_db, client, _server, _server_conn_manager) =
-            get_testing_environment(BLOCK_VERSION, 3, &[], &[], logger.clone(), &mut rng);
+        // Unknown ids are rejected.
+        get_request.set_payment_request_id(payment_request.get_id().wrapping_add(1));
+        assert!(client.get_payment_request(&get_request).is_err());
+    }
 
-        // Call get ledger info.
-        let response = client.get_ledger_info(&api::Empty::new()).unwrap();
-        assert_eq!(response.block_count, ledger_db.num_blocks().unwrap());
-        assert_eq!(response.txo_count, ledger_db.num_txos().unwrap());
+    /// Read the events of a monitor events stream, until it reports the given
+    /// block as synced.
+    fn read_monitor_events(
+        stream: &mut ClientSStreamReceiver<api::MonitorEvent>,
+        synced_block: u64,
+    ) -> Vec<api::MonitorEvent> {
+        let mut events = Vec::new();
+        loop {
+            let event = block_on(stream.next())
+                .expect("stream ended")
+                .expect("stream failed");
+            if event.get_event_type() != api::MonitorEventType::MonitorSynced {
+                events.push(event);
+            } else if event.get_block() >= synced_block {
+                return events;
+            }
+        }
     }
 
     #[test_with_logger]
-    fn test_get_block_info_impl(logger: Logger) {
+    fn test_subscribe_monitor_events(logger: Logger) {
         let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
 
-        // no known recipient, 3 random recipients and no monitors.
-        let (ledger_db, _mobilecoind_db, client, _server, _server_conn_manager) =
-            get_testing_environment(BLOCK_VERSION, 3, &[], &[], logger.clone(), &mut rng);
-
-        // Call get block info for a valid block.
-        let mut request = api::GetBlockInfoRequest::new();
-        request.set_block(0);
-
-        let response = client.get_block_info(&request).unwrap();
-        assert_eq!(response.key_image_count, 0); // test code does not generate any key images
-        assert_eq!(response.txo_count, 3); // 3 recipients = 3 tx outs
+        let account_key = AccountKey::random(&mut rng);
+        let monitor_data = MonitorData::new(
+            account_key.clone(),
+            0,  // first_subaddress
+            20, // num_subaddresses
+            0,  // first_block
+            "", // name
+        )
+        .unwrap();
 
-        // Call with an invalid block number.
-        let mut request = api::GetBlockInfoRequest::new();
-        request.set_block(ledger_db.num_blocks().unwrap());
+        // 1 known recipient, 3 random recipients and no monitors.
+        let (mut ledger_db, mobilecoind_db, client, _server, _server_conn_manager) =
+            get_testing_environment(
+                BLOCK_VERSION,
+                3,
+                &[account_key.default_subaddress()],
+                &[],
+                logger.clone(),
+                &mut rng,
+            );
 
-        assert!(client.get_block_info(&request).is_err());
-    }
+        // Insert into database.
+        let monitor_id = mobilecoind_db.add_monitor(&monitor_data).unwrap();
 
-    #[test_with_logger]
-    fn test_get_block_impl(logger: Logger) {
-        let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
+        // Allow the new monitor to process the ledger.
+        wait_for_monitors(&mobilecoind_db, &ledger_db, &logger);
+        let num_blocks = ledger_db.num_blocks().expect("failed getting num blocks");
 
-        // no known recipient, 3 random recipients and no monitors.
-        let (ledger_db, _mobilecoind_db, client, _server, _server_conn_manager) =
-            get_testing_environment(BLOCK_VERSION, 3, &[], &[], logger.clone(), &mut rng);
+        // Subscribing from the first block replays the entire history. The test ledger
+        // has one utxo for our account per block.
+        let mut request = api::SubscribeMonitorEventsRequest::new();
+        request.set_monitor_id(monitor_id.to_vec());
+        let mut stream = client.subscribe_monitor_events(&request).unwrap();
 
-        // Call get block info for a valid block.
-        let mut request = api::GetBlockRequest::new();
-        request.set_block(0);
+        let events = read_monitor_events(&mut stream, num_blocks);
+        assert_eq!(events.len(), 2 * num_blocks as usize);
+        for (block_index, block_events) in events.chunks(2).enumerate() {
+            assert_eq!(
+                block_events[0].get_event_type(),
+                api::MonitorEventType::MonitorTxOutReceived
+            );
+            assert_eq!(block_events[0].get_block(), block_index as u64);
+            assert_eq!(
+                block_events[0].get_tx_out().get_value(),
+                DEFAULT_PER_RECIPIENT_AMOUNT
+            );
 
-        let response = client.get_block(&request).unwrap();
-        assert_eq!(
-            Block::try_from(response.get_block()).unwrap(),
-            ledger_db.get_block(0).unwrap()
-        );
-        // FIXME: Implement block signatures for mobilecoind and test
-        assert_eq!(response.txos.len(), 3); // 3 recipients = 3 tx outs
-        assert_eq!(response.key_images.len(), 0); // test code does not generate
-                                                  // any key images
-    }
+            assert_eq!(
+                block_events[1].get_event_type(),
+                api::MonitorEventType::MonitorBalanceChanged
+            );
+            assert_eq!(block_events[1].get_block(), block_index as u64);
+            assert_eq!(block_events[1].get_token_id(), *Mob::ID);
+            assert_eq!(
+                block_events[1].get_balance(),
+                (block_index as u64 + 1) * DEFAULT_PER_RECIPIENT_AMOUNT
+            );
+        }
 
-    #[test_with_logger]
-    fn test_get_tx_status_as_sender_impl(logger: Logger) {
-        let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
+        // Resuming from a later block only sends the events from that block onward,
+        // with balances that account for the earlier blocks.
+        {
+            let mut request = api::SubscribeMonitorEventsRequest::new();
+            request.set_monitor_id(monitor_id.to_vec());
+            request.set_first_block(num_blocks - 1);
+            let mut stream = client.subscribe_monitor_events(&request).unwrap();
 
-        // no known recipient, 3 random recipients and no monitors.
-        let (mut ledger_db, _mobilecoind_db, client, _server, _server_conn_manager) =
-            get_testing_environment(BLOCK_VERSION, 3, &[], &[], logger.clone(), &mut rng);
+            let events = read_monitor_events(&mut stream, num_blocks);
+            assert_eq!(
+                events
+                    .iter()
+                    .map(|event| (event.get_event_type(), event.get_block()))
+                    .collect::<Vec<_>>(),
+                vec![
+                    (api::MonitorEventType::MonitorTxOutReceived, num_blocks - 1),
+                    (api::MonitorEventType::MonitorBalanceChanged, num_blocks - 1),
+                ]
+            );
+            assert_eq!(
+                events[1].get_balance(),
+                num_blocks * DEFAULT_PER_RECIPIENT_AMOUNT
+            );
+        }
 
-        // Insert a block with some key images in it.
-        let recipient = AccountKey::random(&mut rng).default_subaddress();
+        // New blocks are pushed to subscribed clients as the monitor processes them.
+        let utxos = mobilecoind_db
+            .get_utxos_for_subaddress(&monitor_id, 0)
+            .unwrap();
         add_block_to_ledger(
             &mut ledger_db,
             BLOCK_VERSION,
-            &[recipient.clone()],
-            Amount::new(DEFAULT_PER_RECIPIENT_AMOUNT, Mob::ID),
-            &[KeyImage::from(1), KeyImage::from(2), KeyImage::from(3)],
+            &[account_key.default_subaddress()],
+            Amount::new(2 * DEFAULT_PER_RECIPIENT_AMOUNT, Mob::ID),
+            &[utxos[0].key_image],
             &mut rng,
         )
         .unwrap();
+        wait_for_monitors(&mobilecoind_db, &ledger_db, &logger);
 
-        // Create receiver_tx_receipt based on the txout created in
-        // add_block_to_ledger
-        let block = ledger_db
-            .get_block_contents(ledger_db.num_blocks().unwrap() - 1)
+        let events = read_monitor_events(&mut stream, num_blocks + 1);
+        assert_eq!(events.len(), 3);
+        assert!(events.iter().all(|event| event.get_block() == num_blocks));
+
+        let spent = events
+            .iter()
+            .find(|event| event.get_event_type() == api::MonitorEventType::MonitorTxOutSpent)
             .unwrap();
-        let output = block.outputs[0].clone();
+        assert_eq!(
+            spent.get_tx_out().get_key_image(),
+            &(&utxos[0].key_image).into()
+        );
+        let received = events
+            .iter()
+            .find(|event| event.get_event_type() == api::MonitorEventType::MonitorTxOutReceived)
+            .unwrap();
+        assert_eq!(
+            received.get_tx_out().get_value(),
+            2 * DEFAULT_PER_RECIPIENT_AMOUNT
+        );
 
-        let mut receiver_receipt = api::ReceiverTxReceipt::new();
-        receiver_receipt.set_recipient(api::external::PublicAddress::from(&recipient));
-        receiver_receipt
-            .set_tx_public_key(api::external::CompressedRistretto::from(&output.public_key));
-        receiver_receipt.set_tx_out_hash(output.hash().into());
-        receiver_receipt.set_tombstone(1);
-        // For this test, confirmation number is irrelevant, so left blank
+        // Balance changes come last.
+        assert_eq!(
+            events[2].get_event_type(),
+            api::MonitorEventType::MonitorBalanceChanged
+        );
+        assert_eq!(
+            events[2].get_balance(),
+            (num_blocks + 1) * DEFAULT_PER_RECIPIENT_AMOUNT
+        );
 
-        // A receipt with all key images in the same block is verified.
+        // Resuming from the block with the spend computes the balance before it from
+        // the monitor's unspent TxOuts.
         {
-            let mut sender_receipt = api::SenderTxReceipt::new();
-            sender_receipt.set_key_image_list(RepeatedField::from_vec(vec![
-                (&KeyImage::from(1)).into(),
-                (&KeyImage::from(2)).into(),
-                (&KeyImage::from(3)).into(),
-            ]));
-            sender_receipt.set_tombstone(1);
-
-            let mut request = api::SubmitTxResponse::new();
-            request.set_sender_tx_receipt(sender_receipt);
-            request.set_receiver_tx_receipt_list(RepeatedField::from_vec(vec![
-                receiver_receipt.clone()
-            ]));
-
-            let response = client.get_tx_status_as_sender(&request).unwrap();
+            let mut request = api::SubscribeMonitorEventsRequest::new();
+            request.set_monitor_id(monitor_id.to_vec());
+            request.set_first_block(num_blocks);
+            let mut stream = client.subscribe_monitor_events(&request).unwrap();
 
-            assert_eq!(response.get_status(), api::TxStatus::Verified);
+            let events = read_monitor_events(&mut stream, num_blocks + 1);
+            assert_eq!(events.len(), 3);
+            assert_eq!(
+                events[2].get_event_type(),
+                api::MonitorEventType::MonitorBalanceChanged
+            );
+            assert_eq!(
+                events[2].get_balance(),
+                (num_blocks + 1) * DEFAULT_PER_RECIPIENT_AMOUNT
+            );
         }
 
-        // A receipt with an extra key image should be
-        // TransactionFailureKeyImageBlockMismatch.
-        {
-            let mut sender_receipt = api::SenderTxReceipt::new();
-            sender_receipt.set_key_image_list(RepeatedField::from_vec(vec![
-                (&KeyImage::from(1)).into(),
-                (&KeyImage::from(2)).into(),
-                (&KeyImage::from(3)).into(),
-                (&KeyImage::from(4)).into(),
-            ]));
-            sender_receipt.set_tombstone(1);
+        // Subscribing to an unknown monitor fails.
+        let mut request = api::SubscribeMonitorEventsRequest::new();
+        request.set_monitor_id(vec![1; 32]);
+        let mut stream = client.subscribe_monitor_events(&request).unwrap();
+        assert!(block_on(stream.next()).unwrap().is_err());
+    }
 
-            let mut request = api::SubmitTxResponse::new();
-            request.set_sender_tx_receipt(sender_receipt);
-            request.set_receiver_tx_receipt_list(RepeatedField::from_vec(vec![
-                receiver_receipt.clone()
-            ]));
+    #[test_with_logger]
+    fn test_subscribe_monitor_events_rescan(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([24u8; 32]);
 
-            let response = client.get_tx_status_as_sender(&request).unwrap();
+        let account_key = AccountKey::random(&mut rng);
+        let monitor_data = MonitorData::new(
+            account_key.clone(),
+            0,  // first_subaddress
+            20, // num_subaddresses
+            0,  // first_block
+            "", // name
+        )
+        .unwrap();
 
-            assert_eq!(
-                response.get_status(),
-                api::TxStatus::TransactionFailureKeyImageBlockMismatch
+        // 1 known recipient, 3 random recipients and no monitors.
+        let (ledger_db, mobilecoind_db, client, _server, _server_conn_manager) =
+            get_testing_environment(
+                BLOCK_VERSION,
+                3,
+                &[account_key.default_subaddress()],
+                &[],
+                logger.clone(),
+                &mut rng,
             );
-        }
 
-        // A receipt with key images that are not in the ledger is pending (unknown) if
-        // its tombstone block has not been exceeded.
-        {
-            let mut sender_receipt = api::SenderTxReceipt::new();
-            sender_receipt.set_key_image_list(RepeatedField::from_vec(vec![
-                (&KeyImage::from(4)).into(),
-                (&KeyImage::from(5)).into(),
-            ]));
-            sender_receipt.set_tombstone(ledger_db.num_blocks().unwrap() as u64 + 1);
+        // Insert into database.
+        let monitor_id = mobilecoind_db.add_monitor(&monitor_data).unwrap();
 
-            let mut request = api::SubmitTxResponse::new();
-            request.set_sender_tx_receipt(sender_receipt);
-            request.set_receiver_tx_receipt_list(RepeatedField::from_vec(vec![
-                receiver_receipt.clone()
-            ]));
+        // Allow the new monitor to process the ledger.
+        wait_for_monitors(&mobilecoind_db, &ledger_db, &logger);
+        let num_blocks = ledger_db.num_blocks().expect("failed getting num blocks");
 
-            let response = client.get_tx_status_as_sender(&request).unwrap();
+        let mut request = api::SubscribeMonitorEventsRequest::new();
+        request.set_monitor_id(monitor_id.to_vec());
+        let mut stream = client.subscribe_monitor_events(&request).unwrap();
+        let events = read_monitor_events(&mut stream, num_blocks);
+        assert_eq!(events.len(), 2 * num_blocks as usize);
+
+        // Rescan the monitor from block 2.
+        let mut rescan_request = api::RescanMonitorRequest::new();
+        rescan_request.set_monitor_id(monitor_id.to_vec());
+        rescan_request.set_first_block(2);
+        client.rescan_monitor(&rescan_request).unwrap();
+        wait_for_monitors(&mobilecoind_db, &ledger_db, &logger);
 
-            assert_eq!(response.get_status(), api::TxStatus::Unknown);
+        // The stream fails rather than sending the events of the rescanned blocks a
+        // second time.
+        loop {
+            match block_on(stream.next()).expect("stream ended") {
+                Ok(event) => {
+                    assert_eq!(event.get_event_type(), api::MonitorEventType::MonitorSynced)
+                }
+                Err(GrpcError::RpcFailure(rpc_status)) => {
+                    assert_eq!(rpc_status.code(), RpcStatusCode::ABORTED);
+                    break;
+                }
+                Err(err) => panic!("Unexpected error: {:?}", err),
+            }
         }
 
-        // A receipt with key images that are not in the ledger having its tombstone
-        // block exceeded.
-        {
-            let mut sender_receipt = api::SenderTxReceipt::new();
-            sender_receipt.set_key_image_list(RepeatedField::from_vec(vec![
-                (&KeyImage::from(4)).into(),
-                (&KeyImage::from(5)).into(),
-            ]));
-            sender_receipt.set_tombstone(ledger_db.num_blocks().unwrap() as u64);
+        // Subscribing again sends the events of the rescan, with balances counted from
+        // the new first block.
+        let mut stream = client.subscribe_monitor_events(&request).unwrap();
+        let events = read_monitor_events(&mut stream, num_blocks);
+        assert_eq!(events.len(), 2 * (num_blocks - 2) as usize);
+        for (index, block_events) in events.chunks(2).enumerate() {
+            assert_eq!(
+                block_events[0].get_event_type(),
+                api::MonitorEventType::MonitorTxOutReceived
+            );
+            assert_eq!(block_events[0].get_block(), index as u64 + 2);
+            assert_eq!(
+                block_events[1].get_event_type(),
+                api::MonitorEventType::MonitorBalanceChanged
+            );
+            assert_eq!(
+                block_events[1].get_balance(),
+                (index as u64 + 1) * DEFAULT_PER_RECIPIENT_AMOUNT
+            );
+        }
+    }
+
+    #[test_with_logger]
+    fn test_subscribe_monitor_events_gap_limit(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([25u8; 32]);
+
+        let account_key = AccountKey::random(&mut rng);
+        let mut monitor_data = MonitorData::new(
+            account_key.clone(),
+            0,  // first_subaddress
+            5,  // num_subaddresses
+            0,  // first_block
+            "", // name
+        )
+        .unwrap();
+        monitor_data.gap_limit = 3;
+
+        // Every block pays subaddress 6, which is outside of the monitored range.
+        let (mut ledger_db, mobilecoind_db, client, _server, _server_conn_manager) =
+            get_testing_environment(
+                BLOCK_VERSION,
+                3,
+                &[account_key.subaddress(6)],
+                &[],
+                logger.clone(),
+                &mut rng,
+            );
 
-            let mut request = api::SubmitTxResponse::new();
-            request.set_sender_tx_receipt(sender_receipt);
-            request.set_receiver_tx_receipt_list(RepeatedField::from_vec(vec![
-                receiver_receipt.clone()
-            ]));
+        // Insert into database.
+        let monitor_id = mobilecoind_db.add_monitor(&monitor_data).unwrap();
 
-            let response = client.get_tx_status_as_sender(&request).unwrap();
+        // Allow the new monitor to process the ledger.
+        wait_for_monitors(&mobilecoind_db, &ledger_db, &logger);
+        let num_blocks = ledger_db.num_blocks().expect("failed getting num blocks");
 
-            assert_eq!(response.get_status(), api::TxStatus::TombstoneBlockExceeded);
-        }
+        let mut request = api::SubscribeMonitorEventsRequest::new();
+        request.set_monitor_id(monitor_id.to_vec());
+        let mut stream = client.subscribe_monitor_events(&request).unwrap();
+        assert!(read_monitor_events(&mut stream, num_blocks).is_empty());
 
-        // Add another block to the ledger with different key images, to the same
-        // recipient
+        // Receiving on subaddress 3 grows the range, which finds the TxOuts sent to
+        // subaddress 6 in the blocks the stream already went through.
         add_block_to_ledger(
             &mut ledger_db,
             BLOCK_VERSION,
-            &[recipient.clone()],
+            &[account_key.subaddress(3)],
             Amount::new(DEFAULT_PER_RECIPIENT_AMOUNT, Mob::ID),
-            &[KeyImage::from(4), KeyImage::from(5), KeyImage::from(6)],
+            &[KeyImage::from(rng.next_u64())],
             &mut rng,
         )
         .unwrap();
 
-        // A receipt with all the key_images in the ledger, but in different blocks,
-        // should fail.
-        {
-            let mut sender_receipt = api::SenderTxReceipt::new();
-            sender_receipt.set_key_image_list(RepeatedField::from_vec(vec![
-                (&KeyImage::from(1)).into(),
-                (&KeyImage::from(2)).into(),
-                (&KeyImage::from(4)).into(),
-            ]));
-            sender_receipt.set_tombstone(1);
-
-            let mut request = api::SubmitTxResponse::new();
-            request.set_sender_tx_receipt(sender_receipt);
-            request.set_receiver_tx_receipt_list(RepeatedField::from_vec(vec![
-                receiver_receipt.clone()
-            ]));
-
-            let response = client.get_tx_status_as_sender(&request).unwrap();
+        // The stream may send the events of the new block, and then fails.
+        loop {
+            match block_on(stream.next()).expect("stream ended") {
+                Ok(event) => assert!(event.get_block() >= num_blocks),
+                Err(GrpcError::RpcFailure(rpc_status)) => {
+                    assert_eq!(rpc_status.code(), RpcStatusCode::ABORTED);
+                    break;
+                }
+                Err(err) => panic!("Unexpected error: {:?}", err),
+            }
+        }
 
+        // Subscribing again sends the events of the TxOuts found by growing the range.
+        wait_for_monitors(&mobilecoind_db, &ledger_db, &logger);
+        let mut stream = client.subscribe_monitor_events(&request).unwrap();
+        let events = read_monitor_events(&mut stream, num_blocks + 1);
+        assert_eq!(events.len(), 2 * (num_blocks + 1) as usize);
+        for (block_index, block_events) in events.chunks(2).enumerate() {
+            let subaddress_index = if block_index as u64 == num_blocks {
+                3
+            } else {
+                6
+            };
             assert_eq!(
-                response.get_status(),
-                api::TxStatus::TransactionFailureKeyImageBlockMismatch
+                block_events[0].get_event_type(),
+                api::MonitorEventType::MonitorTxOutReceived
+            );
+            assert_eq!(block_events[0].get_block(), block_index as u64);
+            assert_eq!(
+                block_events[0].get_tx_out().get_subaddress_index(),
+                subaddress_index
+            );
+            assert_eq!(
+                block_events[1].get_balance(),
+                (block_index as u64 + 1) * DEFAULT_PER_RECIPIENT_AMOUNT
             );
         }
+    }
 
-        // Create receiver_tx_receipt based on the txout created in
-        // add_block_to_ledger
-        let block2 = ledger_db
-            .get_block_contents(ledger_db.num_blocks().unwrap() - 1)
-            .unwrap();
-        let output2 = block2.outputs[0].clone();
+    #[test_with_logger]
+    fn test_subscribe_monitor_events_limit(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([26u8; 32]);
 
-        let mut receiver_receipt2 = api::ReceiverTxReceipt::new();
-        receiver_receipt2.set_recipient(api::external::PublicAddress::from(&recipient));
-        receiver_receipt2.set_tx_public_key(api::external::CompressedRistretto::from(
-            &output2.public_key,
-        ));
-        receiver_receipt2.set_tx_out_hash(output2.hash().into());
-        receiver_receipt2.set_tombstone(1);
-        // For this test, confirmation number is irrelevant, so left blank
+        let account_key = AccountKey::random(&mut rng);
+        let monitor_data = MonitorData::new(
+            account_key.clone(),
+            0,  // first_subaddress
+            1,  // num_subaddresses
+            0,  // first_block
+            "", // name
+        )
+        .unwrap();
 
-        // A receiver receipt with multiple public keys in different blocks should fail
-        {
-            let mut sender_receipt = api::SenderTxReceipt::new();
-            sender_receipt.set_key_image_list(RepeatedField::from_vec(vec![
-                (&KeyImage::from(1)).into(),
-                (&KeyImage::from(2)).into(),
-            ]));
-            sender_receipt.set_tombstone(1);
+        // 1 known recipient, 3 random recipients and one monitor.
+        let (ledger_db, mobilecoind_db, client, _server, _server_conn_manager) =
+            get_testing_environment(
+                BLOCK_VERSION,
+                3,
+                &[account_key.default_subaddress()],
+                &[monitor_data.clone()],
+                logger.clone(),
+                &mut rng,
+            );
+        let monitor_id = MonitorId::try_from(&monitor_data).unwrap();
+        let num_blocks = ledger_db.num_blocks().expect("failed getting num blocks");
 
-            let mut request = api::SubmitTxResponse::new();
-            request.set_sender_tx_receipt(sender_receipt);
-            request
-                .mut_receiver_tx_receipt_list()
-                .push(receiver_receipt.clone());
-            request
-                .mut_receiver_tx_receipt_list()
-                .push(receiver_receipt2);
+        let mut request = api::SubscribeMonitorEventsRequest::new();
+        request.set_monitor_id(monitor_id.to_vec());
+        let mut streams = (0..MAX_MONITOR_EVENT_STREAMS)
+            .map(|_| {
+                let mut stream = client.subscribe_monitor_events(&request).unwrap();
+                read_monitor_events(&mut stream, num_blocks);
+                stream
+            })
+            .collect::<Vec<_>>();
 
-            let response = client.get_tx_status_as_sender(&request).unwrap();
+        // Streams past the limit are refused.
+        let mut stream = client.subscribe_monitor_events(&request).unwrap();
+        match block_on(stream.next()).expect("stream ended") {
+            Err(GrpcError::RpcFailure(rpc_status)) => {
+                assert_eq!(rpc_status.code(), RpcStatusCode::RESOURCE_EXHAUSTED);
+            }
+            result => panic!("Unexpected result: {:?}", result),
+        }
 
-            assert_eq!(
-                response.get_status(),
-                api::TxStatus::PublicKeysInDifferentBlocks
-            );
+        // Rescanning the monitor ends every stream, which makes room for new ones.
+        let mut rescan_request = api::RescanMonitorRequest::new();
+        rescan_request.set_monitor_id(monitor_id.to_vec());
+        client.rescan_monitor(&rescan_request).unwrap();
+        for stream in streams.iter_mut() {
+            while block_on(stream.next()).expect("stream ended").is_ok() {}
         }
+        wait_for_monitors(&mobilecoind_db, &ledger_db, &logger);
 
-        // A receipt with a public key which has not landed in the ledger, but
-        // key_images which have should fail.
-        // A receiver receipt with multiple public keys in different blocks should fail
-        {
-            let mut sender_receipt = api::SenderTxReceipt::new();
-            sender_receipt.set_key_image_list(RepeatedField::from_vec(vec![
-                (&KeyImage::from(1)).into(),
-                (&KeyImage::from(4)).into(),
-            ]));
-            sender_receipt.set_tombstone(1);
+        let mut stream = client.subscribe_monitor_events(&request).unwrap();
+        let events = read_monitor_events(&mut stream, num_blocks);
+        assert_eq!(events.len(), 2 * num_blocks as usize);
+    }
 
-            let mut request = api::SubmitTxResponse::new();
-            request.set_sender_tx_receipt(sender_receipt);
-            // Modify the receiver_receipt to have a public key not in the ledger
-            receiver_receipt.set_tx_public_key(api::external::CompressedRistretto::from(
-                &CompressedRistrettoPublic::from(&RistrettoPublic::from_random(&mut rng)),
-            ));
-            request.set_receiver_tx_receipt_list(RepeatedField::from_vec(vec![receiver_receipt]));
+    #[test_with_logger]
+    /// Get mixins should return the correct number of distinct mixins.
+    fn test_get_mixins(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([44u8; 32]);
 
-            let response = client.get_tx_status_as_sender(&request).unwrap();
+        let sender = AccountKey::random(&mut rng);
 
-            assert_eq!(
-                response.get_status(),
-                api::TxStatus::TransactionFailureKeyImageAlreadySpent
+        // 1 known recipient, 3 random recipients and no monitors.
+        let (ledger_db, _mobilecoind_db, client, _server, _server_conn_manager) =
+            get_testing_environment(
+                BLOCK_VERSION,
+                3,
+                &[sender.default_subaddress()],
+                &[],
+                logger.clone(),
+                &mut rng,
             );
+
+        // The ledger contains 40 transaction outputs.
+        assert_eq!(ledger_db.num_txos().unwrap(), 40);
+
+        // Response should contain the requested number of distinct mixins.
+        {
+            let mut request = api::GetMixinsRequest::new();
+            request.set_num_mixins(13);
+            let response = client.get_mixins(&request).unwrap();
+            let mixins_with_proofs: Vec<api::TxOutWithProof> = response.get_mixins().to_vec();
+
+            assert_eq!(mixins_with_proofs.len(), 13);
+
+            // Mixins should be distinct.
+            let mixin_hashes: HashSet<_> = mixins_with_proofs
+                .iter()
+                .map(|mixin| {
+                    let tx_out: TxOut = TxOut::try_from(mixin.get_output()).unwrap();
+                    tx_out.hash()
+                })
+                .collect();
+
+            assert_eq!(mixin_hashes.len(), mixins_with_proofs.len());
+        }
+
+        // Requesting more mixins than exist in the ledger should return an error.
+        // TODO: enforce a limit on the number of mixins that may be requested.
+        {
+            let mut bad_request = api::GetMixinsRequest::new();
+            bad_request.set_num_mixins(10000);
+            let response = client.get_mixins(&bad_request);
+
+            assert!(response.is_err());
         }
     }
 
     #[test_with_logger]
-    fn test_get_tx_status_as_receiver_impl(logger: Logger) {
-        let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
+    /// Get mixins should not return an "excluded" TxOut.
+    fn test_get_mixins_excluded(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([74u8; 32]);
 
-        // no known recipient, 3 random recipients and no monitors.
-        let (mut ledger_db, mobilecoind_db, client, _server, _server_conn_manager) =
-            get_testing_environment(BLOCK_VERSION, 3, &[], &[], logger.clone(), &mut rng);
+        let sender = AccountKey::random(&mut rng);
 
-        // A call with an invalid hash should fail
-        {
-            let mut receipt = api::ReceiverTxReceipt::new();
-            receipt.set_tombstone(1);
+        // 1 known recipient, 3 random recipients and no monitors.
+        let (ledger_db, mobilecoind_db, client, _server, _server_conn_manager) =
+            get_testing_environment(
+                BLOCK_VERSION,
+                3,
+                &[sender.default_subaddress()],
+                &[],
+                logger.clone(),
+                &mut rng,
+            );
 
-            let mut request = api::GetTxStatusAsReceiverRequest::new();
-            request.set_receipt(receipt);
+        assert_eq!(ledger_db.num_txos().unwrap(), 40);
 
-            assert!(client.get_tx_status_as_receiver(&request).is_err());
-        }
+        // A list of outputs to exclude.
+        let to_exclude: Vec<TxOut> = {
+            let data = MonitorData::new(
+                sender, 0,  // first_subaddress
+                20, // num_subaddresses
+                0,  // first_block
+                "", // name
+            )
+            .unwrap();
 
-        // A call with a hash thats in the ledger should return Verified
-        {
-            let tx_out = ledger_db.get_tx_out_by_index(1).unwrap();
-            let hash = tx_out.hash();
+            // Insert into database.
+            let monitor_id = mobilecoind_db.add_monitor(&data).unwrap();
 
-            let mut receipt = api::ReceiverTxReceipt::new();
-            receipt.set_tx_out_hash(hash.to_vec());
-            receipt.set_tombstone(1);
+            // Allow the new monitor to process the ledger.
+            wait_for_monitors(&mobilecoind_db, &ledger_db, &logger);
 
-            let mut request = api::GetTxStatusAsReceiverRequest::new();
-            request.set_receipt(receipt);
+            // Select some outputs from the ledger.
+            mobilecoind_db
+                .get_utxos_for_subaddress(&monitor_id, 0)
+                .unwrap()
+                .into_iter()
+                .map(|utxo| utxo.tx_out)
+                .collect()
+        };
+
+        assert_eq!(to_exclude.len(), 10);
+
+        // The ledger contains 40 outputs. Requesting 30 and excluding 10 should return
+        // exactly the remaining 30.
+        let mut request = api::GetMixinsRequest::new();
+        request.set_num_mixins(30);
+        request.set_excluded(RepeatedField::from_vec(
+            to_exclude.iter().map(api::external::TxOut::from).collect(),
+        ));
+
+        let response = client.get_mixins(&request).unwrap();
+
+        let mixins_with_proofs: Vec<api::TxOutWithProof> = response.get_mixins().to_vec();
+
+        // Should contain 30 mixins
+        assert_eq!(mixins_with_proofs.len(), 30);
 
-            let response = client.get_tx_status_as_receiver(&request).unwrap();
-            assert_eq!(response.get_status(), api::TxStatus::Verified);
-        }
+        // None of the excluded outputs should be returned as mixins.
+        let excluded_hashes: HashSet<_> = to_exclude.iter().map(|tx_out| tx_out.hash()).collect();
 
-        // A call with a hash thats is not in the ledger and hasn't exceeded tombstone
-        // block should return Unknown
-        {
-            let hash = [0; 32];
+        for mixin in &mixins_with_proofs {
+            let mixin: TxOut = TxOut::try_from(mixin.get_output()).unwrap();
+            assert!(!excluded_hashes.contains(&mixin.hash()));
+        }
+    }
 
-            let mut receipt = api::ReceiverTxReceipt::new();
-            receipt.set_tx_out_hash(hash.to_vec());
-            receipt.set_tombstone(ledger_db.num_blocks().unwrap() as u64 + 1);
+    #[test_with_logger]
+    /// Get mixins should return valid membership proofs.
+    fn test_get_mixins_membership_proofs(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([89u8; 32]);
+        let sender = AccountKey::random(&mut rng);
 
-            let mut request = api::GetTxStatusAsReceiverRequest::new();
-            request.set_receipt(receipt);
+        // 1 known recipient, 3 random recipients and no monitors.
+        let (ledger_db, _mobilecoind_db, client, _server, _server_conn_manager) =
+            get_testing_environment(
+                BLOCK_VERSION,
+                3,
+                &[sender.default_subaddress()],
+                &[],
+                logger.clone(),
+                &mut rng,
+            );
 
-            let response = client.get_tx_status_as_receiver(&request).unwrap();
-            assert_eq!(response.get_status(), api::TxStatus::Unknown);
-        }
+        let mixins_with_proofs: Vec<api::TxOutWithProof> = {
+            let mut request = api::GetMixinsRequest::new();
+            request.set_num_mixins(13);
+            let response = client.get_mixins(&request).unwrap();
+            response.get_mixins().to_vec()
+        };
 
-        // A call with a hash thats is not in the ledger and has exceeded tombstone
-        // block should return TombstoneBlockExceeded
-        {
-            let hash = [0; 32];
+        assert_eq!(mixins_with_proofs.len(), 13);
 
-            let mut receipt = api::ReceiverTxReceipt::new();
-            receipt.set_tx_out_hash(hash.to_vec());
-            receipt.set_tombstone(ledger_db.num_blocks().unwrap() as u64);
+        // Each membership proof should be correct.
+        for mixin_with_proof in &mixins_with_proofs {
+            let mixin: TxOut = TxOut::try_from(mixin_with_proof.get_output()).unwrap();
 
-            let mut request = api::GetTxStatusAsReceiverRequest::new();
-            request.set_receipt(receipt);
+            // The returned proof should be correct.
+            let expected_proof = {
+                let index = ledger_db.get_tx_out_index_by_hash(&mixin.hash()).unwrap();
+                let proofs = ledger_db.get_tx_out_proof_of_memberships(&[index]).unwrap();
+                assert_eq!(proofs.len(), 1);
+                api::external::TxOutMembershipProof::from(&proofs[0])
+            };
 
-            let response = client.get_tx_status_as_receiver(&request).unwrap();
-            assert_eq!(response.get_status(), api::TxStatus::TombstoneBlockExceeded);
+            assert_eq!(mixin_with_proof.get_proof(), &expected_proof);
         }
+    }
 
-        // Now create a monitor for the receiver to test confirmation numbers
-        let receiver = AccountKey::random(&mut rng);
+    #[test_with_logger]
+    /// Should return a correct proof-of-membership for each requested TxOut.
+    fn test_get_membership_proofs(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
+
+        let sender = AccountKey::random(&mut rng);
         let data = MonitorData::new(
-            receiver.clone(),
+            sender.clone(),
             0,  // first_subaddress
             20, // num_subaddresses
             0,  // first_block
@@ -3045,79 +6405,76 @@ mod test {
         )
         .unwrap();
 
+        // 1 known recipient, 3 random recipients and no monitors.
+        let (ledger_db, mobilecoind_db, client, _server, _server_conn_manager) =
+            get_testing_environment(
+                BLOCK_VERSION,
+                3,
+                &[sender.default_subaddress()],
+                &[],
+                logger.clone(),
+                &mut rng,
+            );
+
         // Insert into database.
         let monitor_id = mobilecoind_db.add_monitor(&data).unwrap();
-        let mut transaction_builder = TransactionBuilder::new(
-            BLOCK_VERSION,
-            Amount::new(Mob::MINIMUM_FEE, Mob::ID),
-            MockFogResolver::default(),
-            EmptyMemoBuilder::default(),
-        )
-        .unwrap();
-        let TxOutContext {
-            tx_out,
-            confirmation,
-            ..
-        } = transaction_builder
-            .add_output(Amount::new(10, Mob::ID), &receiver.subaddress(0), &mut rng)
-            .unwrap();
-
-        add_txos_to_ledger(&mut ledger_db, BLOCK_VERSION, &[tx_out.clone()], &mut rng).unwrap();
 
-        // A request with a valid confirmation number and monitor ID should return
-        // Verified
-        {
-            let hash = tx_out.hash();
-
-            let mut receipt = api::ReceiverTxReceipt::new();
-            receipt.set_tx_public_key(api::external::CompressedRistretto::from(&tx_out.public_key));
-            receipt.set_tx_out_hash(hash.to_vec());
-            receipt.set_tombstone(10);
-            receipt.set_confirmation_number(confirmation.to_vec());
+        // Allow the new monitor to process the ledger.
+        wait_for_monitors(&mobilecoind_db, &ledger_db, &logger);
 
-            let mut request = api::GetTxStatusAsReceiverRequest::new();
-            request.set_receipt(receipt);
-            request.set_monitor_id(monitor_id.to_vec());
+        // Select some outputs from the ledger.
+        let outputs: Vec<TxOut> = {
+            let unspent_outputs = mobilecoind_db
+                .get_utxos_for_subaddress(&monitor_id, 0)
+                .unwrap();
 
-            let response = client.get_tx_status_as_receiver(&request).unwrap();
-            assert_eq!(response.get_status(), api::TxStatus::Verified);
-        }
+            vec![
+                unspent_outputs[1].tx_out.clone(),
+                unspent_outputs[3].tx_out.clone(),
+                unspent_outputs[5].tx_out.clone(),
+            ]
+        };
 
-        // A request with an a bad confirmation number and a monitor ID should return
-        // InvalidConfirmationNumber
-        {
-            let hash = tx_out.hash();
+        let mut request = api::GetMembershipProofsRequest::new();
+        request.set_outputs(RepeatedField::from_vec(
+            outputs.iter().map(api::external::TxOut::from).collect(),
+        ));
 
-            let mut receipt = api::ReceiverTxReceipt::new();
-            receipt.set_tx_public_key(api::external::CompressedRistretto::from(&tx_out.public_key));
-            receipt.set_tx_out_hash(hash.to_vec());
-            receipt.set_tombstone(10);
-            receipt.set_confirmation_number(vec![0u8; 32]);
+        let response = client.get_membership_proofs(&request).unwrap();
 
-            let mut request = api::GetTxStatusAsReceiverRequest::new();
-            request.set_receipt(receipt);
-            request.set_monitor_id(monitor_id.to_vec());
+        // The response should should contain an element for each requested output.
+        assert_eq!(response.output_list.len(), outputs.len());
 
-            let response = client.get_tx_status_as_receiver(&request).unwrap();
+        for (tx_out, output_with_proof) in outputs.iter().zip(response.get_output_list().iter()) {
+            // The response should contain a TxOutWithProof for each requested TxOut.
             assert_eq!(
-                response.get_status(),
-                api::TxStatus::InvalidConfirmationNumber
+                output_with_proof.get_output(),
+                &api::external::TxOut::from(tx_out)
             );
+
+            // The returned proof should be correct.
+            let expected_proof = {
+                let index = ledger_db.get_tx_out_index_by_hash(&tx_out.hash()).unwrap();
+                let proofs = ledger_db.get_tx_out_proof_of_memberships(&[index]).unwrap();
+                assert_eq!(proofs.len(), 1);
+
+                api::external::TxOutMembershipProof::from(&proofs[0])
+            };
+
+            assert_eq!(output_with_proof.get_proof(), &expected_proof);
         }
     }
 
     #[test_with_logger]
-    fn test_get_processed_block(logger: Logger) {
+    fn test_generate_tx(logger: Logger) {
         let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
 
-        let account_key = AccountKey::random(&mut rng);
-        // Note: we skip the first block to test what happens when we try and query a
-        // block that will never get processed.
-        let monitor_data = MonitorData::new(
-            account_key.clone(),
+        let sender = AccountKey::random(&mut rng);
+        let data = MonitorData::new(
+            sender.clone(),
             0,  // first_subaddress
             20, // num_subaddresses
-            1,  // first_block
+            0,  // first_block
             "", // name
         )
         .unwrap();
@@ -3127,235 +6484,321 @@ mod test {
             get_testing_environment(
                 BLOCK_VERSION,
                 3,
-                &[account_key.default_subaddress()],
+                &[sender.default_subaddress()],
                 &[],
                 logger.clone(),
                 &mut rng,
             );
 
+        // Add a block with a non-MOB token ID.
+        add_block_to_ledger(
+            &mut ledger_db,
+            BlockVersion::MAX,
+            &[
+                AccountKey::random(&mut rng).default_subaddress(),
+                AccountKey::random(&mut rng).default_subaddress(),
+                AccountKey::random(&mut rng).default_subaddress(),
+                sender.default_subaddress(),
+            ],
+            Amount::new(1_000_000_000_000, TokenId::from(2)),
+            &[KeyImage::from(101)],
+            &mut rng,
+        )
+        .unwrap();
+
         // Insert into database.
-        let monitor_id = mobilecoind_db.add_monitor(&monitor_data).unwrap();
+        let monitor_id = mobilecoind_db.add_monitor(&data).unwrap();
 
         // Allow the new monitor to process the ledger.
         wait_for_monitors(&mobilecoind_db, &ledger_db, &logger);
 
-        // Verify the data we got matches what we expected. This assumes knowledge about
-        // how the test ledger is constructed by the test utils.
-        let num_blocks = ledger_db.num_blocks().expect("failed getting num blocks");
-        let account_tx_outs: Vec<TxOut> = (0..num_blocks)
-            .map(|idx| {
-                let block_contents = ledger_db.get_block_contents(idx as u64).unwrap();
-                // We grab the 4th tx out in each block since the test ledger had 3 random
-                // recipients, followed by our known recipient.
-                // See the call to `get_testing_environment` at the beginning of the test.
-                block_contents.outputs[3].clone()
-            })
-            .collect();
-
-        let expected_utxos: Vec<UnspentTxOut> = account_tx_outs
-            .iter()
-            .map(|tx_out| {
-                // Calculate the key image for this tx out.
-                let tx_public_key = RistrettoPublic::try_from(&tx_out.public_key).unwrap();
-                let onetime_private_key = recover_onetime_private_key(
-                    &tx_public_key,
-                    account_key.view_private_key(),
-                    &account_key.subaddress_spend_private(0),
-                );
-                let key_image = KeyImage::from(&onetime_private_key);
+        // Get list of unspent tx outs
+        let utxos = mobilecoind_db
+            .get_utxos_for_subaddress(&monitor_id, 0)
+            .unwrap();
+        assert!(!utxos.is_empty());
 
-                // Craft the expected UnspentTxOut
-                UnspentTxOut {
-                    tx_out: tx_out.clone(),
-                    subaddress_index: 0,
-                    key_image,
-                    value: test_utils::DEFAULT_PER_RECIPIENT_AMOUNT,
-                    attempted_spend_height: 0,
-                    attempted_spend_tombstone: 0,
-                    token_id: *Mob::ID,
-                }
-            })
-            .collect();
+        // Generate two random recipients.
+        let receiver1 = AccountKey::random(&mut rng);
+        let receiver2 = AccountKey::random(&mut rng);
 
-        // Query a bunch of blocks and verify the data.
-        for block_index in 1..num_blocks {
-            let mut request = api::GetProcessedBlockRequest::new();
-            request.set_monitor_id(monitor_id.to_vec());
-            request.set_block(block_index);
+        let outlays = vec![
+            Outlay {
+                value: 123,
+                receiver: receiver1.default_subaddress(),
+            },
+            Outlay {
+                value: 456,
+                receiver: receiver2.default_subaddress(),
+            },
+        ];
 
-            let response = client
-                .get_processed_block(&request)
-                .expect("failed to get processed block");
+        // Call generate tx.
+        let mut request = api::GenerateTxRequest::new();
+        request.set_sender_monitor_id(monitor_id.to_vec());
+        request.set_change_subaddress(0);
+        request.set_input_list(RepeatedField::from_vec(
+            utxos
+                .iter()
+                .filter(|utxo| utxo.token_id == *Mob::ID)
+                .map(api::UnspentTxOut::from)
+                .collect(),
+        ));
+        request.set_outlay_list(RepeatedField::from_vec(
+            outlays.iter().map(api::Outlay::from).collect(),
+        ));
 
-            // We expect one utxo per block for our monitor.
-            let tx_outs = response.get_tx_outs();
-            assert_eq!(tx_outs.len(), 1);
-            let tx_out = &tx_outs[0];
+        // Test the happy flow for MOB.
+        {
+            let response = client.generate_tx(&request).unwrap();
 
-            let expected_utxo = &expected_utxos[block_index as usize];
+            // Sanity test the response.
+            let tx_proposal = response.get_tx_proposal();
 
-            assert_eq!(tx_out.get_monitor_id().to_vec(), monitor_id.to_vec());
+            let expected_num_inputs: u64 = (outlays.iter().map(|outlay| outlay.value).sum::<u64>()
+                / test_utils::DEFAULT_PER_RECIPIENT_AMOUNT)
+                + 1;
             assert_eq!(
-                tx_out.get_subaddress_index(),
-                expected_utxo.subaddress_index
+                tx_proposal.get_input_list().len(),
+                expected_num_inputs as usize
             );
             assert_eq!(
-                tx_out.get_public_key(),
-                &(&expected_utxo.tx_out.public_key).into(),
+                tx_proposal.get_tx().get_prefix().get_inputs().len(),
+                expected_num_inputs as usize
             );
-            assert_eq!(tx_out.get_key_image(), &(&expected_utxo.key_image).into());
-            assert_eq!(tx_out.value, expected_utxo.value);
+            assert_eq!(tx_proposal.get_outlay_list(), request.get_outlay_list());
             assert_eq!(
-                tx_out.get_direction(),
-                api::ProcessedTxOutDirection::Received,
+                tx_proposal.get_tx().get_prefix().get_outputs().len(),
+                outlays.len() + 1
+            ); // Extra output for change.
+
+            let tx = Tx::try_from(tx_proposal.get_tx()).unwrap();
+
+            // The transaction should contain an output for each outlay, and one for change.
+            assert_eq!(tx.prefix.outputs.len(), outlays.len() + 1);
+
+            // The transaction should have a confirmation code for each outlay
+            assert_eq!(
+                outlays.len(),
+                tx_proposal.get_outlay_confirmation_numbers().len()
             );
 
-            // test address code
-            let mut request = api::GetPublicAddressRequest::new();
-            request.set_monitor_id(monitor_id.to_vec());
-            request.set_subaddress_index(expected_utxo.subaddress_index);
-            let response = client.get_public_address(&request).unwrap();
-            let public_address = PublicAddress::try_from(response.get_public_address()).unwrap();
+            let change_value = test_utils::DEFAULT_PER_RECIPIENT_AMOUNT
+                - outlays.iter().map(|outlay| outlay.value).sum::<u64>()
+                - Mob::MINIMUM_FEE;
 
-            let mut request = api::CreateAddressCodeRequest::new();
-            request.set_receiver(mc_api::external::PublicAddress::from(&public_address));
-            let response = client.create_address_code(&request).unwrap();
-            let b58_code = response.get_b58_code();
+            for (account_key, expected_value) in &[
+                (&receiver1, outlays[0].value),
+                (&receiver2, outlays[1].value),
+                (&sender, change_value),
+            ] {
+                // Find the first output belonging to the account, and get its value.
+                // This assumes that each output is sent to a different account key.
+                let (amount, _blinding) = tx
+                    .prefix
+                    .outputs
+                    .iter()
+                    .find_map(|tx_out| {
+                        let output_public_key =
+                            RistrettoPublic::try_from(&tx_out.public_key).unwrap();
+                        let shared_secret = get_tx_out_shared_secret(
+                            account_key.view_private_key(),
+                            &output_public_key,
+                        );
+                        tx_out
+                            .get_masked_amount()
+                            .unwrap()
+                            .get_value(&shared_secret)
+                            .ok()
+                    })
+                    .expect("There should be an output belonging to the account key.");
 
-            assert_eq!(tx_out.get_address_code(), b58_code);
+                assert_eq!(amount.token_id, Mob::ID);
+                assert_eq!(amount.value, *expected_value);
+            }
 
-            assert_eq!(tx_out.token_id, *Mob::ID);
+            // Santity test fee
+            assert_eq!(tx_proposal.get_fee(), Mob::MINIMUM_FEE);
+            assert_eq!(tx_proposal.get_tx().get_prefix().fee, Mob::MINIMUM_FEE);
+
+            // Sanity test tombstone block
+            let num_blocks = ledger_db.num_blocks().unwrap();
+            assert_eq!(
+                tx_proposal.get_tx().get_prefix().tombstone_block,
+                num_blocks + DEFAULT_NEW_TX_BLOCK_ATTEMPTS
+            );
         }
 
-        // Add a block with a key images that spend the first two utxos and see that we
-        // get the data we expect.
+        // Test the happy flow for TokenId(2)
         {
-            let recipient = AccountKey::random(&mut rng).default_subaddress();
-            add_block_to_ledger(
-                &mut ledger_db,
-                BLOCK_VERSION,
-                &[recipient],
-                Amount::new(DEFAULT_PER_RECIPIENT_AMOUNT, Mob::ID),
-                &[
-                    expected_utxos[monitor_data.first_block as usize].key_image,
-                    expected_utxos[monitor_data.first_block as usize + 1].key_image,
-                ],
-                &mut rng,
-            )
-            .unwrap();
-
-            wait_for_monitors(&mobilecoind_db, &ledger_db, &logger);
+            let mut request = api::GenerateTxRequest::new();
+            request.set_sender_monitor_id(monitor_id.to_vec());
+            request.set_change_subaddress(0);
+            request.set_input_list(RepeatedField::from_vec(
+                utxos
+                    .iter()
+                    .filter(|utxo| utxo.token_id == 2)
+                    .map(api::UnspentTxOut::from)
+                    .collect(),
+            ));
+            request.set_outlay_list(RepeatedField::from_vec(
+                outlays.iter().map(api::Outlay::from).collect(),
+            ));
+            request.set_token_id(2);
 
-            let mut request = api::GetProcessedBlockRequest::new();
-            request.set_monitor_id(monitor_id.to_vec());
-            request.set_block(num_blocks);
+            let fee = 10_000;
+            request.set_fee(fee);
 
-            let response = client
-                .get_processed_block(&request)
-                .expect("failed to get processed block");
+            let response = client.generate_tx(&request).unwrap();
 
-            let tx_outs = response.get_tx_outs();
-            assert_eq!(tx_outs.len(), 2);
+            // Sanity test the response.
+            let tx_proposal = response.get_tx_proposal();
 
-            let expected_utxos_by_key_image = HashMap::from_iter(
-                expected_utxos
-                    .iter()
-                    .skip(monitor_data.first_block as usize)
-                    .take(2)
-                    .map(|utxo| (utxo.key_image, utxo.clone())),
-            );
+            assert_eq!(tx_proposal.get_input_list().len(), 1,);
+            assert_eq!(tx_proposal.get_tx().get_prefix().get_inputs().len(), 1,);
+            assert_eq!(tx_proposal.get_outlay_list(), request.get_outlay_list());
+            assert_eq!(
+                tx_proposal.get_tx().get_prefix().get_outputs().len(),
+                outlays.len() + 1
+            ); // Extra output for change.
 
-            for tx_out in tx_outs.iter() {
-                let expected_utxo = expected_utxos_by_key_image
-                    .get(
-                        &KeyImage::try_from(tx_out.get_key_image().get_data())
-                            .expect("failed constructing key image"),
-                    )
-                    .expect("failed getting expected utxo");
+            let tx = Tx::try_from(tx_proposal.get_tx()).unwrap();
 
-                assert_eq!(tx_out.get_monitor_id().to_vec(), monitor_id.to_vec());
-                assert_eq!(
-                    tx_out.get_subaddress_index(),
-                    expected_utxo.subaddress_index
-                );
-                assert_eq!(
-                    tx_out.get_public_key(),
-                    &(&expected_utxo.tx_out.public_key).into(),
-                );
-                assert_eq!(tx_out.get_key_image(), &(&expected_utxo.key_image).into());
-                assert_eq!(tx_out.value, expected_utxo.value);
-                assert_eq!(tx_out.get_direction(), api::ProcessedTxOutDirection::Spent,);
-            }
-        }
+            // The transaction should contain an output for each outlay, and one for change.
+            assert_eq!(tx.prefix.outputs.len(), outlays.len() + 1);
 
-        // Add a block with a non-MOB token id and see that it gets picked up
-        // correctly.
-        {
-            add_block_to_ledger(
-                &mut ledger_db,
-                BlockVersion::THREE,
-                &[account_key.subaddress(5)],
-                Amount::new(102030, 2.into()),
-                &[KeyImage::from(101)],
-                &mut rng,
-            )
-            .unwrap();
+            // The transaction should have a confirmation code for each outlay
+            assert_eq!(
+                outlays.len(),
+                tx_proposal.get_outlay_confirmation_numbers().len()
+            );
 
-            wait_for_monitors(&mobilecoind_db, &ledger_db, &logger);
+            let change_value =
+                1_000_000_000_000 - outlays.iter().map(|outlay| outlay.value).sum::<u64>() - fee;
 
-            let mut request = api::GetProcessedBlockRequest::new();
-            request.set_monitor_id(monitor_id.to_vec());
-            request.set_block(num_blocks + 1);
+            for (account_key, expected_value) in &[
+                (&receiver1, outlays[0].value),
+                (&receiver2, outlays[1].value),
+                (&sender, change_value),
+            ] {
+                // Find the first output belonging to the account, and get its value.
+                // This assumes that each output is sent to a different account key.
+                let (amount, _blinding) = tx
+                    .prefix
+                    .outputs
+                    .iter()
+                    .find_map(|tx_out| {
+                        let output_public_key =
+                            RistrettoPublic::try_from(&tx_out.public_key).unwrap();
+                        let shared_secret = get_tx_out_shared_secret(
+                            account_key.view_private_key(),
+                            &output_public_key,
+                        );
+                        tx_out
+                            .get_masked_amount()
+                            .unwrap()
+                            .get_value(&shared_secret)
+                            .ok()
+                    })
+                    .expect("There should be an output belonging to the account key.");
 
-            let response = client
-                .get_processed_block(&request)
-                .expect("failed to get processed block");
+                assert_eq!(amount.token_id, TokenId::from(2));
+                assert_eq!(amount.value, *expected_value);
+            }
 
-            let tx_outs = response.get_tx_outs();
-            assert_eq!(tx_outs.len(), 1);
+            // Santity test fee
+            assert_eq!(tx_proposal.get_fee(), fee);
+            assert_eq!(tx_proposal.get_tx().get_prefix().fee, fee);
 
-            let tx_out = &tx_outs[0];
-            assert_eq!(tx_out.get_monitor_id().to_vec(), monitor_id.to_vec());
-            assert_eq!(tx_out.get_value(), 102030);
+            // Sanity test tombstone block
+            let num_blocks = ledger_db.num_blocks().unwrap();
             assert_eq!(
-                tx_out.get_direction(),
-                api::ProcessedTxOutDirection::Received
+                tx_proposal.get_tx().get_prefix().tombstone_block,
+                num_blocks + DEFAULT_NEW_TX_BLOCK_ATTEMPTS
             );
-            assert_eq!(tx_out.get_token_id(), 2);
         }
 
-        // Query a block that will never get processed since its before the monitor's
-        // first block.
-        let mut request = api::GetProcessedBlockRequest::new();
-        request.set_monitor_id(monitor_id.to_vec());
-        request.set_block(0);
+        // Invalid input scenarios should result in an error.
+        {
+            // No monitor id
+            let mut request = request.clone();
+            request.set_sender_monitor_id(vec![]);
+            assert!(client.generate_tx(&request).is_err());
+        }
 
-        assert!(client.get_processed_block(&request).is_err());
+        {
+            // Unrecognized monitor id
+            let sender = AccountKey::random(&mut rng);
+            let data = MonitorData::new(
+                sender, 0,  // first_subaddress
+                20, // num_subaddresses
+                0,  // first_block
+                "", // name
+            )
+            .unwrap();
 
-        // Query a block that hasn't been processed yet.
-        let mut request = api::GetProcessedBlockRequest::new();
-        request.set_monitor_id(monitor_id.to_vec());
-        request.set_block(num_blocks + 2);
+            let mut request = request.clone();
+            request.set_sender_monitor_id(MonitorId::try_from(&data).unwrap().to_vec());
+            assert!(client.generate_tx(&request).is_err());
+        }
 
-        assert!(client.get_processed_block(&request).is_err());
+        {
+            // Subaddress index out of range
+            let mut request = request.clone();
+            request.set_change_subaddress(data.first_subaddress + data.num_subaddresses + 1);
+            assert!(client.generate_tx(&request).is_err());
+        }
 
-        // Query with an unknown monitor id.
-        let mut request = api::GetProcessedBlockRequest::new();
-        request.set_monitor_id(vec![1; 32]);
-        request.set_block(1);
+        {
+            // Junk input
+            let mut request = request.clone();
+            request.mut_input_list().push(api::UnspentTxOut::default());
+            assert!(client.generate_tx(&request).is_err());
+        }
 
-        assert!(client.get_processed_block(&request).is_err());
+        {
+            // Attempt to spend more than we have
+            let num_blocks = ledger_db.num_blocks().unwrap();
+            let mut request = request.clone();
+            request.set_outlay_list(RepeatedField::from_vec(vec![api::Outlay::from(&Outlay {
+                receiver: receiver1.default_subaddress(),
+                value: test_utils::DEFAULT_PER_RECIPIENT_AMOUNT * num_blocks,
+            })]));
+            assert!(client.generate_tx(&request).is_err());
+        }
+
+        {
+            // Mixing input tokens (utxos has both Mob and TokenId(2))
+            let mut request = api::GenerateTxRequest::new();
+            request.set_sender_monitor_id(monitor_id.to_vec());
+            request.set_change_subaddress(0);
+            request.set_input_list(RepeatedField::from_vec(
+                utxos.iter().map(api::UnspentTxOut::from).collect(),
+            ));
+            request.set_outlay_list(RepeatedField::from_vec(
+                outlays.iter().map(api::Outlay::from).collect(),
+            ));
+            assert!(client.generate_tx(&request).is_err());
+        }
     }
 
     #[test_with_logger]
-    /// Get mixins should return the correct number of distinct mixins.
-    fn test_get_mixins(logger: Logger) {
-        let mut rng: StdRng = SeedableRng::from_seed([44u8; 32]);
+    fn test_generate_burn_redemption_tx(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
+        let token_id2 = TokenId::from(2);
 
         let sender = AccountKey::random(&mut rng);
+        let data = MonitorData::new(
+            sender.clone(),
+            0,  // first_subaddress
+            20, // num_subaddresses
+            0,  // first_block
+            "", // name
+        )
+        .unwrap();
 
         // 1 known recipient, 3 random recipients and no monitors.
-        let (ledger_db, _mobilecoind_db, client, _server, _server_conn_manager) =
+        let (mut ledger_db, mobilecoind_db, client, _server, _server_conn_manager) =
             get_testing_environment(
                 BLOCK_VERSION,
                 3,
@@ -3365,50 +6808,176 @@ mod test {
                 &mut rng,
             );
 
-        // The ledger contains 40 transaction outputs.
-        assert_eq!(ledger_db.num_txos().unwrap(), 40);
+        // Add a block with a non-MOB token ID.
+        add_block_to_ledger(
+            &mut ledger_db,
+            BlockVersion::MAX,
+            &[
+                AccountKey::random(&mut rng).default_subaddress(),
+                AccountKey::random(&mut rng).default_subaddress(),
+                AccountKey::random(&mut rng).default_subaddress(),
+                sender.default_subaddress(),
+            ],
+            Amount::new(1_000_000_000_000, token_id2),
+            &[KeyImage::from(101)],
+            &mut rng,
+        )
+        .unwrap();
 
-        // Response should contain the requested number of distinct mixins.
+        // Insert into database.
+        let monitor_id = mobilecoind_db.add_monitor(&data).unwrap();
+
+        // Allow the new monitor to process the ledger.
+        wait_for_monitors(&mobilecoind_db, &ledger_db, &logger);
+
+        // Get list of unspent tx outs that we want to burn
+        let utxos = mobilecoind_db
+            .get_utxos_for_subaddress(&monitor_id, 0)
+            .unwrap()
+            .iter()
+            .filter(|utxo| utxo.token_id == token_id2)
+            .map(Into::into)
+            .collect::<RepeatedField<_>>();
+        assert!(!utxos.is_empty());
+
+        // Prepare request
+        let mut request = api::GenerateBurnRedemptionTxRequest::new();
+        request.set_sender_monitor_id(monitor_id.to_vec());
+        request.set_change_subaddress(0);
+        request.set_input_list(utxos);
+        request.set_burn_amount(100_000);
+        request.set_fee(200_000);
+        request.set_token_id(*token_id2);
+        request.set_redemption_memo(vec![5u8; BurnRedemptionMemo::MEMO_DATA_LEN]);
+        request.set_enable_destination_memo(true);
+
+        // Test the happy flow.
         {
-            let mut request = api::GetMixinsRequest::new();
-            request.set_num_mixins(13);
-            let response = client.get_mixins(&request).unwrap();
-            let mixins_with_proofs: Vec<api::TxOutWithProof> = response.get_mixins().to_vec();
+            let response = client.generate_burn_redemption_tx(&request).unwrap();
 
-            assert_eq!(mixins_with_proofs.len(), 13);
+            // Sanity test the response.
+            let tx_proposal = response.get_tx_proposal();
+            let tx = Tx::try_from(tx_proposal.get_tx()).unwrap();
 
-            // Mixins should be distinct.
-            let mixin_hashes: HashSet<_> = mixins_with_proofs
+            // Two outputs - change and burn
+            assert_eq!(tx.prefix.outputs.len(), 2);
+
+            // Validate the change output.
+            let (change_tx_out, change_amount) = tx
+                .prefix
+                .outputs
                 .iter()
-                .map(|mixin| {
-                    let tx_out: TxOut = TxOut::try_from(mixin.get_output()).unwrap();
-                    tx_out.hash()
+                .find_map(|tx_out| {
+                    tx_out
+                        .view_key_match(sender.view_private_key())
+                        .map(|(amount, _commitment)| (tx_out.clone(), amount))
+                        .ok()
                 })
-                .collect();
+                .expect("Didn't find sender's change output");
 
-            assert_eq!(mixin_hashes.len(), mixins_with_proofs.len());
+            assert_eq!(change_amount.value, 1_000_000_000_000 - 100_000 - 200_000);
+
+            let ss = get_tx_out_shared_secret(
+                sender.view_private_key(),
+                &RistrettoPublic::try_from(&change_tx_out.public_key).unwrap(),
+            );
+            let memo = change_tx_out.e_memo.unwrap().decrypt(&ss);
+            match MemoType::try_from(&memo).expect("Couldn't decrypt memo") {
+                MemoType::Destination(memo) => {
+                    assert_eq!(
+                        memo.get_address_hash(),
+                        &ShortAddressHash::from(&burn_address()),
+                        "lookup based on address hash failed"
+                    );
+                    assert_eq!(memo.get_num_recipients(), 1);
+                    assert_eq!(memo.get_fee(), 200_000);
+                    assert_eq!(
+                        memo.get_total_outlay(),
+                        300_000,
+                        "outlay should be amount sent to recipient + fee"
+                    );
+                }
+                _ => {
+                    panic!("unexpected memo type")
+                }
+            }
+
+            // Validate the burn output.
+            let (burn_tx_out, burn_amount) = tx
+                .prefix
+                .outputs
+                .iter()
+                .find_map(|tx_out| {
+                    tx_out
+                        .view_key_match(&burn_address_view_private())
+                        .map(|(amount, _commitment)| (tx_out.clone(), amount))
+                        .ok()
+                })
+                .expect("Didn't find burn output");
+
+            assert_eq!(burn_amount.value, 100_000);
+
+            let ss = get_tx_out_shared_secret(
+                &burn_address_view_private(),
+                &RistrettoPublic::try_from(&burn_tx_out.public_key).unwrap(),
+            );
+            let memo = burn_tx_out.e_memo.unwrap().decrypt(&ss);
+            assert_matches!(MemoType::try_from(&memo).expect("Couldn't decrypt memo"), MemoType::BurnRedemption(memo) if memo.memo_data() == &[5u8; 64]);
         }
 
-        // Requesting more mixins than exist in the ledger should return an error.
-        // TODO: enforce a limit on the number of mixins that may be requested.
+        // Invalid memo data length results in an error.
         {
-            let mut bad_request = api::GetMixinsRequest::new();
-            bad_request.set_num_mixins(10000);
-            let response = client.get_mixins(&bad_request);
+            let mut request = request.clone();
+            request.set_redemption_memo(vec![5u8; BurnRedemptionMemo::MEMO_DATA_LEN + 1]);
+            assert!(client.generate_burn_redemption_tx(&request).is_err());
+        }
+
+        // Trying to burn more than we have results in an error.
+        {
+            let mut request = request.clone();
+            request.set_burn_amount(1_000_000_000_000 - request.get_fee() + 1);
+            assert!(client.generate_burn_redemption_tx(&request).is_err());
+        }
+    }
+
+    #[test_with_logger]
+    fn test_get_block_index_by_tx_pub_key(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
+
+        // no known recipient, 3 random recipients and no monitors.
+        let (ledger_db, _mobilecoind_db, client, _server, _server_conn_manager) =
+            get_testing_environment(BLOCK_VERSION, 3, &[], &[], logger.clone(), &mut rng);
+
+        // Grab the first TxOut of each block in the database and verify its index.
+        for block_index in 0..test_utils::GET_TESTING_ENVIRONMENT_NUM_BLOCKS as u64 {
+            let block_contents = ledger_db.get_block_contents(block_index).unwrap();
+            let tx_out_pub_key =
+                api::external::CompressedRistretto::from(&block_contents.outputs[0].public_key);
+
+            let mut request = api::GetBlockIndexByTxPubKeyRequest::new();
+            request.set_tx_public_key(tx_out_pub_key);
 
-            assert!(response.is_err());
+            let response = client.get_block_index_by_tx_pub_key(&request).unwrap();
+            assert_eq!(block_index, response.block);
         }
     }
 
     #[test_with_logger]
-    /// Get mixins should not return an "excluded" TxOut.
-    fn test_get_mixins_excluded(logger: Logger) {
-        let mut rng: StdRng = SeedableRng::from_seed([74u8; 32]);
+    fn test_generate_transfer_code_tx(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
 
         let sender = AccountKey::random(&mut rng);
+        let data = MonitorData::new(
+            sender.clone(),
+            0,  // first_subaddress
+            20, // num_subaddresses
+            0,  // first_block
+            "", // name
+        )
+        .unwrap();
 
         // 1 known recipient, 3 random recipients and no monitors.
-        let (ledger_db, mobilecoind_db, client, _server, _server_conn_manager) =
+        let (mut ledger_db, mobilecoind_db, client, _server, _server_conn_manager) =
             get_testing_environment(
                 BLOCK_VERSION,
                 3,
@@ -3418,178 +6987,187 @@ mod test {
                 &mut rng,
             );
 
-        assert_eq!(ledger_db.num_txos().unwrap(), 40);
-
-        // A list of outputs to exclude.
-        let to_exclude: Vec<TxOut> = {
-            let data = MonitorData::new(
-                sender, 0,  // first_subaddress
-                20, // num_subaddresses
-                0,  // first_block
-                "", // name
-            )
-            .unwrap();
-
-            // Insert into database.
-            let monitor_id = mobilecoind_db.add_monitor(&data).unwrap();
-
-            // Allow the new monitor to process the ledger.
-            wait_for_monitors(&mobilecoind_db, &ledger_db, &logger);
+        // Insert into database.
+        let monitor_id = mobilecoind_db.add_monitor(&data).unwrap();
 
-            // Select some outputs from the ledger.
-            mobilecoind_db
-                .get_utxos_for_subaddress(&monitor_id, 0)
-                .unwrap()
-                .into_iter()
-                .map(|utxo| utxo.tx_out)
-                .collect()
-        };
+        // Allow the new monitor to process the ledger.
+        wait_for_monitors(&mobilecoind_db, &ledger_db, &logger);
 
-        assert_eq!(to_exclude.len(), 10);
+        // Get list of unspent tx outs
+        let utxos = mobilecoind_db
+            .get_utxos_for_subaddress(&monitor_id, 0)
+            .unwrap();
+        assert!(!utxos.is_empty());
 
-        // The ledger contains 40 outputs. Requesting 30 and excluding 10 should return
-        // exactly the remaining 30.
-        let mut request = api::GetMixinsRequest::new();
-        request.set_num_mixins(30);
-        request.set_excluded(RepeatedField::from_vec(
-            to_exclude.iter().map(api::external::TxOut::from).collect(),
+        // Call generate transfer code ctx.
+        let mut request = api::GenerateTransferCodeTxRequest::new();
+        request.set_sender_monitor_id(monitor_id.to_vec());
+        request.set_change_subaddress(0);
+        request.set_input_list(RepeatedField::from_vec(
+            utxos.iter().map(api::UnspentTxOut::from).collect(),
         ));
+        request.set_value(1337);
 
-        let response = client.get_mixins(&request).unwrap();
-
-        let mixins_with_proofs: Vec<api::TxOutWithProof> = response.get_mixins().to_vec();
-
-        // Should contain 30 mixins
-        assert_eq!(mixins_with_proofs.len(), 30);
-
-        // None of the excluded outputs should be returned as mixins.
-        let excluded_hashes: HashSet<_> = to_exclude.iter().map(|tx_out| tx_out.hash()).collect();
-
-        for mixin in &mixins_with_proofs {
-            let mixin: TxOut = TxOut::try_from(mixin.get_output()).unwrap();
-            assert!(!excluded_hashes.contains(&mixin.hash()));
-        }
-    }
-
-    #[test_with_logger]
-    /// Get mixins should return valid membership proofs.
-    fn test_get_mixins_membership_proofs(logger: Logger) {
-        let mut rng: StdRng = SeedableRng::from_seed([89u8; 32]);
-        let sender = AccountKey::random(&mut rng);
+        let response = client.generate_transfer_code_tx(&request).unwrap();
 
-        // 1 known recipient, 3 random recipients and no monitors.
-        let (ledger_db, _mobilecoind_db, client, _server, _server_conn_manager) =
-            get_testing_environment(
+        // Test that the generated transaction can be picked up by mobilecoind.
+        {
+            let tx_proposal = TxProposal::try_from(response.get_tx_proposal()).unwrap();
+            let key_images = tx_proposal.tx.key_images();
+            let outputs = tx_proposal.tx.prefix.outputs;
+            add_txos_and_key_images_to_ledger(
+                &mut ledger_db,
                 BLOCK_VERSION,
-                3,
-                &[sender.default_subaddress()],
-                &[],
-                logger.clone(),
+                outputs,
+                key_images,
                 &mut rng,
-            );
+            )
+            .unwrap();
 
-        let mixins_with_proofs: Vec<api::TxOutWithProof> = {
-            let mut request = api::GetMixinsRequest::new();
-            request.set_num_mixins(13);
-            let response = client.get_mixins(&request).unwrap();
-            response.get_mixins().to_vec()
-        };
+            // Use bip39 entropy to construct AccountKey.
+            let mnemonic =
+                Mnemonic::from_entropy(response.get_bip39_entropy(), Language::English).unwrap();
+            let key = mnemonic.derive_slip10_key(0);
+            let account_key = AccountKey::from(key);
 
-        assert_eq!(mixins_with_proofs.len(), 13);
+            // Add a monitor based on the entropy we received.
+            let monitor_data = MonitorData::new(
+                account_key,
+                DEFAULT_SUBADDRESS_INDEX, // first_subaddress
+                1,                        // num_subaddresses
+                0,                        // first_block
+                "",                       // name
+            )
+            .unwrap();
 
-        // Each membership proof should be correct.
-        for mixin_with_proof in &mixins_with_proofs {
-            let mixin: TxOut = TxOut::try_from(mixin_with_proof.get_output()).unwrap();
+            let monitor_id = mobilecoind_db.add_monitor(&monitor_data).unwrap();
 
-            // The returned proof should be correct.
-            let expected_proof = {
-                let index = ledger_db.get_tx_out_index_by_hash(&mixin.hash()).unwrap();
-                let proofs = ledger_db.get_tx_out_proof_of_memberships(&[index]).unwrap();
-                assert_eq!(proofs.len(), 1);
-                api::external::TxOutMembershipProof::from(&proofs[0])
-            };
+            // Wait for sync to complete.
+            wait_for_monitors(&mobilecoind_db, &ledger_db, &logger);
 
-            assert_eq!(mixin_with_proof.get_proof(), &expected_proof);
+            // Get utxos for the new account and verify we only have one utxo.
+            let utxos = mobilecoind_db
+                .get_utxos_for_subaddress(&monitor_id, DEFAULT_SUBADDRESS_INDEX)
+                .unwrap();
+            assert_eq!(utxos.len(), 1);
+
+            let utxo = &utxos[0];
+
+            assert_eq!(utxo.value, 1337);
+            assert_eq!(
+                utxo.tx_out.public_key,
+                RistrettoPublic::try_from(response.get_tx_public_key())
+                    .unwrap()
+                    .into()
+            );
         }
     }
 
     #[test_with_logger]
-    /// Should return a correct proof-of-membership for each requested TxOut.
-    fn test_get_membership_proofs(logger: Logger) {
+    fn test_generate_optimization_tx(logger: Logger) {
         let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
 
         let sender = AccountKey::random(&mut rng);
+        let sender_default_subaddress = sender.default_subaddress();
         let data = MonitorData::new(
-            sender.clone(),
-            0,  // first_subaddress
+            sender, 0,  // first_subaddress
             20, // num_subaddresses
             0,  // first_block
             "", // name
         )
         .unwrap();
 
-        // 1 known recipient, 3 random recipients and no monitors.
-        let (ledger_db, mobilecoind_db, client, _server, _server_conn_manager) =
+        // 1 known recipient, and a bunch of random recipients and no monitors.
+        // The random recipients are needed for mixins.
+        let num_random_recipients = MAX_INPUTS as u32 * RING_SIZE as u32
+            / test_utils::GET_TESTING_ENVIRONMENT_NUM_BLOCKS as u32;
+        let (mut ledger_db, mobilecoind_db, client, _server, _server_conn_manager) =
             get_testing_environment(
                 BLOCK_VERSION,
-                3,
-                &[sender.default_subaddress()],
+                num_random_recipients as u32,
+                &[sender_default_subaddress.clone()],
                 &[],
                 logger.clone(),
                 &mut rng,
             );
 
+        // Add a bunch of blocks/utxos for our recipient.
+        for _ in 0..MAX_INPUTS {
+            let _ = add_block_to_ledger(
+                &mut ledger_db,
+                BLOCK_VERSION,
+                &[sender_default_subaddress.clone()],
+                Amount::new(DEFAULT_PER_RECIPIENT_AMOUNT, Mob::ID),
+                &[KeyImage::from(rng.next_u64())],
+                &mut rng,
+            );
+        }
+
         // Insert into database.
         let monitor_id = mobilecoind_db.add_monitor(&data).unwrap();
 
         // Allow the new monitor to process the ledger.
         wait_for_monitors(&mobilecoind_db, &ledger_db, &logger);
 
-        // Select some outputs from the ledger.
-        let outputs: Vec<TxOut> = {
-            let unspent_outputs = mobilecoind_db
-                .get_utxos_for_subaddress(&monitor_id, 0)
-                .unwrap();
+        // Get list of unspent tx outs
+        let utxos = mobilecoind_db
+            .get_utxos_for_subaddress(&monitor_id, 0)
+            .unwrap();
+        assert!(!utxos.is_empty());
 
-            vec![
-                unspent_outputs[1].tx_out.clone(),
-                unspent_outputs[3].tx_out.clone(),
-                unspent_outputs[5].tx_out.clone(),
-            ]
-        };
+        // Call generate optimization tx.
+        let mut request = api::GenerateOptimizationTxRequest::new();
+        request.set_monitor_id(monitor_id.to_vec());
+        request.set_subaddress(0);
 
-        let mut request = api::GetMembershipProofsRequest::new();
-        request.set_outputs(RepeatedField::from_vec(
-            outputs.iter().map(api::external::TxOut::from).collect(),
-        ));
+        let response = client.generate_optimization_tx(&request).unwrap();
 
-        let response = client.get_membership_proofs(&request).unwrap();
+        // Sanity test the response.
+        let tx_proposal = TxProposal::try_from(response.get_tx_proposal()).unwrap();
 
-        // The response should should contain an element for each requested output.
-        assert_eq!(response.output_list.len(), outputs.len());
+        let expected_num_inputs: usize = MAX_INPUTS as usize;
+        assert_eq!(tx_proposal.utxos.len(), expected_num_inputs);
+        assert_eq!(tx_proposal.tx.prefix.inputs.len(), expected_num_inputs);
 
-        for (tx_out, output_with_proof) in outputs.iter().zip(response.get_output_list().iter()) {
-            // The response should contain a TxOutWithProof for each requested TxOut.
-            assert_eq!(
-                output_with_proof.get_output(),
-                &api::external::TxOut::from(tx_out)
-            );
+        assert_eq!(tx_proposal.outlays.len(), 1);
+        assert_eq!(tx_proposal.outlays[0].receiver, data.subaddress(0).unwrap());
+        assert_eq!(
+            tx_proposal.outlays[0].value,
+            // Each UTXO we have has PER_RECIPIENT_AMOUNT coins. We will be merging MAX_INPUTS of
+            // those into a single output, minus the fee.
+            (DEFAULT_PER_RECIPIENT_AMOUNT * MAX_INPUTS as u64) - Mob::MINIMUM_FEE,
+        );
 
-            // The returned proof should be correct.
-            let expected_proof = {
-                let index = ledger_db.get_tx_out_index_by_hash(&tx_out.hash()).unwrap();
-                let proofs = ledger_db.get_tx_out_proof_of_memberships(&[index]).unwrap();
-                assert_eq!(proofs.len(), 1);
+        assert_eq!(tx_proposal.outlay_index_to_tx_out_index.len(), 1);
+        assert_eq!(tx_proposal.outlay_index_to_tx_out_index[&0], 0);
 
-                api::external::TxOutMembershipProof::from(&proofs[0])
-            };
+        assert_eq!(tx_proposal.tx.prefix.outputs.len(), 1);
+        let tx_out = &tx_proposal.tx.prefix.outputs[0];
+        let tx_public_key = RistrettoPublic::try_from(&tx_out.public_key).unwrap();
+        let shared_secret =
+            get_tx_out_shared_secret(data.view_private_key().unwrap(), &tx_public_key);
+        let (amount, _blinding) = tx_out
+            .get_masked_amount()
+            .unwrap()
+            .get_value(&shared_secret)
+            .unwrap();
+        assert_eq!(amount.value, tx_proposal.outlays[0].value);
+        assert_eq!(amount.token_id, Mob::ID);
+
+        // Santity test fee
+        assert_eq!(tx_proposal.fee(), Mob::MINIMUM_FEE);
+        assert_eq!(tx_proposal.tx.prefix.fee, Mob::MINIMUM_FEE);
 
-            assert_eq!(output_with_proof.get_proof(), &expected_proof);
-        }
+        // Sanity test tombstone block
+        let num_blocks = ledger_db.num_blocks().unwrap();
+        assert_eq!(
+            tx_proposal.tx.prefix.tombstone_block,
+            num_blocks + DEFAULT_NEW_TX_BLOCK_ATTEMPTS
+        );
     }
 
     #[test_with_logger]
-    fn test_generate_tx(logger: Logger) {
+    fn test_generate_replacement_tx(logger: Logger) {
         let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
 
         let sender = AccountKey::random(&mut rng);
@@ -3613,22 +7191,6 @@ mod test {
                 &mut rng,
             );
 
-        // Add a block with a non-MOB token ID.
-        add_block_to_ledger(
-            &mut ledger_db,
-            BlockVersion::MAX,
-            &[
-                AccountKey::random(&mut rng).default_subaddress(),
-                AccountKey::random(&mut rng).default_subaddress(),
-                AccountKey::random(&mut rng).default_subaddress(),
-                sender.default_subaddress(),
-            ],
-            Amount::new(1_000_000_000_000, TokenId::from(2)),
-            &[KeyImage::from(101)],
-            &mut rng,
-        )
-        .unwrap();
-
         // Insert into database.
         let monitor_id = mobilecoind_db.add_monitor(&data).unwrap();
 
@@ -3641,463 +7203,313 @@ mod test {
             .unwrap();
         assert!(!utxos.is_empty());
 
-        // Generate two random recipients.
-        let receiver1 = AccountKey::random(&mut rng);
-        let receiver2 = AccountKey::random(&mut rng);
-
-        let outlays = vec![
-            Outlay {
-                value: 123,
-                receiver: receiver1.default_subaddress(),
-            },
-            Outlay {
-                value: 456,
-                receiver: receiver2.default_subaddress(),
-            },
-        ];
+        // Generate and submit a payment to a random recipient.
+        let receiver = AccountKey::random(&mut rng);
+        let outlays = vec![Outlay {
+            value: 123,
+            receiver: receiver.default_subaddress(),
+        }];
 
-        // Call generate tx.
         let mut request = api::GenerateTxRequest::new();
         request.set_sender_monitor_id(monitor_id.to_vec());
         request.set_change_subaddress(0);
         request.set_input_list(RepeatedField::from_vec(
-            utxos
-                .iter()
-                .filter(|utxo| utxo.token_id == *Mob::ID)
-                .map(api::UnspentTxOut::from)
-                .collect(),
+            utxos.iter().map(api::UnspentTxOut::from).collect(),
         ));
         request.set_outlay_list(RepeatedField::from_vec(
             outlays.iter().map(api::Outlay::from).collect(),
         ));
 
-        // Test the happy flow for MOB.
-        {
-            let response = client.generate_tx(&request).unwrap();
-
-            // Sanity test the response.
-            let tx_proposal = response.get_tx_proposal();
-
-            let expected_num_inputs: u64 = (outlays.iter().map(|outlay| outlay.value).sum::<u64>()
-                / test_utils::DEFAULT_PER_RECIPIENT_AMOUNT)
-                + 1;
-            assert_eq!(
-                tx_proposal.get_input_list().len(),
-                expected_num_inputs as usize
-            );
-            assert_eq!(
-                tx_proposal.get_tx().get_prefix().get_inputs().len(),
-                expected_num_inputs as usize
-            );
-            assert_eq!(tx_proposal.get_outlay_list(), request.get_outlay_list());
-            assert_eq!(
-                tx_proposal.get_tx().get_prefix().get_outputs().len(),
-                outlays.len() + 1
-            ); // Extra output for change.
-
-            let tx = Tx::try_from(tx_proposal.get_tx()).unwrap();
-
-            // The transaction should contain an output for each outlay, and one for change.
-            assert_eq!(tx.prefix.outputs.len(), outlays.len() + 1);
-
-            // The transaction should have a confirmation code for each outlay
-            assert_eq!(
-                outlays.len(),
-                tx_proposal.get_outlay_confirmation_numbers().len()
-            );
-
-            let change_value = test_utils::DEFAULT_PER_RECIPIENT_AMOUNT
-                - outlays.iter().map(|outlay| outlay.value).sum::<u64>()
-                - Mob::MINIMUM_FEE;
-
-            for (account_key, expected_value) in &[
-                (&receiver1, outlays[0].value),
-                (&receiver2, outlays[1].value),
-                (&sender, change_value),
-            ] {
-                // Find the first output belonging to the account, and get its value.
-                // This assumes that each output is sent to a different account key.
-                let (amount, _blinding) = tx
-                    .prefix
-                    .outputs
-                    .iter()
-                    .find_map(|tx_out| {
-                        let output_public_key =
-                            RistrettoPublic::try_from(&tx_out.public_key).unwrap();
-                        let shared_secret = get_tx_out_shared_secret(
-                            account_key.view_private_key(),
-                            &output_public_key,
-                        );
-                        tx_out
-                            .get_masked_amount()
-                            .unwrap()
-                            .get_value(&shared_secret)
-                            .ok()
-                    })
-                    .expect("There should be an output belonging to the account key.");
+        let response = client.generate_tx(&request).unwrap();
+        let tx_proposal = TxProposal::try_from(response.get_tx_proposal()).unwrap();
 
-                assert_eq!(amount.token_id, Mob::ID);
-                assert_eq!(amount.value, *expected_value);
-            }
+        let mut request = api::SubmitTxRequest::new();
+        request.set_tx_proposal(api::TxProposal::from(&tx_proposal));
+        let submit_response = client.submit_tx(&request).unwrap();
 
-            // Santity test fee
-            assert_eq!(tx_proposal.get_fee(), Mob::MINIMUM_FEE);
-            assert_eq!(tx_proposal.get_tx().get_prefix().fee, Mob::MINIMUM_FEE);
+        // A replacement fee that is not higher than the original fee should fail.
+        {
+            let mut request = api::GenerateReplacementTxRequest::new();
+            request.set_sender_monitor_id(monitor_id.to_vec());
+            request.set_tx_proposal(api::TxProposal::from(&tx_proposal));
+            request.set_fee(tx_proposal.fee());
 
-            // Sanity test tombstone block
-            let num_blocks = ledger_db.num_blocks().unwrap();
-            assert_eq!(
-                tx_proposal.get_tx().get_prefix().tombstone_block,
-                num_blocks + DEFAULT_NEW_TX_BLOCK_ATTEMPTS
-            );
+            assert!(client.generate_replacement_tx(&request).is_err());
         }
 
-        // Test the happy flow for TokenId(2)
+        // A change subaddress not covered by the monitor should fail.
         {
-            let mut request = api::GenerateTxRequest::new();
+            let mut request = api::GenerateReplacementTxRequest::new();
             request.set_sender_monitor_id(monitor_id.to_vec());
-            request.set_change_subaddress(0);
-            request.set_input_list(RepeatedField::from_vec(
-                utxos
-                    .iter()
-                    .filter(|utxo| utxo.token_id == 2)
-                    .map(api::UnspentTxOut::from)
-                    .collect(),
-            ));
-            request.set_outlay_list(RepeatedField::from_vec(
-                outlays.iter().map(api::Outlay::from).collect(),
-            ));
-            request.set_token_id(2);
-
-            let fee = 10_000;
-            request.set_fee(fee);
-
-            let response = client.generate_tx(&request).unwrap();
-
-            // Sanity test the response.
-            let tx_proposal = response.get_tx_proposal();
-
-            assert_eq!(tx_proposal.get_input_list().len(), 1,);
-            assert_eq!(tx_proposal.get_tx().get_prefix().get_inputs().len(), 1,);
-            assert_eq!(tx_proposal.get_outlay_list(), request.get_outlay_list());
-            assert_eq!(
-                tx_proposal.get_tx().get_prefix().get_outputs().len(),
-                outlays.len() + 1
-            ); // Extra output for change.
-
-            let tx = Tx::try_from(tx_proposal.get_tx()).unwrap();
-
-            // The transaction should contain an output for each outlay, and one for change.
-            assert_eq!(tx.prefix.outputs.len(), outlays.len() + 1);
-
-            // The transaction should have a confirmation code for each outlay
-            assert_eq!(
-                outlays.len(),
-                tx_proposal.get_outlay_confirmation_numbers().len()
-            );
-
-            let change_value =
-                1_000_000_000_000 - outlays.iter().map(|outlay| outlay.value).sum::<u64>() - fee;
-
-            for (account_key, expected_value) in &[
-                (&receiver1, outlays[0].value),
-                (&receiver2, outlays[1].value),
-                (&sender, change_value),
-            ] {
-                // Find the first output belonging to the account, and get its value.
-                // This assumes that each output is sent to a different account key.
-                let (amount, _blinding) = tx
-                    .prefix
-                    .outputs
-                    .iter()
-                    .find_map(|tx_out| {
-                        let output_public_key =
-                            RistrettoPublic::try_from(&tx_out.public_key).unwrap();
-                        let shared_secret = get_tx_out_shared_secret(
-                            account_key.view_private_key(),
-                            &output_public_key,
-                        );
-                        tx_out
-                            .get_masked_amount()
-                            .unwrap()
-                            .get_value(&shared_secret)
-                            .ok()
-                    })
-                    .expect("There should be an output belonging to the account key.");
+            request.set_tx_proposal(api::TxProposal::from(&tx_proposal));
+            request.set_change_subaddress(data.num_subaddresses + 1);
 
-                assert_eq!(amount.token_id, TokenId::from(2));
-                assert_eq!(amount.value, *expected_value);
-            }
+            assert!(client.generate_replacement_tx(&request).is_err());
+        }
 
-            // Santity test fee
-            assert_eq!(tx_proposal.get_fee(), fee);
-            assert_eq!(tx_proposal.get_tx().get_prefix().fee, fee);
+        // Generate a replacement with the default fee.
+        let mut request = api::GenerateReplacementTxRequest::new();
+        request.set_sender_monitor_id(monitor_id.to_vec());
+        request.set_tx_proposal(api::TxProposal::from(&tx_proposal));
 
-            // Sanity test tombstone block
-            let num_blocks = ledger_db.num_blocks().unwrap();
-            assert_eq!(
-                tx_proposal.get_tx().get_prefix().tombstone_block,
-                num_blocks + DEFAULT_NEW_TX_BLOCK_ATTEMPTS
-            );
-        }
+        let response = client.generate_replacement_tx(&request).unwrap();
+        let replacement_tx_proposal = TxProposal::try_from(response.get_tx_proposal()).unwrap();
 
-        // Invalid input scenarios should result in an error.
-        {
-            // No monitor id
-            let mut request = request.clone();
-            request.set_sender_monitor_id(vec![]);
-            assert!(client.generate_tx(&request).is_err());
-        }
+        // The replacement spends a single input of the original transaction.
+        assert_eq!(replacement_tx_proposal.utxos.len(), 1);
+        let replacement_utxo = &replacement_tx_proposal.utxos[0];
+        assert!(tx_proposal.utxos.contains(replacement_utxo));
 
-        {
-            // Unrecognized monitor id
-            let sender = AccountKey::random(&mut rng);
-            let data = MonitorData::new(
-                sender, 0,  // first_subaddress
-                20, // num_subaddresses
-                0,  // first_block
-                "", // name
-            )
-            .unwrap();
+        // It pays a higher fee.
+        assert_eq!(replacement_tx_proposal.fee(), tx_proposal.fee() * 2);
 
-            let mut request = request.clone();
-            request.set_sender_monitor_id(MonitorId::from(&data).to_vec());
-            assert!(client.generate_tx(&request).is_err());
-        }
+        // And pays everything else back to the sender.
+        assert_eq!(replacement_tx_proposal.outlays.len(), 1);
+        assert_eq!(
+            replacement_tx_proposal.outlays[0].receiver,
+            sender.subaddress(0)
+        );
+        assert_eq!(
+            replacement_tx_proposal.outlays[0].value,
+            replacement_utxo.value - replacement_tx_proposal.fee()
+        );
 
-        {
-            // Subaddress index out of range
-            let mut request = request.clone();
-            request.set_change_subaddress(data.first_subaddress + data.num_subaddresses + 1);
-            assert!(client.generate_tx(&request).is_err());
-        }
+        let mut request = api::SubmitTxRequest::new();
+        request.set_tx_proposal(api::TxProposal::from(&replacement_tx_proposal));
+        let replacement_submit_response = client.submit_tx(&request).unwrap();
 
-        {
-            // Junk input
-            let mut request = request.clone();
-            request.mut_input_list().push(api::UnspentTxOut::default());
-            assert!(client.generate_tx(&request).is_err());
-        }
+        // Neither transaction has landed yet.
+        assert_eq!(
+            client
+                .get_tx_status_as_sender(&submit_response)
+                .unwrap()
+                .get_status(),
+            api::TxStatus::Unknown
+        );
+        assert_eq!(
+            client
+                .get_tx_status_as_sender(&replacement_submit_response)
+                .unwrap()
+                .get_status(),
+            api::TxStatus::Unknown
+        );
 
-        {
-            // Attempt to spend more than we have
-            let num_blocks = ledger_db.num_blocks().unwrap();
-            let mut request = request.clone();
-            request.set_outlay_list(RepeatedField::from_vec(vec![api::Outlay::from(&Outlay {
-                receiver: receiver1.default_subaddress(),
-                value: test_utils::DEFAULT_PER_RECIPIENT_AMOUNT * num_blocks,
-            })]));
-            assert!(client.generate_tx(&request).is_err());
-        }
+        // Land the replacement.
+        add_txos_and_key_images_to_ledger(
+            &mut ledger_db,
+            BLOCK_VERSION,
+            replacement_tx_proposal.tx.prefix.outputs.clone(),
+            vec![replacement_utxo.key_image],
+            &mut rng,
+        )
+        .unwrap();
 
-        {
-            // Mixing input tokens (utxos has both Mob and TokenId(2))
-            let mut request = api::GenerateTxRequest::new();
-            request.set_sender_monitor_id(monitor_id.to_vec());
-            request.set_change_subaddress(0);
-            request.set_input_list(RepeatedField::from_vec(
-                utxos.iter().map(api::UnspentTxOut::from).collect(),
-            ));
-            request.set_outlay_list(RepeatedField::from_vec(
-                outlays.iter().map(api::Outlay::from).collect(),
-            ));
-            assert!(client.generate_tx(&request).is_err());
-        }
+        // The replacement is verified, and the original is reported as replaced.
+        assert_eq!(
+            client
+                .get_tx_status_as_sender(&replacement_submit_response)
+                .unwrap()
+                .get_status(),
+            api::TxStatus::Verified
+        );
+        assert_eq!(
+            client
+                .get_tx_status_as_sender(&submit_response)
+                .unwrap()
+                .get_status(),
+            api::TxStatus::TransactionReplaced
+        );
     }
 
     #[test_with_logger]
-    fn test_generate_burn_redemption_tx(logger: Logger) {
-        let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
+    fn test_swap(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([24u8; 32]);
         let token_id2 = TokenId::from(2);
 
-        let sender = AccountKey::random(&mut rng);
-        let data = MonitorData::new(
-            sender.clone(),
-            0,  // first_subaddress
-            20, // num_subaddresses
-            0,  // first_block
-            "", // name
-        )
-        .unwrap();
+        let maker = AccountKey::random(&mut rng);
+        let taker = AccountKey::random(&mut rng);
 
         // 1 known recipient, 3 random recipients and no monitors.
         let (mut ledger_db, mobilecoind_db, client, _server, _server_conn_manager) =
             get_testing_environment(
                 BLOCK_VERSION,
                 3,
-                &[sender.default_subaddress()],
+                &[maker.default_subaddress()],
                 &[],
                 logger.clone(),
                 &mut rng,
             );
 
-        // Add a block with a non-MOB token ID.
+        // Give the taker some of the counter token.
         add_block_to_ledger(
             &mut ledger_db,
-            BlockVersion::MAX,
-            &[
-                AccountKey::random(&mut rng).default_subaddress(),
-                AccountKey::random(&mut rng).default_subaddress(),
-                AccountKey::random(&mut rng).default_subaddress(),
-                sender.default_subaddress(),
-            ],
+            BLOCK_VERSION,
+            &[taker.default_subaddress()],
             Amount::new(1_000_000_000_000, token_id2),
             &[KeyImage::from(101)],
             &mut rng,
         )
         .unwrap();
 
-        // Insert into database.
-        let monitor_id = mobilecoind_db.add_monitor(&data).unwrap();
+        let maker_monitor_id = mobilecoind_db
+            .add_monitor(&MonitorData::new(maker.clone(), 0, 20, 0, "").unwrap())
+            .unwrap();
+        let taker_monitor_id = mobilecoind_db
+            .add_monitor(&MonitorData::new(taker.clone(), 0, 20, 0, "").unwrap())
+            .unwrap();
 
-        // Allow the new monitor to process the ledger.
+        // Allow the new monitors to process the ledger.
         wait_for_monitors(&mobilecoind_db, &ledger_db, &logger);
 
-        // Get list of unspent tx outs that we want to burn
-        let utxos = mobilecoind_db
-            .get_utxos_for_subaddress(&monitor_id, 0)
+        let maker_utxos = mobilecoind_db
+            .get_utxos_for_subaddress(&maker_monitor_id, 0)
+            .unwrap();
+        let offered_utxo = maker_utxos[0].clone();
+        let taker_utxos = mobilecoind_db
+            .get_utxos_for_subaddress(&taker_monitor_id, 0)
             .unwrap()
-            .iter()
-            .filter(|utxo| utxo.token_id == token_id2)
-            .map(Into::into)
-            .collect::<RepeatedField<_>>();
-        assert!(!utxos.is_empty());
-
-        // Prepare request
-        let mut request = api::GenerateBurnRedemptionTxRequest::new();
-        request.set_sender_monitor_id(monitor_id.to_vec());
+            .into_iter()
+            .filter(|utxo| utxo.token_id == *token_id2)
+            .collect::<Vec<_>>();
+        assert!(!taker_utxos.is_empty());
+
+        // The maker offers their whole utxo for 1000 of token 2, allowing partial
+        // fills of at least a tenth of it.
+        let min_fill_value = offered_utxo.value / 10;
+        let mut request = api::GenerateSwapRequest::new();
+        request.set_sender_monitor_id(maker_monitor_id.to_vec());
         request.set_change_subaddress(0);
-        request.set_input_list(utxos);
-        request.set_burn_amount(100_000);
-        request.set_fee(200_000);
-        request.set_token_id(*token_id2);
-        request.set_redemption_memo(vec![5u8; BurnRedemptionMemo::MEMO_DATA_LEN]);
-        request.set_enable_destination_memo(true);
-
-        // Test the happy flow.
-        {
-            let response = client.generate_burn_redemption_tx(&request).unwrap();
-
-            // Sanity test the response.
-            let tx_proposal = response.get_tx_proposal();
-            let tx = Tx::try_from(tx_proposal.get_tx()).unwrap();
-
-            // Two outputs - change and burn
-            assert_eq!(tx.prefix.outputs.len(), 2);
-
-            // Validate the change output.
-            let (change_tx_out, change_amount) = tx
-                .prefix
-                .outputs
-                .iter()
-                .find_map(|tx_out| {
-                    tx_out
-                        .view_key_match(sender.view_private_key())
-                        .map(|(amount, _commitment)| (tx_out.clone(), amount))
-                        .ok()
-                })
-                .expect("Didn't find sender's change output");
-
-            assert_eq!(change_amount.value, 1_000_000_000_000 - 100_000 - 200_000);
+        request.set_utxo((&offered_utxo).into());
+        request.set_counter_token_id(*token_id2);
+        request.set_counter_value(1000);
+        request.set_allow_partial_fill(true);
+        request.set_min_fill_value(min_fill_value);
+
+        let response = client.generate_swap(&request).unwrap();
+        let sci = response.get_sci().clone();
+
+        let quote = response.get_quote();
+        assert_eq!(quote.get_base_token_id(), *Mob::ID);
+        assert_eq!(quote.get_base_value(), offered_utxo.value);
+        assert_eq!(quote.get_counter_token_id(), *token_id2);
+        assert_eq!(quote.get_counter_value(), 1000);
+        assert!(quote.get_partial_fill());
+        assert_eq!(quote.get_min_fill_value(), min_fill_value);
+
+        // An offer that was tampered with is reported, but not used.
+        let mut tampered_sci = sci.clone();
+        let mut pseudo_output_amount = tampered_sci.get_pseudo_output_amount().clone();
+        pseudo_output_amount.set_value(offered_utxo.value + 1);
+        tampered_sci.set_pseudo_output_amount(pseudo_output_amount);
+
+        let base_value = offered_utxo.value / 2;
+        let mut request = api::GetSwapFillsRequest::new();
+        request.set_sci_list(RepeatedField::from_vec(vec![tampered_sci, sci.clone()]));
+        request.set_base_token_id(*Mob::ID);
+        request.set_counter_token_id(*token_id2);
+        request.set_base_value(base_value);
+
+        let response = client.get_swap_fills(&request).unwrap();
+        let offers = response.get_offer_list();
+        assert_eq!(offers.len(), 2);
+        assert!(!offers[0].has_quote());
+        assert!(!offers[0].get_error().is_empty());
+        assert_eq!(offers[1].get_quote(), quote);
+        assert!(offers[1].get_error().is_empty());
+
+        let expected_counter_value = ((1000 * base_value as u128 + offered_utxo.value as u128 - 1)
+            / offered_utxo.value as u128) as u64;
+        let fills = response.get_fill_list();
+        assert_eq!(fills.len(), 1);
+        assert_eq!(fills[0].get_index(), 1);
+        assert_eq!(fills[0].get_base_value(), base_value);
+        assert_eq!(fills[0].get_counter_value(), expected_counter_value);
+
+        // The taker fills half of the offer.
+        let mut request = api::GenerateSwapFillTxRequest::new();
+        request.set_sender_monitor_id(taker_monitor_id.to_vec());
+        request.set_change_subaddress(0);
+        request.set_sci_list(RepeatedField::from_vec(vec![sci.clone()]));
+        request.set_base_value_list(vec![base_value]);
+        request.set_input_list(RepeatedField::from_vec(
+            taker_utxos.iter().map(api::UnspentTxOut::from).collect(),
+        ));
 
-            let ss = get_tx_out_shared_secret(
-                sender.view_private_key(),
-                &RistrettoPublic::try_from(&change_tx_out.public_key).unwrap(),
-            );
-            let memo = change_tx_out.e_memo.unwrap().decrypt(&ss);
-            match MemoType::try_from(&memo).expect("Couldn't decrypt memo") {
-                MemoType::Destination(memo) => {
-                    assert_eq!(
-                        memo.get_address_hash(),
-                        &ShortAddressHash::from(&burn_address()),
-                        "lookup based on address hash failed"
-                    );
-                    assert_eq!(memo.get_num_recipients(), 1);
-                    assert_eq!(memo.get_fee(), 200_000);
-                    assert_eq!(
-                        memo.get_total_outlay(),
-                        300_000,
-                        "outlay should be amount sent to recipient + fee"
-                    );
-                }
-                _ => {
-                    panic!("unexpected memo type")
-                }
+        // A fee that overflows the total paid for the offers is rejected.
+        let mut overflowing_request = request.clone();
+        overflowing_request.set_fee(u64::MAX);
+        match client.generate_swap_fill_tx(&overflowing_request) {
+            Ok(_) => panic!("Should've returned an error"),
+            Err(GrpcError::RpcFailure(rpc_status)) => {
+                assert_eq!(rpc_status.code(), RpcStatusCode::INVALID_ARGUMENT);
             }
+            Err(err) => panic!("Unexpected error: {:?}", err),
+        };
 
-            // Validate the burn output.
-            let (burn_tx_out, burn_amount) = tx
-                .prefix
-                .outputs
-                .iter()
-                .find_map(|tx_out| {
-                    tx_out
-                        .view_key_match(&burn_address_view_private())
-                        .map(|(amount, _commitment)| (tx_out.clone(), amount))
-                        .ok()
-                })
-                .expect("Didn't find burn output");
-
-            assert_eq!(burn_amount.value, 100_000);
-
-            let ss = get_tx_out_shared_secret(
-                &burn_address_view_private(),
-                &RistrettoPublic::try_from(&burn_tx_out.public_key).unwrap(),
-            );
-            let memo = burn_tx_out.e_memo.unwrap().decrypt(&ss);
-            assert_matches!(MemoType::try_from(&memo).expect("Couldn't decrypt memo"), MemoType::BurnRedemption(memo) if memo.memo_data() == &[5u8; 64]);
-        }
-
-        // Invalid memo data length results in an error.
-        {
-            let mut request = request.clone();
-            request.set_redemption_memo(vec![5u8; BurnRedemptionMemo::MEMO_DATA_LEN + 1]);
-            assert!(client.generate_burn_redemption_tx(&request).is_err());
-        }
-
-        // Trying to burn more than we have results in an error.
-        {
-            let mut request = request.clone();
-            request.set_burn_amount(1_000_000_000_000 - request.get_fee() + 1);
-            assert!(client.generate_burn_redemption_tx(&request).is_err());
-        }
-    }
+        let response = client.generate_swap_fill_tx(&request).unwrap();
+        let tx_proposal = TxProposal::try_from(response.get_tx_proposal()).unwrap();
+        let tx = &tx_proposal.tx;
 
-    #[test_with_logger]
-    fn test_get_block_index_by_tx_pub_key(logger: Logger) {
-        let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
+        // The transaction spends the taker's input and the offer, and pays the fee
+        // in the counter token.
+        assert_eq!(tx_proposal.utxos.len(), 1);
+        assert_eq!(tx.prefix.inputs.len(), 2);
+        assert_eq!(tx.prefix.fee_token_id, *token_id2);
+        assert_eq!(
+            tx_proposal.outlays,
+            vec![Outlay {
+                value: base_value,
+                receiver: taker.subaddress(0),
+            }]
+        );
 
-        // no known recipient, 3 random recipients and no monitors.
-        let (ledger_db, _mobilecoind_db, client, _server, _server_conn_manager) =
-            get_testing_environment(BLOCK_VERSION, 3, &[], &[], logger.clone(), &mut rng);
+        // The taker receives the filled value.
+        let taker_output = &tx.prefix.outputs[tx_proposal.outlay_index_to_tx_out_index[&0]];
+        let (amount, _) = taker_output
+            .view_key_match(taker.view_private_key())
+            .unwrap();
+        assert_eq!(amount, Amount::new(base_value, Mob::ID));
 
-        // Grab the first TxOut of each block in the database and verify its index.
-        for block_index in 0..test_utils::GET_TESTING_ENVIRONMENT_NUM_BLOCKS as u64 {
-            let block_contents = ledger_db.get_block_contents(block_index).unwrap();
-            let tx_out_pub_key =
-                api::external::CompressedRistretto::from(&block_contents.outputs[0].public_key);
+        // The maker receives the counter value, and the rest of their utxo back.
+        let maker_amounts = tx
+            .prefix
+            .outputs
+            .iter()
+            .filter_map(|tx_out| tx_out.view_key_match(maker.view_private_key()).ok())
+            .map(|(amount, _)| amount)
+            .collect::<Vec<_>>();
+        assert!(maker_amounts.contains(&Amount::new(expected_counter_value, token_id2)));
+        assert!(maker_amounts.contains(&Amount::new(offered_utxo.value - base_value, Mob::ID)));
+
+        // Once the offer is spent it can no longer be filled.
+        let key_image = SignedContingentInput::try_from(&sci).unwrap().key_image();
+        add_block_to_ledger(
+            &mut ledger_db,
+            BLOCK_VERSION,
+            &[AccountKey::random(&mut rng).default_subaddress()],
+            Amount::new(DEFAULT_PER_RECIPIENT_AMOUNT, Mob::ID),
+            &[key_image],
+            &mut rng,
+        )
+        .unwrap();
 
-            let mut request = api::GetBlockIndexByTxPubKeyRequest::new();
-            request.set_tx_public_key(tx_out_pub_key);
+        let mut request = api::GetSwapFillsRequest::new();
+        request.set_sci_list(RepeatedField::from_vec(vec![sci]));
+        request.set_base_token_id(*Mob::ID);
+        request.set_counter_token_id(*token_id2);
+        request.set_base_value(base_value);
 
-            let response = client.get_block_index_by_tx_pub_key(&request).unwrap();
-            assert_eq!(block_index, response.block);
-        }
+        let response = client.get_swap_fills(&request).unwrap();
+        assert!(!response.get_offer_list()[0].get_error().is_empty());
+        assert!(response.get_fill_list().is_empty());
     }
 
     #[test_with_logger]
-    fn test_generate_transfer_code_tx(logger: Logger) {
-        let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
+    fn test_gift_code(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([25u8; 32]);
 
         let sender = AccountKey::random(&mut rng);
-        let data = MonitorData::new(
-            sender.clone(),
-            0,  // first_subaddress
-            20, // num_subaddresses
-            0,  // first_block
-            "", // name
-        )
-        .unwrap();
+        let recipient = AccountKey::random(&mut rng);
 
         // 1 known recipient, 3 random recipients and no monitors.
         let (mut ledger_db, mobilecoind_db, client, _server, _server_conn_manager) =
@@ -4110,186 +7522,370 @@ mod test {
                 &mut rng,
             );
 
-        // Insert into database.
-        let monitor_id = mobilecoind_db.add_monitor(&data).unwrap();
+        let sender_monitor_id = mobilecoind_db
+            .add_monitor(&MonitorData::new(sender.clone(), 0, 20, 0, "").unwrap())
+            .unwrap();
+        let recipient_monitor_id = mobilecoind_db
+            .add_monitor(&MonitorData::new(recipient.clone(), 0, 20, 0, "").unwrap())
+            .unwrap();
 
-        // Allow the new monitor to process the ledger.
+        // Allow the new monitors to process the ledger.
         wait_for_monitors(&mobilecoind_db, &ledger_db, &logger);
 
-        // Get list of unspent tx outs
-        let utxos = mobilecoind_db
-            .get_utxos_for_subaddress(&monitor_id, 0)
+        // Lands a transaction in the ledger, and waits for the monitors to process
+        // it.
+        let land_tx = |ledger_db: &mut LedgerDB, tx_proposal: &TxProposal, rng: &mut StdRng| {
+            add_txos_and_key_images_to_ledger(
+                ledger_db,
+                BLOCK_VERSION,
+                tx_proposal.tx.prefix.outputs.clone(),
+                tx_proposal.tx.key_images(),
+                rng,
+            )
             .unwrap();
-        assert!(!utxos.is_empty());
+            wait_for_monitors(&mobilecoind_db, ledger_db, &logger);
+        };
 
-        // Call generate transfer code ctx.
-        let mut request = api::GenerateTransferCodeTxRequest::new();
-        request.set_sender_monitor_id(monitor_id.to_vec());
-        request.set_change_subaddress(0);
-        request.set_input_list(RepeatedField::from_vec(
-            utxos.iter().map(api::UnspentTxOut::from).collect(),
-        ));
-        request.set_value(1337);
+        // Funds a gift code from the sender, and creates it once the funding
+        // transaction lands.
+        let fund_gift_code = |ledger_db: &mut LedgerDB, value: u64, rng: &mut StdRng| {
+            let utxos = mobilecoind_db
+                .get_utxos_for_subaddress(&sender_monitor_id, 0)
+                .unwrap();
+
+            let mut request = api::GenerateGiftCodeFundingTxRequest::new();
+            request.set_sender_monitor_id(sender_monitor_id.to_vec());
+            request.set_change_subaddress(0);
+            request.set_input_list(RepeatedField::from_vec(
+                utxos.iter().map(api::UnspentTxOut::from).collect(),
+            ));
+            request.set_value(value);
+            request.set_memo("Happy birthday!".to_owned());
+
+            let response = client.generate_gift_code_funding_tx(&request).unwrap();
+            let tx_proposal = TxProposal::try_from(response.get_tx_proposal()).unwrap();
+            assert_eq!(
+                tx_proposal.outlays,
+                vec![Outlay {
+                    value,
+                    receiver: sender.gift_code_subaddress(),
+                }]
+            );
+
+            // The change output carries the funding memo.
+            assert!(tx_proposal.tx.prefix.outputs.iter().any(|tx_out| {
+                let (_amount, shared_secret) =
+                    tx_out.view_key_match(sender.view_private_key()).unwrap();
+                matches!(
+                    MemoType::try_from(&tx_out.decrypt_memo(&shared_secret)),
+                    Ok(MemoType::GiftCodeFunding(memo)) if memo.funding_note().unwrap() == "Happy birthday!"
+                )
+            }));
+
+            land_tx(ledger_db, &tx_proposal, rng);
 
-        let response = client.generate_transfer_code_tx(&request).unwrap();
+            let mut request = api::CreateGiftCodeRequest::new();
+            request.set_monitor_id(sender_monitor_id.to_vec());
+            request.set_tx_public_key(response.get_tx_public_key().clone());
 
-        // Test that the generated transaction can be picked up by mobilecoind.
+            let response = client.create_gift_code(&request).unwrap();
+            assert_eq!(response.get_value(), value);
+            assert_eq!(response.get_token_id(), *Mob::ID);
+            response.get_b58_code().to_owned()
+        };
+
+        // A gift code that is claimed by the recipient.
         {
-            let tx_proposal = TxProposal::try_from(response.get_tx_proposal()).unwrap();
-            let key_images = tx_proposal.tx.key_images();
-            let outputs = tx_proposal.tx.prefix.outputs;
-            add_txos_and_key_images_to_ledger(
-                &mut ledger_db,
-                BLOCK_VERSION,
-                outputs,
-                key_images,
-                &mut rng,
-            )
-            .unwrap();
+            let value = 1_000_000_000_000;
+            let b58_code = fund_gift_code(&mut ledger_db, value, &mut rng);
 
-            // Use bip39 entropy to construct AccountKey.
-            let mnemonic =
-                Mnemonic::from_entropy(response.get_bip39_entropy(), Language::English).unwrap();
-            let key = mnemonic.derive_slip10_key(0);
-            let account_key = AccountKey::from(key);
+            let mut status_request = api::GetGiftCodeStatusRequest::new();
+            status_request.set_b58_code(b58_code.clone());
+            let response = client.get_gift_code_status(&status_request).unwrap();
+            assert_eq!(
+                response.get_status(),
+                api::GiftCodeStatus::GiftCodeUnclaimed
+            );
+            assert_eq!(response.get_value(), value);
+            assert_eq!(response.get_token_id(), *Mob::ID);
 
-            // Add a monitor based on the entropy we received.
-            let monitor_data = MonitorData::new(
-                account_key,
-                DEFAULT_SUBADDRESS_INDEX, // first_subaddress
-                1,                        // num_subaddresses
-                0,                        // first_block
-                "",                       // name
-            )
-            .unwrap();
+            // Only the sender can cancel it.
+            {
+                let mut request = api::GenerateGiftCodeCancellationTxRequest::new();
+                request.set_b58_code(b58_code.clone());
+                request.set_sender_monitor_id(recipient_monitor_id.to_vec());
+                assert!(client.generate_gift_code_cancellation_tx(&request).is_err());
+            }
 
-            let monitor_id = mobilecoind_db.add_monitor(&monitor_data).unwrap();
+            let mut request = api::GenerateGiftCodeClaimTxRequest::new();
+            request.set_b58_code(b58_code);
+            request.set_monitor_id(recipient_monitor_id.to_vec());
+            request.set_subaddress(1);
+            request.set_memo("Thanks!".to_owned());
 
-            // Wait for sync to complete.
-            wait_for_monitors(&mobilecoind_db, &ledger_db, &logger);
+            let response = client.generate_gift_code_claim_tx(&request).unwrap();
+            let tx_proposal = TxProposal::try_from(response.get_tx_proposal()).unwrap();
+            assert_eq!(
+                tx_proposal.outlays,
+                vec![Outlay {
+                    value: value - tx_proposal.fee(),
+                    receiver: recipient.subaddress(1),
+                }]
+            );
+            land_tx(&mut ledger_db, &tx_proposal, &mut rng);
 
-            // Get utxos for the new account and verify we only have one utxo.
+            // The recipient received the gift code, with a gift code sender memo.
             let utxos = mobilecoind_db
-                .get_utxos_for_subaddress(&monitor_id, DEFAULT_SUBADDRESS_INDEX)
+                .get_utxos_for_subaddress(&recipient_monitor_id, 1)
                 .unwrap();
             assert_eq!(utxos.len(), 1);
+            assert_eq!(utxos[0].value, value - tx_proposal.fee());
+            let (_amount, shared_secret) = utxos[0]
+                .tx_out
+                .view_key_match(recipient.view_private_key())
+                .unwrap();
+            assert_matches!(
+                MemoType::try_from(&utxos[0].tx_out.decrypt_memo(&shared_secret)),
+                Ok(MemoType::GiftCodeSender(memo)) if memo.sender_note().unwrap() == "Thanks!"
+            );
 
-            let utxo = &utxos[0];
+            let response = client.get_gift_code_status(&status_request).unwrap();
+            assert_eq!(response.get_status(), api::GiftCodeStatus::GiftCodeClaimed);
 
-            assert_eq!(utxo.value, 1337);
+            // It cannot be claimed twice.
+            assert!(client.generate_gift_code_claim_tx(&request).is_err());
+        }
+
+        // A gift code that is cancelled by the sender.
+        {
+            let value = 2_000_000_000_000;
+            let b58_code = fund_gift_code(&mut ledger_db, value, &mut rng);
+
+            let mut request = api::GenerateGiftCodeCancellationTxRequest::new();
+            request.set_b58_code(b58_code.clone());
+            request.set_sender_monitor_id(sender_monitor_id.to_vec());
+            request.set_change_subaddress(2);
+
+            let response = client.generate_gift_code_cancellation_tx(&request).unwrap();
+            let tx_proposal = TxProposal::try_from(response.get_tx_proposal()).unwrap();
             assert_eq!(
-                utxo.tx_out.public_key,
-                RistrettoPublic::try_from(response.get_tx_public_key())
-                    .unwrap()
-                    .into()
+                tx_proposal.outlays,
+                vec![Outlay {
+                    value: value - tx_proposal.fee(),
+                    receiver: sender.subaddress(2),
+                }]
+            );
+            land_tx(&mut ledger_db, &tx_proposal, &mut rng);
+
+            // The sender got the gift code back, with a gift code cancellation memo.
+            let gift_code_global_index = ledger_db
+                .get_tx_out_index_by_public_key(&tx_proposal.utxos[0].tx_out.public_key)
+                .unwrap();
+            let utxos = mobilecoind_db
+                .get_utxos_for_subaddress(&sender_monitor_id, 2)
+                .unwrap();
+            assert_eq!(utxos.len(), 1);
+            assert_eq!(utxos[0].value, value - tx_proposal.fee());
+            let (_amount, shared_secret) = utxos[0]
+                .tx_out
+                .view_key_match(sender.view_private_key())
+                .unwrap();
+            assert_matches!(
+                MemoType::try_from(&utxos[0].tx_out.decrypt_memo(&shared_secret)),
+                Ok(MemoType::GiftCodeCancellation(memo)) if memo.cancelled_gift_code_index() == gift_code_global_index
+            );
+
+            let mut status_request = api::GetGiftCodeStatusRequest::new();
+            status_request.set_b58_code(b58_code);
+            let response = client.get_gift_code_status(&status_request).unwrap();
+            assert_eq!(
+                response.get_status(),
+                api::GiftCodeStatus::GiftCodeCancelled
             );
         }
+
+        // Codes that are not gift codes are rejected.
+        {
+            let mut wrapper = api::printable::PrintableWrapper::new();
+            wrapper.set_public_address((&sender.default_subaddress()).into());
+
+            let mut request = api::GetGiftCodeStatusRequest::new();
+            request.set_b58_code(wrapper.b58_encode().unwrap());
+            assert!(client.get_gift_code_status(&request).is_err());
+        }
     }
 
     #[test_with_logger]
-    fn test_generate_optimization_tx(logger: Logger) {
-        let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
+    fn test_view_only_monitor(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([26u8; 32]);
 
         let sender = AccountKey::random(&mut rng);
-        let sender_default_subaddress = sender.default_subaddress();
-        let data = MonitorData::new(
-            sender, 0,  // first_subaddress
-            20, // num_subaddresses
-            0,  // first_block
-            "", // name
-        )
-        .unwrap();
 
-        // 1 known recipient, and a bunch of random recipients and no monitors.
-        // The random recipients are needed for mixins.
-        let num_random_recipients = MAX_INPUTS as u32 * RING_SIZE as u32
-            / test_utils::GET_TESTING_ENVIRONMENT_NUM_BLOCKS as u32;
+        // 1 known recipient, 3 random recipients and no monitors.
         let (mut ledger_db, mobilecoind_db, client, _server, _server_conn_manager) =
             get_testing_environment(
                 BLOCK_VERSION,
-                num_random_recipients as u32,
-                &[sender_default_subaddress.clone()],
+                3,
+                &[sender.default_subaddress()],
                 &[],
                 logger.clone(),
                 &mut rng,
             );
 
-        // Add a bunch of blocks/utxos for our recipient.
-        for _ in 0..MAX_INPUTS {
-            let _ = add_block_to_ledger(
-                &mut ledger_db,
-                BLOCK_VERSION,
-                &[sender_default_subaddress.clone()],
-                Amount::new(DEFAULT_PER_RECIPIENT_AMOUNT, Mob::ID),
-                &[KeyImage::from(rng.next_u64())],
-                &mut rng,
-            );
-        }
+        // Add a view-only monitor through the API.
+        let mut request = api::AddMonitorRequest::new();
+        request.set_view_account_key((&ViewAccountKey::from(&sender)).into());
+        request.set_first_subaddress(0);
+        request.set_num_subaddresses(20);
+        let monitor_id = client.add_monitor(&request).unwrap().monitor_id;
+        let monitor_id = MonitorId::try_from(&monitor_id).unwrap();
 
-        // Insert into database.
-        let monitor_id = mobilecoind_db.add_monitor(&data).unwrap();
+        // Setting both keys is rejected.
+        request.set_account_key((&sender).into());
+        assert!(client.add_monitor(&request).is_err());
+
+        // The monitor reports the view account key.
+        let mut request = api::GetMonitorStatusRequest::new();
+        request.set_monitor_id(monitor_id.to_vec());
+        let status = client.get_monitor_status(&request).unwrap();
+        assert!(!status.get_status().has_account_key());
+        assert_eq!(
+            ViewAccountKey::try_from(status.get_status().get_view_account_key()).unwrap(),
+            ViewAccountKey::from(&sender)
+        );
 
         // Allow the new monitor to process the ledger.
         wait_for_monitors(&mobilecoind_db, &ledger_db, &logger);
 
-        // Get list of unspent tx outs
+        // The monitor found our outputs, but cannot know their key images yet.
         let utxos = mobilecoind_db
             .get_utxos_for_subaddress(&monitor_id, 0)
             .unwrap();
         assert!(!utxos.is_empty());
+        assert!(utxos.iter().all(|utxo| !utxo.has_key_image()));
 
-        // Call generate optimization tx.
-        let mut request = api::GenerateOptimizationTxRequest::new();
-        request.set_monitor_id(monitor_id.to_vec());
-        request.set_subaddress(0);
+        // Generating a transaction returns an unsigned transaction.
+        let receiver = AccountKey::random(&mut rng);
+        let mut request = api::GenerateTxRequest::new();
+        request.set_sender_monitor_id(monitor_id.to_vec());
+        request.set_change_subaddress(0);
+        request.set_input_list(RepeatedField::from_vec(
+            utxos.iter().map(api::UnspentTxOut::from).collect(),
+        ));
+        request.set_outlay_list(RepeatedField::from_vec(vec![(&Outlay {
+            value: 123,
+            receiver: receiver.default_subaddress(),
+        })
+            .into()]));
 
-        let response = client.generate_optimization_tx(&request).unwrap();
+        let response = client.generate_tx(&request).unwrap();
+        assert!(!response.has_tx_proposal());
+        let unsigned_tx_proposal =
+            UnsignedTxProposal::try_from(response.get_unsigned_tx_proposal()).unwrap();
+        assert_eq!(unsigned_tx_proposal.fee(), Mob::MINIMUM_FEE);
+
+        // Sign it offline with the account key, and land it in the ledger.
+        let tx_proposal = unsigned_tx_proposal.sign(&sender, &mut rng).unwrap();
+        assert_eq!(tx_proposal.outlays, unsigned_tx_proposal.outlays);
+        add_txos_and_key_images_to_ledger(
+            &mut ledger_db,
+            BLOCK_VERSION,
+            tx_proposal.tx.prefix.outputs.clone(),
+            tx_proposal.tx.key_images(),
+            &mut rng,
+        )
+        .unwrap();
+        let spending_block = ledger_db.num_blocks().unwrap() - 1;
+        wait_for_monitors(&mobilecoind_db, &ledger_db, &logger);
 
-        // Sanity test the response.
-        let tx_proposal = TxProposal::try_from(response.get_tx_proposal()).unwrap();
+        // Without key images, the monitor did not notice the spend.
+        assert!(mobilecoind_db
+            .get_processed_block(&monitor_id, spending_block)
+            .unwrap()
+            .iter()
+            .all(|tx_out| tx_out.direction != ProcessedTxOutDirection::Spent as i32));
 
-        let expected_num_inputs: usize = MAX_INPUTS as usize;
-        assert_eq!(tx_proposal.utxos.len(), expected_num_inputs);
-        assert_eq!(tx_proposal.tx.prefix.inputs.len(), expected_num_inputs);
+        // Import the key images of all our outputs.
+        let key_image_list = mobilecoind_db
+            .get_utxos_for_subaddress(&monitor_id, 0)
+            .unwrap()
+            .iter()
+            .map(|utxo| {
+                let tx_public_key = RistrettoPublic::try_from(&utxo.tx_out.public_key).unwrap();
+                let onetime_private_key = recover_onetime_private_key(
+                    &tx_public_key,
+                    sender.view_private_key(),
+                    &sender.subaddress_spend_private(utxo.subaddress_index),
+                );
+                let mut key_image = api::TxOutKeyImage::new();
+                key_image.set_tx_public_key((&utxo.tx_out.public_key).into());
+                key_image.set_key_image((&KeyImage::from(&onetime_private_key)).into());
+                key_image
+            })
+            .collect::<Vec<_>>();
+        let num_outputs = key_image_list.len();
 
-        assert_eq!(tx_proposal.outlays.len(), 1);
-        assert_eq!(
-            tx_proposal.outlays[0].receiver,
-            data.account_key.subaddress(0)
-        );
+        let mut request = api::ImportKeyImagesRequest::new();
+        request.set_monitor_id(monitor_id.to_vec());
+        request.set_key_image_list(RepeatedField::from_vec(key_image_list));
+        let response = client.import_key_images(&request).unwrap();
+        assert_eq!(response.get_num_imported(), num_outputs as u64);
+
+        // The outputs spent by the transaction are gone, and the rest have key images.
+        let spent_key_images = tx_proposal.tx.key_images();
         assert_eq!(
-            tx_proposal.outlays[0].value,
-            // Each UTXO we have has PER_RECIPIENT_AMOUNT coins. We will be merging MAX_INPUTS of
-            // those into a single output, minus the fee.
-            (DEFAULT_PER_RECIPIENT_AMOUNT * MAX_INPUTS as u64) - Mob::MINIMUM_FEE,
+            response.get_spent_output_list().len(),
+            spent_key_images.len()
         );
-
-        assert_eq!(tx_proposal.outlay_index_to_tx_out_index.len(), 1);
-        assert_eq!(tx_proposal.outlay_index_to_tx_out_index[&0], 0);
-
-        assert_eq!(tx_proposal.tx.prefix.outputs.len(), 1);
-        let tx_out = &tx_proposal.tx.prefix.outputs[0];
-        let tx_public_key = RistrettoPublic::try_from(&tx_out.public_key).unwrap();
-        let shared_secret =
-            get_tx_out_shared_secret(data.account_key.view_private_key(), &tx_public_key);
-        let (amount, _blinding) = tx_out
-            .get_masked_amount()
-            .unwrap()
-            .get_value(&shared_secret)
+        let utxos = mobilecoind_db
+            .get_utxos_for_subaddress(&monitor_id, 0)
             .unwrap();
-        assert_eq!(amount.value, tx_proposal.outlays[0].value);
-        assert_eq!(amount.token_id, Mob::ID);
-
-        // Santity test fee
-        assert_eq!(tx_proposal.fee(), Mob::MINIMUM_FEE);
-        assert_eq!(tx_proposal.tx.prefix.fee, Mob::MINIMUM_FEE);
+        assert_eq!(utxos.len(), num_outputs - spent_key_images.len());
+        assert!(utxos
+            .iter()
+            .all(|utxo| utxo.has_key_image() && !spent_key_images.contains(&utxo.key_image)));
 
-        // Sanity test tombstone block
-        let num_blocks = ledger_db.num_blocks().unwrap();
-        assert_eq!(
-            tx_proposal.tx.prefix.tombstone_block,
-            num_blocks + DEFAULT_NEW_TX_BLOCK_ATTEMPTS
+        // The spend is recorded in the block the key images appeared in.
+        let mut spent_in_block = mobilecoind_db
+            .get_processed_block(&monitor_id, spending_block)
+            .unwrap()
+            .into_iter()
+            .filter(|tx_out| tx_out.direction == ProcessedTxOutDirection::Spent as i32)
+            .map(|tx_out| tx_out.key_image)
+            .collect::<Vec<_>>();
+        let mut expected_spent = spent_key_images.clone();
+        spent_in_block.sort();
+        expected_spent.sort();
+        assert_eq!(spent_in_block, expected_spent);
+
+        // Importing the same key images again is a no-op.
+        let response = client.import_key_images(&request).unwrap();
+        assert_eq!(response.get_num_imported(), 0);
+        assert!(response.get_spent_output_list().is_empty());
+
+        // A key image for a TxOut the monitor does not own is rejected.
+        let mut bogus_key_image = api::TxOutKeyImage::new();
+        bogus_key_image.set_tx_public_key(
+            (&CompressedRistrettoPublic::from(&RistrettoPublic::from_random(&mut rng))).into(),
         );
+        bogus_key_image.set_key_image((&KeyImage::from(rng.next_u64())).into());
+        let mut bogus_request = api::ImportKeyImagesRequest::new();
+        bogus_request.set_monitor_id(monitor_id.to_vec());
+        bogus_request.set_key_image_list(RepeatedField::from_vec(vec![bogus_key_image]));
+        match client.import_key_images(&bogus_request) {
+            Ok(_) => panic!("Should've returned an error"),
+            Err(GrpcError::RpcFailure(rpc_status)) => {
+                assert_eq!(rpc_status.code(), RpcStatusCode::INVALID_ARGUMENT);
+            }
+            Err(err) => panic!("Unexpected error: {:?}", err),
+        };
+
+        // Key images cannot be imported into monitors that have the account key.
+        let full_monitor_id = mobilecoind_db
+            .add_monitor(&MonitorData::new(sender, 0, 20, 0, "").unwrap())
+            .unwrap();
+        request.set_monitor_id(full_monitor_id.to_vec());
+        assert!(client.import_key_images(&request).is_err());
     }
 
     #[test_with_logger]
@@ -4787,6 +8383,145 @@ mod test {
         assert_eq!(matched_utxos, tx_proposal.utxos.len());
     }
 
+    #[test_with_logger]
+    fn test_get_sent_tx_list(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
+
+        let sender = AccountKey::random(&mut rng);
+        let data = MonitorData::new(
+            sender.clone(),
+            0,  // first_subaddress
+            20, // num_subaddresses
+            0,  // first_block
+            "", // name
+        )
+        .unwrap();
+
+        // 1 known recipient, 3 random recipients and no monitors.
+        let (mut ledger_db, mobilecoind_db, client, _server, _server_conn_manager) =
+            get_testing_environment(
+                BLOCK_VERSION,
+                3,
+                &[sender.default_subaddress()],
+                &[],
+                logger.clone(),
+                &mut rng,
+            );
+
+        // Insert into database.
+        let monitor_id = mobilecoind_db.add_monitor(&data).unwrap();
+
+        // Allow the new monitor to process the ledger.
+        wait_for_monitors(&mobilecoind_db, &ledger_db, &logger);
+
+        let get_sent_tx_list = |offset, limit| {
+            let mut request = api::GetSentTxListRequest::new();
+            request.set_monitor_id(monitor_id.to_vec());
+            request.set_offset(offset);
+            request.set_limit(limit);
+            client.get_sent_tx_list(&request).unwrap()
+        };
+
+        // Nothing was sent yet.
+        let response = get_sent_tx_list(0, 0);
+        assert_eq!(response.total, 0);
+        assert!(response.get_sent_tx_list().is_empty());
+
+        // Send two payments, the second one expiring after the next block.
+        let receiver = AccountKey::random(&mut rng);
+        let num_blocks = ledger_db.num_blocks().unwrap();
+        let tx_proposals = [0, num_blocks + 1]
+            .iter()
+            .map(|tombstone| {
+                let mut request = api::SendPaymentRequest::new();
+                request.set_sender_monitor_id(monitor_id.to_vec());
+                request.set_sender_subaddress(0);
+                request.set_outlay_list(RepeatedField::from_vec(vec![api::Outlay::from(
+                    &Outlay {
+                        value: 123,
+                        receiver: receiver.default_subaddress(),
+                    },
+                )]));
+                request.set_tombstone(*tombstone);
+
+                let response = client.send_payment(&request).unwrap();
+                TxProposal::try_from(response.get_tx_proposal()).unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        // Both are pending, most recent first.
+        let response = get_sent_tx_list(0, 0);
+        assert_eq!(response.total, 2);
+        assert_eq!(response.get_sent_tx_list().len(), 2);
+        for (sent_tx, tx_proposal) in response
+            .get_sent_tx_list()
+            .iter()
+            .zip(tx_proposals.iter().rev())
+        {
+            assert_eq!(sent_tx.get_status(), api::SentTxStatus::SentTxPending);
+            assert_eq!(sent_tx.fee, tx_proposal.fee());
+            assert_eq!(sent_tx.tombstone, tx_proposal.tx.prefix.tombstone_block);
+            assert_eq!(
+                sent_tx
+                    .get_key_image_list()
+                    .iter()
+                    .map(|key_image| KeyImage::try_from(key_image).unwrap())
+                    .collect::<Vec<_>>(),
+                tx_proposal
+                    .utxos
+                    .iter()
+                    .map(|utxo| utxo.key_image)
+                    .collect::<Vec<_>>(),
+            );
+
+            assert_eq!(sent_tx.get_outlay_list().len(), 1);
+            let outlay = &sent_tx.get_outlay_list()[0];
+            assert_eq!(
+                PublicAddress::try_from(outlay.get_receiver()).unwrap(),
+                receiver.default_subaddress()
+            );
+            assert_eq!(outlay.value, 123);
+        }
+        assert_eq!(response.get_sent_tx_list()[0].index, 1);
+        assert_eq!(response.get_sent_tx_list()[1].index, 0);
+
+        // Pagination.
+        let response = get_sent_tx_list(1, 1);
+        assert_eq!(response.total, 2);
+        assert_eq!(response.get_sent_tx_list().len(), 1);
+        assert_eq!(response.get_sent_tx_list()[0].index, 0);
+
+        // Land the first payment. The second one expires.
+        add_txos_and_key_images_to_ledger(
+            &mut ledger_db,
+            BLOCK_VERSION,
+            tx_proposals[0].tx.prefix.outputs.clone(),
+            tx_proposals[0]
+                .utxos
+                .iter()
+                .map(|utxo| utxo.key_image)
+                .collect(),
+            &mut rng,
+        )
+        .unwrap();
+
+        let response = get_sent_tx_list(0, 0);
+        assert_eq!(
+            response.get_sent_tx_list()[0].get_status(),
+            api::SentTxStatus::SentTxExpired
+        );
+        assert_eq!(
+            response.get_sent_tx_list()[1].get_status(),
+            api::SentTxStatus::SentTxLanded
+        );
+        assert_eq!(response.get_sent_tx_list()[1].landed_block, num_blocks);
+
+        // An unknown monitor should fail.
+        let mut request = api::GetSentTxListRequest::new();
+        request.set_monitor_id(vec![3; 32]);
+        assert!(client.get_sent_tx_list(&request).is_err());
+    }
+
     #[test_with_logger]
     fn test_send_payment_with_max_input_utxo_value(logger: Logger) {
         let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
@@ -5777,7 +9512,9 @@ mod test {
             );
 
         let mut request = api::AddMonitorRequest::new();
-        request.set_account_key(mc_api::external::AccountKey::from(&data.account_key));
+        request.set_account_key(mc_api::external::AccountKey::from(
+            data.account_key.as_ref().unwrap(),
+        ));
         request.set_first_subaddress(data.first_subaddress);
         request.set_num_subaddresses(data.num_subaddresses);
         request.set_first_block(data.first_block);
@@ -5866,7 +9603,9 @@ mod test {
 
         // Re-add the monitor.
         let mut request = api::AddMonitorRequest::new();
-        request.set_account_key(mc_api::external::AccountKey::from(&data.account_key));
+        request.set_account_key(mc_api::external::AccountKey::from(
+            data.account_key.as_ref().unwrap(),
+        ));
         request.set_first_subaddress(data.first_subaddress);
         request.set_num_subaddresses(data.num_subaddresses);
         request.set_first_block(data.first_block);
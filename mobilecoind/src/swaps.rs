@@ -0,0 +1,429 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! Utilities for running a book of atomic swap offers.
+//!
+//! A swap offer is a signed contingent input (see MCIP #31 and #42) which
+//! spends some amount of a "base" token, contingent on the transaction paying
+//! the originator some amount of a "counter" token. The helpers here extract
+//! the terms of such offers, validate them against the local ledger, and pick
+//! the cheapest combination of offers to fill a desired amount.
+
+use crate::error::Error;
+use mc_ledger_db::{Error as LedgerError, Ledger};
+use mc_transaction_core::TokenId;
+use mc_transaction_extra::{SignedContingentInput, SignedContingentInputError};
+use std::cmp::Ordering;
+
+/// The terms of a swap offered by a signed contingent input.
+///
+/// When the offer is filled completely, the originator gives up `base_value`
+/// of `base_token_id` and receives `counter_value` of `counter_token_id`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SwapQuote {
+    /// The token id the originator is offering.
+    pub base_token_id: TokenId,
+
+    /// The value of the base token available to the counterparty.
+    pub base_value: u64,
+
+    /// The token id the originator is asking for.
+    pub counter_token_id: TokenId,
+
+    /// The value of the counter token asked for, when filled completely.
+    pub counter_value: u64,
+
+    /// Whether the offer may be filled partially.
+    pub partial_fill: bool,
+
+    /// The minimum base value a partial fill must take.
+    pub min_fill_value: u64,
+
+    /// The values of each of the partial fill outputs, which determine the
+    /// exact cost of a partial fill.
+    partial_fill_output_values: Vec<u64>,
+}
+
+impl SwapQuote {
+    /// Extract the terms of a swap offer from a signed contingent input.
+    ///
+    /// Only offers that trade a single base token for a single counter token
+    /// are supported. Required outputs in the base token are treated as
+    /// change to the originator. A partial fill offer must make the whole of
+    /// its remaining base value fillable, and must not ask for a fixed amount
+    /// of the counter token in addition to its partial fill outputs.
+    pub fn from_sci(sci: &SignedContingentInput) -> Result<Self, Error> {
+        let rules = sci
+            .tx_in
+            .input_rules
+            .as_ref()
+            .ok_or(SignedContingentInputError::MissingRules)?;
+
+        if sci.required_output_amounts.len() != rules.required_outputs.len() {
+            return Err(SignedContingentInputError::WrongNumberOfRequiredOutputAmounts.into());
+        }
+
+        let base_token_id = TokenId::from(sci.pseudo_output_amount.token_id);
+        let mut counter_token_id: Option<TokenId> = None;
+        let mut set_counter_token_id = |token_id: TokenId| -> Result<(), Error> {
+            match counter_token_id {
+                Some(prev) if prev != token_id => Err(Error::UnsupportedSwap(
+                    "offer asks for more than one counter token".to_owned(),
+                )),
+                _ => {
+                    counter_token_id = Some(token_id);
+                    Ok(())
+                }
+            }
+        };
+
+        let mut required_base_value = 0u64;
+        let mut required_counter_value = 0u64;
+        for amount in sci.required_output_amounts.iter() {
+            let token_id = TokenId::from(amount.token_id);
+            let total = if token_id == base_token_id {
+                &mut required_base_value
+            } else {
+                set_counter_token_id(token_id)?;
+                &mut required_counter_value
+            };
+            *total = total
+                .checked_add(amount.value)
+                .ok_or_else(|| Error::UnsupportedSwap("required outputs overflow".to_owned()))?;
+        }
+
+        let available_base_value = sci
+            .pseudo_output_amount
+            .value
+            .checked_sub(required_base_value)
+            .ok_or_else(|| {
+                Error::UnsupportedSwap("required change exceeds the input value".to_owned())
+            })?;
+
+        let (base_value, counter_value, partial_fill_output_values) =
+            if let Some(partial_fill_change) = rules.partial_fill_change.as_ref() {
+                let (change_amount, _) = partial_fill_change
+                    .reveal_amount()
+                    .map_err(SignedContingentInputError::from)?;
+                if change_amount.token_id != base_token_id {
+                    return Err(SignedContingentInputError::TokenIdMismatch.into());
+                }
+                if change_amount.value != available_base_value {
+                    return Err(Error::UnsupportedSwap(
+                        "partial fill change does not match the available input value".to_owned(),
+                    ));
+                }
+                if required_counter_value != 0 {
+                    return Err(Error::UnsupportedSwap(
+                        "partial fill offer asks for a fixed counter amount".to_owned(),
+                    ));
+                }
+
+                let mut partial_fill_output_values = Vec::new();
+                for partial_fill_output in rules.partial_fill_outputs.iter() {
+                    let (amount, _) = partial_fill_output
+                        .reveal_amount()
+                        .map_err(SignedContingentInputError::from)?;
+                    if amount.token_id == base_token_id {
+                        return Err(Error::UnsupportedSwap(
+                            "partial fill output is in the base token".to_owned(),
+                        ));
+                    }
+                    set_counter_token_id(amount.token_id)?;
+                    partial_fill_output_values.push(amount.value);
+                }
+                let counter_value = partial_fill_output_values
+                    .iter()
+                    .try_fold(0u64, |acc, value| acc.checked_add(*value))
+                    .ok_or_else(|| {
+                        Error::UnsupportedSwap("partial fill outputs overflow".to_owned())
+                    })?;
+
+                (
+                    change_amount.value,
+                    counter_value,
+                    partial_fill_output_values,
+                )
+            } else {
+                (available_base_value, required_counter_value, Vec::new())
+            };
+
+        let counter_token_id = counter_token_id.ok_or_else(|| {
+            Error::UnsupportedSwap("offer does not ask for a counter token".to_owned())
+        })?;
+        if base_value == 0 || counter_value == 0 {
+            return Err(Error::UnsupportedSwap(
+                "offer has nothing to trade".to_owned(),
+            ));
+        }
+
+        Ok(Self {
+            base_token_id,
+            base_value,
+            counter_token_id,
+            counter_value,
+            partial_fill: rules.partial_fill_change.is_some(),
+            min_fill_value: rules.min_partial_fill_value,
+            partial_fill_output_values,
+        })
+    }
+
+    /// The value of the counter token needed to take `base_fill_value` of
+    /// the base token from this offer.
+    ///
+    /// For partial fill offers this matches the amounts computed by
+    /// `TransactionBuilder::add_presigned_partial_fill_input`, i.e. each
+    /// partial fill output is scaled by the fill fraction, rounding up.
+    pub fn counter_value_for_fill(&self, base_fill_value: u64) -> Result<u64, Error> {
+        if !self.partial_fill {
+            if base_fill_value != self.base_value {
+                return Err(Error::InvalidArgument(
+                    "base_fill_value".to_owned(),
+                    "offer can only be filled completely".to_owned(),
+                ));
+            }
+            return Ok(self.counter_value);
+        }
+
+        if base_fill_value < self.min_fill_value || base_fill_value > self.base_value {
+            return Err(Error::InvalidArgument(
+                "base_fill_value".to_owned(),
+                format!(
+                    "fill must be between {} and {}",
+                    self.min_fill_value, self.base_value
+                ),
+            ));
+        }
+
+        let num = base_fill_value as u128;
+        let denom = self.base_value as u128;
+        Ok(self
+            .partial_fill_output_values
+            .iter()
+            .map(|value| ((*value as u128 * num + (denom - 1)) / denom) as u64)
+            .sum())
+    }
+
+    /// Compare the price (counter value per base value) of two offers.
+    pub fn cmp_price(&self, other: &Self) -> Ordering {
+        let lhs = self.counter_value as u128 * other.base_value as u128;
+        let rhs = other.counter_value as u128 * self.base_value as u128;
+        lhs.cmp(&rhs)
+    }
+}
+
+/// How much of a single offer to take.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SwapFill {
+    /// Index of the offer in the list of quotes.
+    pub index: usize,
+
+    /// The value of the base token taken from the offer.
+    pub base_value: u64,
+
+    /// The value of the counter token paid to the originator.
+    pub counter_value: u64,
+}
+
+/// Validate a signed contingent input against the local ledger, and return
+/// the terms of the swap it offers.
+///
+/// In addition to the checks performed by `SignedContingentInput::validate`,
+/// this checks that:
+/// * The key image has not been spent yet.
+/// * The max tombstone block imposed by the input rules has not passed.
+/// * Each ring member is present in the ledger at the claimed global index, so
+///   that current proofs of membership can be produced for it.
+pub fn validate_sci(sci: &SignedContingentInput, ledger: &impl Ledger) -> Result<SwapQuote, Error> {
+    sci.validate()?;
+
+    if ledger.contains_key_image(&sci.key_image())? {
+        return Err(Error::KeyImageAlreadySpent);
+    }
+
+    if let Some(rules) = sci.tx_in.input_rules.as_ref() {
+        if rules.max_tombstone_block != 0 && ledger.num_blocks()? >= rules.max_tombstone_block {
+            return Err(Error::StaleSignedContingentInput(format!(
+                "max tombstone block {} has passed",
+                rules.max_tombstone_block
+            )));
+        }
+    }
+
+    for (tx_out, index) in sci.tx_in.ring.iter().zip(sci.tx_out_global_indices.iter()) {
+        match ledger.get_tx_out_by_index(*index) {
+            Ok(ledger_tx_out) if ledger_tx_out == *tx_out => {}
+            Ok(_) | Err(LedgerError::NotFound) => {
+                return Err(Error::StaleSignedContingentInput(format!(
+                    "ring member at index {} does not match the ledger",
+                    index
+                )));
+            }
+            Err(err) => return Err(err.into()),
+        }
+    }
+
+    SwapQuote::from_sci(sci)
+}
+
+/// Replace the proofs of membership of a signed contingent input's ring with
+/// proofs against the current state of the ledger. This must be done before
+/// the input is added to a transaction.
+pub fn add_membership_proofs(
+    sci: &mut SignedContingentInput,
+    ledger: &impl Ledger,
+) -> Result<(), Error> {
+    sci.tx_in.proofs = ledger.get_tx_out_proof_of_memberships(&sci.tx_out_global_indices)?;
+    Ok(())
+}
+
+/// Compute the cheapest way to take `base_value` of `base_token_id` from a
+/// set of offers, paying in `counter_token_id`.
+///
+/// Offers are taken greedily, best price first. Offers that can't be
+/// partially filled are only taken if they fit entirely in what remains to be
+/// filled, and partial fills respect each offer's minimum fill value. Offers
+/// for other token pairs are ignored.
+///
+/// The result may fill less than `base_value` if the offers are insufficient.
+pub fn compute_best_fills(
+    quotes: &[SwapQuote],
+    base_token_id: TokenId,
+    counter_token_id: TokenId,
+    base_value: u64,
+) -> Vec<SwapFill> {
+    let mut candidates: Vec<(usize, &SwapQuote)> = quotes
+        .iter()
+        .enumerate()
+        .filter(|(_, quote)| {
+            quote.base_token_id == base_token_id && quote.counter_token_id == counter_token_id
+        })
+        .collect();
+    // Stable sort, so that offers with the same price are taken in order.
+    candidates.sort_by(|(_, a), (_, b)| a.cmp_price(b));
+
+    let mut fills = Vec::new();
+    let mut remaining = base_value;
+    for (index, quote) in candidates {
+        if remaining == 0 {
+            break;
+        }
+
+        let fill_value = if quote.partial_fill {
+            remaining.min(quote.base_value)
+        } else {
+            quote.base_value
+        };
+
+        // Skip offers that can't take this fill, i.e. whole offers that are too
+        // big, or partial fills below the minimum fill value.
+        if fill_value > remaining {
+            continue;
+        }
+        let counter_value = match quote.counter_value_for_fill(fill_value) {
+            Ok(counter_value) => counter_value,
+            Err(_) => continue,
+        };
+
+        remaining -= fill_value;
+        fills.push(SwapFill {
+            index,
+            base_value: fill_value,
+            counter_value,
+        });
+    }
+
+    fills
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn quote(
+        base_value: u64,
+        counter_value: u64,
+        partial_fill: bool,
+        min_fill_value: u64,
+    ) -> SwapQuote {
+        SwapQuote {
+            base_token_id: TokenId::from(0),
+            base_value,
+            counter_token_id: TokenId::from(2),
+            counter_value,
+            partial_fill,
+            min_fill_value,
+            partial_fill_output_values: if partial_fill {
+                vec![counter_value]
+            } else {
+                vec![]
+            },
+        }
+    }
+
+    #[test]
+    fn test_counter_value_for_fill() {
+        let partial = quote(1000, 333, true, 100);
+        assert_eq!(partial.counter_value_for_fill(1000).unwrap(), 333);
+        // 333 * 500 / 1000 = 166.5, rounded up
+        assert_eq!(partial.counter_value_for_fill(500).unwrap(), 167);
+        assert!(partial.counter_value_for_fill(99).is_err());
+        assert!(partial.counter_value_for_fill(1001).is_err());
+
+        let whole = quote(1000, 333, false, 0);
+        assert_eq!(whole.counter_value_for_fill(1000).unwrap(), 333);
+        assert!(whole.counter_value_for_fill(500).is_err());
+    }
+
+    #[test]
+    fn test_compute_best_fills() {
+        let quotes = vec![
+            // Price 3
+            quote(100, 300, false, 0),
+            // Price 1, but can only be taken whole
+            quote(500, 500, false, 0),
+            // Price 2
+            quote(1000, 2000, true, 50),
+            // Price 1, but for another token pair
+            SwapQuote {
+                counter_token_id: TokenId::from(3),
+                ..quote(1000, 1000, true, 0)
+            },
+            // Price 1.5
+            quote(100, 150, true, 0),
+        ];
+
+        let fills = compute_best_fills(&quotes, TokenId::from(0), TokenId::from(2), 700);
+        assert_eq!(
+            fills,
+            vec![
+                SwapFill {
+                    index: 1,
+                    base_value: 500,
+                    counter_value: 500,
+                },
+                SwapFill {
+                    index: 4,
+                    base_value: 100,
+                    counter_value: 150,
+                },
+                SwapFill {
+                    index: 2,
+                    base_value: 100,
+                    counter_value: 200,
+                },
+            ]
+        );
+
+        // The whole offers no longer fit, and the 30 remaining after the offer at
+        // price 1.5 is below the minimum fill of the offer at price 2.
+        let fills = compute_best_fills(&quotes, TokenId::from(0), TokenId::from(2), 130);
+        assert_eq!(
+            fills,
+            vec![SwapFill {
+                index: 4,
+                base_value: 100,
+                counter_value: 150,
+            }]
+        );
+    }
+}
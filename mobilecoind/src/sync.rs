@@ -19,17 +19,30 @@
 //! available blocks gets processed at once. When that happens, instead of
 //! removing the monitor id from the hashset, it would be placed back into the
 //! queue to be picked up by the next available worker thread.
+//! When syncing a monitor fails, the worker thread removes it from the hashset
+//! and the main thread waits before queueing it again, doubling the wait on
+//! every consecutive failure.
+//! Monitors with a gap limit have their subaddress range grown by the worker
+//! thread when a TxOut is received close to its end. The blocks the monitor
+//! already processed are then rescanned for TxOuts sent to the new
+//! subaddresses. The highest subaddress a TxOut was received on is saved
+//! along with each block, and the range is checked against it before every
+//! block, so growing it is retried if it fails.
+//! Fog monitors do not scan blocks. Instead, the worker thread polls fog for
+//! them, see the `fog_sync` module. Fog may lag behind the local ledger, so
+//! a fog monitor is polled at most once every `FOG_POLL_INTERVAL`.
 
 use crate::{
     database::Database,
     error::Error,
+    fog_sync::{sync_fog_monitor, FogClients, FogSyncConfig},
     monitor_store::{MonitorData, MonitorId},
     subaddress_store::SubaddressSPKId,
     utxo_store::UnspentTxOut,
 };
 use mc_common::{
     logger::{log, Logger},
-    HashSet,
+    HashMap, HashSet,
 };
 use mc_crypto_keys::RistrettoPublic;
 use mc_ledger_db::{Ledger, LedgerDB};
@@ -40,16 +53,29 @@ use mc_transaction_core::{
     tx::TxOut,
 };
 use std::{
+    ops::Range,
     sync::{
         atomic::{AtomicBool, Ordering},
         Arc, Mutex,
     },
     thread,
+    time::{Duration, Instant},
 };
 
 ///  The maximal number of blocks a worker thread would process at once.
 const MAX_BLOCKS_PROCESSING_CHUNK_SIZE: usize = 5;
 
+/// The minimal time between two polls of fog for the same fog monitor.
+const FOG_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The time to wait before syncing a monitor again after its sync failed.
+/// Doubled on every consecutive failure, up to `MAX_SYNC_ERROR_BACKOFF`.
+const SYNC_ERROR_BACKOFF: Duration = Duration::from_secs(1);
+
+/// The maximal time to wait before syncing a monitor again after its sync
+/// failed.
+const MAX_SYNC_ERROR_BACKOFF: Duration = Duration::from_secs(60);
+
 /// Message type the our crossbeam channel used to communicate with the worker
 /// thread pull.
 enum SyncMsg {
@@ -65,6 +91,42 @@ enum SyncMonitorOk {
 
     // More blocks might be available.
     MoreBlocksPotentiallyAvailable,
+
+    // Fog was polled, and should not be polled again before
+    // `FOG_POLL_INTERVAL`.
+    FogPolled,
+}
+
+/// Tracks monitors that should not be queued again before a given time.
+#[derive(Clone, Copy, Debug)]
+struct SyncDelay {
+    /// The monitor should not be queued before this time.
+    until: Instant,
+
+    /// The number of consecutive failed syncs of the monitor.
+    num_failures: u32,
+}
+
+impl SyncDelay {
+    /// The delay after a successful poll of fog.
+    fn fog_polled() -> Self {
+        Self {
+            until: Instant::now() + FOG_POLL_INTERVAL,
+            num_failures: 0,
+        }
+    }
+
+    /// The delay after a failed sync, given the delay from the previous sync
+    /// of the monitor, if any.
+    fn failed(previous: Option<&SyncDelay>) -> Self {
+        let num_failures = previous.map_or(0, |delay| delay.num_failures) + 1;
+        let backoff =
+            (SYNC_ERROR_BACKOFF * (1 << (num_failures - 1).min(16))).min(MAX_SYNC_ERROR_BACKOFF);
+        Self {
+            until: Instant::now() + backoff,
+            num_failures,
+        }
+    }
 }
 
 /// Sync thread - holds objects needed to cleanly terminate the sync thread.
@@ -81,6 +143,7 @@ impl SyncThread {
         ledger_db: LedgerDB,
         mobilecoind_db: Database,
         num_workers: Option<usize>,
+        fog_sync_config: Option<FogSyncConfig>,
         logger: Logger,
     ) -> Self {
         // Queue for sending jobs to our worker threads.
@@ -90,6 +153,11 @@ impl SyncThread {
         // preventing them from being sent again until they are processed.
         let queued_monitor_ids = Arc::new(Mutex::new(HashSet::<MonitorId>::default()));
 
+        // Monitors that should not be queued again until some time passes, because
+        // their last sync failed or because they are fog monitors that were just
+        // polled.
+        let delayed_monitor_ids = Arc::new(Mutex::new(HashMap::<MonitorId, SyncDelay>::default()));
+
         // Create worker threads.
         let mut worker_join_handles = Vec::new();
 
@@ -99,6 +167,8 @@ impl SyncThread {
             let thread_sender = sender.clone();
             let thread_receiver = receiver.clone();
             let thread_queued_monitor_ids = queued_monitor_ids.clone();
+            let thread_delayed_monitor_ids = delayed_monitor_ids.clone();
+            let thread_fog_sync_config = fog_sync_config.clone();
             let thread_logger = logger.clone();
             let join_handle = thread::Builder::new()
                 .name(format!("sync_worker_{}", idx))
@@ -109,6 +179,8 @@ impl SyncThread {
                         thread_sender,
                         thread_receiver,
                         thread_queued_monitor_ids,
+                        thread_delayed_monitor_ids,
+                        thread_fog_sync_config,
                         thread_logger,
                     );
                 })
@@ -149,15 +221,44 @@ impl SyncThread {
 
                         // Go over our list of monitors and see which one needs to process these
                         // blocks.
-                        for (monitor_id, monitor_data) in mobilecoind_db
+                        let monitor_map = mobilecoind_db
                             .get_monitor_map()
-                            .expect("failed getting monitor map")
-                        {
+                            .expect("failed getting monitor map");
+
+                        // Forget the delays of monitors that were removed.
+                        delayed_monitor_ids
+                            .lock()
+                            .expect("mutex poisoned")
+                            .retain(|monitor_id, _| monitor_map.contains_key(monitor_id));
+
+                        for (monitor_id, monitor_data) in monitor_map {
                             // If there are no new blocks for this monitor, don't do anything.
                             if monitor_data.next_block >= num_blocks {
                                 continue;
                             }
 
+                            // Fog monitors cannot sync without fog.
+                            if monitor_data.is_fog() && fog_sync_config.is_none() {
+                                log::trace!(
+                                    logger,
+                                    "{}: skipping, fog is not configured",
+                                    monitor_id
+                                );
+                                continue;
+                            }
+
+                            // Wait for the monitor's delay to pass, if it has one.
+                            if let Some(delay) = delayed_monitor_ids
+                                .lock()
+                                .expect("mutex poisoned")
+                                .get(&monitor_id)
+                            {
+                                if delay.until > Instant::now() {
+                                    log::trace!(logger, "{}: skipping, delayed", monitor_id);
+                                    continue;
+                                }
+                            }
+
                             let mut queued_monitor_ids =
                                 queued_monitor_ids.lock().expect("mutex poisoned");
                             if !queued_monitor_ids.insert(monitor_id) {
@@ -237,18 +338,49 @@ fn sync_thread_entry_point(
     sender: crossbeam_channel::Sender<SyncMsg>,
     receiver: crossbeam_channel::Receiver<SyncMsg>,
     queued_monitor_ids: Arc<Mutex<HashSet<MonitorId>>>,
+    delayed_monitor_ids: Arc<Mutex<HashMap<MonitorId, SyncDelay>>>,
+    fog_sync_config: Option<FogSyncConfig>,
     logger: Logger,
 ) {
+    let mut fog_clients = fog_sync_config
+        .as_ref()
+        .map(|config| FogClients::new(config, &logger));
+
     for msg in receiver.iter() {
         match msg {
             SyncMsg::SyncMonitor(monitor_id) => {
-                match sync_monitor(&ledger_db, &mobilecoind_db, &monitor_id, &logger) {
+                match sync_monitor(
+                    &ledger_db,
+                    &mobilecoind_db,
+                    &monitor_id,
+                    fog_clients.as_mut(),
+                    &logger,
+                ) {
                     // Success - No more blocks are currently available.
                     Ok(SyncMonitorOk::NoMoreBlocks) => {
                         // Remove the monitor id from the list of queued ones so that the main
                         // thread could queue it again if necessary.
                         log::trace!(logger, "{}: sync_monitor returned NoMoreBlocks", monitor_id);
 
+                        delayed_monitor_ids
+                            .lock()
+                            .expect("mutex poisoned")
+                            .remove(&monitor_id);
+
+                        let mut queued_monitor_ids =
+                            queued_monitor_ids.lock().expect("mutex poisoned");
+                        queued_monitor_ids.remove(&monitor_id);
+                    }
+
+                    // Success - fog was polled, wait before polling it again.
+                    Ok(SyncMonitorOk::FogPolled) => {
+                        log::trace!(logger, "{}: sync_monitor returned FogPolled", monitor_id);
+
+                        delayed_monitor_ids
+                            .lock()
+                            .expect("mutex poisoned")
+                            .insert(monitor_id, SyncDelay::fog_polled());
+
                         let mut queued_monitor_ids =
                             queued_monitor_ids.lock().expect("mutex poisoned");
                         queued_monitor_ids.remove(&monitor_id);
@@ -268,12 +400,36 @@ fn sync_thread_entry_point(
                             .expect("failed sending to channel");
                     }
 
-                    // Errors that are acceptable - nothing to do.
-                    Err(Error::MonitorIdNotFound) => {}
+                    // Errors that are acceptable - the monitor was removed.
+                    Err(Error::MonitorIdNotFound) => {
+                        delayed_monitor_ids
+                            .lock()
+                            .expect("mutex poisoned")
+                            .remove(&monitor_id);
 
-                    // Other errors - log.
+                        let mut queued_monitor_ids =
+                            queued_monitor_ids.lock().expect("mutex poisoned");
+                        queued_monitor_ids.remove(&monitor_id);
+                    }
+
+                    // Other errors - log, and retry after a delay.
                     Err(err) => {
-                        log::error!(logger, "error syncing monitor {}: {:?}", monitor_id, err);
+                        let mut delayed_monitor_ids =
+                            delayed_monitor_ids.lock().expect("mutex poisoned");
+                        let delay = SyncDelay::failed(delayed_monitor_ids.get(&monitor_id));
+                        delayed_monitor_ids.insert(monitor_id, delay);
+
+                        log::error!(
+                            logger,
+                            "error syncing monitor {} ({} consecutive failures): {:?}",
+                            monitor_id,
+                            delay.num_failures,
+                            err
+                        );
+
+                        let mut queued_monitor_ids =
+                            queued_monitor_ids.lock().expect("mutex poisoned");
+                        queued_monitor_ids.remove(&monitor_id);
                     }
                 };
             }
@@ -290,12 +446,28 @@ fn sync_monitor(
     ledger_db: &LedgerDB,
     mobilecoind_db: &Database,
     monitor_id: &MonitorId,
+    fog_clients: Option<&mut FogClients>,
     logger: &Logger,
 ) -> Result<SyncMonitorOk, Error> {
+    // Fog monitors process all the blocks fog has at once.
+    if mobilecoind_db.get_monitor_data(monitor_id)?.is_fog() {
+        let fog_clients = fog_clients.ok_or(Error::FogNotConfigured)?;
+        sync_fog_monitor(ledger_db, mobilecoind_db, monitor_id, fog_clients, logger)?;
+        return Ok(SyncMonitorOk::FogPolled);
+    }
+
     for _ in 0..MAX_BLOCKS_PROCESSING_CHUNK_SIZE {
         // Get the monitor data. If it is no longer available, the monitor has been
         // removed and we can simply return.
-        let monitor_data = mobilecoind_db.get_monitor_data(monitor_id)?;
+        let mut monitor_data = mobilecoind_db.get_monitor_data(monitor_id)?;
+
+        // Grow the subaddress range if a TxOut was received close to its end, before
+        // processing more blocks. If this fails, it is retried on the next pass.
+        if monitor_data.gap_limit_pending_num_subaddresses().is_some() {
+            apply_gap_limit(ledger_db, mobilecoind_db, monitor_id, logger)?;
+            monitor_data = mobilecoind_db.get_monitor_data(monitor_id)?;
+        }
+
         let block_contents = match ledger_db.get_block_contents(monitor_data.next_block) {
             Ok(block_contents) => block_contents,
             Err(mc_ledger_db::Error::NotFound) => {
@@ -337,14 +509,14 @@ fn sync_monitor(
 }
 
 /// Helper function for matching a list of TxOuts to a given monitor.
-fn match_tx_outs_into_utxos(
+pub fn match_tx_outs_into_utxos(
     mobilecoind_db: &Database,
     outputs: &[TxOut],
     monitor_id: &MonitorId,
     monitor_data: &MonitorData,
     logger: &Logger,
 ) -> Result<Vec<UnspentTxOut>, Error> {
-    let account_key = &monitor_data.account_key;
+    let view_private_key = monitor_data.view_private_key()?;
     let mut results = Vec::new();
 
     for tx_out in outputs {
@@ -353,7 +525,7 @@ fn match_tx_outs_into_utxos(
         let tx_public_key = RistrettoPublic::try_from(&tx_out.public_key)?;
 
         let subaddress_spk = SubaddressSPKId::from(&recover_public_subaddress_spend_key(
-            account_key.view_private_key(),
+            view_private_key,
             &tx_out_target_key,
             &tx_public_key,
         ));
@@ -379,35 +551,158 @@ fn match_tx_outs_into_utxos(
         // Sanity - we should only get a match for our own monitor id.
         assert_eq!(monitor_id, &subaddress_id.monitor_id);
 
-        let shared_secret =
-            get_tx_out_shared_secret(account_key.view_private_key(), &tx_public_key);
+        results.push(tx_out_to_utxo(
+            tx_out,
+            &tx_public_key,
+            subaddress_id.index,
+            monitor_data,
+        )?);
+    }
 
-        let (amount, _blinding) = tx_out
-            .get_masked_amount()
-            .expect("missing masked amount")
-            .get_value(&shared_secret)
-            .expect("Malformed amount"); // TODO
+    Ok(results)
+}
 
-        let onetime_private_key = recover_onetime_private_key(
-            &tx_public_key,
-            account_key.view_private_key(),
-            &account_key.subaddress_spend_private(subaddress_id.index),
+/// Helper function for constructing the UnspentTxOut of a TxOut received on
+/// a given subaddress of a monitor.
+fn tx_out_to_utxo(
+    tx_out: &TxOut,
+    tx_public_key: &RistrettoPublic,
+    subaddress_index: u64,
+    monitor_data: &MonitorData,
+) -> Result<UnspentTxOut, Error> {
+    let view_private_key = monitor_data.view_private_key()?;
+    let shared_secret = get_tx_out_shared_secret(view_private_key, tx_public_key);
+
+    let (amount, _blinding) = tx_out
+        .get_masked_amount()
+        .expect("missing masked amount")
+        .get_value(&shared_secret)
+        .expect("Malformed amount"); // TODO
+
+    // View-only monitors cannot compute key images, so a placeholder is stored
+    // until the real key image is imported.
+    let key_image = match &monitor_data.account_key {
+        Some(account_key) => {
+            let onetime_private_key = recover_onetime_private_key(
+                tx_public_key,
+                view_private_key,
+                &account_key.subaddress_spend_private(subaddress_index),
+            );
+            KeyImage::from(&onetime_private_key)
+        }
+        None => UnspentTxOut::placeholder_key_image(&tx_out.public_key),
+    };
+
+    Ok(UnspentTxOut {
+        tx_out: tx_out.clone(),
+        subaddress_index,
+        key_image,
+        value: amount.value,
+        attempted_spend_height: 0,
+        attempted_spend_tombstone: 0,
+        token_id: *amount.token_id,
+    })
+}
+
+/// Grow the subaddress range of a monitor with a gap limit, so that there are
+/// enough unused subaddresses past the highest one it received a TxOut on.
+/// The blocks the monitor already processed are rescanned for TxOuts sent to
+/// the new subaddresses. Those can make the range grow further.
+fn apply_gap_limit(
+    ledger_db: &LedgerDB,
+    mobilecoind_db: &Database,
+    monitor_id: &MonitorId,
+    logger: &Logger,
+) -> Result<(), Error> {
+    loop {
+        let monitor_data = mobilecoind_db.get_monitor_data(monitor_id)?;
+        let num_subaddresses = match monitor_data.gap_limit_pending_num_subaddresses() {
+            Some(num_subaddresses) => num_subaddresses,
+            None => return Ok(()),
+        };
+
+        let new_indexes =
+            monitor_data.subaddress_indexes().end..monitor_data.first_subaddress + num_subaddresses;
+        log::info!(
+            logger,
+            "monitor {} received a TxOut on subaddress {:?}, rescanning blocks {}..{} for subaddresses {:?}",
+            monitor_id,
+            monitor_data.highest_used_subaddress,
+            monitor_data.first_block,
+            monitor_data.next_block,
+            new_indexes,
         );
 
-        let key_image = KeyImage::from(&onetime_private_key);
-
-        results.push(UnspentTxOut {
-            tx_out: tx_out.clone(),
-            subaddress_index: subaddress_id.index,
-            key_image,
-            value: amount.value,
-            attempted_spend_height: 0,
-            attempted_spend_tombstone: 0,
-            token_id: *amount.token_id,
-        });
+        let (discovered_utxos, spent_utxos) =
+            rescan_subaddresses(ledger_db, &monitor_data, new_indexes)?;
+
+        // This records the subaddresses the rescan found TxOuts on, so the next
+        // iteration grows the range further if needed.
+        mobilecoind_db.extend_monitor_subaddresses(
+            monitor_id,
+            monitor_data.next_block,
+            num_subaddresses,
+            &discovered_utxos,
+            &spent_utxos,
+        )?;
+    }
+}
+
+/// Helper function for finding the TxOuts that were sent to a range of
+/// subaddresses of a monitor, in the blocks it already processed.
+/// Returns the TxOuts along with the block they were received in, and those
+/// that were also spent before the monitor's next block along with the block
+/// they were spent in.
+#[allow(clippy::type_complexity)]
+fn rescan_subaddresses(
+    ledger_db: &LedgerDB,
+    monitor_data: &MonitorData,
+    subaddress_indexes: Range<u64>,
+) -> Result<(Vec<(u64, UnspentTxOut)>, Vec<(u64, UnspentTxOut)>), Error> {
+    let view_private_key = monitor_data.view_private_key()?;
+    let spk_to_index: HashMap<SubaddressSPKId, u64> = subaddress_indexes
+        .map(|index| {
+            let subaddress = monitor_data.subaddress(index)?;
+            Ok((SubaddressSPKId::from(subaddress.spend_public_key()), index))
+        })
+        .collect::<Result<_, Error>>()?;
+
+    let mut discovered_utxos = Vec::new();
+    let mut spent_utxos = Vec::new();
+
+    for block_index in monitor_data.first_block..monitor_data.next_block {
+        let block_contents = ledger_db.get_block_contents(block_index)?;
+
+        for tx_out in &block_contents.outputs {
+            let tx_out_target_key = RistrettoPublic::try_from(&tx_out.target_key)?;
+            let tx_public_key = RistrettoPublic::try_from(&tx_out.public_key)?;
+
+            let subaddress_spk = SubaddressSPKId::from(&recover_public_subaddress_spend_key(
+                view_private_key,
+                &tx_out_target_key,
+                &tx_public_key,
+            ));
+
+            let index = match spk_to_index.get(&subaddress_spk) {
+                Some(index) => *index,
+                None => continue,
+            };
+
+            let utxo = tx_out_to_utxo(tx_out, &tx_public_key, index, monitor_data)?;
+
+            // Spends in blocks the monitor did not process yet are picked up by
+            // the sync code as usual.
+            if let Some(spent_block) = ledger_db.check_key_image(&utxo.key_image)? {
+                if spent_block < monitor_data.next_block {
+                    spent_utxos.push((spent_block, utxo.clone()));
+                }
+            }
+
+            discovered_utxos.push((block_index, utxo));
+        }
     }
 
-    Ok(results)
+    Ok((discovered_utxos, spent_utxos))
 }
 
 #[cfg(test)]
@@ -415,15 +710,17 @@ mod test {
     use super::*;
     use crate::{
         monitor_store::MonitorData,
+        processed_block_store::ProcessedBlockKey,
         test_utils::{
             self, add_block_to_ledger, get_test_databases, BlockVersion,
             DEFAULT_PER_RECIPIENT_AMOUNT,
         },
+        webhook_store::Webhook,
     };
     use mc_account_keys::{AccountKey, PublicAddress, DEFAULT_SUBADDRESS_INDEX};
     use mc_common::logger::{test_with_logger, Logger};
-    use mc_transaction_core::{tokens::Mob, tx::TxOut, Amount, Token};
-    use rand::{rngs::StdRng, SeedableRng};
+    use mc_transaction_core::{ring_signature::KeyImage, tokens::Mob, tx::TxOut, Amount, Token};
+    use rand::{rngs::StdRng, RngCore, SeedableRng};
 
     #[test_with_logger]
     fn test_sync_monitor(logger: Logger) {
@@ -440,7 +737,7 @@ mod test {
         )
         .unwrap();
 
-        let monitor_id = MonitorId::from(&data);
+        let monitor_id = MonitorId::try_from(&data).unwrap();
 
         let recipients: Vec<PublicAddress> = account_keys
             .iter()
@@ -489,7 +786,7 @@ mod test {
         assert_eq!(monitor_data.next_block, 0);
 
         // Process the first MAX_BLOCKS_PROCESSING_CHUNK_SIZE blocks.
-        let result = sync_monitor(&ledger_db, &mobilecoind_db, &monitor_id, &logger).unwrap();
+        let result = sync_monitor(&ledger_db, &mobilecoind_db, &monitor_id, None, &logger).unwrap();
         assert_eq!(result, SyncMonitorOk::MoreBlocksPotentiallyAvailable);
 
         // We should now discover some outputs. Each block has 1 output per recipient,
@@ -514,7 +811,7 @@ mod test {
         }
 
         // Process the second MAX_BLOCKS_PROCESSING_CHUNK_SIZE blocks.
-        let result = sync_monitor(&ledger_db, &mobilecoind_db, &monitor_id, &logger).unwrap();
+        let result = sync_monitor(&ledger_db, &mobilecoind_db, &monitor_id, None, &logger).unwrap();
         assert_eq!(result, SyncMonitorOk::MoreBlocksPotentiallyAvailable);
 
         let monitor_data = mobilecoind_db.get_monitor_data(&monitor_id).unwrap();
@@ -537,7 +834,7 @@ mod test {
         }
 
         // Process the last remaining block.
-        let result = sync_monitor(&ledger_db, &mobilecoind_db, &monitor_id, &logger).unwrap();
+        let result = sync_monitor(&ledger_db, &mobilecoind_db, &monitor_id, None, &logger).unwrap();
         assert_eq!(result, SyncMonitorOk::NoMoreBlocks);
 
         let monitor_data = mobilecoind_db.get_monitor_data(&monitor_id).unwrap();
@@ -557,7 +854,7 @@ mod test {
         }
 
         // Calling sync_monitor again should not change the results.
-        let result = sync_monitor(&ledger_db, &mobilecoind_db, &monitor_id, &logger).unwrap();
+        let result = sync_monitor(&ledger_db, &mobilecoind_db, &monitor_id, None, &logger).unwrap();
         assert_eq!(result, SyncMonitorOk::NoMoreBlocks);
 
         let monitor_data = mobilecoind_db.get_monitor_data(&monitor_id).unwrap();
@@ -592,7 +889,7 @@ mod test {
         )
         .unwrap();
 
-        let result = sync_monitor(&ledger_db, &mobilecoind_db, &monitor_id, &logger).unwrap();
+        let result = sync_monitor(&ledger_db, &mobilecoind_db, &monitor_id, None, &logger).unwrap();
         assert_eq!(result, SyncMonitorOk::NoMoreBlocks);
 
         let utxos = mobilecoind_db
@@ -618,7 +915,7 @@ mod test {
         )
         .unwrap();
 
-        let monitor_id = MonitorId::from(&data);
+        let monitor_id = MonitorId::try_from(&data).unwrap();
 
         let recipients: Vec<PublicAddress> = account_keys
             .iter()
@@ -639,7 +936,7 @@ mod test {
         assert_eq!(mobilecoind_db.add_monitor(&data).unwrap(), monitor_id);
 
         // Sync.
-        let result = sync_monitor(&ledger_db, &mobilecoind_db, &monitor_id, &logger).unwrap();
+        let result = sync_monitor(&ledger_db, &mobilecoind_db, &monitor_id, None, &logger).unwrap();
         assert_eq!(result, SyncMonitorOk::NoMoreBlocks);
 
         // Should have a single non-zero utxo for our monitor.
@@ -661,7 +958,7 @@ mod test {
         )
         .unwrap();
 
-        let result = sync_monitor(&ledger_db, &mobilecoind_db, &monitor_id, &logger).unwrap();
+        let result = sync_monitor(&ledger_db, &mobilecoind_db, &monitor_id, None, &logger).unwrap();
         assert_eq!(result, SyncMonitorOk::NoMoreBlocks);
 
         // We should now have only a zero utxo.
@@ -672,4 +969,238 @@ mod test {
         assert_eq!(utxos.len(), 1);
         assert_eq!(utxos[0].value, 0);
     }
+
+    #[test_with_logger]
+    fn test_gap_limit(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([98u8; 32]);
+
+        let account_key = AccountKey::random(&mut rng);
+
+        let mut data = MonitorData::new(
+            account_key.clone(),
+            DEFAULT_SUBADDRESS_INDEX, // first subaddress
+            5,                        // number of subaddresses
+            0,                        // first block
+            "",                       // name
+        )
+        .unwrap();
+        data.gap_limit = 3;
+
+        let monitor_id = MonitorId::try_from(&data).unwrap();
+
+        // Generate a test database where every block pays subaddress 6, which is
+        // outside of the monitored range.
+        let num_blocks = 3;
+        let (mut ledger_db, mobilecoind_db) = get_test_databases(
+            BlockVersion::MAX,
+            1,
+            &[account_key.subaddress(6)],
+            num_blocks,
+            logger.clone(),
+            &mut rng,
+        );
+
+        assert_eq!(mobilecoind_db.add_monitor(&data).unwrap(), monitor_id);
+        mobilecoind_db
+            .set_monitor_webhook(
+                &monitor_id,
+                Some(&Webhook {
+                    url: "http://localhost/webhook".to_string(),
+                    secret: vec![],
+                }),
+            )
+            .unwrap();
+
+        // Nothing should be found on subaddress 6, and the range stays the same.
+        let result = sync_monitor(&ledger_db, &mobilecoind_db, &monitor_id, None, &logger).unwrap();
+        assert_eq!(result, SyncMonitorOk::NoMoreBlocks);
+
+        let monitor_data = mobilecoind_db.get_monitor_data(&monitor_id).unwrap();
+        assert_eq!(monitor_data.next_block, num_blocks as u64);
+        assert_eq!(monitor_data.num_subaddresses, 5);
+        assert_eq!(monitor_data.rescan_generation, 0);
+
+        // Receiving on subaddress 1 leaves 3 unused subaddresses after it.
+        add_block_to_ledger(
+            &mut ledger_db,
+            BlockVersion::MAX,
+            &[account_key.subaddress(1)],
+            Amount::new(DEFAULT_PER_RECIPIENT_AMOUNT, Mob::ID),
+            &[KeyImage::from(rng.next_u64())],
+            &mut rng,
+        )
+        .unwrap();
+
+        let result = sync_monitor(&ledger_db, &mobilecoind_db, &monitor_id, None, &logger).unwrap();
+        assert_eq!(result, SyncMonitorOk::NoMoreBlocks);
+
+        let monitor_data = mobilecoind_db.get_monitor_data(&monitor_id).unwrap();
+        assert_eq!(monitor_data.num_subaddresses, 5);
+
+        // Receiving on subaddress 3 grows the range to 7 subaddresses, and the
+        // rescan then finds the TxOuts previously sent to subaddress 6, growing
+        // the range to 10 subaddresses.
+        add_block_to_ledger(
+            &mut ledger_db,
+            BlockVersion::MAX,
+            &[account_key.subaddress(3)],
+            Amount::new(DEFAULT_PER_RECIPIENT_AMOUNT, Mob::ID),
+            &[KeyImage::from(rng.next_u64())],
+            &mut rng,
+        )
+        .unwrap();
+
+        let result = sync_monitor(&ledger_db, &mobilecoind_db, &monitor_id, None, &logger).unwrap();
+        assert_eq!(result, SyncMonitorOk::NoMoreBlocks);
+
+        let monitor_data = mobilecoind_db.get_monitor_data(&monitor_id).unwrap();
+        assert_eq!(monitor_data.next_block, num_blocks as u64 + 2);
+        assert_eq!(monitor_data.num_subaddresses, 10);
+
+        // Growing the range to 10 subaddresses found nothing, so the events of past
+        // blocks changed only once.
+        assert_eq!(monitor_data.rescan_generation, 1);
+
+        let utxos = mobilecoind_db
+            .get_utxos_for_subaddress(&monitor_id, 3)
+            .unwrap();
+        assert_eq!(utxos.len(), 1);
+
+        let utxos = mobilecoind_db
+            .get_utxos_for_subaddress(&monitor_id, 6)
+            .unwrap();
+        assert_eq!(utxos.len(), num_blocks);
+
+        // The rescanned TxOuts are recorded in the blocks they were received in, and
+        // their events are queued for the webhook.
+        let processed_tx_outs = mobilecoind_db.get_processed_block(&monitor_id, 0).unwrap();
+        assert_eq!(processed_tx_outs.len(), 1);
+        assert_eq!(processed_tx_outs[0].subaddress_index, 6);
+
+        let deliveries = mobilecoind_db.get_next_webhook_deliveries().unwrap();
+        assert_eq!(deliveries.len(), 1);
+        assert_eq!(deliveries[0].0, ProcessedBlockKey::new(&monitor_id, 0));
+
+        // The new subaddresses are matched when syncing new blocks.
+        add_block_to_ledger(
+            &mut ledger_db,
+            BlockVersion::MAX,
+            &[account_key.subaddress(8)],
+            Amount::new(DEFAULT_PER_RECIPIENT_AMOUNT, Mob::ID),
+            &[KeyImage::from(rng.next_u64())],
+            &mut rng,
+        )
+        .unwrap();
+
+        let result = sync_monitor(&ledger_db, &mobilecoind_db, &monitor_id, None, &logger).unwrap();
+        assert_eq!(result, SyncMonitorOk::NoMoreBlocks);
+
+        let utxos = mobilecoind_db
+            .get_utxos_for_subaddress(&monitor_id, 8)
+            .unwrap();
+        assert_eq!(utxos.len(), 1);
+
+        let monitor_data = mobilecoind_db.get_monitor_data(&monitor_id).unwrap();
+        assert_eq!(monitor_data.num_subaddresses, 12);
+        assert_eq!(monitor_data.rescan_generation, 1);
+    }
+
+    #[test_with_logger]
+    fn test_gap_limit_after_block_processed(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([97u8; 32]);
+
+        let account_key = AccountKey::random(&mut rng);
+
+        let mut data = MonitorData::new(
+            account_key.clone(),
+            DEFAULT_SUBADDRESS_INDEX, // first subaddress
+            5,                        // number of subaddresses
+            0,                        // first block
+            "",                       // name
+        )
+        .unwrap();
+        data.gap_limit = 3;
+
+        // The first block pays subaddress 6, the second one subaddress 3.
+        let (mut ledger_db, mobilecoind_db) = get_test_databases(
+            BlockVersion::MAX,
+            1,
+            &[account_key.subaddress(6)],
+            1,
+            logger.clone(),
+            &mut rng,
+        );
+        add_block_to_ledger(
+            &mut ledger_db,
+            BlockVersion::MAX,
+            &[account_key.subaddress(3)],
+            Amount::new(DEFAULT_PER_RECIPIENT_AMOUNT, Mob::ID),
+            &[KeyImage::from(rng.next_u64())],
+            &mut rng,
+        )
+        .unwrap();
+
+        let monitor_id = mobilecoind_db.add_monitor(&data).unwrap();
+
+        // Process both blocks without growing the range, as if growing it failed
+        // right after the second block was processed.
+        for block_index in 0..2 {
+            let monitor_data = mobilecoind_db.get_monitor_data(&monitor_id).unwrap();
+            let block_contents = ledger_db.get_block_contents(block_index).unwrap();
+            let utxos = match_tx_outs_into_utxos(
+                &mobilecoind_db,
+                &block_contents.outputs,
+                &monitor_id,
+                &monitor_data,
+                &logger,
+            )
+            .unwrap();
+            mobilecoind_db
+                .block_processed(&monitor_id, block_index, &utxos, &[])
+                .unwrap();
+        }
+
+        let monitor_data = mobilecoind_db.get_monitor_data(&monitor_id).unwrap();
+        assert_eq!(monitor_data.num_subaddresses, 5);
+        assert_eq!(monitor_data.highest_used_subaddress, Some(3));
+
+        // The next sync grows the range from the recorded subaddress, and finds the
+        // TxOut sent to subaddress 6.
+        let result = sync_monitor(&ledger_db, &mobilecoind_db, &monitor_id, None, &logger).unwrap();
+        assert_eq!(result, SyncMonitorOk::NoMoreBlocks);
+
+        let monitor_data = mobilecoind_db.get_monitor_data(&monitor_id).unwrap();
+        assert_eq!(monitor_data.num_subaddresses, 10);
+        assert_eq!(monitor_data.highest_used_subaddress, Some(6));
+        let utxos = mobilecoind_db
+            .get_utxos_for_subaddress(&monitor_id, 6)
+            .unwrap();
+        assert_eq!(utxos.len(), 1);
+    }
+
+    #[test]
+    fn test_sync_delay_backoff() {
+        let delay = SyncDelay::failed(None);
+        assert_eq!(delay.num_failures, 1);
+        assert!(delay.until <= Instant::now() + SYNC_ERROR_BACKOFF);
+
+        // Each consecutive failure doubles the wait, up to the maximum.
+        let delay = SyncDelay::failed(Some(&delay));
+        assert_eq!(delay.num_failures, 2);
+        assert!(delay.until > Instant::now() + SYNC_ERROR_BACKOFF);
+        assert!(delay.until <= Instant::now() + SYNC_ERROR_BACKOFF * 2);
+
+        let mut delay = delay;
+        for _ in 0..40 {
+            delay = SyncDelay::failed(Some(&delay));
+        }
+        assert_eq!(delay.num_failures, 42);
+        assert!(delay.until <= Instant::now() + MAX_SYNC_ERROR_BACKOFF);
+        assert!(delay.until > Instant::now() + MAX_SYNC_ERROR_BACKOFF / 2);
+
+        // Polling fog successfully resets the failures.
+        let delay = SyncDelay::fog_polled();
+        assert_eq!(delay.num_failures, 0);
+        assert!(delay.until <= Instant::now() + FOG_POLL_INTERVAL);
+    }
 }
@@ -114,7 +114,7 @@ pub fn get_test_monitor_data_and_id(
     )
     .unwrap();
 
-    let monitor_id = MonitorId::from(&data);
+    let monitor_id = MonitorId::try_from(&data).unwrap();
     (data, monitor_id)
 }
 
@@ -173,6 +173,7 @@ pub fn setup_server<FPR: FogPubkeyResolver + Default + Send + Sync + 'static>(
         network_state,
         uri,
         None,
+        None,
         logger,
     );
 
@@ -13,6 +13,7 @@ use mc_common::{
     logger::{log, Logger},
     HashMap,
 };
+use mc_crypto_keys::CompressedRistrettoPublic;
 use mc_transaction_core::{ring_signature::KeyImage, tx::TxOut};
 use mc_util_serial::Message;
 use std::sync::Arc;
@@ -38,6 +39,8 @@ pub struct UnspentTxOut {
     pub subaddress_index: u64,
 
     /// Key image of this TxOut.
+    /// For TxOuts received by view-only monitors, this is a placeholder until
+    /// the real key image is imported (see `placeholder_key_image`).
     #[prost(message, required, tag = "3")]
     pub key_image: KeyImage,
 
@@ -62,6 +65,22 @@ pub struct UnspentTxOut {
     pub token_id: u64,
 }
 
+impl UnspentTxOut {
+    /// The key image stored for a TxOut received by a view-only monitor, until
+    /// its real key image is imported. Computing a key image requires the
+    /// spend private key, so the public key of the TxOut is used instead. This
+    /// keeps UtxoIds unique, but it never matches a key image in the ledger.
+    pub fn placeholder_key_image(tx_public_key: &CompressedRistrettoPublic) -> KeyImage {
+        KeyImage::from(*tx_public_key.as_bytes())
+    }
+
+    /// Whether the real key image of this TxOut is known, i.e. whether it is
+    /// possible to tell when it gets spent.
+    pub fn has_key_image(&self) -> bool {
+        self.key_image != Self::placeholder_key_image(&self.tx_out.public_key)
+    }
+}
+
 /// Type used as the key in the utxo_id_to_utxo  database.
 pub type UtxoId = DatabaseByteArrayKey;
 
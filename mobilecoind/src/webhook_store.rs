@@ -0,0 +1,548 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! Database storage for webhooks.
+//! * Stores a map of monitor id -> webhook that payment events of the monitor
+//!   get delivered to.
+//! * Stores an outbox of (monitor id, block index) -> webhook delivery, for
+//!   events that were not delivered yet. Deliveries are added to the outbox in
+//!   the same transaction that processes the block they originate from, so that
+//!   no events are lost across restarts.
+
+use crate::{
+    db_crypto::{DbCryptoProvider, EncryptionAudit},
+    error::Error,
+    monitor_store::MonitorId,
+    processed_block_store::ProcessedBlockKey,
+    utxo_store::UnspentTxOut,
+};
+use hex_fmt::HexFmt;
+use lmdb::{Cursor, Database, DatabaseFlags, Environment, RwTransaction, Transaction, WriteFlags};
+use mc_common::logger::{log, Logger};
+use prost::Message;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+// LMDB Database Names
+pub const MONITOR_ID_TO_WEBHOOK_DB_NAME: &str =
+    "mobilecoind_db:webhook_store:monitor_id_to_webhook";
+pub const WEBHOOK_DELIVERY_KEY_TO_WEBHOOK_DELIVERY_DB_NAME: &str =
+    "mobilecoind_db:webhook_store:webhook_delivery_key_to_webhook_delivery";
+
+/// Type used as the stored data in the monitor_id_to_webhook database.
+#[derive(Clone, Eq, PartialEq, Message)]
+pub struct Webhook {
+    /// The URL events get POSTed to.
+    #[prost(string, tag = "1")]
+    pub url: String,
+
+    /// The secret used to HMAC-sign deliveries.
+    #[prost(bytes, tag = "2")]
+    pub secret: Vec<u8>,
+}
+
+/// Type used as the stored data in the outbox database.
+#[derive(Clone, Eq, PartialEq, Message)]
+pub struct WebhookDelivery {
+    /// The JSON payload to POST.
+    #[prost(bytes, tag = "1")]
+    pub payload: Vec<u8>,
+
+    /// Number of failed delivery attempts so far.
+    #[prost(uint32, tag = "2")]
+    pub num_attempts: u32,
+
+    /// Time (in milliseconds since the Unix epoch) before which the delivery
+    /// should not be attempted.
+    #[prost(uint64, tag = "3")]
+    pub next_attempt_at: u64,
+}
+
+/// The JSON representation of an UnspentTxOut in a webhook payload.
+fn utxo_to_json(utxo: &UnspentTxOut) -> Value {
+    json!({
+        "subaddress_index": utxo.subaddress_index,
+        "tx_public_key": HexFmt(utxo.tx_out.public_key.as_bytes()).to_string(),
+        "key_image": HexFmt(utxo.key_image.as_bytes()).to_string(),
+        "value": utxo.value.to_string(),
+        "token_id": utxo.token_id.to_string(),
+    })
+}
+
+impl WebhookDelivery {
+    /// Create a delivery for the UnspentTxOuts a monitor received and spent in
+    /// a given block.
+    pub fn new(
+        monitor_id: &MonitorId,
+        block_index: u64,
+        received_utxos: &[UnspentTxOut],
+        spent_utxos: &[UnspentTxOut],
+    ) -> Self {
+        let payload = json!({
+            "monitor_id": monitor_id.to_string(),
+            "block_index": block_index.to_string(),
+            "received": received_utxos.iter().map(utxo_to_json).collect::<Vec<_>>(),
+            "spent": spent_utxos.iter().map(utxo_to_json).collect::<Vec<_>>(),
+        });
+
+        Self {
+            payload: payload.to_string().into_bytes(),
+            num_attempts: 0,
+            next_attempt_at: 0,
+        }
+    }
+
+    /// Add UnspentTxOuts the monitor received and spent in the delivery's
+    /// block, which were found after the delivery was created.
+    pub fn add_events(
+        &mut self,
+        received_utxos: &[UnspentTxOut],
+        spent_utxos: &[UnspentTxOut],
+    ) -> Result<(), Error> {
+        let mut payload: Value = serde_json::from_slice(&self.payload)
+            .map_err(|err| Error::InvalidArgument("payload".to_string(), err.to_string()))?;
+        for (field, utxos) in [("received", received_utxos), ("spent", spent_utxos)] {
+            let events = payload
+                .get_mut(field)
+                .and_then(Value::as_array_mut)
+                .ok_or_else(|| {
+                    Error::InvalidArgument("payload".to_string(), format!("missing {}", field))
+                })?;
+            events.extend(utxos.iter().map(utxo_to_json));
+        }
+        self.payload = payload.to_string().into_bytes();
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+pub struct WebhookStore {
+    /// Retain a reference to the Environment so the Database handles are valid.
+    _env: Arc<Environment>,
+
+    /// Crypto provider, used for managing database encryption.
+    crypto_provider: DbCryptoProvider,
+
+    /// Mapping of MonitorId -> Webhook
+    monitor_id_to_webhook: Database,
+
+    /// Mapping of ProcessedBlockKey -> WebhookDelivery
+    webhook_delivery_key_to_webhook_delivery: Database,
+
+    /// Logger.
+    logger: Logger,
+}
+
+impl WebhookStore {
+    pub fn new(
+        env: Arc<Environment>,
+        crypto_provider: DbCryptoProvider,
+        logger: Logger,
+    ) -> Result<Self, Error> {
+        let monitor_id_to_webhook =
+            env.create_db(Some(MONITOR_ID_TO_WEBHOOK_DB_NAME), DatabaseFlags::empty())?;
+        let webhook_delivery_key_to_webhook_delivery = env.create_db(
+            Some(WEBHOOK_DELIVERY_KEY_TO_WEBHOOK_DELIVERY_DB_NAME),
+            DatabaseFlags::empty(),
+        )?;
+
+        Ok(Self {
+            _env: env,
+            crypto_provider,
+            monitor_id_to_webhook,
+            webhook_delivery_key_to_webhook_delivery,
+            logger,
+        })
+    }
+
+    /// Set the webhook of a monitor, replacing the existing one.
+    pub fn set_webhook<'env>(
+        &self,
+        db_txn: &mut RwTransaction<'env>,
+        monitor_id: &MonitorId,
+        webhook: &Webhook,
+    ) -> Result<(), Error> {
+        let value_bytes = self
+            .crypto_provider
+            .encrypt(&mc_util_serial::encode(webhook))?;
+
+        db_txn.put(
+            self.monitor_id_to_webhook,
+            monitor_id,
+            &value_bytes,
+            WriteFlags::empty(),
+        )?;
+
+        log::trace!(
+            self.logger,
+            "Set webhook {} for monitor {}",
+            webhook.url,
+            monitor_id
+        );
+        Ok(())
+    }
+
+    /// Get the webhook of a monitor, if any.
+    pub fn get_webhook(
+        &self,
+        db_txn: &impl Transaction,
+        monitor_id: &MonitorId,
+    ) -> Result<Option<Webhook>, Error> {
+        match db_txn.get(self.monitor_id_to_webhook, monitor_id) {
+            Ok(value_bytes) => {
+                let value_bytes = self.crypto_provider.decrypt(value_bytes)?;
+                Ok(Some(mc_util_serial::decode(&value_bytes)?))
+            }
+            Err(lmdb::Error::NotFound) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Remove the webhook of a monitor, alongside its undelivered events.
+    pub fn remove<'env>(
+        &self,
+        db_txn: &mut RwTransaction<'env>,
+        monitor_id: &MonitorId,
+    ) -> Result<(), Error> {
+        match db_txn.del(self.monitor_id_to_webhook, monitor_id, None) {
+            Ok(()) | Err(lmdb::Error::NotFound) => {}
+            Err(err) => return Err(err.into()),
+        }
+
+        let start_key = ProcessedBlockKey::new(monitor_id, 0);
+        let start_key_bytes = start_key.to_vec();
+
+        let mut cursor = db_txn.open_rw_cursor(self.webhook_delivery_key_to_webhook_delivery)?;
+        for (db_key, _db_value) in cursor.iter_from(&start_key_bytes).filter_map(|r| r.ok()) {
+            let key = ProcessedBlockKey::try_from(db_key)?;
+            if key.monitor_id == *monitor_id {
+                cursor.del(WriteFlags::empty())?;
+            } else {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Add a delivery to the outbox.
+    pub fn enqueue<'env>(
+        &self,
+        db_txn: &mut RwTransaction<'env>,
+        key: &ProcessedBlockKey,
+        delivery: &WebhookDelivery,
+    ) -> Result<(), Error> {
+        db_txn.put(
+            self.webhook_delivery_key_to_webhook_delivery,
+            &key.to_vec(),
+            &mc_util_serial::encode(delivery),
+            WriteFlags::empty(),
+        )?;
+        Ok(())
+    }
+
+    /// Add the UnspentTxOuts a monitor received and spent in a given block to
+    /// the outbox, merging them into the block's delivery if it was not
+    /// delivered yet.
+    pub fn enqueue_events<'env>(
+        &self,
+        db_txn: &mut RwTransaction<'env>,
+        monitor_id: &MonitorId,
+        block_index: u64,
+        received_utxos: &[UnspentTxOut],
+        spent_utxos: &[UnspentTxOut],
+    ) -> Result<(), Error> {
+        let key = ProcessedBlockKey::new(monitor_id, block_index);
+        let delivery =
+            match db_txn.get(self.webhook_delivery_key_to_webhook_delivery, &key.to_vec()) {
+                Ok(value_bytes) => {
+                    let mut delivery: WebhookDelivery = mc_util_serial::decode(value_bytes)?;
+                    delivery.add_events(received_utxos, spent_utxos)?;
+                    delivery
+                }
+                Err(lmdb::Error::NotFound) => {
+                    WebhookDelivery::new(monitor_id, block_index, received_utxos, spent_utxos)
+                }
+                Err(err) => return Err(err.into()),
+            };
+        self.enqueue(db_txn, &key, &delivery)
+    }
+
+    /// Get the oldest delivery of each monitor in the outbox. Deliveries of a
+    /// given monitor are attempted in order, so the rest have to wait.
+    pub fn get_next_deliveries(
+        &self,
+        db_txn: &impl Transaction,
+    ) -> Result<Vec<(ProcessedBlockKey, WebhookDelivery)>, Error> {
+        let mut cursor = db_txn.open_ro_cursor(self.webhook_delivery_key_to_webhook_delivery)?;
+
+        let mut deliveries: Vec<(ProcessedBlockKey, WebhookDelivery)> = Vec::new();
+        for result in cursor.iter_start() {
+            let (db_key, db_value) = result?;
+            let key = ProcessedBlockKey::try_from(db_key)?;
+            if deliveries
+                .last()
+                .map_or(true, |(last_key, _)| last_key.monitor_id != key.monitor_id)
+            {
+                deliveries.push((key, mc_util_serial::decode(db_value)?));
+            }
+        }
+
+        Ok(deliveries)
+    }
+
+    /// Remove a delivery from the outbox.
+    pub fn remove_delivery<'env>(
+        &self,
+        db_txn: &mut RwTransaction<'env>,
+        key: &ProcessedBlockKey,
+    ) -> Result<(), Error> {
+        match db_txn.del(
+            self.webhook_delivery_key_to_webhook_delivery,
+            &key.to_vec(),
+            None,
+        ) {
+            // The delivery might have been removed alongside its webhook in the meantime.
+            Ok(()) | Err(lmdb::Error::NotFound) => Ok(()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Update a delivery in the outbox, if it is still there.
+    pub fn update_delivery<'env>(
+        &self,
+        db_txn: &mut RwTransaction<'env>,
+        key: &ProcessedBlockKey,
+        delivery: &WebhookDelivery,
+    ) -> Result<(), Error> {
+        let key_bytes = key.to_vec();
+        match db_txn.get(self.webhook_delivery_key_to_webhook_delivery, &key_bytes) {
+            Ok(_value_bytes) => self.enqueue(db_txn, key, delivery),
+            Err(lmdb::Error::NotFound) => Ok(()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Re-encrypt the encrypted parts of the database with a new password.
+    /// This will fail if the current password is not set in the crypto_provider
+    /// since part of the re-encryption process relies on being able to
+    /// decrypt the existing data.
+    pub fn re_encrypt<'env>(
+        &self,
+        db_txn: &mut RwTransaction<'env>,
+        new_password: &[u8],
+    ) -> Result<(), Error> {
+        let mut cursor = db_txn.open_rw_cursor(self.monitor_id_to_webhook)?;
+
+        for (key_bytes, value_bytes) in cursor.iter().filter_map(|r| r.ok()) {
+            let decrypted_bytes = self.crypto_provider.decrypt(value_bytes)?;
+            let encrypted_bytes = self
+                .crypto_provider
+                .encrypt_with_password(new_password, &decrypted_bytes)?;
+            cursor.put(&key_bytes, &encrypted_bytes, WriteFlags::CURRENT)?;
+        }
+
+        Ok(())
+    }
+
+    /// Count the records that cannot be decrypted with the current password.
+    pub fn audit_encryption(&self, db_txn: &impl Transaction) -> Result<EncryptionAudit, Error> {
+        let mut cursor = db_txn.open_ro_cursor(self.monitor_id_to_webhook)?;
+
+        let mut audit = EncryptionAudit::default();
+        for result in cursor.iter() {
+            let (_key_bytes, value_bytes) = result?;
+            audit.num_records += 1;
+
+            let readable = self
+                .crypto_provider
+                .decrypt(value_bytes)
+                .ok()
+                .and_then(|value_bytes| mc_util_serial::decode::<Webhook>(&value_bytes).ok())
+                .is_some();
+            if !readable {
+                audit.num_unreadable += 1;
+            }
+        }
+
+        Ok(audit)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::{get_test_databases, get_test_monitor_data_and_id, BlockVersion};
+    use mc_common::logger::{test_with_logger, Logger};
+    use mc_ledger_db::Ledger;
+    use mc_transaction_core::{ring_signature::KeyImage, tokens::Mob, Token};
+    use rand::{rngs::StdRng, SeedableRng};
+    use tempdir::TempDir;
+
+    fn setup_test_webhook_store(logger: &Logger) -> (Arc<Environment>, WebhookStore) {
+        let db_tmp =
+            TempDir::new("webhook_store_db").expect("Could not make tempdir for webhook store db");
+        let db_path = db_tmp
+            .path()
+            .to_str()
+            .expect("Could not get path as string");
+
+        let env = Arc::new(
+            Environment::new()
+                .set_max_dbs(10)
+                .set_map_size(10000000)
+                .open(db_path.as_ref())
+                .unwrap(),
+        );
+
+        let crypto_provider = DbCryptoProvider::new(env.clone()).unwrap();
+        let webhook_store =
+            WebhookStore::new(env.clone(), crypto_provider, logger.clone()).unwrap();
+
+        (env, webhook_store)
+    }
+
+    #[test_with_logger]
+    fn test_webhook_store(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([1u8; 32]);
+        let (env, webhook_store) = setup_test_webhook_store(&logger);
+
+        let (_monitor_data1, monitor_id1) = get_test_monitor_data_and_id(&mut rng);
+        let (_monitor_data2, monitor_id2) = get_test_monitor_data_and_id(&mut rng);
+
+        let webhook = Webhook {
+            url: "http://localhost/payments".to_owned(),
+            secret: b"secret".to_vec(),
+        };
+        let delivery = WebhookDelivery::new(&monitor_id1, 5, &[], &[]);
+
+        let mut db_txn = env.begin_rw_txn().unwrap();
+        assert_eq!(
+            webhook_store.get_webhook(&db_txn, &monitor_id1).unwrap(),
+            None
+        );
+        webhook_store
+            .set_webhook(&mut db_txn, &monitor_id1, &webhook)
+            .unwrap();
+        assert_eq!(
+            webhook_store.get_webhook(&db_txn, &monitor_id1).unwrap(),
+            Some(webhook.clone())
+        );
+
+        // Only the oldest delivery of each monitor is returned.
+        for (monitor_id, block_index) in [(monitor_id1, 7), (monitor_id1, 5), (monitor_id2, 9)] {
+            webhook_store
+                .enqueue(
+                    &mut db_txn,
+                    &ProcessedBlockKey::new(&monitor_id, block_index),
+                    &delivery,
+                )
+                .unwrap();
+        }
+        let mut expected_keys = vec![
+            ProcessedBlockKey::new(&monitor_id1, 5),
+            ProcessedBlockKey::new(&monitor_id2, 9),
+        ];
+        expected_keys.sort();
+        let next_deliveries = webhook_store.get_next_deliveries(&db_txn).unwrap();
+        assert_eq!(
+            next_deliveries
+                .iter()
+                .map(|(key, _)| *key)
+                .collect::<Vec<_>>(),
+            expected_keys
+        );
+        assert!(next_deliveries
+            .iter()
+            .all(|(_, next_delivery)| *next_delivery == delivery));
+
+        // Updating a delivery keeps it in place, removing it makes room for the next
+        // one.
+        let failed_delivery = WebhookDelivery {
+            num_attempts: 1,
+            next_attempt_at: 1000,
+            ..delivery.clone()
+        };
+        let key = ProcessedBlockKey::new(&monitor_id1, 5);
+        webhook_store
+            .update_delivery(&mut db_txn, &key, &failed_delivery)
+            .unwrap();
+        let next_deliveries = webhook_store.get_next_deliveries(&db_txn).unwrap();
+        assert!(next_deliveries.contains(&(key, failed_delivery)));
+
+        webhook_store.remove_delivery(&mut db_txn, &key).unwrap();
+        let next_deliveries = webhook_store.get_next_deliveries(&db_txn).unwrap();
+        assert!(next_deliveries.contains(&(ProcessedBlockKey::new(&monitor_id1, 7), delivery)));
+
+        // Removing a monitor's webhook drops its undelivered events, but not those of
+        // other monitors.
+        webhook_store.remove(&mut db_txn, &monitor_id1).unwrap();
+        assert_eq!(
+            webhook_store.get_webhook(&db_txn, &monitor_id1).unwrap(),
+            None
+        );
+        let next_deliveries = webhook_store.get_next_deliveries(&db_txn).unwrap();
+        assert_eq!(next_deliveries.len(), 1);
+        assert_eq!(next_deliveries[0].0.monitor_id, monitor_id2);
+
+        // Removing a monitor without a webhook is fine.
+        webhook_store.remove(&mut db_txn, &monitor_id1).unwrap();
+    }
+
+    #[test_with_logger]
+    fn test_enqueue_events(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([2u8; 32]);
+        let (env, webhook_store) = setup_test_webhook_store(&logger);
+        let (_monitor_data, monitor_id) = get_test_monitor_data_and_id(&mut rng);
+
+        let (ledger_db, _mobilecoind_db) =
+            get_test_databases(BlockVersion::MAX, 2, &[], 1, logger.clone(), &mut rng);
+        let utxos: Vec<UnspentTxOut> = (0..2)
+            .map(|idx| UnspentTxOut {
+                tx_out: ledger_db.get_tx_out_by_index(idx).unwrap(),
+                subaddress_index: 0,
+                key_image: KeyImage::from(idx),
+                value: idx,
+                attempted_spend_height: 0,
+                attempted_spend_tombstone: 0,
+                token_id: *Mob::ID,
+            })
+            .collect();
+
+        let mut db_txn = env.begin_rw_txn().unwrap();
+        webhook_store
+            .enqueue_events(&mut db_txn, &monitor_id, 3, &utxos[..1], &[])
+            .unwrap();
+
+        // Events found later for a block that was not delivered yet are merged into
+        // its delivery.
+        webhook_store
+            .enqueue_events(&mut db_txn, &monitor_id, 3, &[], &utxos[1..])
+            .unwrap();
+
+        let next_deliveries = webhook_store.get_next_deliveries(&db_txn).unwrap();
+        assert_eq!(
+            next_deliveries,
+            vec![(
+                ProcessedBlockKey::new(&monitor_id, 3),
+                WebhookDelivery::new(&monitor_id, 3, &utxos[..1], &utxos[1..])
+            )]
+        );
+
+        // Once it was delivered, they get a delivery of their own.
+        webhook_store
+            .remove_delivery(&mut db_txn, &ProcessedBlockKey::new(&monitor_id, 3))
+            .unwrap();
+        webhook_store
+            .enqueue_events(&mut db_txn, &monitor_id, 3, &[], &utxos[..1])
+            .unwrap();
+
+        let next_deliveries = webhook_store.get_next_deliveries(&db_txn).unwrap();
+        assert_eq!(
+            next_deliveries,
+            vec![(
+                ProcessedBlockKey::new(&monitor_id, 3),
+                WebhookDelivery::new(&monitor_id, 3, &[], &utxos[..1])
+            )]
+        );
+    }
+}
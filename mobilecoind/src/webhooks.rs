@@ -0,0 +1,343 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! Delivers the payment events of monitors to their webhooks.
+//!
+//! As blocks get processed, events for monitors that have a webhook are queued
+//! in the webhook outbox of the database. The webhook thread periodically goes
+//! over the outbox and POSTs the oldest undelivered event of each monitor to
+//! its webhook, so that each webhook sees events in block order. An event is
+//! only removed from the outbox once the webhook acknowledged it with a 2xx
+//! response, otherwise it is retried with an exponential backoff.
+//!
+//! Each request carries the hex-encoded HMAC-SHA256 of its body, keyed with the
+//! webhook's secret, in the `X-Mobilecoind-Signature` header.
+
+use crate::{
+    database::Database,
+    error::Error,
+    webhook_store::{Webhook, WebhookDelivery},
+};
+use hex_fmt::HexFmt;
+use hmac::{Hmac, Mac};
+use mc_common::logger::{log, Logger};
+use reqwest::{blocking::Client, header::CONTENT_TYPE};
+use sha2::Sha256;
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// The header holding the signature of a delivery.
+pub const WEBHOOK_SIGNATURE_HEADER: &str = "X-Mobilecoind-Signature";
+
+/// How long to wait before checking the outbox again, when there is nothing
+/// to deliver.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Timeout for a single delivery attempt.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Delay before retrying a failed delivery for the first time. The delay
+/// doubles with each failed attempt.
+const MIN_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Maximal delay between two attempts of a delivery.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(3600);
+
+/// Webhook thread - holds objects needed to cleanly terminate the webhook
+/// thread.
+pub struct WebhookThread {
+    /// The webhook thread handle.
+    join_handle: Option<thread::JoinHandle<()>>,
+
+    /// Stop trigger, used to signal the thread to terminate.
+    stop_requested: Arc<AtomicBool>,
+}
+
+impl WebhookThread {
+    pub fn start(mobilecoind_db: Database, logger: Logger) -> Self {
+        let stop_requested = Arc::new(AtomicBool::new(false));
+        let thread_stop_requested = stop_requested.clone();
+
+        let join_handle = Some(
+            thread::Builder::new()
+                .name("webhooks".to_string())
+                .spawn(move || {
+                    log::debug!(logger, "WebhookThread started.");
+
+                    let client = Client::builder()
+                        .timeout(REQUEST_TIMEOUT)
+                        .build()
+                        .expect("failed creating http client");
+
+                    while !thread_stop_requested.load(Ordering::SeqCst) {
+                        let num_delivered =
+                            deliver_pending_events(&mobilecoind_db, &client, &logger)
+                                .unwrap_or_else(|err| {
+                                    log::error!(logger, "Failed delivering webhooks: {}", err);
+                                    0
+                                });
+
+                        // If nothing got delivered, sleep for a bit so that we do not use 100%
+                        // cpu.
+                        if num_delivered == 0 {
+                            thread::sleep(POLL_INTERVAL);
+                        }
+                    }
+
+                    log::debug!(logger, "WebhookThread stopped.");
+                })
+                .expect("failed starting webhook thread"),
+        );
+
+        Self {
+            join_handle,
+            stop_requested,
+        }
+    }
+
+    pub fn stop(&mut self) {
+        self.stop_requested.store(true, Ordering::SeqCst);
+        if let Some(join_handle) = self.join_handle.take() {
+            join_handle.join().expect("WebhookThread join failed");
+        }
+    }
+}
+
+impl Drop for WebhookThread {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Compute the signature of a payload, as carried by the
+/// `X-Mobilecoind-Signature` header.
+pub fn sign_payload(secret: &[u8], payload: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC can take a key of any size");
+    mac.update(payload);
+    HexFmt(mac.finalize().into_bytes()).to_string()
+}
+
+/// Attempt all deliveries that are due, returning how many succeeded.
+fn deliver_pending_events(
+    mobilecoind_db: &Database,
+    client: &Client,
+    logger: &Logger,
+) -> Result<usize, Error> {
+    let now = now_millis();
+    let mut num_delivered = 0;
+
+    for (key, webhook, delivery) in mobilecoind_db.get_next_webhook_deliveries()? {
+        if delivery.next_attempt_at > now {
+            continue;
+        }
+
+        match deliver(client, &webhook, &delivery) {
+            Ok(()) => {
+                log::debug!(
+                    logger,
+                    "Delivered block {} of monitor {} to {}",
+                    key.block_index,
+                    key.monitor_id,
+                    webhook.url
+                );
+                mobilecoind_db.webhook_delivered(&key)?;
+                num_delivered += 1;
+            }
+            Err(err) => {
+                let num_attempts = delivery.num_attempts.saturating_add(1);
+                let retry_delay = MIN_RETRY_DELAY
+                    .checked_mul(1 << num_attempts.saturating_sub(1).min(31))
+                    .unwrap_or(MAX_RETRY_DELAY)
+                    .min(MAX_RETRY_DELAY);
+                log::warn!(
+                    logger,
+                    "Failed delivering block {} of monitor {} to {} (attempt {}), retrying in {:?}: {}",
+                    key.block_index,
+                    key.monitor_id,
+                    webhook.url,
+                    num_attempts,
+                    retry_delay,
+                    err
+                );
+                mobilecoind_db.webhook_delivery_failed(
+                    &key,
+                    &WebhookDelivery {
+                        num_attempts,
+                        next_attempt_at: now + retry_delay.as_millis() as u64,
+                        ..delivery
+                    },
+                )?;
+            }
+        }
+    }
+
+    Ok(num_delivered)
+}
+
+/// POST a delivery to a webhook.
+fn deliver(client: &Client, webhook: &Webhook, delivery: &WebhookDelivery) -> Result<(), Error> {
+    let response = client
+        .post(&webhook.url)
+        .header(CONTENT_TYPE, "application/json")
+        .header(
+            WEBHOOK_SIGNATURE_HEADER,
+            sign_payload(&webhook.secret, &delivery.payload),
+        )
+        .body(delivery.payload.clone())
+        .send()
+        .map_err(|err| Error::Webhook(err.to_string()))?;
+
+    if !response.status().is_success() {
+        return Err(Error::Webhook(format!(
+            "unexpected response status {}",
+            response.status()
+        )));
+    }
+
+    Ok(())
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("time went backwards")
+        .as_millis() as u64
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        monitor_store::MonitorData,
+        sync::SyncThread,
+        test_utils::{get_test_databases, wait_for_monitors, BlockVersion},
+    };
+    use mc_account_keys::AccountKey;
+    use mc_common::logger::{test_with_logger, Logger};
+    use mc_ledger_db::Ledger;
+    use rand::{rngs::StdRng, SeedableRng};
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::TcpListener,
+        sync::mpsc,
+    };
+
+    /// A minimal HTTP server standing in for a webhook. It answers the first
+    /// `num_failures` requests with a 500, and the rest with a 200, reporting
+    /// the signature header and body of each request.
+    fn start_webhook_server(num_failures: usize) -> (String, mpsc::Receiver<(String, Vec<u8>)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/payments", listener.local_addr().unwrap());
+        let (sender, receiver) = mpsc::channel();
+
+        thread::spawn(move || {
+            for (i, stream) in listener.incoming().enumerate() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+                let mut signature = String::new();
+                let mut content_length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    let line = line.trim_end();
+                    if line.is_empty() {
+                        break;
+                    }
+                    if let Some((name, value)) = line.split_once(':') {
+                        if name.eq_ignore_ascii_case("content-length") {
+                            content_length = value.trim().parse().unwrap();
+                        } else if name.eq_ignore_ascii_case(WEBHOOK_SIGNATURE_HEADER) {
+                            signature = value.trim().to_owned();
+                        }
+                    }
+                }
+                let mut body = vec![0u8; content_length];
+                reader.read_exact(&mut body).unwrap();
+
+                let status = if i < num_failures {
+                    "500 Internal Server Error"
+                } else {
+                    "200 OK"
+                };
+                write!(
+                    stream,
+                    "HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                    status
+                )
+                .unwrap();
+
+                if sender.send((signature, body)).is_err() {
+                    break;
+                }
+            }
+        });
+
+        (url, receiver)
+    }
+
+    #[test_with_logger]
+    fn test_webhook_delivery(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([123u8; 32]);
+        let account_key = AccountKey::random(&mut rng);
+
+        // Set up a db with a known recipient, 3 random recipients and 3 blocks.
+        let (ledger_db, mobilecoind_db) = get_test_databases(
+            BlockVersion::MAX,
+            3,
+            &[account_key.default_subaddress()],
+            3,
+            logger.clone(),
+            &mut rng,
+        );
+        let num_blocks = ledger_db.num_blocks().unwrap();
+
+        let monitor_data = MonitorData::new(account_key, 0, 20, 0, "").unwrap();
+        let monitor_id = mobilecoind_db.add_monitor(&monitor_data).unwrap();
+
+        // The first delivery fails, and gets retried.
+        let (url, receiver) = start_webhook_server(1);
+        let webhook = Webhook {
+            url,
+            secret: b"webhook secret".to_vec(),
+        };
+        mobilecoind_db
+            .set_monitor_webhook(&monitor_id, Some(&webhook))
+            .unwrap();
+
+        let _webhook_thread = WebhookThread::start(mobilecoind_db.clone(), logger.clone());
+        let _sync_thread = SyncThread::start(
+            ledger_db.clone(),
+            mobilecoind_db.clone(),
+            None,
+            None,
+            logger.clone(),
+        );
+        wait_for_monitors(&mobilecoind_db, &ledger_db, &logger);
+
+        let mut requests = (0..num_blocks + 1)
+            .map(|_| receiver.recv_timeout(Duration::from_secs(30)).unwrap())
+            .collect::<Vec<_>>();
+
+        // All requests are signed.
+        for (signature, body) in requests.iter() {
+            assert_eq!(*signature, sign_payload(&webhook.secret, body));
+        }
+
+        // The failed request was retried, and each block was delivered in order.
+        assert_eq!(requests[0], requests[1]);
+        requests.remove(0);
+        for (block_index, (_signature, body)) in requests.iter().enumerate() {
+            let payload: serde_json::Value = serde_json::from_slice(body).unwrap();
+            assert_eq!(payload["monitor_id"], monitor_id.to_string());
+            assert_eq!(payload["block_index"], block_index.to_string());
+            assert_eq!(payload["received"].as_array().unwrap().len(), 1);
+            assert!(payload["spent"].as_array().unwrap().is_empty());
+        }
+    }
+}
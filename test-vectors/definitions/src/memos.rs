@@ -152,3 +152,106 @@ impl TestVector for IncorrectEncryptedSenderWithPaymentRequestIdMemoData {
     const FILE_NAME: &'static str = "incorrect_encrypted_sender_with_payment_request_id_memos";
     const MODULE_SUBDIR: &'static str = "memos";
 }
+
+/// Contains data associated with an encrypted sender with invoice memo that
+/// has the correct sender and recipient data.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CorrectEncryptedSenderWithInvoiceMemoData {
+    /// The transaction sender's public address proto bytes encoded as hex. This
+    /// user wrote the sender with invoice memo.
+    pub sender_public_address_hex_proto_bytes: String,
+
+    /// The transaction recipient's view public key raw bytes encoded as hex.
+    /// This user received the transaction with the sender memo.
+    pub recipient_view_public_key_hex_raw_bytes: String,
+
+    /// The invoice hash raw bytes included in the memo, encoded as hex.
+    pub invoice_hash_hex_raw_bytes: String,
+
+    /// The invoice due date included in the memo, in seconds since the unix
+    /// epoch.
+    pub due_date: u64,
+
+    /// The zero-padded merchant reference raw bytes included in the memo,
+    /// encoded as hex.
+    pub merchant_reference_hex_raw_bytes: String,
+
+    /// The encrypted sender with invoice memo raw bytes encoded as hex.
+    pub encrypted_sender_with_invoice_memo_hex_raw_bytes: String,
+}
+
+impl TestVector for CorrectEncryptedSenderWithInvoiceMemoData {
+    const FILE_NAME: &'static str = "correct_encrypted_sender_with_invoice_memos";
+    const MODULE_SUBDIR: &'static str = "memos";
+}
+
+/// Contains data associated with an encrypted sender with invoice memo that
+/// has incorrect sender and recipient data.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IncorrectEncryptedSenderWithInvoiceMemoData {
+    /// A public address's proto bytes encoded as hex. This address did not
+    /// write the sender with invoice memo.
+    pub incorrect_sender_public_address_hex_proto_bytes: String,
+
+    /// A view public key's raw bytes encoded as hex. This key is not the key
+    /// that received the transaction with the sender with invoice memo.
+    pub incorrect_recipient_view_public_key_hex_raw_bytes: String,
+
+    /// The invoice hash raw bytes included in the memo, encoded as hex.
+    pub invoice_hash_hex_raw_bytes: String,
+
+    /// The invoice due date included in the memo, in seconds since the unix
+    /// epoch.
+    pub due_date: u64,
+
+    /// The zero-padded merchant reference raw bytes included in the memo,
+    /// encoded as hex.
+    pub merchant_reference_hex_raw_bytes: String,
+
+    /// The encrypted sender with invoice memo raw bytes encoded as hex.
+    pub encrypted_sender_with_invoice_memo_hex_raw_bytes: String,
+}
+
+impl TestVector for IncorrectEncryptedSenderWithInvoiceMemoData {
+    const FILE_NAME: &'static str = "incorrect_encrypted_sender_with_invoice_memos";
+    const MODULE_SUBDIR: &'static str = "memos";
+}
+
+/// Contains data associated with an encrypted destination with invoice memo
+/// that has the correct sender and recipient data.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CorrectEncryptedDestinationWithInvoiceMemoData {
+    /// The transaction sender's public address proto bytes encoded as hex. This
+    /// user wrote the destination with invoice memo.
+    pub sender_public_address_hex_proto_bytes: String,
+
+    /// The transaction recipient's short address hash raw bytes encoded as hex.
+    /// This user received the TxOut that this destination memo describes.
+    pub recipient_short_address_hash_hex_raw_bytes: String,
+
+    /// The sum of all the outlays in the transaction. See destination memo
+    /// documentation for more info.
+    pub total_outlay: u64,
+
+    /// The fee for the transaction.
+    pub fee: u64,
+
+    /// The invoice hash raw bytes included in the memo, encoded as hex.
+    pub invoice_hash_hex_raw_bytes: String,
+
+    /// The invoice due date included in the memo, in seconds since the unix
+    /// epoch.
+    pub due_date: u64,
+
+    /// The zero-padded merchant reference raw bytes included in the memo,
+    /// encoded as hex.
+    pub merchant_reference_hex_raw_bytes: String,
+
+    /// The encrypted destination with invoice memo raw bytes encoded as hex.
+    pub encrypted_destination_with_invoice_memo_hex_raw_bytes: String,
+}
+
+impl TestVector for CorrectEncryptedDestinationWithInvoiceMemoData {
+    const FILE_NAME: &'static str = "correct_encrypted_destination_with_invoice_memos";
+    const MODULE_SUBDIR: &'static str = "memos";
+}
@@ -3,12 +3,15 @@
 use mc_account_keys::{AccountKey, ShortAddressHash};
 use mc_crypto_keys::{CompressedRistrettoPublic, RistrettoPrivate};
 use mc_test_vectors_definitions::memos::{
-    CorrectEncryptedDestinationMemoData, CorrectEncryptedSenderMemoData,
+    CorrectEncryptedDestinationMemoData, CorrectEncryptedDestinationWithInvoiceMemoData,
+    CorrectEncryptedSenderMemoData, CorrectEncryptedSenderWithInvoiceMemoData,
     CorrectEncryptedSenderWithPaymentRequestIdMemoData, IncorrectEncryptedSenderMemoData,
+    IncorrectEncryptedSenderWithInvoiceMemoData,
     IncorrectEncryptedSenderWithPaymentRequestIdMemoData,
 };
 use mc_transaction_extra::{
-    AuthenticatedSenderMemo, AuthenticatedSenderWithPaymentRequestIdMemo, DestinationMemo,
+    AuthenticatedSenderMemo, AuthenticatedSenderWithInvoiceMemo,
+    AuthenticatedSenderWithPaymentRequestIdMemo, DestinationMemo, DestinationWithInvoiceMemo,
     SenderMemoCredential,
 };
 use mc_util_from_random::FromRandom;
@@ -25,6 +28,10 @@ fn main() {
 
     write_correct_encrypted_sender_with_payment_request_id_memos();
     write_incorrect_encrypted_sender_with_payment_request_id_memos();
+
+    write_correct_encrypted_sender_with_invoice_memos();
+    write_incorrect_encrypted_sender_with_invoice_memos();
+    write_correct_encrypted_destination_with_invoice_memos();
 }
 
 fn write_correct_encrypted_sender_memos() {
@@ -327,3 +334,185 @@ fn write_incorrect_encrypted_sender_with_payment_request_id_memos() {
     })
     .expect("Unable to write test vectors");
 }
+
+fn write_correct_encrypted_sender_with_invoice_memos() {
+    write_jsonl("../vectors", || {
+        let mut rng: StdRng = SeedableRng::from_seed([2u8; 32]);
+        let mut encrypted_sender_with_invoice_memos: Vec<
+            CorrectEncryptedSenderWithInvoiceMemoData,
+        > = Vec::new();
+        for _ in 0..10 {
+            let sender_account_key = AccountKey::new(
+                &RistrettoPrivate::from_random(&mut rng),
+                &RistrettoPrivate::from_random(&mut rng),
+            );
+            let sender_credential = SenderMemoCredential::from(&sender_account_key);
+            let sender_public_address = sender_account_key.default_subaddress();
+
+            let recipient_account_key = AccountKey::new(
+                &RistrettoPrivate::from_random(&mut rng),
+                &RistrettoPrivate::from_random(&mut rng),
+            );
+            let recipient_public_address = recipient_account_key.default_subaddress();
+
+            let tx_public_key = CompressedRistrettoPublic::from_random(&mut rng);
+            let invoice_hash = [23u8; 16];
+            let due_date = 1_700_000_000u64;
+            let merchant_reference = *b"INV-0023";
+
+            let encrypted_sender_with_invoice_memo = AuthenticatedSenderWithInvoiceMemo::new(
+                &sender_credential,
+                recipient_public_address.view_public_key(),
+                &tx_public_key,
+                &invoice_hash,
+                due_date,
+                &merchant_reference,
+            );
+            let sender_with_invoice_memo_bytes: [u8; 64] =
+                encrypted_sender_with_invoice_memo.clone().into();
+
+            let encrypted_sender_with_invoice_memo_data =
+                CorrectEncryptedSenderWithInvoiceMemoData {
+                    sender_public_address_hex_proto_bytes: hex::encode(mc_util_serial::encode(
+                        &sender_public_address.clone(),
+                    )),
+                    recipient_view_public_key_hex_raw_bytes: hex::encode(
+                        recipient_public_address.view_public_key().to_bytes(),
+                    ),
+                    invoice_hash_hex_raw_bytes: hex::encode(invoice_hash),
+                    due_date,
+                    merchant_reference_hex_raw_bytes: hex::encode(merchant_reference),
+                    encrypted_sender_with_invoice_memo_hex_raw_bytes: hex::encode(
+                        sender_with_invoice_memo_bytes,
+                    ),
+                };
+            encrypted_sender_with_invoice_memos.push(encrypted_sender_with_invoice_memo_data);
+        }
+
+        encrypted_sender_with_invoice_memos
+    })
+    .expect("Unable to write test vectors");
+}
+
+fn write_incorrect_encrypted_sender_with_invoice_memos() {
+    write_jsonl("../vectors", || {
+        let mut rng: StdRng = SeedableRng::from_seed([2u8; 32]);
+        let mut encrypted_sender_with_invoice_memos: Vec<
+            IncorrectEncryptedSenderWithInvoiceMemoData,
+        > = Vec::new();
+        for _ in 0..10 {
+            let sender_account_key = AccountKey::new(
+                &RistrettoPrivate::from_random(&mut rng),
+                &RistrettoPrivate::from_random(&mut rng),
+            );
+            let sender_credential = SenderMemoCredential::from(&sender_account_key);
+            let sender_public_address = sender_account_key.default_subaddress();
+
+            let recipient_account_key = AccountKey::new(
+                &RistrettoPrivate::from_random(&mut rng),
+                &RistrettoPrivate::from_random(&mut rng),
+            );
+            let recipient_public_address = recipient_account_key.default_subaddress();
+
+            let tx_public_key = CompressedRistrettoPublic::from_random(&mut rng);
+            let invoice_hash = [23u8; 16];
+            let due_date = 1_700_000_000u64;
+            let merchant_reference = *b"INV-0023";
+
+            let encrypted_sender_with_invoice_memo = AuthenticatedSenderWithInvoiceMemo::new(
+                &sender_credential,
+                recipient_public_address.view_public_key(),
+                &tx_public_key,
+                &invoice_hash,
+                due_date,
+                &merchant_reference,
+            );
+            let sender_with_invoice_memo_bytes: [u8; 64] =
+                encrypted_sender_with_invoice_memo.clone().into();
+
+            let encrypted_sender_with_invoice_memo_data =
+                IncorrectEncryptedSenderWithInvoiceMemoData {
+                    incorrect_sender_public_address_hex_proto_bytes: hex::encode(
+                        mc_util_serial::encode(&recipient_public_address.clone()),
+                    ),
+                    incorrect_recipient_view_public_key_hex_raw_bytes: hex::encode(
+                        sender_public_address.view_public_key().to_bytes(),
+                    ),
+                    invoice_hash_hex_raw_bytes: hex::encode(invoice_hash),
+                    due_date,
+                    merchant_reference_hex_raw_bytes: hex::encode(merchant_reference),
+                    encrypted_sender_with_invoice_memo_hex_raw_bytes: hex::encode(
+                        sender_with_invoice_memo_bytes,
+                    ),
+                };
+            encrypted_sender_with_invoice_memos.push(encrypted_sender_with_invoice_memo_data);
+        }
+
+        encrypted_sender_with_invoice_memos
+    })
+    .expect("Unable to write test vectors");
+}
+
+fn write_correct_encrypted_destination_with_invoice_memos() {
+    write_jsonl("../vectors", || {
+        let mut rng: StdRng = SeedableRng::from_seed([2u8; 32]);
+        let mut encrypted_destination_with_invoice_memos: Vec<
+            CorrectEncryptedDestinationWithInvoiceMemoData,
+        > = Vec::new();
+        for _ in 0..10 {
+            let sender_account_key = AccountKey::new(
+                &RistrettoPrivate::from_random(&mut rng),
+                &RistrettoPrivate::from_random(&mut rng),
+            );
+            let sender_public_address = sender_account_key.default_subaddress();
+
+            let recipient_account_key = AccountKey::new(
+                &RistrettoPrivate::from_random(&mut rng),
+                &RistrettoPrivate::from_random(&mut rng),
+            );
+            let recipient_public_address = recipient_account_key.default_subaddress();
+            let recipient_short_address_hash = ShortAddressHash::from(&recipient_public_address);
+
+            let total_outlay = 12u64;
+            let fee = 13u64;
+            let invoice_hash = [23u8; 16];
+            let due_date = 1_700_000_000u64;
+            let merchant_reference = *b"INV-0023";
+
+            let encrypted_destination_with_invoice_memo = DestinationWithInvoiceMemo::new(
+                recipient_short_address_hash.clone(),
+                total_outlay,
+                fee,
+                invoice_hash,
+                due_date,
+                merchant_reference,
+            )
+            .unwrap();
+            let destination_with_invoice_memo_bytes: [u8; 64] =
+                encrypted_destination_with_invoice_memo.clone().into();
+
+            let encrypted_destination_with_invoice_memo_data =
+                CorrectEncryptedDestinationWithInvoiceMemoData {
+                    sender_public_address_hex_proto_bytes: hex::encode(mc_util_serial::encode(
+                        &sender_public_address.clone(),
+                    )),
+                    recipient_short_address_hash_hex_raw_bytes: hex::encode(<[u8; 16]>::from(
+                        recipient_short_address_hash,
+                    )),
+                    total_outlay,
+                    fee,
+                    invoice_hash_hex_raw_bytes: hex::encode(invoice_hash),
+                    due_date,
+                    merchant_reference_hex_raw_bytes: hex::encode(merchant_reference),
+                    encrypted_destination_with_invoice_memo_hex_raw_bytes: hex::encode(
+                        destination_with_invoice_memo_bytes,
+                    ),
+                };
+            encrypted_destination_with_invoice_memos
+                .push(encrypted_destination_with_invoice_memo_data);
+        }
+
+        encrypted_destination_with_invoice_memos
+    })
+    .expect("Unable to write test vectors");
+}
@@ -91,12 +91,30 @@ class FogNetwork(Network):
         )
         self.fog_ingest.start()
 
-        self.fog_view = FogView(
-            'view1',
+        # Two view stores, each holding half of the search key space, behind a
+        # view router which clients reach through nginx.
+        self.fog_view_stores = [
+            FogView(
+                f'view_store{i + 1}',
+                f'localhost:{BASE_VIEW_CLIENT_PORT + i + 1}',
+                BASE_VIEW_CLIENT_PORT + i + 1,
+                BASE_VIEW_ADMIN_PORT + i + 1,
+                BASE_VIEW_ADMIN_HTTP_GATEWAY_PORT + i + 1,
+                release=True,
+                sharding_strategy=sharding_strategy,
+            )
+            for i, sharding_strategy in enumerate(['search-keys:0000-8000', 'search-keys:8000-10000'])
+        ]
+        for store in self.fog_view_stores:
+            store.start()
+
+        self.fog_view = FogViewRouter(
+            'view_router1',
             f'localhost:{BASE_NGINX_CLIENT_PORT}',
             BASE_VIEW_CLIENT_PORT,
             BASE_VIEW_ADMIN_PORT,
             BASE_VIEW_ADMIN_HTTP_GATEWAY_PORT,
+            self.fog_view_stores,
             release=True,
         )
         self.fog_view.start()
@@ -143,6 +161,8 @@ class FogNetwork(Network):
         stop_server("fog_ledger")
         stop_server("fog_report")
         stop_server("fog_view")
+        for store in getattr(self, "fog_view_stores", []):
+            store.stop()
         stop_server("fog_ingest")
 
 if __name__ == '__main__':
@@ -173,12 +173,15 @@ class FogIngest:
         return self.run_client_command(f'report-lost-ingress-key -k "{lost_key}"')
 
 class FogView:
-    def __init__(self, name, client_responder_id, client_port, admin_port, admin_http_gateway_port, release):
+    def __init__(self, name, client_responder_id, client_port, admin_port, admin_http_gateway_port, release, sharding_strategy=None):
         self.name = name
 
         self.client_responder_id = client_responder_id
         self.client_port = client_port
         self.client_listen_url = f'insecure-fog-view://{LISTEN_HOST}:{self.client_port}/'
+        # The uri a fog view router uses to reach this server as a view store
+        self.store_url = f'insecure-fog-view-store://{LISTEN_HOST}:{self.client_port}/'
+        self.sharding_strategy = sharding_strategy
 
         self.admin_port = admin_port
         self.admin_http_gateway_port = admin_http_gateway_port
@@ -204,7 +207,7 @@ class FogView:
             f'--ias-api-key={IAS_API_KEY}',
             f'--ias-spid={IAS_SPID}',
             f'--admin-listen-uri=insecure-mca://{LISTEN_HOST}:{self.admin_port}/',
-        ])
+        ] + ([f'--sharding-strategy={self.sharding_strategy}'] if self.sharding_strategy else []))
         self.view_server_process = log_and_popen_shell(cmd)
 
         print(f'Starting admin http gateway for fog view')
@@ -220,6 +223,57 @@ class FogView:
             self.admin_http_gateway_process = None
 
 
+class FogViewRouter:
+    def __init__(self, name, client_responder_id, client_port, admin_port, admin_http_gateway_port, view_stores, release):
+        self.name = name
+
+        self.client_responder_id = client_responder_id
+        self.client_port = client_port
+        self.client_listen_url = f'insecure-fog-view://{LISTEN_HOST}:{self.client_port}/'
+        self.view_stores = view_stores
+
+        self.admin_port = admin_port
+        self.admin_http_gateway_port = admin_http_gateway_port
+
+        self.release = release
+        self.target_dir = target_dir(self.release)
+
+        self.router_process = None
+        self.admin_http_gateway_process = None
+
+    def __repr__(self):
+        return self.name
+
+    def start(self):
+        self.stop()
+
+        print(f'Starting fog view router {self.name}')
+        view_store_urls = ','.join(store.store_url for store in self.view_stores)
+        cmd = ' '.join([
+            DATABASE_URL_ENV,
+            f'exec {self.target_dir}/fog_view_router',
+            f'--client-listen-uri={self.client_listen_url}',
+            f'--client-responder-id={self.client_responder_id}',
+            f'--view-store-uris={view_store_urls}',
+            f'--ias-api-key={IAS_API_KEY}',
+            f'--ias-spid={IAS_SPID}',
+            f'--admin-listen-uri=insecure-mca://{LISTEN_HOST}:{self.admin_port}/',
+        ])
+        self.router_process = log_and_popen_shell(cmd)
+
+        print(f'Starting admin http gateway for fog view router')
+        self.admin_http_gateway_process = start_admin_http_gateway(self.admin_http_gateway_port, self.admin_port, self.target_dir)
+
+    def stop(self):
+        if self.router_process and self.router_process.poll() is None:
+            self.router_process.terminate()
+            self.router_process = None
+
+        if self.admin_http_gateway_process and self.admin_http_gateway_process.poll() is None:
+            self.admin_http_gateway_process.terminate()
+            self.admin_http_gateway_process = None
+
+
 class FogReport:
     def __init__(self, name, client_port, admin_port, admin_http_gateway_port, release, chain, key):
         self.name = name
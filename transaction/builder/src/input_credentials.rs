@@ -46,16 +46,11 @@ impl InputCredentials {
         onetime_key_derive_data: impl Into<OneTimeKeyDeriveData>,
         view_private_key: RistrettoPrivate,
     ) -> Result<Self, TxBuilderError> {
-        debug_assert_eq!(ring.len(), membership_proofs.len());
-
         if real_index > ring.len() || ring.is_empty() {
             return Err(TxBuilderError::InvalidRingSize);
         }
 
-        let real_input: TxOut = ring
-            .get(real_index)
-            .cloned()
-            .ok_or(TxBuilderError::NoInputs)?;
+        let real_input: &TxOut = ring.get(real_index).ok_or(TxBuilderError::NoInputs)?;
         let real_output_public_key = RistrettoPublic::try_from(&real_input.public_key)?;
 
         // Note: The caller likely already has the shared secret if they already
@@ -63,6 +58,45 @@ impl InputCredentials {
         // optimization we could avoid recomputing it.
         let tx_out_shared_secret = create_shared_secret(&real_output_public_key, &view_private_key);
 
+        Self::new_with_shared_secret(
+            ring,
+            membership_proofs,
+            real_index,
+            onetime_key_derive_data,
+            tx_out_shared_secret,
+        )
+    }
+
+    /// Creates an InputCredential instance from the shared secret of the real
+    /// output, rather than the view private key of its owner. This allows
+    /// spending outputs whose owner's view private key is not known, such as
+    /// gift codes.
+    ///
+    /// # Arguments
+    /// * `ring` - A "ring" of transaction outputs.
+    /// * `membership_proofs` - Proof that each TxOut in `ring` is in the
+    ///   ledger.
+    /// * `real_index` - Index in `ring` of the output being spent.
+    /// * `onetime_private_key` - Private key for the output being spent.
+    /// * `tx_out_shared_secret` - The shared secret of the output being spent.
+    pub fn new_with_shared_secret(
+        ring: Vec<TxOut>,
+        membership_proofs: Vec<TxOutMembershipProof>,
+        real_index: usize,
+        onetime_key_derive_data: impl Into<OneTimeKeyDeriveData>,
+        tx_out_shared_secret: RistrettoPublic,
+    ) -> Result<Self, TxBuilderError> {
+        debug_assert_eq!(ring.len(), membership_proofs.len());
+
+        if real_index > ring.len() || ring.is_empty() {
+            return Err(TxBuilderError::InvalidRingSize);
+        }
+
+        let real_input: TxOut = ring
+            .get(real_index)
+            .cloned()
+            .ok_or(TxBuilderError::NoInputs)?;
+
         // Sort the ring and the corresponding proofs. This ensures that the ordering
         // of mixins in the transaction does not depend on the user's implementation for
         // obtaining mixins.
@@ -0,0 +1,336 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! Defines the Memo Builder for the invoice memos (0x0103 and 0x0205).
+//! This is a variant of the RTHMemoBuilder in which the memos carry a
+//! reference to the invoice being paid.
+
+use super::MemoBuilder;
+use crate::ReservedSubaddresses;
+use alloc::format;
+use mc_account_keys::{PublicAddress, ShortAddressHash};
+use mc_transaction_core::{
+    tokens::Mob, Amount, MemoContext, MemoPayload, NewMemoError, Token, TokenId,
+};
+use mc_transaction_extra::{
+    AuthenticatedSenderWithInvoiceMemo, DestinationMemoError, DestinationWithInvoiceMemo,
+    SenderMemoCredential, UnusedMemo,
+};
+
+/// This memo builder attaches 0x0103 Authenticated Sender With Invoice Memos
+/// to normal outputs, and 0x0205 Destination With Invoice Memos to change
+/// outputs.
+///
+/// Usage:
+///
+///   let mut mb = InvoiceMemoBuilder::new(
+///       SenderMemoCredential::from(&account_key),
+///       invoice_hash,
+///       due_date,
+///       "INV-0042",
+///   )?;
+///   mb.enable_destination_memo();
+///
+/// Then use it to construct a transaction builder.
+///
+/// If mb.enable_destination_memo() is not called 0x0000 Unused will appear on
+/// change outputs, instead of 0x0205 Destination With Invoice Memo.
+///
+/// As with the RTHMemoBuilder, the change output must be created last, and if
+/// more than one normal output is created, only the last recipient's public
+/// address will be recorded in the destination memo.
+#[derive(Clone, Debug)]
+pub struct InvoiceMemoBuilder {
+    // The credential used to form 0x0103 memos
+    sender_cred: SenderMemoCredential,
+    // The hash identifying the invoice
+    invoice_hash: [u8; AuthenticatedSenderWithInvoiceMemo::INVOICE_HASH_LEN],
+    // The due date of the invoice, in seconds since the unix epoch
+    due_date: u64,
+    // The zero-padded merchant reference
+    merchant_reference: [u8; AuthenticatedSenderWithInvoiceMemo::MERCHANT_REFERENCE_LEN],
+    // Whether destination memos are enabled.
+    destination_memo_enabled: bool,
+    // Tracks if we already wrote a destination memo, for error reporting
+    wrote_destination_memo: bool,
+    // Tracks the last recipient
+    last_recipient: ShortAddressHash,
+    // Tracks the total outlay so far
+    total_outlay: u64,
+    // Tracks the total outlay token id
+    outlay_token_id: Option<TokenId>,
+    // Tracks the number of recipients so far
+    num_recipients: u8,
+    // Tracks the fee
+    fee: Amount,
+}
+
+impl InvoiceMemoBuilder {
+    /// Initialize memo builder with a sender credential and an invoice
+    /// reference. The merchant reference can be up to 8 bytes long, and is
+    /// zero-padded. This method returns a NewMemoError if it is longer.
+    pub fn new(
+        sender_cred: SenderMemoCredential,
+        invoice_hash: [u8; AuthenticatedSenderWithInvoiceMemo::INVOICE_HASH_LEN],
+        due_date: u64,
+        merchant_reference: &str,
+    ) -> Result<Self, NewMemoError> {
+        let reference_bytes = merchant_reference.as_bytes();
+        if reference_bytes.len() > AuthenticatedSenderWithInvoiceMemo::MERCHANT_REFERENCE_LEN {
+            return Err(NewMemoError::BadInputs(format!(
+                "Merchant reference cannot be greater than {} bytes",
+                AuthenticatedSenderWithInvoiceMemo::MERCHANT_REFERENCE_LEN
+            )));
+        }
+        let mut merchant_reference =
+            [0u8; AuthenticatedSenderWithInvoiceMemo::MERCHANT_REFERENCE_LEN];
+        merchant_reference[..reference_bytes.len()].copy_from_slice(reference_bytes);
+
+        Ok(Self {
+            sender_cred,
+            invoice_hash,
+            due_date,
+            merchant_reference,
+            destination_memo_enabled: false,
+            wrote_destination_memo: false,
+            last_recipient: Default::default(),
+            total_outlay: 0,
+            outlay_token_id: None,
+            num_recipients: 0,
+            fee: Amount::new(Mob::MINIMUM_FEE, Mob::ID),
+        })
+    }
+
+    /// Enable destination memos
+    pub fn enable_destination_memo(&mut self) {
+        self.destination_memo_enabled = true;
+    }
+
+    /// Disable destination memos
+    pub fn disable_destination_memo(&mut self) {
+        self.destination_memo_enabled = false;
+    }
+}
+
+impl MemoBuilder for InvoiceMemoBuilder {
+    /// Set the fee
+    fn set_fee(&mut self, fee: Amount) -> Result<(), NewMemoError> {
+        if self.wrote_destination_memo {
+            return Err(NewMemoError::FeeAfterChange);
+        }
+        self.fee = fee;
+        Ok(())
+    }
+
+    /// Build a memo for a normal output (to another party).
+    fn make_memo_for_output(
+        &mut self,
+        amount: Amount,
+        recipient: &PublicAddress,
+        memo_context: MemoContext,
+    ) -> Result<MemoPayload, NewMemoError> {
+        if self.wrote_destination_memo {
+            return Err(NewMemoError::OutputsAfterChange);
+        }
+        // Check if the outlay is mixing token ids
+        if let Some(prev_token_id) = self.outlay_token_id {
+            if prev_token_id != amount.token_id {
+                return Err(NewMemoError::MixedTokenIds);
+            }
+        } else {
+            // If this is the first outlay, then this is the token id for the whole outlay.
+            self.outlay_token_id = Some(amount.token_id);
+        }
+        self.total_outlay = self
+            .total_outlay
+            .checked_add(amount.value)
+            .ok_or(NewMemoError::LimitsExceeded("total_outlay"))?;
+        self.num_recipients = self
+            .num_recipients
+            .checked_add(1)
+            .ok_or(NewMemoError::LimitsExceeded("num_recipients"))?;
+        self.last_recipient = ShortAddressHash::from(recipient);
+        Ok(AuthenticatedSenderWithInvoiceMemo::new(
+            &self.sender_cred,
+            recipient.view_public_key(),
+            &memo_context.tx_public_key.into(),
+            &self.invoice_hash,
+            self.due_date,
+            &self.merchant_reference,
+        )
+        .into())
+    }
+
+    /// Build a memo for a change output (to ourselves).
+    fn make_memo_for_change_output(
+        &mut self,
+        amount: Amount,
+        _change_destination: &ReservedSubaddresses,
+        _memo_context: MemoContext,
+    ) -> Result<MemoPayload, NewMemoError> {
+        if !self.destination_memo_enabled {
+            return Ok(UnusedMemo {}.into());
+        }
+        if self.wrote_destination_memo {
+            return Err(NewMemoError::MultipleChangeOutputs);
+        }
+        // Check if the outlay is mixing token ids
+        if let Some(prev_token_id) = self.outlay_token_id {
+            if prev_token_id != amount.token_id {
+                return Err(NewMemoError::MixedTokenIds);
+            }
+        } else {
+            // If no outlays occurred yet, this should be the token id for the whole tx.
+            self.outlay_token_id = Some(amount.token_id);
+        }
+
+        // The fee must be in the same token id as the outlay, otherwise the
+        // total outlay would be meaningless.
+        if self.fee.token_id != amount.token_id {
+            return Err(NewMemoError::MixedTokenIds);
+        }
+
+        self.total_outlay = self
+            .total_outlay
+            .checked_add(self.fee.value)
+            .ok_or(NewMemoError::LimitsExceeded("total_outlay"))?;
+
+        match DestinationWithInvoiceMemo::new(
+            self.last_recipient.clone(),
+            self.total_outlay,
+            self.fee.value,
+            self.invoice_hash,
+            self.due_date,
+            self.merchant_reference,
+        ) {
+            Ok(mut d_memo) => {
+                self.wrote_destination_memo = true;
+                d_memo.set_num_recipients(self.num_recipients);
+                Ok(d_memo.into())
+            }
+            Err(err) => match err {
+                DestinationMemoError::FeeTooLarge => Err(NewMemoError::LimitsExceeded("fee")),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::build_change_memo_with_amount;
+    use assert_matches::assert_matches;
+    use mc_account_keys::AccountKey;
+    use mc_crypto_keys::RistrettoPublic;
+    use mc_transaction_extra::MemoType;
+    use mc_util_from_random::FromRandom;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    const INVOICE_HASH: [u8; 16] = [7u8; 16];
+    const DUE_DATE: u64 = 1_700_000_000;
+
+    fn build_output_memo(
+        builder: &mut InvoiceMemoBuilder,
+        amount: Amount,
+        recipient: &PublicAddress,
+    ) -> Result<MemoPayload, NewMemoError> {
+        let mut rng: StdRng = SeedableRng::from_seed([1u8; 32]);
+        let tx_public_key = RistrettoPublic::from_random(&mut rng);
+        let memo_context = MemoContext {
+            tx_public_key: &tx_public_key,
+        };
+        builder.make_memo_for_output(amount, recipient, memo_context)
+    }
+
+    #[test]
+    fn test_invoice_memos_built_successfully() {
+        let mut rng: StdRng = SeedableRng::from_seed([0u8; 32]);
+        let alice = AccountKey::random(&mut rng);
+        let bob = AccountKey::random(&mut rng);
+
+        let mut builder = InvoiceMemoBuilder::new(
+            SenderMemoCredential::from(&alice),
+            INVOICE_HASH,
+            DUE_DATE,
+            "INV-42",
+        )
+        .unwrap();
+        builder.enable_destination_memo();
+        builder.set_fee(Amount::new(10, 0.into())).unwrap();
+
+        let output_memo = build_output_memo(
+            &mut builder,
+            Amount::new(100, 0.into()),
+            &bob.default_subaddress(),
+        )
+        .unwrap();
+        assert_matches!(
+            MemoType::try_from(&output_memo).unwrap(),
+            MemoType::AuthenticatedSenderWithInvoice(memo)
+                if memo.invoice_hash() == INVOICE_HASH
+                    && memo.due_date() == DUE_DATE
+                    && &memo.merchant_reference() == b"INV-42\0\0"
+        );
+
+        let change_memo =
+            build_change_memo_with_amount(&mut builder, Amount::new(5, 0.into())).unwrap();
+        match MemoType::try_from(&change_memo).unwrap() {
+            MemoType::DestinationWithInvoice(memo) => {
+                assert_eq!(
+                    memo.get_address_hash(),
+                    &ShortAddressHash::from(&bob.default_subaddress())
+                );
+                assert_eq!(memo.get_total_outlay(), 110);
+                assert_eq!(memo.get_fee(), 10);
+                assert_eq!(memo.get_num_recipients(), 1);
+                assert_eq!(memo.get_invoice_hash(), &INVOICE_HASH);
+                assert_eq!(memo.get_due_date(), DUE_DATE);
+                assert_eq!(memo.get_merchant_reference(), b"INV-42\0\0");
+            }
+            _ => panic!("unexpected memo type"),
+        }
+    }
+
+    #[test]
+    fn test_invoice_memo_builder_creation_fails_with_long_merchant_reference() {
+        let mut rng: StdRng = SeedableRng::from_seed([0u8; 32]);
+        let alice = AccountKey::random(&mut rng);
+
+        let result = InvoiceMemoBuilder::new(
+            SenderMemoCredential::from(&alice),
+            INVOICE_HASH,
+            DUE_DATE,
+            "INVOICE-0042",
+        );
+        assert_matches!(result, Err(NewMemoError::BadInputs(_)));
+    }
+
+    #[test]
+    fn test_invoice_memo_builder_fails_for_outputs_after_change() {
+        let mut rng: StdRng = SeedableRng::from_seed([0u8; 32]);
+        let alice = AccountKey::random(&mut rng);
+        let bob = AccountKey::random(&mut rng);
+
+        let mut builder = InvoiceMemoBuilder::new(
+            SenderMemoCredential::from(&alice),
+            INVOICE_HASH,
+            DUE_DATE,
+            "INV-42",
+        )
+        .unwrap();
+        builder.enable_destination_memo();
+
+        build_change_memo_with_amount(&mut builder, Amount::new(5, Mob::ID)).unwrap();
+        assert_matches!(
+            build_output_memo(
+                &mut builder,
+                Amount::new(100, Mob::ID),
+                &bob.default_subaddress()
+            ),
+            Err(NewMemoError::OutputsAfterChange)
+        );
+        assert_matches!(
+            build_change_memo_with_amount(&mut builder, Amount::new(5, Mob::ID)),
+            Err(NewMemoError::MultipleChangeOutputs)
+        );
+    }
+}
@@ -14,12 +14,14 @@ mod burn_redemption_memo_builder;
 mod gift_code_cancellation_memo_builder;
 mod gift_code_funding_memo_builder;
 mod gift_code_sender_memo_builder;
+mod invoice_memo_builder;
 mod rth_memo_builder;
 
 pub use burn_redemption_memo_builder::BurnRedemptionMemoBuilder;
 pub use gift_code_cancellation_memo_builder::GiftCodeCancellationMemoBuilder;
 pub use gift_code_funding_memo_builder::GiftCodeFundingMemoBuilder;
 pub use gift_code_sender_memo_builder::GiftCodeSenderMemoBuilder;
+pub use invoice_memo_builder::InvoiceMemoBuilder;
 pub use rth_memo_builder::RTHMemoBuilder;
 
 /// The MemoBuilder trait defines the API that the transaction builder uses
@@ -13,6 +13,7 @@
 extern crate alloc;
 
 mod memo;
+mod memo_handler;
 mod signed_contingent_input;
 mod tx_out_confirmation_number;
 mod tx_out_gift_code;
@@ -20,12 +21,16 @@ mod tx_summary_unblinding;
 mod unsigned_tx;
 
 pub use memo::{
-    AuthenticatedSenderMemo, AuthenticatedSenderWithPaymentIntentIdMemo,
-    AuthenticatedSenderWithPaymentRequestIdMemo, BurnRedemptionMemo, DestinationMemo,
-    DestinationMemoError, DestinationWithPaymentIntentIdMemo, DestinationWithPaymentRequestIdMemo,
+    AuthenticatedSenderMemo, AuthenticatedSenderWithInvoiceMemo,
+    AuthenticatedSenderWithPaymentIntentIdMemo, AuthenticatedSenderWithPaymentRequestIdMemo,
+    BurnRedemptionMemo, DestinationMemo, DestinationMemoError, DestinationWithInvoiceMemo,
+    DestinationWithPaymentIntentIdMemo, DestinationWithPaymentRequestIdMemo,
     GiftCodeCancellationMemo, GiftCodeFundingMemo, GiftCodeSenderMemo, MemoDecodingError, MemoType,
     RegisteredMemoType, SenderMemoCredential, UnusedMemo,
 };
+pub use memo_handler::{
+    ContactStore, HandledMemo, MemoClassification, MemoHandler, MemoHandlerError,
+};
 pub use signed_contingent_input::{
     SignedContingentInput, SignedContingentInputError, UnmaskedAmount,
 };
@@ -40,20 +40,24 @@
 //! | 0x0100          | Authenticated Sender Memo                         |
 //! | 0x0101          | Authenticated Sender With Payment Request Id Memo |
 //! | 0x0102          | Authenticated Sender With Payment Intent Id Memo  |
+//! | 0x0103          | Authenticated Sender With Invoice Memo            |
 //! | 0x0200          | Destination Memo                                  |
 //! | 0x0201          | Gift Code Funding Memo                            |
 //! | 0x0202          | Gift Code Cancellation Memo                       |
 //! | 0x0203          | Destination With Payment Request Id Memo          |
 //! | 0x0204          | Destination With Payment Intent Id Memo           |
+//! | 0x0205          | Destination With Invoice Memo                     |
 
 pub use self::{
     authenticated_common::compute_category1_hmac,
     authenticated_sender::AuthenticatedSenderMemo,
+    authenticated_sender_with_invoice::AuthenticatedSenderWithInvoiceMemo,
     authenticated_sender_with_payment_intent_id::AuthenticatedSenderWithPaymentIntentIdMemo,
     authenticated_sender_with_payment_request_id::AuthenticatedSenderWithPaymentRequestIdMemo,
     burn_redemption::BurnRedemptionMemo,
     credential::SenderMemoCredential,
     destination::{DestinationMemo, DestinationMemoError},
+    destination_with_invoice::DestinationWithInvoiceMemo,
     destination_with_payment_intent_id::DestinationWithPaymentIntentIdMemo,
     destination_with_payment_request_id::DestinationWithPaymentRequestIdMemo,
     gift_code_cancellation::GiftCodeCancellationMemo,
@@ -64,11 +68,13 @@ pub use self::{
 
 mod authenticated_common;
 mod authenticated_sender;
+mod authenticated_sender_with_invoice;
 mod authenticated_sender_with_payment_intent_id;
 mod authenticated_sender_with_payment_request_id;
 mod burn_redemption;
 mod credential;
 mod destination;
+mod destination_with_invoice;
 mod destination_with_payment_intent_id;
 mod destination_with_payment_request_id;
 mod gift_code_cancellation;
@@ -106,10 +112,12 @@ impl_memo_enum! { MemoType,
     AuthenticatedSender(AuthenticatedSenderMemo),
     AuthenticatedSenderWithPaymentRequestId(AuthenticatedSenderWithPaymentRequestIdMemo),
     AuthenticatedSenderWithPaymentIntentId(AuthenticatedSenderWithPaymentIntentIdMemo),
+    AuthenticatedSenderWithInvoice(AuthenticatedSenderWithInvoiceMemo),
     BurnRedemption(BurnRedemptionMemo),
     Destination(DestinationMemo),
     DestinationWithPaymentRequestId(DestinationWithPaymentRequestIdMemo),
     DestinationWithPaymentIntentId(DestinationWithPaymentIntentIdMemo),
+    DestinationWithInvoice(DestinationWithInvoiceMemo),
     GiftCodeCancellation(GiftCodeCancellationMemo),
     GiftCodeFunding(GiftCodeFundingMemo),
     GiftCodeSender(GiftCodeSenderMemo),
@@ -208,6 +216,41 @@ mod tests {
                 panic!("unexpected deserialization");
             }
         }
+
+        let memo7 = AuthenticatedSenderWithInvoiceMemo::new(
+            &alice_cred,
+            bob_addr.view_public_key(),
+            &tx_public_key,
+            &[3u8; 16],
+            1_700_000_000u64,
+            b"INV-0042",
+        );
+        match MemoType::try_from(&MemoPayload::from(memo7.clone())).unwrap() {
+            MemoType::AuthenticatedSenderWithInvoice(memo) => {
+                assert_eq!(memo7, memo);
+            }
+            _ => {
+                panic!("unexpected deserialization");
+            }
+        }
+
+        let memo8 = DestinationWithInvoiceMemo::new(
+            ShortAddressHash::from(&bob_addr),
+            17,
+            18,
+            [3u8; 16],
+            1_700_000_000u64,
+            *b"INV-0042",
+        )
+        .unwrap();
+        match MemoType::try_from(&MemoPayload::from(memo8.clone())).unwrap() {
+            MemoType::DestinationWithInvoice(memo) => {
+                assert_eq!(memo8, memo);
+            }
+            _ => {
+                panic!("unexpected deserialization");
+            }
+        }
     }
 
     #[test]
@@ -378,6 +421,88 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_invoice_memo_authentication() {
+        let mut rng: StdRng = SeedableRng::from_seed([3u8; 32]);
+
+        let alice = AccountKey::new(
+            &RistrettoPrivate::from_random(&mut rng),
+            &RistrettoPrivate::from_random(&mut rng),
+        );
+        let alice_cred = SenderMemoCredential::from(&alice);
+        let alice_addr = alice.default_subaddress();
+
+        let bob = AccountKey::new(
+            &RistrettoPrivate::from_random(&mut rng),
+            &RistrettoPrivate::from_random(&mut rng),
+        );
+        let bob_addr = bob.default_subaddress();
+
+        let tx_public_key = CompressedRistrettoPublic::from_random(&mut rng);
+        let tx_public_key2 = CompressedRistrettoPublic::from_random(&mut rng);
+
+        let memo = AuthenticatedSenderWithInvoiceMemo::new(
+            &alice_cred,
+            bob_addr.view_public_key(),
+            &tx_public_key,
+            &[9u8; 16],
+            1_700_000_000u64,
+            b"INV-0042",
+        );
+        assert_eq!(
+            memo.sender_address_hash(),
+            ShortAddressHash::from(&alice_addr)
+        );
+        assert_eq!(memo.invoice_hash(), [9u8; 16]);
+        assert_eq!(memo.due_date(), 1_700_000_000u64);
+        assert_eq!(&memo.merchant_reference(), b"INV-0042");
+        assert!(
+            bool::from(memo.validate(
+                &alice_addr,
+                &bob.default_subaddress_view_private(),
+                &tx_public_key
+            )),
+            "validation should have passed"
+        );
+        assert!(
+            !bool::from(memo.validate(
+                &bob_addr,
+                &bob.default_subaddress_view_private(),
+                &tx_public_key
+            )),
+            "validation should have failed"
+        );
+        assert!(
+            !bool::from(memo.validate(
+                &alice_addr,
+                &alice.default_subaddress_view_private(),
+                &tx_public_key
+            )),
+            "validation should have failed"
+        );
+        assert!(
+            !bool::from(memo.validate(
+                &alice_addr,
+                &bob.default_subaddress_view_private(),
+                &tx_public_key2
+            )),
+            "validation should have failed"
+        );
+
+        // Tampering with the invoice reference invalidates the mac
+        let mut memo_bytes: [u8; 64] = memo.into();
+        memo_bytes[40] ^= 1;
+        let tampered = AuthenticatedSenderWithInvoiceMemo::from(&memo_bytes);
+        assert!(
+            !bool::from(tampered.validate(
+                &alice_addr,
+                &bob.default_subaddress_view_private(),
+                &tx_public_key
+            )),
+            "validation should have failed"
+        );
+    }
+
     #[test]
     fn test_destination_memo() {
         let mut rng: StdRng = SeedableRng::from_seed([2u8; 32]);
@@ -0,0 +1,298 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! A memo handler which decodes the memo attached to a TxOut, validates it
+//! against an address book, and classifies the TxOut accordingly.
+//!
+//! Authenticated sender memos (0x0100 family) are validated by looking up the
+//! sender's address hash in a [ContactStore] and checking the hmac.
+//! Destination memos (0x0200 family) are validated by confirming that the TxOut
+//! belongs to the change subaddress.
+
+use crate::memo::{MemoDecodingError, MemoType};
+use alloc::collections::BTreeMap;
+use displaydoc::Display;
+use mc_account_keys::{AccountKey, PublicAddress, ShortAddressHash, CHANGE_SUBADDRESS_INDEX};
+use mc_crypto_keys::{KeyError, RistrettoPublic};
+use mc_transaction_core::{get_tx_out_shared_secret, subaddress_matches_tx_out, tx::TxOut};
+
+/// A store of known contacts, against which authenticated sender memos are
+/// validated.
+pub trait ContactStore {
+    /// Look up a contact by the short address hash of their public address
+    fn get_contact(&self, address_hash: &ShortAddressHash) -> Option<PublicAddress>;
+}
+
+impl ContactStore for BTreeMap<ShortAddressHash, PublicAddress> {
+    fn get_contact(&self, address_hash: &ShortAddressHash) -> Option<PublicAddress> {
+        self.get(address_hash).cloned()
+    }
+}
+
+impl<C: ContactStore + ?Sized> ContactStore for &C {
+    fn get_contact(&self, address_hash: &ShortAddressHash) -> Option<PublicAddress> {
+        (**self).get_contact(address_hash)
+    }
+}
+
+/// The classification of a TxOut, based on the memo attached to it
+#[derive(Clone, Copy, Debug, Display, Eq, Hash, PartialEq)]
+pub enum MemoClassification {
+    /// The memo was authenticated
+    Authenticated,
+    /// The memo failed validation, or carries no authenticated data
+    Unauthenticated,
+    /// The memo claims a sender who is not in the contact store
+    UnknownSender,
+}
+
+/// The result of handling the memo attached to a TxOut
+#[derive(Clone, Debug)]
+pub struct HandledMemo {
+    /// The decoded memo
+    pub memo_type: MemoType,
+
+    /// The classification of the TxOut
+    pub classification: MemoClassification,
+
+    /// The sender address hash claimed by the memo, for authenticated sender
+    /// memos
+    pub sender_address_hash: Option<ShortAddressHash>,
+
+    /// The sender's public address, if the memo was authenticated against a
+    /// contact
+    pub sender: Option<PublicAddress>,
+}
+
+/// A handler object that decodes memos and validates them against a contact
+/// store.
+#[derive(Clone, Debug)]
+pub struct MemoHandler<C: ContactStore> {
+    contacts: C,
+}
+
+impl<C: ContactStore> MemoHandler<C> {
+    /// Make a new memo handler with a given contact store
+    pub fn new(contacts: C) -> Self {
+        Self { contacts }
+    }
+
+    /// Get the contact store
+    pub fn contacts(&self) -> &C {
+        &self.contacts
+    }
+
+    /// Get the contact store mutably, e.g. to add contacts
+    pub fn contacts_mut(&mut self) -> &mut C {
+        &mut self.contacts
+    }
+
+    /// Decode, validate and classify the memo attached to a TxOut.
+    ///
+    /// Arguments:
+    /// * tx_out: The TxOut whose memo should be handled
+    /// * account_key: The account key of the owner of the TxOut
+    /// * receiving_subaddress_index: The subaddress index at which the TxOut
+    ///   was received
+    pub fn handle_memo(
+        &self,
+        tx_out: &TxOut,
+        account_key: &AccountKey,
+        receiving_subaddress_index: u64,
+    ) -> Result<HandledMemo, MemoHandlerError> {
+        let decompressed_tx_pub = RistrettoPublic::try_from(&tx_out.public_key)?;
+        let shared_secret =
+            get_tx_out_shared_secret(account_key.view_private_key(), &decompressed_tx_pub);
+
+        let memo_payload = tx_out.decrypt_memo(&shared_secret);
+        let memo_type = MemoType::try_from(&memo_payload)?;
+
+        // Validate an authenticated sender memo against the contact store
+        let receiving_subaddress_view_private_key =
+            account_key.subaddress_view_private(receiving_subaddress_index);
+        macro_rules! validate_sender {
+            ($memo:expr) => {{
+                let address_hash = $memo.sender_address_hash();
+                let contact = self.contacts.get_contact(&address_hash);
+                let classification = match &contact {
+                    Some(addr)
+                        if bool::from($memo.validate(
+                            addr,
+                            &receiving_subaddress_view_private_key,
+                            &tx_out.public_key,
+                        )) =>
+                    {
+                        MemoClassification::Authenticated
+                    }
+                    Some(_) => MemoClassification::Unauthenticated,
+                    None => MemoClassification::UnknownSender,
+                };
+                let sender =
+                    contact.filter(|_| classification == MemoClassification::Authenticated);
+                (classification, Some(address_hash), sender)
+            }};
+        }
+
+        let (classification, sender_address_hash, sender) = match &memo_type {
+            MemoType::AuthenticatedSender(memo) => validate_sender!(memo),
+            MemoType::AuthenticatedSenderWithPaymentRequestId(memo) => validate_sender!(memo),
+            MemoType::AuthenticatedSenderWithPaymentIntentId(memo) => validate_sender!(memo),
+            MemoType::AuthenticatedSenderWithInvoice(memo) => validate_sender!(memo),
+            MemoType::Destination(_)
+            | MemoType::DestinationWithPaymentRequestId(_)
+            | MemoType::DestinationWithPaymentIntentId(_)
+            | MemoType::DestinationWithInvoice(_) => {
+                if subaddress_matches_tx_out(account_key, CHANGE_SUBADDRESS_INDEX, tx_out)? {
+                    (MemoClassification::Authenticated, None, None)
+                } else {
+                    (MemoClassification::Unauthenticated, None, None)
+                }
+            }
+            // These memo types carry no authenticated data
+            MemoType::BurnRedemption(_)
+            | MemoType::GiftCodeCancellation(_)
+            | MemoType::GiftCodeFunding(_)
+            | MemoType::GiftCodeSender(_)
+            | MemoType::Unused(_) => (MemoClassification::Unauthenticated, None, None),
+        };
+
+        Ok(HandledMemo {
+            memo_type,
+            classification,
+            sender_address_hash,
+            sender,
+        })
+    }
+}
+
+/// An error that occurs when the memo handler can't process a memo
+#[derive(Display, Debug, Clone)]
+pub enum MemoHandlerError {
+    /// Key: {0}
+    Key(KeyError),
+
+    /// Memo Decoding: {0}
+    MemoDecode(MemoDecodingError),
+}
+
+impl From<KeyError> for MemoHandlerError {
+    fn from(src: KeyError) -> Self {
+        Self::Key(src)
+    }
+}
+
+impl From<MemoDecodingError> for MemoHandlerError {
+    fn from(src: MemoDecodingError) -> Self {
+        Self::MemoDecode(src)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AuthenticatedSenderMemo, DestinationMemo, SenderMemoCredential};
+    use mc_account_keys::DEFAULT_SUBADDRESS_INDEX;
+    use mc_crypto_keys::{CompressedRistrettoPublic, RistrettoPrivate};
+    use mc_transaction_core::{tokens::Mob, Amount, BlockVersion, MemoPayload, Token};
+    use mc_util_from_random::FromRandom;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    fn tx_out_with_memo(
+        recipient: &PublicAddress,
+        rng: &mut StdRng,
+        make_memo: impl FnOnce(&RistrettoPublic) -> MemoPayload,
+    ) -> TxOut {
+        let tx_private_key = RistrettoPrivate::from_random(rng);
+        TxOut::new_with_memo(
+            BlockVersion::MAX,
+            Amount::new(10, Mob::ID),
+            recipient,
+            &tx_private_key,
+            Default::default(),
+            |memo_ctxt| Ok(make_memo(memo_ctxt.tx_public_key)),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_handle_authenticated_sender_memo() {
+        let mut rng: StdRng = SeedableRng::from_seed([1u8; 32]);
+
+        let alice = AccountKey::random(&mut rng);
+        let alice_addr = alice.default_subaddress();
+        let alice_cred = SenderMemoCredential::from(&alice);
+        let bob = AccountKey::random(&mut rng);
+        let bob_addr = bob.default_subaddress();
+        let mallory = AccountKey::random(&mut rng);
+
+        let tx_out = tx_out_with_memo(&bob_addr, &mut rng, |tx_public_key| {
+            AuthenticatedSenderMemo::new(
+                &alice_cred,
+                bob_addr.view_public_key(),
+                &CompressedRistrettoPublic::from(tx_public_key),
+            )
+            .into()
+        });
+
+        // Alice is not a contact yet
+        let mut handler = MemoHandler::new(BTreeMap::new());
+        let handled = handler
+            .handle_memo(&tx_out, &bob, DEFAULT_SUBADDRESS_INDEX)
+            .unwrap();
+        assert_eq!(handled.classification, MemoClassification::UnknownSender);
+        assert_eq!(
+            handled.sender_address_hash,
+            Some(ShortAddressHash::from(&alice_addr))
+        );
+        assert_eq!(handled.sender, None);
+
+        // Once she is a contact the memo authenticates
+        handler
+            .contacts_mut()
+            .insert(ShortAddressHash::from(&alice_addr), alice_addr.clone());
+        let handled = handler
+            .handle_memo(&tx_out, &bob, DEFAULT_SUBADDRESS_INDEX)
+            .unwrap();
+        assert_eq!(handled.classification, MemoClassification::Authenticated);
+        assert_eq!(handled.sender, Some(alice_addr.clone()));
+
+        // A contact with the right hash but the wrong keys fails validation
+        handler.contacts_mut().insert(
+            ShortAddressHash::from(&alice_addr),
+            mallory.default_subaddress(),
+        );
+        let handled = handler
+            .handle_memo(&tx_out, &bob, DEFAULT_SUBADDRESS_INDEX)
+            .unwrap();
+        assert_eq!(handled.classification, MemoClassification::Unauthenticated);
+        assert_eq!(handled.sender, None);
+    }
+
+    #[test]
+    fn test_handle_destination_memo() {
+        let mut rng: StdRng = SeedableRng::from_seed([2u8; 32]);
+
+        let alice = AccountKey::random(&mut rng);
+        let bob = AccountKey::random(&mut rng);
+        let bob_addr = bob.default_subaddress();
+
+        let make_memo = |_: &RistrettoPublic| {
+            DestinationMemo::new(ShortAddressHash::from(&bob_addr), 20, 10)
+                .unwrap()
+                .into()
+        };
+        let change_tx_out = tx_out_with_memo(&alice.change_subaddress(), &mut rng, make_memo);
+        let default_tx_out = tx_out_with_memo(&alice.default_subaddress(), &mut rng, make_memo);
+
+        let handler = MemoHandler::new(BTreeMap::new());
+        let handled = handler
+            .handle_memo(&change_tx_out, &alice, CHANGE_SUBADDRESS_INDEX)
+            .unwrap();
+        assert_eq!(handled.classification, MemoClassification::Authenticated);
+        assert_eq!(handled.sender_address_hash, None);
+
+        let handled = handler
+            .handle_memo(&default_tx_out, &alice, DEFAULT_SUBADDRESS_INDEX)
+            .unwrap();
+        assert_eq!(handled.classification, MemoClassification::Unauthenticated);
+    }
+}